@@ -0,0 +1,425 @@
+//! A typed client for the admin endpoints of GraphGate.
+//!
+//! The models are shared with the gateway, which serves them, so tooling
+//! managing fleets of gateways breaks at compile time rather than at runtime
+//! when they change:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), graphgate_admin_client::Error> {
+//! let client = graphgate_admin_client::AdminClient::new("http://gateway:9000");
+//! let status = client.status().await?;
+//! for error in status.entity_resolver_errors {
+//!     println!(
+//!         "{} cannot resolve {}: {}",
+//!         error.service, error.type_name, error.message
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![forbid(unsafe_code)]
+
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// The body of the `/status` endpoint.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Status {
+    /// Whether a schema has been composed and the gateway serves requests.
+    pub ready: bool,
+
+    /// The entity types the subgraphs failed to resolve when last probed.
+    #[serde(default)]
+    pub entity_resolver_errors: Vec<EntityResolverError>,
+
+    /// The versions of the SDLs of the subgraphs the schema is composed
+    /// from.
+    #[serde(default)]
+    pub subgraphs: Vec<SubgraphSchema>,
+}
+
+/// The version of the SDL of a subgraph the schema is composed from.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SubgraphSchema {
+    pub service: String,
+
+    /// The SHA-256 hash of the SDL, in hexadecimal.
+    pub sdl_hash: String,
+
+    /// When this version of the SDL was first fetched.
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// An entity type a subgraph failed to resolve when probed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EntityResolverError {
+    pub service: String,
+
+    #[serde(rename = "type")]
+    pub type_name: String,
+
+    pub message: String,
+}
+
+/// A route of the route table the gateway discovered, from `/routes`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Route {
+    pub service: String,
+
+    /// The URL the queries of the service are sent to.
+    pub url: String,
+
+    /// The URL the subscriptions of the service are sent to.
+    pub websocket_url: String,
+
+    pub source: RouteSource,
+
+    pub health: RouteHealth,
+
+    /// Why the SDL of the service could not be fetched, if it is unhealthy.
+    #[serde(default)]
+    pub error: Option<String>,
+
+    /// The SHA-256 hash of the SDL the schema is composed from, in
+    /// hexadecimal.
+    #[serde(default)]
+    pub sdl_hash: Option<String>,
+}
+
+/// Where a route was discovered.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteSource {
+    /// The config file or the environment variables.
+    Config,
+    /// The services of the Kubernetes namespace.
+    Kubernetes,
+    /// The schema snapshot, until the services are discovered.
+    Snapshot,
+    /// The `join__Graph` enum of a supergraph SDL.
+    Supergraph,
+}
+
+/// Whether the SDL of a service could be fetched in the latest update.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteHealth {
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+/// The SDL of a service the schema is composed from, from `/schema`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ServiceSdl {
+    pub service: String,
+
+    pub sdl: String,
+}
+
+/// The usage of a cache of the gateway since it started, from `/caches`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Cache {
+    /// `persisted_queries`, `entities` or `introspection`.
+    pub name: String,
+
+    /// The number of entries, unless the cache is kept outside the gateway,
+    /// such as in Redis.
+    #[serde(default)]
+    pub entries: Option<usize>,
+
+    /// An estimate of the memory used by the keys and values of the
+    /// entries, in bytes, unless the cache is kept outside the gateway.
+    #[serde(default)]
+    pub memory_bytes: Option<usize>,
+
+    pub hits: u64,
+
+    pub misses: u64,
+
+    /// The share of the lookups that were hits, if there were any.
+    #[serde(default)]
+    pub hit_ratio: Option<f64>,
+
+    /// The entries hit the most since they were stored, most hit first.
+    #[serde(default)]
+    pub hottest: Vec<CacheEntry>,
+}
+
+/// An entry of a cache and how often it was hit.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CacheEntry {
+    /// The key the entry can be evicted by.
+    pub key: String,
+
+    pub hits: u64,
+}
+
+/// The effective configuration of the gateway, logged at startup and served
+/// at `/startup-report`, with the services as currently discovered.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StartupReport {
+    /// The version of the gateway.
+    pub version: String,
+
+    pub gateway_name: String,
+
+    pub started_at: DateTime<Utc>,
+
+    /// The addresses the gateway listens on.
+    pub listeners: Vec<Listener>,
+
+    /// The features that are configured, by the name of their config key,
+    /// sorted.
+    pub features: Vec<String>,
+
+    /// The routes the gateway discovered, with the hashes of their SDLs.
+    pub services: Vec<Route>,
+
+    /// The providers of the keys the tokens of the requests are verified
+    /// with.
+    #[serde(default)]
+    pub auth_providers: Vec<AuthProvider>,
+
+    /// Where the caches keep their entries.
+    #[serde(default)]
+    pub caches: Vec<CacheBackend>,
+}
+
+/// An address the gateway listens on and the endpoints it serves there.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Listener {
+    pub addr: String,
+
+    /// The paths of the endpoints, such as `/` and `/metrics`.
+    pub endpoints: Vec<String>,
+}
+
+/// A provider of the keys the tokens of the requests are verified with.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuthProvider {
+    /// The `iss` claim of the tokens of the provider, any if unset.
+    #[serde(default)]
+    pub issuer: Option<String>,
+
+    /// The URL of the JSON Web Key Set.
+    pub jwks: String,
+
+    /// Whether requests without a token are rejected.
+    pub required: bool,
+}
+
+/// Where a cache keeps its entries.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CacheBackend {
+    /// `persisted_queries`, `entities` or `introspection`.
+    pub name: String,
+
+    pub backend: CacheBackendKind,
+}
+
+/// The kind of storage of a cache.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheBackendKind {
+    /// The memory of the gateway.
+    Memory,
+    /// A Redis server shared by the replicas.
+    Redis,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("request to the gateway failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("the gateway answered with status {0}")]
+    Status(StatusCode),
+}
+
+/// A client for the admin endpoints of a gateway.
+#[derive(Clone, Debug)]
+pub struct AdminClient {
+    client: Client,
+    url: String,
+}
+
+impl AdminClient {
+    /// Create a client for the gateway at `url`, such as
+    /// `http://gateway:8000`.
+    ///
+    /// `/health` and `/ready` are served at the `bind` address of the gateway,
+    /// the other endpoints at its `admin_bind` address. Without an
+    /// `admin_bind`, the read-only endpoints are served at `bind` too, and
+    /// refreshing the schema, blocking operations and the caches are not
+    /// available.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_client(Client::new(), url)
+    }
+
+    /// Create a client sending its requests with `client`, for example to
+    /// set timeouts or default headers.
+    pub fn with_client(client: Client, url: impl Into<String>) -> Self {
+        let mut url = url.into();
+        while url.ends_with('/') {
+            url.pop();
+        }
+        Self { client, url }
+    }
+
+    /// The URL of the gateway.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether the gateway is alive, from `/health`.
+    pub async fn health(&self) -> Result<bool, Error> {
+        let resp = self.get("health").await?;
+        Ok(resp.status().is_success())
+    }
+
+    /// Whether the gateway has composed a schema and serves requests, from
+    /// `/ready`.
+    pub async fn ready(&self) -> Result<bool, Error> {
+        let resp = self.get("ready").await?;
+        match resp.status() {
+            StatusCode::SERVICE_UNAVAILABLE => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(Error::Status(status)),
+        }
+    }
+
+    /// The status of the gateway, from `/status`.
+    pub async fn status(&self) -> Result<Status, Error> {
+        let resp = self.get("status").await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The routes the gateway discovered, sorted by service name, from
+    /// `/routes`.
+    pub async fn routes(&self) -> Result<Vec<Route>, Error> {
+        let resp = self.get("routes").await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The SDLs of the services the schema is composed from, empty if it is
+    /// loaded from a supergraph SDL, from `/schema`.
+    pub async fn schema(&self) -> Result<Vec<ServiceSdl>, Error> {
+        let resp = self.get("schema").await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Fetch the SDLs of the services and compose the schema without waiting
+    /// for the next update, by `POST /refresh`.
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let resp = self.client.post(format!("{}/refresh", self.url)).send().await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(())
+    }
+
+    /// The SHA-256 hashes of the blocked operations, from
+    /// `/blocked-operations`.
+    pub async fn blocked_operations(&self) -> Result<Vec<String>, Error> {
+        let resp = self.get("blocked-operations").await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Reject the operation with the SHA-256 `hash`, as sent in the
+    /// `persistedQuery` extension, until it is unblocked.
+    pub async fn block_operation(&self, hash: &str) -> Result<(), Error> {
+        let resp = self
+            .client
+            .put(format!("{}/blocked-operations/{}", self.url, hash))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(())
+    }
+
+    /// Serve the operation with the SHA-256 `hash` again, returning whether
+    /// it was blocked.
+    pub async fn unblock_operation(&self, hash: &str) -> Result<bool, Error> {
+        let resp = self
+            .client
+            .delete(format!("{}/blocked-operations/{}", self.url, hash))
+            .send()
+            .await?;
+        match resp.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(Error::Status(status)),
+        }
+    }
+
+    /// The usage of the caches of the gateway with their `top` most hit
+    /// entries, from `/caches`.
+    pub async fn caches(&self, top: usize) -> Result<Vec<Cache>, Error> {
+        let resp = self
+            .client
+            .get(format!("{}/caches", self.url))
+            .query(&[("top", top)])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Evict the entry stored under `key` from the cache named `cache`,
+    /// returning whether there was one.
+    pub async fn evict_cache_entry(&self, cache: &str, key: &str) -> Result<bool, Error> {
+        let resp = self
+            .client
+            .delete(format!("{}/caches/{}", self.url, cache))
+            .query(&[("key", key)])
+            .send()
+            .await?;
+        match resp.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(Error::Status(status)),
+        }
+    }
+
+    /// The JSON schema of the config file of the gateway, from
+    /// `/config-schema`.
+    pub async fn config_schema(&self) -> Result<serde_json::Value, Error> {
+        let resp = self.get("config-schema").await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The effective configuration of the gateway, from `/startup-report`.
+    pub async fn startup_report(&self) -> Result<StartupReport, Error> {
+        let resp = self.get("startup-report").await?;
+        if !resp.status().is_success() {
+            return Err(Error::Status(resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    async fn get(&self, path: &str) -> Result<reqwest::Response, Error> {
+        Ok(self.client.get(format!("{}/{}", self.url, path)).send().await?)
+    }
+}
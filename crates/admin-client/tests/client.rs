@@ -0,0 +1,263 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use graphgate_admin_client::{
+    AdminClient,
+    AuthProvider,
+    Cache,
+    CacheBackend,
+    CacheBackendKind,
+    CacheEntry,
+    EntityResolverError,
+    Error,
+    Listener,
+    Route,
+    RouteHealth,
+    RouteSource,
+    ServiceSdl,
+    StartupReport,
+    Status,
+    SubgraphSchema,
+};
+use serde_json::json;
+use warp::{http::StatusCode, Filter};
+
+/// Serve the admin endpoints of a gateway that is not ready, returning its
+/// URL.
+async fn serve() -> String {
+    let health = warp::path!("health").map(|| warp::reply::json(&"healthy"));
+    let ready = warp::path!("ready")
+        .map(|| warp::reply::with_status(warp::reply::json(&"not ready"), StatusCode::SERVICE_UNAVAILABLE));
+    let status = warp::path!("status").map(|| {
+        warp::reply::json(&json!({
+            "ready": false,
+            "entity_resolver_errors": [
+                { "service": "accounts", "type": "User", "message": "no entity" },
+            ],
+            "subgraphs": [
+                { "service": "accounts", "sdl_hash": "ab12", "fetched_at": "2024-01-02T03:04:05Z" },
+            ],
+        }))
+    });
+    let routes = warp::path!("routes").map(|| {
+        warp::reply::json(&json!([
+            {
+                "service": "accounts",
+                "url": "http://accounts:8000",
+                "websocket_url": "ws://accounts:8000",
+                "source": "kubernetes",
+                "health": "unhealthy",
+                "error": "Failed to fetch SDL from 'accounts'.",
+            },
+        ]))
+    });
+    let config_schema = warp::path!("config-schema")
+        .map(|| warp::reply::json(&json!({ "title": "Config", "type": "object", "properties": {} })));
+    let (addr, server) =
+        warp::serve(health.or(ready).or(status).or(routes).or(config_schema)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    format!("http://{}/", addr)
+}
+
+#[tokio::test]
+async fn query_admin_endpoints() {
+    let client = AdminClient::new(serve().await);
+
+    assert!(client.health().await.unwrap());
+    assert!(!client.ready().await.unwrap());
+    assert_eq!(client.status().await.unwrap(), Status {
+        ready: false,
+        entity_resolver_errors: vec![EntityResolverError {
+            service: "accounts".to_string(),
+            type_name: "User".to_string(),
+            message: "no entity".to_string(),
+        }],
+        subgraphs: vec![SubgraphSchema {
+            service: "accounts".to_string(),
+            sdl_hash: "ab12".to_string(),
+            fetched_at: "2024-01-02T03:04:05Z".parse().unwrap(),
+        }],
+    });
+    assert_eq!(client.routes().await.unwrap(), vec![Route {
+        service: "accounts".to_string(),
+        url: "http://accounts:8000".to_string(),
+        websocket_url: "ws://accounts:8000".to_string(),
+        source: RouteSource::Kubernetes,
+        health: RouteHealth::Unhealthy,
+        error: Some("Failed to fetch SDL from 'accounts'.".to_string()),
+        sdl_hash: None,
+    }]);
+    assert_eq!(client.config_schema().await.unwrap()["title"], "Config");
+}
+
+#[tokio::test]
+async fn unknown_endpoint() {
+    let (addr, server) = warp::serve(warp::path!("health").map(warp::reply)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let client = AdminClient::new(format!("http://{}", addr));
+
+    assert!(matches!(
+        client.status().await,
+        Err(Error::Status(StatusCode::NOT_FOUND))
+    ));
+}
+
+#[tokio::test]
+async fn manage_caches() {
+    let caches = warp::path!("caches")
+        .and(warp::get())
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|params: HashMap<String, String>| {
+            assert_eq!(params["top"], "5");
+            warp::reply::json(&json!([
+                {
+                    "name": "persisted_queries",
+                    "entries": 2,
+                    "memory_bytes": 120,
+                    "hits": 3,
+                    "misses": 1,
+                    "hit_ratio": 0.75,
+                    "hottest": [{ "key": "ab12", "hits": 3 }],
+                },
+                { "name": "entities", "hits": 0, "misses": 0 },
+            ]))
+        });
+    let evict = warp::path!("caches" / String)
+        .and(warp::delete())
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|name: String, params: HashMap<String, String>| {
+            let status = match (name.as_str(), params["key"].as_str()) {
+                ("persisted_queries", "ab12") => StatusCode::OK,
+                _ => StatusCode::NOT_FOUND,
+            };
+            warp::reply::with_status(warp::reply(), status)
+        });
+    let (addr, server) = warp::serve(caches.or(evict)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let client = AdminClient::new(format!("http://{}", addr));
+
+    assert_eq!(client.caches(5).await.unwrap(), vec![
+        Cache {
+            name: "persisted_queries".to_string(),
+            entries: Some(2),
+            memory_bytes: Some(120),
+            hits: 3,
+            misses: 1,
+            hit_ratio: Some(0.75),
+            hottest: vec![CacheEntry {
+                key: "ab12".to_string(),
+                hits: 3,
+            }],
+        },
+        Cache {
+            name: "entities".to_string(),
+            entries: None,
+            memory_bytes: None,
+            hits: 0,
+            misses: 0,
+            hit_ratio: None,
+            hottest: Vec::new(),
+        },
+    ]);
+    assert!(client.evict_cache_entry("persisted_queries", "ab12").await.unwrap());
+    assert!(!client.evict_cache_entry("persisted_queries", "cd34").await.unwrap());
+}
+
+#[tokio::test]
+async fn fetch_startup_report() {
+    let report = warp::path!("startup-report").map(|| {
+        warp::reply::json(&json!({
+            "version": "0.6.1",
+            "gateway_name": "graphgate",
+            "started_at": "2024-01-02T03:04:05Z",
+            "listeners": [{ "addr": "0.0.0.0:8000", "endpoints": ["/", "/metrics"] }],
+            "features": ["authorization", "entity_cache"],
+            "services": [],
+            "auth_providers": [{ "jwks": "https://auth/jwks.json", "required": true }],
+            "caches": [{ "name": "entities", "backend": "redis" }],
+        }))
+    });
+    let (addr, server) = warp::serve(report).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let client = AdminClient::new(format!("http://{}", addr));
+
+    assert_eq!(client.startup_report().await.unwrap(), StartupReport {
+        version: "0.6.1".to_string(),
+        gateway_name: "graphgate".to_string(),
+        started_at: "2024-01-02T03:04:05Z".parse().unwrap(),
+        listeners: vec![Listener {
+            addr: "0.0.0.0:8000".to_string(),
+            endpoints: vec!["/".to_string(), "/metrics".to_string()],
+        }],
+        features: vec!["authorization".to_string(), "entity_cache".to_string()],
+        services: Vec::new(),
+        auth_providers: vec![AuthProvider {
+            issuer: None,
+            jwks: "https://auth/jwks.json".to_string(),
+            required: true,
+        }],
+        caches: vec![CacheBackend {
+            name: "entities".to_string(),
+            backend: CacheBackendKind::Redis,
+        }],
+    });
+}
+
+#[tokio::test]
+async fn manage_schema() {
+    let schema = warp::path!("schema").and(warp::get()).map(|| {
+        warp::reply::json(&json!([
+            { "service": "accounts", "sdl": "type Query { me: User }" },
+        ]))
+    });
+    let refresh = warp::path!("refresh")
+        .and(warp::post())
+        .map(|| warp::reply::with_status(warp::reply::json(&"refreshing"), StatusCode::ACCEPTED));
+    let (addr, server) = warp::serve(schema.or(refresh)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let client = AdminClient::new(format!("http://{}", addr));
+
+    assert_eq!(client.schema().await.unwrap(), vec![ServiceSdl {
+        service: "accounts".to_string(),
+        sdl: "type Query { me: User }".to_string(),
+    }]);
+    client.refresh().await.unwrap();
+}
+
+#[tokio::test]
+async fn manage_blocked_operations() {
+    const HASH: &str = "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38";
+
+    let blocked = Arc::new(Mutex::new(HashSet::new()));
+    let list = warp::path!("blocked-operations").and(warp::get()).map({
+        let blocked = blocked.clone();
+        move || warp::reply::json(&blocked.lock().unwrap().iter().cloned().collect::<Vec<String>>())
+    });
+    let block = warp::path!("blocked-operations" / String).and(warp::put()).map({
+        let blocked = blocked.clone();
+        move |hash: String| {
+            blocked.lock().unwrap().insert(hash);
+            warp::reply()
+        }
+    });
+    let unblock = warp::path!("blocked-operations" / String)
+        .and(warp::delete())
+        .map(move |hash: String| {
+            let status = match blocked.lock().unwrap().remove(&hash) {
+                true => StatusCode::OK,
+                false => StatusCode::NOT_FOUND,
+            };
+            warp::reply::with_status(warp::reply(), status)
+        });
+    let (addr, server) = warp::serve(list.or(block).or(unblock)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let client = AdminClient::new(format!("http://{}", addr));
+
+    client.block_operation(HASH).await.unwrap();
+    assert_eq!(client.blocked_operations().await.unwrap(), vec![HASH.to_string()]);
+    assert!(client.unblock_operation(HASH).await.unwrap());
+    assert!(!client.unblock_operation(HASH).await.unwrap());
+    assert!(client.blocked_operations().await.unwrap().is_empty());
+}
@@ -0,0 +1,89 @@
+//! Run with `cargo bench -p graphgate-executor`.
+
+#![feature(test)]
+
+extern crate test;
+
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use test::Bencher;
+use value::ConstValue;
+
+const USERS: usize = 2000;
+const DISTINCT_USERS: usize = 50;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "accounts".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { users: [User!]! }
+                type User @key(fields: "id") { id: ID! username: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review { body: String! }
+                extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Answers with many users sharing a few ids.
+struct UsersFetcher;
+
+#[async_trait::async_trait]
+impl Fetcher for UsersFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let data = match service {
+            "accounts" => {
+                let users = (0..USERS)
+                    .map(|i| {
+                        json!({
+                            "__key1___typename": "User",
+                            "__key1_id": (i % DISTINCT_USERS).to_string(),
+                            "username": "user",
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({ "users": users })
+            },
+            _ => {
+                let count = match request.variables.get("representations") {
+                    Some(ConstValue::List(representations)) => representations.len(),
+                    _ => 0,
+                };
+                json!({ "_entities": vec![json!({ "reviews": [{ "body": "great" }] }); count] })
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[bench]
+fn repeated_representations(b: &mut Bencher) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let schema = schema();
+    let document = parser::parse_query("{ users { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    b.iter(|| runtime.block_on(execute(&schema, &UsersFetcher, &plan)));
+}
@@ -0,0 +1,101 @@
+use futures_util::future::join_all;
+use graphgate_planner::{FlattenNode, PathSegment, Response};
+use graphgate_schema::ComposedSchema;
+use indexmap::IndexMap;
+use value::{ConstValue, Name, Variables};
+
+use crate::{
+    executor::{all_entities_null, fetch_entities, fetch_error, retry_alternate_keys},
+    fetcher::Fetcher,
+};
+
+/// Whether the entities fetched at a path are the nodes of a Relay
+/// connection, selected through `edges { node }` or `nodes`.
+pub(crate) fn is_connection_path(path: &[PathSegment<'_>]) -> bool {
+    match path {
+        [.., edges, node] if edges.name == "edges" && edges.is_list && node.name == "node" => !node.is_list,
+        [.., nodes] => nodes.name == "nodes" && nodes.is_list,
+        _ => false,
+    }
+}
+
+/// Fetch the nodes of a connection page in concurrent batches of at most
+/// `batch_size` representations, returning whether any batch was fetched by
+/// the alternate keys of its nodes.
+///
+/// A batch the service resolves none of the nodes of is fetched again by
+/// their `alternate_representations`, lined up with `representations`. The
+/// entities of a failed batch are left unresolved, so the edges of the page
+/// and their cursors are kept with the nodes of the other batches.
+pub(crate) async fn fetch_in_batches(
+    schema: &ComposedSchema,
+    fetcher: &impl Fetcher,
+    flatten: &FlattenNode<'_>,
+    representations: Vec<ConstValue>,
+    alternate_representations: &[Variables],
+    batch_size: usize,
+) -> (Response, bool) {
+    let batches = representations.chunks(batch_size).collect::<Vec<_>>();
+    let results = join_all(batches.iter().enumerate().map(|(idx, batch)| async move {
+        let res = fetch_entities(schema, fetcher, flatten, flatten.to_request(batch_variables(batch))).await;
+        if matches!(&res, Ok(resp) if resp.errors.is_empty() && all_entities_null(&resp.data)) {
+            let range = idx * batch_size..idx * batch_size + batch.len();
+            let alternate_representations = alternate_representations
+                .iter()
+                .filter_map(|variables| match variables.get("representations") {
+                    Some(ConstValue::List(values)) => values.get(range.clone()).map(batch_variables),
+                    _ => None,
+                })
+                .collect();
+            if let Some(resp) = retry_alternate_keys(fetcher, flatten, alternate_representations).await {
+                return (Ok(resp), true);
+            }
+        }
+        (res, false)
+    }))
+    .await;
+    let retried = results.iter().any(|(_, retried)| *retried);
+
+    let mut entities = Vec::with_capacity(representations.len());
+    let mut resp = Response::default();
+    for (batch, (res, _)) in batches.iter().zip(results) {
+        let offset = entities.len();
+        match res {
+            Ok(batch_resp) => {
+                for warning in batch_resp.warnings() {
+                    resp.add_warning(warning);
+                }
+                for mut err in batch_resp.errors {
+                    if let [ConstValue::String(field), ConstValue::Number(idx), ..] = err.path.as_mut_slice() {
+                        if let Some(n) = idx.as_u64().filter(|_| field == "_entities") {
+                            *idx = (n + offset as u64).into();
+                        }
+                    }
+                    resp.errors.push(err);
+                }
+                match batch_resp.data {
+                    ConstValue::Object(mut data) => match data.shift_remove("_entities") {
+                        Some(ConstValue::List(values)) if values.len() == batch.len() => entities.extend(values),
+                        _ => entities.resize(offset + batch.len(), ConstValue::Null),
+                    },
+                    _ => entities.resize(offset + batch.len(), ConstValue::Null),
+                }
+            },
+            Err(err) => {
+                resp.errors.push(fetch_error(err));
+                entities.resize(offset + batch.len(), ConstValue::Null);
+            },
+        }
+    }
+
+    let mut data = IndexMap::new();
+    data.insert(Name::new("_entities"), ConstValue::List(entities));
+    resp.data = ConstValue::Object(data);
+    (resp, retried)
+}
+
+fn batch_variables(batch: &[ConstValue]) -> Variables {
+    let mut variables = Variables::default();
+    variables.insert(Name::new("representations"), ConstValue::List(batch.to_vec()));
+    variables
+}
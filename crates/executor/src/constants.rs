@@ -0,0 +1,15 @@
+use opentelemetry::Key;
+
+pub const KEY_SERVICE: Key = Key::from_static_str("graphgate.service");
+pub const KEY_TARGET_SERVICE: Key = Key::from_static_str("graphgate.targetService");
+pub const KEY_QUERY: Key = Key::from_static_str("graphgate.query");
+pub const KEY_PATH: Key = Key::from_static_str("graphgate.path");
+pub const KEY_PARENT_TYPE: Key = Key::from_static_str("graphgate.parentType");
+pub const KEY_RETURN_TYPE: Key = Key::from_static_str("graphgate.returnType");
+pub const KEY_FIELD_NAME: Key = Key::from_static_str("graphgate.fieldName");
+pub const KEY_VARIABLES: Key = Key::from_static_str("graphgate.variables");
+pub const KEY_ERROR: Key = Key::from_static_str("graphgate.error");
+pub const KEY_ENTITY_TYPE: Key = Key::from_static_str("graphgate.entityType");
+pub const KEY_REPRESENTATIONS: Key = Key::from_static_str("graphgate.representations");
+pub const KEY_REQUEST_BYTES: Key = Key::from_static_str("graphgate.requestBytes");
+pub const KEY_RESPONSE_BYTES: Key = Key::from_static_str("graphgate.responseBytes");
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// The error code of requests refused because a subgraph is rate limiting
+/// the gateway.
+pub const SUBGRAPH_RATE_LIMITED: &str = "SUBGRAPH_RATE_LIMITED";
+
+/// The error code of requests failed fast because the circuit of a subgraph
+/// is open.
+pub const SUBGRAPH_CIRCUIT_OPEN: &str = "SUBGRAPH_CIRCUIT_OPEN";
+
+/// The error code of responses exceeding a configured size limit.
+pub const RESPONSE_TOO_LARGE: &str = "RESPONSE_TOO_LARGE";
+
+/// The error code of the entities a subgraph resolved with other keys than
+/// those of their representations.
+pub const ENTITY_KEY_MISMATCH: &str = "ENTITY_KEY_MISMATCH";
+
+/// The error code of requests a subgraph refused with `401 Unauthorized`.
+pub const UNAUTHENTICATED: &str = "UNAUTHENTICATED";
+
+/// The error code of requests a subgraph refused with `403 Forbidden`.
+pub const FORBIDDEN: &str = "FORBIDDEN";
+
+/// The error code of requests a subgraph failed with any other non-2xx
+/// status.
+pub const BAD_GATEWAY: &str = "BAD_GATEWAY";
+
+/// The response header naming the service the entities of a subgraph moved
+/// to, sent with a non-2xx status such as `410 Gone`.
+pub const ENTITY_MOVED_HEADER: &str = "x-entity-moved";
+
+/// A subgraph responded with a status code other than 2xx.
+///
+/// The body is kept for logging, but is not part of the message, which is
+/// returned to clients.
+#[derive(Error, Debug)]
+#[error("service \"{service}\" responded with status {status}")]
+pub struct SubgraphStatusError {
+    pub service: String,
+    pub status: u16,
+    pub body: String,
+}
+
+impl SubgraphStatusError {
+    /// The error code of the status.
+    pub fn code(&self) -> &'static str {
+        match self.status {
+            401 => UNAUTHENTICATED,
+            403 => FORBIDDEN,
+            _ => BAD_GATEWAY,
+        }
+    }
+}
+
+/// A subgraph did not respond within the timeout of its route.
+#[derive(Error, Debug)]
+#[error("service \"{service}\" did not respond within {} ms", timeout.as_millis())]
+pub struct SubgraphTimeoutError {
+    pub service: String,
+    pub timeout: Duration,
+}
+
+/// A subgraph refused an entity fetch because the entities moved to another
+/// service, named by the [`ENTITY_MOVED_HEADER`] of its response.
+#[derive(Error, Debug)]
+#[error("the entities of service \"{service}\" moved to service \"{moved_to}\"")]
+pub struct EntityMovedError {
+    pub service: String,
+    pub moved_to: String,
+}
+
+/// A subgraph asked the gateway to back off.
+#[derive(Error, Debug)]
+#[error("service \"{service}\" is rate limited, retry after {} seconds", retry_after.as_secs_f64().ceil())]
+pub struct RateLimitedError {
+    pub service: String,
+    pub retry_after: Duration,
+}
+
+/// The circuit of a subgraph is open after too many consecutive failures.
+#[derive(Error, Debug)]
+#[error("the circuit of service \"{service}\" is open, retry after {} seconds", retry_after.as_secs_f64().ceil())]
+pub struct CircuitOpenError {
+    pub service: String,
+    pub retry_after: Duration,
+}
+
+/// A subgraph response, or the responses of a request together, exceeded
+/// the configured size limit.
+#[derive(Error, Debug)]
+#[error("response of service \"{service}\" exceeds the limit of {limit} bytes")]
+pub struct ResponseTooLargeError {
+    pub service: String,
+    pub limit: usize,
+}
@@ -0,0 +1,1464 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use futures_util::{
+    future::BoxFuture,
+    stream::{BoxStream, FuturesUnordered},
+    StreamExt,
+};
+use graphgate_planner::{
+    DeferNode,
+    DeferredNode,
+    FetchNode,
+    FlattenNode,
+    IncrementalPayload,
+    IncrementalResponse,
+    IntrospectionNode,
+    ParallelNode,
+    PathSegment,
+    PlanNode,
+    Request,
+    Response,
+    ResponsePath,
+    RootNode,
+    SequenceNode,
+    ServerError,
+    SubscribeNode,
+};
+use graphgate_schema::ComposedSchema;
+use indexmap::IndexMap;
+use opentelemetry::{
+    global,
+    trace::{FutureExt, TraceContextExt, Tracer},
+    Context,
+};
+use serde::{Deserialize, Deserializer};
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::Instant,
+};
+use tracing::instrument;
+use value::{ConstValue, Name, Variables};
+
+use crate::{
+    connection::{fetch_in_batches, is_connection_path},
+    constants::*,
+    error::{
+        CircuitOpenError,
+        EntityMovedError,
+        RateLimitedError,
+        ResponseTooLargeError,
+        SubgraphStatusError,
+        ENTITY_KEY_MISMATCH,
+        RESPONSE_TOO_LARGE,
+        SUBGRAPH_CIRCUIT_OPEN,
+        SUBGRAPH_RATE_LIMITED,
+    },
+    fetcher::{Fetcher, Subscriber, SubscriberFetcher},
+    introspection::{IntrospectionRoot, Resolver},
+    metrics::METRICS,
+    representations::{entity_index, RepresentationCache},
+};
+
+/// Query plan executor
+pub struct Executor<'e> {
+    schema: &'e ComposedSchema,
+    introspection_schema: Option<&'e ComposedSchema>,
+    resp: Mutex<Response>,
+    debug_errors: bool,
+    normalize_error_paths: bool,
+    connection_batch_size: Option<usize>,
+    representations: RepresentationCache,
+}
+
+impl<'e> Executor<'e> {
+    pub fn new(schema: &'e ComposedSchema) -> Self {
+        Executor {
+            schema,
+            introspection_schema: None,
+            resp: Mutex::new(Response::default()),
+            debug_errors: false,
+            normalize_error_paths: false,
+            connection_batch_size: None,
+            representations: Default::default(),
+        }
+    }
+
+    /// Answer the `__schema` and `__type` fields from another schema, such as
+    /// one with some types and fields hidden.
+    pub fn introspection_schema(self, introspection_schema: &'e ComposedSchema) -> Self {
+        Self {
+            introspection_schema: Some(introspection_schema),
+            ..self
+        }
+    }
+
+    /// Describe the subgraph request in the extensions of every error it
+    /// caused.
+    pub fn debug_errors(self, debug_errors: bool) -> Self {
+        Self { debug_errors, ..self }
+    }
+
+    /// Translate the paths of the errors of the entity fetches, relative to
+    /// the `_entities` field, to the paths of the entities in the response,
+    /// and keep the indices of the paths of the other fetches.
+    pub fn normalize_error_paths(self, normalize_error_paths: bool) -> Self {
+        Self {
+            normalize_error_paths,
+            ..self
+        }
+    }
+
+    /// Fetch the nodes of Relay connection pages with more than `batch_size`
+    /// entities in concurrent batches of that size, 0 to fetch every page at
+    /// once.
+    pub fn connection_batch_size(self, batch_size: usize) -> Self {
+        Self {
+            connection_batch_size: (batch_size > 0).then_some(batch_size),
+            ..self
+        }
+    }
+
+    /// An executor with the same settings, for a separately merged part of
+    /// the plan.
+    fn fork(&self) -> Executor<'e> {
+        Executor {
+            schema: self.schema,
+            introspection_schema: self.introspection_schema,
+            resp: Mutex::new(Response::default()),
+            debug_errors: self.debug_errors,
+            normalize_error_paths: self.normalize_error_paths,
+            connection_batch_size: self.connection_batch_size,
+            representations: Default::default(),
+        }
+    }
+
+    /// Execute a query plan and return the results.
+    ///
+    /// Only `Query` and `Mutation` operations are supported.
+    #[instrument(skip(self, fetcher), ret, level = "trace")]
+    pub async fn execute_query(self, fetcher: &impl Fetcher, node: &RootNode<'_>) -> Response {
+        match node {
+            RootNode::Query(node) => {
+                self.execute_node(fetcher, node).await;
+                self.resp.into_inner()
+            },
+            RootNode::Defer(DeferNode { primary, deferred }) => {
+                let (_, deferred) = futures_util::future::join(
+                    self.execute_node(fetcher, primary),
+                    futures_util::future::join_all(
+                        deferred
+                            .iter()
+                            .map(|deferred| execute_deferred(self.fork(), fetcher, deferred)),
+                    ),
+                )
+                .await;
+                let mut resp = self.resp.into_inner();
+                for (_, deferred_resp) in deferred {
+                    merge_response(&mut resp, deferred_resp);
+                }
+                resp
+            },
+            RootNode::Subscribe(_) => Response {
+                data: ConstValue::Null,
+                errors: vec![ServerError {
+                    message: "Not supported".to_string(),
+                    path: Default::default(),
+                    locations: Default::default(),
+                    extensions: Default::default(),
+                    status: None,
+                }],
+                extensions: Default::default(),
+                headers: Default::default(),
+            },
+        }
+    }
+
+    /// Execute a query plan and return a stream of incremental payloads.
+    ///
+    /// The first item is always the initial payload. Deferred parts that
+    /// finish within `latency_budget` after the primary part completes are
+    /// merged into it, the rest are yielded as subsequent payloads.
+    pub fn execute_incremental<'a>(
+        self,
+        fetcher: &'a impl Fetcher,
+        node: &'a RootNode<'_>,
+        latency_budget: std::time::Duration,
+    ) -> BoxStream<'a, IncrementalResponse>
+    where
+        'e: 'a,
+    {
+        let DeferNode { primary, deferred } = match node {
+            RootNode::Defer(node) => node,
+            _ => {
+                return Box::pin(futures_util::stream::once(async move {
+                    IncrementalResponse::Initial {
+                        response: self.execute_query(fetcher, node).await,
+                        has_next: false,
+                    }
+                }))
+            },
+        };
+
+        Box::pin(async_stream::stream! {
+            let mut pending = deferred
+                .iter()
+                .map(|deferred| execute_deferred(self.fork(), fetcher, deferred))
+                .collect::<FuturesUnordered<_>>();
+            let mut completed = Vec::new();
+
+            {
+                let primary = self.execute_node(fetcher, primary);
+                tokio::pin!(primary);
+                loop {
+                    tokio::select! {
+                        _ = &mut primary => break,
+                        Some(item) = pending.next() => completed.push(item),
+                    }
+                }
+            }
+
+            let deadline = tokio::time::sleep_until(Instant::now() + latency_budget);
+            tokio::pin!(deadline);
+            while !pending.is_empty() {
+                tokio::select! {
+                    _ = &mut deadline => break,
+                    Some(item) = pending.next() => completed.push(item),
+                }
+            }
+
+            let mut resp = self.resp.into_inner();
+            for (_, deferred_resp) in completed {
+                merge_response(&mut resp, deferred_resp);
+            }
+            // Every root field may be deferred.
+            if resp.data == ConstValue::Null && resp.errors.is_empty() {
+                resp.data = ConstValue::Object(Default::default());
+            }
+            yield IncrementalResponse::Initial {
+                response: resp,
+                has_next: !pending.is_empty(),
+            };
+
+            while let Some((label, resp)) = pending.next().await {
+                yield IncrementalResponse::Subsequent {
+                    incremental: vec![IncrementalPayload {
+                        data: resp.data,
+                        path: Vec::new(),
+                        label: label.map(ToString::to_string),
+                        errors: resp.errors,
+                        extensions: resp.extensions,
+                    }],
+                    has_next: !pending.is_empty(),
+                };
+            }
+        })
+    }
+
+    /// Execute a query plan over the connections of `subscriber` and return
+    /// a stream of incremental payloads, as [`Executor::execute_incremental`].
+    pub fn execute_stream_incremental<'a, S: Subscriber + 'a>(
+        self,
+        subscriber: S,
+        node: &'a RootNode<'e>,
+        latency_budget: std::time::Duration,
+    ) -> BoxStream<'a, IncrementalResponse> {
+        Box::pin(async_stream::stream! {
+            let fetcher = SubscriberFetcher::new(subscriber);
+            let mut stream = self.execute_incremental(&fetcher, node, latency_budget);
+            while let Some(resp) = stream.next().await {
+                yield resp;
+            }
+        })
+    }
+
+    /// Execute a subscription plan and return a stream.
+    pub async fn execute_stream<'a, S: Subscriber + 'a>(
+        self,
+        subscriber: S,
+        id: &str,
+        node: &'a RootNode<'e>,
+    ) -> BoxStream<'a, Response> {
+        let fetcher = SubscriberFetcher::new(subscriber.clone());
+        match node {
+            RootNode::Query(node) => Box::pin(async_stream::stream! {
+                self.execute_node(&fetcher, node).await;
+                yield self.resp.into_inner();
+            }),
+            RootNode::Defer(_) => Box::pin(async_stream::stream! {
+                yield self.execute_query(&fetcher, node).await;
+            }),
+            RootNode::Subscribe(SubscribeNode {
+                subscribe_nodes,
+                flatten_node,
+            }) => {
+                let tracer = global::tracer("graphql");
+                let span = tracer.start("subscribe");
+                let cx = Context::current_with_span(span);
+
+                let res = {
+                    let subscriber = subscriber.clone();
+                    async move {
+                        let (tx, rx) = mpsc::unbounded_channel();
+
+                        futures_util::future::try_join_all(subscribe_nodes.iter().map(|node| {
+                            let tracer = global::tracer("graphql");
+                            let attributes = vec![
+                                KEY_SERVICE.string(node.service.to_string()),
+                                KEY_QUERY.string(node.query.to_string()),
+                                KEY_VARIABLES.string(serde_json::to_string(&node.variables).unwrap()),
+                            ];
+                            let span = tracer
+                                .span_builder(format!("subscribe [{}]", node.service))
+                                .with_attributes(attributes)
+                                .start(&tracer);
+                            let cx = Context::current_with_span(span);
+                            subscriber
+                                .subscribe(
+                                    id,
+                                    node.service,
+                                    Request::new(node.query.to_string()).variables(node.variables.to_variables()),
+                                    tx.clone(),
+                                )
+                                .with_context(cx)
+                        }))
+                        .await
+                        .map(move |_| rx)
+                        .map_err(|err| {
+                            Context::current()
+                                .span()
+                                .add_event("Failed to subscribe".to_string(), vec![
+                                    KEY_ERROR.string(err.to_string())
+                                ]);
+                            Response {
+                                data: ConstValue::Null,
+                                errors: vec![ServerError {
+                                    message: err.to_string(),
+                                    path: Default::default(),
+                                    locations: Default::default(),
+                                    extensions: Default::default(),
+                                    status: None,
+                                }],
+                                extensions: Default::default(),
+                                headers: Default::default(),
+                            }
+                        })
+                    }
+                    .with_context(cx.clone())
+                    .await
+                };
+
+                match res {
+                    Ok(mut stream) => Box::pin(
+                        async_stream::stream! {
+                            while let Some(mut response) = stream.recv().await {
+                                // The root fields of the services subscribed to have distinct response keys.
+                                for node in subscribe_nodes {
+                                    node.query.rename_enum_values(&mut response.data);
+                                }
+                                if let Some(flatten_node) = flatten_node {
+                                    *self.resp.lock().await = response;
+
+                                    let cx = Context::current_with_span(tracer.span_builder("push").start(&tracer));
+                                    self.execute_node(&fetcher, flatten_node).with_context(cx).await;
+
+                                    yield std::mem::take(&mut *self.resp.lock().await);
+                                } else {
+                                    yield response;
+                                }
+                            }
+                        }
+                        .with_context(cx),
+                    ),
+                    Err(response) => {
+                        subscriber.stop(id).await;
+                        Box::pin(futures_util::stream::once(async move { response }).boxed())
+                    },
+                }
+            },
+        }
+    }
+
+    fn execute_node<'a>(&'a self, fetcher: &'a impl Fetcher, node: &'a PlanNode<'_>) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            match node {
+                PlanNode::Sequence(sequence) => {
+                    let tracer = global::tracer("graphql");
+                    self.execute_sequence_node(fetcher, sequence)
+                        .with_context(Context::current_with_span(tracer.start("sequence")))
+                        .await
+                },
+                PlanNode::Parallel(parallel) => {
+                    let tracer = global::tracer("graphql");
+                    self.execute_parallel_node(fetcher, parallel)
+                        .with_context(Context::current_with_span(tracer.start("parallel")))
+                        .await
+                },
+                PlanNode::Introspection(introspection) => {
+                    let tracer = global::tracer("graphql");
+                    self.execute_introspection_node(introspection)
+                        .with_context(Context::current_with_span(tracer.start("introspection")))
+                        .await
+                },
+                PlanNode::Fetch(fetch) => self.execute_fetch_node(fetcher, fetch).await,
+                PlanNode::Flatten(flatten) => self.execute_flatten_node(fetcher, flatten).await,
+            }
+        })
+    }
+
+    async fn execute_sequence_node(&self, fetcher: &impl Fetcher, sequence: &SequenceNode<'_>) {
+        for node in &sequence.nodes {
+            self.execute_node(fetcher, node).await;
+        }
+    }
+
+    async fn execute_parallel_node(&self, fetcher: &impl Fetcher, parallel: &ParallelNode<'_>) {
+        futures_util::future::join_all(
+            parallel
+                .nodes
+                .iter()
+                .map(|node| async move { self.execute_node(fetcher, node).await }),
+        )
+        .await;
+    }
+
+    async fn execute_introspection_node(&self, introspection: &IntrospectionNode) {
+        let value = IntrospectionRoot.resolve(
+            &introspection.selection_set,
+            self.introspection_schema.unwrap_or(self.schema),
+        );
+        let mut current_resp = self.resp.lock().await;
+        merge_data(&mut current_resp.data, value);
+    }
+
+    async fn execute_fetch_node(&self, fetcher: &impl Fetcher, fetch: &FetchNode<'_>) {
+        let request = fetch.to_request();
+
+        let tracer = global::tracer("graphql");
+        let span = tracer
+            .span_builder(format!("fetch [{}]", fetch.service))
+            .with_attributes(vec![
+                KEY_SERVICE.string(fetch.service.to_string()),
+                KEY_QUERY.string(fetch.query.to_string()),
+                KEY_VARIABLES.string(serde_json::to_string(&request.variables).unwrap()),
+            ])
+            .start(&tracer);
+        let cx = Context::current_with_span(span);
+
+        let metadata = self
+            .debug_errors
+            .then(|| RequestMetadata::new(fetcher, fetch.service, &request));
+
+        async move {
+            let res = fetcher.query(fetch.service, request).await;
+            let mut current_resp = self.resp.lock().await;
+            let errors_start = current_resp.errors.len();
+            let status = res.as_ref().err().and_then(error_status);
+
+            match res {
+                Ok(mut resp) => {
+                    fetch.query.rename_enum_values(&mut resp.data);
+                    forward_warnings(&mut current_resp, &resp);
+                    if resp.errors.is_empty() {
+                        add_tracing_spans(&mut resp);
+                        current_resp.headers = resp.headers;
+                        merge_data(&mut current_resp.data, resp.data);
+                    } else if self.normalize_error_paths {
+                        current_resp.errors.extend(resp.errors);
+                    } else {
+                        rewrite_errors(None, &mut current_resp.errors, resp.errors);
+                    }
+                },
+                Err(err) => current_resp.errors.push(fetch_error(err)),
+            }
+
+            if let Some(metadata) = metadata {
+                metadata.add_to(&mut current_resp.errors[errors_start..], status);
+            }
+        }
+        .with_context(cx)
+        .await
+    }
+
+    async fn execute_flatten_node(&self, fetcher: &impl Fetcher, flatten: &FlattenNode<'_>) {
+        enum Representation {
+            Keys(ConstValue),
+            Skip,
+        }
+
+        fn extract_keys(
+            from: &mut IndexMap<Name, ConstValue>,
+            prefix: &str,
+            possible_type: Option<&str>,
+        ) -> Representation {
+            // Objects of the possible types not fetched have no keys selected.
+            match from.get(format!("{}__typename", prefix).as_str()) {
+                Some(ConstValue::String(typename)) if possible_type.is_none_or(|ty| typename == ty) => {},
+                _ => return Representation::Skip,
+            }
+
+            let mut res = IndexMap::new();
+            let mut keys = Vec::new();
+            for key in from.keys() {
+                if key.as_str().starts_with(prefix) {
+                    keys.push(key.clone());
+                }
+            }
+            for key in keys {
+                if let Some(value) = from.shift_remove(&key) {
+                    let name = Name::new(&key[prefix.len()..]);
+                    res.insert(name, value);
+                }
+            }
+            Representation::Keys(ConstValue::Object(res))
+        }
+
+        fn get_representations(
+            representations: &mut Vec<Representation>,
+            value: &mut ConstValue,
+            path: &[PathSegment<'_>],
+            prefix: &str,
+        ) {
+            let segment = match path.first() {
+                Some(segment) => segment,
+                None => return,
+            };
+            let is_last = path.len() == 1;
+
+            if is_last {
+                match value {
+                    ConstValue::Object(object) if !segment.is_list => {
+                        if let Some(ConstValue::Object(key_object)) = object.get_mut(segment.name) {
+                            representations.push(extract_keys(key_object, prefix, segment.possible_type));
+                        } else {
+                            representations.push(Representation::Skip);
+                        }
+                    },
+                    ConstValue::Object(object) if segment.is_list => {
+                        if let Some(ConstValue::List(array)) = object.get_mut(segment.name) {
+                            for element in array {
+                                if let ConstValue::Object(element_obj) = element {
+                                    representations.push(extract_keys(element_obj, prefix, segment.possible_type));
+                                } else {
+                                    representations.push(Representation::Skip);
+                                }
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            } else {
+                match value {
+                    ConstValue::Object(object) if !segment.is_list => {
+                        if let Some(next_value) = object.get_mut(segment.name) {
+                            get_representations(representations, next_value, &path[1..], prefix);
+                        } else {
+                            representations.push(Representation::Skip);
+                        }
+                    },
+                    ConstValue::Object(object) if segment.is_list => {
+                        if let Some(ConstValue::List(array)) = object.get_mut(segment.name) {
+                            for element in array {
+                                get_representations(representations, element, &path[1..], prefix);
+                            }
+                        } else {
+                            representations.push(Representation::Skip);
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        fn flatten_values(
+            target: &mut ConstValue,
+            path: &[PathSegment<'_>],
+            values: &mut impl Iterator<Item = ConstValue>,
+            flags: &mut impl Iterator<Item = bool>,
+        ) {
+            let segment = match path.first() {
+                Some(segment) => segment,
+                None => return,
+            };
+            let is_last = path.len() == 1;
+            if is_last {
+                match target {
+                    ConstValue::Object(object) if !segment.is_list => {
+                        if let Some(target) = object.get_mut(segment.name) {
+                            if let Some(true) = flags.next() {
+                                if let Some(value) = values.next() {
+                                    merge_data(target, value);
+                                }
+                            }
+                        }
+                    },
+                    ConstValue::Object(object) if segment.is_list => {
+                        if let Some(ConstValue::List(array)) = object.get_mut(segment.name) {
+                            for element in array {
+                                if let Some(true) = flags.next() {
+                                    if let Some(value) = values.next() {
+                                        merge_data(element, value);
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            } else {
+                match target {
+                    ConstValue::Object(object) if !segment.is_list => {
+                        if let Some(next_value) = object.get_mut(segment.name) {
+                            flatten_values(next_value, &path[1..], values, flags);
+                        }
+                    },
+                    ConstValue::Object(object) if segment.is_list => {
+                        if let Some(ConstValue::List(array)) = object.get_mut(segment.name) {
+                            for element in array {
+                                flatten_values(element, &path[1..], values, flags);
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        fn to_variables(values: Vec<ConstValue>) -> Variables {
+            let mut variables = Variables::default();
+            variables.insert(Name::new("representations"), ConstValue::List(values));
+            variables
+        }
+
+        let (representations, lookup, alternate_representations, flags, sent_positions) = {
+            let mut representations = Vec::new();
+            let mut resp = self.resp.lock().await;
+            get_representations(
+                &mut representations,
+                &mut resp.data,
+                &flatten.path,
+                &flatten.key_prefix(flatten.prefix),
+            );
+            if representations.is_empty() {
+                return;
+            }
+
+            let mut flags = Vec::with_capacity(representations.len());
+            let mut values = Vec::with_capacity(representations.len());
+
+            for representation in representations {
+                match representation {
+                    Representation::Keys(value) => {
+                        values.push(value);
+                        flags.push(true);
+                    },
+                    Representation::Skip => flags.push(false),
+                }
+            }
+            rename_entity_types(&mut values, &flatten.interface_objects);
+
+            let lookup = self
+                .representations
+                .lookup(flatten.service, flatten.query.to_string(), &values);
+
+            // The alternate keys are selected next to the first key, so their
+            // representations line up with the flags.
+            let alternate_representations = flatten
+                .alternate_prefixes
+                .iter()
+                .map(|prefix| {
+                    let mut representations = Vec::new();
+                    get_representations(
+                        &mut representations,
+                        &mut resp.data,
+                        &flatten.path,
+                        &flatten.key_prefix(*prefix),
+                    );
+                    let mut values = representations
+                        .into_iter()
+                        .zip(&flags)
+                        .filter(|(_, flag)| **flag)
+                        .filter_map(|(representation, _)| match representation {
+                            Representation::Keys(value) => Some(value),
+                            Representation::Skip => None,
+                        })
+                        .collect::<Vec<_>>();
+                    rename_entity_types(&mut values, &flatten.interface_objects);
+                    to_variables(lookup.missing(&values))
+                })
+                .collect::<Vec<_>>();
+
+            if lookup.is_complete() {
+                let values = self.representations.resolve(lookup, Vec::new(), &[]);
+                flatten_values(
+                    &mut resp.data,
+                    &flatten.path,
+                    &mut values.into_iter().fuse(),
+                    &mut flags.into_iter().fuse(),
+                );
+                return;
+            }
+
+            // The positions of the occurrences of the representations sent
+            // among those of the round, to translate the paths of the errors.
+            let sent_positions = self.normalize_error_paths.then(|| {
+                let positions = flags
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, flag)| **flag)
+                    .map(|(position, _)| position)
+                    .collect::<Vec<_>>();
+                lookup
+                    .missing_indices()
+                    .map(|indices| {
+                        indices
+                            .iter()
+                            .filter_map(|index| positions.get(*index).copied())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            (
+                to_variables(lookup.missing(&values)),
+                lookup,
+                alternate_representations,
+                flags,
+                sent_positions,
+            )
+        };
+        let (entity_types, representation_count) = entity_types(&representations);
+        let sent_representations = match representations.get("representations") {
+            Some(ConstValue::List(representations)) if flatten.check_keys => Some(representations.clone()),
+            _ => None,
+        };
+        let request = flatten.to_request(representations);
+
+        let tracer = global::tracer("graphql");
+        let span = tracer
+            .span_builder(format!("flatten [{}]", flatten.service))
+            .with_attributes(vec![
+                KEY_SERVICE.string(flatten.service.to_string()),
+                KEY_QUERY.string(flatten.query.to_string()),
+                KEY_VARIABLES.string(serde_json::to_string(&request.variables).unwrap()),
+                KEY_PATH.string(flatten.path.to_string()),
+                KEY_ENTITY_TYPE.string(entity_types),
+                KEY_REPRESENTATIONS.i64(representation_count as i64),
+            ])
+            .start(&tracer);
+        let cx = Context::current_with_span(span);
+
+        let metadata = self
+            .debug_errors
+            .then(|| RequestMetadata::new(fetcher, flatten.service, &request));
+
+        let batch_size = self
+            .connection_batch_size
+            .filter(|batch_size| representation_count > *batch_size && is_connection_path(&flatten.path));
+        let batches = batch_size.zip(match request.variables.get("representations") {
+            Some(ConstValue::List(representations)) => Some(representations.clone()),
+            _ => None,
+        });
+
+        async move {
+            let mut sent_representations = sent_representations;
+            let res = match batches {
+                Some((batch_size, representations)) => {
+                    let (resp, retried) = fetch_in_batches(
+                        self.schema,
+                        fetcher,
+                        flatten,
+                        representations,
+                        &alternate_representations,
+                        batch_size,
+                    )
+                    .await;
+                    if retried {
+                        // The entities fetched by other keys are not checked.
+                        sent_representations = None;
+                    }
+                    Ok(resp)
+                },
+                None => {
+                    let mut res = fetch_entities(self.schema, fetcher, flatten, request).await;
+                    if matches!(&res, Ok(resp) if resp.errors.is_empty() && all_entities_null(&resp.data)) {
+                        if let Some(resp) = retry_alternate_keys(fetcher, flatten, alternate_representations).await {
+                            // The entities fetched by other keys are not checked.
+                            sent_representations = None;
+                            res = Ok(resp);
+                        }
+                    }
+                    res
+                },
+            };
+            let current_resp = &mut self.resp.lock().await;
+            let errors_start = current_resp.errors.len();
+            let status = res.as_ref().err().and_then(error_status);
+            let entity_paths = sent_positions.map(|positions| {
+                let paths = representation_paths(&current_resp.data, &flatten.path);
+                positions
+                    .into_iter()
+                    .map(|positions| {
+                        positions
+                            .into_iter()
+                            .filter_map(|position| paths.get(position).cloned())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            });
+            let rewrite_errors = |target: &mut Vec<ServerError>, errors: Vec<ServerError>| match &entity_paths {
+                Some(entity_paths) => normalize_entity_errors(entity_paths, target, errors),
+                None => rewrite_errors(Some(&flatten.path), target, errors),
+            };
+
+            match res {
+                Ok(mut resp) => {
+                    forward_warnings(current_resp, &resp);
+                    // The entities of batches that succeeded are merged even
+                    // if other batches failed.
+                    if resp.errors.is_empty() || batch_size.is_some() {
+                        add_tracing_spans(&mut resp);
+                        let mut errors = resp.errors;
+                        let entities = match resp.data {
+                            ConstValue::Object(mut data) => match data.shift_remove("_entities") {
+                                Some(ConstValue::List(values)) => Some(values),
+                                _ => None,
+                            },
+                            _ => None,
+                        };
+                        if let Some(mut values) = entities {
+                            if let Some(sent) = &sent_representations {
+                                errors.extend(check_entity_keys(flatten, sent, &mut values));
+                            }
+                            let values = self.representations.resolve(lookup, values, &errors);
+                            flatten_values(
+                                &mut current_resp.data,
+                                &flatten.path,
+                                &mut values.into_iter().fuse(),
+                                &mut flags.into_iter().fuse(),
+                            );
+                        }
+                        rewrite_errors(&mut current_resp.errors, errors);
+                    } else {
+                        rewrite_errors(&mut current_resp.errors, resp.errors);
+                    }
+                },
+                Err(err) => current_resp.errors.push(fetch_error(err)),
+            }
+
+            if let Some(metadata) = metadata {
+                metadata.add_to(&mut current_resp.errors[errors_start..], status);
+            }
+        }
+        .with_context(cx)
+        .await
+    }
+}
+
+/// Whether the keys a service resolved an entity with match those of its
+/// representation, which may have more fields, such as required ones.
+fn keys_match(sent: &ConstValue, echoed: &ConstValue) -> bool {
+    match (sent, echoed) {
+        (ConstValue::Object(sent), ConstValue::Object(echoed)) => echoed
+            .iter()
+            .all(|(name, echoed)| sent.get(name).map(|sent| keys_match(sent, echoed)).unwrap_or_default()),
+        (sent, echoed) => sent == echoed,
+    }
+}
+
+/// Check that the entities fetched echo the keys of the representations
+/// they were fetched by.
+///
+/// The echoed keys are removed from the entities, and the entities resolved
+/// with other keys, which would be merged into the wrong objects, are
+/// replaced with `null`.
+fn check_entity_keys(flatten: &FlattenNode<'_>, sent: &[ConstValue], values: &mut [ConstValue]) -> Vec<ServerError> {
+    let prefix = flatten.key_prefix(flatten.prefix);
+    let mut errors = Vec::new();
+    for (index, (sent, value)) in sent.iter().zip(values.iter_mut()).enumerate() {
+        let entity = match value {
+            ConstValue::Object(entity) => entity,
+            _ => continue,
+        };
+        let keys = entity
+            .keys()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        let echoed = ConstValue::Object(
+            keys.into_iter()
+                .filter_map(|key| {
+                    let value = entity.shift_remove(&key)?;
+                    Some((Name::new(&key[prefix.len()..]), value))
+                })
+                .collect(),
+        );
+        if echoed == ConstValue::Object(Default::default()) || keys_match(sent, &echoed) {
+            continue;
+        }
+
+        tracing::warn!(
+            service = flatten.service,
+            path = %flatten.path,
+            sent = %sent,
+            echoed = %echoed,
+            "The service resolved an entity with other keys than those of its representation."
+        );
+        let mut error = ServerError::new(format!(
+            "The service \"{}\" resolved the entity {} with the keys {}.",
+            flatten.service, sent, echoed
+        ));
+        error.path = vec![
+            ConstValue::String("_entities".to_string()),
+            ConstValue::Number(index.into()),
+        ];
+        error
+            .extensions
+            .insert("code".to_string(), ConstValue::String(ENTITY_KEY_MISMATCH.to_string()));
+        error
+            .extensions
+            .insert("service".to_string(), ConstValue::String(flatten.service.to_string()));
+        errors.push(error);
+        *value = ConstValue::Null;
+    }
+    errors
+}
+
+/// Replace the `__typename` of the representations of the types the service
+/// resolves as an interface.
+fn rename_entity_types(values: &mut [ConstValue], interface_objects: &IndexMap<&str, &str>) {
+    if interface_objects.is_empty() {
+        return;
+    }
+    for value in values {
+        if let ConstValue::Object(value) = value {
+            if let Some(ConstValue::String(typename)) = value.get_mut("__typename") {
+                if let Some(interface) = interface_objects.get(typename.as_str()) {
+                    *typename = interface.to_string();
+                }
+            }
+        }
+    }
+}
+
+/// The distinct `__typename`s of the representations of an entity fetch,
+/// joined with commas, and the number of representations.
+fn entity_types(representations: &Variables) -> (String, usize) {
+    let representations = match representations.get("representations") {
+        Some(ConstValue::List(representations)) => representations,
+        _ => return (String::new(), 0),
+    };
+    let mut entity_types = Vec::new();
+    for representation in representations {
+        if let ConstValue::Object(representation) = representation {
+            if let Some(ConstValue::String(typename)) = representation.get("__typename") {
+                if !entity_types.contains(&typename.as_str()) {
+                    entity_types.push(typename.as_str());
+                }
+            }
+        }
+    }
+    (entity_types.join(","), representations.len())
+}
+
+/// Whether a service returned `null` for every representation of an entity
+/// fetch.
+pub(crate) fn all_entities_null(data: &ConstValue) -> bool {
+    match data {
+        ConstValue::Object(data) => match data.get("_entities") {
+            Some(ConstValue::List(values)) => !values.is_empty() && values.iter().all(|v| *v == ConstValue::Null),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Fetch entities from the service of a flatten node, or from the service
+/// they moved to if it refuses the fetch with an [`EntityMovedError`].
+///
+/// This shifts the traffic of entities migrating to another service before
+/// the schema is recomposed. Fetches are redirected once at most, so that
+/// services naming each other cannot loop, and only to a service defining a
+/// key of every entity type, so that a subgraph cannot send the fetch and its
+/// forwarded headers to any service of the routing table.
+pub(crate) async fn fetch_entities(
+    schema: &ComposedSchema,
+    fetcher: &impl Fetcher,
+    flatten: &FlattenNode<'_>,
+    request: Request,
+) -> anyhow::Result<Response> {
+    let redirected_request = Request::new(request.query.clone()).variables(request.variables.clone());
+    let err = match fetcher.query(flatten.service, request).await {
+        Ok(mut resp) => {
+            flatten.query.rename_enum_values(&mut resp.data);
+            return Ok(resp);
+        },
+        Err(err) => err,
+    };
+    let moved_to = match err.downcast_ref::<EntityMovedError>() {
+        Some(moved) if moved.moved_to != flatten.service => moved.moved_to.clone(),
+        _ => return Err(err),
+    };
+    if !defines_entities(schema, &moved_to, &redirected_request.variables) {
+        tracing::warn!(
+            service = flatten.service,
+            moved_to = %moved_to,
+            path = %flatten.path,
+            "The entities moved to a service not defining them, the fetch is not redirected."
+        );
+        return Err(err);
+    }
+
+    METRICS.entity_redirects.add(1, &[
+        KEY_SERVICE.string(flatten.service.to_string()),
+        KEY_TARGET_SERVICE.string(moved_to.clone()),
+    ]);
+    tracing::warn!(
+        service = flatten.service,
+        moved_to = %moved_to,
+        path = %flatten.path,
+        "The entities moved to another service, redirecting the fetch."
+    );
+    let mut resp = fetcher.query(&moved_to, redirected_request).await?;
+    flatten.query.rename_enum_values(&mut resp.data);
+    resp.add_warning(format!(
+        "The entities at \"{}\" moved from service \"{}\" to service \"{}\" and were fetched from it.",
+        flatten.path, flatten.service, moved_to
+    ));
+    Ok(resp)
+}
+
+/// Whether a service defines a key of every entity type of the
+/// representations.
+fn defines_entities(schema: &ComposedSchema, service: &str, representations: &Variables) -> bool {
+    let (entity_types, _) = entity_types(representations);
+    entity_types.split(',').all(|entity_type| {
+        schema
+            .types
+            .get(entity_type)
+            .is_some_and(|ty| ty.keys.contains_key(service))
+    })
+}
+
+/// Fetch the entities by their alternate keys, in order, returning the first
+/// response that resolves any of them.
+///
+/// Services may only be able to resolve an entity by some of its keys, for
+/// example while a key is being migrated.
+pub(crate) async fn retry_alternate_keys(
+    fetcher: &impl Fetcher,
+    flatten: &FlattenNode<'_>,
+    alternate_representations: Vec<Variables>,
+) -> Option<Response> {
+    for representations in alternate_representations {
+        METRICS.entity_key_retries.add(1, &[
+            KEY_SERVICE.string(flatten.service.to_string()),
+            KEY_PATH.string(flatten.path.to_string()),
+        ]);
+        tracing::warn!(
+            service = flatten.service,
+            path = %flatten.path,
+            "The service returned null for every entity, retrying with an alternate key."
+        );
+        match fetcher
+            .query(flatten.service, flatten.to_request(representations))
+            .await
+        {
+            Ok(mut resp) if resp.errors.is_empty() && !all_entities_null(&resp.data) => {
+                flatten.query.rename_enum_values(&mut resp.data);
+                resp.add_warning(format!(
+                    "The service \"{}\" resolved no entity at \"{}\" by its first key, they were fetched by an \
+                     alternate key.",
+                    flatten.service, flatten.path
+                ));
+                return Some(resp);
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// The subgraph request added to the extensions of errors in debug mode.
+///
+/// Variable values are left out, as they may contain personal data.
+struct RequestMetadata {
+    service: String,
+    url: Option<String>,
+    query: String,
+    variables: Vec<Name>,
+}
+
+impl RequestMetadata {
+    fn new(fetcher: &impl Fetcher, service: &str, request: &Request) -> Self {
+        Self {
+            service: service.to_string(),
+            url: fetcher.url(service),
+            query: request.query.clone(),
+            variables: request.variables.keys().cloned().collect(),
+        }
+    }
+
+    fn add_to(self, errors: &mut [ServerError], status: Option<u16>) {
+        let mut metadata = IndexMap::new();
+        metadata.insert(Name::new("service"), ConstValue::String(self.service));
+        if let Some(url) = self.url {
+            metadata.insert(Name::new("url"), ConstValue::String(url));
+        }
+        metadata.insert(Name::new("query"), ConstValue::String(self.query));
+        metadata.insert(
+            Name::new("variables"),
+            ConstValue::List(
+                self.variables
+                    .into_iter()
+                    .map(|name| ConstValue::String(name.to_string()))
+                    .collect(),
+            ),
+        );
+        if let Some(status) = status {
+            metadata.insert(Name::new("status"), ConstValue::Number(status.into()));
+        }
+
+        let metadata = ConstValue::Object(metadata);
+        for error in errors {
+            error.extensions.insert("subgraphRequest".to_string(), metadata.clone());
+        }
+    }
+}
+
+/// The HTTP status of a failed subgraph request, if it got a response.
+fn error_status(err: &anyhow::Error) -> Option<u16> {
+    err.downcast_ref::<SubgraphStatusError>().map(|err| err.status)
+}
+
+async fn execute_deferred<'a>(
+    executor: Executor<'_>,
+    fetcher: &impl Fetcher,
+    deferred: &'a DeferredNode<'_>,
+) -> (Option<&'a str>, Response) {
+    executor.execute_node(fetcher, &deferred.node).await;
+    (deferred.label, executor.resp.into_inner())
+}
+
+/// Convert a failed fetch to an error, with a code for known failures.
+pub(crate) fn fetch_error(err: anyhow::Error) -> ServerError {
+    let mut error = ServerError::new(err.to_string());
+    if let Some(err) = err.downcast_ref::<RateLimitedError>() {
+        error.extensions.insert(
+            "code".to_string(),
+            ConstValue::String(SUBGRAPH_RATE_LIMITED.to_string()),
+        );
+        error
+            .extensions
+            .insert("service".to_string(), ConstValue::String(err.service.clone()));
+        error.extensions.insert(
+            "retryAfter".to_string(),
+            ConstValue::Number((err.retry_after.as_secs_f64().ceil() as u64).into()),
+        );
+    }
+    if let Some(err) = err.downcast_ref::<CircuitOpenError>() {
+        error.extensions.insert(
+            "code".to_string(),
+            ConstValue::String(SUBGRAPH_CIRCUIT_OPEN.to_string()),
+        );
+        error
+            .extensions
+            .insert("service".to_string(), ConstValue::String(err.service.clone()));
+        error.extensions.insert(
+            "retryAfter".to_string(),
+            ConstValue::Number((err.retry_after.as_secs_f64().ceil() as u64).into()),
+        );
+    }
+    if let Some(err) = err.downcast_ref::<SubgraphStatusError>() {
+        error.status = Some(err.status);
+        error
+            .extensions
+            .insert("code".to_string(), ConstValue::String(err.code().to_string()));
+        error
+            .extensions
+            .insert("service".to_string(), ConstValue::String(err.service.clone()));
+    }
+    if let Some(err) = err.downcast_ref::<ResponseTooLargeError>() {
+        error
+            .extensions
+            .insert("code".to_string(), ConstValue::String(RESPONSE_TOO_LARGE.to_string()));
+        error
+            .extensions
+            .insert("service".to_string(), ConstValue::String(err.service.clone()));
+    }
+    error
+}
+
+/// Add the warnings of a subgraph response to the response of the
+/// operation.
+fn forward_warnings(target: &mut Response, resp: &Response) {
+    for warning in resp.warnings() {
+        target.add_warning(warning);
+    }
+}
+
+fn merge_response(target: &mut Response, resp: Response) {
+    merge_data(&mut target.data, resp.data);
+    target.errors.extend(resp.errors);
+    target.merge_extensions(resp.extensions);
+    if target.headers.is_none() {
+        target.headers = resp.headers;
+    }
+}
+
+fn merge_data(target: &mut ConstValue, value: ConstValue) {
+    match (target, value) {
+        (target @ ConstValue::Null, fragment) => *target = fragment,
+        (ConstValue::Object(object), ConstValue::Object(fragment_object)) => {
+            for (key, value) in fragment_object {
+                match object.get_mut(&key) {
+                    Some(target) => merge_data(target, value),
+                    None => {
+                        object.insert(key, value);
+                    },
+                }
+            }
+        },
+        (ConstValue::List(array), ConstValue::List(fragment_array)) if array.len() == fragment_array.len() => {
+            for (idx, element) in fragment_array.into_iter().enumerate() {
+                merge_data(&mut array[idx], element);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn rewrite_errors(prefix_path: Option<&ResponsePath<'_>>, target: &mut Vec<ServerError>, errors: Vec<ServerError>) {
+    for mut err in errors {
+        let mut path = Vec::new();
+
+        if let Some(prefix_path) = prefix_path {
+            for segment in prefix_path.iter() {
+                path.push(ConstValue::String(segment.name.to_string()));
+                if segment.is_list {
+                    path.push(ConstValue::Number(0.into()));
+                }
+            }
+        }
+
+        if matches!(err.path.first(), Some(ConstValue::String(s)) if s=="_entities") {
+            path.extend(err.path.drain(1..));
+        }
+
+        err.path
+            .iter()
+            .filter(|x| matches!(x, ConstValue::String(_)))
+            .for_each(|x| {
+                path.push(x.clone());
+            });
+
+        target.push(ServerError {
+            message: err.message,
+            path,
+            locations: err.locations,
+            extensions: err.extensions,
+            status: err.status,
+        })
+    }
+}
+
+/// The paths in the response of the representations of an entity fetch, in
+/// the order they are collected, including those skipped.
+fn representation_paths(data: &ConstValue, path: &[PathSegment<'_>]) -> Vec<Vec<ConstValue>> {
+    fn collect(
+        paths: &mut Vec<Vec<ConstValue>>,
+        current: &mut Vec<ConstValue>,
+        value: &ConstValue,
+        path: &[PathSegment<'_>],
+    ) {
+        let (segment, object) = match (path.first(), value) {
+            (Some(segment), ConstValue::Object(object)) => (segment, object),
+            _ => return,
+        };
+        current.push(ConstValue::String(segment.name.to_string()));
+        match (object.get(segment.name), segment.is_list) {
+            (Some(ConstValue::List(array)), true) => {
+                for (index, element) in array.iter().enumerate() {
+                    current.push(ConstValue::Number(index.into()));
+                    match path.len() {
+                        1 => paths.push(current.clone()),
+                        _ => collect(paths, current, element, &path[1..]),
+                    }
+                    current.pop();
+                }
+            },
+            (_, true) if path.len() == 1 => {},
+            (Some(next_value), false) if path.len() > 1 => collect(paths, current, next_value, &path[1..]),
+            _ => paths.push(current.clone()),
+        }
+        current.pop();
+    }
+
+    let mut paths = Vec::new();
+    collect(&mut paths, &mut Vec::new(), data, path);
+    paths
+}
+
+/// Translate the paths of the errors of an entity fetch, relative to its
+/// `_entities` field, to the paths of the entities they are about, by the
+/// paths of the occurrences of the representations sent.
+///
+/// The errors about a representation repeated in the round are reported at
+/// every occurrence, and the errors about no entity lose their path.
+fn normalize_entity_errors(
+    entity_paths: &[Vec<Vec<ConstValue>>],
+    target: &mut Vec<ServerError>,
+    errors: Vec<ServerError>,
+) {
+    for mut err in errors {
+        let paths = entity_index(&err)
+            .and_then(|index| entity_paths.get(index))
+            .map(|paths| {
+                paths
+                    .iter()
+                    .filter(|entity_path| !entity_path.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if paths.is_empty() {
+            err.path = Vec::new();
+            target.push(err);
+            continue;
+        }
+        let field_path = err.path.split_off(2);
+        for entity_path in paths {
+            let mut err = err.clone();
+            err.path = entity_path.iter().cloned().chain(field_path.iter().cloned()).collect();
+            target.push(err);
+        }
+    }
+}
+
+fn add_tracing_spans(response: &mut Response) {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TracingResult {
+        version: i32,
+        start_time: DateTime<Utc>,
+        execution: TracingExecution,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TracingExecution {
+        resolvers: Vec<TracingResolver>,
+    }
+
+    #[derive(Default, Clone)]
+    struct Path {
+        path: String,
+        parent_end: usize,
+    }
+
+    impl Path {
+        fn full_path(&self) -> &str {
+            &self.path
+        }
+
+        fn parent_path(&self) -> &str {
+            &self.path[..self.parent_end]
+        }
+    }
+
+    fn deserialize_path<'de, D>(deserialize: D) -> Result<Path, D::Error>
+    where D: Deserializer<'de> {
+        let segments = Vec::<ConstValue>::deserialize(deserialize)?;
+
+        fn write_path<W: std::fmt::Write>(w: &mut W, value: &ConstValue) {
+            match value {
+                ConstValue::Number(idx) => {
+                    write!(w, "{}", idx).unwrap();
+                },
+                ConstValue::String(name) => {
+                    write!(w, "{}", name).unwrap();
+                },
+                _ => {},
+            }
+        }
+
+        match segments.split_last() {
+            Some((last, parents)) => {
+                let mut full_path = String::new();
+                for (idx, p) in parents.iter().enumerate() {
+                    if idx > 0 {
+                        full_path.push('.');
+                    }
+                    write_path(&mut full_path, p);
+                }
+                let parent_end = full_path.len();
+                if !full_path.is_empty() {
+                    full_path.push('.');
+                }
+                write_path(&mut full_path, last);
+                Ok(Path {
+                    path: full_path,
+                    parent_end,
+                })
+            },
+            None => Ok(Path::default()),
+        }
+    }
+
+    #[derive(Deserialize, Clone)]
+    #[serde(rename_all = "camelCase")]
+    struct TracingResolver {
+        #[serde(deserialize_with = "deserialize_path")]
+        path: Path,
+        field_name: String,
+        parent_type: String,
+        return_type: String,
+        start_offset: i64,
+        duration: i64,
+    }
+
+    let tracing_result = match response
+        .extensions
+        .remove("tracing")
+        .and_then(|value| value::from_value::<TracingResult>(value).ok())
+    {
+        Some(tracing_result) => tracing_result,
+        None => return,
+    };
+
+    if tracing_result.version != 1 {
+        return;
+    }
+
+    let tracer = global::tracer("graphql");
+
+    let mut resolvers = HashMap::<_, Context>::new();
+    for resolver in tracing_result.execution.resolvers {
+        let attributes = vec![
+            KEY_PARENT_TYPE.string(resolver.parent_type.clone()),
+            KEY_RETURN_TYPE.string(resolver.return_type.clone()),
+            KEY_FIELD_NAME.string(resolver.field_name.clone()),
+        ];
+
+        let full_path = String::from(resolver.path.full_path());
+
+        let parent_path = String::from(resolver.path.parent_path());
+
+        let span_builder = tracer
+            .span_builder(full_path.clone())
+            .with_start_time(tracing_result.start_time + Duration::nanoseconds(resolver.start_offset))
+            .with_end_time(
+                tracing_result.start_time +
+                    Duration::nanoseconds(resolver.start_offset) +
+                    Duration::nanoseconds(resolver.duration),
+            )
+            .with_attributes(attributes);
+
+        let span = if let Some(parent_cx) = resolvers.get(&parent_path) {
+            span_builder.start_with_context(&tracer, parent_cx)
+        } else {
+            span_builder.start(&tracer)
+        };
+
+        resolvers.insert(full_path, Context::current_with_span(span));
+    }
+}
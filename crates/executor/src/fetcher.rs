@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+use graphgate_planner::{Request, Response};
+use tokio::sync::mpsc;
+
+/// Sends the requests of a query plan to the subgraphs.
+///
+/// Errors of the types in [`crate::error`] are reported with their details
+/// in the extensions of the response errors.
+#[async_trait::async_trait]
+pub trait Fetcher: Send + Sync {
+    async fn query(&self, service: &str, request: Request) -> Result<Response>;
+
+    /// The URL requests to the service are sent to, if known.
+    fn url(&self, _service: &str) -> Option<String> {
+        None
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Fetcher + ?Sized> Fetcher for &F {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        (**self).query(service, request).await
+    }
+
+    fn url(&self, service: &str) -> Option<String> {
+        (**self).url(service)
+    }
+}
+
+/// Subscribes to the subgraphs of a subscription plan.
+#[async_trait::async_trait]
+pub trait Subscriber: Clone + Send + Sync {
+    /// Subscribe to a service, sending its responses to `tx` until the
+    /// subscription is stopped.
+    async fn subscribe(
+        &self,
+        id: &str,
+        service: &str,
+        request: Request,
+        tx: mpsc::UnboundedSender<Response>,
+    ) -> Result<()>;
+
+    async fn stop(&self, id: &str);
+}
+
+/// Sends queries over the connections of a subscriber.
+pub(crate) struct SubscriberFetcher<S> {
+    subscriber: S,
+    id: AtomicU64,
+}
+
+impl<S> SubscriberFetcher<S> {
+    pub(crate) fn new(subscriber: S) -> Self {
+        Self {
+            subscriber,
+            id: Default::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Subscriber> Fetcher for SubscriberFetcher<S> {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        self.subscriber
+            .subscribe(&format!("__req{}", id), service, request, tx)
+            .await?;
+        rx.recv().await.ok_or_else(|| anyhow::anyhow!("Connection closed."))
+    }
+}
@@ -5,7 +5,7 @@ use value::ConstValue;
 use super::{
     input_value::IntrospectionInputValue,
     r#type::IntrospectionType,
-    resolver::{resolve_obj, Resolver},
+    resolver::{is_include_deprecated, resolve_obj, Resolver},
 };
 
 pub struct IntrospectionField<'a>(pub &'a MetaField);
@@ -25,6 +25,13 @@ impl Resolver for IntrospectionField<'_> {
                 self.0
                     .arguments
                     .values()
+                    .filter(|arg| {
+                        if is_include_deprecated(&field.arguments) {
+                            true
+                        } else {
+                            !arg.deprecation.is_deprecated()
+                        }
+                    })
                     .map(|arg| IntrospectionInputValue(arg).resolve(&field.selection_set, schema))
                     .collect(),
             ),
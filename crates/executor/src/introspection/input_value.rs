@@ -24,6 +24,13 @@ impl Resolver for IntrospectionInputValue<'_> {
                 Some(value) => ConstValue::String(value.to_string()),
                 None => ConstValue::Null,
             },
+            "isDeprecated" => ConstValue::Boolean(self.0.deprecation.is_deprecated()),
+            "deprecationReason" => self
+                .0
+                .deprecation
+                .reason()
+                .map(|reason| ConstValue::String(reason.to_string()))
+                .unwrap_or_default(),
             _ => ConstValue::Null,
         })
     }
@@ -1,5 +1,6 @@
 mod resolver;
 
+mod directive;
 mod enum_value;
 mod field;
 mod input_value;
@@ -3,6 +3,12 @@ use graphgate_schema::ComposedSchema;
 use indexmap::IndexMap;
 use value::{ConstValue, Name};
 
+/// The scalars of the GraphQL specification.
+pub const BUILTIN_SCALARS: &[&str] = &["Boolean", "Float", "ID", "Int", "String"];
+
+/// The directives the gateway defines for every schema.
+pub const BUILTIN_DIRECTIVES: &[&str] = &["defer", "deprecated", "include", "oneOf", "skip", "specifiedBy"];
+
 pub trait Resolver {
     fn resolve(&self, selection_set: &IntrospectionSelectionSet, schema: &ComposedSchema) -> ConstValue;
 }
@@ -47,3 +53,13 @@ pub fn is_include_deprecated(arguments: &IndexMap<Name, ConstValue>) -> bool {
         false
     }
 }
+
+/// Sort introspected items alphabetically by name, with the built-ins last,
+/// so that the output does not depend on the order the services were
+/// composed in.
+pub fn sort_by_name<T>(items: &mut [T], name: impl Fn(&T) -> &str, builtins: &[&str]) {
+    items.sort_by(|a, b| {
+        let (a, b) = (name(a), name(b));
+        (builtins.contains(&a), a).cmp(&(builtins.contains(&b), b))
+    });
+}
@@ -3,8 +3,9 @@ use graphgate_schema::ComposedSchema;
 use value::ConstValue;
 
 use super::{
+    directive::IntrospectionDirective,
     r#type::IntrospectionType,
-    resolver::{resolve_obj, Resolver},
+    resolver::{resolve_obj, sort_by_name, Resolver, BUILTIN_DIRECTIVES, BUILTIN_SCALARS},
 };
 
 pub struct IntrospectionSchema;
@@ -12,14 +13,20 @@ pub struct IntrospectionSchema;
 impl Resolver for IntrospectionSchema {
     fn resolve(&self, selection_set: &IntrospectionSelectionSet, schema: &ComposedSchema) -> ConstValue {
         resolve_obj(selection_set, |name, field| match name {
-            "types" => ConstValue::List(
-                schema
+            "types" => {
+                let mut types = schema
                     .types
                     .values()
                     .filter(|ty| !ty.name.starts_with("__"))
-                    .map(|ty| IntrospectionType::Named(ty).resolve(&field.selection_set, schema))
-                    .collect(),
-            ),
+                    .collect::<Vec<_>>();
+                sort_by_name(&mut types, |ty| &ty.name, BUILTIN_SCALARS);
+                ConstValue::List(
+                    types
+                        .into_iter()
+                        .map(|ty| IntrospectionType::Named(ty).resolve(&field.selection_set, schema))
+                        .collect(),
+                )
+            },
             "queryType" => {
                 let query_type = schema
                     .types
@@ -44,6 +51,16 @@ impl Resolver for IntrospectionSchema {
                     None => ConstValue::Null,
                 }
             },
+            "directives" => {
+                let mut directives = schema.directives.values().collect::<Vec<_>>();
+                sort_by_name(&mut directives, |directive| &directive.name, BUILTIN_DIRECTIVES);
+                ConstValue::List(
+                    directives
+                        .into_iter()
+                        .map(|directive| IntrospectionDirective(directive).resolve(&field.selection_set, schema))
+                        .collect(),
+                )
+            },
             _ => ConstValue::Null,
         })
     }
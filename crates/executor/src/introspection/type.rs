@@ -8,7 +8,7 @@ use super::{
     enum_value::IntrospectionEnumValue,
     field::IntrospectionField,
     input_value::IntrospectionInputValue,
-    resolver::{is_include_deprecated, resolve_obj, Resolver},
+    resolver::{is_include_deprecated, resolve_obj, sort_by_name, Resolver},
 };
 
 static SCALAR: Lazy<Name> = Lazy::new(|| Name::new("SCALAR"));
@@ -76,59 +76,55 @@ impl Resolver for IntrospectionType<'_> {
                 _ => ConstValue::Null,
             },
             "fields" => match self {
-                Self::Named(ty) if ty.kind == TypeKind::Object || ty.kind == TypeKind::Interface => ConstValue::List(
-                    ty.fields
+                Self::Named(ty) if ty.kind == TypeKind::Object || ty.kind == TypeKind::Interface => {
+                    let mut fields = ty
+                        .fields
                         .values()
                         .filter(|item| !item.name.starts_with("__"))
-                        .filter(|item| {
-                            if is_include_deprecated(&field.arguments) {
-                                true
-                            } else {
-                                !item.deprecation.is_deprecated()
-                            }
-                        })
-                        .map(|f| IntrospectionField(f).resolve(&field.selection_set, schema))
-                        .collect(),
-                ),
+                        .filter(|item| is_include_deprecated(&field.arguments) || !item.deprecation.is_deprecated())
+                        .collect::<Vec<_>>();
+                    sort_by_name(&mut fields, |f| &f.name, &[]);
+                    ConstValue::List(
+                        fields
+                            .into_iter()
+                            .map(|f| IntrospectionField(f).resolve(&field.selection_set, schema))
+                            .collect(),
+                    )
+                },
                 _ => ConstValue::Null,
             },
             "interfaces" => match self {
-                Self::Named(ty) if ty.kind == TypeKind::Object => ConstValue::List(
-                    ty.implements
-                        .iter()
-                        .map(|name| {
-                            IntrospectionType::Named(
-                                schema
-                                    .types
-                                    .get(name)
-                                    .expect("The query validator should find this error."),
-                            )
-                            .resolve(&field.selection_set, schema)
-                        })
-                        .collect(),
-                ),
+                Self::Named(ty) if ty.kind == TypeKind::Object => {
+                    named_types(&ty.implements, &field.selection_set, schema)
+                },
                 _ => ConstValue::Null,
             },
             "possibleTypes" => match self {
-                Self::Named(ty) if ty.kind == TypeKind::Interface || ty.kind == TypeKind::Union => ConstValue::List(
-                    ty.possible_types
-                        .iter()
-                        .map(|name| {
-                            IntrospectionType::Named(
-                                schema
-                                    .types
-                                    .get(name)
-                                    .expect("The query validator should find this error."),
-                            )
-                            .resolve(&field.selection_set, schema)
-                        })
-                        .collect(),
-                ),
+                Self::Named(ty) if ty.kind == TypeKind::Interface || ty.kind == TypeKind::Union => {
+                    named_types(&ty.possible_types, &field.selection_set, schema)
+                },
                 _ => ConstValue::Null,
             },
             "enumValues" => match self {
-                Self::Named(ty) if ty.kind == TypeKind::Enum => ConstValue::List(
-                    ty.enum_values
+                Self::Named(ty) if ty.kind == TypeKind::Enum => {
+                    let mut values = ty
+                        .enum_values
+                        .values()
+                        .filter(|item| is_include_deprecated(&field.arguments) || !item.deprecation.is_deprecated())
+                        .collect::<Vec<_>>();
+                    sort_by_name(&mut values, |value| &value.value, &[]);
+                    ConstValue::List(
+                        values
+                            .into_iter()
+                            .map(|value| IntrospectionEnumValue(value).resolve(&field.selection_set, schema))
+                            .collect(),
+                    )
+                },
+                _ => ConstValue::Null,
+            },
+            "inputFields" => match self {
+                Self::Named(ty) if ty.kind == TypeKind::InputObject => ConstValue::List(
+                    ty.input_fields
                         .values()
                         .filter(|item| {
                             if is_include_deprecated(&field.arguments) {
@@ -137,18 +133,13 @@ impl Resolver for IntrospectionType<'_> {
                                 !item.deprecation.is_deprecated()
                             }
                         })
-                        .map(|value| IntrospectionEnumValue(value).resolve(&field.selection_set, schema))
+                        .map(|value| IntrospectionInputValue(value).resolve(&field.selection_set, schema))
                         .collect(),
                 ),
                 _ => ConstValue::Null,
             },
-            "inputFields" => match self {
-                Self::Named(ty) if ty.kind == TypeKind::InputObject => ConstValue::List(
-                    ty.input_fields
-                        .values()
-                        .map(|value| IntrospectionInputValue(value).resolve(&field.selection_set, schema))
-                        .collect(),
-                ),
+            "isOneOf" => match self {
+                Self::Named(ty) if ty.kind == TypeKind::InputObject => ConstValue::Boolean(ty.one_of),
                 _ => ConstValue::Null,
             },
             "ofType" => match self {
@@ -159,3 +150,28 @@ impl Resolver for IntrospectionType<'_> {
         })
     }
 }
+
+/// The introspection of the types named by an object or an abstract type,
+/// sorted by name.
+fn named_types<'a>(
+    names: impl IntoIterator<Item = &'a Name>,
+    selection_set: &IntrospectionSelectionSet,
+    schema: &ComposedSchema,
+) -> ConstValue {
+    let mut names = names.into_iter().collect::<Vec<_>>();
+    sort_by_name(&mut names, |name| name.as_str(), &[]);
+    ConstValue::List(
+        names
+            .into_iter()
+            .map(|name| {
+                IntrospectionType::Named(
+                    schema
+                        .types
+                        .get(name)
+                        .expect("The query validator should find this error."),
+                )
+                .resolve(selection_set, schema)
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,58 @@
+//! Execution of GraphGate query plans.
+//!
+//! The executor runs the plans built by `graphgate-planner` against the
+//! subgraphs through a [`Fetcher`], and merges their responses. It does not
+//! depend on an HTTP stack, so plans can be executed with any transport:
+//!
+//! ```no_run
+//! # async fn run(schema: &graphgate_schema::ComposedSchema, fetcher: &impl graphgate_executor::Fetcher) {
+//! let document = parser::parse_query("{ me { id } }").unwrap();
+//! let plan_builder = graphgate_planner::PlanBuilder::new(schema, document);
+//! let plan = plan_builder.plan().unwrap();
+//! let response = graphgate_executor::execute(schema, fetcher, &plan).await;
+//! # }
+//! ```
+//!
+//! Variables are bound when planning, with [`PlanBuilder::variables`].
+//!
+//! [`PlanBuilder::variables`]: graphgate_planner::PlanBuilder::variables
+
+#![forbid(unsafe_code)]
+
+mod connection;
+pub mod constants;
+pub mod error;
+mod executor;
+mod fetcher;
+mod introspection;
+mod metrics;
+mod parallelism;
+mod representations;
+
+use graphgate_planner::{Response, RootNode};
+use graphgate_schema::ComposedSchema;
+
+pub use error::{
+    CircuitOpenError,
+    EntityMovedError,
+    RateLimitedError,
+    ResponseTooLargeError,
+    SubgraphStatusError,
+    SubgraphTimeoutError,
+    BAD_GATEWAY,
+    ENTITY_KEY_MISMATCH,
+    ENTITY_MOVED_HEADER,
+    FORBIDDEN,
+    RESPONSE_TOO_LARGE,
+    SUBGRAPH_CIRCUIT_OPEN,
+    SUBGRAPH_RATE_LIMITED,
+    UNAUTHENTICATED,
+};
+pub use executor::Executor;
+pub use fetcher::{Fetcher, Subscriber};
+pub use parallelism::{LimitedFetcher, Parallelism};
+
+/// Execute a query or mutation plan.
+pub async fn execute(schema: &ComposedSchema, fetcher: &impl Fetcher, plan: &RootNode<'_>) -> Response {
+    Executor::new(schema).execute_query(fetcher, plan).await
+}
@@ -0,0 +1,32 @@
+use once_cell::sync::Lazy;
+use opentelemetry::{
+    global,
+    metrics::{Counter, Histogram},
+};
+
+pub struct Metrics {
+    pub entity_key_retries: Counter<u64>,
+    pub entity_redirects: Counter<u64>,
+    pub scheduler_wait: Histogram<f64>,
+}
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(|| {
+    let meter = global::meter("graphgate");
+    let entity_key_retries = meter
+        .u64_counter("graphgate.entity_key_retries_total")
+        .with_description("Total number of entity fetches retried with an alternate key")
+        .init();
+    let entity_redirects = meter
+        .u64_counter("graphgate.entity_redirects_total")
+        .with_description("Total number of entity fetches redirected to the service the entities moved to")
+        .init();
+    let scheduler_wait = meter
+        .f64_histogram("graphgate.scheduler_wait_duration_seconds")
+        .with_description("The time subgraph fetches waited for the parallelism limits in seconds.")
+        .init();
+    Metrics {
+        entity_key_retries,
+        entity_redirects,
+        scheduler_wait,
+    }
+});
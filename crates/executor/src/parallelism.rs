@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use graphgate_planner::{Request, Response};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+
+use crate::{constants::KEY_SERVICE, fetcher::Fetcher, metrics::METRICS};
+
+/// Caps on the subgraph fetches running at once, over all requests and
+/// per request.
+///
+/// Waiting fetches are started in the order they were issued, so wide plans
+/// queue behind the fetches of other requests instead of overtaking them.
+#[derive(Clone, Default)]
+pub struct Parallelism {
+    global: Option<Arc<Semaphore>>,
+    max_request_fetches: Option<usize>,
+}
+
+impl Parallelism {
+    /// Create the caps, `None` leaving the fetches unlimited.
+    pub fn new(max_fetches: Option<usize>, max_request_fetches: Option<usize>) -> Self {
+        Self {
+            global: max_fetches.map(|max_fetches| Arc::new(Semaphore::new(max_fetches))),
+            max_request_fetches,
+        }
+    }
+
+    /// Apply the caps to the fetcher of a request.
+    pub fn limit<F: Fetcher>(&self, fetcher: F) -> LimitedFetcher<F> {
+        LimitedFetcher {
+            inner: fetcher,
+            global: self.global.clone(),
+            request: self.max_request_fetches.map(Semaphore::new),
+        }
+    }
+}
+
+/// A fetcher waiting for the permits of [`Parallelism`] before each fetch.
+pub struct LimitedFetcher<F> {
+    inner: F,
+    global: Option<Arc<Semaphore>>,
+    request: Option<Semaphore>,
+}
+
+async fn acquire(semaphore: Option<&Semaphore>) -> Option<SemaphorePermit<'_>> {
+    // The semaphores are never closed.
+    match semaphore {
+        Some(semaphore) => semaphore.acquire().await.ok(),
+        None => None,
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Fetcher> Fetcher for LimitedFetcher<F> {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let start = Instant::now();
+        // The request permit is taken first, so that a request does not hold
+        // global permits while waiting for its own.
+        let _request_permit = acquire(self.request.as_ref()).await;
+        let _global_permit = acquire(self.global.as_deref()).await;
+        if self.request.is_some() || self.global.is_some() {
+            METRICS.scheduler_wait.record(
+                start.elapsed().as_secs_f64(),
+                &[KEY_SERVICE.string(service.to_string())],
+            );
+        }
+        self.inner.query(service, request).await
+    }
+
+    fn url(&self, service: &str) -> Option<String> {
+        self.inner.url(service)
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use graphgate_planner::ServerError;
+use indexmap::IndexMap;
+use value::ConstValue;
+
+/// The entities resolved by the flatten rounds of a request.
+///
+/// Entities are keyed by their representation, made of their typename and
+/// key, for each service and query. Representations repeated within a round,
+/// or by a later round selecting the same fields, are only fetched once.
+///
+/// The entities resolved with errors are not reused by later rounds, which
+/// fetch them again to report their errors.
+#[derive(Default)]
+pub(crate) struct RepresentationCache {
+    entities: Mutex<HashMap<(String, String), HashMap<String, ConstValue>>>,
+}
+
+/// The representations of a flatten round, and which of them are not
+/// resolved yet.
+pub(crate) struct Lookup {
+    service: String,
+    query: String,
+    keys: Vec<String>,
+    /// The indices of the occurrences of each unresolved representation.
+    missing: IndexMap<String, Vec<usize>>,
+}
+
+impl Lookup {
+    /// Whether every representation is resolved.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// The indices of the occurrences of the unresolved representations, in
+    /// the order they are picked.
+    pub(crate) fn missing_indices(&self) -> impl Iterator<Item = &[usize]> + '_ {
+        self.missing.values().map(Vec::as_slice)
+    }
+
+    /// Pick the unresolved representations, once each, from values lined up
+    /// with the representations of the round.
+    pub(crate) fn missing(&self, values: &[ConstValue]) -> Vec<ConstValue> {
+        self.missing
+            .values()
+            .filter_map(|indices| values.get(indices[0]).cloned())
+            .collect()
+    }
+}
+
+impl RepresentationCache {
+    pub(crate) fn lookup(&self, service: &str, query: String, representations: &[ConstValue]) -> Lookup {
+        let keys = representations.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let entities = self.entities.lock().unwrap();
+        let resolved = entities.get(&(service.to_string(), query.clone()));
+        let mut missing = IndexMap::<_, Vec<_>>::new();
+        for (index, key) in keys.iter().enumerate() {
+            if !resolved.is_some_and(|resolved| resolved.contains_key(key)) {
+                missing.entry(key.clone()).or_default().push(index);
+            }
+        }
+        Lookup {
+            service: service.to_string(),
+            query,
+            keys,
+            missing,
+        }
+    }
+
+    /// Store the entities fetched for the unresolved representations,
+    /// returning the entities of every representation of the round.
+    ///
+    /// The entities the `errors` of the fetch are about are not stored, nor
+    /// are its null entities if any error is about no entity.
+    pub(crate) fn resolve(&self, lookup: Lookup, fetched: Vec<ConstValue>, errors: &[ServerError]) -> Vec<ConstValue> {
+        let mut failed = HashSet::new();
+        let mut unplaced_errors = false;
+        for err in errors {
+            match entity_index(err) {
+                Some(index) => {
+                    failed.insert(index);
+                },
+                None => unplaced_errors = true,
+            }
+        }
+
+        let mut entities = self.entities.lock().unwrap();
+        let resolved = entities.entry((lookup.service, lookup.query)).or_default();
+        let mut round = HashMap::new();
+        for (index, (key, entity)) in lookup.missing.into_keys().zip(fetched).enumerate() {
+            if failed.contains(&index) || (unplaced_errors && entity == ConstValue::Null) {
+                round.insert(key, entity);
+            } else {
+                resolved.insert(key, entity);
+            }
+        }
+        lookup
+            .keys
+            .iter()
+            .map(|key| {
+                round
+                    .get(key)
+                    .or_else(|| resolved.get(key))
+                    .cloned()
+                    .unwrap_or(ConstValue::Null)
+            })
+            .collect()
+    }
+}
+
+/// The index of the entity an error of an `_entities` fetch is about.
+pub(crate) fn entity_index(err: &ServerError) -> Option<usize> {
+    match err.path.as_slice() {
+        [ConstValue::String(field), ConstValue::Number(index), ..] if field == "_entities" => {
+            index.as_u64().map(|index| index as usize)
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "accounts".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { me: User! }
+                type User @key(fields: "id") { id: ID! username: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review { body: String! }
+                extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Answers with the fields the services would select for the queries of
+/// the plan.
+struct AliasesFetcher;
+
+#[async_trait::async_trait]
+impl Fetcher for AliasesFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let data = match service {
+            "accounts" => {
+                assert_eq!(
+                    request.query,
+                    "query\n{ __key1_id:me { __key1___typename:username __key_:id __key__1___typename:__typename \
+                     __key__1_id:id } }"
+                );
+                json!({
+                    "__key1_id": {
+                        "__key1___typename": "alice",
+                        "__key_": "1",
+                        "__key__1___typename": "User",
+                        "__key__1_id": "1",
+                    }
+                })
+            },
+            _ => {
+                assert_eq!(
+                    request.variables.get("representations").cloned().unwrap(),
+                    ConstValue::from_json(json!([{ "__typename": "User", "id": "1" }])).unwrap()
+                );
+                json!({ "_entities": [{ "reviews": [{ "__key2_body": "great" }] }] })
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn aliases_colliding_with_entity_keys() {
+    let schema = schema();
+    let document = parser::parse_query(
+        "{ __key1_id: me { __key1___typename: username __key_: id reviews { __key2_body: body } } }",
+    )
+    .unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = execute(&schema, &AliasesFetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({
+            "__key1_id": {
+                "__key1___typename": "alice",
+                "__key_": "1",
+                "reviews": [{ "__key2_body": "great" }],
+            }
+        })
+    );
+}
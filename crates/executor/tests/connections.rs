@@ -0,0 +1,283 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use graphgate_executor::{Executor, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { reviews(first: Int): ReviewConnection! }
+                type Mutation { publish: ReviewConnection! }
+                type ReviewConnection { edges: [ReviewEdge!]! }
+                type ReviewEdge { cursor: String! node: Review! }
+                extend type Review @key(fields: "id") { id: ID! @external }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "content".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review @key(fields: "id") { id: ID! body: String! }
+                type Mutation { touch: Boolean! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Answers with a page of five reviews, failing the batch of reviews `3`
+/// and `4`.
+#[derive(Default)]
+struct ReviewsFetcher {
+    batches: Mutex<Vec<usize>>,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for ReviewsFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let data = match service {
+            "reviews" => {
+                // Every root field of a mutation selects its keys under its own
+                // prefix.
+                let (response_key, prefix) = match ["a", "b"]
+                    .into_iter()
+                    .position(|alias| request.query.contains(&format!("{}:publish", alias)))
+                {
+                    Some(idx) => (["a", "b"][idx], idx + 1),
+                    None => ("reviews", 1),
+                };
+                let edges = (1..=5)
+                    .map(|id| {
+                        json!({
+                            "cursor": format!("cursor{}", id),
+                            "node": {
+                                format!("__key{}___typename", prefix): "Review",
+                                format!("__key{}_id", prefix): id.to_string(),
+                            },
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({ response_key: { "edges": edges } })
+            },
+            _ if request.query.contains("touch") => json!({ "touch": true }),
+            _ => {
+                let representations = match request.variables.get("representations") {
+                    Some(ConstValue::List(representations)) => representations.clone(),
+                    _ => Vec::new(),
+                };
+                self.batches.lock().unwrap().push(representations.len());
+                let ids = representations
+                    .iter()
+                    .map(|representation| representation.clone().into_json().unwrap()["id"].clone())
+                    .collect::<Vec<_>>();
+                if ids.contains(&json!("3")) {
+                    anyhow::bail!("content unavailable");
+                }
+                let entities = ids
+                    .iter()
+                    .map(|id| json!({ "body": format!("review {}", id.as_str().unwrap()) }))
+                    .collect::<Vec<_>>();
+                json!({ "_entities": entities })
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn connection_nodes_are_fetched_in_batches() {
+    let schema = schema();
+    let document = parser::parse_query("{ reviews(first: 5) { edges { cursor node { body } } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = ReviewsFetcher::default();
+
+    let resp = Executor::new(&schema)
+        .connection_batch_size(2)
+        .execute_query(&fetcher, &plan)
+        .await;
+    let mut batches = fetcher.batches.lock().unwrap().clone();
+    batches.sort_unstable();
+    assert_eq!(batches, vec![1, 2, 2]);
+    assert_eq!(resp.errors.len(), 1);
+    assert_eq!(resp.errors[0].message, "content unavailable");
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({
+            "reviews": {
+                "edges": [
+                    { "cursor": "cursor1", "node": { "body": "review 1" } },
+                    { "cursor": "cursor2", "node": { "body": "review 2" } },
+                    { "cursor": "cursor3", "node": {} },
+                    { "cursor": "cursor4", "node": {} },
+                    { "cursor": "cursor5", "node": { "body": "review 5" } },
+                ]
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn small_connection_pages_are_fetched_at_once() {
+    let schema = schema();
+    let document = parser::parse_query("{ reviews(first: 5) { edges { cursor node { body } } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = ReviewsFetcher::default();
+
+    let resp = Executor::new(&schema)
+        .connection_batch_size(10)
+        .execute_query(&fetcher, &plan)
+        .await;
+    assert_eq!(*fetcher.batches.lock().unwrap(), vec![5]);
+    assert_eq!(resp.errors.len(), 1);
+}
+
+#[tokio::test]
+async fn failed_batches_are_fetched_again() {
+    let schema = schema();
+    let document = parser::parse_query(
+        "mutation { a: publish { edges { node { body } } } touch b: publish { edges { node { body } } } }",
+    )
+    .unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = ReviewsFetcher::default();
+
+    let resp = Executor::new(&schema)
+        .connection_batch_size(2)
+        .execute_query(&fetcher, &plan)
+        .await;
+    // The second page reuses the reviews resolved by the first one, and
+    // fetches the reviews of the failed batch again.
+    let mut batches = fetcher.batches.lock().unwrap().clone();
+    batches.sort_unstable();
+    assert_eq!(batches, vec![1, 2, 2, 2]);
+    assert_eq!(resp.errors.len(), 2);
+}
+
+/// Answers with a page of four reviews, resolving them by their slug only.
+#[derive(Default)]
+struct SlugFetcher {
+    batches: Mutex<Vec<ConstValue>>,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for SlugFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let data = match service {
+            "reviews" => {
+                let edges = (1..=4)
+                    .map(|id| {
+                        json!({
+                            "node": {
+                                "__key1___typename": "Review",
+                                "__key1_id": id.to_string(),
+                                "__key2___typename": "Review",
+                                "__key2_slug": format!("review-{}", id),
+                            },
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({ "reviews": { "edges": edges } })
+            },
+            _ => {
+                let representations = request.variables.get("representations").cloned().unwrap();
+                self.batches.lock().unwrap().push(representations.clone());
+                let entities = representations
+                    .into_json()
+                    .unwrap()
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|representation| match representation.get("slug") {
+                        Some(slug) => json!({ "body": format!("body of {}", slug.as_str().unwrap()) }),
+                        None => json!(null),
+                    })
+                    .collect::<Vec<_>>();
+                json!({ "_entities": entities })
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn batches_are_retried_with_alternate_keys() {
+    let schema = ComposedSchema::combine([
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { reviews(first: Int): ReviewConnection! }
+                type ReviewConnection { edges: [ReviewEdge!]! }
+                type ReviewEdge { node: Review! }
+                extend type Review @key(fields: "id") @key(fields: "slug") {
+                    id: ID! @external
+                    slug: String! @external
+                }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "content".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review @key(fields: "id") @key(fields: "slug") { id: ID! slug: String! body: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap();
+    let document = parser::parse_query("{ reviews(first: 4) { edges { node { body } } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = SlugFetcher::default();
+
+    let resp = Executor::new(&schema)
+        .connection_batch_size(2)
+        .execute_query(&fetcher, &plan)
+        .await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert!(resp
+        .warnings()
+        .any(|warning| warning.contains("fetched by an alternate key")));
+    // Both batches are fetched again by the slugs of their reviews.
+    assert_eq!(fetcher.batches.lock().unwrap().len(), 4);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({
+            "reviews": {
+                "edges": [
+                    { "node": { "body": "body of review-1" } },
+                    { "node": { "body": "body of review-2" } },
+                    { "node": { "body": "body of review-3" } },
+                    { "node": { "body": "body of review-4" } },
+                ]
+            }
+        })
+    );
+}
@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "media".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { media: [Media!]! }
+                union Media = Image | Audio | Text
+                type Image @key(fields: "id") { id: ID! width: Int! }
+                type Audio @key(fields: "id") { id: ID! duration: Int! }
+                type Text { content: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "files".to_string(),
+            parser::parse_schema(
+                r#"
+                extend type Image @key(fields: "id") { id: ID! @external url: String! }
+                extend type Audio @key(fields: "id") { id: ID! @external url: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Records the representations sent to the files service.
+#[derive(Default)]
+struct MediaFetcher {
+    representations: Mutex<Vec<ConstValue>>,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for MediaFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let data = match service {
+            "media" => json!({
+                "media": [
+                    { "__typename": "Image", "__key1___typename": "Image", "__key1_id": "1" },
+                    { "__typename": "Text", "content": "hello" },
+                    { "__typename": "Audio", "__key1___typename": "Audio", "__key1_id": "2" },
+                ]
+            }),
+            _ => {
+                self.representations
+                    .lock()
+                    .unwrap()
+                    .push(request.variables.get("representations").cloned().unwrap());
+                json!({ "_entities": [{ "url": "image.png" }, { "url": "audio.mp3" }] })
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn fetch_entities_of_several_types_at_once() {
+    let schema = schema();
+    let document = parser::parse_query(
+        "{ media { __typename ... on Image { url } ... on Audio { url } ... on Text { content } } }",
+    )
+    .unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let fetcher = MediaFetcher::default();
+    let resp = execute(&schema, &fetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(*fetcher.representations.lock().unwrap(), vec![ConstValue::from_json(
+        json!([
+            { "__typename": "Image", "id": "1" },
+            { "__typename": "Audio", "id": "2" },
+        ])
+    )
+    .unwrap()]);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({
+            "media": [
+                { "__typename": "Image", "url": "image.png" },
+                { "__typename": "Text", "content": "hello" },
+                { "__typename": "Audio", "url": "audio.mp3" },
+            ]
+        })
+    );
+}
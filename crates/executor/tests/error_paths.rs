@@ -0,0 +1,102 @@
+use anyhow::Result;
+use graphgate_executor::{Executor, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response, ServerError};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "accounts".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { users: [User!]! }
+                type User @key(fields: "id") { id: ID! username: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review { body: String }
+                extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+fn error(message: &str, path: serde_json::Value) -> ServerError {
+    let mut error = ServerError::new(message);
+    error.path = serde_json::from_value(path).unwrap();
+    error
+}
+
+/// Answers with the same user twice, and fails to resolve the bodies of the
+/// reviews of both distinct users.
+struct UsersFetcher;
+
+#[async_trait::async_trait]
+impl Fetcher for UsersFetcher {
+    async fn query(&self, service: &str, _request: Request) -> Result<Response> {
+        let (data, errors) = match service {
+            "accounts" => {
+                let user = json!({ "__key1___typename": "User", "__key1_id": "1", "username": "alice" });
+                let data = json!({ "users": [user.clone(), user, { "__key1___typename": "User", "__key1_id": "2", "username": "bob" }] });
+                (data, Vec::new())
+            },
+            _ => {
+                let data = json!({ "_entities": [
+                    { "reviews": [{ "body": null }] },
+                    { "reviews": [{ "body": null }] },
+                ] });
+                let errors = vec![
+                    error(
+                        "body of alice unavailable",
+                        json!(["_entities", 0, "reviews", 0, "body"]),
+                    ),
+                    error("body unavailable", json!(["_entities", 1, "reviews", 0, "body"])),
+                    error("reviews degraded", json!([])),
+                ];
+                (data, errors)
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors,
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn normalize_entity_error_paths() {
+    let schema = schema();
+    let document = parser::parse_query("{ users { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = Executor::new(&schema)
+        .normalize_error_paths(true)
+        .execute_query(&UsersFetcher, &plan)
+        .await;
+    let paths = resp
+        .errors
+        .iter()
+        .map(|error| (error.message.as_str(), serde_json::to_value(&error.path).unwrap()))
+        .collect::<Vec<_>>();
+    // The index of the entity is that of the representations sent, the
+    // users repeated are sent once and their errors reported at each of them.
+    assert_eq!(paths, [
+        ("body of alice unavailable", json!(["users", 0, "reviews", 0, "body"])),
+        ("body of alice unavailable", json!(["users", 1, "reviews", 0, "body"])),
+        ("body unavailable", json!(["users", 2, "reviews", 0, "body"])),
+        ("reviews degraded", json!([])),
+    ]);
+}
@@ -0,0 +1,186 @@
+use std::{sync::Mutex, time::Duration};
+
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher, RateLimitedError, SUBGRAPH_RATE_LIMITED};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        ("accounts".to_string(), parser::parse_schema(ACCOUNTS_SDL).unwrap()),
+        ("reviews".to_string(), parser::parse_schema(REVIEWS_SDL).unwrap()),
+    ])
+    .unwrap()
+}
+
+/// Answers every request to a service with a fixed response.
+#[derive(Default)]
+struct StaticFetcher {
+    requests: Mutex<Vec<(String, Request)>>,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for StaticFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        self.requests.lock().unwrap().push((service.to_string(), request));
+        let data = match service {
+            "accounts" => json!({ "me": { "__key1___typename": "User", "__key1_id": "1", "username": "alice" } }),
+            "reviews" => json!({ "_entities": [{ "reviews": [{ "body": "great" }] }] }),
+            _ => {
+                return Err(RateLimitedError {
+                    service: service.to_string(),
+                    retry_after: Duration::from_secs(1),
+                }
+                .into())
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn execute_plan() {
+    let schema = schema();
+    let document = parser::parse_query("{ me { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = StaticFetcher::default();
+
+    let resp = execute(&schema, &fetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({ "me": { "username": "alice", "reviews": [{ "body": "great" }] } })
+    );
+
+    let requests = fetcher.requests.lock().unwrap();
+    let services = requests.iter().map(|(service, _)| service.as_str()).collect::<Vec<_>>();
+    assert_eq!(services, vec!["accounts", "reviews"]);
+    assert_eq!(
+        requests[1].1.variables.get("representations").cloned(),
+        Some(ConstValue::from_json(json!([{ "__typename": "User", "id": "1" }])).unwrap())
+    );
+}
+
+#[tokio::test]
+async fn fetch_error_extensions() {
+    let schema = ComposedSchema::combine([(
+        "inventory".to_string(),
+        parser::parse_schema("type Query { stock: Int }").unwrap(),
+    )])
+    .unwrap();
+    let document = parser::parse_query("{ stock }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = execute(&schema, &StaticFetcher::default(), &plan).await;
+    assert_eq!(resp.errors.len(), 1);
+    assert_eq!(
+        resp.errors[0].extensions.get("code"),
+        Some(&ConstValue::String(SUBGRAPH_RATE_LIMITED.to_string()))
+    );
+}
+
+/// Resolves users by their email only.
+#[derive(Default)]
+struct EmailFetcher {
+    representations: Mutex<Vec<Option<ConstValue>>>,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for EmailFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let representations = request.variables.get("representations").cloned();
+        self.representations.lock().unwrap().push(representations.clone());
+        let data = match service {
+            "accounts" => json!({
+                "me": {
+                    "__key1___typename": "User",
+                    "__key1_id": "1",
+                    "__key2___typename": "User",
+                    "__key2_email": "alice@example.com",
+                    "username": "alice",
+                }
+            }),
+            _ => match representations.unwrap().into_json().unwrap()[0].get("email") {
+                Some(_) => json!({ "_entities": [{ "reviews": [{ "body": "great" }] }] }),
+                None => json!({ "_entities": [null] }),
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn retry_entities_with_alternate_key() {
+    let schema = ComposedSchema::combine([
+        (
+            "accounts".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { me: User }
+                type User @key(fields: "id") @key(fields: "email") { id: ID! email: String! username: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review { body: String! }
+                extend type User @key(fields: "id") @key(fields: "email") {
+                    id: ID! @external
+                    email: String! @external
+                    reviews: [Review!]!
+                }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap();
+    let document = parser::parse_query("{ me { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = EmailFetcher::default();
+
+    let resp = execute(&schema, &fetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    let warnings = resp.warnings().collect::<Vec<_>>();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("fetched by an alternate key"), "{}", warnings[0]);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({ "me": { "username": "alice", "reviews": [{ "body": "great" }] } })
+    );
+
+    let representations = fetcher.representations.lock().unwrap();
+    assert_eq!(representations.len(), 3);
+    assert_eq!(
+        representations[2],
+        Some(ConstValue::from_json(json!([{ "__typename": "User", "email": "alice@example.com" }])).unwrap())
+    );
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([(
+        "reviews".to_string(),
+        parser::parse_schema(
+            r#"
+            type Query { reviews(first: Int @deprecated(reason: "Use `limit`."), limit: Int, filter: ReviewFilter): [Review!]! }
+            type Review { body: String! }
+            input ReviewFilter { rating: Int @deprecated stars: Int }
+            "#,
+        )
+        .unwrap(),
+    )])
+    .unwrap()
+}
+
+/// Introspection is answered by the gateway alone.
+struct NoFetcher;
+
+#[async_trait::async_trait]
+impl Fetcher for NoFetcher {
+    async fn query(&self, service: &str, _request: Request) -> Result<Response> {
+        panic!("unexpected request to {}", service)
+    }
+}
+
+#[tokio::test]
+async fn introspect_deprecated_arguments_and_input_fields() {
+    let schema = schema();
+    let document = parser::parse_query(
+        r#"{
+            query: __type(name: "Query") {
+                fields {
+                    args { name }
+                    allArgs: args(includeDeprecated: true) { name isDeprecated deprecationReason }
+                }
+            }
+            filter: __type(name: "ReviewFilter") {
+                inputFields { name }
+                allInputFields: inputFields(includeDeprecated: true) { name isDeprecated }
+            }
+        }"#,
+    )
+    .unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = execute(&schema, &NoFetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({
+            "query": {
+                "fields": [{
+                    "args": [{ "name": "limit" }, { "name": "filter" }],
+                    "allArgs": [
+                        { "name": "first", "isDeprecated": true, "deprecationReason": "Use `limit`." },
+                        { "name": "limit", "isDeprecated": false, "deprecationReason": null },
+                        { "name": "filter", "isDeprecated": false, "deprecationReason": null },
+                    ],
+                }],
+            },
+            "filter": {
+                "inputFields": [{ "name": "stars" }],
+                "allInputFields": [
+                    { "name": "rating", "isDeprecated": true },
+                    { "name": "stars", "isDeprecated": false },
+                ],
+            },
+        })
+    );
+}
+
+#[tokio::test]
+async fn introspect_one_of_input_objects() {
+    let schema = ComposedSchema::combine([(
+        "reviews".to_string(),
+        parser::parse_schema(
+            r#"
+            type Query { review(by: ReviewBy!, filter: ReviewFilter): Review }
+            type Review { body: String! }
+            input ReviewBy @oneOf { id: ID url: String }
+            input ReviewFilter { stars: Int }
+            "#,
+        )
+        .unwrap(),
+    )])
+    .unwrap();
+    let document = parser::parse_query(
+        r#"{
+            by: __type(name: "ReviewBy") { isOneOf }
+            filter: __type(name: "ReviewFilter") { isOneOf }
+            review: __type(name: "Review") { isOneOf }
+            __schema { directives { name locations } }
+        }"#,
+    )
+    .unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = execute(&schema, &NoFetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    let data = resp.data.into_json().unwrap();
+    assert_eq!(data["by"], json!({ "isOneOf": true }));
+    assert_eq!(data["filter"], json!({ "isOneOf": false }));
+    assert_eq!(data["review"], json!({ "isOneOf": null }));
+    assert!(data["__schema"]["directives"]
+        .as_array()
+        .unwrap()
+        .contains(&json!({ "name": "oneOf", "locations": ["INPUT_OBJECT"] })));
+}
+
+#[tokio::test]
+async fn introspect_in_deterministic_order() {
+    let accounts = r#"
+        type Query { me: User }
+        type User @key(fields: "id") { id: ID! username: String! role: Role! }
+        enum Role { USER ADMIN }
+        interface Node { id: ID! }
+    "#;
+    let reviews = r#"
+        type Review implements Node { id: ID! body: String! }
+        extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+        union Content = User | Review
+        extend type Query { content: [Content!]! }
+    "#;
+    let data = introspect_services([("accounts", accounts), ("reviews", reviews)]).await;
+    assert_eq!(
+        data,
+        introspect_services([("reviews", reviews), ("accounts", accounts)]).await
+    );
+
+    let names = |items: &serde_json::Value| {
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let types = &data["__schema"]["types"];
+    assert_eq!(names(types), [
+        "Content", "Node", "Query", "Review", "Role", "User", "Boolean", "Float", "ID", "Int", "String"
+    ]);
+    assert_eq!(names(&types[2]["fields"]), ["content", "me"]);
+    assert_eq!(names(&types[4]["enumValues"]), ["ADMIN", "USER"]);
+    assert_eq!(names(&types[0]["possibleTypes"]), ["Review", "User"]);
+    assert_eq!(names(&data["__schema"]["directives"]), [
+        "defer", "include", "oneOf", "skip"
+    ]);
+}
+
+async fn introspect_services(services: [(&str, &str); 2]) -> serde_json::Value {
+    let schema = ComposedSchema::combine(
+        services.map(|(service, sdl)| (service.to_string(), parser::parse_schema(sdl).unwrap())),
+    )
+    .unwrap();
+    let document = parser::parse_query(
+        r#"{
+            __schema {
+                types { name fields { name } enumValues { name } possibleTypes { name } }
+                directives { name }
+            }
+        }"#,
+    )
+    .unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let resp = execute(&schema, &NoFetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    resp.data.into_json().unwrap()
+}
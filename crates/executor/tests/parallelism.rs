@@ -0,0 +1,63 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher, Parallelism};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use value::ConstValue;
+
+/// Records the most fetches running at once.
+#[derive(Default)]
+struct SlowFetcher {
+    running: AtomicUsize,
+    max_running: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for SlowFetcher {
+    async fn query(&self, service: &str, _request: Request) -> Result<Response> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        let data = format!(r#"{{ "{}": 1 }}"#, service);
+        Ok(Response {
+            data: ConstValue::from_json(serde_json::from_str(&data).unwrap()).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn limit_request_fetches() {
+    let services = ["a", "b", "c", "d"];
+    let schema = ComposedSchema::combine(services.iter().map(|service| {
+        let sdl = format!("type Query {{ {}: Int }}", service);
+        (service.to_string(), parser::parse_schema(sdl).unwrap())
+    }))
+    .unwrap();
+    let document = parser::parse_query("{ a b c d }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let fetcher = SlowFetcher::default();
+    let resp = execute(&schema, &fetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(fetcher.max_running.load(Ordering::SeqCst), 4);
+
+    let parallelism = Parallelism::new(Some(3), Some(2));
+    let fetcher = SlowFetcher::default();
+    let resp = execute(&schema, &parallelism.limit(&fetcher), &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        serde_json::json!({ "a": 1, "b": 1, "c": 1, "d": 1 })
+    );
+    assert_eq!(fetcher.max_running.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,102 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "accounts".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { users: [User!]! }
+                type User @key(fields: "id") { id: ID! username: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review { body: String! author: User! }
+                extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Answers with the same user twice, and the reviews of each user.
+#[derive(Default)]
+struct UsersFetcher {
+    representations: Mutex<Vec<ConstValue>>,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for UsersFetcher {
+    async fn query(&self, service: &str, request: Request) -> Result<Response> {
+        let data = match service {
+            "accounts" => {
+                let user = json!({ "__key1___typename": "User", "__key1_id": "1", "username": "alice" });
+                json!({ "users": [user.clone(), user, { "__key1___typename": "User", "__key1_id": "2", "username": "bob" }] })
+            },
+            _ => {
+                let representations = request.variables.get("representations").cloned().unwrap();
+                self.representations.lock().unwrap().push(representations.clone());
+                let entities = match representations {
+                    ConstValue::List(representations) => representations
+                        .iter()
+                        .map(|representation| {
+                            let id = representation.clone().into_json().unwrap()["id"].clone();
+                            json!({ "reviews": [{ "body": format!("review of {}", id.as_str().unwrap()) }] })
+                        })
+                        .collect::<Vec<_>>(),
+                    _ => Vec::new(),
+                };
+                json!({ "_entities": entities })
+            },
+        };
+        Ok(Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn repeated_representations_are_fetched_once() {
+    let schema = schema();
+    let document = parser::parse_query("{ users { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+    let fetcher = UsersFetcher::default();
+
+    let resp = execute(&schema, &fetcher, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({
+            "users": [
+                { "username": "alice", "reviews": [{ "body": "review of 1" }] },
+                { "username": "alice", "reviews": [{ "body": "review of 1" }] },
+                { "username": "bob", "reviews": [{ "body": "review of 2" }] },
+            ]
+        })
+    );
+    assert_eq!(*fetcher.representations.lock().unwrap(), vec![ConstValue::from_json(
+        json!([
+            { "__typename": "User", "id": "1" },
+            { "__typename": "User", "id": "2" },
+        ])
+    )
+    .unwrap()]);
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use graphgate_executor::{execute, Fetcher};
+use graphgate_planner::{PlanBuilder, Request, Response, ServerError};
+use graphgate_schema::ComposedSchema;
+use serde_json::json;
+use value::ConstValue;
+
+fn schema() -> ComposedSchema {
+    ComposedSchema::combine([
+        (
+            "accounts".to_string(),
+            parser::parse_schema(
+                r#"
+                type Query { me: User }
+                type User @key(fields: "id") { id: ID! username: String! }
+                "#,
+            )
+            .unwrap(),
+        ),
+        (
+            "reviews".to_string(),
+            parser::parse_schema(
+                r#"
+                type Review { body: String! }
+                extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+                "#,
+            )
+            .unwrap(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Answers with warnings in the extensions of every service, the reviews
+/// service failing after warning.
+struct WarningFetcher {
+    fail_reviews: bool,
+}
+
+#[async_trait::async_trait]
+impl Fetcher for WarningFetcher {
+    async fn query(&self, service: &str, _request: Request) -> Result<Response> {
+        let (data, warnings) = match service {
+            "accounts" => (
+                json!({ "me": { "__key1___typename": "User", "__key1_id": "1", "username": "alice" } }),
+                json!(["accounts served a stale cache", "User.username is deprecated"]),
+            ),
+            _ => (
+                json!({ "_entities": [{ "reviews": [{ "body": "great" }] }] }),
+                json!(["User.username is deprecated", "reviews is slow"]),
+            ),
+        };
+        let mut resp = Response {
+            data: ConstValue::from_json(data).unwrap(),
+            errors: Vec::new(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        };
+        resp.extensions
+            .insert("warnings".to_string(), ConstValue::from_json(warnings).unwrap());
+        if service == "reviews" && self.fail_reviews {
+            resp.data = ConstValue::Null;
+            resp.errors.push(ServerError::new("reviews unavailable"));
+        }
+        Ok(resp)
+    }
+}
+
+#[tokio::test]
+async fn aggregate_service_warnings() {
+    let schema = schema();
+    let document = parser::parse_query("{ me { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = execute(&schema, &WarningFetcher { fail_reviews: false }, &plan).await;
+    assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+    assert_eq!(resp.warnings().collect::<Vec<_>>(), [
+        "accounts served a stale cache",
+        "User.username is deprecated",
+        "reviews is slow"
+    ]);
+    assert_eq!(
+        resp.data.into_json().unwrap(),
+        json!({ "me": { "username": "alice", "reviews": [{ "body": "great" }] } })
+    );
+}
+
+#[tokio::test]
+async fn keep_warnings_of_failed_fetches() {
+    let schema = schema();
+    let document = parser::parse_query("{ me { username reviews { body } } }").unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    let resp = execute(&schema, &WarningFetcher { fail_reviews: true }, &plan).await;
+    assert_eq!(resp.errors.len(), 1);
+    assert_eq!(resp.warnings().collect::<Vec<_>>(), [
+        "accounts served a stale cache",
+        "User.username is deprecated",
+        "reviews is slow"
+    ]);
+}
+
+#[test]
+fn merge_extensions_appends_warnings() {
+    let mut resp = Response::default();
+    resp.add_warning("limit injected");
+    resp.extensions.insert(
+        "cost".to_string(),
+        ConstValue::from_json(json!({ "estimated": 1 })).unwrap(),
+    );
+
+    let mut extensions = std::collections::HashMap::new();
+    extensions.insert(
+        "warnings".to_string(),
+        ConstValue::from_json(json!(["limit injected", "field deprecated"])).unwrap(),
+    );
+    extensions.insert(
+        "cost".to_string(),
+        ConstValue::from_json(json!({ "estimated": 2 })).unwrap(),
+    );
+    resp.merge_extensions(extensions);
+
+    assert_eq!(resp.warnings().collect::<Vec<_>>(), [
+        "limit injected",
+        "field deprecated"
+    ]);
+    assert_eq!(
+        resp.extensions["cost"].clone().into_json().unwrap(),
+        json!({ "estimated": 2 })
+    );
+}
@@ -0,0 +1,328 @@
+use std::{
+    collections::BTreeSet,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use chrono::{SecondsFormat, Utc};
+use clap::Args;
+use graphgate_planner::{PlanNode, Response, RootNode, ServerError};
+use parser::types::{DocumentOperations, ExecutableDocument, OperationType};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use value::{ConstValue, Variables};
+
+use crate::context_injection::RequestContext;
+
+/// The error code of mutations that were not executed because their audit
+/// record could not be written.
+pub const AUDIT_LOG_UNAVAILABLE: &str = "AUDIT_LOG_UNAVAILABLE";
+
+/// The value replacing the redacted variables in audit records.
+const REDACTED: &str = "[REDACTED]";
+
+/// The number of records waiting to be appended to the audit file, beyond
+/// which the mutations wait for the file to catch up.
+const FILE_QUEUE_SIZE: usize = 1024;
+
+/// How long connecting to the audit URL may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long sending a record to the audit URL may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A line to append to the audit file, acknowledged once it is synced.
+type FileRecord = (String, oneshot::Sender<std::io::Result<()>>);
+
+/// Records every executed mutation, before executing it and with its
+/// result, as JSON lines appended to a file or `POST`ed to a URL.
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct AuditConfig {
+    /// Append the audit records to this file.
+    #[clap(id = "audit_file", long = "audit-file", env = "AUDIT_FILE")]
+    pub file: Option<PathBuf>,
+
+    /// Send each audit record in a `POST` request to this URL.
+    #[clap(id = "audit_url", long = "audit-url", env = "AUDIT_URL")]
+    pub url: Option<String>,
+
+    /// The claim of the JWT identifying the actor, nested claims are
+    /// separated by `.`.
+    #[clap(long = "audit-actor-claim", env = "AUDIT_ACTOR_CLAIM", default_value = "sub")]
+    #[serde(default = "default_actor_claim")]
+    pub actor_claim: String,
+
+    /// Variables, or fields of input objects, whose values are replaced with
+    /// `[REDACTED]` in the audit records.
+    #[clap(
+        long = "audit-redact-variables",
+        env = "AUDIT_REDACT_VARIABLES",
+        value_delimiter = ','
+    )]
+    #[serde(default)]
+    pub redact_variables: Vec<String>,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            url: None,
+            actor_claim: default_actor_claim(),
+            redact_variables: Vec::new(),
+        }
+    }
+}
+
+/// The audit record of a mutation being executed, completed with its
+/// result.
+pub(crate) struct AuditEntry {
+    id: String,
+    operation_name: Option<String>,
+    actor: Option<String>,
+    services: Vec<String>,
+    start_time: Instant,
+}
+
+pub(crate) struct AuditLog {
+    config: AuditConfig,
+    /// The queue of the thread appending to the audit file, so that the
+    /// writes and syncs never block the executor.
+    file: Option<mpsc::Sender<FileRecord>>,
+    client: reqwest::Client,
+    sequence: AtomicU64,
+}
+
+impl AuditLog {
+    pub(crate) fn open(config: AuditConfig) -> std::io::Result<Self> {
+        let file = match &config.file {
+            Some(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                let (tx, mut rx) = mpsc::channel::<FileRecord>(FILE_QUEUE_SIZE);
+                // Stops once the audit log is dropped.
+                std::thread::Builder::new()
+                    .name("audit-log".to_string())
+                    .spawn(move || {
+                        while let Some((line, ack)) = rx.blocking_recv() {
+                            let res = writeln!(file, "{}", line).and_then(|_| file.sync_data());
+                            ack.send(res).ok();
+                        }
+                    })?;
+                Some(tx)
+            },
+            None => None,
+        };
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(std::io::Error::other)?;
+        Ok(Self {
+            config,
+            file,
+            client,
+            sequence: AtomicU64::new(0),
+        })
+    }
+
+    /// Write the record of a mutation before executing it, which must not be
+    /// executed if this fails.
+    pub(crate) async fn begin(
+        &self,
+        operation_name: Option<&str>,
+        context: &RequestContext,
+        variables: &Variables,
+        plan: &RootNode<'_>,
+    ) -> anyhow::Result<AuditEntry> {
+        let timestamp = Utc::now();
+        let entry = AuditEntry {
+            id: format!(
+                "{}-{}",
+                timestamp.timestamp_nanos_opt().unwrap_or_default(),
+                self.sequence.fetch_add(1, Ordering::Relaxed)
+            ),
+            operation_name: operation_name.map(ToString::to_string),
+            actor: self.actor(context),
+            services: plan_services(plan).into_iter().map(ToString::to_string).collect(),
+            start_time: Instant::now(),
+        };
+        let variables = variables
+            .iter()
+            .map(|(name, value)| {
+                let value = match self.is_redacted(name) {
+                    true => Value::String(REDACTED.to_string()),
+                    false => self.redact(value.clone().into_json().unwrap_or_default()),
+                };
+                (name.to_string(), value)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        self.write(json!({
+            "id": entry.id,
+            "timestamp": timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "phase": "started",
+            "operationName": entry.operation_name,
+            "actor": entry.actor,
+            "services": entry.services,
+            "variables": variables,
+        }))
+        .await?;
+        Ok(entry)
+    }
+
+    /// Write the result of an executed mutation.
+    pub(crate) async fn complete(&self, entry: AuditEntry, response: &Response) {
+        let status = match (&response.data, response.errors.is_empty()) {
+            (_, true) => "ok",
+            (ConstValue::Null, false) => "error",
+            (_, false) => "partial",
+        };
+        let record = json!({
+            "id": entry.id,
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "phase": "completed",
+            "operationName": entry.operation_name,
+            "actor": entry.actor,
+            "services": entry.services,
+            "status": status,
+            "errors": response.errors.iter().map(|err| err.message.as_str()).collect::<Vec<_>>(),
+            "durationMs": entry.start_time.elapsed().as_millis() as u64,
+        });
+        if let Err(err) = self.write(record).await {
+            tracing::error!(error = %err, id = %entry.id, "Failed to write the audit record of a mutation.");
+        }
+    }
+
+    async fn write(&self, record: Value) -> anyhow::Result<()> {
+        if let Some(file) = &self.file {
+            let (ack, synced) = oneshot::channel();
+            file.send((record.to_string(), ack))
+                .await
+                .ok()
+                .context("The audit file is closed.")?;
+            synced
+                .await
+                .context("The audit file is closed.")?
+                .context("Failed to append to the audit file.")?;
+        }
+        if let Some(url) = &self.config.url {
+            self.client
+                .post(url)
+                .json(&record)
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status())
+                .context("Failed to send the audit record.")?;
+        }
+        Ok(())
+    }
+
+    fn actor(&self, context: &RequestContext) -> Option<String> {
+        let claim = self
+            .config
+            .actor_claim
+            .split('.')
+            .try_fold(context.claims.as_ref()?, |value, key| value.get(key))?;
+        match claim {
+            Value::String(actor) => Some(actor.clone()),
+            Value::Null => None,
+            claim => Some(claim.to_string()),
+        }
+    }
+
+    fn is_redacted(&self, name: &str) -> bool {
+        self.config.redact_variables.iter().any(|redacted| redacted == name)
+    }
+
+    fn redact(&self, value: Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(name, value)| match self.is_redacted(&name) {
+                        true => (name, Value::String(REDACTED.to_string())),
+                        false => (name, self.redact(value)),
+                    })
+                    .collect(),
+            ),
+            Value::Array(values) => Value::Array(values.into_iter().map(|value| self.redact(value)).collect()),
+            value => value,
+        }
+    }
+}
+
+/// The response to a mutation that was not executed because its audit record
+/// could not be written.
+pub(crate) fn audit_unavailable() -> Response {
+    let mut error = ServerError::new("The mutation was not executed, as it could not be audited.");
+    error.extensions.insert(
+        "code".to_string(),
+        ConstValue::String(AUDIT_LOG_UNAVAILABLE.to_string()),
+    );
+    Response {
+        data: ConstValue::Null,
+        errors: vec![error],
+        extensions: Default::default(),
+        headers: Default::default(),
+    }
+}
+
+/// Whether the executed operation of a document is a mutation.
+pub(crate) fn is_mutation(document: &ExecutableDocument, operation_name: Option<&str>) -> bool {
+    let operation = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), None) => &operation.node,
+        (DocumentOperations::Multiple(operations), Some(name)) => match operations.get(name) {
+            Some(operation) => &operation.node,
+            None => return false,
+        },
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            &operations.values().next().unwrap().node
+        },
+        _ => return false,
+    };
+    operation.ty == OperationType::Mutation
+}
+
+/// The services fetched from by a plan.
+fn plan_services<'a>(plan: &RootNode<'a>) -> BTreeSet<&'a str> {
+    fn add<'a>(node: &PlanNode<'a>, services: &mut BTreeSet<&'a str>) {
+        match node {
+            PlanNode::Sequence(sequence) => sequence.nodes.iter().for_each(|node| add(node, services)),
+            PlanNode::Parallel(parallel) => parallel.nodes.iter().for_each(|node| add(node, services)),
+            PlanNode::Fetch(fetch) => {
+                services.insert(fetch.service);
+            },
+            PlanNode::Flatten(flatten) => {
+                services.insert(flatten.service);
+            },
+            PlanNode::Introspection(_) => {},
+        }
+    }
+
+    let mut services = BTreeSet::new();
+    match plan {
+        RootNode::Query(node) => add(node, &mut services),
+        RootNode::Defer(defer) => {
+            add(&defer.primary, &mut services);
+            for deferred in &defer.deferred {
+                add(&deferred.node, &mut services);
+            }
+        },
+        RootNode::Subscribe(subscribe) => {
+            services.extend(subscribe.subscribe_nodes.iter().map(|fetch| fetch.service));
+            if let Some(node) = &subscribe.flatten_node {
+                add(node, &mut services);
+            }
+        },
+    }
+    services
+}
+
+fn default_actor_claim() -> String {
+    "sub".to_string()
+}
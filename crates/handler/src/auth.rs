@@ -1,20 +1,43 @@
-use std::{collections::HashMap, convert::Infallible, sync::Arc};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    str::FromStr,
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
+use arc_swap::ArcSwap;
 use clap::Args;
-use http::{header::AUTHORIZATION, HeaderMap};
-use jsonwebtoken::{jwk::JwkSet, DecodingKey};
-use serde::Deserialize;
+use http::{
+    header::{AUTHORIZATION, COOKIE},
+    HeaderMap,
+    HeaderName,
+    HeaderValue,
+};
+use jsonwebtoken::{jwk::JwkSet, DecodingKey, Validation};
+use schemars::JsonSchema;
+use serde::{de::Error as _, Deserialize, Deserializer};
 use thiserror::Error;
 use warp::{header::headers_cloned, Filter, Rejection};
 
+use crate::shared_config::SharedConfig;
+
+/// The shortest time between two fetches of a key set for tokens with an
+/// unknown `kid`, so that such tokens cannot flood the identity provider.
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long fetching a key set may take.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Default)]
 pub struct Auth {
     pub config: AuthConfig,
-    pub decoding_keys: HashMap<String, DecodingKey>,
+    /// The issuers the tokens are verified against, the `jwks` key set first.
+    pub issuers: Vec<Issuer>,
 }
 
-#[derive(Args, Clone, Debug, Default, Deserialize)]
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
 pub struct AuthConfig {
     #[clap(long, env = "AUTH_ENABLED", default_value_t = false)]
     #[serde(default)]
@@ -32,20 +55,200 @@ pub struct AuthConfig {
     #[serde(default)]
     pub required: bool,
 
+    /// The URL of the key set of the tokens of any issuer, unused if empty.
     #[clap(long, env = "AUTH_JWKS", default_value = "")]
+    #[serde(default)]
     pub jwks: String,
+
+    /// The accepted `aud` claims of the tokens verified with `jwks`, not
+    /// checked if empty.
+    #[clap(long = "auth-audiences", env = "AUTH_AUDIENCES", value_delimiter = ',')]
+    #[serde(default)]
+    pub audiences: Vec<String>,
+
+    /// Further identity providers, whose tokens are told apart by their
+    /// `iss` claim.
+    #[clap(skip)]
+    #[serde(default)]
+    pub issuers: Vec<IssuerConfig>,
+
+    /// How many seconds the `exp` and `nbf` claims may be off, for the clock
+    /// drift between the identity providers and the gateway.
+    #[clap(long = "auth-clock-skew-secs", env = "AUTH_CLOCK_SKEW_SECS", default_value_t = 60)]
+    #[serde(default = "default_clock_skew_secs")]
+    pub clock_skew_secs: u64,
+
+    /// How often the key sets are fetched again to pick up rotated keys, 0
+    /// to only fetch them again for tokens with an unknown `kid`.
+    #[clap(
+        long = "auth-jwks-refresh-secs",
+        env = "AUTH_JWKS_REFRESH_SECS",
+        default_value_t = 3600
+    )]
+    #[serde(default = "default_jwks_refresh_secs")]
+    pub jwks_refresh_secs: u64,
+
+    /// Claims of the verified tokens sent to the subgraphs in headers,
+    /// replacing the headers of the same names sent by the clients.
+    #[clap(skip)]
+    #[serde(default)]
+    pub claim_headers: Vec<ClaimHeader>,
+
+    /// Further headers holding the token, tried after `header_name`.
+    #[clap(skip)]
+    #[serde(default)]
+    pub headers: Vec<TokenHeader>,
+
+    /// Cookies holding the token, tried after the headers.
+    #[clap(long = "auth-cookies", env = "AUTH_COOKIES", value_delimiter = ',')]
+    #[serde(default)]
+    pub cookies: Vec<String>,
+
+    /// Query parameters holding the token of WebSocket upgrades, as browsers
+    /// cannot set their headers.
+    #[clap(long = "auth-query-params", env = "AUTH_QUERY_PARAMS", value_delimiter = ',')]
+    #[serde(default)]
+    pub query_params: Vec<String>,
 }
 
-impl Auth {
-    pub async fn try_new(config: AuthConfig) -> anyhow::Result<Self> {
-        let jwks = reqwest::get(&config.jwks)
+/// An identity provider issuing tokens.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct IssuerConfig {
+    /// The `iss` claim of the tokens of the provider.
+    pub issuer: String,
+
+    /// The URL of the key set of the provider.
+    pub jwks: String,
+
+    /// The accepted `aud` claims of the tokens, not checked if empty.
+    #[serde(default)]
+    pub audiences: Vec<String>,
+}
+
+/// A claim of the verified tokens sent to the subgraphs in a header, such as
+/// `sub` in `x-user-id`.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct ClaimHeader {
+    /// The claim, nested claims are separated by `.`.
+    pub claim: String,
+
+    /// The header the claim is sent in. Arrays are sent as their items
+    /// separated by `,`, and objects as JSON.
+    #[serde(deserialize_with = "deserialize_header_name")]
+    #[schemars(with = "String")]
+    pub header: HeaderName,
+}
+
+/// Deserialize a header name, so that invalid ones fail loading the config.
+fn deserialize_header_name<'de, D>(deserializer: D) -> Result<HeaderName, D::Error>
+where D: Deserializer<'de> {
+    let name = String::deserialize(deserializer)?;
+    HeaderName::from_str(&name).map_err(|_| D::Error::custom(format!("invalid header name '{}'", name)))
+}
+
+/// A header holding the token after a scheme prefix, such as `Token`.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct TokenHeader {
+    pub name: String,
+
+    /// The scheme before the token, the whole value is the token if empty.
+    #[serde(default)]
+    pub prefix: String,
+}
+
+impl AuthConfig {
+    /// Set the headers of the mapped claims of the verified token in the
+    /// headers sent to the subgraphs.
+    ///
+    /// The headers of the same names are removed even if the claims are
+    /// missing, so that the clients cannot set them.
+    pub(crate) fn forward_claims(&self, claims: Option<&serde_json::Value>, header_map: &mut HeaderMap) {
+        for claim_header in &self.claim_headers {
+            header_map.remove(&claim_header.header);
+
+            let claim = claims.and_then(|claims| {
+                claim_header
+                    .claim
+                    .split('.')
+                    .try_fold(claims, |value, key| value.get(key))
+            });
+            let value = match claim {
+                None | Some(serde_json::Value::Null) => continue,
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(serde_json::Value::Array(items)) => items
+                    .iter()
+                    .map(|item| match item {
+                        serde_json::Value::String(item) => item.clone(),
+                        item => item.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+                Some(value) => value.to_string(),
+            };
+            match HeaderValue::from_str(&value) {
+                Ok(value) => {
+                    header_map.insert(claim_header.header.clone(), value);
+                },
+                Err(_) => tracing::warn!(claim = %claim_header.claim, "The claim is not a valid header value."),
+            }
+        }
+    }
+}
+
+/// The keys of a JSON Web Key Set by their `kid`, fetched again when keys are
+/// rotated.
+pub struct KeySet {
+    jwks: String,
+    client: reqwest::Client,
+    keys: ArcSwap<HashMap<String, DecodingKey>>,
+    /// The time of the latest fetch, locked while fetching for unknown `kid`s
+    /// so that concurrent tokens wait for the same fetch.
+    fetched_at: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl KeySet {
+    /// A key set that is never fetched, such as the keys of shared secrets.
+    pub fn new(keys: HashMap<String, DecodingKey>) -> Self {
+        Self {
+            jwks: String::new(),
+            client: Default::default(),
+            keys: ArcSwap::from_pointee(keys),
+            fetched_at: Default::default(),
+        }
+    }
+
+    /// Fetch the key set at the `jwks` URL.
+    pub async fn fetch(jwks: &str) -> anyhow::Result<Self> {
+        let key_set = Self {
+            jwks: jwks.to_string(),
+            client: reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?,
+            keys: Default::default(),
+            fetched_at: Default::default(),
+        };
+        key_set.refresh().await?;
+        Ok(key_set)
+    }
+
+    /// Fetch the keys again, keeping the current ones if that fails.
+    pub async fn refresh(&self) -> anyhow::Result<()> {
+        let mut fetched_at = self.fetched_at.lock().await;
+        self.fetch_keys(&mut fetched_at).await
+    }
+
+    /// Fetch the keys, holding the lock of the time of the latest fetch.
+    async fn fetch_keys(&self, fetched_at: &mut Option<Instant>) -> anyhow::Result<()> {
+        *fetched_at = Some(Instant::now());
+        let jwks = self
+            .client
+            .get(&self.jwks)
+            .send()
             .await
             .context("failed to fetch jwks")?
             .json::<JwkSet>()
             .await
             .context("failed to decode jwks")?;
 
-        let decoding_keys = jwks
+        let keys = jwks
             .keys
             .into_iter()
             .filter_map(|jwk| {
@@ -53,8 +256,138 @@ impl Auth {
                 jwk.common.key_id.map(|kid| res.map(|key| (kid, key)))
             })
             .collect::<Result<HashMap<_, _>, _>>()?;
+        self.keys.store(Arc::new(keys));
+        Ok(())
+    }
+
+    /// The key with the `kid`, fetching the key set again if it is unknown
+    /// and the keys were not fetched recently.
+    async fn get(&self, kid: &str) -> Option<DecodingKey> {
+        if let Some(key) = self.keys.load().get(kid) {
+            return Some(key.clone());
+        }
+        if self.jwks.is_empty() {
+            return None;
+        }
+        let mut fetched_at = self.fetched_at.lock().await;
+        // The keys may have been fetched while waiting for the lock.
+        if let Some(key) = self.keys.load().get(kid) {
+            return Some(key.clone());
+        }
+        if fetched_at.is_some_and(|fetched_at| fetched_at.elapsed() < MIN_REFETCH_INTERVAL) {
+            return None;
+        }
+        if let Err(err) = self.fetch_keys(&mut fetched_at).await {
+            tracing::warn!(jwks = %self.jwks, error = %err, "Failed to fetch the key set.");
+        }
+        self.keys.load().get(kid).cloned()
+    }
+}
+
+/// Fetch the key set again every `interval`, until it is dropped.
+fn spawn_refresh(key_set: Weak<KeySet>, interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let Some(key_set) = key_set.upgrade() else {
+                return;
+            };
+            if let Err(err) = key_set.refresh().await {
+                tracing::warn!(jwks = %key_set.jwks, error = %err, "Failed to fetch the key set.");
+            }
+        }
+    });
+}
+
+/// The key set and the accepted claims of the tokens of an identity provider.
+pub struct Issuer {
+    /// The `iss` claim of the tokens, any if unset.
+    pub issuer: Option<String>,
+    pub audiences: Vec<String>,
+    pub keys: Arc<KeySet>,
+}
+
+impl Auth {
+    pub async fn try_new(config: AuthConfig) -> anyhow::Result<Self> {
+        let sources = (!config.jwks.is_empty())
+            .then(|| (None, config.jwks.clone(), config.audiences.clone()))
+            .into_iter()
+            .chain(config.issuers.iter().map(|issuer| {
+                (
+                    Some(issuer.issuer.clone()),
+                    issuer.jwks.clone(),
+                    issuer.audiences.clone(),
+                )
+            }))
+            .collect::<Vec<_>>();
+        anyhow::ensure!(
+            !config.enabled || !sources.is_empty(),
+            "authorization requires the `jwks` or the `issuers` key sets"
+        );
+        // The key sets of the issuers are fetched concurrently.
+        let key_sets = futures_util::future::try_join_all(sources.iter().map(|(_, jwks, _)| async move {
+            KeySet::fetch(jwks)
+                .await
+                .with_context(|| format!("failed to load the key set at '{}'", jwks))
+        }))
+        .await?;
+
+        let issuers = sources
+            .into_iter()
+            .zip(key_sets)
+            .map(|((issuer, _, audiences), key_set)| {
+                let keys = Arc::new(key_set);
+                if config.jwks_refresh_secs > 0 {
+                    spawn_refresh(Arc::downgrade(&keys), Duration::from_secs(config.jwks_refresh_secs));
+                }
+                Issuer {
+                    issuer,
+                    audiences,
+                    keys,
+                }
+            })
+            .collect();
+        Ok(Self { config, issuers })
+    }
+
+    /// Verify the tokens of any issuer with static keys, by their `kid`.
+    pub fn with_keys(config: AuthConfig, keys: HashMap<String, DecodingKey>) -> Self {
+        Self {
+            config,
+            issuers: vec![Issuer {
+                issuer: None,
+                audiences: Vec::new(),
+                keys: Arc::new(KeySet::new(keys)),
+            }],
+        }
+    }
+
+    /// The issuer of a token by its `iss` claim, or the one accepting tokens
+    /// of any issuer.
+    fn issuer(&self, token: &str) -> Result<&Issuer, AuthError> {
+        if let [issuer] = self.issuers.as_slice() {
+            if issuer.issuer.is_none() {
+                return Ok(issuer);
+            }
+        }
+
+        #[derive(Deserialize)]
+        struct Claims {
+            iss: Option<String>,
+        }
+        let mut validation = Validation::default();
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+        let claims = jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(&[]), &validation)
+            .map_err(AuthError::DecodingError)?
+            .claims;
 
-        Ok(Self { config, decoding_keys })
+        self.issuers
+            .iter()
+            .find(|issuer| issuer.issuer.is_some() && issuer.issuer == claims.iss)
+            .or_else(|| self.issuers.iter().find(|issuer| issuer.issuer.is_none()))
+            .ok_or(AuthError::UnknownIssuer)
     }
 }
 
@@ -74,51 +407,129 @@ pub enum AuthError {
 
     #[error("invalid kid in authorization header")]
     InvalidKid,
+
+    #[error("unknown issuer of the token")]
+    UnknownIssuer,
 }
 
 impl warp::reject::Reject for AuthError {}
 
-pub fn with_auth_state(auth: Arc<Auth>) -> impl Filter<Extract = (Arc<Auth>,), Error = Infallible> + Clone {
-    warp::any().map(move || auth.clone())
+/// The authorization of the current settings.
+pub fn with_auth_state(shared_config: SharedConfig) -> impl Filter<Extract = (Arc<Auth>,), Error = Infallible> + Clone {
+    warp::any().map(move || shared_config.load().auth.clone())
 }
 
-pub fn with_auth(auth: Arc<Auth>) -> impl Filter<Extract = ((),), Error = Rejection> + Clone {
-    headers_cloned().and(with_auth_state(auth)).and_then(jwt_auth_validate)
+/// Verifies the JWT of the request, extracting its claims if present.
+pub fn with_auth(
+    shared_config: SharedConfig,
+) -> impl Filter<Extract = (Option<serde_json::Value>,), Error = Rejection> + Clone {
+    headers_cloned()
+        .and(with_auth_state(shared_config))
+        .and_then(|header_map: HeaderMap, auth: Arc<Auth>| async move {
+            jwt_auth_validate(&header_map, &HashMap::new(), &auth).await
+        })
 }
 
-async fn jwt_auth_validate(header_map: HeaderMap, auth: Arc<Auth>) -> Result<(), Rejection> {
+/// Verifies the JWT of a WebSocket upgrade, which may also be sent in the
+/// `query_params`.
+pub fn with_websocket_auth(
+    shared_config: SharedConfig,
+) -> impl Filter<Extract = (Option<serde_json::Value>,), Error = Rejection> + Clone {
+    headers_cloned()
+        .and(
+            warp::query::<HashMap<String, String>>()
+                .or(warp::any().map(HashMap::new))
+                .unify(),
+        )
+        .and(with_auth_state(shared_config))
+        .and_then(
+            |header_map: HeaderMap, query: HashMap<String, String>, auth: Arc<Auth>| async move {
+                jwt_auth_validate(&header_map, &query, &auth).await
+            },
+        )
+}
+
+/// Find the token in the configured headers, cookies and query parameters,
+/// in this order.
+fn find_token<'a>(
+    config: &AuthConfig,
+    header_map: &'a HeaderMap,
+    query: &'a HashMap<String, String>,
+) -> Result<Option<&'a str>, AuthError> {
+    let mut prefix_not_found = false;
+    let headers = std::iter::once((config.header_name.as_str(), config.header_prefix.as_str())).chain(
+        config
+            .headers
+            .iter()
+            .map(|header| (header.name.as_str(), header.prefix.as_str())),
+    );
+    for (name, prefix) in headers {
+        if let Some(value) = header_map.get(name) {
+            match value.to_str().unwrap_or_default().strip_prefix(prefix) {
+                Some(token) => return Ok(Some(token.trim_start())),
+                None => prefix_not_found = true,
+            }
+        }
+    }
+
+    let cookies = header_map
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='));
+    for (name, value) in cookies {
+        if config.cookies.iter().any(|cookie| cookie == name) {
+            return Ok(Some(value));
+        }
+    }
+
+    if let Some(token) = config.query_params.iter().find_map(|name| query.get(name)) {
+        return Ok(Some(token));
+    }
+
+    match prefix_not_found {
+        true => Err(AuthError::AuthorizationPrefixNotFound),
+        false => Ok(None),
+    }
+}
+
+async fn jwt_auth_validate(
+    header_map: &HeaderMap,
+    query: &HashMap<String, String>,
+    auth: &Auth,
+) -> Result<Option<serde_json::Value>, Rejection> {
     if !auth.config.enabled {
-        return Ok(());
+        return Ok(None);
     }
 
-    let header = header_map.get(auth.config.header_name.as_str());
-    if header.is_none() && auth.config.required {
+    let token = find_token(&auth.config, header_map, query)?;
+    if token.is_none() && auth.config.required {
         return Err(warp::reject::custom(AuthError::MissingAuthorizationHeader));
     }
 
-    if let Some(header) = header {
-        let token = header
-            .to_str()
-            .unwrap_or_default()
-            .strip_prefix(&auth.config.header_prefix)
-            .ok_or(warp::reject::custom(AuthError::AuthorizationPrefixNotFound))?
-            .trim_start();
-
+    if let Some(token) = token {
         let token_header = jsonwebtoken::decode_header(token).map_err(AuthError::DecodingError)?;
 
         let kid = token_header.kid.ok_or(AuthError::MissingKid)?;
 
-        let decoding_key = auth.decoding_keys.get(&kid).ok_or(AuthError::InvalidKid)?;
+        let issuer = auth.issuer(token)?;
+        let decoding_key = issuer.keys.get(&kid).await.ok_or(AuthError::InvalidKid)?;
 
-        jsonwebtoken::decode::<serde_json::Value>(
-            token,
-            decoding_key,
-            &jsonwebtoken::Validation::new(token_header.alg),
-        )
-        .map_err(AuthError::DecodingError)?;
+        let mut validation = Validation::new(token_header.alg);
+        validation.leeway = auth.config.clock_skew_secs;
+        if let Some(iss) = &issuer.issuer {
+            validation.set_issuer(&[iss]);
+        }
+        if !issuer.audiences.is_empty() {
+            validation.set_audience(&issuer.audiences);
+        }
+        let token_data = jsonwebtoken::decode::<serde_json::Value>(token, &decoding_key, &validation)
+            .map_err(AuthError::DecodingError)?;
+        return Ok(Some(token_data.claims));
     }
 
-    Ok(())
+    Ok(None)
 }
 
 fn default_header_name() -> String {
@@ -127,3 +538,9 @@ fn default_header_name() -> String {
 fn default_header_prefix() -> String {
     "Bearer".to_string()
 }
+fn default_clock_skew_secs() -> u64 {
+    60
+}
+fn default_jwks_refresh_secs() -> u64 {
+    3600
+}
@@ -0,0 +1,367 @@
+use std::collections::HashSet;
+
+use graphgate_planner::ServerError;
+use graphgate_schema::{ComposedSchema, MetaField, MetaType, TypeExt, ValueExt};
+use parser::types::{DocumentOperations, ExecutableDocument, OperationType, Selection, SelectionSet};
+use value::{ConstValue, Name};
+
+use crate::cost::has_scope;
+
+/// The error code of the fields removed from operations for requiring
+/// authentication or scopes the request does not have.
+pub const UNAUTHORIZED_FIELD_OR_TYPE: &str = "UNAUTHORIZED_FIELD_OR_TYPE";
+
+/// Whether the claims of the verified JWT, if any, satisfy `@authenticated`
+/// and `@requiresScopes`.
+fn is_authorized(claims: Option<&serde_json::Value>, authenticated: bool, requires_scopes: &[Vec<String>]) -> bool {
+    (!authenticated || claims.is_some()) &&
+        (requires_scopes.is_empty() ||
+            requires_scopes
+                .iter()
+                .any(|scopes| scopes.iter().all(|scope| has_scope(claims, scope))))
+}
+
+struct Enforcer<'a> {
+    schema: &'a ComposedSchema,
+    claims: Option<&'a serde_json::Value>,
+}
+
+impl<'a> Enforcer<'a> {
+    /// Whether the request has access to a field, its parent type and the
+    /// type it returns.
+    fn allows(&self, parent_type: &MetaType, field: &MetaField) -> bool {
+        let ty = self.schema.types.get(field.ty.concrete_typename());
+        is_authorized(self.claims, field.authenticated, &field.requires_scopes) &&
+            is_authorized(self.claims, parent_type.authenticated, &parent_type.requires_scopes) &&
+            ty.map(|ty| is_authorized(self.claims, ty.authenticated, &ty.requires_scopes))
+                .unwrap_or(true)
+    }
+
+    fn type_condition(&self, parent_type: &'a MetaType, type_condition: Option<&Name>) -> &'a MetaType {
+        type_condition
+            .and_then(|name| self.schema.types.get(name))
+            .unwrap_or(parent_type)
+    }
+
+    /// Report the unauthorized fields of a selection set at their path in
+    /// the response.
+    ///
+    /// The fields of a fragment are reported at the path of its first
+    /// spread only, so that the fragments spread repeatedly are checked
+    /// once.
+    fn check(
+        &self,
+        document: &'a ExecutableDocument,
+        parent_type: &'a MetaType,
+        selection_set: &'a SelectionSet,
+        path: &mut Vec<ConstValue>,
+        checked: &mut HashSet<&'a Name>,
+        errors: &mut Vec<ServerError>,
+    ) {
+        for selection in &selection_set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    let meta_field = match parent_type.field_by_name(&field.node.name.node) {
+                        Some(meta_field) => meta_field,
+                        None => continue,
+                    };
+                    path.push(ConstValue::String(field.node.response_key().node.to_string()));
+                    if !self.allows(parent_type, meta_field) {
+                        let mut error = ServerError::new(format!(
+                            "Unauthorized field or type \"{}.{}\".",
+                            parent_type.name, meta_field.name
+                        ));
+                        error.path = path.clone();
+                        error.locations = vec![field.pos];
+                        error.extensions.insert(
+                            "code".to_string(),
+                            ConstValue::String(UNAUTHORIZED_FIELD_OR_TYPE.to_string()),
+                        );
+                        errors.push(error);
+                    } else if let Some(ty) = self.schema.types.get(meta_field.ty.concrete_typename()) {
+                        self.check(document, ty, &field.node.selection_set.node, path, checked, errors);
+                    }
+                    path.pop();
+                },
+                Selection::InlineFragment(fragment) => {
+                    let ty = self.type_condition(
+                        parent_type,
+                        fragment
+                            .node
+                            .type_condition
+                            .as_ref()
+                            .map(|type_condition| &type_condition.node.on.node),
+                    );
+                    self.check(document, ty, &fragment.node.selection_set.node, path, checked, errors);
+                },
+                Selection::FragmentSpread(spread) => {
+                    let name = &spread.node.fragment_name.node;
+                    if !checked.insert(name) {
+                        continue;
+                    }
+                    if let Some(fragment) = document.fragments.get(name) {
+                        let ty = self.type_condition(parent_type, Some(&fragment.node.type_condition.node.on.node));
+                        self.check(document, ty, &fragment.node.selection_set.node, path, checked, errors);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Remove the unauthorized fields of a selection set, and the fields and
+    /// inline fragments left without selections. Returns `true` if any is
+    /// removed.
+    fn strip(&self, parent_type: &'a MetaType, selection_set: &mut SelectionSet) -> bool {
+        let mut stripped = false;
+        selection_set.items.retain_mut(|selection| match &mut selection.node {
+            Selection::Field(field) => {
+                let meta_field = match parent_type.field_by_name(&field.node.name.node) {
+                    Some(meta_field) => meta_field,
+                    None => return true,
+                };
+                if !self.allows(parent_type, meta_field) {
+                    stripped = true;
+                    return false;
+                }
+                match self.schema.types.get(meta_field.ty.concrete_typename()) {
+                    Some(ty) if !field.node.selection_set.node.items.is_empty() => {
+                        stripped |= self.strip(ty, &mut field.node.selection_set.node);
+                        !field.node.selection_set.node.items.is_empty()
+                    },
+                    _ => true,
+                }
+            },
+            Selection::InlineFragment(fragment) => {
+                let ty = self.type_condition(
+                    parent_type,
+                    fragment
+                        .node
+                        .type_condition
+                        .as_ref()
+                        .map(|type_condition| &type_condition.node.on.node),
+                );
+                stripped |= self.strip(ty, &mut fragment.node.selection_set.node);
+                !fragment.node.selection_set.node.items.is_empty()
+            },
+            Selection::FragmentSpread(_) => true,
+        });
+        stripped
+    }
+}
+
+/// Remove the spreads of the removed fragments, and the fields and inline
+/// fragments left without selections.
+fn remove_spreads(selection_set: &mut SelectionSet, removed: &HashSet<Name>) {
+    selection_set.items.retain_mut(|selection| match &mut selection.node {
+        Selection::Field(field) => {
+            if field.node.selection_set.node.items.is_empty() {
+                return true;
+            }
+            remove_spreads(&mut field.node.selection_set.node, removed);
+            !field.node.selection_set.node.items.is_empty()
+        },
+        Selection::InlineFragment(fragment) => {
+            remove_spreads(&mut fragment.node.selection_set.node, removed);
+            !fragment.node.selection_set.node.items.is_empty()
+        },
+        Selection::FragmentSpread(spread) => !removed.contains(&spread.node.fragment_name.node),
+    });
+}
+
+/// Collect the fragments spread in a selection set, and the variables its
+/// arguments and directives reference.
+fn collect_references<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    fragments: &mut HashSet<&'a Name>,
+    variables: &mut HashSet<&'a str>,
+) {
+    for selection in &selection_set.items {
+        let directives = match &selection.node {
+            Selection::Field(field) => {
+                for (_, value) in &field.node.arguments {
+                    variables.extend(value.node.referenced_variables());
+                }
+                collect_references(document, &field.node.selection_set.node, fragments, variables);
+                &field.node.directives
+            },
+            Selection::InlineFragment(fragment) => {
+                collect_references(document, &fragment.node.selection_set.node, fragments, variables);
+                &fragment.node.directives
+            },
+            Selection::FragmentSpread(spread) => {
+                let name = &spread.node.fragment_name.node;
+                if fragments.insert(name) {
+                    if let Some(fragment) = document.fragments.get(name) {
+                        for directive in &fragment.node.directives {
+                            for (_, value) in &directive.node.arguments {
+                                variables.extend(value.node.referenced_variables());
+                            }
+                        }
+                        collect_references(document, &fragment.node.selection_set.node, fragments, variables);
+                    }
+                }
+                &spread.node.directives
+            },
+        };
+        for directive in directives {
+            for (_, value) in &directive.node.arguments {
+                variables.extend(value.node.referenced_variables());
+            }
+        }
+    }
+}
+
+/// Remove the fragments no longer spread and the variables no longer used,
+/// which would fail the validation of the document.
+fn remove_unused_definitions(document: &mut ExecutableDocument) {
+    let mut used_fragments = HashSet::new();
+    let mut unused_variables = Vec::new();
+    let operations: Vec<_> = match &document.operations {
+        DocumentOperations::Single(operation) => vec![(None, operation)],
+        DocumentOperations::Multiple(operations) => operations
+            .iter()
+            .map(|(name, operation)| (Some(name.clone()), operation))
+            .collect(),
+    };
+    for (name, operation) in operations {
+        let mut fragments = HashSet::new();
+        let mut variables = HashSet::new();
+        collect_references(
+            document,
+            &operation.node.selection_set.node,
+            &mut fragments,
+            &mut variables,
+        );
+        used_fragments.extend(fragments.into_iter().cloned());
+        unused_variables.extend(
+            operation
+                .node
+                .variable_definitions
+                .iter()
+                .filter(|definition| !variables.contains(definition.node.name.node.as_str()))
+                .map(|definition| (name.clone(), definition.node.name.node.clone())),
+        );
+    }
+
+    document.fragments.retain(|name, _| used_fragments.contains(name));
+    for (operation_name, variable) in unused_variables {
+        let operation = match (&mut document.operations, operation_name) {
+            (DocumentOperations::Single(operation), None) => operation,
+            (DocumentOperations::Multiple(operations), Some(name)) => match operations.get_mut(&name) {
+                Some(operation) => operation,
+                None => continue,
+            },
+            _ => continue,
+        };
+        operation
+            .node
+            .variable_definitions
+            .retain(|definition| definition.node.name.node != variable);
+    }
+}
+
+/// Remove the fields of a document the request has no access to, as
+/// required by `@authenticated` and `@requiresScopes` on the fields, their
+/// parent types or the types they return.
+///
+/// Returns the errors of the unauthorized fields of the executed operation,
+/// or `Err` if none of its fields is left to execute.
+pub(crate) fn remove_unauthorized_fields(
+    schema: &ComposedSchema,
+    document: &mut ExecutableDocument,
+    operation_name: Option<&str>,
+    claims: Option<&serde_json::Value>,
+) -> Result<Vec<ServerError>, Vec<ServerError>> {
+    let enforcer = Enforcer { schema, claims };
+    let root_type = |ty: OperationType| {
+        match ty {
+            OperationType::Query => Some(schema.query_type()),
+            OperationType::Mutation => schema.mutation_type(),
+            OperationType::Subscription => schema.subscription_type(),
+        }
+        .and_then(|name| schema.types.get(name))
+    };
+
+    let operation = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), _) => Some(operation),
+        (DocumentOperations::Multiple(operations), Some(name)) => operations.get(name),
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => operations.values().next(),
+        _ => None,
+    };
+    let mut errors = Vec::new();
+    if let Some((operation, root_type)) =
+        operation.and_then(|operation| Some(operation).zip(root_type(operation.node.ty)))
+    {
+        enforcer.check(
+            document,
+            root_type,
+            &operation.node.selection_set.node,
+            &mut Vec::new(),
+            &mut HashSet::new(),
+            &mut errors,
+        );
+    }
+
+    let mut stripped = false;
+    for fragment in document.fragments.values_mut() {
+        if let Some(ty) = schema.types.get(&fragment.node.type_condition.node.on.node) {
+            stripped |= enforcer.strip(ty, &mut fragment.node.selection_set.node);
+        }
+    }
+    let operations: Vec<_> = match &mut document.operations {
+        DocumentOperations::Single(operation) => vec![operation],
+        DocumentOperations::Multiple(operations) => operations.values_mut().collect(),
+    };
+    for operation in operations {
+        if let Some(root_type) = root_type(operation.node.ty) {
+            stripped |= enforcer.strip(root_type, &mut operation.node.selection_set.node);
+        }
+    }
+    if !stripped {
+        return Ok(errors);
+    }
+
+    // The fragments left without selections are removed with their spreads,
+    // which may leave others without selections.
+    loop {
+        let removed = document
+            .fragments
+            .iter()
+            .filter(|(_, fragment)| fragment.node.selection_set.node.items.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        if removed.is_empty() {
+            break;
+        }
+        document.fragments.retain(|name, _| !removed.contains(name));
+        for fragment in document.fragments.values_mut() {
+            remove_spreads(&mut fragment.node.selection_set.node, &removed);
+        }
+        let operations: Vec<_> = match &mut document.operations {
+            DocumentOperations::Single(operation) => vec![operation],
+            DocumentOperations::Multiple(operations) => operations.values_mut().collect(),
+        };
+        for operation in operations {
+            remove_spreads(&mut operation.node.selection_set.node, &removed);
+        }
+    }
+    remove_unused_definitions(document);
+
+    let is_empty = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), _) => operation.node.selection_set.node.items.is_empty(),
+        (DocumentOperations::Multiple(operations), Some(name)) => operations
+            .get(name)
+            .is_some_and(|operation| operation.node.selection_set.node.items.is_empty()),
+        (DocumentOperations::Multiple(operations), None) => {
+            operations.len() == 1 &&
+                operations
+                    .values()
+                    .all(|operation| operation.node.selection_set.node.items.is_empty())
+        },
+    };
+    match is_empty {
+        true => Err(errors),
+        false => Ok(errors),
+    }
+}
@@ -0,0 +1,52 @@
+use std::{collections::HashSet, sync::RwLock};
+
+use graphgate_planner::ServerError;
+use value::ConstValue;
+
+use crate::cache_key::query_hash;
+
+/// The error code of the operations blocked by the admin endpoints.
+pub const OPERATION_BLOCKED: &str = "OPERATION_BLOCKED";
+
+/// The error of a blocked operation.
+pub(crate) fn operation_blocked() -> ServerError {
+    let mut error = ServerError::new("The operation is blocked.");
+    error
+        .extensions
+        .insert("code".to_string(), ConstValue::String(OPERATION_BLOCKED.to_string()));
+    error
+}
+
+/// The SHA-256 hashes of the text of the operations rejected until they are
+/// unblocked, such as an operation overloading a subgraph.
+#[derive(Debug, Default)]
+pub(crate) struct Blocklist {
+    hashes: RwLock<HashSet<String>>,
+}
+
+impl Blocklist {
+    /// Block the operation with the SHA-256 `hash`, returning `false` if it
+    /// was already blocked.
+    pub(crate) fn block(&self, hash: &str) -> bool {
+        self.hashes.write().unwrap().insert(hash.to_ascii_lowercase())
+    }
+
+    /// Unblock the operation with the SHA-256 `hash`, returning `false` if it
+    /// was not blocked.
+    pub(crate) fn unblock(&self, hash: &str) -> bool {
+        self.hashes.write().unwrap().remove(&hash.to_ascii_lowercase())
+    }
+
+    /// The hashes of the blocked operations, sorted.
+    pub(crate) fn hashes(&self) -> Vec<String> {
+        let mut hashes = self.hashes.read().unwrap().iter().cloned().collect::<Vec<_>>();
+        hashes.sort();
+        hashes
+    }
+
+    /// Returns `true` if the operation text is blocked.
+    pub(crate) fn contains(&self, query: &str) -> bool {
+        let hashes = self.hashes.read().unwrap();
+        !hashes.is_empty() && hashes.contains(&query_hash(query))
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::context_injection::RequestContext;
+
+/// Assigns requests to a stable bucket derived from a request key, so that
+/// the same user always gets the same canary, rollout or experiment
+/// decision.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct BucketingConfig {
+    /// The part of the request identifying who sends it.
+    pub key: BucketKey,
+
+    /// The number of buckets the keys are spread over.
+    #[serde(default = "default_buckets")]
+    pub buckets: u32,
+
+    /// Mixed into every hash, changing it reshuffles all the assignments.
+    #[serde(default)]
+    pub salt: String,
+}
+
+/// The part of a request hashed into a bucket.
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BucketKey {
+    /// The value of a request header.
+    Header(String),
+
+    /// A claim of the verified JWT, nested claims are separated by `.`.
+    Claim(String),
+
+    /// The IP address of the client.
+    Ip,
+}
+
+/// Hashes the keys of the requests, the same key must always give the same
+/// hash.
+pub trait BucketHasher: Send + Sync {
+    fn hash(&self, key: &str) -> u64;
+}
+
+/// Hashes keys with SHA-256, stable across processes and versions.
+#[derive(Debug, Default)]
+pub struct Sha256BucketHasher;
+
+impl BucketHasher for Sha256BucketHasher {
+    fn hash(&self, key: &str) -> u64 {
+        let digest = Sha256::digest(key.as_bytes());
+        u64::from_be_bytes(digest[..8].try_into().unwrap())
+    }
+}
+
+/// Puts requests into buckets shared by the features that need sticky
+/// per-request decisions.
+#[derive(Clone)]
+pub struct Bucketing {
+    config: BucketingConfig,
+    hasher: Arc<dyn BucketHasher>,
+}
+
+impl Bucketing {
+    pub fn new(config: BucketingConfig) -> Self {
+        Self::with_hasher(config, Arc::new(Sha256BucketHasher))
+    }
+
+    pub fn with_hasher(config: BucketingConfig, hasher: Arc<dyn BucketHasher>) -> Self {
+        Self { config, hasher }
+    }
+
+    pub fn buckets(&self) -> u32 {
+        self.config.buckets.max(1)
+    }
+
+    /// The bucket of a request, `None` if it lacks the key.
+    ///
+    /// Each feature passes its own `namespace`, so that the users of a
+    /// bucket in one feature are spread over all the buckets of another.
+    pub fn bucket(&self, namespace: &str, context: &RequestContext) -> Option<u32> {
+        let key = self.key(context)?;
+        let hash = self.hasher.hash(&format!("{}:{}:{}", self.config.salt, namespace, key));
+        Some((hash % self.buckets() as u64) as u32)
+    }
+
+    /// Whether a request is among the `percentage` of requests of a rollout,
+    /// requests without the key never are.
+    ///
+    /// Raising the percentage keeps the requests already in the rollout.
+    pub fn in_rollout(&self, namespace: &str, context: &RequestContext, percentage: f64) -> bool {
+        match self.bucket(namespace, context) {
+            Some(bucket) => (bucket as f64) < percentage / 100.0 * self.buckets() as f64,
+            None => false,
+        }
+    }
+
+    fn key(&self, context: &RequestContext) -> Option<String> {
+        match &self.config.key {
+            BucketKey::Header(name) => context
+                .headers
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .map(ToString::to_string),
+            BucketKey::Claim(path) => {
+                let claim = path
+                    .split('.')
+                    .try_fold(context.claims.as_ref()?, |value, key| value.get(key))?;
+                match claim {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(claim) => Some(claim.clone()),
+                    claim => Some(claim.to_string()),
+                }
+            },
+            BucketKey::Ip => context.remote_addr.map(|addr| addr.ip().to_string()),
+        }
+    }
+}
+
+fn default_buckets() -> u32 {
+    100
+}
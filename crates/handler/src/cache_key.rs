@@ -0,0 +1,55 @@
+use graphgate_planner::Request;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+/// The SHA-256 hash of a query in hex, as sent in the `persistedQuery`
+/// extension.
+pub(crate) fn query_hash(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+/// The canonical URL of a GET request, so that CDNs cache the requests of
+/// an operation with the same variables under one key.
+///
+/// The parameters are in alphabetical order, the `persistedQuery` extension
+/// carries the hash of the query and the keys of the variables are sorted.
+/// The query text is left out of the URL of a `persisted` query, so that the
+/// requests sending only its hash share the key of those sending its text.
+pub(crate) fn canonical_url(path: &str, request: &Request, persisted: bool) -> String {
+    let extensions = json!({
+        "persistedQuery": {
+            "sha256Hash": query_hash(&request.query),
+            "version": 1,
+        },
+    });
+    let mut params = vec![("extensions", extensions.to_string())];
+    if let Some(operation) = &request.operation {
+        params.push(("operationName", operation.clone()));
+    }
+    if !persisted {
+        params.push(("query", request.query.clone()));
+    }
+    match serde_json::to_value(&request.variables) {
+        Ok(Value::Object(variables)) if variables.is_empty() => {},
+        Ok(variables) => params.push(("variables", sort_keys(variables).to_string())),
+        Err(_) => {},
+    }
+    format!("{}?{}", path, serde_urlencoded::to_string(params).unwrap_or_default())
+}
+
+pub(crate) fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries = object.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        },
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
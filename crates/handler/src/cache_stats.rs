@@ -0,0 +1,147 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The caches of the gateway. The query plans are not cached, every request
+/// is planned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CacheKind {
+    /// The queries of the Automatic Persisted Queries protocol.
+    PersistedQueries,
+    /// The entities of the `_entities` fetches.
+    Entities,
+    /// The responses of the introspection operations.
+    Introspection,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [
+        CacheKind::PersistedQueries,
+        CacheKind::Entities,
+        CacheKind::Introspection,
+    ];
+
+    /// The name of the cache in the admin endpoints.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheKind::PersistedQueries => "persisted_queries",
+            CacheKind::Entities => "entities",
+            CacheKind::Introspection => "introspection",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// The usage of a cache since the gateway started.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// The number of entries, unless the cache is kept outside the gateway.
+    pub entries: Option<usize>,
+
+    /// An estimate of the memory used by the keys and values of the entries,
+    /// in bytes, unless the cache is kept outside the gateway.
+    pub memory_bytes: Option<usize>,
+
+    pub hits: u64,
+
+    pub misses: u64,
+
+    /// The entries hit the most since they were stored, most hit first.
+    pub hottest: Vec<HotEntry>,
+}
+
+impl CacheStats {
+    /// The share of the lookups that were hits, if any.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// An entry of a cache and how often it was hit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HotEntry {
+    pub key: String,
+    pub hits: u64,
+}
+
+/// Counts the hits and misses of a cache.
+#[derive(Default)]
+pub(crate) struct HitCounter {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl HitCounter {
+    pub(crate) fn record(&self, hit: bool) {
+        match hit {
+            true => self.hits.fetch_add(1, Ordering::Relaxed),
+            false => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    /// The stats of a cache kept outside the gateway.
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..Default::default()
+        }
+    }
+
+    /// The stats of a cache kept in memory, from its entries with their
+    /// size in bytes and hits.
+    pub(crate) fn memory_stats<'a>(
+        &self,
+        entries: impl Iterator<Item = (&'a str, usize, u64)>,
+        top: usize,
+    ) -> CacheStats {
+        let (mut count, mut memory_bytes) = (0, 0);
+        let hottest = hottest(
+            entries.map(|(key, size, hits)| {
+                count += 1;
+                memory_bytes += key.len() + size;
+                (key, hits)
+            }),
+            top,
+        );
+        self.counted_stats(count, memory_bytes, hottest)
+    }
+
+    /// The stats of a cache kept in memory that counts its entries and their
+    /// size as they are stored and removed.
+    pub(crate) fn counted_stats(&self, entries: usize, memory_bytes: usize, hottest: Vec<HotEntry>) -> CacheStats {
+        CacheStats {
+            entries: Some(entries),
+            memory_bytes: Some(memory_bytes),
+            hottest,
+            ..self.stats()
+        }
+    }
+}
+
+/// The `top` most hit entries, most hit first, cloning only their keys.
+pub(crate) fn hottest<'a>(entries: impl Iterator<Item = (&'a str, u64)>, top: usize) -> Vec<HotEntry> {
+    let mut heap = BinaryHeap::with_capacity(top + 1);
+    for (key, hits) in entries {
+        if hits == 0 || top == 0 {
+            continue;
+        }
+        heap.push(Reverse((hits, Reverse(key))));
+        if heap.len() > top {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((hits, Reverse(key)))| HotEntry {
+            key: key.to_string(),
+            hits,
+        })
+        .collect()
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use clap::Args;
+use graphgate_planner::{Response, ServerError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::ConstValue;
+
+use crate::metrics::METRICS;
+
+/// The error code of operations that exceed the limit of subgraph requests.
+pub const CALL_BUDGET_EXCEEDED: &str = "CALL_BUDGET_EXCEEDED";
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct CallBudgetConfig {
+    /// The most subgraph requests a single operation may send, counting
+    /// every fetch, entity fetch and batch, 0 for no limit.
+    ///
+    /// Entities served from the entity cache are not counted.
+    #[clap(long = "call-budget-max-calls", env = "CALL_BUDGET_MAX_CALLS", default_value_t = 0)]
+    #[serde(default)]
+    pub max_calls: usize,
+}
+
+/// The subgraph requests of an operation, counted against the limit.
+#[derive(Default)]
+pub(crate) struct CallBudget {
+    max_calls: Option<usize>,
+    calls: AtomicUsize,
+    exceeded: AtomicBool,
+}
+
+impl CallBudget {
+    pub(crate) fn new(config: &CallBudgetConfig) -> Self {
+        Self {
+            max_calls: (config.max_calls > 0).then_some(config.max_calls),
+            ..Default::default()
+        }
+    }
+
+    /// Count a subgraph request, failing it once the operation exceeds the
+    /// limit, so that no further requests are sent.
+    pub(crate) fn call(&self) -> anyhow::Result<()> {
+        let max_calls = match self.max_calls {
+            Some(max_calls) => max_calls,
+            None => return Ok(()),
+        };
+        if self.calls.fetch_add(1, Ordering::Relaxed) >= max_calls {
+            if !self.exceeded.swap(true, Ordering::Relaxed) {
+                METRICS.call_budget_exceeded_counter.add(1, &[]);
+            }
+            anyhow::bail!("the operation exceeds the limit of {} subgraph requests", max_calls);
+        }
+        Ok(())
+    }
+
+    /// Start counting again, for the next event of a subscription.
+    pub(crate) fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+        self.exceeded.store(false, Ordering::Relaxed);
+    }
+
+    /// The error response to send instead of a partial response if the
+    /// operation exceeded the limit.
+    pub(crate) fn check(&self) -> Option<Response> {
+        let max_calls = self.max_calls?;
+        if !self.exceeded.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let mut error = ServerError::new(format!(
+            "The operation exceeds the limit of {} subgraph requests.",
+            max_calls
+        ));
+        error
+            .extensions
+            .insert("code".to_string(), ConstValue::String(CALL_BUDGET_EXCEEDED.to_string()));
+        Some(Response {
+            data: ConstValue::Null,
+            errors: vec![error],
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use clap::Args;
+use graphgate_executor::SubgraphStatusError;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    bucketing::Bucketing,
+    constants::{KEY_FAULT, KEY_SERVICE},
+    context_injection::RequestContext,
+    metrics::METRICS,
+};
+
+/// A subgraph request dropped by an injected fault, failing as if the
+/// connection could not be made.
+#[derive(Debug, Error)]
+#[error("connection to service \"{service}\" dropped by an injected fault")]
+pub(crate) struct DroppedConnectionError {
+    service: String,
+}
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ChaosConfig {
+    /// Inject the faults in the subgraph requests, for resilience testing
+    /// only.
+    #[clap(
+        id = "chaos_enabled",
+        long = "chaos-enabled",
+        env = "CHAOS_ENABLED",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// The faults injected in the subgraph requests, every matching fault
+    /// is rolled for independently.
+    #[clap(skip)]
+    #[serde(default)]
+    pub faults: Vec<Fault>,
+}
+
+/// A fault injected in a percentage of the requests to a service.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct Fault {
+    /// The service the fault is injected in, every service if unset.
+    #[serde(default)]
+    pub service: Option<String>,
+
+    /// The percentage of the requests the fault is injected in, from 0 to
+    /// 100.
+    ///
+    /// When the requests are bucketed, the fault is injected in every
+    /// request of the clients in the rollout of this percentage instead.
+    #[serde(default = "default_percentage")]
+    pub percentage: f64,
+
+    #[serde(flatten)]
+    pub kind: FaultKind,
+}
+
+fn default_percentage() -> f64 {
+    100.0
+}
+
+/// What a [`Fault`] does to a subgraph request.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FaultKind {
+    /// Delay the request before it is sent.
+    Latency { latency_ms: u64 },
+
+    /// Fail the request as if the service responded with the status code,
+    /// without sending it.
+    Status {
+        status: u16,
+        #[serde(default)]
+        body: String,
+    },
+
+    /// Fail the request as if the connection to the service was dropped,
+    /// without sending it.
+    Drop,
+}
+
+impl FaultKind {
+    fn name(&self) -> &'static str {
+        match self {
+            FaultKind::Latency { .. } => "latency",
+            FaultKind::Status { .. } => "status",
+            FaultKind::Drop => "drop",
+        }
+    }
+}
+
+/// The faults injected in the subgraph requests of a request.
+pub(crate) struct ChaosFaults {
+    config: Arc<ChaosConfig>,
+    /// Whether the request is in the rollout of each fault, by its bucket,
+    /// `None` for the faults rolled for on every subgraph request.
+    in_rollout: Vec<Option<bool>>,
+}
+
+impl ChaosFaults {
+    pub(crate) fn new(config: Arc<ChaosConfig>, bucketing: Option<&Bucketing>, context: &RequestContext) -> Self {
+        let in_rollout = match bucketing {
+            Some(bucketing) => config
+                .faults
+                .iter()
+                .enumerate()
+                .map(|(idx, fault)| {
+                    let namespace = format!("chaos:{}", idx);
+                    bucketing
+                        .bucket(&namespace, context)
+                        .map(|_| bucketing.in_rollout(&namespace, context, fault.percentage))
+                })
+                .collect(),
+            None => vec![None; config.faults.len()],
+        };
+        Self { config, in_rollout }
+    }
+
+    /// Roll for the faults of a service before a request is sent to it,
+    /// delaying it or failing it instead of sending it.
+    pub(crate) async fn inject(&self, service: &str) -> Result<()> {
+        let faults = self
+            .config
+            .faults
+            .iter()
+            .zip(&self.in_rollout)
+            .filter(|(fault, _)| fault.service.as_deref().is_none_or(|name| name == service));
+        for (fault, in_rollout) in faults {
+            if !in_rollout.unwrap_or_else(|| fastrand::f64() * 100.0 < fault.percentage) {
+                continue;
+            }
+
+            METRICS.chaos_fault_counter.add(1, &[
+                KEY_SERVICE.string(service.to_string()),
+                KEY_FAULT.string(fault.kind.name()),
+            ]);
+            tracing::debug!(service = %service, fault = fault.kind.name(), "Injected fault.");
+            match &fault.kind {
+                FaultKind::Latency { latency_ms } => tokio::time::sleep(Duration::from_millis(*latency_ms)).await,
+                FaultKind::Status { status, body } => {
+                    return Err(SubgraphStatusError {
+                        service: service.to_string(),
+                        status: *status,
+                        body: body.clone(),
+                    }
+                    .into())
+                },
+                FaultKind::Drop => {
+                    return Err(DroppedConnectionError {
+                        service: service.to_string(),
+                    }
+                    .into())
+                },
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::Duration,
+};
+
+use clap::Args;
+use graphgate_executor::CircuitOpenError;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use crate::{
+    constants::{KEY_CIRCUIT_STATE, KEY_SERVICE},
+    metrics::METRICS,
+};
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failed fetches, timing out, failing to
+    /// connect or with a 5xx status, that open the circuit of a service.
+    #[clap(
+        long = "circuit-breaker-failure-threshold",
+        env = "CIRCUIT_BREAKER_FAILURE_THRESHOLD",
+        default_value_t = 5
+    )]
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// How long the fetches to a service fail fast once its circuit opened,
+    /// in seconds, before one fetch is let through to probe it.
+    #[clap(
+        long = "circuit-breaker-cooldown-secs",
+        env = "CIRCUIT_BREAKER_COOLDOWN_SECS",
+        default_value_t = 30
+    )]
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+
+    /// The thresholds of the services that differ from the defaults, by
+    /// service name.
+    #[clap(skip)]
+    #[serde(default)]
+    pub services: HashMap<String, ServiceCircuitBreakerConfig>,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            cooldown_secs: default_cooldown_secs(),
+            services: Default::default(),
+        }
+    }
+}
+
+/// The thresholds of the circuit of a service, the defaults apply to those
+/// not set.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ServiceCircuitBreakerConfig {
+    #[serde(default)]
+    pub failure_threshold: Option<u32>,
+
+    #[serde(default)]
+    pub cooldown_secs: Option<u64>,
+}
+
+impl CircuitBreakerConfig {
+    fn failure_threshold(&self, service: &str) -> u32 {
+        self.services
+            .get(service)
+            .and_then(|config| config.failure_threshold)
+            .unwrap_or(self.failure_threshold)
+            .max(1)
+    }
+
+    fn cooldown(&self, service: &str) -> Duration {
+        Duration::from_secs(
+            self.services
+                .get(service)
+                .and_then(|config| config.cooldown_secs)
+                .unwrap_or(self.cooldown_secs),
+        )
+    }
+}
+
+/// The state of the circuit of a service.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Circuit {
+    /// Fetches are sent, counting the consecutive failures.
+    Closed { failures: u32 },
+    /// Fetches fail fast until the cool-down ends.
+    Open { until: Instant },
+    /// A fetch was let through to probe the service, the others fail fast
+    /// until it completes, or another cool-down ends without it completing.
+    HalfOpen { until: Instant },
+}
+
+impl Circuit {
+    fn name(&self) -> &'static str {
+        match self {
+            Circuit::Closed { .. } => "closed",
+            Circuit::Open { .. } => "open",
+            Circuit::HalfOpen { .. } => "half_open",
+        }
+    }
+}
+
+/// Tracks the consecutive failures of the subgraphs, failing the fetches to
+/// those that keep failing fast for a cool-down.
+#[derive(Default)]
+pub(crate) struct CircuitBreaker {
+    config: RwLock<Option<CircuitBreakerConfig>>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn set_config(&self, config: CircuitBreakerConfig) {
+        *self.config.write().unwrap() = Some(config);
+    }
+
+    /// Check that a fetch may be sent to the service, failing fast if its
+    /// circuit is open.
+    pub(crate) fn acquire(&self, service: &str) -> Result<(), CircuitOpenError> {
+        let config = self.config.read().unwrap();
+        let config = match &*config {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = match circuits.get_mut(service) {
+            Some(circuit) => circuit,
+            None => return Ok(()),
+        };
+        let now = Instant::now();
+        let retry_after = match *circuit {
+            Circuit::Closed { .. } => return Ok(()),
+            Circuit::Open { until } | Circuit::HalfOpen { until } if until <= now => {
+                let until = now + config.cooldown(service);
+                transition(service, circuit, Circuit::HalfOpen { until });
+                return Ok(());
+            },
+            Circuit::Open { until } | Circuit::HalfOpen { until } => until - now,
+        };
+        METRICS
+            .circuit_rejected_counter
+            .add(1, &[KEY_SERVICE.string(service.to_string())]);
+        Err(CircuitOpenError {
+            service: service.to_string(),
+            retry_after,
+        })
+    }
+
+    /// Record the outcome of a fetch to the service, opening its circuit
+    /// after too many consecutive failures.
+    pub(crate) fn record(&self, service: &str, failed: bool) {
+        let config = self.config.read().unwrap();
+        let config = match &*config {
+            Some(config) => config,
+            None => return,
+        };
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits
+            .entry(service.to_string())
+            .or_insert(Circuit::Closed { failures: 0 });
+        let next = match (*circuit, failed) {
+            (Circuit::Closed { failures }, true) if failures + 1 < config.failure_threshold(service) => {
+                Circuit::Closed { failures: failures + 1 }
+            },
+            // The probe failed, or the threshold was reached.
+            (Circuit::Closed { .. } | Circuit::HalfOpen { .. }, true) => Circuit::Open {
+                until: Instant::now() + config.cooldown(service),
+            },
+            (Circuit::Open { .. }, _) => return,
+            (_, false) => Circuit::Closed { failures: 0 },
+        };
+        transition(service, circuit, next);
+    }
+}
+
+/// Move a circuit to another state, reporting the changes of state.
+fn transition(service: &str, circuit: &mut Circuit, next: Circuit) {
+    if circuit.name() != next.name() {
+        match next {
+            Circuit::Open { .. } => tracing::warn!(service = %service, "The circuit of the service opened."),
+            _ => tracing::info!(service = %service, state = next.name(), "The circuit of the service changed state."),
+        }
+        METRICS.circuit_transition_counter.add(1, &[
+            KEY_SERVICE.string(service.to_string()),
+            KEY_CIRCUIT_STATE.string(next.name()),
+        ]);
+    }
+    *circuit = next;
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_cooldown_secs() -> u64 {
+    30
+}
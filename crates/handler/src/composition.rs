@@ -0,0 +1,37 @@
+use clap::Args;
+use graphgate_schema::{composition_hints, infer_shareable, CompositionHint};
+use parser::types::ServiceDocument;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// How the schemas of the subgraphs are composed.
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct CompositionConfig {
+    /// Make the value types that several subgraphs define identically
+    /// shareable, as if they were marked `@shareable`, with a composition
+    /// hint for each of them.
+    ///
+    /// Value types defined differently still fail to compose.
+    #[clap(
+        long = "composition-infer-shareable",
+        env = "COMPOSITION_INFER_SHAREABLE",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub infer_shareable: bool,
+}
+
+impl CompositionConfig {
+    /// Prepare the SDLs of the subgraphs for composition, returning the
+    /// composition hints, including those of the changes, ordered by
+    /// severity.
+    pub fn prepare(&self, documents: &mut [(String, ServiceDocument)]) -> Vec<CompositionHint> {
+        let mut hints = match self.infer_shareable {
+            true => infer_shareable(documents),
+            false => Vec::new(),
+        };
+        hints.extend(composition_hints(documents));
+        hints.sort_by_key(|hint| hint.severity);
+        hints
+    }
+}
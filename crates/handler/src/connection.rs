@@ -0,0 +1,13 @@
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ConnectionConfig {
+    /// Fetch the nodes of Relay connection pages from the service resolving
+    /// them in concurrent batches of at most this many entities, 0 to fetch
+    /// every page at once.
+    #[clap(long = "connections-batch-size", env = "CONNECTIONS_BATCH_SIZE", default_value_t = 0)]
+    #[serde(default)]
+    pub batch_size: usize,
+}
@@ -1,10 +1,11 @@
+pub use graphgate_executor::constants::{KEY_QUERY, KEY_SERVICE, KEY_VARIABLES};
 use opentelemetry::Key;
 
-pub const KEY_SERVICE: Key = Key::from_static_str("graphgate.service");
-pub const KEY_QUERY: Key = Key::from_static_str("graphgate.query");
-pub const KEY_PATH: Key = Key::from_static_str("graphgate.path");
-pub const KEY_PARENT_TYPE: Key = Key::from_static_str("graphgate.parentType");
-pub const KEY_RETURN_TYPE: Key = Key::from_static_str("graphgate.returnType");
-pub const KEY_FIELD_NAME: Key = Key::from_static_str("graphgate.fieldName");
-pub const KEY_VARIABLES: Key = Key::from_static_str("graphgate.variables");
+pub const KEY_CIRCUIT_STATE: Key = Key::from_static_str("graphgate.circuit_state");
 pub const KEY_ERROR: Key = Key::from_static_str("graphgate.error");
+pub const KEY_FAULT: Key = Key::from_static_str("graphgate.fault");
+pub const KEY_HTTP_VERSION: Key = Key::from_static_str("graphgate.http_version");
+pub const KEY_OPERATION: Key = Key::from_static_str("graphgate.operation");
+pub const KEY_RETRIES: Key = Key::from_static_str("graphgate.retries");
+pub const KEY_RETRY_ATTEMPT: Key = Key::from_static_str("graphgate.retry_attempt");
+pub const KEY_SDL_HASH: Key = Key::from_static_str("graphgate.sdl_hash");
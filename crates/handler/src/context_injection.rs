@@ -0,0 +1,388 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use graphgate_schema::{ComposedSchema, MetaInputValue, MetaType, TypeExt, TypeKind};
+use http::HeaderMap;
+use parser::{
+    types::{
+        BaseType,
+        DocumentOperations,
+        ExecutableDocument,
+        OperationType,
+        Selection,
+        SelectionSet,
+        Type,
+        VariableDefinition,
+    },
+    Pos,
+    Positioned,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::{ConstValue, Name, Value, Variables};
+
+/// Injects a value derived from the incoming request into a field argument,
+/// so that subgraphs receive it as a regular variable.
+///
+/// Any value the client provides for the argument is replaced.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct ContextRule {
+    /// The field receiving the value, for example `Query.orders`.
+    pub field: String,
+
+    /// The name of the argument that receives the value.
+    pub argument: String,
+
+    #[serde(flatten)]
+    pub source: ContextSource,
+}
+
+/// Where the value of a [`ContextRule`] comes from.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextSource {
+    /// The value of a request header.
+    Header(String),
+
+    /// A claim of the verified JWT, nested claims are separated by `.`.
+    Claim(String),
+}
+
+/// The parts of an incoming request that context rules read from.
+#[derive(Debug, Default)]
+pub struct RequestContext {
+    pub headers: HeaderMap,
+    pub claims: Option<serde_json::Value>,
+    pub remote_addr: Option<SocketAddr>,
+    /// The services of the `allowedServices` extension, that the operation
+    /// is planned with if the extension is enabled and the request is
+    /// authenticated.
+    pub allowed_services: Option<Vec<String>>,
+}
+
+impl ContextRule {
+    /// Checks that the argument exists in the composed schema and can hold
+    /// the injected value.
+    pub fn validate<'a>(&self, schema: &'a ComposedSchema) -> Result<&'a MetaInputValue, String> {
+        let (type_name, field_name) = self
+            .field
+            .split_once('.')
+            .ok_or_else(|| format!("Invalid context field \"{}\", expected \"Type.field\".", self.field))?;
+        let argument = schema
+            .types
+            .get(type_name)
+            .and_then(|ty| ty.fields.get(field_name))
+            .ok_or_else(|| format!("Unknown context field \"{}\".", self.field))?
+            .arguments
+            .get(self.argument.as_str())
+            .ok_or_else(|| {
+                format!(
+                    "Unknown argument \"{}\" of context field \"{}\".",
+                    self.argument, self.field
+                )
+            })?;
+
+        let named_type = argument.ty.concrete_typename();
+        let is_scalar = matches!(&argument.ty.base, BaseType::Named(_)) &&
+            schema
+                .types
+                .get(named_type)
+                .map(|ty| ty.kind == TypeKind::Scalar)
+                .unwrap_or_default();
+        let accepts_source = match self.source {
+            ContextSource::Header(_) => !matches!(named_type, "Int" | "Float" | "Boolean"),
+            ContextSource::Claim(_) => true,
+        };
+        if !is_scalar || !accepts_source {
+            return Err(format!(
+                "Argument \"{}\" of context field \"{}\" has type \"{}\", which cannot hold a value from {}.",
+                self.argument,
+                self.field,
+                argument.ty,
+                match self.source {
+                    ContextSource::Header(_) => "a header",
+                    ContextSource::Claim(_) => "a claim",
+                }
+            ));
+        }
+
+        Ok(argument)
+    }
+
+    fn value(&self, context: &RequestContext) -> Option<ConstValue> {
+        match &self.source {
+            ContextSource::Header(name) => context
+                .headers
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .map(|value| ConstValue::String(value.to_string())),
+            ContextSource::Claim(path) => {
+                let claim = path
+                    .split('.')
+                    .try_fold(context.claims.as_ref()?, |value, key| value.get(key))?;
+                match claim {
+                    serde_json::Value::Null => None,
+                    claim => ConstValue::from_json(claim.clone()).ok(),
+                }
+            },
+        }
+    }
+
+    fn matches(&self, parent_type: &str, field_name: &str) -> bool {
+        self.field
+            .split_once('.')
+            .map(|(type_name, name)| type_name == parent_type && name == field_name)
+            .unwrap_or_default()
+    }
+}
+
+/// A context rule valid against the composed schema, with the type of the
+/// argument it injects into.
+pub(crate) struct ValidRule {
+    rule: ContextRule,
+    ty: Type,
+}
+
+/// The rules validated against a composed schema.
+struct ValidatedRules {
+    schema: Arc<ComposedSchema>,
+    valid: Arc<Vec<ValidRule>>,
+    invalid: Vec<ContextRule>,
+}
+
+/// The context rules, validated once against each composed schema.
+#[derive(Default)]
+pub(crate) struct ContextInjector {
+    rules: Vec<ContextRule>,
+    validated: Mutex<Option<ValidatedRules>>,
+}
+
+impl ContextInjector {
+    pub(crate) fn new(rules: Vec<ContextRule>) -> Self {
+        Self {
+            rules,
+            validated: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rules valid against `schema`, the invalid ones are logged and
+    /// dropped when a schema is first seen.
+    pub(crate) fn valid_rules(&self, schema: &Arc<ComposedSchema>) -> Arc<Vec<ValidRule>> {
+        let mut validated = self.validated.lock().unwrap();
+        if let Some(validated) = validated.as_ref() {
+            if Arc::ptr_eq(&validated.schema, schema) {
+                return validated.valid.clone();
+            }
+        }
+
+        let mut valid = Vec::new();
+        let mut invalid = Vec::new();
+        for rule in &self.rules {
+            match rule.validate(schema) {
+                Ok(argument) => valid.push(ValidRule {
+                    rule: rule.clone(),
+                    ty: argument.ty.clone(),
+                }),
+                Err(err) => {
+                    tracing::error!(error = %err, "Invalid context rule, it is ignored.");
+                    invalid.push(rule.clone());
+                },
+            }
+        }
+        let valid = Arc::new(valid);
+        *validated = Some(ValidatedRules {
+            schema: schema.clone(),
+            valid: valid.clone(),
+            invalid,
+        });
+        valid
+    }
+
+    /// The rules invalid against the last schema they were validated
+    /// against.
+    pub(crate) fn invalid_rules(&self) -> Vec<ContextRule> {
+        self.validated
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|validated| validated.invalid.clone())
+            .unwrap_or_default()
+    }
+
+    /// Apply the context rules to a query document, adding a variable for
+    /// every injected value.
+    pub(crate) fn inject(
+        &self,
+        schema: &Arc<ComposedSchema>,
+        document: &mut ExecutableDocument,
+        variables: &mut Variables,
+        context: &RequestContext,
+    ) -> Result<(), String> {
+        let rules = self.valid_rules(schema);
+        inject_context(&rules, schema, document, variables, context)
+    }
+}
+
+fn variable_name(idx: usize) -> Name {
+    Name::new(format!("__context{}", idx))
+}
+
+struct Injector<'a> {
+    schema: &'a ComposedSchema,
+    rules: &'a [ValidRule],
+    values: &'a [Option<ConstValue>],
+}
+
+impl Injector<'_> {
+    fn inject(
+        &self,
+        parent_type: &MetaType,
+        selection_set: &mut SelectionSet,
+        used: &mut BTreeSet<usize>,
+        spreads: &mut HashSet<Name>,
+    ) {
+        for selection in &mut selection_set.items {
+            match &mut selection.node {
+                Selection::Field(field) => {
+                    let field = &mut field.node;
+                    for (idx, rule) in self.rules.iter().enumerate() {
+                        let rule = &rule.rule;
+                        if !rule.matches(&parent_type.name, &field.name.node) {
+                            continue;
+                        }
+                        field.arguments.retain(|(name, _)| name.node != rule.argument.as_str());
+                        if self.values[idx].is_some() {
+                            field.arguments.push((
+                                Positioned::new(Name::new(&rule.argument), field.name.pos),
+                                Positioned::new(Value::Variable(variable_name(idx)), field.name.pos),
+                            ));
+                        }
+                        used.insert(idx);
+                    }
+
+                    let field_type = parent_type
+                        .fields
+                        .get(field.name.node.as_str())
+                        .and_then(|field| self.schema.concrete_type_by_name(&field.ty));
+                    if let Some(field_type) = field_type {
+                        self.inject(field_type, &mut field.selection_set.node, used, spreads);
+                    }
+                },
+                Selection::InlineFragment(fragment) => {
+                    let fragment = &mut fragment.node;
+                    let fragment_type = match &fragment.type_condition {
+                        Some(type_condition) => self.schema.types.get(type_condition.node.on.node.as_str()),
+                        None => Some(parent_type),
+                    };
+                    if let Some(fragment_type) = fragment_type {
+                        self.inject(fragment_type, &mut fragment.selection_set.node, used, spreads);
+                    }
+                },
+                Selection::FragmentSpread(spread) => {
+                    spreads.insert(spread.node.fragment_name.node.clone());
+                },
+            }
+        }
+    }
+}
+
+fn inject_context(
+    rules: &[ValidRule],
+    schema: &ComposedSchema,
+    document: &mut ExecutableDocument,
+    variables: &mut Variables,
+    context: &RequestContext,
+) -> Result<(), String> {
+    let values: Vec<_> = rules.iter().map(|rule| rule.rule.value(context)).collect();
+    let injector = Injector {
+        schema,
+        rules,
+        values: &values,
+    };
+
+    // Rules used and fragments spread directly by each fragment.
+    let mut fragment_usages = HashMap::new();
+    for (name, fragment) in &mut document.fragments {
+        let mut used = BTreeSet::new();
+        let mut spreads = HashSet::new();
+        if let Some(ty) = schema.types.get(fragment.node.type_condition.node.on.node.as_str()) {
+            injector.inject(ty, &mut fragment.node.selection_set.node, &mut used, &mut spreads);
+        }
+        fragment_usages.insert(name.clone(), (used, spreads));
+    }
+
+    let mut all_used = BTreeSet::new();
+    let operations: Vec<_> = match &mut document.operations {
+        DocumentOperations::Single(operation) => vec![operation],
+        DocumentOperations::Multiple(operations) => operations.values_mut().collect(),
+    };
+    for operation in operations {
+        let root_type = match operation.node.ty {
+            OperationType::Query => Some(schema.query_type()),
+            OperationType::Mutation => schema.mutation_type(),
+            OperationType::Subscription => schema.subscription_type(),
+        };
+        let root_type = match root_type.and_then(|name| schema.types.get(name)) {
+            Some(root_type) => root_type,
+            None => continue,
+        };
+
+        let mut used = BTreeSet::new();
+        let mut spreads = HashSet::new();
+        injector.inject(
+            root_type,
+            &mut operation.node.selection_set.node,
+            &mut used,
+            &mut spreads,
+        );
+
+        let mut visited = HashSet::new();
+        let mut pending = spreads.into_iter().collect::<Vec<_>>();
+        while let Some(name) = pending.pop() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+            if let Some((fragment_used, fragment_spreads)) = fragment_usages.get(&name) {
+                used.extend(fragment_used.iter().copied());
+                pending.extend(fragment_spreads.iter().cloned());
+            }
+        }
+
+        for idx in used.iter().filter(|idx| values[**idx].is_some()) {
+            operation.node.variable_definitions.push(Positioned::new(
+                VariableDefinition {
+                    name: Positioned::new(variable_name(*idx), Pos::default()),
+                    var_type: Positioned::new(rules[*idx].ty.clone(), Pos::default()),
+                    directives: Vec::new(),
+                    default_value: None,
+                },
+                Pos::default(),
+            ));
+        }
+        all_used.extend(used);
+    }
+
+    for (idx, value) in values.into_iter().enumerate() {
+        match value {
+            Some(value) if all_used.contains(&idx) => {
+                variables.insert(variable_name(idx), value);
+            },
+            None if all_used.contains(&idx) && !rules[idx].ty.nullable => {
+                return Err(format!(
+                    "Missing context value for argument \"{}\" of field \"{}\".",
+                    rules[idx].rule.argument, rules[idx].rule.field
+                ));
+            },
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use clap::Args;
+use http::{
+    header::{ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN},
+    HeaderMap,
+    Method,
+    Request,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use warp::{
+    cors::Cors,
+    filters::BoxedFilter,
+    hyper::{service::Service, Body},
+    reply::Response,
+    Filter,
+    Rejection,
+    Reply,
+};
+
+use crate::shared_config::SharedConfig;
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct CorsConfig {
+    #[clap(long, env = "CORS_ALLOW_METHODS", value_delimiter = ',')]
+    pub allow_methods: Option<Vec<String>>,
+
+    #[clap(long, env = "CORS_ALLOW_CREDENTIALS")]
+    pub allow_credentials: Option<bool>,
+
+    #[clap(long, env = "CORS_ALLOW_HEADERS", value_delimiter = ',')]
+    pub allow_headers: Option<Vec<String>>,
+
+    #[clap(long, env = "CORS_ALLOW_ORIGINS", value_delimiter = ',')]
+    pub allow_origins: Option<Vec<String>>,
+}
+
+/// The CORS policy of the settings, answering preflight requests and
+/// forbidding the requests of other origins, or allowing any origin to
+/// `POST` if unset.
+pub fn cors_filter(config: Option<&CorsConfig>) -> Cors {
+    let config = match config {
+        Some(config) => config,
+        None => {
+            return warp::cors()
+                .allow_any_origin()
+                .allow_methods(vec!["POST", "OPTIONS"])
+                .build()
+        },
+    };
+    warp::cors()
+        .allow_methods(
+            config
+                .allow_methods
+                .clone()
+                .unwrap_or_else(|| vec!["POST".to_string(), "OPTIONS".to_string()])
+                .iter()
+                .map(String::as_str),
+        )
+        .allow_credentials(config.allow_credentials.unwrap_or_default())
+        .allow_headers(config.allow_headers.clone().unwrap_or_default())
+        .allow_origins(config.allow_origins.iter().flatten().map(String::as_str))
+        .build()
+}
+
+/// The [`cors_filter`] of a [`CorsConfig`], applied to the requests by
+/// [`with_cors`] so that it is replaced with the settings of the gateway.
+#[derive(Clone)]
+pub struct CorsPolicy {
+    filter: BoxedFilter<(Response,)>,
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// How the CORS policy answers a request.
+enum CorsOutcome {
+    /// The response to a preflight request, or to a forbidden request.
+    Respond(Response),
+    /// The headers added to the response of an allowed request.
+    Allow(HeaderMap),
+}
+
+impl CorsPolicy {
+    pub fn new(config: Option<&CorsConfig>) -> Self {
+        Self {
+            filter: warp::any()
+                .map(Response::default)
+                .with(cors_filter(config))
+                .map(Reply::into_response)
+                .boxed(),
+        }
+    }
+
+    /// Check a request against the policy, with the CORS headers of the
+    /// request only.
+    async fn check(&self, method: Method, headers: &HeaderMap) -> CorsOutcome {
+        let mut request = Request::builder().method(method).uri("/");
+        for name in [ORIGIN, ACCESS_CONTROL_REQUEST_METHOD, ACCESS_CONTROL_REQUEST_HEADERS] {
+            for value in headers.get_all(&name) {
+                request = request.header(&name, value);
+            }
+        }
+        let is_preflight = request.method_ref() == Some(&Method::OPTIONS) &&
+            headers.contains_key(ORIGIN) &&
+            headers.contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+        let request = request.body(Body::empty()).unwrap();
+        // The filter runs in its own task, warp routes cannot be nested in the
+        // route of the request.
+        let mut service = warp::service(self.filter.clone());
+        let response = match tokio::spawn(async move { service.call(request).await }).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(err)) => match err {},
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        };
+        match is_preflight || !response.status().is_success() {
+            true => CorsOutcome::Respond(response),
+            false => CorsOutcome::Allow(response.headers().clone()),
+        }
+    }
+}
+
+/// Apply the CORS policy of the current settings to the requests of the
+/// routes.
+pub fn with_cors<F, R>(
+    shared_config: SharedConfig,
+    routes: F,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    let check = warp::method()
+        .and(warp::header::headers_cloned())
+        .then(move |method, headers: HeaderMap| {
+            let cors = shared_config.load().cors.clone();
+            async move { cors.check(method, &headers).await }
+        });
+    let respond = check.clone().and_then(|outcome| async move {
+        match outcome {
+            CorsOutcome::Respond(response) => Ok(response),
+            CorsOutcome::Allow(_) => Err(warp::reject()),
+        }
+    });
+    let allow = check
+        .and_then(|outcome| async move {
+            match outcome {
+                CorsOutcome::Allow(headers) => Ok(headers),
+                CorsOutcome::Respond(_) => Err(warp::reject()),
+            }
+        })
+        .and(routes)
+        .map(|headers: HeaderMap, reply: R| {
+            let mut response = reply.into_response();
+            response.headers_mut().extend(headers);
+            response
+        });
+    respond.or(allow).unify()
+}
@@ -0,0 +1,401 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::Args;
+use graphgate_schema::{ComposedSchema, MetaField, MetaType};
+use indexmap::IndexMap;
+use parser::types::{
+    BaseType,
+    DocumentOperations,
+    ExecutableDocument,
+    Field,
+    OperationDefinition,
+    OperationType,
+    Selection,
+    SelectionSet,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::{ConstValue, Name, Value, Variables};
+
+use crate::context_injection::RequestContext;
+
+/// The error code of operations whose estimated cost exceeds the limit.
+pub const COST_LIMIT_EXCEEDED: &str = "COST_LIMIT_EXCEEDED";
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct CostConfig {
+    /// The highest estimated cost of an operation that is executed.
+    #[clap(long = "cost-max", env = "COST_MAX", default_value_t = 1000)]
+    #[serde(default = "default_max_cost")]
+    pub max_cost: u64,
+
+    /// The size assumed for list fields without `@listSize`.
+    #[clap(
+        long = "cost-default-list-size",
+        env = "COST_DEFAULT_LIST_SIZE",
+        default_value_t = 10
+    )]
+    #[serde(default = "default_list_size")]
+    pub default_list_size: u64,
+
+    /// The header naming the client, matched by the `client_name` of
+    /// budgets. Any client can send it, so it is only trusted behind a proxy
+    /// that authenticates the clients and sets it, see `client_name_claim`
+    /// otherwise.
+    #[clap(
+        long = "cost-client-name-header",
+        env = "COST_CLIENT_NAME_HEADER",
+        default_value = "apollographql-client-name"
+    )]
+    #[serde(default = "default_client_name_header")]
+    pub client_name_header: String,
+
+    /// The claim of the verified JWT naming the client, matched by the
+    /// `client_name` of budgets instead of the client name header.
+    #[clap(long = "cost-client-name-claim", env = "COST_CLIENT_NAME_CLAIM")]
+    #[serde(default)]
+    pub client_name_claim: Option<String>,
+
+    /// The header holding the API key, matched by the `api_keys` of budgets.
+    #[clap(
+        long = "cost-api-key-header",
+        env = "COST_API_KEY_HEADER",
+        default_value = "x-api-key"
+    )]
+    #[serde(default = "default_api_key_header")]
+    pub api_key_header: String,
+
+    /// Limits replacing `max_cost` for some clients, the first matching
+    /// budget applies.
+    #[clap(skip)]
+    #[serde(default)]
+    pub budgets: Vec<CostBudget>,
+
+    /// The weights of fields by `Type.field`, replacing their `@cost`.
+    #[clap(skip)]
+    #[serde(default)]
+    pub field_weights: HashMap<String, u64>,
+}
+
+impl Default for CostConfig {
+    fn default() -> Self {
+        Self {
+            max_cost: default_max_cost(),
+            default_list_size: default_list_size(),
+            client_name_header: default_client_name_header(),
+            client_name_claim: None,
+            api_key_header: default_api_key_header(),
+            budgets: Vec::new(),
+            field_weights: HashMap::new(),
+        }
+    }
+}
+
+impl CostConfig {
+    /// The cost limit of a request.
+    pub(crate) fn max_cost_for(&self, context: &RequestContext) -> u64 {
+        self.budgets
+            .iter()
+            .find(|budget| budget.matches(self, context))
+            .map(|budget| budget.max_cost)
+            .unwrap_or(self.max_cost)
+    }
+}
+
+/// The cost limit of the requests matching every criterion that is set.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct CostBudget {
+    /// The name of the client, from the `client_name_claim` of the verified
+    /// JWT if set, or else from the client name header.
+    #[serde(default)]
+    pub client_name: Option<String>,
+
+    /// A scope of the verified JWT, from its `scope` or `scp` claim.
+    #[serde(default)]
+    pub scope: Option<String>,
+
+    /// The API keys of a tier.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+
+    pub max_cost: u64,
+}
+
+impl CostBudget {
+    fn matches(&self, config: &CostConfig, context: &RequestContext) -> bool {
+        let header = |name: &str| context.headers.get(name).and_then(|value| value.to_str().ok());
+
+        if let Some(client_name) = &self.client_name {
+            let name = match &config.client_name_claim {
+                Some(claim) => context
+                    .claims
+                    .as_ref()
+                    .and_then(|claims| claims.get(claim))
+                    .and_then(|name| name.as_str()),
+                None => header(&config.client_name_header),
+            };
+            if name != Some(client_name.as_str()) {
+                return false;
+            }
+        }
+        if let Some(scope) = &self.scope {
+            if !has_scope(context.claims.as_ref(), scope) {
+                return false;
+            }
+        }
+        if !self.api_keys.is_empty() {
+            match header(&config.api_key_header) {
+                Some(api_key) if self.api_keys.iter().any(|key| key == api_key) => {},
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Whether the claims grant a scope, either in a space separated `scope`
+/// claim or in a `scp` claim holding a list or a string.
+pub(crate) fn has_scope(claims: Option<&serde_json::Value>, scope: &str) -> bool {
+    let claims = match claims {
+        Some(claims) => claims,
+        None => return false,
+    };
+    ["scope", "scp"].iter().any(|name| match claims.get(name) {
+        Some(serde_json::Value::String(scopes)) => scopes.split(' ').any(|s| s == scope),
+        Some(serde_json::Value::Array(scopes)) => scopes.iter().any(|s| s.as_str() == Some(scope)),
+        _ => false,
+    })
+}
+
+/// The `cost` response extension.
+pub(crate) fn cost_extension(estimated: u64, max_cost: u64) -> ConstValue {
+    let mut extension = IndexMap::new();
+    extension.insert(Name::new("estimated"), ConstValue::Number(estimated.into()));
+    extension.insert(Name::new("limit"), ConstValue::Number(max_cost.into()));
+    extension.insert(
+        Name::new("remaining"),
+        ConstValue::Number(max_cost.saturating_sub(estimated).into()),
+    );
+    ConstValue::Object(extension)
+}
+
+struct Estimator<'a> {
+    config: &'a CostConfig,
+    schema: &'a ComposedSchema,
+    document: &'a ExecutableDocument,
+    variables: &'a Variables,
+    /// The fragments being estimated, to skip cyclic spreads.
+    fragments: HashSet<&'a str>,
+    /// The cost of each fragment estimated, by the sizes of its fields, so
+    /// that the fragments spread repeatedly are estimated once.
+    costs: HashMap<(&'a str, Vec<Name>, u64), u64>,
+}
+
+impl<'a> Estimator<'a> {
+    /// The cost of a selection set, whose fields named in `sized_fields`
+    /// return `size` items each.
+    fn selection_set(
+        &mut self,
+        parent_type: &MetaType,
+        selection_set: &'a SelectionSet,
+        sized_fields: &[Name],
+        size: u64,
+    ) -> Result<u64, String> {
+        let mut cost = 0u64;
+        for selection in &selection_set.items {
+            let selection_cost = match &selection.node {
+                Selection::Field(field) => {
+                    let field = &field.node;
+                    match parent_type.fields.get(field.name.node.as_str()) {
+                        Some(field_definition) => {
+                            let cost = self.field(parent_type, field_definition, field)?;
+                            match sized_fields.contains(&field.name.node) {
+                                true => cost.saturating_mul(size),
+                                false => cost,
+                            }
+                        },
+                        None => 0,
+                    }
+                },
+                Selection::InlineFragment(fragment) => {
+                    let fragment = &fragment.node;
+                    let fragment_type = match &fragment.type_condition {
+                        Some(type_condition) => self.schema.types.get(type_condition.node.on.node.as_str()),
+                        None => Some(parent_type),
+                    };
+                    match fragment_type {
+                        Some(fragment_type) => {
+                            self.selection_set(fragment_type, &fragment.selection_set.node, sized_fields, size)?
+                        },
+                        None => 0,
+                    }
+                },
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.node.fragment_name.node.as_str();
+                    let key = (name, sized_fields.to_vec(), size);
+                    if let Some(fragment_cost) = self.costs.get(&key) {
+                        cost = cost.saturating_add(*fragment_cost);
+                        continue;
+                    }
+                    let fragment = match self.document.fragments.get(name) {
+                        // Cyclic spreads are rejected by validation.
+                        Some(fragment) if self.fragments.insert(name) => fragment,
+                        _ => continue,
+                    };
+                    let cost = match self
+                        .schema
+                        .types
+                        .get(fragment.node.type_condition.node.on.node.as_str())
+                    {
+                        Some(fragment_type) => {
+                            self.selection_set(fragment_type, &fragment.node.selection_set.node, sized_fields, size)?
+                        },
+                        None => 0,
+                    };
+                    self.fragments.remove(name);
+                    self.costs.insert(key, cost);
+                    cost
+                },
+            };
+            cost = cost.saturating_add(selection_cost);
+        }
+        Ok(cost)
+    }
+
+    fn field(&mut self, parent_type: &MetaType, field_definition: &MetaField, field: &'a Field) -> Result<u64, String> {
+        let field_type = self.schema.concrete_type_by_name(&field_definition.ty);
+
+        // Scalars and enums are free unless weighted, composite types cost 1.
+        let configured_weight = match self.config.field_weights.is_empty() {
+            true => None,
+            false => self
+                .config
+                .field_weights
+                .get(&format!("{}.{}", parent_type.name, field.name.node))
+                .copied(),
+        };
+        let weight = configured_weight
+            .or(field_definition.cost)
+            .or_else(|| field_type.and_then(|ty| ty.cost))
+            .unwrap_or_else(|| match field_type {
+                Some(ty) if ty.is_composite() => 1,
+                _ => 0,
+            });
+        let arguments_weight = field
+            .arguments
+            .iter()
+            .filter_map(|(name, _)| field_definition.arguments.get(name.node.as_str()))
+            .filter_map(|argument| argument.cost)
+            .fold(0u64, u64::saturating_add);
+
+        let list_size = field_definition.list_size.as_ref();
+        let size = match list_size {
+            Some(list_size) if !list_size.slicing_arguments.is_empty() => {
+                let sizes = list_size
+                    .slicing_arguments
+                    .iter()
+                    .filter_map(|name| self.argument_u64(field, name))
+                    .collect::<Vec<_>>();
+                if list_size.require_one_slicing_argument && sizes.len() != 1 {
+                    return Err(format!(
+                        "Exactly one of the arguments {} must be provided to \"{}.{}\".",
+                        list_size
+                            .slicing_arguments
+                            .iter()
+                            .map(|name| format!("\"{}\"", name))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        parent_type.name,
+                        field.name.node
+                    ));
+                }
+                sizes
+                    .into_iter()
+                    .max()
+                    .or(list_size.assumed_size)
+                    .unwrap_or(self.config.default_list_size)
+            },
+            Some(list_size) => list_size.assumed_size.unwrap_or(self.config.default_list_size),
+            None => self.config.default_list_size,
+        };
+
+        let (sized_fields, field_size, child_size) = match list_size {
+            // The size applies to the fields of the returned connection.
+            Some(list_size) if !list_size.sized_fields.is_empty() => (list_size.sized_fields.as_slice(), 1, size),
+            _ if matches!(field_definition.ty.base, BaseType::List(_)) => (&[][..], size, 1),
+            _ => (&[][..], 1, 1),
+        };
+        let children = match field_type {
+            Some(field_type) => self.selection_set(field_type, &field.selection_set.node, sized_fields, child_size)?,
+            None => 0,
+        };
+        Ok(weight
+            .saturating_add(children)
+            .saturating_mul(field_size)
+            .saturating_add(arguments_weight))
+    }
+
+    fn argument_u64(&self, field: &Field, name: &str) -> Option<u64> {
+        match &field.get_argument(name)?.node {
+            Value::Number(number) => number.as_u64(),
+            Value::Variable(variable) => match self.variables.get(variable) {
+                Some(ConstValue::Number(number)) => number.as_u64(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Estimate the cost of an operation from the `@cost` and `@listSize`
+/// directives of the subgraphs, returning an error if the arguments
+/// required by `@listSize` are missing.
+///
+/// Returns `None` if the operation does not exist, which the planner
+/// reports.
+pub(crate) fn estimate_cost(
+    config: &CostConfig,
+    schema: &ComposedSchema,
+    document: &ExecutableDocument,
+    operation_name: Option<&str>,
+    variables: &Variables,
+) -> Option<Result<u64, String>> {
+    let operation: &OperationDefinition = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), None) => &operation.node,
+        (DocumentOperations::Multiple(operations), Some(name)) => &operations.get(name)?.node,
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => &operations.values().next()?.node,
+        _ => return None,
+    };
+    let root_type = match operation.ty {
+        OperationType::Query => Some(schema.query_type()),
+        OperationType::Mutation => schema.mutation_type(),
+        OperationType::Subscription => schema.subscription_type(),
+    }
+    .and_then(|name| schema.types.get(name))?;
+
+    let mut estimator = Estimator {
+        config,
+        schema,
+        document,
+        variables,
+        fragments: HashSet::new(),
+        costs: HashMap::new(),
+    };
+    Some(estimator.selection_set(root_type, &operation.selection_set.node, &[], 1))
+}
+
+fn default_max_cost() -> u64 {
+    1000
+}
+
+fn default_list_size() -> u64 {
+    10
+}
+
+fn default_client_name_header() -> String {
+    "apollographql-client-name".to_string()
+}
+
+fn default_api_key_header() -> String {
+    "x-api-key".to_string()
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, Utc};
+use clap::Args;
+use graphgate_planner::ServerError;
+use graphgate_schema::ComposedSchema;
+use graphgate_validation::check_deprecation_sunsets;
+use parser::types::ExecutableDocument;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::{ConstValue, Variables};
+
+/// The error code of operations selecting deprecated fields past their
+/// sunset date.
+pub const DEPRECATED_FIELD_SUNSET: &str = "DEPRECATED_FIELD_SUNSET";
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct DeprecationConfig {
+    /// How many days after the sunset date of a field operations selecting
+    /// it are answered with a warning, before being rejected.
+    #[clap(
+        long = "deprecation-grace-period-days",
+        env = "DEPRECATION_GRACE_PERIOD_DAYS",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub grace_period_days: u32,
+
+    /// The sunset dates of deprecated fields, by coordinate such as
+    /// `User.name`.
+    #[clap(skip)]
+    #[serde(default)]
+    pub sunsets: HashMap<String, NaiveDate>,
+}
+
+/// Check the deprecated fields of an operation against their sunset dates,
+/// returning the warnings of the fields in their grace period, or the errors
+/// of those past it.
+pub(crate) fn check_sunsets(
+    config: &DeprecationConfig,
+    schema: &ComposedSchema,
+    document: &ExecutableDocument,
+    variables: &Variables,
+) -> Result<Vec<String>, Vec<ServerError>> {
+    let (errors, warnings) = check_deprecation_sunsets(
+        schema,
+        document,
+        variables,
+        &config.sunsets,
+        Duration::days(config.grace_period_days.into()),
+        Utc::now().date_naive(),
+    );
+    if errors.is_empty() {
+        for warning in &warnings {
+            tracing::warn!(warning = %warning.message, "Sunset field selected.");
+        }
+        return Ok(warnings.into_iter().map(|warning| warning.message).collect());
+    }
+
+    Err(errors
+        .into_iter()
+        .map(|err| {
+            let mut error = ServerError::new(err.message);
+            error.locations = err.locations;
+            error.extensions.insert(
+                "code".to_string(),
+                ConstValue::String(DEPRECATED_FIELD_SUNSET.to_string()),
+            );
+            error
+        })
+        .collect())
+}
@@ -0,0 +1,334 @@
+use std::fmt::Write;
+
+use clap::Args;
+use graphgate_schema::{ComposedSchema, Deprecation, KeyFields, MetaInputValue, MetaType, TypeExt, TypeKind};
+use indexmap::IndexMap;
+use parser::types::Type;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::Name;
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct DocsConfig {
+    /// Serve an HTML reference of the composed schema at `/docs`.
+    #[clap(
+        id = "docs_enabled",
+        long = "docs-enabled",
+        env = "DOCS_ENABLED",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Only serve the reference to requests with a verified JWT.
+    #[clap(
+        id = "docs_require_auth",
+        long = "docs-require-auth",
+        env = "DOCS_REQUIRE_AUTH",
+        default_value_t = true
+    )]
+    #[serde(default = "default_require_auth")]
+    pub require_auth: bool,
+}
+
+impl Default for DocsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            require_auth: default_require_auth(),
+        }
+    }
+}
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 0; display: flex; }
+nav { width: 260px; height: 100vh; overflow-y: auto; position: sticky; top: 0; padding: 16px; box-sizing: border-box; border-right: 1px solid #ddd; }
+nav input { width: 100%; margin-bottom: 12px; }
+nav a { display: block; color: #333; text-decoration: none; padding: 2px 0; }
+main { flex: 1; padding: 16px 32px; }
+section { border-bottom: 1px solid #eee; padding-bottom: 16px; }
+table { border-collapse: collapse; width: 100%; }
+td { vertical-align: top; padding: 4px 8px; border-top: 1px solid #f0f0f0; }
+code { font-family: monospace; }
+.kind, .service { color: #777; font-size: 0.9em; }
+.deprecated { color: #b00; }
+"#;
+
+const SCRIPT: &str = r#"
+document.getElementById("search").addEventListener("input", function (event) {
+  var term = event.target.value.toLowerCase();
+  document.querySelectorAll("[data-search]").forEach(function (element) {
+    element.hidden = element.getAttribute("data-search").indexOf(term) < 0;
+  });
+});
+"#;
+
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+fn kind_name(kind: TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Scalar => "scalar",
+        TypeKind::Object => "type",
+        TypeKind::Interface => "interface",
+        TypeKind::Union => "union",
+        TypeKind::Enum => "enum",
+        TypeKind::InputObject => "input",
+    }
+}
+
+fn format_key_fields(key_fields: &KeyFields) -> String {
+    key_fields
+        .iter()
+        .map(|(name, children)| match children.is_empty() {
+            true => name.to_string(),
+            false => format!("{} {{ {} }}", name, format_key_fields(children)),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether a type is documented, which excludes introspection and
+/// federation types.
+fn is_documented(ty: &MetaType) -> bool {
+    !ty.name.starts_with('_')
+}
+
+/// The lowercase names a type is found by, its own and those of its fields.
+fn search_terms(ty: &MetaType) -> String {
+    std::iter::once(&ty.name)
+        .chain(ty.fields.keys())
+        .chain(ty.enum_values.keys())
+        .chain(ty.input_fields.keys())
+        .map(|name| name.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+struct Renderer<'a> {
+    schema: &'a ComposedSchema,
+    html: String,
+}
+
+impl Renderer<'_> {
+    fn type_link(&self, ty: &Type) -> String {
+        let name = ty.concrete_typename();
+        let ty = escape(&ty.to_string());
+        match self.schema.types.get(name).filter(|ty| is_documented(ty)) {
+            Some(_) => {
+                let name = escape(name);
+                format!("<a href=\"#{name}\"><code>{ty}</code></a>")
+            },
+            None => format!("<code>{ty}</code>"),
+        }
+    }
+
+    fn description(&mut self, description: Option<&str>) {
+        if let Some(description) = description {
+            write!(self.html, "<p>{}</p>", escape(description)).unwrap();
+        }
+    }
+
+    fn deprecation(&mut self, deprecation: &Deprecation) {
+        if deprecation.is_deprecated() {
+            write!(
+                self.html,
+                "<p class=\"deprecated\">Deprecated{}</p>",
+                deprecation
+                    .reason()
+                    .map(|reason| format!(": {}", escape(reason)))
+                    .unwrap_or_default()
+            )
+            .unwrap();
+        }
+    }
+
+    fn arguments(&self, arguments: &IndexMap<Name, MetaInputValue>) -> String {
+        if arguments.is_empty() {
+            return String::new();
+        }
+        let arguments = arguments
+            .values()
+            .map(|argument| {
+                let mut res = format!("{}: {}", escape(&argument.name), self.type_link(&argument.ty));
+                if let Some(default_value) = &argument.default_value {
+                    write!(res, " = <code>{}</code>", escape(&default_value.to_string())).unwrap();
+                }
+                res
+            })
+            .collect::<Vec<_>>();
+        format!("({})", arguments.join(", "))
+    }
+
+    fn input_values(&mut self, input_values: &IndexMap<Name, MetaInputValue>) {
+        self.html.push_str("<table>");
+        for input_value in input_values.values() {
+            write!(
+                self.html,
+                "<tr><td><code>{}</code>: {}</td><td>",
+                escape(&input_value.name),
+                self.type_link(&input_value.ty)
+            )
+            .unwrap();
+            self.description(input_value.description.as_deref());
+            self.deprecation(&input_value.deprecation);
+            self.html.push_str("</td></tr>");
+        }
+        self.html.push_str("</table>");
+    }
+
+    fn render_type(&mut self, ty: &MetaType) {
+        let name = escape(&ty.name);
+        write!(
+            self.html,
+            "<section id=\"{}\" data-search=\"{}\"><h2><span class=\"kind\">{}</span> {}</h2>",
+            name,
+            escape(&search_terms(ty)),
+            kind_name(ty.kind),
+            name
+        )
+        .unwrap();
+
+        if let Some(owner) = &ty.owner {
+            write!(
+                self.html,
+                "<p class=\"service\">Owned by <code>{}</code></p>",
+                escape(owner)
+            )
+            .unwrap();
+        }
+        let mut keys = ty.keys.iter().collect::<Vec<_>>();
+        keys.sort_by_key(|(service, _)| *service);
+        for (service, keys) in keys {
+            for key in keys {
+                write!(
+                    self.html,
+                    "<p class=\"service\">Key <code>{}</code> in <code>{}</code></p>",
+                    escape(&format_key_fields(key)),
+                    escape(service)
+                )
+                .unwrap();
+            }
+        }
+        if !ty.implements.is_empty() {
+            let implements = ty
+                .implements
+                .iter()
+                .map(|name| format!("<a href=\"#{0}\"><code>{0}</code></a>", escape(name)))
+                .collect::<Vec<_>>();
+            write!(self.html, "<p>Implements {}</p>", implements.join(", ")).unwrap();
+        }
+        self.description(ty.description.as_deref());
+
+        match ty.kind {
+            TypeKind::Object | TypeKind::Interface => {
+                self.html.push_str("<table>");
+                for field in ty.fields.values().filter(|field| !field.name.starts_with("__")) {
+                    write!(
+                        self.html,
+                        "<tr><td><code>{}</code>{}: {}</td><td>",
+                        escape(&field.name),
+                        self.arguments(&field.arguments),
+                        self.type_link(&field.ty)
+                    )
+                    .unwrap();
+                    self.description(field.description.as_deref());
+                    self.deprecation(&field.deprecation);
+                    self.html.push_str("</td><td class=\"service\">");
+                    if let Some(service) = field.service.as_ref().or(ty.owner.as_ref()) {
+                        write!(self.html, "<code>{}</code>", escape(service)).unwrap();
+                    }
+                    self.html.push_str("</td></tr>");
+                }
+                self.html.push_str("</table>");
+            },
+            TypeKind::Union => {
+                let possible_types = ty
+                    .possible_types
+                    .iter()
+                    .map(|name| format!("<a href=\"#{0}\"><code>{0}</code></a>", escape(name)))
+                    .collect::<Vec<_>>();
+                write!(self.html, "<p>{}</p>", possible_types.join(" | ")).unwrap();
+            },
+            TypeKind::Enum => {
+                self.html.push_str("<table>");
+                for enum_value in ty.enum_values.values() {
+                    write!(self.html, "<tr><td><code>{}</code></td><td>", escape(&enum_value.value)).unwrap();
+                    self.description(enum_value.description.as_deref());
+                    self.deprecation(&enum_value.deprecation);
+                    self.html.push_str("</td></tr>");
+                }
+                self.html.push_str("</table>");
+            },
+            TypeKind::InputObject => self.input_values(&ty.input_fields),
+            TypeKind::Scalar => {},
+        }
+
+        self.html.push_str("</section>");
+    }
+}
+
+/// Render a searchable HTML reference of the composed schema.
+///
+/// Root types come first, followed by the other types in alphabetical
+/// order.
+pub(crate) fn render_docs(schema: &ComposedSchema) -> String {
+    let root_types = [
+        Some(schema.query_type()),
+        schema.mutation_type(),
+        schema.subscription_type(),
+    ];
+    let mut types = schema.types.values().filter(|ty| is_documented(ty)).collect::<Vec<_>>();
+    types.sort_by_key(|ty| {
+        let root_idx = root_types.iter().position(|name| *name == Some(ty.name.as_str()));
+        (root_idx.unwrap_or(root_types.len()), ty.name.as_str())
+    });
+
+    let mut renderer = Renderer {
+        schema,
+        html: String::new(),
+    };
+    write!(
+        renderer.html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Schema \
+         reference</title><style>{STYLE}</style></head><body>"
+    )
+    .unwrap();
+
+    renderer
+        .html
+        .push_str("<nav><input id=\"search\" type=\"search\" placeholder=\"Search types\">");
+    for ty in &types {
+        write!(
+            renderer.html,
+            "<a href=\"#{0}\" data-search=\"{1}\">{0}</a>",
+            escape(&ty.name),
+            escape(&search_terms(ty))
+        )
+        .unwrap();
+    }
+    renderer.html.push_str("</nav><main><h1>Schema reference</h1>");
+
+    for ty in types {
+        renderer.render_type(ty);
+    }
+
+    write!(renderer.html, "</main><script>{SCRIPT}</script></body></html>").unwrap();
+    renderer.html
+}
+
+fn default_require_auth() -> bool {
+    true
+}
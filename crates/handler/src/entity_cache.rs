@@ -0,0 +1,704 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use graphgate_planner::{Request, Response};
+use graphgate_schema::{CacheControl, CacheScope, ComposedSchema, MetaType};
+use http::{
+    header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION},
+    HeaderMap,
+};
+use indexmap::IndexMap;
+use parser::types::{DocumentOperations, Selection, SelectionSet};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use value::{ConstValue, Name};
+
+use crate::cache_stats::{hottest, CacheStats, HitCounter};
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct EntityCacheConfig {
+    /// The number of entities kept in memory, the least recently used are
+    /// evicted first.
+    #[clap(
+        id = "entity_cache_size",
+        long = "entity-cache-size",
+        env = "ENTITY_CACHE_SIZE",
+        default_value_t = 10000
+    )]
+    #[serde(default = "default_cache_size")]
+    pub cache_size: usize,
+
+    /// Store the entities in the Redis server at this URL instead, such as
+    /// `redis://127.0.0.1:6379`, so that every gateway replica shares them.
+    /// Requires the `redis` feature.
+    #[clap(
+        id = "entity_cache_redis_url",
+        long = "entity-cache-redis-url",
+        env = "ENTITY_CACHE_REDIS_URL"
+    )]
+    #[serde(default)]
+    pub redis_url: Option<String>,
+
+    /// Key the `PRIVATE` entities by the values of these claims of the
+    /// verified token, with dot paths such as `org.id`, instead of by the
+    /// `Authorization` header. The anonymous requests share the `public`
+    /// partition, and the tokens without one of the claims are not cached.
+    #[clap(
+        id = "entity_cache_partition_claims",
+        long = "entity-cache-partition-claims",
+        env = "ENTITY_CACHE_PARTITION_CLAIMS",
+        value_delimiter = ','
+    )]
+    #[serde(default)]
+    pub partition_claims: Vec<String>,
+
+    /// Further headers carrying the credentials of the clients, such as
+    /// `x-api-key`. The anonymous requests with one of them, or with an
+    /// `Authorization`, `Proxy-Authorization` or `Cookie` header, do not
+    /// share the `public` partition and are not cached.
+    #[clap(
+        id = "entity_cache_credential_headers",
+        long = "entity-cache-credential-headers",
+        env = "ENTITY_CACHE_CREDENTIAL_HEADERS",
+        value_delimiter = ','
+    )]
+    #[serde(default)]
+    pub credential_headers: Vec<String>,
+}
+
+impl Default for EntityCacheConfig {
+    fn default() -> Self {
+        Self {
+            cache_size: default_cache_size(),
+            redis_url: None,
+            partition_claims: Vec::new(),
+            credential_headers: Vec::new(),
+        }
+    }
+}
+
+impl EntityCacheConfig {
+    /// Create the cache the entities are stored in.
+    pub fn create_cache(&self) -> anyhow::Result<Arc<dyn EntityCache>> {
+        match &self.redis_url {
+            #[cfg(feature = "redis")]
+            Some(url) => Ok(Arc::new(RedisEntityCache::new(url)?)),
+            #[cfg(not(feature = "redis"))]
+            Some(_) => anyhow::bail!("Storing entities in Redis requires the `redis` feature."),
+            None => Ok(Arc::new(MemoryEntityCache::new(self.cache_size))),
+        }
+    }
+}
+
+/// Stores the entities resolved by the `_entities` fetches, for as long as
+/// their `@cacheControl` hints allow.
+#[async_trait::async_trait]
+pub trait EntityCache: Send + Sync {
+    /// The entities stored under the keys, lined up with them.
+    async fn get(&self, keys: &[String]) -> anyhow::Result<Vec<Option<ConstValue>>>;
+
+    async fn insert(&self, key: &str, entity: &ConstValue, max_age: Duration) -> anyhow::Result<()>;
+
+    /// Remove the entity stored under `key`, returning whether there was
+    /// one.
+    async fn evict(&self, key: &str) -> anyhow::Result<bool>;
+
+    /// The usage of the cache, with its `top` most hit entities.
+    fn stats(&self, top: usize) -> CacheStats;
+}
+
+type CachedEntity = (ConstValue, Instant, u64, usize);
+
+/// The entities, when they expire, their hits and their size in bytes by
+/// key, with the size of all of them, so that the stats do not go through
+/// every entity.
+#[derive(Default)]
+struct Entities {
+    map: IndexMap<String, CachedEntity>,
+    memory_bytes: usize,
+}
+
+impl Entities {
+    /// Store an entity under a `key` not stored yet.
+    fn insert(&mut self, key: String, entity: CachedEntity) {
+        self.memory_bytes += key.len() + entity.3;
+        self.map.insert(key, entity);
+    }
+
+    fn remove_index(&mut self, index: usize) -> bool {
+        match self.map.shift_remove_index(index) {
+            Some((key, (.., size))) => {
+                self.memory_bytes -= key.len() + size;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        match self.map.get_index_of(key) {
+            Some(index) => self.remove_index(index),
+            None => false,
+        }
+    }
+
+    /// The entity stored under `key` unless it expired, counting the hit.
+    fn lookup(&mut self, key: &str, now: Instant) -> Option<ConstValue> {
+        let index = self.map.get_index_of(key)?;
+        if self.map[index].1 <= now {
+            self.remove_index(index);
+            return None;
+        }
+        let last = self.map.len() - 1;
+        self.map.move_index(index, last);
+        let (entity, _, hits, _) = &mut self.map[last];
+        *hits += 1;
+        Some(entity.clone())
+    }
+}
+
+/// Keeps the most recently used entities in memory.
+pub struct MemoryEntityCache {
+    capacity: usize,
+    entities: Mutex<Entities>,
+    counter: HitCounter,
+}
+
+impl MemoryEntityCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entities: Default::default(),
+            counter: Default::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EntityCache for MemoryEntityCache {
+    async fn get(&self, keys: &[String]) -> anyhow::Result<Vec<Option<ConstValue>>> {
+        let mut entities = self.entities.lock().unwrap();
+        let now = Instant::now();
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let entity = entities.lookup(key, now);
+                self.counter.record(entity.is_some());
+                entity
+            })
+            .collect())
+    }
+
+    async fn insert(&self, key: &str, entity: &ConstValue, max_age: Duration) -> anyhow::Result<()> {
+        // The size is measured once, so that the stats do not serialize the
+        // entities while holding the lock.
+        let size = entity.to_string().len();
+        let mut entities = self.entities.lock().unwrap();
+        entities.remove(key);
+        if entities.map.len() >= self.capacity {
+            entities.remove_index(0);
+        }
+        entities.insert(key.to_string(), (entity.clone(), Instant::now() + max_age, 0, size));
+        Ok(())
+    }
+
+    async fn evict(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(self.entities.lock().unwrap().remove(key))
+    }
+
+    fn stats(&self, top: usize) -> CacheStats {
+        let entities = self.entities.lock().unwrap();
+        let hottest = hottest(
+            entities.map.iter().map(|(key, (_, _, hits, _))| (key.as_str(), *hits)),
+            top,
+        );
+        self.counter
+            .counted_stats(entities.map.len(), entities.memory_bytes, hottest)
+    }
+}
+
+/// Keeps the entities in a Redis server shared by the gateway replicas.
+#[cfg(feature = "redis")]
+pub struct RedisEntityCache {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::MultiplexedConnection>,
+    counter: HitCounter,
+}
+
+#[cfg(feature = "redis")]
+impl RedisEntityCache {
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: Default::default(),
+            counter: Default::default(),
+        })
+    }
+
+    async fn connection(&self) -> anyhow::Result<redis::aio::MultiplexedConnection> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await?;
+        Ok(connection.clone())
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait::async_trait]
+impl EntityCache for RedisEntityCache {
+    async fn get(&self, keys: &[String]) -> anyhow::Result<Vec<Option<ConstValue>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection = self.connection().await?;
+        let entities: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(
+                keys.iter()
+                    .map(|key| format!("graphgate:entity:{}", key))
+                    .collect::<Vec<_>>(),
+            )
+            .query_async(&mut connection)
+            .await?;
+        Ok(entities
+            .into_iter()
+            .map(|entity| {
+                let entity = entity.and_then(|entity| serde_json::from_str(&entity).ok());
+                self.counter.record(entity.is_some());
+                entity
+            })
+            .collect())
+    }
+
+    async fn insert(&self, key: &str, entity: &ConstValue, max_age: Duration) -> anyhow::Result<()> {
+        let mut connection = self.connection().await?;
+        redis::cmd("SET")
+            .arg(format!("graphgate:entity:{}", key))
+            .arg(serde_json::to_string(entity)?)
+            .arg("EX")
+            .arg(max_age.as_secs().max(1))
+            .query_async::<()>(&mut connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn evict(&self, key: &str) -> anyhow::Result<bool> {
+        let mut connection = self.connection().await?;
+        let removed: u64 = redis::cmd("DEL")
+            .arg(format!("graphgate:entity:{}", key))
+            .query_async(&mut connection)
+            .await?;
+        Ok(removed > 0)
+    }
+
+    fn stats(&self, _top: usize) -> CacheStats {
+        self.counter.stats()
+    }
+}
+
+/// The partition the `PRIVATE` entities of a request are cached in.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum CachePartition {
+    /// Keyed by the `Authorization` header forwarded to the service, and not
+    /// cached without one.
+    #[default]
+    Authorization,
+    /// Keyed by the hash of the partition claims, or shared by the anonymous
+    /// requests.
+    Key(String),
+    /// Not cached, as the request cannot be told apart from others.
+    Uncached,
+}
+
+/// The partition of the anonymous requests.
+const PUBLIC_PARTITION: &str = "public";
+
+impl CachePartition {
+    /// The partition of a request with the claims of its verified token, if
+    /// any, and the headers forwarded to the services.
+    pub(crate) fn new(
+        partition_claims: &[String],
+        credential_headers: &[String],
+        claims: Option<&serde_json::Value>,
+        header_map: &HeaderMap,
+    ) -> Self {
+        if partition_claims.is_empty() {
+            return Self::Authorization;
+        }
+        let claims = match claims {
+            Some(claims) => claims,
+            // The credentials of requests without a verified token are not
+            // known to the gateway.
+            None if has_credentials(credential_headers, header_map) => return Self::Uncached,
+            None => return Self::Key(PUBLIC_PARTITION.to_string()),
+        };
+        let mut hasher = Sha256::new();
+        for claim in partition_claims {
+            match claim.split('.').try_fold(claims, |value, key| value.get(key)) {
+                None | Some(serde_json::Value::Null) => return Self::Uncached,
+                Some(value) => {
+                    hasher.update(claim.as_bytes());
+                    hasher.update(b"=");
+                    hasher.update(value.to_string().as_bytes());
+                    hasher.update(b"\n");
+                },
+            }
+        }
+        Self::Key(format!("{:x}", hasher.finalize()))
+    }
+}
+
+/// Whether a request carries credentials the gateway did not verify, which
+/// may identify its client to the services.
+fn has_credentials(credential_headers: &[String], header_map: &HeaderMap) -> bool {
+    [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE]
+        .iter()
+        .any(|name| header_map.contains_key(name)) ||
+        credential_headers
+            .iter()
+            .any(|name| header_map.contains_key(name.as_str()))
+}
+
+/// The representations of an `_entities` fetch, and the entities of them
+/// found in the cache.
+///
+/// Entities are keyed by the service, the query and its variables, and their
+/// representation, made of their typename and key. The entities whose hints
+/// are `PRIVATE` are also keyed by the [`CachePartition`] of the request.
+pub(crate) struct EntityLookup<'a> {
+    cache: &'a dyn EntityCache,
+    /// The hints of the fields selected on each entity type by the query.
+    hints: HashMap<String, CacheControl>,
+    typenames: Vec<Option<String>>,
+    public_keys: Vec<String>,
+    private_keys: Option<Vec<String>>,
+    entities: Vec<Option<ConstValue>>,
+}
+
+impl<'a> EntityLookup<'a> {
+    /// Look up the entities of a request, unless it is not an `_entities`
+    /// fetch.
+    pub(crate) async fn new(
+        cache: &'a dyn EntityCache,
+        schema: &ComposedSchema,
+        service: &str,
+        request: &Request,
+        header_map: &HeaderMap,
+        partition: &CachePartition,
+    ) -> Option<EntityLookup<'a>> {
+        let representations = match request.variables.get("representations") {
+            Some(ConstValue::List(representations)) if request.query.contains("_entities") => representations,
+            _ => return None,
+        };
+
+        let mut variables = request.variables.clone();
+        variables.remove("representations");
+        let prefix = format!("{}\n{}\n{}\n", service, request.query, variables);
+        let partition = match partition {
+            CachePartition::Authorization => header_map
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| format!("{:x}", Sha256::digest(value.as_bytes()))),
+            CachePartition::Key(key) => Some(key.clone()),
+            CachePartition::Uncached => None,
+        };
+        let public_keys = representations
+            .iter()
+            .map(|representation| entity_key(&prefix, representation, None))
+            .collect::<Vec<_>>();
+        let private_keys = partition.map(|partition| {
+            representations
+                .iter()
+                .map(|representation| entity_key(&prefix, representation, Some(&partition)))
+                .collect::<Vec<_>>()
+        });
+
+        // Look up the public entity, then the private entity of each
+        // representation in one round trip.
+        let keys = match &private_keys {
+            Some(private_keys) => public_keys
+                .iter()
+                .zip(private_keys)
+                .flat_map(|(public_key, private_key)| [public_key.clone(), private_key.clone()])
+                .collect(),
+            None => public_keys.clone(),
+        };
+        let found = match cache.get(&keys).await {
+            Ok(found) if found.len() == keys.len() => found,
+            Ok(_) => vec![None; keys.len()],
+            Err(err) => {
+                tracing::warn!(error = %err, "Failed to load cached entities.");
+                vec![None; keys.len()]
+            },
+        };
+        let entities = match private_keys.is_some() {
+            true => found
+                .chunks(2)
+                .map(|found| found[0].clone().or_else(|| found[1].clone()))
+                .collect(),
+            false => found,
+        };
+
+        Some(EntityLookup {
+            cache,
+            hints: schema_hints(schema, &request.query),
+            typenames: representations
+                .iter()
+                .map(|representation| match representation {
+                    ConstValue::Object(object) => match object.get("__typename") {
+                        Some(ConstValue::String(typename)) => Some(typename.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+            public_keys,
+            private_keys,
+            entities,
+        })
+    }
+
+    /// Whether every entity was found in the cache.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.entities.iter().all(Option::is_some)
+    }
+
+    /// The request fetching the entities not found in the cache.
+    pub(crate) fn missing_request(&self, mut request: Request) -> Request {
+        if let Some(ConstValue::List(representations)) = request.variables.get_mut("representations") {
+            let mut found = self.entities.iter().map(Option::is_some);
+            representations.retain(|_| !found.next().unwrap_or_default());
+        }
+        request
+    }
+
+    /// Store the entities fetched for the entities not found in the cache,
+    /// returning the response with the entities of every representation.
+    ///
+    /// Entities are only stored if they resolved without errors and their
+    /// hints, from the schema and the `cacheControl` extension of the
+    /// response, have a `maxAge`.
+    pub(crate) async fn resolve(mut self, resp: Option<Response>) -> Response {
+        let missing = self
+            .entities
+            .iter()
+            .enumerate()
+            .filter(|(_, entity)| entity.is_none())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let mut resp = resp.unwrap_or_default();
+        let fetched = match &mut resp.data {
+            ConstValue::Object(data) => match data.shift_remove("_entities") {
+                Some(ConstValue::List(values)) if values.len() == missing.len() => values,
+                _ => vec![ConstValue::Null; missing.len()],
+            },
+            _ => vec![ConstValue::Null; missing.len()],
+        };
+
+        // The errors of the fetched entities are moved to the index of their
+        // representations.
+        let mut failed = vec![false; missing.len()];
+        let mut cacheable = true;
+        for err in &mut resp.errors {
+            match err.path.as_mut_slice() {
+                [ConstValue::String(name), ConstValue::Number(idx), ..] if name == "_entities" => {
+                    match idx
+                        .as_u64()
+                        .and_then(|n| missing.get(n as usize).map(|index| (n, index)))
+                    {
+                        Some((n, index)) => {
+                            failed[n as usize] = true;
+                            *idx = (*index as u64).into();
+                        },
+                        None => cacheable = false,
+                    }
+                },
+                _ => cacheable = false,
+            }
+        }
+        let response_hints = response_hints(&resp);
+
+        for (n, (index, entity)) in missing.iter().zip(fetched).enumerate() {
+            if cacheable && !failed[n] && entity != ConstValue::Null {
+                let hint = self.typenames[*index]
+                    .as_ref()
+                    .and_then(|typename| self.hints.get(typename))
+                    .copied();
+                let hint = merge_hints(hint, response_hints.get(&n).copied());
+                let key = match hint.map(|hint| hint.scope) {
+                    Some(CacheScope::Public) => Some(&self.public_keys[*index]),
+                    Some(CacheScope::Private) => self.private_keys.as_ref().map(|keys| &keys[*index]),
+                    None => None,
+                };
+                let max_age = hint.and_then(|hint| hint.max_age).filter(|max_age| *max_age > 0);
+                if let (Some(key), Some(max_age)) = (key, max_age) {
+                    if let Err(err) = self.cache.insert(key, &entity, Duration::from_secs(max_age)).await {
+                        tracing::warn!(error = %err, "Failed to store cached entity.");
+                    }
+                }
+            }
+            self.entities[*index] = Some(entity);
+        }
+
+        let mut data = IndexMap::new();
+        data.insert(
+            Name::new("_entities"),
+            ConstValue::List(self.entities.into_iter().map(Option::unwrap_or_default).collect()),
+        );
+        resp.data = ConstValue::Object(data);
+        resp
+    }
+}
+
+fn entity_key(prefix: &str, representation: &ConstValue, partition: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prefix.as_bytes());
+    hasher.update(representation.to_string().as_bytes());
+    if let Some(partition) = partition {
+        hasher.update(b"\n");
+        hasher.update(partition.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn merge_hints(a: Option<CacheControl>, b: Option<CacheControl>) -> Option<CacheControl> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.merge(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// The hints of the types and fields selected on each entity type of an
+/// `_entities` query, from their `@cacheControl` directives.
+fn schema_hints(schema: &ComposedSchema, query: &str) -> HashMap<String, CacheControl> {
+    fn selection_set_hint(
+        schema: &ComposedSchema,
+        ty: &MetaType,
+        selection_set: &SelectionSet,
+        hint: &mut Option<CacheControl>,
+    ) {
+        for selection in &selection_set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    let field = &field.node;
+                    let meta_field = match ty.fields.get(field.name.node.as_str()) {
+                        Some(meta_field) => meta_field,
+                        None => continue,
+                    };
+                    *hint = merge_hints(*hint, meta_field.cache_control);
+                    if let Some(field_type) = schema.concrete_type_by_name(&meta_field.ty) {
+                        if field_type.is_composite() {
+                            *hint = merge_hints(*hint, field_type.cache_control);
+                            selection_set_hint(schema, field_type, &field.selection_set.node, hint);
+                        }
+                    }
+                },
+                Selection::InlineFragment(fragment) => {
+                    let fragment_type = match &fragment.node.type_condition {
+                        Some(type_condition) => schema.types.get(type_condition.node.on.node.as_str()),
+                        None => Some(ty),
+                    };
+                    if let Some(fragment_type) = fragment_type {
+                        selection_set_hint(schema, fragment_type, &fragment.node.selection_set.node, hint);
+                    }
+                },
+                Selection::FragmentSpread(_) => {},
+            }
+        }
+    }
+
+    let document = match parser::parse_query(query) {
+        Ok(document) => document,
+        Err(_) => return HashMap::new(),
+    };
+    let operation = match &document.operations {
+        DocumentOperations::Single(operation) => operation,
+        DocumentOperations::Multiple(operations) => match operations.values().next() {
+            Some(operation) => operation,
+            None => return HashMap::new(),
+        },
+    };
+
+    let mut hints = HashMap::new();
+    for selection in &operation.node.selection_set.node.items {
+        let field = match &selection.node {
+            Selection::Field(field) if field.node.name.node == "_entities" => &field.node,
+            _ => continue,
+        };
+        for selection in &field.selection_set.node.items {
+            let fragment = match &selection.node {
+                Selection::InlineFragment(fragment) => &fragment.node,
+                _ => continue,
+            };
+            let ty = match fragment
+                .type_condition
+                .as_ref()
+                .and_then(|type_condition| schema.types.get(type_condition.node.on.node.as_str()))
+            {
+                Some(ty) => ty,
+                None => continue,
+            };
+            let mut hint = ty.cache_control;
+            selection_set_hint(schema, ty, &fragment.selection_set.node, &mut hint);
+            if let Some(hint) = hint {
+                hints.insert(ty.name.to_string(), hint);
+            }
+        }
+    }
+    hints
+}
+
+/// The hints of each fetched entity, by its index, from the
+/// `cacheControl` extension of the response.
+fn response_hints(resp: &Response) -> HashMap<usize, CacheControl> {
+    let mut hints = HashMap::new();
+    let list = match resp.extensions.get("cacheControl") {
+        Some(ConstValue::Object(cache_control)) => match cache_control.get("hints") {
+            Some(ConstValue::List(list)) => list,
+            _ => return hints,
+        },
+        _ => return hints,
+    };
+    for hint in list {
+        let hint = match hint {
+            ConstValue::Object(hint) => hint,
+            _ => continue,
+        };
+        let index = match hint.get("path") {
+            Some(ConstValue::List(path)) => match path.as_slice() {
+                [ConstValue::String(name), ConstValue::Number(index), ..] if name == "_entities" => index.as_u64(),
+                _ => None,
+            },
+            _ => None,
+        };
+        let index = match index {
+            Some(index) => index as usize,
+            None => continue,
+        };
+        let cache_control = CacheControl {
+            max_age: match hint.get("maxAge") {
+                Some(ConstValue::Number(max_age)) => max_age.as_u64(),
+                _ => None,
+            },
+            scope: match hint.get("scope") {
+                Some(ConstValue::String(scope)) if scope == "PRIVATE" => CacheScope::Private,
+                Some(ConstValue::Enum(scope)) if scope.as_str() == "PRIVATE" => CacheScope::Private,
+                _ => CacheScope::Public,
+            },
+        };
+        let merged = merge_hints(hints.get(&index).copied(), Some(cache_control));
+        if let Some(merged) = merged {
+            hints.insert(index, merged);
+        }
+    }
+    hints
+}
+
+fn default_cache_size() -> usize {
+    10000
+}
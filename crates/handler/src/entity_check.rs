@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use clap::Args;
+use graphgate_planner::Request;
+use graphgate_schema::{ComposedSchema, KeyFields, MetaType, TypeExt};
+use indexmap::IndexMap;
+use parser::types::{BaseType, Type};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use value::{ConstValue, Name, Variables};
+
+use crate::service_route::ServiceRouteTable;
+
+const QUERY_ENTITIES: &str =
+    "query($representations: [_Any!]!) { _entities(representations: $representations) { __typename } }";
+
+/// Probe the reference resolvers of the subgraphs, so that entity types a
+/// subgraph cannot resolve are reported before requests get `null`.
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct EntityCheckConfig {
+    /// Seconds between probes, besides those after every composition, 0 to
+    /// only probe after compositions.
+    #[clap(
+        long = "entity-check-interval-secs",
+        env = "ENTITY_CHECK_INTERVAL_SECS",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub interval_secs: u64,
+
+    /// Representations probed instead of synthetic keys, by entity type,
+    /// without `__typename`.
+    ///
+    /// Subgraphs answering unknown keys with an error need a sample of an
+    /// existing entity.
+    #[clap(skip)]
+    #[serde(default)]
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
+    pub samples: HashMap<String, ConstValue>,
+}
+
+/// An entity type a subgraph failed to resolve when probed.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct EntityResolverError {
+    pub service: String,
+
+    #[serde(rename = "type")]
+    pub type_name: String,
+
+    pub message: String,
+}
+
+/// Send one `_entities` request per entity type to every subgraph resolving
+/// fields of that type, returning the failed ones.
+pub(crate) async fn check_entity_resolvers(
+    config: &EntityCheckConfig,
+    schema: &ComposedSchema,
+    route_table: &ServiceRouteTable,
+) -> Vec<EntityResolverError> {
+    let probes = schema
+        .types
+        .values()
+        .flat_map(|meta_type| {
+            meta_type
+                .keys
+                .iter()
+                .filter(|(service, _)| resolves_fields(meta_type, service))
+                // Services resolve the implementations of the interfaces
+                // they contribute to as the interface.
+                .filter(|(service, _)| {
+                    meta_type
+                        .interface_objects
+                        .get(*service)
+                        .is_none_or(|interface| *interface == meta_type.name)
+                })
+                .filter_map(|(service, keys)| {
+                    let representation = match config.samples.get(meta_type.name.as_str()) {
+                        Some(sample) => sample.clone(),
+                        None => synthetic_value(schema, meta_type, keys.first()?),
+                    };
+                    Some((service.clone(), meta_type.name.to_string(), representation))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let results = futures_util::future::join_all(probes.into_iter().map(
+        |(service, type_name, representation)| async move {
+            let message = probe(route_table, &service, &type_name, representation).await?;
+            Some(EntityResolverError {
+                service,
+                type_name,
+                message,
+            })
+        },
+    ))
+    .await;
+
+    let mut errors = results.into_iter().flatten().collect::<Vec<_>>();
+    errors.sort_by(|a, b| (&a.service, &a.type_name).cmp(&(&b.service, &b.type_name)));
+    errors
+}
+
+/// Whether the planner fetches fields of the type from the service.
+fn resolves_fields(meta_type: &MetaType, service: &str) -> bool {
+    meta_type.owner.as_deref() == Some(service) ||
+        meta_type
+            .fields
+            .values()
+            .any(|field| field.service.as_deref() == Some(service))
+}
+
+/// Request an entity from a service, returning the error message if that
+/// failed.
+async fn probe(
+    route_table: &ServiceRouteTable,
+    service: &str,
+    type_name: &str,
+    representation: ConstValue,
+) -> Option<String> {
+    let mut representation = match representation {
+        ConstValue::Object(representation) => representation,
+        _ => return Some("The sample representation is not an object.".to_string()),
+    };
+    representation.insert(Name::new("__typename"), ConstValue::String(type_name.to_string()));
+
+    let mut variables = Variables::default();
+    variables.insert(
+        Name::new("representations"),
+        ConstValue::List(vec![ConstValue::Object(representation)]),
+    );
+    match route_table
+        .query(service, Request::new(QUERY_ENTITIES).variables(variables), None, None)
+        .await
+    {
+        Ok(resp) if resp.errors.is_empty() => None,
+        Ok(resp) => Some(
+            resp.errors
+                .into_iter()
+                .map(|err| err.message)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        Err(err) => Some(format!("{:#}", err)),
+    }
+}
+
+/// A value of the key fields of a type, with placeholders for the scalars.
+fn synthetic_value(schema: &ComposedSchema, meta_type: &MetaType, key_fields: &KeyFields) -> ConstValue {
+    let mut object = IndexMap::new();
+    for (name, sub_fields) in key_fields.iter() {
+        let value = match meta_type.fields.get(name) {
+            Some(field) => synthetic_field_value(schema, &field.ty, sub_fields),
+            None => ConstValue::String("graphgate-probe".to_string()),
+        };
+        object.insert(name.clone(), value);
+    }
+    ConstValue::Object(object)
+}
+
+fn synthetic_field_value(schema: &ComposedSchema, ty: &Type, sub_fields: &KeyFields) -> ConstValue {
+    if let BaseType::List(element) = &ty.base {
+        return ConstValue::List(vec![synthetic_field_value(schema, element, sub_fields)]);
+    }
+    match ty.concrete_typename() {
+        "Int" => ConstValue::Number(0.into()),
+        "Float" => ConstValue::Number(serde_json::Number::from_f64(0.0).unwrap()),
+        "Boolean" => ConstValue::Boolean(false),
+        name => match schema.types.get(name) {
+            Some(meta_type) if !sub_fields.is_empty() => synthetic_value(schema, meta_type, sub_fields),
+            Some(meta_type) if !meta_type.enum_values.is_empty() => {
+                ConstValue::Enum(meta_type.enum_values[0].value.clone())
+            },
+            _ => ConstValue::String("graphgate-probe".to_string()),
+        },
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use graphgate_schema::{ComposedSchema, EnumValueRenames};
+use parser::types::{ServiceDocument, TypeKind, TypeSystemDefinition};
+use value::Name;
+
+use crate::ServiceRouteTable;
+
+/// Enum values a service names differently from the supergraph, by enum type
+/// and supergraph value.
+pub type EnumValues = HashMap<String, HashMap<String, String>>;
+
+/// Rename the enum values of the SDL of a service to their supergraph
+/// values, before composition.
+pub fn rename_sdl_enum_values(document: &mut ServiceDocument, enum_values: &EnumValues) {
+    for definition in &mut document.definitions {
+        let definition = match definition {
+            TypeSystemDefinition::Type(definition) => definition,
+            _ => continue,
+        };
+        let values = match enum_values.get(definition.node.name.node.as_str()) {
+            Some(values) => values,
+            None => continue,
+        };
+        if let TypeKind::Enum(enum_type) = &mut definition.node.kind {
+            for value in &mut enum_type.values {
+                if let Some(supergraph_value) = supergraph_value(values, &value.node.value.node) {
+                    value.node.value.node = Name::new(supergraph_value);
+                }
+            }
+        }
+    }
+}
+
+fn supergraph_value<'a>(values: &'a HashMap<String, String>, service_value: &str) -> Option<&'a str> {
+    values
+        .iter()
+        .find(|(_, value)| value.as_str() == service_value)
+        .map(|(supergraph_value, _)| supergraph_value.as_str())
+}
+
+/// Rename the enum values of the fetches of the composed schema to the
+/// values of the services of the route table that name them differently.
+pub fn set_enum_value_renames(schema: &mut ComposedSchema, route_table: &ServiceRouteTable) {
+    schema.enum_value_renames = route_table
+        .iter()
+        .filter(|(_, route)| !route.enum_values.is_empty())
+        .map(|(service, route)| (service.clone(), EnumValueRenames::new(&route.enum_values)))
+        .collect();
+}
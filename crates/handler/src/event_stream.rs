@@ -0,0 +1,29 @@
+use futures_util::{Stream, StreamExt};
+use graphgate_planner::Response;
+use warp::hyper::Body;
+
+pub const EVENT_STREAM_CONTENT_TYPE: &str = "text/event-stream";
+
+/// Returns `true` if the `Accept` header allows Server-Sent Events.
+pub fn accepts_event_stream(accept: Option<&str>) -> bool {
+    accept
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media_type| media_type.trim().starts_with(EVENT_STREAM_CONTENT_TYPE))
+        })
+        .unwrap_or_default()
+}
+
+/// Encode a stream of responses as Server-Sent Events, as specified by the
+/// distinct connections mode of the GraphQL over SSE protocol: a `next`
+/// event per response and a `complete` event once the stream ends.
+pub fn event_stream_body(stream: impl Stream<Item = Response> + Send + 'static) -> Body {
+    let events = stream
+        .map(|resp| format!("event: next\ndata: {}\n\n", serde_json::to_string(&resp).unwrap()))
+        .chain(futures_util::stream::once(async {
+            "event: complete\ndata:\n\n".to_string()
+        }))
+        .map(Ok::<_, std::convert::Infallible>);
+    Body::wrap_stream(events)
+}
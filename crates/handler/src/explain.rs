@@ -0,0 +1,36 @@
+use serde_json::Value;
+
+use crate::metrics::FETCH_LATENCIES;
+
+/// Add the recent latencies of each fetch in a serialized plan as its
+/// `latency` field, which is `null` for fetches that were never executed.
+pub(crate) fn annotate_fetches(plan: &mut Value) {
+    match plan {
+        Value::Object(obj) => {
+            let latency = match (obj.get("service"), obj.get("query")) {
+                (Some(Value::String(service)), Some(Value::String(query))) => {
+                    Some(FETCH_LATENCIES.percentiles(service, query))
+                },
+                _ => None,
+            };
+            match latency {
+                Some(latency) => {
+                    obj.insert("latency".to_string(), serde_json::to_value(latency).unwrap());
+                },
+                None => obj.values_mut().for_each(annotate_fetches),
+            }
+        },
+        Value::Array(values) => values.iter_mut().for_each(annotate_fetches),
+        _ => {},
+    }
+}
+
+/// The recent latencies of a fetch as a line of its step in a plan graph,
+/// `None` for fetches that were never executed.
+pub(crate) fn fetch_latency_line(service: &str, query: &str) -> Option<String> {
+    let latency = FETCH_LATENCIES.percentiles(service, query)?;
+    Some(format!(
+        "p50 {:.1}ms p90 {:.1}ms p99 {:.1}ms ({} samples)",
+        latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.samples
+    ))
+}
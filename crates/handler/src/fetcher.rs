@@ -1,21 +1,48 @@
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::{
+    borrow::Cow,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
+use graphgate_executor::{Fetcher, SubgraphStatusError, SubgraphTimeoutError, Subscriber};
 use graphgate_planner::{Request, Response};
-use http::HeaderMap;
+use graphgate_schema::ComposedSchema;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::{
+    global,
+    propagation::{Injector, TextMapPropagator},
+    sdk::propagation::TraceContextPropagator,
+    trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer},
+    Context,
+};
 use tokio::sync::mpsc;
 use tracing::instrument;
 
-use crate::{websocket::WebSocketController, ServiceRouteTable};
-
-#[async_trait::async_trait]
-pub trait Fetcher: Send + Sync {
-    async fn query(&self, service: &str, request: Request) -> Result<Response>;
-}
+use crate::{
+    call_budget::CallBudget,
+    chaos::{ChaosFaults, DroppedConnectionError},
+    constants::{KEY_ERROR, KEY_OPERATION, KEY_RETRIES, KEY_RETRY_ATTEMPT, KEY_SERVICE},
+    entity_cache::{CachePartition, EntityCache, EntityLookup},
+    metrics::FETCH_LATENCIES,
+    response_headers::ResponseHeaders,
+    response_limit::ResponseBudget,
+    server_timing::ServerTiming,
+    websocket::WebSocketController,
+    ServiceRouteTable,
+};
 
 pub struct HttpFetcher<'a> {
     router_table: &'a ServiceRouteTable,
     header_map: &'a HeaderMap,
+    response_budget: Option<&'a ResponseBudget>,
+    call_budget: Option<&'a CallBudget>,
+    chaos: Option<&'a ChaosFaults>,
+    response_headers: Option<&'a ResponseHeaders>,
+    server_timing: Option<&'a ServerTiming>,
+    schema: Option<&'a ComposedSchema>,
+    entity_cache: Option<(&'a dyn EntityCache, &'a CachePartition)>,
 }
 
 impl<'a> HttpFetcher<'a> {
@@ -23,6 +50,254 @@ impl<'a> HttpFetcher<'a> {
         Self {
             router_table,
             header_map,
+            response_budget: None,
+            call_budget: None,
+            chaos: None,
+            response_headers: None,
+            server_timing: None,
+            schema: None,
+            entity_cache: None,
+        }
+    }
+
+    /// Count the subgraph responses against the size limits.
+    pub fn response_budget(self, response_budget: &'a ResponseBudget) -> Self {
+        Self {
+            response_budget: Some(response_budget),
+            ..self
+        }
+    }
+
+    /// Count the subgraph requests against the limit of the operation.
+    pub fn call_budget(self, call_budget: &'a CallBudget) -> Self {
+        Self {
+            call_budget: Some(call_budget),
+            ..self
+        }
+    }
+
+    /// Inject the configured faults in the subgraph requests.
+    pub(crate) fn chaos(self, chaos: &'a ChaosFaults) -> Self {
+        Self {
+            chaos: Some(chaos),
+            ..self
+        }
+    }
+
+    /// Record the selected headers of the subgraph responses.
+    pub fn response_headers(self, response_headers: &'a ResponseHeaders) -> Self {
+        Self {
+            response_headers: Some(response_headers),
+            ..self
+        }
+    }
+
+    /// Aggregate the fetch durations and the `Server-Timing` headers of the
+    /// subgraph responses.
+    pub fn server_timing(self, server_timing: &'a ServerTiming) -> Self {
+        Self {
+            server_timing: Some(server_timing),
+            ..self
+        }
+    }
+
+    /// The schema the fetches are planned on, to look up the cached
+    /// entities by.
+    pub fn schema(self, schema: &'a ComposedSchema) -> Self {
+        Self {
+            schema: Some(schema),
+            ..self
+        }
+    }
+
+    /// Serve the `_entities` fetches from the cache, as the `@cacheControl`
+    /// hints of the schema allow, with the `PRIVATE` entities in the
+    /// partition of the request.
+    pub(crate) fn entity_cache(self, entity_cache: &'a dyn EntityCache, partition: &'a CachePartition) -> Self {
+        Self {
+            entity_cache: Some((entity_cache, partition)),
+            ..self
+        }
+    }
+
+    async fn fetch(&self, service: &str, request: Request) -> Result<Response> {
+        let query = request.query.clone();
+        let cx = start_span(service, &request);
+        let mut header_map = match self.router_table.get(service) {
+            Some(route) => route.forwarded_headers(self.header_map),
+            None => Cow::Borrowed(self.header_map),
+        };
+        inject_trace_context(&cx, header_map.to_mut());
+
+        // The spans of the retries and of the connection are children of the
+        // span of the fetch.
+        let start_time = Instant::now();
+        let resp = self
+            .send(service, request, &header_map, &cx)
+            .with_context(cx.clone())
+            .await;
+        match &resp {
+            Ok(resp) => {
+                if let Some(response_headers) = self.response_headers {
+                    response_headers.record(service, resp.headers.as_ref(), &cx);
+                }
+            },
+            Err(err) => cx.span().set_status(Status::error(err.to_string())),
+        }
+        cx.span().end();
+        FETCH_LATENCIES.record(service, &query, start_time.elapsed(), &cx);
+        if let Some(server_timing) = self.server_timing {
+            let headers = resp.as_ref().ok().and_then(|resp| resp.headers.as_ref());
+            let header = headers.and_then(|headers| headers.get("server-timing"));
+            server_timing.record(service, start_time.elapsed(), header.map(Vec::as_slice));
+        }
+        resp
+    }
+}
+
+impl HttpFetcher<'_> {
+    /// Count a retry against the call budget, returning whether it may be
+    /// sent.
+    fn may_retry(&self) -> bool {
+        self.call_budget.is_none_or(|call_budget| call_budget.call().is_ok())
+    }
+
+    /// Send a request within the timeout of the route of the service,
+    /// retrying the queries that failed transiently as many times as the
+    /// route allows.
+    ///
+    /// Every attempt counts against the call budget, and the injected faults
+    /// fail the attempts as the service would.
+    async fn send(&self, service: &str, request: Request, header_map: &HeaderMap, cx: &Context) -> Result<Response> {
+        let route = self.router_table.get(service);
+        let timeout = route.and_then(|route| route.timeout_ms).map(Duration::from_millis);
+        // Mutations are not idempotent.
+        let retry_count = match operation_type(&request) {
+            "query" => route.map(|route| route.retry_count).unwrap_or_default(),
+            _ => 0,
+        };
+        let backoff = Duration::from_millis(route.map(|route| route.retry_backoff_ms).unwrap_or_default());
+        if let Some(call_budget) = self.call_budget {
+            call_budget.call()?;
+        }
+        self.router_table.state().circuit_breaker.acquire(service)?;
+
+        let mut attempt = 0;
+        loop {
+            let send = async {
+                if let Some(chaos) = self.chaos {
+                    chaos.inject(service).await?;
+                }
+                self.router_table
+                    .query_limited(service, request.clone(), Some(header_map), self.response_budget)
+                    .await
+            };
+            let resp = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, send).await.unwrap_or_else(|_| {
+                    Err(SubgraphTimeoutError {
+                        service: service.to_string(),
+                        timeout,
+                    }
+                    .into())
+                }),
+                None => send.await,
+            };
+            match resp {
+                Err(err) if attempt < retry_count && is_transient(&err) && self.may_retry() => {
+                    attempt += 1;
+                    tracing::debug!(service = %service, attempt, error = %err, "Retrying the request.");
+                    cx.span().add_event("retry", vec![
+                        KEY_RETRY_ATTEMPT.i64(attempt as i64),
+                        KEY_ERROR.string(err.to_string()),
+                    ]);
+                    tokio::time::sleep(backoff * 2u32.saturating_pow(attempt - 1)).await;
+                },
+                resp => {
+                    self.router_table
+                        .state()
+                        .circuit_breaker
+                        .record(service, resp.as_ref().is_err_and(is_transient));
+                    if attempt > 0 {
+                        cx.span().set_attribute(KEY_RETRIES.i64(attempt as i64));
+                    }
+                    return resp;
+                },
+            }
+        }
+    }
+}
+
+/// Whether a failed request may succeed if sent again: it timed out, could
+/// not be sent or the service failed with a 5xx status, for real or by an
+/// injected fault.
+fn is_transient(err: &anyhow::Error) -> bool {
+    if err.is::<SubgraphTimeoutError>() || err.is::<DroppedConnectionError>() {
+        return true;
+    }
+    if let Some(err) = err.downcast_ref::<SubgraphStatusError>() {
+        return err.status >= 500;
+    }
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(|err| err.is_connect() || err.is_timeout())
+}
+
+/// The name of the operation of a subgraph request, or its type if it is
+/// anonymous.
+fn operation_name(request: &Request) -> String {
+    match &request.operation {
+        Some(operation) => operation.clone(),
+        None => operation_type(request).to_string(),
+    }
+}
+
+/// The type of the operation of a subgraph request.
+fn operation_type(request: &Request) -> &str {
+    let query = request.query.trim_start();
+    let ty = &query[..query.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(query.len())];
+    match ty.is_empty() {
+        // The shorthand of queries.
+        true => "query",
+        false => ty,
+    }
+}
+
+/// The context of the plan node a subgraph request is sent for, whose span
+/// records the payload sizes of the request too.
+pub(crate) struct PlanNodeContext(pub(crate) Context);
+
+/// Start the client span of a request to the service, in the context it
+/// is propagated with.
+fn start_span(service: &str, request: &Request) -> Context {
+    let operation = operation_name(request);
+    let tracer = global::tracer("graphql");
+    let span = tracer
+        .span_builder(format!("{} [{}]", operation, service))
+        .with_kind(SpanKind::Client)
+        .with_attributes(vec![
+            KEY_SERVICE.string(service.to_string()),
+            KEY_OPERATION.string(operation),
+        ])
+        .start(&tracer);
+    let node_cx = Context::current();
+    node_cx.with_span(span).with_value(PlanNodeContext(node_cx.clone()))
+}
+
+/// Add the `traceparent` header of the span of the context, if it is valid,
+/// to the headers of a request.
+pub(crate) fn inject_trace_context(cx: &Context, header_map: &mut HeaderMap) {
+    if cx.span().span_context().is_valid() {
+        TraceContextPropagator::new().inject_context(cx, &mut HeaderInjector(header_map));
+    }
+}
+
+/// Writes the `traceparent` and `tracestate` headers of the trace context
+/// into the headers of a subgraph request.
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_str(key), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
         }
     }
 }
@@ -31,34 +306,117 @@ impl<'a> HttpFetcher<'a> {
 impl Fetcher for HttpFetcher<'_> {
     #[instrument(err(Debug), skip(self, request), ret, level = "trace")]
     async fn query(&self, service: &str, request: Request) -> Result<Response> {
-        self.router_table
-            .query(service, request, Some(self.header_map), None)
-            .await
+        let lookup = match self.entity_cache.zip(self.schema) {
+            Some(((entity_cache, partition), schema)) => {
+                EntityLookup::new(entity_cache, schema, service, &request, self.header_map, partition).await
+            },
+            None => None,
+        };
+        match lookup {
+            Some(lookup) if lookup.is_complete() => Ok(lookup.resolve(None).await),
+            Some(lookup) => {
+                let resp = self.fetch(service, lookup.missing_request(request)).await?;
+                Ok(lookup.resolve(Some(resp)).await)
+            },
+            None => self.fetch(service, request).await,
+        }
+    }
+
+    fn url(&self, service: &str) -> Option<String> {
+        self.router_table.url(service, false)
     }
 }
 
-pub struct WebSocketFetcher {
-    controller: WebSocketController,
-    id: AtomicU64,
+#[async_trait::async_trait]
+impl Subscriber for WebSocketController {
+    /// Subscribe within the timeout of the route of the service, retrying
+    /// the subscriptions that could not be started as many times as the
+    /// route allows.
+    ///
+    /// Subscriptions that time out are not retried, they may still start.
+    async fn subscribe(
+        &self,
+        id: &str,
+        service: &str,
+        request: Request,
+        tx: mpsc::UnboundedSender<Response>,
+    ) -> Result<()> {
+        let route = self.route_table().get(service);
+        let timeout = route.and_then(|route| route.timeout_ms).map(Duration::from_millis);
+        // Mutations are not idempotent.
+        let retry_count = match operation_type(&request) {
+            "mutation" => 0,
+            _ => route.map(|route| route.retry_count).unwrap_or_default(),
+        };
+        let backoff = Duration::from_millis(route.map(|route| route.retry_backoff_ms).unwrap_or_default());
+        // The handshake of a new connection to the service propagates the
+        // span of the subscription, ended once it is dropped.
+        let cx = start_span(service, &request);
+
+        let mut attempt = 0;
+        loop {
+            let subscribe =
+                WebSocketController::subscribe(self, id, service, request.clone(), tx.clone()).with_context(cx.clone());
+            let res = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, subscribe).await {
+                    Ok(res) => res,
+                    Err(_) => {
+                        return Err(SubgraphTimeoutError {
+                            service: service.to_string(),
+                            timeout,
+                        }
+                        .into())
+                    },
+                },
+                None => subscribe.await,
+            };
+            match res {
+                Err(err) if attempt < retry_count => {
+                    attempt += 1;
+                    tracing::debug!(service = %service, attempt, error = %err, "Retrying the subscription.");
+                    tokio::time::sleep(backoff * 2u32.saturating_pow(attempt - 1)).await;
+                },
+                res => return res,
+            }
+        }
+    }
+
+    async fn stop(&self, id: &str) {
+        WebSocketController::stop(self, id).await
+    }
 }
 
-impl WebSocketFetcher {
-    pub fn new(controller: WebSocketController) -> Self {
+/// Counts the requests of a subscriber against the call budget of an
+/// operation, failing those exceeding it.
+#[derive(Clone)]
+pub(crate) struct BudgetedSubscriber<S> {
+    subscriber: S,
+    call_budget: Arc<CallBudget>,
+}
+
+impl<S> BudgetedSubscriber<S> {
+    pub(crate) fn new(subscriber: S, call_budget: Arc<CallBudget>) -> Self {
         Self {
-            controller,
-            id: Default::default(),
+            subscriber,
+            call_budget,
         }
     }
 }
 
 #[async_trait::async_trait]
-impl Fetcher for WebSocketFetcher {
-    async fn query(&self, service: &str, request: Request) -> Result<Response> {
-        let id = self.id.fetch_add(1, Ordering::Relaxed);
-        let (tx, mut rx) = mpsc::unbounded_channel();
-        self.controller
-            .subscribe(format!("__req{}", id), service, request, tx)
-            .await?;
-        rx.recv().await.ok_or_else(|| anyhow::anyhow!("Connection closed."))
+impl<S: Subscriber> Subscriber for BudgetedSubscriber<S> {
+    async fn subscribe(
+        &self,
+        id: &str,
+        service: &str,
+        request: Request,
+        tx: mpsc::UnboundedSender<Response>,
+    ) -> Result<()> {
+        self.call_budget.call()?;
+        self.subscriber.subscribe(id, service, request, tx).await
+    }
+
+    async fn stop(&self, id: &str) {
+        self.subscriber.stop(id).await
     }
 }
@@ -1,28 +1,60 @@
-use std::{convert::Infallible, net::SocketAddr, str::FromStr, sync::Arc, time::Instant};
+use std::{collections::HashMap, convert::Infallible, net::SocketAddr, str::FromStr, sync::Arc, time::Instant};
 
 use async_graphql::http::GraphiQLSource;
-use graphgate_planner::Request;
-use http::{header::HeaderName, HeaderMap};
+use futures_util::{future::Either, SinkExt};
+use graphgate_planner::{PlanFormat, Request, Response, ServerError};
+use http::{
+    header::{HeaderName, CONTENT_LOCATION, CONTENT_TYPE},
+    HeaderMap,
+    HeaderValue,
+    StatusCode,
+};
 use opentelemetry::{
     global,
     trace::{FutureExt, TraceContextExt, Tracer},
     Context,
 };
+use parser::types::{DocumentOperations, OperationType};
+use serde::Deserialize;
+use thiserror::Error;
 use tracing::instrument;
-use warp::{http::Response as HttpResponse, ws::Ws, Filter, Rejection, Reply};
+use value::{ConstValue, Variables};
+use warp::{
+    http::Response as HttpResponse,
+    hyper::{body::Bytes, Body},
+    path::FullPath,
+    ws::{Message, Ws},
+    Filter,
+    Rejection,
+    Reply,
+};
 
 use crate::{
-    auth::{with_auth, Auth},
+    auth::{with_auth, with_websocket_auth, AuthError},
+    cache_key::canonical_url,
     constants::*,
-    metrics::METRICS,
+    context_injection::RequestContext,
+    docs::{render_docs, DocsConfig},
+    event_stream::accepts_event_stream,
+    incremental::accepts_multipart,
+    metrics::{record_query_duration, METRICS},
+    operation_label::OperationLabeler,
+    panic::isolate,
+    persisted_queries::{resolve_persisted_query, PERSISTED_QUERY_NOT_FOUND},
+    safelist,
+    shared_config::SharedConfig,
     websocket,
+    CompositionStatus,
     SharedRouteTable,
 };
 
 #[derive(Clone)]
 pub struct HandlerConfig {
     pub shared_route_table: SharedRouteTable,
-    pub forward_headers: Arc<Vec<String>>,
+    /// The forwarded headers and connection params, the authorization and
+    /// CORS, reloaded with the config file.
+    pub shared_config: SharedConfig,
+    pub operation_labeler: Arc<OperationLabeler>,
 }
 
 fn do_forward_headers<T: AsRef<str>>(
@@ -46,25 +78,310 @@ fn do_forward_headers<T: AsRef<str>>(
     new_header_map
 }
 
-pub fn graphql_request(
-    auth: Arc<Auth>,
-    config: HandlerConfig,
-) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
-    warp::post()
-        .and(with_auth(auth))
-        .and(warp::body::json())
+#[derive(Error, Debug)]
+pub enum RequestError {
+    #[error("invalid request body: {0}")]
+    InvalidBody(serde_json::Error),
+
+    #[error("query body is not valid UTF-8")]
+    InvalidUtf8,
+
+    #[error("invalid variables: {0}")]
+    InvalidVariables(serde_json::Error),
+
+    #[error("invalid extensions: {0}")]
+    InvalidExtensions(serde_json::Error),
+
+    #[error("PersistedQueryNotSupported")]
+    PersistedQueryNotSupported,
+
+    #[error("PersistedQueryNotFound")]
+    PersistedQueryNotFound,
+
+    #[error("provided sha does not match query")]
+    PersistedQueryHashMismatch,
+
+    #[error("only queries can be sent with GET")]
+    NotAQuery,
+}
+
+impl warp::reject::Reject for RequestError {}
+
+/// Returns `true` for the `application/graphql` media type, whose body is the
+/// query text.
+fn is_graphql_content_type(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .map(|media_type| media_type.trim().eq_ignore_ascii_case("application/graphql"))
+        .unwrap_or_default()
+}
+
+/// A JSON request body, whose query may be left out for the hash of the
+/// `persistedQuery` extension.
+#[derive(Deserialize)]
+struct RequestBody {
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default, rename = "operationName", alias = "operation")]
+    operation: Option<String>,
+    #[serde(default)]
+    variables: Variables,
+    #[serde(default)]
+    extensions: Option<serde_json::Value>,
+}
+
+/// The extensions of a request that the gateway reads.
+#[derive(Default)]
+struct RequestExtensions {
+    /// The hash of the `persistedQuery` extension.
+    persisted_query_hash: Option<String>,
+    /// The services of the `allowedServices` extension.
+    allowed_services: Option<Vec<String>>,
+    /// The format of the plans served at `/explain`, from the `format` of
+    /// the `explain` extension.
+    explain_format: PlanFormat,
+}
+
+/// The `explain` extension.
+#[derive(Deserialize)]
+struct ExplainExtension {
+    #[serde(default)]
+    format: PlanFormat,
+}
+
+impl RequestExtensions {
+    fn parse(extensions: Option<&serde_json::Value>) -> Result<Self, RequestError> {
+        let extensions = match extensions {
+            Some(extensions) => extensions,
+            None => return Ok(Default::default()),
+        };
+        let persisted_query_hash = extensions["persistedQuery"]["sha256Hash"]
+            .as_str()
+            .map(ToString::to_string);
+        let allowed_services = extensions
+            .get("allowedServices")
+            .map(|services| serde_json::from_value(services.clone()))
+            .transpose()
+            .map_err(RequestError::InvalidExtensions)?;
+        let explain_format = extensions
+            .get("explain")
+            .map(|explain| serde_json::from_value::<ExplainExtension>(explain.clone()))
+            .transpose()
+            .map_err(RequestError::InvalidExtensions)?
+            .map(|explain| explain.format)
+            .unwrap_or_default();
+        Ok(Self {
+            persisted_query_hash,
+            allowed_services,
+            explain_format,
+        })
+    }
+}
+
+/// Parses a GraphQL request with its extensions, the query is empty if only
+/// the hash of its persisted query is sent.
+fn parse_request(
+    content_type: Option<&str>,
+    body: &[u8],
+    params: &HashMap<String, String>,
+) -> Result<(Request, RequestExtensions), RequestError> {
+    if !content_type.map(is_graphql_content_type).unwrap_or_default() {
+        let body: RequestBody = serde_json::from_slice(body).map_err(RequestError::InvalidBody)?;
+        let extensions = RequestExtensions::parse(body.extensions.as_ref())?;
+        let query = match (body.query, &extensions.persisted_query_hash) {
+            (Some(query), _) => query,
+            (None, Some(_)) => String::new(),
+            (None, None) => return Err(RequestError::InvalidBody(serde::de::Error::missing_field("query"))),
+        };
+        let mut request = Request::new(query).variables(body.variables);
+        request.operation = body.operation;
+        return Ok((request, extensions));
+    }
+
+    // The body is the query, variables and the operation name are query
+    // parameters.
+    let query = std::str::from_utf8(body).map_err(|_| RequestError::InvalidUtf8)?;
+    let mut request = Request::new(query);
+    if let Some(variables) = params.get("variables") {
+        request = request.variables(serde_json::from_str(variables).map_err(RequestError::InvalidVariables)?);
+    }
+    if let Some(operation) = params.get("operationName") {
+        request = request.operation(operation);
+    }
+    Ok((request, Default::default()))
+}
+
+/// Parses a GraphQL request from the parameters of a GET request, with its
+/// extensions. The query is empty if only the hash of its persisted query is
+/// sent.
+fn parse_get_request(params: &HashMap<String, String>) -> Result<(Request, RequestExtensions), RequestError> {
+    let extensions = params
+        .get("extensions")
+        .map(|extensions| serde_json::from_str::<serde_json::Value>(extensions))
+        .transpose()
+        .map_err(RequestError::InvalidExtensions)?;
+    let extensions = RequestExtensions::parse(extensions.as_ref())?;
+    let query = match (params.get("query"), &extensions.persisted_query_hash) {
+        (Some(query), _) => query.as_str(),
+        (None, Some(_)) => "",
+        (None, None) => return Err(RequestError::PersistedQueryNotSupported),
+    };
+
+    let mut request = Request::new(query);
+    if let Some(variables) = params.get("variables") {
+        request = request.variables(serde_json::from_str(variables).map_err(RequestError::InvalidVariables)?);
+    }
+    if let Some(operation) = params.get("operationName") {
+        request = request.operation(operation);
+    }
+    Ok((request, extensions))
+}
+
+/// The type of the operation of a request, unless the request is invalid.
+fn operation_type(request: &Request) -> Option<OperationType> {
+    let document = parser::parse_query(&request.query).ok()?;
+    let operation = match (&document.operations, request.operation.as_deref()) {
+        (DocumentOperations::Single(operation), _) => Some(operation),
+        (DocumentOperations::Multiple(operations), Some(name)) => operations.get(name),
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => operations.values().next(),
+        (DocumentOperations::Multiple(_), None) => None,
+    };
+    operation.map(|operation| operation.node.ty)
+}
+
+/// Extracts a GraphQL request from the query string of a GET request, with
+/// its canonical URL and allowed services. GET requests without a query or
+/// extensions are rejected, they are for the playground.
+///
+/// Subscriptions are accepted from the clients that accept Server-Sent
+/// Events, such as `EventSource`.
+fn graphql_get(
+    shared_route_table: SharedRouteTable,
+) -> impl Filter<Extract = (Result<Request, RequestError>, Option<String>, Option<Vec<String>>), Error = Rejection> + Clone
+{
+    warp::get()
+        .and(warp::query::<HashMap<String, String>>())
+        .and(warp::path::full())
+        .and(warp::header::optional::<String>("accept"))
+        .and_then(
+            move |params: HashMap<String, String>, path: FullPath, accept: Option<String>| {
+                let shared_route_table = shared_route_table.clone();
+                async move {
+                    if !params.contains_key("query") && !params.contains_key("extensions") {
+                        return Err(warp::reject::not_found());
+                    }
+                    let (request, persisted, allowed_services) = match parse_get_request(&params) {
+                        Ok((request, extensions)) => {
+                            let request = resolve_persisted_query(
+                                shared_route_table.persisted_query_cache(),
+                                request,
+                                extensions.persisted_query_hash.as_deref(),
+                            )
+                            .await;
+                            // The hash alone finds the query only if the queries are cached.
+                            let persisted = extensions.persisted_query_hash.is_some() &&
+                                shared_route_table.persisted_query_cache().is_some();
+                            (request, persisted, extensions.allowed_services)
+                        },
+                        Err(err) => (Err(err), false, None),
+                    };
+                    let event_stream = accepts_event_stream(accept.as_deref());
+                    let request = request.and_then(|request| match operation_type(&request) {
+                        None | Some(OperationType::Query) => Ok(request),
+                        Some(OperationType::Subscription) if event_stream => Ok(request),
+                        _ => Err(RequestError::NotAQuery),
+                    });
+                    let canonical_url = request
+                        .as_ref()
+                        .ok()
+                        .map(|request| canonical_url(path.as_str(), request, persisted));
+                    Ok((request, canonical_url, allowed_services))
+                }
+            },
+        )
+        .untuple_one()
+}
+
+/// Extracts a GraphQL request from a JSON or an `application/graphql` body,
+/// with its extensions.
+fn graphql_body() -> impl Filter<Extract = (Request, RequestExtensions), Error = Rejection> + Clone {
+    warp::header::optional::<String>("content-type")
+        .and(warp::body::bytes())
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(
+            |content_type: Option<String>, body: Bytes, params: HashMap<String, String>| async move {
+                parse_request(content_type.as_deref(), &body, &params).map_err(warp::reject::custom)
+            },
+        )
+        .untuple_one()
+}
+
+pub fn graphql_request(config: HandlerConfig) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let post = warp::post()
+        .and(with_auth(config.shared_config.clone()))
+        .and(graphql_body())
+        .then({
+            let shared_route_table = config.shared_route_table.clone();
+            move |claims, request, extensions: RequestExtensions| {
+                let shared_route_table = shared_route_table.clone();
+                async move {
+                    let request = resolve_persisted_query(
+                        shared_route_table.persisted_query_cache(),
+                        request,
+                        extensions.persisted_query_hash.as_deref(),
+                    )
+                    .await;
+                    (claims, request, None, extensions.allowed_services)
+                }
+            }
+        })
+        .untuple_one();
+    let get = graphql_get(config.shared_route_table.clone())
+        .and(with_auth(config.shared_config.clone()))
+        .map(|request, canonical_url, allowed_services, claims| (claims, request, canonical_url, allowed_services))
+        .untuple_one();
+
+    post.or(get)
+        .unify()
         .and(warp::header::headers_cloned())
         .and(warp::addr::remote())
         .and_then({
-            move |_auth: (), request: Request, header_map: HeaderMap, remote_addr: Option<SocketAddr>| {
+            move |claims: Option<serde_json::Value>,
+                  request: Result<Request, RequestError>,
+                  canonical_url: Option<String>,
+                  allowed_services: Option<Vec<String>>,
+                  header_map: HeaderMap,
+                  remote_addr: Option<SocketAddr>| {
                 let config = config.clone();
+                let accept = header_map
+                    .get(http::header::ACCEPT)
+                    .and_then(|value| value.to_str().ok());
+                let incremental = accepts_multipart(accept);
+                let event_stream = accepts_event_stream(accept);
                 async move {
+                    let request = match request {
+                        Ok(request) => request,
+                        Err(RequestError::PersistedQueryNotFound) => return Ok(persisted_query_not_found()),
+                        Err(err) => return Ok(bad_request(err)),
+                    };
+                    let operation = config.operation_labeler.label(request.operation.as_deref());
+                    if let Some(safelist) = config.shared_route_table.safelist() {
+                        if !safelist.contains(&request.query) {
+                            METRICS
+                                .safelist_rejected_counter
+                                .add(1, &[KEY_OPERATION.string(operation.clone())]);
+                            tracing::debug!(operation = %operation, "Operation rejected by the safelist.");
+                            return Ok(operation_not_safelisted_response());
+                        }
+                    }
                     let tracer = global::tracer("graphql");
 
                     let query = Context::current_with_span(
                         tracer
                             .span_builder("query")
                             .with_attributes(vec![
+                                KEY_OPERATION.string(operation.clone()),
                                 KEY_QUERY.string(request.query.clone()),
                                 KEY_VARIABLES.string(serde_json::to_string(&request.variables).unwrap()),
                             ])
@@ -72,19 +389,46 @@ pub fn graphql_request(
                     );
 
                     let start_time = Instant::now();
-                    let resp = config
-                        .shared_route_table
-                        .query(
-                            request,
-                            do_forward_headers(&config.forward_headers, &header_map, remote_addr),
-                        )
-                        .with_context(query)
-                        .await;
+                    let settings = config.shared_config.load();
+                    // The persisted operations may forward other headers.
+                    let operation_forward_headers = config.shared_route_table.operation_forward_headers(&request.query);
+                    let mut forward_header_map = do_forward_headers(
+                        operation_forward_headers
+                            .as_deref()
+                            .unwrap_or(&settings.forward_headers),
+                        &header_map,
+                        remote_addr,
+                    );
+                    settings
+                        .auth
+                        .config
+                        .forward_claims(claims.as_ref(), &mut forward_header_map);
+                    let context = RequestContext {
+                        headers: header_map,
+                        claims,
+                        remote_addr,
+                        allowed_services,
+                    };
+                    // Subscriptions are streamed as Server-Sent Events to the
+                    // clients that accept them.
+                    let shared_route_table = &config.shared_route_table;
+                    let resp = match event_stream && operation_type(&request) == Some(OperationType::Subscription) {
+                        true => Either::Left(shared_route_table.subscribe(request, forward_header_map, context)),
+                        false => {
+                            Either::Right(shared_route_table.query(request, forward_header_map, context, incremental))
+                        },
+                    };
+                    let mut resp = isolate(resp.with_context(query.clone())).await;
 
-                    METRICS
-                        .query_histogram
-                        .record((Instant::now() - start_time).as_secs_f64(), &[]);
-                    METRICS.query_counter.add(1, &[]);
+                    let duration = Instant::now() - start_time;
+                    let attributes = [KEY_OPERATION.string(operation.clone())];
+                    record_query_duration(duration, &attributes, &query);
+                    METRICS.query_counter.add(1, &attributes);
+                    tracing::debug!(operation = %operation, duration = ?duration, "Query executed.");
+
+                    if let Some(canonical_url) = canonical_url.and_then(|url| HeaderValue::from_str(&url).ok()) {
+                        resp.headers_mut().insert(CONTENT_LOCATION, canonical_url);
+                    }
 
                     Ok::<_, Infallible>(resp)
                 }
@@ -92,19 +436,114 @@ pub fn graphql_request(
         })
 }
 
-pub fn graphql_websocket(
-    auth: Arc<Auth>,
+/// A GraphQL response with a single error, for GET requests whose
+/// parameters are invalid.
+fn bad_request(err: RequestError) -> HttpResponse<Body> {
+    let resp = Response {
+        data: ConstValue::Null,
+        errors: vec![ServerError::new(err.to_string())],
+        extensions: Default::default(),
+        headers: None,
+    };
+    HttpResponse::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&resp).unwrap().into())
+        .unwrap()
+}
+
+/// Asks the client to send the query of its persisted query again, as
+/// specified by the Automatic Persisted Queries protocol.
+fn persisted_query_not_found() -> HttpResponse<Body> {
+    let mut error = ServerError::new(RequestError::PersistedQueryNotFound.to_string());
+    error.extensions.insert(
+        "code".to_string(),
+        ConstValue::String(PERSISTED_QUERY_NOT_FOUND.to_string()),
+    );
+    let resp = Response {
+        data: ConstValue::Null,
+        errors: vec![error],
+        extensions: Default::default(),
+        headers: None,
+    };
+    HttpResponse::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&resp).unwrap().into())
+        .unwrap()
+}
+
+/// Rejects an operation that is not in the safelist.
+fn operation_not_safelisted_response() -> HttpResponse<Body> {
+    let resp = Response {
+        data: ConstValue::Null,
+        errors: vec![safelist::operation_not_safelisted()],
+        extensions: Default::default(),
+        headers: None,
+    };
+    HttpResponse::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&resp).unwrap().into())
+        .unwrap()
+}
+
+/// Serves the plans of queries at `/explain`, without executing them, in the
+/// format of the `explain` extension.
+pub fn graphql_explain(
     config: HandlerConfig,
+    enabled: bool,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("explain")
+        .and(warp::post())
+        .and(with_auth(config.shared_config.clone()))
+        .and(graphql_body())
+        .and(warp::header::headers_cloned())
+        .and(warp::addr::remote())
+        .and_then(
+            move |claims: Option<serde_json::Value>,
+                  request: Request,
+                  extensions: RequestExtensions,
+                  header_map: HeaderMap,
+                  remote_addr: Option<SocketAddr>| {
+                let config = config.clone();
+                async move {
+                    if !enabled {
+                        return Err(warp::reject::not_found());
+                    }
+                    let resp = config
+                        .shared_route_table
+                        .explain(
+                            request,
+                            RequestContext {
+                                headers: header_map,
+                                claims,
+                                remote_addr,
+                                allowed_services: extensions.allowed_services,
+                            },
+                            extensions.explain_format,
+                        )
+                        .await;
+                    Ok(resp)
+                }
+            },
+        )
+}
+
+pub fn graphql_websocket(config: HandlerConfig) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::ws()
         .and(warp::get())
-        .and(with_auth(auth))
+        .and(with_websocket_auth(config.shared_config.clone()))
         .and(warp::header::exact_ignore_case("upgrade", "websocket"))
         .and(warp::header::optional::<String>("sec-websocket-protocol"))
         .and(warp::header::headers_cloned())
         .and(warp::addr::remote())
         .map({
-            move |ws: Ws, _auth: (), protocols: Option<String>, header_map, remote_addr: Option<SocketAddr>| {
+            move |ws: Ws,
+                  claims: Option<serde_json::Value>,
+                  protocols: Option<String>,
+                  header_map: HeaderMap,
+                  remote_addr: Option<SocketAddr>| {
                 let config = config.clone();
                 let protocol = protocols
                     .and_then(|protocols| {
@@ -113,11 +552,34 @@ pub fn graphql_websocket(
                             .find_map(|p| websocket::Protocols::from_str(p.trim()).ok())
                     })
                     .unwrap_or(websocket::Protocols::SubscriptionsTransportWS);
-                let header_map = do_forward_headers(&config.forward_headers, &header_map, remote_addr);
+                let settings = config.shared_config.load();
+                let mut forward_header_map = do_forward_headers(&settings.forward_headers, &header_map, remote_addr);
+                settings
+                    .auth
+                    .config
+                    .forward_claims(claims.as_ref(), &mut forward_header_map);
+                let forward_connection_params = Arc::new(settings.forward_connection_params.clone());
+                let context = RequestContext {
+                    headers: header_map,
+                    claims,
+                    remote_addr,
+                    ..Default::default()
+                };
 
                 let reply = ws.on_upgrade(move |websocket| async move {
                     if let Some((composed_schema, route_table)) = config.shared_route_table.get().await {
-                        websocket::server(composed_schema, route_table, websocket, protocol, header_map).await;
+                        websocket::server(
+                            config.shared_route_table.clone(),
+                            composed_schema,
+                            route_table,
+                            websocket,
+                            protocol,
+                            forward_header_map,
+                            forward_connection_params,
+                            config.operation_labeler.clone(),
+                            Arc::new(context),
+                        )
+                        .await;
                     }
                 });
 
@@ -126,15 +588,100 @@ pub fn graphql_websocket(
         })
 }
 
+/// Reloads the playground whenever [`graphql_watch`] reports a new schema.
+const RELOAD_SCRIPT: &str = r#"<script>
+  (function connect() {
+    var protocol = location.protocol === "https:" ? "wss://" : "ws://";
+    var socket = new WebSocket(protocol + location.host + "/__watch");
+    socket.onmessage = function () { location.reload(); };
+    socket.onclose = function () { setTimeout(connect, 1000); };
+  })();
+</script>"#;
+
+/// Serves the playground, which reloads itself when the schema changes if
+/// `reload` is set.
 #[instrument(level = "trace")]
-pub fn graphql_playground(path: String) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+pub fn graphql_playground(
+    path: String,
+    reload: bool,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     let endpoint = format!("/{path}");
     warp::get().map(move || {
-        HttpResponse::builder().header("content-type", "text/html").body(
-            GraphiQLSource::build()
-                .endpoint(endpoint.as_str())
-                .subscription_endpoint(endpoint.as_str())
-                .finish(),
-        )
+        let mut html = GraphiQLSource::build()
+            .endpoint(endpoint.as_str())
+            .subscription_endpoint(endpoint.as_str())
+            .finish();
+        if reload {
+            let index = html.rfind("</body>").unwrap_or(html.len());
+            html.insert_str(index, RELOAD_SCRIPT);
+        }
+        HttpResponse::builder().header("content-type", "text/html").body(html)
     })
 }
+
+/// Sends `reload` over the WebSockets connected to `/__watch` whenever a
+/// new schema is composed, in development mode.
+pub fn graphql_watch(
+    shared_route_table: SharedRouteTable,
+    enabled: bool,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("__watch")
+        .and(warp::ws())
+        .and_then(move |ws: Ws| {
+            let shared_route_table = shared_route_table.clone();
+            async move {
+                match enabled {
+                    true => Ok((ws, shared_route_table.watch_composition())),
+                    false => Err(warp::reject::not_found()),
+                }
+            }
+        })
+        .untuple_one()
+        .map(
+            |ws: Ws, mut composition: tokio::sync::watch::Receiver<CompositionStatus>| {
+                ws.on_upgrade(move |mut socket| async move {
+                    composition.borrow_and_update();
+                    while composition.changed().await.is_ok() {
+                        let composed = matches!(*composition.borrow_and_update(), CompositionStatus::Composed(_));
+                        if composed && socket.send(Message::text("reload")).await.is_err() {
+                            break;
+                        }
+                    }
+                })
+            },
+        )
+}
+
+/// Serves the HTML reference of the composed schema at `/docs`.
+pub fn graphql_docs(
+    shared_config: SharedConfig,
+    docs_config: DocsConfig,
+    shared_route_table: SharedRouteTable,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let docs_config = Arc::new(docs_config);
+    warp::path!("docs")
+        .and(warp::get())
+        .and(with_auth(shared_config))
+        .and_then(move |claims: Option<serde_json::Value>| {
+            let docs_config = docs_config.clone();
+            let shared_route_table = shared_route_table.clone();
+            async move {
+                if !docs_config.enabled {
+                    return Err(warp::reject::not_found());
+                }
+                if docs_config.require_auth && claims.is_none() {
+                    return Err(warp::reject::custom(AuthError::MissingAuthorizationHeader));
+                }
+
+                let resp = match shared_route_table.get().await {
+                    Some((composed_schema, _)) => HttpResponse::builder()
+                        .header("content-type", "text/html; charset=utf-8")
+                        .body(render_docs(&composed_schema)),
+                    None => HttpResponse::builder()
+                        .status(http::StatusCode::SERVICE_UNAVAILABLE)
+                        .body("Not ready.".to_string()),
+                };
+                Ok(resp.unwrap())
+            }
+        })
+}
@@ -0,0 +1,54 @@
+use std::{collections::HashMap, str::FromStr};
+
+use http::{header::FORWARDED, HeaderMap, HeaderName};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Which of the forwarded headers are sent to a service, and under which
+/// name.
+///
+/// The policy applies to the headers selected by `forward_headers`, the
+/// `Forwarded` header added by the gateway is only removed if denied.
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq)]
+pub struct HeaderPolicy {
+    /// Only send these forwarded headers to the service, or all of them if
+    /// empty.
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Never send these forwarded headers to the service.
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Send forwarded headers under another name, by forwarded name.
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+}
+
+impl HeaderPolicy {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && self.rename.is_empty()
+    }
+
+    /// The forwarded headers sent to the service.
+    pub(crate) fn apply(&self, header_map: &HeaderMap) -> HeaderMap {
+        let contains =
+            |names: &[String], name: &HeaderName| names.iter().any(|n| n.eq_ignore_ascii_case(name.as_str()));
+        let mut headers = HeaderMap::new();
+        for (name, value) in header_map {
+            if contains(&self.deny, name) {
+                continue;
+            }
+            if !self.allow.is_empty() && *name != FORWARDED && !contains(&self.allow, name) {
+                continue;
+            }
+            let renamed = self
+                .rename
+                .iter()
+                .find(|(from, _)| from.eq_ignore_ascii_case(name.as_str()))
+                .and_then(|(_, to)| HeaderName::from_str(to).ok());
+            headers.append(renamed.unwrap_or_else(|| name.clone()), value.clone());
+        }
+        headers
+    }
+}
@@ -0,0 +1,61 @@
+use clap::Args;
+use futures_util::{Stream, StreamExt};
+use graphgate_planner::IncrementalResponse;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use warp::hyper::Body;
+
+/// Content type of multipart incremental delivery responses.
+pub const MULTIPART_CONTENT_TYPE: &str = "multipart/mixed; boundary=\"-\"; deferSpec=20220824";
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct DeferConfig {
+    /// Root query fields that are returned as soon as they are resolved.
+    #[clap(long = "defer-primary-fields", env = "DEFER_PRIMARY_FIELDS", value_delimiter = ',')]
+    #[serde(default)]
+    pub primary_fields: Vec<String>,
+
+    /// How long to wait for the remaining root fields, in milliseconds,
+    /// before sending them as incremental payloads.
+    #[clap(
+        long = "defer-latency-budget-ms",
+        env = "DEFER_LATENCY_BUDGET_MS",
+        default_value_t = 100
+    )]
+    #[serde(default = "default_latency_budget_ms")]
+    pub latency_budget_ms: u64,
+}
+
+/// Returns `true` if the `Accept` header allows multipart incremental
+/// delivery.
+pub fn accepts_multipart(accept: Option<&str>) -> bool {
+    accept
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media_type| media_type.trim().starts_with("multipart/mixed"))
+        })
+        .unwrap_or_default()
+}
+
+/// Encode a stream of incremental payloads as a multipart body.
+pub fn multipart_body(stream: impl Stream<Item = IncrementalResponse> + Send + 'static) -> Body {
+    let parts = stream.map(|resp| {
+        let mut part = String::from("\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n");
+        part.push_str(&serde_json::to_string(&resp).unwrap());
+        let has_next = match &resp {
+            IncrementalResponse::Initial { has_next, .. } | IncrementalResponse::Subsequent { has_next, .. } => {
+                *has_next
+            },
+        };
+        if !has_next {
+            part.push_str("\r\n-----\r\n");
+        }
+        Ok::<_, std::convert::Infallible>(part)
+    });
+    Body::wrap_stream(parts)
+}
+
+fn default_latency_budget_ms() -> u64 {
+    100
+}
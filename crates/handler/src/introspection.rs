@@ -0,0 +1,419 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use clap::Args;
+use graphgate_planner::{Response, ServerError};
+use graphgate_schema::{ComposedSchema, TypeExt};
+use indexmap::IndexMap;
+use parser::types::{
+    DocumentOperations,
+    ExecutableDocument,
+    OperationDefinition,
+    OperationType,
+    Selection,
+    SelectionSet,
+    Type,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::{
+    sync::OnceCell,
+    time::{Duration, Instant},
+};
+use value::{ConstValue, Name};
+
+use crate::{
+    cache_stats::{CacheStats, HitCounter},
+    metrics::METRICS,
+};
+
+/// The error code of introspection operations rejected by the rate limit.
+pub const INTROSPECTION_RATE_LIMITED: &str = "INTROSPECTION_RATE_LIMITED";
+
+/// The error code of operations selecting `__schema` or `__type` while
+/// introspection is disabled.
+pub const INTROSPECTION_DISABLED: &str = "INTROSPECTION_DISABLED";
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct IntrospectionConfig {
+    /// The most introspection operations served per second over all
+    /// clients, 0 for no limit. Operations over the limit are rejected,
+    /// whether their response is cached or not.
+    #[clap(
+        long = "introspection-max-per-second",
+        env = "INTROSPECTION_MAX_PER_SECOND",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_per_second: u32,
+
+    /// The number of distinct introspection operations whose responses are
+    /// cached for the current schema.
+    #[clap(
+        long = "introspection-cache-size",
+        env = "INTROSPECTION_CACHE_SIZE",
+        default_value_t = 32
+    )]
+    #[serde(default = "default_cache_size")]
+    pub cache_size: usize,
+
+    /// Only expose the types and fields that are neither `@inaccessible`
+    /// nor tagged with one of the `hidden_tags`. Fields of hidden types are
+    /// hidden too, but they can still be queried.
+    #[clap(
+        id = "introspection_allowlist",
+        long = "introspection-allowlist",
+        env = "INTROSPECTION_ALLOWLIST",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub allowlist: bool,
+
+    /// The names of the `@tag` directives hiding types and fields in the
+    /// allowlist mode.
+    #[clap(
+        long = "introspection-hidden-tags",
+        env = "INTROSPECTION_HIDDEN_TAGS",
+        value_delimiter = ','
+    )]
+    #[serde(default)]
+    pub hidden_tags: Vec<String>,
+}
+
+impl Default for IntrospectionConfig {
+    fn default() -> Self {
+        Self {
+            max_per_second: 0,
+            cache_size: default_cache_size(),
+            allowlist: false,
+            hidden_tags: Vec::new(),
+        }
+    }
+}
+
+/// The data and errors of an introspection operation, cached without the
+/// extensions of the request that executed it.
+struct CachedResponse {
+    data: ConstValue,
+    errors: Vec<ServerError>,
+    /// The size of the data and errors in JSON, in bytes.
+    size: usize,
+}
+
+impl From<Response> for CachedResponse {
+    fn from(resp: Response) -> Self {
+        let size = serde_json::to_vec(&resp.data)
+            .map(|data| data.len())
+            .unwrap_or_default() +
+            serde_json::to_vec(&resp.errors)
+                .map(|errors| errors.len())
+                .unwrap_or_default();
+        Self {
+            data: resp.data,
+            errors: resp.errors,
+            size,
+        }
+    }
+}
+
+/// The responses of the introspection operations, valid for one schema.
+#[derive(Default)]
+struct IntrospectionCache {
+    /// Kept alive so that a new schema is never mistaken for it.
+    schema: Option<Arc<ComposedSchema>>,
+    /// The responses and their hits by key.
+    responses: IndexMap<String, (Arc<OnceCell<CachedResponse>>, u64)>,
+    /// The schema exposed in the allowlist mode.
+    visible_schema: Option<Arc<ComposedSchema>>,
+}
+
+impl IntrospectionCache {
+    /// Clear the cache unless it is valid for `schema`.
+    fn validate(&mut self, schema: &Arc<ComposedSchema>) {
+        if !self.schema.as_ref().is_some_and(|cached| Arc::ptr_eq(cached, schema)) {
+            *self = IntrospectionCache {
+                schema: Some(schema.clone()),
+                ..Default::default()
+            };
+        }
+    }
+}
+
+/// Shields the gateway from tooling polling the schema: introspection
+/// operations have their own rate limit, and each distinct operation is
+/// executed once per schema, concurrent requests waiting for the same
+/// execution.
+pub(crate) struct IntrospectionGuard {
+    config: IntrospectionConfig,
+    /// The start of the current one second window and the operations served
+    /// in it.
+    window: Mutex<(Instant, u32)>,
+    cache: Mutex<IntrospectionCache>,
+    counter: HitCounter,
+}
+
+impl Default for IntrospectionGuard {
+    fn default() -> Self {
+        Self::new(IntrospectionConfig::default())
+    }
+}
+
+impl IntrospectionGuard {
+    pub(crate) fn new(config: IntrospectionConfig) -> Self {
+        Self {
+            config,
+            window: Mutex::new((Instant::now(), 0)),
+            cache: Default::default(),
+            counter: Default::default(),
+        }
+    }
+
+    /// Count an introspection operation, returning the response to send
+    /// instead if it exceeds the rate limit.
+    pub(crate) fn acquire(&self) -> Option<Response> {
+        if self.config.max_per_second == 0 {
+            return None;
+        }
+
+        let mut window = self.window.lock().unwrap();
+        let now = Instant::now();
+        if now.duration_since(window.0) >= Duration::from_secs(1) {
+            *window = (now, 0);
+        }
+        if window.1 < self.config.max_per_second {
+            window.1 += 1;
+            return None;
+        }
+        METRICS.introspection_rate_limited_counter.add(1, &[]);
+
+        let mut error = ServerError::new(format!(
+            "Introspection is limited to {} operations per second.",
+            self.config.max_per_second
+        ));
+        error.extensions.insert(
+            "code".to_string(),
+            ConstValue::String(INTROSPECTION_RATE_LIMITED.to_string()),
+        );
+        Some(Response {
+            data: ConstValue::Null,
+            errors: vec![error],
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+
+    /// The response of the introspection operation identified by `key`
+    /// against `schema`, executed by `execute` unless cached.
+    ///
+    /// Only the data and errors are cached, the response has no extensions.
+    pub(crate) async fn get_or_execute<F, Fut>(&self, schema: &Arc<ComposedSchema>, key: String, execute: F) -> Response
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Response>,
+    {
+        let cell = {
+            let mut cache = self.cache.lock().unwrap();
+            cache.validate(schema);
+            let hit = cache.responses.contains_key(&key);
+            self.counter.record(hit);
+            if !hit && cache.responses.len() >= self.config.cache_size.max(1) {
+                cache.responses.shift_remove_index(0);
+            }
+            let (cell, hits) = cache.responses.entry(key).or_default();
+            if hit {
+                *hits += 1;
+            }
+            cell.clone()
+        };
+        let cached = cell.get_or_init(|| async { execute().await.into() }).await;
+        Response {
+            data: cached.data.clone(),
+            errors: cached.errors.clone(),
+            extensions: Default::default(),
+            headers: Default::default(),
+        }
+    }
+
+    /// The schema the introspection fields are answered from in the
+    /// allowlist mode, computed once per schema.
+    pub(crate) fn visible_schema(&self, schema: &Arc<ComposedSchema>) -> Option<Arc<ComposedSchema>> {
+        if !self.config.allowlist {
+            return None;
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.validate(schema);
+        let visible_schema = cache
+            .visible_schema
+            .get_or_insert_with(|| Arc::new(visible_schema(schema, &self.config.hidden_tags)));
+        Some(visible_schema.clone())
+    }
+
+    /// Remove the response cached under `key`, returning whether there was
+    /// one.
+    pub(crate) fn evict(&self, key: &str) -> bool {
+        self.cache.lock().unwrap().responses.shift_remove(key).is_some()
+    }
+
+    /// The usage of the response cache, with its `top` most hit responses.
+    pub(crate) fn stats(&self, top: usize) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        self.counter.memory_stats(
+            cache.responses.iter().map(|(key, (cell, hits))| {
+                let size = cell.get().map(|cached| cached.size).unwrap_or_default();
+                (key.as_str(), size, *hits)
+            }),
+            top,
+        )
+    }
+}
+
+/// Whether the operation only selects `__schema`, `__type` and
+/// `__typename` on the query type, selecting at least one of the former.
+pub(crate) fn is_introspection(document: &ExecutableDocument, operation_name: Option<&str>) -> bool {
+    let operation = match find_operation(document, operation_name) {
+        Some(operation) => operation,
+        None => return false,
+    };
+    if operation.ty != OperationType::Query {
+        return false;
+    }
+
+    let mut visited = HashSet::new();
+    let mut introspects = false;
+    only_introspection_fields(document, &operation.selection_set.node, &mut visited, &mut introspects) && introspects
+}
+
+fn only_introspection_fields<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    visited: &mut HashSet<&'a Name>,
+    introspects: &mut bool,
+) -> bool {
+    selection_set.items.iter().all(|selection| match &selection.node {
+        Selection::Field(field) => match field.node.name.node.as_str() {
+            "__schema" | "__type" => {
+                *introspects = true;
+                true
+            },
+            name => name == "__typename",
+        },
+        Selection::InlineFragment(fragment) => {
+            only_introspection_fields(document, &fragment.node.selection_set.node, visited, introspects)
+        },
+        Selection::FragmentSpread(spread) => {
+            let name = &spread.node.fragment_name.node;
+            if !visited.insert(name) {
+                return true;
+            }
+            match document.fragments.get(name) {
+                Some(fragment) => {
+                    only_introspection_fields(document, &fragment.node.selection_set.node, visited, introspects)
+                },
+                None => false,
+            }
+        },
+    })
+}
+
+/// Whether the operation selects `__schema` or `__type`.
+pub(crate) fn selects_introspection(document: &ExecutableDocument, operation_name: Option<&str>) -> bool {
+    match find_operation(document, operation_name) {
+        Some(operation) => any_introspection_field(document, &operation.selection_set.node, &mut HashSet::new()),
+        None => false,
+    }
+}
+
+fn any_introspection_field<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    visited: &mut HashSet<&'a Name>,
+) -> bool {
+    selection_set.items.iter().any(|selection| match &selection.node {
+        Selection::Field(field) => matches!(field.node.name.node.as_str(), "__schema" | "__type"),
+        Selection::InlineFragment(fragment) => {
+            any_introspection_field(document, &fragment.node.selection_set.node, visited)
+        },
+        Selection::FragmentSpread(spread) => {
+            let name = &spread.node.fragment_name.node;
+            visited.insert(name) &&
+                document.fragments.get(name).is_some_and(|fragment| {
+                    any_introspection_field(document, &fragment.node.selection_set.node, visited)
+                })
+        },
+    })
+}
+
+/// The operation of a document to execute, if it exists.
+fn find_operation<'a>(
+    document: &'a ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<&'a OperationDefinition> {
+    match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), None) => Some(&operation.node),
+        (DocumentOperations::Multiple(operations), Some(name)) => operations.get(name).map(|operation| &operation.node),
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            operations.values().next().map(|operation| &operation.node)
+        },
+        _ => None,
+    }
+}
+
+/// The error of operations selecting `__schema` or `__type` while
+/// introspection is disabled.
+pub(crate) fn introspection_disabled() -> ServerError {
+    let mut error = ServerError::new("Introspection is disabled.");
+    error.extensions.insert(
+        "code".to_string(),
+        ConstValue::String(INTROSPECTION_DISABLED.to_string()),
+    );
+    error
+}
+
+/// A copy of the schema without the types, fields, arguments, enum values
+/// and input fields that are `@inaccessible` or tagged with one of
+/// `hidden_tags`, nor the fields and input fields referring to the hidden
+/// types.
+fn visible_schema(schema: &ComposedSchema, hidden_tags: &[String]) -> ComposedSchema {
+    let is_hidden =
+        |tags: &[String], inaccessible: bool| inaccessible || tags.iter().any(|tag| hidden_tags.contains(tag));
+    let hidden_types = schema
+        .types
+        .values()
+        .filter(|ty| ty.name.as_str() != schema.query_type() && is_hidden(&ty.tags, ty.inaccessible))
+        .map(|ty| ty.name.clone())
+        .collect::<HashSet<_>>();
+    let is_visible = |ty: &Type| !hidden_types.contains(ty.concrete_typename());
+
+    let mut visible_schema = schema.clone();
+    visible_schema.types.retain(|name, _| !hidden_types.contains(name));
+    for ty in visible_schema.types.values_mut() {
+        ty.fields.retain(|_, field| {
+            !is_hidden(&field.tags, field.inaccessible) &&
+                is_visible(&field.ty) &&
+                field.arguments.values().all(|arg| is_visible(&arg.ty))
+        });
+        for field in ty.fields.values_mut() {
+            field.arguments.retain(|_, arg| !is_hidden(&arg.tags, arg.inaccessible));
+        }
+        ty.input_fields
+            .retain(|_, field| !is_hidden(&field.tags, field.inaccessible) && is_visible(&field.ty));
+        ty.enum_values
+            .retain(|_, value| !is_hidden(&value.tags, value.inaccessible));
+        ty.implements.retain(|name| !hidden_types.contains(name));
+        ty.possible_types.retain(|name| !hidden_types.contains(name));
+    }
+    for root in [&mut visible_schema.mutation_type, &mut visible_schema.subscription_type] {
+        if root.as_ref().is_some_and(|name| hidden_types.contains(name)) {
+            *root = None;
+        }
+    }
+    visible_schema
+}
+
+fn default_cache_size() -> usize {
+    32
+}
@@ -0,0 +1,62 @@
+use graphgate_planner::Response;
+use serde_json::{Map, Value};
+
+/// Parse the response of a subgraph whose errors do not follow the GraphQL
+/// specification, normalizing them into a list of errors.
+///
+/// A single error object or string in `errors`, a top-level `error` and
+/// errors without a message string are accepted, so that they fail the
+/// fields of the service instead of the whole request.
+pub(crate) fn parse_lenient_response(body: &[u8]) -> serde_json::Result<Response> {
+    let mut value = serde_json::from_slice::<Value>(body)?;
+    if let Value::Object(object) = &mut value {
+        let mut errors = Vec::new();
+        for key in ["errors", "error"] {
+            match object.remove(key) {
+                None | Some(Value::Null) => {},
+                Some(Value::Array(items)) => errors.extend(items),
+                Some(error) => errors.push(error),
+            }
+        }
+        object.insert(
+            "errors".to_string(),
+            Value::Array(errors.into_iter().map(normalize_error).collect()),
+        );
+        object.entry("data").or_insert(Value::Null);
+    }
+    serde_json::from_value(value)
+}
+
+fn normalize_error(error: Value) -> Value {
+    let mut object = match error {
+        Value::Object(object) => object,
+        Value::String(message) => {
+            let mut object = Map::new();
+            object.insert("message".to_string(), Value::String(message));
+            return Value::Object(object);
+        },
+        error => {
+            let mut object = Map::new();
+            object.insert("message".to_string(), Value::String(error.to_string()));
+            return Value::Object(object);
+        },
+    };
+
+    if !matches!(object.get("message"), Some(Value::String(_))) {
+        let message = match object.remove("message").or_else(|| object.remove("error")) {
+            Some(Value::String(message)) => message,
+            Some(message) => message.to_string(),
+            None => Value::Object(object.clone()).to_string(),
+        };
+        object.insert("message".to_string(), Value::String(message));
+    }
+    for key in ["path", "locations"] {
+        if !matches!(object.get(key), None | Some(Value::Array(_))) {
+            object.remove(key);
+        }
+    }
+    if !matches!(object.get("extensions"), None | Some(Value::Object(_))) {
+        object.remove("extensions");
+    }
+    Value::Object(object)
+}
@@ -1,17 +1,141 @@
 #![forbid(unsafe_code)]
 #![allow(clippy::blocks_in_conditions)]
 
-pub use service_route::{ServiceRoute, ServiceRouteTable};
-pub use shared_route_table::SharedRouteTable;
+pub use audit::{AuditConfig, AUDIT_LOG_UNAVAILABLE};
+pub use authorization::UNAUTHORIZED_FIELD_OR_TYPE;
+pub use blocklist::OPERATION_BLOCKED;
+pub use bucketing::{BucketHasher, BucketKey, Bucketing, BucketingConfig, Sha256BucketHasher};
+pub use cache_stats::{CacheKind, CacheStats, HotEntry};
+pub use call_budget::{CallBudgetConfig, CALL_BUDGET_EXCEEDED};
+pub use chaos::{ChaosConfig, Fault, FaultKind};
+pub use circuit_breaker::{CircuitBreakerConfig, ServiceCircuitBreakerConfig};
+pub use composition::CompositionConfig;
+pub use connection::ConnectionConfig;
+pub use context_injection::{ContextRule, ContextSource, RequestContext};
+pub use cors::{cors_filter, with_cors, CorsConfig, CorsPolicy};
+pub use cost::{CostBudget, CostConfig, COST_LIMIT_EXCEEDED};
+pub use deprecation::{DeprecationConfig, DEPRECATED_FIELD_SUNSET};
+pub use docs::DocsConfig;
+#[cfg(feature = "redis")]
+pub use entity_cache::RedisEntityCache;
+pub use entity_cache::{EntityCache, EntityCacheConfig, MemoryEntityCache};
+pub use entity_check::{EntityCheckConfig, EntityResolverError};
+pub use enum_values::{rename_sdl_enum_values, set_enum_value_renames, EnumValues};
+pub use graphgate_executor::{
+    BAD_GATEWAY,
+    ENTITY_KEY_MISMATCH,
+    ENTITY_MOVED_HEADER,
+    FORBIDDEN,
+    RESPONSE_TOO_LARGE,
+    SUBGRAPH_CIRCUIT_OPEN,
+    SUBGRAPH_RATE_LIMITED,
+    UNAUTHENTICATED,
+};
+pub use header_policy::HeaderPolicy;
+pub use incremental::DeferConfig;
+pub use introspection::{IntrospectionConfig, INTROSPECTION_DISABLED, INTROSPECTION_RATE_LIMITED};
+pub use metrics::{escape_label_value, latency_histogram_names, record_route_conflict, register_latency_histograms};
+pub use oauth2::OAuth2Config;
+pub use operation_label::{OperationLabelConfig, OperationLabelMode, OperationLabeler};
+pub use operation_limits::{OperationLimitsConfig, OPERATION_LIMIT_EXCEEDED};
+pub use pagination::PaginationConfig;
+pub use panic::{install_panic_hook, INTERNAL_SERVER_ERROR};
+pub use parallelism::ParallelismConfig;
+pub use persisted_operations::{
+    parse_manifest,
+    InvalidPersistedOperation,
+    PersistedOperation,
+    PersistedOperationMetadata,
+};
+#[cfg(feature = "redis")]
+pub use persisted_queries::RedisPersistedQueryCache;
+pub use persisted_queries::{
+    MemoryPersistedQueryCache,
+    PersistedQueryCache,
+    PersistedQueryConfig,
+    PERSISTED_QUERY_NOT_FOUND,
+};
+pub use polling::PollingConfig;
+pub use rate_limit::RateLimitConfig;
+pub use redaction::{RedactionAction, RedactionRule, REDACTION_MASK};
+pub use request_signing::{
+    verify_request_signature,
+    RequestSigningConfig,
+    SignatureError,
+    SIGNATURE_HEADER,
+    SIGNATURE_KEY_ID_HEADER,
+    SIGNATURE_TIMESTAMP_HEADER,
+};
+pub use response_limit::ResponseLimitConfig;
+pub use safelist::{Safelist, SafelistConfig, OPERATION_NOT_SAFELISTED};
+pub use service_route::{RouteSource, ServiceRoute, ServiceRouteTable};
+pub use shared_config::{GatewaySettings, SharedConfig};
+pub use shared_route_table::{
+    compose_schema,
+    CompositionStatus,
+    RouteHealth,
+    RouteStatus,
+    SharedRouteTable,
+    SubgraphSchema,
+};
+pub use subgraph_request::SubgraphRequestConfig;
+pub use upstream::{ServiceUpstreamConfig, UpstreamConfig};
+pub use verification::{VerificationConfig, VerificationOperation};
+pub use websocket::{ConnectionParam, Protocols};
 
+mod audit;
 pub mod auth;
+mod authorization;
+mod blocklist;
+mod bucketing;
+mod cache_key;
+mod cache_stats;
+mod call_budget;
+mod chaos;
+mod circuit_breaker;
+mod composition;
+mod connection;
 mod constants;
-mod executor;
+mod context_injection;
+mod cors;
+mod cost;
+mod deprecation;
+mod docs;
+mod entity_cache;
+mod entity_check;
+mod enum_values;
+mod event_stream;
+mod explain;
 mod fetcher;
+mod header_policy;
+mod incremental;
 mod introspection;
+mod lenient_errors;
 mod metrics;
+mod oauth2;
+mod operation_label;
+mod operation_limits;
+mod pagination;
+mod panic;
+mod parallelism;
+mod persisted_operations;
+mod persisted_queries;
+mod polling;
+mod rate_limit;
+mod redaction;
+mod request_signing;
+mod response_headers;
+mod response_limit;
+mod safelist;
+mod server_timing;
 mod service_route;
+mod shared_config;
 mod shared_route_table;
+mod snapshot;
+mod subgraph_request;
+mod supergraph;
+mod upstream;
+mod verification;
 mod websocket;
 
 pub mod handler;
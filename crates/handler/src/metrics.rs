@@ -1,12 +1,81 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+        Weak,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use graphgate_schema::ComposedSchema;
 use once_cell::sync::Lazy;
 use opentelemetry::{
     global,
-    metrics::{Counter, Histogram},
+    metrics::{Counter, Histogram, Meter, ObservableGauge},
+    trace::TraceContextExt,
+    Context,
+    KeyValue,
+};
+use prometheus_client::{
+    metrics::{exemplar::HistogramWithExemplars, family::Family},
+    registry::Registry,
+};
+use serde::Serialize;
+
+use crate::{
+    cache_key::query_hash,
+    constants::{KEY_SDL_HASH, KEY_SERVICE},
+    shared_route_table::SubgraphSchema,
+    upstream::Upstream,
 };
 
+/// The name of the histogram of the GraphQL query latencies.
+const QUERY_DURATION: &str = "graphgate.graphql_query_duration_seconds";
+
+/// The name of the histogram of the subgraph fetch latencies.
+const FETCH_DURATION: &str = "graphgate.subgraph_fetch_duration_seconds";
+
+/// The bucket boundaries of the latency histograms with exemplars, the
+/// default ones of the OpenTelemetry histograms.
+const LATENCY_BUCKETS: [f64; 15] = [
+    0.0, 5.0, 10.0, 25.0, 50.0, 75.0, 100.0, 250.0, 500.0, 750.0, 1000.0, 2500.0, 5000.0, 7500.0, 10000.0,
+];
+
+/// The labels of a histogram series, or of the trace of an exemplar.
+type Labels = Vec<(String, String)>;
+
+/// A latency histogram keeping the latest observation of each bucket, linked
+/// to its trace, as exemplar.
+type ExemplarHistogram = Family<Labels, HistogramWithExemplars<Labels>, fn() -> HistogramWithExemplars<Labels>>;
+
 pub struct Metrics {
     pub query_counter: Counter<u64>,
     pub query_histogram: Histogram<f64>,
+    pub fetch_histogram: Histogram<f64>,
+    query_exemplar_histogram: ExemplarHistogram,
+    fetch_exemplar_histogram: ExemplarHistogram,
+    pub subgraph_request_counter: Counter<u64>,
+    pub subgraph_rate_limited_counter: Counter<u64>,
+    pub subgraph_shed_counter: Counter<u64>,
+    pub circuit_transition_counter: Counter<u64>,
+    pub circuit_rejected_counter: Counter<u64>,
+    pub panic_counter: Counter<u64>,
+    pub response_too_large_counter: Counter<u64>,
+    pub call_budget_exceeded_counter: Counter<u64>,
+    pub chaos_fault_counter: Counter<u64>,
+    pub subgraph_response_too_large_counter: Counter<u64>,
+    pub introspection_rate_limited_counter: Counter<u64>,
+    pub safelist_rejected_counter: Counter<u64>,
+    pub route_conflict_counter: Counter<u64>,
+    pub composition_histogram: Histogram<f64>,
+    pub composition_error_counter: Counter<u64>,
+    pub verification_failure_counter: Counter<u64>,
+    meter: Meter,
+    composition_gauges: [ObservableGauge<u64>; 5],
+    subgraph_schema_gauge: ObservableGauge<u64>,
+    in_flight_gauge: ObservableGauge<u64>,
 }
 
 pub static METRICS: Lazy<Metrics> = Lazy::new(|| {
@@ -16,11 +85,387 @@ pub static METRICS: Lazy<Metrics> = Lazy::new(|| {
         .with_description("Total number of GraphQL queries executed")
         .init();
     let query_histogram = meter
-        .f64_histogram("graphgate.graphql_query_duration_seconds")
+        .f64_histogram(QUERY_DURATION)
         .with_description("The GraphQL query latencies in seconds.")
         .init();
+    let fetch_histogram = meter
+        .f64_histogram(FETCH_DURATION)
+        .with_description("The subgraph fetch latencies in seconds.")
+        .init();
+    let subgraph_request_counter = meter
+        .u64_counter("graphgate.subgraph_requests_total")
+        .with_description("Total number of HTTP requests sent to subgraphs, by HTTP version")
+        .init();
+    let subgraph_rate_limited_counter = meter
+        .u64_counter("graphgate.subgraph_rate_limited_total")
+        .with_description("Total number of 429 responses received from subgraphs")
+        .init();
+    let subgraph_shed_counter = meter
+        .u64_counter("graphgate.subgraph_requests_shed_total")
+        .with_description("Total number of requests not sent to rate limited subgraphs")
+        .init();
+    let circuit_transition_counter = meter
+        .u64_counter("graphgate.circuit_transitions_total")
+        .with_description("Total number of changes of state of the circuits of the subgraphs")
+        .init();
+    let circuit_rejected_counter = meter
+        .u64_counter("graphgate.circuit_rejected_total")
+        .with_description("Total number of requests not sent to subgraphs with an open circuit")
+        .init();
+    let panic_counter = meter
+        .u64_counter("graphgate.request_panics_total")
+        .with_description("Total number of requests that panicked in the gateway")
+        .init();
+    let response_too_large_counter = meter
+        .u64_counter("graphgate.responses_too_large_total")
+        .with_description("Total number of responses replaced by an error for exceeding the size limit")
+        .init();
+    let call_budget_exceeded_counter = meter
+        .u64_counter("graphgate.call_budget_exceeded_total")
+        .with_description("Total number of operations aborted for exceeding the limit of subgraph requests")
+        .init();
+    let chaos_fault_counter = meter
+        .u64_counter("graphgate.chaos_faults_total")
+        .with_description("Total number of faults injected in subgraph requests")
+        .init();
+    let subgraph_response_too_large_counter = meter
+        .u64_counter("graphgate.subgraph_responses_too_large_total")
+        .with_description("Total number of subgraph responses exceeding the size limit")
+        .init();
+    let introspection_rate_limited_counter = meter
+        .u64_counter("graphgate.introspection_rate_limited_total")
+        .with_description("Total number of introspection operations rejected by the rate limit")
+        .init();
+    let safelist_rejected_counter = meter
+        .u64_counter("graphgate.safelist_rejected_total")
+        .with_description("Total number of operations rejected for not being in the safelist")
+        .init();
+    let route_conflict_counter = meter
+        .u64_counter("graphgate.route_conflicts_total")
+        .with_description("Total number of discoveries of several services with the same service name")
+        .init();
+    let composition_histogram = meter
+        .f64_histogram("graphgate.composition_duration_seconds")
+        .with_description("The schema composition durations in seconds.")
+        .init();
+    let composition_error_counter = meter
+        .u64_counter("graphgate.composition_errors_total")
+        .with_description("Total number of schema updates that failed to fetch or compose the SDLs")
+        .init();
+    let verification_failure_counter = meter
+        .u64_counter("graphgate.schema_verification_failures_total")
+        .with_description("Total number of composed schemas rejected by the verification operations")
+        .init();
+    // The gauges observe the state of the latest schema updates, and the
+    // fetches in flight, of the shared route tables.
+    let composition_gauges = [
+        (
+            "graphgate.composition_consecutive_failures",
+            "The number of schema updates that failed since the last successful one",
+        ),
+        (
+            "graphgate.composition_last_success_timestamp_seconds",
+            "The time of the last successful schema update, in seconds since the epoch",
+        ),
+        (
+            "graphgate.supergraph_subgraphs",
+            "The number of subgraphs the schema is composed of",
+        ),
+        (
+            "graphgate.supergraph_types",
+            "The number of types of the composed schema",
+        ),
+        (
+            "graphgate.supergraph_fields",
+            "The number of fields of the composed schema",
+        ),
+    ]
+    .map(|(name, description)| meter.u64_observable_gauge(name).with_description(description).init());
+    let subgraph_schema_gauge = meter
+        .u64_observable_gauge("graphgate.subgraph_schema_fetched_timestamp_seconds")
+        .with_description("The time the composed SDL of each subgraph was first fetched, labeled by its hash")
+        .init();
+    let in_flight_gauge = meter
+        .u64_observable_gauge("graphgate.upstream_fetches_in_flight")
+        .with_description(
+            "The number of fetches sent to each subgraph over its connection pool awaiting their response, which over \
+             HTTP/1.1 is the number of its connections in use",
+        )
+        .init();
     Metrics {
         query_counter,
         query_histogram,
+        fetch_histogram,
+        query_exemplar_histogram: Family::new_with_constructor(|| {
+            HistogramWithExemplars::new(LATENCY_BUCKETS.into_iter())
+        }),
+        fetch_exemplar_histogram: Family::new_with_constructor(|| {
+            HistogramWithExemplars::new(LATENCY_BUCKETS.into_iter())
+        }),
+        subgraph_request_counter,
+        subgraph_rate_limited_counter,
+        subgraph_shed_counter,
+        circuit_transition_counter,
+        circuit_rejected_counter,
+        panic_counter,
+        response_too_large_counter,
+        call_budget_exceeded_counter,
+        chaos_fault_counter,
+        subgraph_response_too_large_counter,
+        introspection_rate_limited_counter,
+        safelist_rejected_counter,
+        route_conflict_counter,
+        composition_histogram,
+        composition_error_counter,
+        verification_failure_counter,
+        meter,
+        composition_gauges,
+        subgraph_schema_gauge,
+        in_flight_gauge,
     }
 });
+
+/// Observe the state of the schema updates and the fetches in flight of a
+/// shared route table with the gauges, for as long as it is in use.
+pub(crate) fn observe_gauges(composition_state: &Arc<CompositionState>, upstream: &Arc<Upstream>) {
+    let metrics = &*METRICS;
+    let composition_gauges = metrics.composition_gauges.clone();
+    let subgraph_schema_gauge = metrics.subgraph_schema_gauge.clone();
+    let in_flight_gauge = metrics.in_flight_gauge.clone();
+    let instruments = composition_gauges
+        .iter()
+        .chain([&subgraph_schema_gauge, &in_flight_gauge])
+        .map(ObservableGauge::as_any)
+        .collect::<Vec<_>>();
+    let composition_state = Arc::downgrade(composition_state);
+    let upstream = Arc::downgrade(upstream);
+    let res = metrics.meter.register_callback(&instruments, move |observer| {
+        if let Some(state) = Weak::upgrade(&composition_state) {
+            let values = [
+                &state.consecutive_failures,
+                &state.last_success,
+                &state.subgraphs,
+                &state.types,
+                &state.fields,
+            ];
+            for (gauge, value) in composition_gauges.iter().zip(values) {
+                observer.observe_u64(gauge, value.load(Ordering::Relaxed), &[]);
+            }
+            for (service, sdl_hash, fetched_at) in state.subgraph_schemas.lock().unwrap().iter() {
+                observer.observe_u64(&subgraph_schema_gauge, *fetched_at, &[
+                    KEY_SERVICE.string(service.clone()),
+                    KEY_SDL_HASH.string(sdl_hash.clone()),
+                ]);
+            }
+        }
+        if let Some(upstream) = Weak::upgrade(&upstream) {
+            for (service, count) in upstream.in_flight() {
+                observer.observe_u64(&in_flight_gauge, count, &[KEY_SERVICE.string(service)]);
+            }
+        }
+    });
+    if let Err(err) = res {
+        tracing::warn!(error = %err, "Failed to observe the gauges of the route table.");
+    }
+}
+
+/// The outcome of the latest schema updates of a shared route table,
+/// observed by the composition gauges.
+#[derive(Default)]
+pub struct CompositionState {
+    subgraphs: AtomicU64,
+    types: AtomicU64,
+    fields: AtomicU64,
+    consecutive_failures: AtomicU64,
+    last_success: AtomicU64,
+    /// The service, SDL hash and fetch time of the subgraphs.
+    subgraph_schemas: Mutex<Vec<(String, String, u64)>>,
+}
+
+impl CompositionState {
+    /// Record a schema composed from the SDLs of the subgraphs.
+    pub fn composed(&self, schema: &ComposedSchema, subgraphs: &[SubgraphSchema], duration: Duration) {
+        METRICS.composition_histogram.record(duration.as_secs_f64(), &[]);
+        let types = schema.types.values().filter(|ty| !ty.name.starts_with("__"));
+        let (types, fields) = types.fold((0, 0), |(types, fields), ty| (types + 1, fields + ty.fields.len()));
+        self.subgraphs.store(subgraphs.len() as u64, Ordering::Relaxed);
+        *self.subgraph_schemas.lock().unwrap() = subgraphs
+            .iter()
+            .map(|subgraph| {
+                let fetched_at = subgraph.fetched_at.timestamp().max(0) as u64;
+                (subgraph.service.clone(), subgraph.sdl_hash.clone(), fetched_at)
+            })
+            .collect();
+        self.types.store(types as u64, Ordering::Relaxed);
+        self.fields.store(fields as u64, Ordering::Relaxed);
+    }
+
+    /// Record a successful update, whether the SDLs changed or not.
+    pub fn updated(&self) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        self.last_success.store(now.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Record an update that failed to fetch or compose the SDLs.
+    pub fn failed(&self) {
+        METRICS.composition_error_counter.add(1, &[]);
+        self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The number of recent samples kept per fetch.
+const MAX_SAMPLES: usize = 1000;
+
+/// The number of distinct fetches tracked per service, further fetches of the
+/// service are not recorded.
+const MAX_FETCHES_PER_SERVICE: usize = 100;
+
+/// Latency percentiles of a fetch, in milliseconds.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyPercentiles {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub samples: usize,
+}
+
+/// Recent latencies of subgraph fetches, by service and SHA-256 hash of the
+/// query.
+///
+/// The samples of a fetch are only allocated once it is recorded, so the
+/// memory grows with the number of services and their distinct fetches.
+#[derive(Default)]
+pub struct FetchLatencies {
+    samples: Mutex<HashMap<String, HashMap<String, VecDeque<f64>>>>,
+}
+
+impl FetchLatencies {
+    pub fn record(&self, service: &str, query: &str, duration: Duration, cx: &Context) {
+        let attributes = [KEY_SERVICE.string(service.to_string())];
+        METRICS.fetch_histogram.record(duration.as_secs_f64(), &attributes);
+        observe_with_exemplar(
+            &METRICS.fetch_exemplar_histogram,
+            &attributes,
+            duration.as_secs_f64(),
+            cx,
+        );
+
+        let mut samples = self.samples.lock().unwrap();
+        let samples = samples.entry(service.to_string()).or_default();
+        let key = query_hash(query);
+        if !samples.contains_key(&key) && samples.len() >= MAX_FETCHES_PER_SERVICE {
+            return;
+        }
+        let samples = samples.entry(key).or_default();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration.as_secs_f64() * 1000.0);
+    }
+
+    pub fn percentiles(&self, service: &str, query: &str) -> Option<LatencyPercentiles> {
+        let mut sorted = {
+            let samples = self.samples.lock().unwrap();
+            let samples = samples.get(service)?.get(&query_hash(query))?;
+            samples.iter().copied().collect::<Vec<_>>()
+        };
+        sorted.sort_by(f64::total_cmp);
+
+        let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        Some(LatencyPercentiles {
+            p50_ms: percentile(0.5),
+            p90_ms: percentile(0.9),
+            p99_ms: percentile(0.99),
+            samples: sorted.len(),
+        })
+    }
+}
+
+pub static FETCH_LATENCIES: Lazy<FetchLatencies> = Lazy::new(Default::default);
+
+/// Record the latency of a GraphQL query, in the trace of `cx`.
+pub(crate) fn record_query_duration(duration: Duration, attributes: &[KeyValue], cx: &Context) {
+    METRICS.query_histogram.record(duration.as_secs_f64(), attributes);
+    observe_with_exemplar(
+        &METRICS.query_exemplar_histogram,
+        attributes,
+        duration.as_secs_f64(),
+        cx,
+    );
+}
+
+/// Record the discovery of several services with the same service name.
+pub fn record_route_conflict(service: &str) {
+    METRICS
+        .route_conflict_counter
+        .add(1, &[KEY_SERVICE.string(service.to_string())]);
+}
+
+/// A metric or label name as exported to Prometheus.
+fn prometheus_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == ':' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+/// A label value escaped for the OpenMetrics text format, which the encoder
+/// writes as is.
+pub fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '"' => escaped.push_str("\\\""),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Observe a latency, with the trace of `cx` as exemplar if it is sampled.
+fn observe_with_exemplar(histogram: &ExemplarHistogram, attributes: &[KeyValue], value: f64, cx: &Context) {
+    let labels = attributes
+        .iter()
+        .map(|kv| {
+            (
+                prometheus_name(kv.key.as_str()),
+                escape_label_value(&kv.value.to_string()),
+            )
+        })
+        .collect::<Labels>();
+    let span = cx.span();
+    let span_context = span.span_context();
+    let trace = (span_context.is_valid() && span_context.is_sampled())
+        .then(|| vec![("trace_id".to_string(), span_context.trace_id().to_string())]);
+    histogram
+        .get_or_create(&labels)
+        .observe(value, trace, Some(SystemTime::now()));
+}
+
+/// The names of the latency histograms as exported to Prometheus, which are
+/// registered with their exemplars by [`register_latency_histograms`].
+pub fn latency_histogram_names() -> [String; 2] {
+    [QUERY_DURATION, FETCH_DURATION].map(prometheus_name)
+}
+
+/// Register the latency histograms, with the trace of the latest observation
+/// of each bucket as exemplar, to export them in the OpenMetrics format.
+pub fn register_latency_histograms(registry: &mut Registry) {
+    // The registry ends the help with a period.
+    registry.register(
+        prometheus_name(QUERY_DURATION),
+        "The GraphQL query latencies in seconds",
+        METRICS.query_exemplar_histogram.clone(),
+    );
+    registry.register(
+        prometheus_name(FETCH_DURATION),
+        "The subgraph fetch latencies in seconds",
+        METRICS.fetch_exemplar_histogram.clone(),
+    );
+}
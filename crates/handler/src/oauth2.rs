@@ -0,0 +1,118 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::service_route::HTTP_CLIENT;
+
+/// Tokens are refreshed this long before they expire.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// The lifetime assumed for tokens issued without `expires_in`.
+const DEFAULT_EXPIRES_IN: Duration = Duration::from_secs(300);
+
+/// An OAuth2 client credentials grant, whose access token the gateway sends
+/// to a subgraph as a bearer token.
+#[derive(Clone, Deserialize, Eq, Hash, JsonSchema, PartialEq)]
+pub struct OAuth2Config {
+    pub token_url: String,
+
+    pub client_id: String,
+
+    pub client_secret: String,
+
+    #[serde(default)]
+    pub scopes: Vec<String>,
+
+    /// The `audience` parameter required by some authorization servers.
+    #[serde(default)]
+    pub audience: Option<String>,
+}
+
+impl fmt::Debug for OAuth2Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Config")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("scopes", &self.scopes)
+            .field("audience", &self.audience)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+struct Token {
+    access_token: String,
+    expires_at: Instant,
+}
+
+/// Caches the access tokens of the client credentials grants.
+#[derive(Default)]
+pub(crate) struct TokenCache {
+    tokens: std::sync::Mutex<HashMap<OAuth2Config, Arc<Mutex<Option<Token>>>>>,
+}
+
+impl TokenCache {
+    /// Returns a valid access token, requesting a new one if the cached
+    /// token is about to expire.
+    ///
+    /// Concurrent requests for the same grant wait for a single token
+    /// request.
+    pub(crate) async fn access_token(&self, config: &OAuth2Config) -> Result<String> {
+        let token = self.tokens.lock().unwrap().entry(config.clone()).or_default().clone();
+        let mut token = token.lock().await;
+
+        if let Some(token) = &*token {
+            if token.expires_at > Instant::now() + REFRESH_MARGIN {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let new_token = request_token(config).await?;
+        let access_token = new_token.access_token.clone();
+        *token = Some(new_token);
+        Ok(access_token)
+    }
+}
+
+async fn request_token(config: &OAuth2Config) -> Result<Token> {
+    let mut params = vec![
+        ("grant_type", "client_credentials".to_string()),
+        ("client_id", config.client_id.clone()),
+        ("client_secret", config.client_secret.clone()),
+    ];
+    if !config.scopes.is_empty() {
+        params.push(("scope", config.scopes.join(" ")));
+    }
+    if let Some(audience) = &config.audience {
+        params.push(("audience", audience.clone()));
+    }
+
+    let resp: TokenResponse = HTTP_CLIENT
+        .post(&config.token_url)
+        .form(&params)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .with_context(|| format!("Failed to request an access token from '{}'.", config.token_url))?
+        .json()
+        .await
+        .with_context(|| format!("Invalid access token response from '{}'.", config.token_url))?;
+
+    let expires_in = resp.expires_in.map(Duration::from_secs).unwrap_or(DEFAULT_EXPIRES_IN);
+    Ok(Token {
+        access_token: resp.access_token,
+        expires_at: Instant::now() + expires_in,
+    })
+}
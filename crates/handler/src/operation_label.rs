@@ -0,0 +1,131 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use clap::{Args, ValueEnum};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// The label of operations excluded by `limit` or `allowlist` mode.
+pub const OTHER_OPERATION: &str = "other";
+
+/// The label of operations without a name.
+pub const ANONYMOUS_OPERATION: &str = "anonymous";
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct OperationLabelConfig {
+    /// How operation names are turned into metric and log labels.
+    #[clap(
+        long = "operation-label-mode",
+        env = "OPERATION_LABEL_MODE",
+        value_enum,
+        default_value = "limit"
+    )]
+    #[serde(default)]
+    pub mode: OperationLabelMode,
+
+    /// The number of distinct operation names that keep their own label in
+    /// `limit` mode.
+    #[clap(long = "operation-label-limit", env = "OPERATION_LABEL_LIMIT", default_value_t = 100)]
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+
+    /// The operation names that keep their own label in `allowlist` mode.
+    #[clap(
+        long = "operation-label-allowlist",
+        env = "OPERATION_LABEL_ALLOWLIST",
+        value_delimiter = ','
+    )]
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+}
+
+impl Default for OperationLabelConfig {
+    fn default() -> Self {
+        Self {
+            mode: OperationLabelMode::default(),
+            limit: default_limit(),
+            allowlist: Vec::new(),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationLabelMode {
+    /// Use the operation name as is.
+    Name,
+
+    /// Use a hash of the operation name.
+    Hash,
+
+    /// Use the first `limit` distinct operation names, and `other` for the
+    /// rest.
+    #[default]
+    Limit,
+
+    /// Use the operation names in `allowlist`, and `other` for the rest.
+    Allowlist,
+}
+
+/// Maps operation names to bounded labels.
+///
+/// Metrics, spans and logs all label requests through the same labeler, so
+/// an operation shows up under the same label everywhere.
+pub struct OperationLabeler {
+    config: OperationLabelConfig,
+    seen: Mutex<HashSet<String>>,
+}
+
+impl Default for OperationLabeler {
+    fn default() -> Self {
+        Self::new(OperationLabelConfig::default())
+    }
+}
+
+impl OperationLabeler {
+    pub fn new(config: OperationLabelConfig) -> Self {
+        Self {
+            config,
+            seen: Default::default(),
+        }
+    }
+
+    /// Returns the label of an operation.
+    pub fn label(&self, operation: Option<&str>) -> String {
+        let operation = match operation {
+            Some(operation) if !operation.is_empty() => operation,
+            _ => return ANONYMOUS_OPERATION.to_string(),
+        };
+
+        match self.config.mode {
+            OperationLabelMode::Name => operation.to_string(),
+            OperationLabelMode::Hash => format!("{:016x}", fnv1a(operation)),
+            OperationLabelMode::Limit => {
+                let mut seen = self.seen.lock().unwrap();
+                if seen.contains(operation) {
+                    operation.to_string()
+                } else if seen.len() < self.config.limit {
+                    seen.insert(operation.to_string());
+                    operation.to_string()
+                } else {
+                    OTHER_OPERATION.to_string()
+                }
+            },
+            OperationLabelMode::Allowlist => match self.config.allowlist.iter().any(|name| name == operation) {
+                true => operation.to_string(),
+                false => OTHER_OPERATION.to_string(),
+            },
+        }
+    }
+}
+
+/// The 64-bit FNV-1a hash, which is stable across builds and platforms
+/// unlike the standard library hasher.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn default_limit() -> usize {
+    100
+}
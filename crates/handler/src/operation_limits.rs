@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::Args;
+use graphgate_planner::ServerError;
+use parser::types::{DocumentOperations, ExecutableDocument, Selection, SelectionSet};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::ConstValue;
+
+/// The error code of operations exceeding a limit of their shape.
+pub const OPERATION_LIMIT_EXCEEDED: &str = "OPERATION_LIMIT_EXCEEDED";
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct OperationLimitsConfig {
+    /// The deepest nesting of fields in an operation, 0 for no limit.
+    #[clap(
+        long = "operation-limits-max-depth",
+        env = "OPERATION_LIMITS_MAX_DEPTH",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_depth: usize,
+
+    /// The most aliased fields in an operation, 0 for no limit.
+    #[clap(
+        long = "operation-limits-max-aliases",
+        env = "OPERATION_LIMITS_MAX_ALIASES",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_aliases: usize,
+
+    /// The most fields selected on the root type of an operation, 0 for no
+    /// limit.
+    #[clap(
+        long = "operation-limits-max-root-fields",
+        env = "OPERATION_LIMITS_MAX_ROOT_FIELDS",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_root_fields: usize,
+}
+
+/// The shape of a selection set, with the fragments it spreads expanded.
+///
+/// The depth counts its own fields as 1, and the root fields are its own
+/// fields.
+#[derive(Clone, Copy, Default)]
+struct Shape {
+    depth: usize,
+    aliases: usize,
+    root_fields: usize,
+}
+
+impl Shape {
+    /// Add the shape of a selection set merged into this one.
+    fn merge(&mut self, other: Shape) {
+        self.depth = self.depth.max(other.depth);
+        self.aliases = self.aliases.saturating_add(other.aliases);
+        self.root_fields = self.root_fields.saturating_add(other.root_fields);
+    }
+}
+
+struct Measurer<'a> {
+    document: &'a ExecutableDocument,
+    /// The fragments being measured, to skip cyclic spreads.
+    fragments: HashSet<&'a str>,
+    /// The shape of each fragment measured, so that the fragments spread
+    /// repeatedly are measured once.
+    shapes: HashMap<&'a str, Shape>,
+}
+
+impl<'a> Measurer<'a> {
+    fn selection_set(&mut self, selection_set: &'a SelectionSet) -> Shape {
+        let mut shape = Shape::default();
+        for selection in &selection_set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    let field = &field.node;
+                    let fields = self.selection_set(&field.selection_set.node);
+                    shape.merge(Shape {
+                        depth: fields.depth + 1,
+                        aliases: fields.aliases.saturating_add(field.alias.is_some() as usize),
+                        root_fields: 1,
+                    });
+                },
+                Selection::InlineFragment(fragment) => {
+                    let fragment = self.selection_set(&fragment.node.selection_set.node);
+                    shape.merge(fragment);
+                },
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.node.fragment_name.node.as_str();
+                    if let Some(fragment) = self.shapes.get(name) {
+                        shape.merge(*fragment);
+                        continue;
+                    }
+                    let fragment = match self.document.fragments.get(name) {
+                        // Cyclic spreads are rejected by validation.
+                        Some(fragment) if self.fragments.insert(name) => fragment,
+                        _ => continue,
+                    };
+                    let fragment = self.selection_set(&fragment.node.selection_set.node);
+                    self.fragments.remove(name);
+                    self.shapes.insert(name, fragment);
+                    shape.merge(fragment);
+                },
+            }
+        }
+        shape
+    }
+}
+
+/// Check the depth, the aliases and the root fields of an operation against
+/// the limits, before it is planned, returning the error of the first limit
+/// exceeded.
+///
+/// Operations that do not exist are left to the planner to report.
+pub(crate) fn check_operation_limits(
+    config: &OperationLimitsConfig,
+    document: &ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<ServerError> {
+    let operation = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), None) => &operation.node,
+        (DocumentOperations::Multiple(operations), Some(name)) => &operations.get(name)?.node,
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            &operations.values().next().unwrap().node
+        },
+        _ => return None,
+    };
+
+    let mut measurer = Measurer {
+        document,
+        fragments: HashSet::new(),
+        shapes: HashMap::new(),
+    };
+    let shape = measurer.selection_set(&operation.selection_set.node);
+
+    for (name, value, max) in [
+        ("depth", shape.depth, config.max_depth),
+        ("number of aliases", shape.aliases, config.max_aliases),
+        ("number of root fields", shape.root_fields, config.max_root_fields),
+    ] {
+        if max > 0 && value > max {
+            let mut error = ServerError::new(format!(
+                "The {} {} of the operation exceeds the limit of {}.",
+                name, value, max
+            ));
+            error.extensions.insert(
+                "code".to_string(),
+                ConstValue::String(OPERATION_LIMIT_EXCEEDED.to_string()),
+            );
+            return Some(error);
+        }
+    }
+    None
+}
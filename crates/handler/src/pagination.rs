@@ -0,0 +1,170 @@
+use std::collections::BTreeSet;
+
+use clap::Args;
+use graphgate_schema::{ComposedSchema, MetaType};
+use parser::{
+    types::{BaseType, DocumentOperations, ExecutableDocument, OperationType, Selection, SelectionSet},
+    Positioned,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::{ConstValue, Name, Value};
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct PaginationConfig {
+    /// Arguments that limit the length of a list field.
+    #[clap(
+        long = "pagination-limit-arguments",
+        env = "PAGINATION_LIMIT_ARGUMENTS",
+        value_delimiter = ',',
+        default_value = "first,limit"
+    )]
+    #[serde(default = "default_limit_arguments")]
+    pub limit_arguments: Vec<String>,
+
+    /// The limit applied to list fields selected without one.
+    #[clap(
+        long = "pagination-default-limit",
+        env = "PAGINATION_DEFAULT_LIMIT",
+        default_value_t = 100
+    )]
+    #[serde(default = "default_limit")]
+    pub default_limit: u64,
+
+    /// The limit applied to list fields nested in another list, defaults to
+    /// `default_limit`.
+    #[clap(long = "pagination-nested-limit", env = "PAGINATION_NESTED_LIMIT")]
+    pub nested_limit: Option<u64>,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            limit_arguments: default_limit_arguments(),
+            default_limit: default_limit(),
+            nested_limit: None,
+        }
+    }
+}
+
+struct Guard<'a> {
+    config: &'a PaginationConfig,
+    schema: &'a ComposedSchema,
+    warnings: BTreeSet<String>,
+}
+
+impl Guard<'_> {
+    fn apply(&mut self, parent_type: &MetaType, selection_set: &mut SelectionSet, in_list: bool) {
+        for selection in &mut selection_set.items {
+            match &mut selection.node {
+                Selection::Field(field) => {
+                    let field = &mut field.node;
+                    let field_definition = match parent_type.fields.get(field.name.node.as_str()) {
+                        Some(field_definition) => field_definition,
+                        None => continue,
+                    };
+                    let is_list = matches!(field_definition.ty.base, BaseType::List(_));
+
+                    if is_list {
+                        let has_limit = field.arguments.iter().any(|(name, value)| {
+                            self.config.limit_arguments.iter().any(|arg| name.node == arg.as_str()) &&
+                                !matches!(value.node, Value::Null)
+                        });
+                        let limit_argument = self.config.limit_arguments.iter().find(|arg| {
+                            field_definition
+                                .arguments
+                                .get(arg.as_str())
+                                .map(|arg| arg.ty.base == BaseType::Named(Name::new("Int")))
+                                .unwrap_or_default()
+                        });
+                        if let (false, Some(limit_argument)) = (has_limit, limit_argument) {
+                            let limit = match in_list {
+                                true => self.config.nested_limit.unwrap_or(self.config.default_limit),
+                                false => self.config.default_limit,
+                            };
+                            field.arguments.retain(|(name, _)| name.node != limit_argument.as_str());
+                            field.arguments.push((
+                                Positioned::new(Name::new(limit_argument), field.name.pos),
+                                Positioned::new(Value::Number(limit.into()), field.name.pos),
+                            ));
+                            self.warnings.insert(format!(
+                                "The default limit {}: {} was applied to \"{}.{}\".",
+                                limit_argument, limit, parent_type.name, field.name.node
+                            ));
+                        }
+                    }
+
+                    if let Some(field_type) = self.schema.concrete_type_by_name(&field_definition.ty) {
+                        self.apply(field_type, &mut field.selection_set.node, in_list || is_list);
+                    }
+                },
+                Selection::InlineFragment(fragment) => {
+                    let fragment = &mut fragment.node;
+                    let fragment_type = match &fragment.type_condition {
+                        Some(type_condition) => self.schema.types.get(type_condition.node.on.node.as_str()),
+                        None => Some(parent_type),
+                    };
+                    if let Some(fragment_type) = fragment_type {
+                        self.apply(fragment_type, &mut fragment.selection_set.node, in_list);
+                    }
+                },
+                Selection::FragmentSpread(_) => {},
+            }
+        }
+    }
+}
+
+/// Add the default limit to the list fields of a query document that accept
+/// a limit argument but were selected without one.
+///
+/// Fragment definitions may be spread anywhere, so they always receive
+/// `default_limit`.
+///
+/// Returns a warning for each limited field.
+pub(crate) fn apply_default_limits(
+    config: &PaginationConfig,
+    schema: &ComposedSchema,
+    document: &mut ExecutableDocument,
+) -> Vec<String> {
+    let mut guard = Guard {
+        config,
+        schema,
+        warnings: BTreeSet::new(),
+    };
+
+    for fragment in document.fragments.values_mut() {
+        if let Some(ty) = schema.types.get(fragment.node.type_condition.node.on.node.as_str()) {
+            guard.apply(ty, &mut fragment.node.selection_set.node, false);
+        }
+    }
+
+    let operations: Vec<_> = match &mut document.operations {
+        DocumentOperations::Single(operation) => vec![operation],
+        DocumentOperations::Multiple(operations) => operations.values_mut().collect(),
+    };
+    for operation in operations {
+        let root_type = match operation.node.ty {
+            OperationType::Query => Some(schema.query_type()),
+            OperationType::Mutation => schema.mutation_type(),
+            OperationType::Subscription => schema.subscription_type(),
+        };
+        if let Some(root_type) = root_type.and_then(|name| schema.types.get(name)) {
+            guard.apply(root_type, &mut operation.node.selection_set.node, false);
+        }
+    }
+
+    guard.warnings.into_iter().collect()
+}
+
+/// The response extension listing the applied default limits.
+pub(crate) fn warnings_extension(warnings: &[String]) -> ConstValue {
+    ConstValue::List(warnings.iter().cloned().map(ConstValue::String).collect())
+}
+
+fn default_limit_arguments() -> Vec<String> {
+    vec!["first".to_string(), "limit".to_string()]
+}
+
+fn default_limit() -> u64 {
+    100
+}
@@ -0,0 +1,145 @@
+use std::{
+    backtrace::Backtrace,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Once,
+    },
+    task::Poll,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{FutureExt, Stream, StreamExt};
+use graphgate_planner::{IncrementalPayload, IncrementalResponse, Response, ServerError};
+use http::{header::CONTENT_TYPE, StatusCode};
+use value::ConstValue;
+use warp::{http::Response as HttpResponse, hyper::Body};
+
+use crate::metrics::METRICS;
+
+/// The error code of requests that panicked in the gateway.
+pub const INTERNAL_SERVER_ERROR: &str = "INTERNAL_SERVER_ERROR";
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// Log the panics of requests with their correlation id and a backtrace,
+/// leaving other panics to the previous hook.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            match CORRELATION_ID.try_with(|correlation_id| correlation_id.clone()) {
+                Ok(correlation_id) => tracing::error!(
+                    correlation_id = %correlation_id,
+                    backtrace = %Backtrace::force_capture(),
+                    "The request panicked: {}",
+                    info
+                ),
+                Err(_) => previous(info),
+            }
+        }));
+    });
+}
+
+fn next_correlation_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    format!("{:x}-{:x}", millis, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// The response to a request that panicked, counting the panic.
+fn panic_response(correlation_id: String) -> Response {
+    METRICS.panic_counter.add(1, &[]);
+
+    let mut error = ServerError::new("Internal server error.");
+    error.extensions.insert(
+        "code".to_string(),
+        ConstValue::String(INTERNAL_SERVER_ERROR.to_string()),
+    );
+    error
+        .extensions
+        .insert("correlationId".to_string(), ConstValue::String(correlation_id));
+    Response {
+        data: ConstValue::Null,
+        errors: vec![error],
+        extensions: Default::default(),
+        headers: Default::default(),
+    }
+}
+
+/// Run a request, answering with a 500 GraphQL error instead of tearing
+/// down the connection if it panics.
+pub(crate) async fn isolate(request: impl std::future::Future<Output = HttpResponse<Body>>) -> HttpResponse<Body> {
+    let correlation_id = next_correlation_id();
+    let res = CORRELATION_ID
+        .scope(correlation_id.clone(), AssertUnwindSafe(request).catch_unwind())
+        .await;
+    match res {
+        Ok(resp) => resp,
+        Err(_) => HttpResponse::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&panic_response(correlation_id)).unwrap().into())
+            .unwrap(),
+    }
+}
+
+/// Stream the payloads of a request, ending it with the GraphQL error made
+/// into a payload by `error_payload` instead of tearing down the connection
+/// if it panics.
+///
+/// `error_payload` is told whether payloads were already streamed.
+pub(crate) fn isolate_stream<T>(
+    stream: impl Stream<Item = T>,
+    error_payload: impl Fn(Response, bool) -> T,
+) -> impl Stream<Item = T> {
+    let correlation_id = next_correlation_id();
+    let mut stream = Some(Box::pin(stream));
+    let mut streamed = false;
+    futures_util::stream::poll_fn(move |cx| {
+        let inner = match &mut stream {
+            Some(inner) => inner,
+            None => return Poll::Ready(None),
+        };
+        let res = CORRELATION_ID.sync_scope(correlation_id.clone(), || {
+            std::panic::catch_unwind(AssertUnwindSafe(|| inner.poll_next_unpin(cx)))
+        });
+        match res {
+            Ok(poll) => {
+                streamed |= matches!(poll, Poll::Ready(Some(_)));
+                poll
+            },
+            Err(_) => {
+                stream = None;
+                Poll::Ready(Some(error_payload(panic_response(correlation_id.clone()), streamed)))
+            },
+        }
+    })
+}
+
+/// The incremental payload of a response that panicked, the initial payload
+/// if none was streamed yet.
+pub(crate) fn incremental_panic_payload(response: Response, streamed: bool) -> IncrementalResponse {
+    match streamed {
+        false => IncrementalResponse::Initial {
+            response,
+            has_next: false,
+        },
+        true => IncrementalResponse::Subsequent {
+            incremental: vec![IncrementalPayload {
+                data: ConstValue::Null,
+                path: Vec::new(),
+                label: None,
+                errors: response.errors,
+                extensions: Default::default(),
+            }],
+            has_next: false,
+        },
+    }
+}
@@ -0,0 +1,34 @@
+use clap::Args;
+use graphgate_executor::Parallelism;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ParallelismConfig {
+    /// The most subgraph fetches running at once over all requests, 0 for
+    /// no limit.
+    #[clap(
+        long = "parallelism-max-fetches",
+        env = "PARALLELISM_MAX_FETCHES",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_fetches: usize,
+
+    /// The most subgraph fetches of a single request running at once, 0 for
+    /// no limit.
+    #[clap(
+        long = "parallelism-max-request-fetches",
+        env = "PARALLELISM_MAX_REQUEST_FETCHES",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_request_fetches: usize,
+}
+
+impl ParallelismConfig {
+    pub(crate) fn parallelism(&self) -> Parallelism {
+        let limit = |max: usize| (max > 0).then_some(max);
+        Parallelism::new(limit(self.max_fetches), limit(self.max_request_fetches))
+    }
+}
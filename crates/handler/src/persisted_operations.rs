@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use graphgate_planner::PlanBuilder;
+use graphgate_schema::ComposedSchema;
+use parser::types::DocumentOperations;
+use serde::Deserialize;
+
+/// An operation of a persisted operation manifest.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PersistedOperation {
+    pub id: String,
+
+    #[serde(default)]
+    pub name: Option<String>,
+
+    pub body: String,
+
+    #[serde(default)]
+    pub metadata: PersistedOperationMetadata,
+}
+
+/// The metadata of an operation of a persisted operation manifest.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedOperationMetadata {
+    /// The headers forwarded to the subgraphs for the operation, instead of
+    /// the `forward_headers` of the gateway, so that sensitive headers are
+    /// only forwarded for the operations that need them.
+    #[serde(default)]
+    pub forward_headers: Option<Vec<String>>,
+}
+
+/// A persisted operation that cannot be executed against the composed schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidPersistedOperation {
+    pub id: String,
+    pub errors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Manifest {
+    /// The Apollo persisted query manifest format.
+    Operations { operations: Vec<PersistedOperation> },
+
+    /// A map from operation ids to queries.
+    Map(BTreeMap<String, String>),
+}
+
+/// Parse a persisted operation manifest, either in the Apollo format or a JSON
+/// object mapping operation ids to queries.
+pub fn parse_manifest(manifest: &str) -> serde_json::Result<Vec<PersistedOperation>> {
+    Ok(match serde_json::from_str(manifest)? {
+        Manifest::Operations { operations } => operations,
+        Manifest::Map(operations) => operations
+            .into_iter()
+            .map(|(id, body)| PersistedOperation {
+                id,
+                name: None,
+                body,
+                metadata: Default::default(),
+            })
+            .collect(),
+    })
+}
+
+/// Plan every persisted operation against a schema, returning those that
+/// failed.
+pub(crate) fn check_persisted_operations(
+    schema: &ComposedSchema,
+    operations: &[PersistedOperation],
+) -> Vec<InvalidPersistedOperation> {
+    let mut invalid = Vec::new();
+    for operation in operations {
+        let document = match parser::parse_query(&operation.body) {
+            Ok(document) => document,
+            Err(err) => {
+                invalid.push(InvalidPersistedOperation {
+                    id: operation.id.clone(),
+                    errors: vec![err.to_string()],
+                });
+                continue;
+            },
+        };
+
+        // The planner expects the operation to exist.
+        let operation_name = match &document.operations {
+            DocumentOperations::Multiple(operations) if operations.len() > 1 => match &operation.name {
+                Some(name) if operations.contains_key(name.as_str()) => Some(name.clone()),
+                _ => {
+                    invalid.push(InvalidPersistedOperation {
+                        id: operation.id.clone(),
+                        errors: vec!["Unknown operation.".to_string()],
+                    });
+                    continue;
+                },
+            },
+            _ => None,
+        };
+
+        let mut plan_builder = PlanBuilder::new(schema, document);
+        if let Some(operation_name) = operation_name {
+            plan_builder = plan_builder.operation_name(operation_name);
+        }
+        if let Err(resp) = plan_builder.plan() {
+            invalid.push(InvalidPersistedOperation {
+                id: operation.id.clone(),
+                errors: resp.errors.into_iter().map(|err| err.message).collect(),
+            });
+        }
+    }
+    invalid
+}
@@ -0,0 +1,267 @@
+use std::sync::{Arc, Mutex};
+
+use clap::Args;
+use graphgate_planner::Request;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    cache_key::query_hash,
+    cache_stats::{CacheStats, HitCounter},
+    handler::RequestError,
+};
+
+/// The error code of the requests whose persisted query is not in the cache,
+/// to be sent again with the query.
+pub const PERSISTED_QUERY_NOT_FOUND: &str = "PERSISTED_QUERY_NOT_FOUND";
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct PersistedQueryConfig {
+    /// The number of queries kept in memory, the least recently used are
+    /// evicted first.
+    #[clap(
+        id = "persisted_queries_cache_size",
+        long = "persisted-queries-cache-size",
+        env = "PERSISTED_QUERIES_CACHE_SIZE",
+        default_value_t = 1000
+    )]
+    #[serde(default = "default_cache_size")]
+    pub cache_size: usize,
+
+    /// Store the queries in the Redis server at this URL instead, such as
+    /// `redis://127.0.0.1:6379`, so that every gateway replica knows them.
+    /// Requires the `redis` feature.
+    #[clap(
+        id = "persisted_queries_redis_url",
+        long = "persisted-queries-redis-url",
+        env = "PERSISTED_QUERIES_REDIS_URL"
+    )]
+    #[serde(default)]
+    pub redis_url: Option<String>,
+
+    /// How long the queries are kept in Redis, 0 to keep them.
+    #[clap(
+        id = "persisted_queries_ttl_secs",
+        long = "persisted-queries-ttl-secs",
+        env = "PERSISTED_QUERIES_TTL_SECS",
+        default_value_t = 86400
+    )]
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+impl Default for PersistedQueryConfig {
+    fn default() -> Self {
+        Self {
+            cache_size: default_cache_size(),
+            redis_url: None,
+            ttl_secs: default_ttl_secs(),
+        }
+    }
+}
+
+impl PersistedQueryConfig {
+    /// Create the cache the queries are stored in.
+    pub fn create_cache(&self) -> anyhow::Result<Arc<dyn PersistedQueryCache>> {
+        match &self.redis_url {
+            #[cfg(feature = "redis")]
+            Some(url) => Ok(Arc::new(RedisPersistedQueryCache::new(url, self.ttl_secs)?)),
+            #[cfg(not(feature = "redis"))]
+            Some(_) => anyhow::bail!("Storing persisted queries in Redis requires the `redis` feature."),
+            None => Ok(Arc::new(MemoryPersistedQueryCache::new(self.cache_size))),
+        }
+    }
+}
+
+/// Stores the queries of the Automatic Persisted Queries protocol by the
+/// SHA-256 hash of their text.
+#[async_trait::async_trait]
+pub trait PersistedQueryCache: Send + Sync {
+    async fn get(&self, hash: &str) -> anyhow::Result<Option<String>>;
+
+    async fn insert(&self, hash: &str, query: &str) -> anyhow::Result<()>;
+
+    /// Remove the query stored under `hash`, returning whether there was
+    /// one.
+    async fn evict(&self, hash: &str) -> anyhow::Result<bool>;
+
+    /// The usage of the cache, with its `top` most hit queries.
+    fn stats(&self, top: usize) -> CacheStats;
+}
+
+/// Keeps the most recently used queries in memory.
+pub struct MemoryPersistedQueryCache {
+    capacity: usize,
+    /// The queries and their hits by hash.
+    queries: Mutex<IndexMap<String, (String, u64)>>,
+    counter: HitCounter,
+}
+
+impl MemoryPersistedQueryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            queries: Default::default(),
+            counter: Default::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistedQueryCache for MemoryPersistedQueryCache {
+    async fn get(&self, hash: &str) -> anyhow::Result<Option<String>> {
+        let mut queries = self.queries.lock().unwrap();
+        let index = queries.get_index_of(hash);
+        self.counter.record(index.is_some());
+        let index = match index {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let last = queries.len() - 1;
+        queries.move_index(index, last);
+        let (query, hits) = &mut queries[last];
+        *hits += 1;
+        Ok(Some(query.clone()))
+    }
+
+    async fn insert(&self, hash: &str, query: &str) -> anyhow::Result<()> {
+        let mut queries = self.queries.lock().unwrap();
+        queries.shift_remove(hash);
+        if queries.len() >= self.capacity {
+            queries.shift_remove_index(0);
+        }
+        queries.insert(hash.to_string(), (query.to_string(), 0));
+        Ok(())
+    }
+
+    async fn evict(&self, hash: &str) -> anyhow::Result<bool> {
+        Ok(self.queries.lock().unwrap().shift_remove(hash).is_some())
+    }
+
+    fn stats(&self, top: usize) -> CacheStats {
+        let queries = self.queries.lock().unwrap();
+        self.counter.memory_stats(
+            queries
+                .iter()
+                .map(|(hash, (query, hits))| (hash.as_str(), query.len(), *hits)),
+            top,
+        )
+    }
+}
+
+/// Keeps the queries in a Redis server shared by the gateway replicas.
+#[cfg(feature = "redis")]
+pub struct RedisPersistedQueryCache {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::MultiplexedConnection>,
+    ttl_secs: u64,
+    counter: HitCounter,
+}
+
+#[cfg(feature = "redis")]
+impl RedisPersistedQueryCache {
+    pub fn new(url: &str, ttl_secs: u64) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: Default::default(),
+            ttl_secs,
+            counter: Default::default(),
+        })
+    }
+
+    async fn connection(&self) -> anyhow::Result<redis::aio::MultiplexedConnection> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await?;
+        Ok(connection.clone())
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait::async_trait]
+impl PersistedQueryCache for RedisPersistedQueryCache {
+    async fn get(&self, hash: &str) -> anyhow::Result<Option<String>> {
+        let mut connection = self.connection().await?;
+        let query: Option<String> = redis::cmd("GET")
+            .arg(format!("graphgate:apq:{}", hash))
+            .query_async(&mut connection)
+            .await?;
+        self.counter.record(query.is_some());
+        Ok(query)
+    }
+
+    async fn insert(&self, hash: &str, query: &str) -> anyhow::Result<()> {
+        let mut connection = self.connection().await?;
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(format!("graphgate:apq:{}", hash)).arg(query);
+        if self.ttl_secs > 0 {
+            cmd.arg("EX").arg(self.ttl_secs);
+        }
+        cmd.query_async::<()>(&mut connection).await?;
+        Ok(())
+    }
+
+    async fn evict(&self, hash: &str) -> anyhow::Result<bool> {
+        let mut connection = self.connection().await?;
+        let removed: u64 = redis::cmd("DEL")
+            .arg(format!("graphgate:apq:{}", hash))
+            .query_async(&mut connection)
+            .await?;
+        Ok(removed > 0)
+    }
+
+    fn stats(&self, _top: usize) -> CacheStats {
+        self.counter.stats()
+    }
+}
+
+/// Resolve the query of a request sent with the hash of the
+/// `persistedQuery` extension, storing the queries sent with their hash.
+///
+/// Cache failures are logged and the query treated as unknown, so that the
+/// clients send it again.
+pub(crate) async fn resolve_persisted_query(
+    cache: Option<&dyn PersistedQueryCache>,
+    mut request: Request,
+    hash: Option<&str>,
+) -> Result<Request, RequestError> {
+    let hash = match hash {
+        Some(hash) => hash,
+        None => return Ok(request),
+    };
+
+    if !request.query.is_empty() {
+        if query_hash(&request.query) != hash {
+            return Err(RequestError::PersistedQueryHashMismatch);
+        }
+        if let Some(cache) = cache {
+            if let Err(err) = cache.insert(hash, &request.query).await {
+                tracing::warn!(error = %err, "Failed to store persisted query.");
+            }
+        }
+        return Ok(request);
+    }
+
+    let cache = cache.ok_or(RequestError::PersistedQueryNotSupported)?;
+    match cache.get(hash).await {
+        Ok(Some(query)) => {
+            request.query = query;
+            Ok(request)
+        },
+        Ok(None) => Err(RequestError::PersistedQueryNotFound),
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load persisted query.");
+            Err(RequestError::PersistedQueryNotFound)
+        },
+    }
+}
+
+fn default_cache_size() -> usize {
+    1000
+}
+
+fn default_ttl_secs() -> u64 {
+    86400
+}
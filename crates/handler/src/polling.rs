@@ -0,0 +1,74 @@
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::time::Duration;
+
+/// How often the SDLs of the services are polled, recomposing the schema
+/// when they change.
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct PollingConfig {
+    /// Seconds between polls of the SDLs.
+    #[clap(
+        id = "polling_interval_secs",
+        long = "polling-interval-secs",
+        env = "POLLING_INTERVAL_SECS",
+        default_value_t = 30
+    )]
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Seconds until a failed poll is retried, doubled after every
+    /// consecutive failure.
+    #[clap(long = "polling-retry-secs", env = "POLLING_RETRY_SECS", default_value_t = 3)]
+    #[serde(default = "default_retry_secs")]
+    pub retry_secs: u64,
+
+    /// The longest delay between retries of failed polls, in seconds.
+    #[clap(
+        long = "polling-max-backoff-secs",
+        env = "POLLING_MAX_BACKOFF_SECS",
+        default_value_t = 30
+    )]
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_interval_secs(),
+            retry_secs: default_retry_secs(),
+            max_backoff_secs: default_max_backoff_secs(),
+        }
+    }
+}
+
+impl PollingConfig {
+    /// Poll every `interval`, retrying failed polls at the same pace.
+    pub fn fixed(interval: Duration) -> Self {
+        let secs = interval.as_secs().max(1);
+        Self {
+            interval_secs: secs,
+            retry_secs: secs,
+            max_backoff_secs: secs,
+        }
+    }
+}
+
+/// The delay until the poll following `failures` consecutive failed polls.
+pub(crate) fn backoff(retry: Duration, max_backoff: Duration, failures: u32) -> Duration {
+    let factor = 1u32.checked_shl(failures.saturating_sub(1)).unwrap_or(u32::MAX);
+    retry.saturating_mul(factor).min(max_backoff)
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_retry_secs() -> u64 {
+    3
+}
+
+fn default_max_backoff_secs() -> u64 {
+    30
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use clap::Args;
+use graphgate_executor::RateLimitedError;
+use http::HeaderMap;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use crate::{constants::KEY_SERVICE, metrics::METRICS};
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct RateLimitConfig {
+    /// How long a request to a rate limited subgraph may wait for the limit
+    /// to expire, in milliseconds. Requests that would wait longer fail
+    /// immediately.
+    #[clap(long = "rate-limit-max-wait-ms", env = "RATE_LIMIT_MAX_WAIT_MS", default_value_t = 0)]
+    #[serde(default)]
+    pub max_wait_ms: u64,
+
+    /// How long a subgraph responding with 429 is backed off if it does not
+    /// send `Retry-After`, in seconds.
+    #[clap(
+        long = "rate-limit-default-retry-after-secs",
+        env = "RATE_LIMIT_DEFAULT_RETRY_AFTER_SECS",
+        default_value_t = 1
+    )]
+    #[serde(default = "default_retry_after_secs")]
+    pub default_retry_after_secs: u64,
+
+    /// The longest backoff honored from `Retry-After`, in seconds.
+    #[clap(
+        long = "rate-limit-max-retry-after-secs",
+        env = "RATE_LIMIT_MAX_RETRY_AFTER_SECS",
+        default_value_t = 60
+    )]
+    #[serde(default = "default_max_retry_after_secs")]
+    pub max_retry_after_secs: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_wait_ms: 0,
+            default_retry_after_secs: default_retry_after_secs(),
+            max_retry_after_secs: default_max_retry_after_secs(),
+        }
+    }
+}
+
+/// Tracks the subgraphs that asked the gateway to back off.
+#[derive(Default)]
+pub(crate) struct RateLimiter {
+    config: RwLock<RateLimitConfig>,
+    limited_until: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn set_config(&self, config: RateLimitConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// Wait until a request may be sent to the service, failing if the
+    /// service is rate limited for longer than the configured wait.
+    pub(crate) async fn acquire(&self, service: &str) -> Result<(), RateLimitedError> {
+        let limited_until = match self.limited_until.lock().unwrap().get(service) {
+            Some(limited_until) if *limited_until > Instant::now() => *limited_until,
+            _ => return Ok(()),
+        };
+
+        let retry_after = limited_until - Instant::now();
+        let max_wait = Duration::from_millis(self.config.read().unwrap().max_wait_ms);
+        if retry_after > max_wait {
+            METRICS
+                .subgraph_shed_counter
+                .add(1, &[KEY_SERVICE.string(service.to_string())]);
+            return Err(RateLimitedError {
+                service: service.to_string(),
+                retry_after,
+            });
+        }
+        tokio::time::sleep_until(limited_until).await;
+        Ok(())
+    }
+
+    /// Back off a service that responded with 429, returning the backoff.
+    pub(crate) fn limit(&self, service: &str, headers: &HeaderMap) -> Duration {
+        let retry_after = {
+            let config = self.config.read().unwrap();
+            parse_retry_after(headers)
+                .unwrap_or(Duration::from_secs(config.default_retry_after_secs))
+                .min(Duration::from_secs(config.max_retry_after_secs))
+        };
+        METRICS
+            .subgraph_rate_limited_counter
+            .add(1, &[KEY_SERVICE.string(service.to_string())]);
+
+        let until = Instant::now() + retry_after;
+        let mut limited_until = self.limited_until.lock().unwrap();
+        let entry = limited_until.entry(service.to_string()).or_insert(until);
+        *entry = (*entry).max(until);
+        retry_after
+    }
+}
+
+/// Parse `Retry-After`, which is either a number of seconds or an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(http::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default())
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+fn default_max_retry_after_secs() -> u64 {
+    60
+}
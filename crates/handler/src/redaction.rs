@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+
+use graphgate_schema::{ComposedSchema, MetaType, TypeExt};
+use indexmap::IndexMap;
+use parser::types::{DocumentOperations, ExecutableDocument, OperationType, Selection, SelectionSet};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use value::{ConstValue, Name};
+
+use crate::{context_injection::RequestContext, cost::has_scope};
+
+/// The value of the string fields redacted with [`RedactionAction::Mask`].
+pub const REDACTION_MASK: &str = "****";
+
+/// Redacts a field in the responses to the requests whose JWT has none of
+/// the `allow_scopes`.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct RedactionRule {
+    /// The redacted field, for example `User.email`.
+    ///
+    /// A field of an interface is redacted in its implementations too.
+    pub field: String,
+
+    pub action: RedactionAction,
+
+    /// The scopes of the JWT, from its `scope` or `scp` claim, allowed to
+    /// see the field unredacted.
+    #[serde(default)]
+    pub allow_scopes: Vec<String>,
+}
+
+/// How a [`RedactionRule`] redacts the values of its field.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionAction {
+    /// Remove the field from the response.
+    Remove,
+
+    /// Replace the values of a `String` or `ID` field with `****`.
+    Mask,
+
+    /// Replace the values of a `String` or `ID` field with their hexadecimal
+    /// SHA-256 hash, so that equal values can still be matched.
+    Hash,
+}
+
+impl RedactionAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            RedactionAction::Remove => "remove",
+            RedactionAction::Mask => "mask",
+            RedactionAction::Hash => "hash",
+        }
+    }
+}
+
+impl RedactionRule {
+    /// Checks that the field exists in the composed schema, and is a `String`
+    /// or `ID` field unless it is removed, so that the redacted values keep
+    /// the type of the field.
+    pub fn validate(&self, schema: &ComposedSchema) -> Result<(), String> {
+        let (type_name, field_name) = self
+            .field
+            .split_once('.')
+            .ok_or_else(|| format!("Invalid redacted field \"{}\", expected \"Type.field\".", self.field))?;
+        let field = schema
+            .types
+            .get(type_name)
+            .and_then(|ty| ty.fields.get(field_name))
+            .ok_or_else(|| format!("Unknown redacted field \"{}\".", self.field))?;
+        match (self.action, field.ty.concrete_typename()) {
+            (RedactionAction::Remove, _) | (_, "String" | "ID") => Ok(()),
+            (action, ty) => Err(format!(
+                "The redacted field \"{}\" of type \"{}\" cannot be redacted with \"{}\", only String and ID fields \
+                 can.",
+                self.field,
+                ty,
+                action.as_str()
+            )),
+        }
+    }
+
+    fn applies(&self, context: &RequestContext) -> bool {
+        !self
+            .allow_scopes
+            .iter()
+            .any(|scope| has_scope(context.claims.as_ref(), scope))
+    }
+
+    fn matches(
+        &self,
+        schema: &ComposedSchema,
+        parent_type: &MetaType,
+        runtime_type: Option<&str>,
+        field: &str,
+    ) -> bool {
+        let (type_name, field_name) = match self.field.split_once('.') {
+            Some(coordinate) => coordinate,
+            None => return false,
+        };
+        if field_name != field {
+            return false;
+        }
+        let ty = runtime_type
+            .and_then(|name| schema.types.get(name))
+            .unwrap_or(parent_type);
+        if ty.name == type_name || ty.implements.contains(type_name) {
+            return true;
+        }
+
+        // Without `__typename`, the field of an abstract type is redacted if
+        // any of its possible types is.
+        runtime_type.is_none() &&
+            schema
+                .types
+                .get(type_name)
+                .map(|rule_type| rule_type.type_overlap(parent_type))
+                .unwrap_or_default()
+    }
+}
+
+/// Redacts the response of an operation with the rules applying to the
+/// request.
+pub(crate) struct Redactor {
+    rules: Vec<RedactionRule>,
+    document: ExecutableDocument,
+    operation_name: Option<String>,
+}
+
+impl Redactor {
+    /// The redactor of an operation, unless no rule applies to the request.
+    pub(crate) fn new(
+        rules: &[RedactionRule],
+        context: &RequestContext,
+        document: &ExecutableDocument,
+        operation_name: Option<&str>,
+    ) -> Option<Self> {
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.applies(context))
+            .cloned()
+            .collect::<Vec<_>>();
+        if rules.is_empty() {
+            return None;
+        }
+        Some(Self {
+            rules,
+            document: document.clone(),
+            operation_name: operation_name.map(ToString::to_string),
+        })
+    }
+
+    /// Redact the data of a response of the operation.
+    pub(crate) fn redact(&self, schema: &ComposedSchema, data: &mut ConstValue) {
+        let operation = match (&self.document.operations, &self.operation_name) {
+            (DocumentOperations::Single(operation), _) => operation,
+            (DocumentOperations::Multiple(operations), Some(name)) => match operations.get(name.as_str()) {
+                Some(operation) => operation,
+                None => return,
+            },
+            (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+                operations.values().next().unwrap()
+            },
+            _ => return,
+        };
+        let root_type = match operation.node.ty {
+            OperationType::Query => Some(schema.query_type()),
+            OperationType::Mutation => schema.mutation_type(),
+            OperationType::Subscription => schema.subscription_type(),
+        };
+        if let (Some(root_type), ConstValue::Object(object)) = (root_type.and_then(|name| schema.types.get(name)), data)
+        {
+            self.redact_object(
+                schema,
+                root_type,
+                &operation.node.selection_set.node,
+                object,
+                &mut HashSet::new(),
+            );
+        }
+    }
+
+    fn redact_object<'a>(
+        &'a self,
+        schema: &ComposedSchema,
+        parent_type: &MetaType,
+        selection_set: &'a SelectionSet,
+        object: &mut IndexMap<Name, ConstValue>,
+        spreads: &mut HashSet<&'a str>,
+    ) {
+        let runtime_type = match object.get("__typename") {
+            Some(ConstValue::String(typename)) => Some(typename.clone()),
+            _ => None,
+        };
+        self.redact_selection_set(
+            schema,
+            parent_type,
+            runtime_type.as_deref(),
+            selection_set,
+            object,
+            &mut HashSet::new(),
+            spreads,
+        );
+    }
+
+    /// Redact the fields of an object selected by a selection set, skipping
+    /// the `redacted` response keys so that a field selected twice is not
+    /// hashed twice.
+    #[allow(clippy::too_many_arguments)]
+    fn redact_selection_set<'a>(
+        &'a self,
+        schema: &ComposedSchema,
+        parent_type: &MetaType,
+        runtime_type: Option<&str>,
+        selection_set: &'a SelectionSet,
+        object: &mut IndexMap<Name, ConstValue>,
+        redacted: &mut HashSet<&'a str>,
+        spreads: &mut HashSet<&'a str>,
+    ) {
+        for selection in &selection_set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    let field = &field.node;
+                    let key = field.response_key().node.as_str();
+                    let name = field.name.node.as_str();
+                    if redacted.contains(key) {
+                        continue;
+                    }
+                    let rule = self
+                        .rules
+                        .iter()
+                        .find(|rule| rule.matches(schema, parent_type, runtime_type, name));
+                    match rule {
+                        Some(rule) if rule.action == RedactionAction::Remove => {
+                            object.shift_remove(key);
+                            redacted.insert(key);
+                        },
+                        Some(rule) => {
+                            if let Some(value) = object.get_mut(key) {
+                                redact_value(rule.action, value);
+                            }
+                            redacted.insert(key);
+                        },
+                        None => {
+                            let field_type = parent_type
+                                .fields
+                                .get(name)
+                                .and_then(|field| schema.concrete_type_by_name(&field.ty));
+                            if let (Some(field_type), Some(value)) = (field_type, object.get_mut(key)) {
+                                self.redact_nested(schema, field_type, &field.selection_set.node, value, spreads);
+                            }
+                        },
+                    }
+                },
+                Selection::InlineFragment(fragment) => {
+                    let fragment = &fragment.node;
+                    let fragment_type = match &fragment.type_condition {
+                        Some(type_condition) => schema.types.get(type_condition.node.on.node.as_str()),
+                        None => Some(parent_type),
+                    };
+                    if let Some(fragment_type) = fragment_type {
+                        if fragment_applies(fragment_type, runtime_type) {
+                            self.redact_selection_set(
+                                schema,
+                                fragment_type,
+                                runtime_type,
+                                &fragment.selection_set.node,
+                                object,
+                                redacted,
+                                spreads,
+                            );
+                        }
+                    }
+                },
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.node.fragment_name.node.as_str();
+                    let fragment = match self.document.fragments.get(name) {
+                        Some(fragment) => &fragment.node,
+                        None => continue,
+                    };
+                    // A fragment spread into itself is invalid, and would not
+                    // terminate.
+                    if !spreads.insert(name) {
+                        continue;
+                    }
+                    if let Some(fragment_type) = schema.types.get(fragment.type_condition.node.on.node.as_str()) {
+                        if fragment_applies(fragment_type, runtime_type) {
+                            self.redact_selection_set(
+                                schema,
+                                fragment_type,
+                                runtime_type,
+                                &fragment.selection_set.node,
+                                object,
+                                redacted,
+                                spreads,
+                            );
+                        }
+                    }
+                    spreads.remove(name);
+                },
+            }
+        }
+    }
+
+    fn redact_nested<'a>(
+        &'a self,
+        schema: &ComposedSchema,
+        ty: &MetaType,
+        selection_set: &'a SelectionSet,
+        value: &mut ConstValue,
+        spreads: &mut HashSet<&'a str>,
+    ) {
+        match value {
+            ConstValue::Object(object) => self.redact_object(schema, ty, selection_set, object, spreads),
+            ConstValue::List(values) => {
+                for value in values {
+                    self.redact_nested(schema, ty, selection_set, value, spreads);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Whether the selections of a fragment apply to an object of the runtime
+/// type, assumed if it is unknown.
+fn fragment_applies(fragment_type: &MetaType, runtime_type: Option<&str>) -> bool {
+    match runtime_type {
+        Some(runtime_type) => fragment_type.name == runtime_type || fragment_type.is_possible_type(runtime_type),
+        None => true,
+    }
+}
+
+fn redact_value(action: RedactionAction, value: &mut ConstValue) {
+    match value {
+        ConstValue::Null => {},
+        ConstValue::List(values) => {
+            for value in values {
+                redact_value(action, value);
+            }
+        },
+        _ => {
+            // The values of `ID` fields may be numbers, hashed as their JSON.
+            *value = match (action, &*value) {
+                (RedactionAction::Hash, ConstValue::String(s)) => {
+                    ConstValue::String(format!("{:x}", Sha256::digest(s.as_bytes())))
+                },
+                (RedactionAction::Hash, value) => ConstValue::String(format!(
+                    "{:x}",
+                    Sha256::digest(serde_json::to_string(value).unwrap_or_default().as_bytes())
+                )),
+                _ => ConstValue::String(REDACTION_MASK.to_string()),
+            }
+        },
+    }
+}
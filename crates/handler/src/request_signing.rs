@@ -0,0 +1,137 @@
+use std::{collections::HashMap, fmt, time::Duration};
+
+use anyhow::Context;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use http::{HeaderMap, HeaderValue};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::cache_key::sort_keys;
+
+/// The header carrying the signature of a request, as `sha256=<hex>`.
+pub const SIGNATURE_HEADER: &str = "x-graphgate-signature";
+
+/// The header carrying the time a request was signed at, in seconds since
+/// the Unix epoch.
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "x-graphgate-timestamp";
+
+/// The header carrying the id of the key a request was signed with.
+pub const SIGNATURE_KEY_ID_HEADER: &str = "x-graphgate-key-id";
+
+/// Signs the requests to a subgraph with an HMAC-SHA256 of their timestamp
+/// and canonical body, so that the subgraph can reject requests that are
+/// not sent by the gateway or are replayed.
+///
+/// The key is rotated by changing `key_id` and `secret` in the config file
+/// while the subgraph accepts both the previous and the new key.
+#[derive(Clone, Deserialize, Eq, JsonSchema, PartialEq)]
+pub struct RequestSigningConfig {
+    /// The id of the key, sent with the signature so that the subgraph can
+    /// pick the secret to verify it with.
+    pub key_id: String,
+
+    pub secret: String,
+}
+
+impl fmt::Debug for RequestSigningConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestSigningConfig")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RequestSigningConfig {
+    /// The headers signing a canonical request body sent now.
+    pub(crate) fn sign(&self, body: &[u8]) -> anyhow::Result<HeaderMap> {
+        let timestamp = Utc::now().timestamp();
+        let signature = signature(self.secret.as_bytes(), timestamp, body);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&format!("sha256={}", signature))?,
+        );
+        headers.insert(SIGNATURE_TIMESTAMP_HEADER, HeaderValue::from(timestamp));
+        headers.insert(
+            SIGNATURE_KEY_ID_HEADER,
+            HeaderValue::from_str(&self.key_id).context("Invalid signing key id.")?,
+        );
+        Ok(headers)
+    }
+}
+
+/// Why the signature of a request was rejected.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum SignatureError {
+    #[error("the request is not signed")]
+    MissingSignature,
+
+    #[error("unknown signing key \"{0}\"")]
+    UnknownKey(String),
+
+    #[error("the request was signed {0} seconds away from the current time")]
+    ClockSkew(u64),
+
+    #[error("invalid signature")]
+    InvalidSignature,
+}
+
+/// Verifies the signature of a request received from the gateway, with the
+/// secrets of the accepted keys by key id.
+///
+/// Requests signed more than `max_skew` before or after the current time are
+/// rejected, which tolerates clocks that drift apart by less than that while
+/// limiting how long a captured request can be replayed.
+pub fn verify_request_signature(
+    headers: &HeaderMap,
+    body: &[u8],
+    secrets: &HashMap<String, String>,
+    max_skew: Duration,
+) -> Result<(), SignatureError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(received), Some(timestamp), Some(key_id)) = (
+        header(SIGNATURE_HEADER).and_then(|signature| signature.strip_prefix("sha256=")),
+        header(SIGNATURE_TIMESTAMP_HEADER).and_then(|timestamp| timestamp.parse::<i64>().ok()),
+        header(SIGNATURE_KEY_ID_HEADER),
+    ) else {
+        return Err(SignatureError::MissingSignature);
+    };
+    let secret = secrets
+        .get(key_id)
+        .ok_or_else(|| SignatureError::UnknownKey(key_id.to_string()))?;
+
+    let skew = Utc::now().timestamp().abs_diff(timestamp);
+    if skew > max_skew.as_secs() {
+        return Err(SignatureError::ClockSkew(skew));
+    }
+
+    let body = canonical_body(body).map_err(|_| SignatureError::InvalidSignature)?;
+    let received = hex::decode(received).map_err(|_| SignatureError::InvalidSignature)?;
+    // Compared in constant time, not to leak how much of the signature is
+    // right.
+    mac(secret.as_bytes(), timestamp, &body)
+        .verify_slice(&received)
+        .map_err(|_| SignatureError::InvalidSignature)
+}
+
+/// The JSON body with its object keys sorted and without whitespace, so
+/// that it is signed and verified the same however it is serialized.
+pub(crate) fn canonical_body(body: &[u8]) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&sort_keys(serde_json::from_slice(body)?))
+}
+
+/// The HMAC-SHA256 of `<timestamp>.<body>` in hex.
+fn signature(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+}
+
+/// The HMAC-SHA256 of `<timestamp>.<body>`.
+fn mac(secret: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    mac
+}
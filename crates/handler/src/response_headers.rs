@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use indexmap::IndexMap;
+use opentelemetry::{trace::TraceContextExt, Context, Key};
+use value::{ConstValue, Name};
+
+/// The selected headers of subgraph responses, by header name.
+pub(crate) type Headers = IndexMap<String, Vec<String>>;
+
+/// Records the selected headers of the subgraph responses of a request, such
+/// as `x-cache` or `server-timing`, as attributes of the fetch spans.
+pub(crate) struct ResponseHeaders {
+    names: Arc<Vec<String>>,
+    /// The headers of each response, in the order they arrived.
+    received: Mutex<Vec<(String, Headers)>>,
+}
+
+impl ResponseHeaders {
+    /// Record the headers of `names`, which are lowercase.
+    pub(crate) fn new(names: Arc<Vec<String>>) -> Self {
+        Self {
+            names,
+            received: Default::default(),
+        }
+    }
+
+    /// Record the selected headers of a response of `service` on the span of
+    /// the fetch in `cx`.
+    pub(crate) fn record(&self, service: &str, headers: Option<&HashMap<String, Vec<String>>>, cx: &Context) {
+        let headers = match headers {
+            Some(headers) if !self.names.is_empty() => headers,
+            _ => return,
+        };
+        let selected = self
+            .names
+            .iter()
+            .filter_map(|name| Some((name.clone(), headers.get(name)?.clone())))
+            .collect::<Headers>();
+        if selected.is_empty() {
+            return;
+        }
+
+        for (name, values) in &selected {
+            cx.span()
+                .set_attribute(Key::new(format!("graphgate.response_header.{}", name)).string(values.join(", ")));
+        }
+        self.received.lock().unwrap().push((service.to_string(), selected));
+    }
+
+    /// The `subgraphResponseHeaders` extension of the response, listing the
+    /// recorded headers by service.
+    pub(crate) fn extension(&self) -> Option<ConstValue> {
+        let received = self.received.lock().unwrap();
+        if received.is_empty() {
+            return None;
+        }
+        Some(ConstValue::List(
+            received
+                .iter()
+                .map(|(service, headers)| {
+                    let mut obj = IndexMap::new();
+                    obj.insert(Name::new("service"), ConstValue::String(service.clone()));
+                    obj.insert(Name::new("headers"), headers_value(headers));
+                    ConstValue::Object(obj)
+                })
+                .collect(),
+        ))
+    }
+}
+
+pub(crate) fn headers_value(headers: &Headers) -> ConstValue {
+    ConstValue::Object(
+        headers
+            .iter()
+            .map(|(name, values)| {
+                let values = values.iter().cloned().map(ConstValue::String).collect();
+                (Name::new(name), ConstValue::List(values))
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use clap::Args;
+use graphgate_executor::RESPONSE_TOO_LARGE;
+use graphgate_planner::{Response, ServerError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::ConstValue;
+
+use crate::metrics::METRICS;
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ResponseLimitConfig {
+    /// The largest response sent to a client, in bytes, 0 for no limit.
+    ///
+    /// The subgraph responses of a request count against the limit as they
+    /// arrive, so that the gateway stops fetching before merging them.
+    #[clap(
+        long = "response-limit-max-bytes",
+        env = "RESPONSE_LIMIT_MAX_BYTES",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_bytes: usize,
+
+    /// The largest response of a single subgraph fetch, in bytes, 0 for no
+    /// limit.
+    #[clap(
+        long = "response-limit-max-subgraph-bytes",
+        env = "RESPONSE_LIMIT_MAX_SUBGRAPH_BYTES",
+        default_value_t = 0
+    )]
+    #[serde(default)]
+    pub max_subgraph_bytes: usize,
+}
+
+/// The bytes of the subgraph responses of a request, counted against the
+/// limits.
+#[derive(Default)]
+pub(crate) struct ResponseBudget {
+    max_bytes: Option<usize>,
+    max_subgraph_bytes: Option<usize>,
+    received: AtomicUsize,
+    exceeded: AtomicBool,
+}
+
+impl ResponseBudget {
+    pub(crate) fn new(config: &ResponseLimitConfig) -> Self {
+        let limit = |max: usize| (max > 0).then_some(max);
+        Self {
+            max_bytes: limit(config.max_bytes),
+            max_subgraph_bytes: limit(config.max_subgraph_bytes),
+            ..Default::default()
+        }
+    }
+
+    /// The most bytes a subgraph response may have.
+    pub(crate) fn max_subgraph_bytes(&self) -> Option<usize> {
+        self.max_subgraph_bytes
+    }
+
+    /// Count a subgraph response, failing once the responses of the request
+    /// exceed the limit.
+    pub(crate) fn receive(&self, bytes: usize) -> anyhow::Result<()> {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return Ok(()),
+        };
+        if self.received.fetch_add(bytes, Ordering::Relaxed) + bytes > max_bytes {
+            self.exceeded.store(true, Ordering::Relaxed);
+            anyhow::bail!("the responses of the request exceed the limit of {} bytes", max_bytes);
+        }
+        Ok(())
+    }
+
+    /// Check the size of the merged response, returning the error response
+    /// to send instead if it, or the subgraph responses, exceed the limit.
+    pub(crate) fn check(&self, body_len: usize) -> Option<Response> {
+        let max_bytes = self.max_bytes?;
+        if !self.exceeded.load(Ordering::Relaxed) && body_len <= max_bytes {
+            return None;
+        }
+        METRICS.response_too_large_counter.add(1, &[]);
+
+        let mut error = ServerError::new(format!("The response exceeds the limit of {} bytes.", max_bytes));
+        error
+            .extensions
+            .insert("code".to_string(), ConstValue::String(RESPONSE_TOO_LARGE.to_string()));
+        Some(Response {
+            data: ConstValue::Null,
+            errors: vec![error],
+            extensions: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
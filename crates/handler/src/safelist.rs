@@ -0,0 +1,113 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use clap::Args;
+use graphgate_planner::ServerError;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use value::ConstValue;
+
+use crate::{cache_key::query_hash, persisted_operations::parse_manifest};
+
+/// The error code of the operations rejected for not being in the safelist.
+pub const OPERATION_NOT_SAFELISTED: &str = "OPERATION_NOT_SAFELISTED";
+
+/// The error of an operation that is not in the safelist.
+pub(crate) fn operation_not_safelisted() -> ServerError {
+    let mut error = ServerError::new("The operation is not in the safelist.");
+    error.extensions.insert(
+        "code".to_string(),
+        ConstValue::String(OPERATION_NOT_SAFELISTED.to_string()),
+    );
+    error
+}
+
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct SafelistConfig {
+    /// Reject the operations that are not in the safelist manifest.
+    #[clap(
+        id = "safelist_enabled",
+        long = "safelist-enabled",
+        env = "SAFELIST_ENABLED",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path of the manifest of the allowed operations, either an Apollo
+    /// operation manifest or a file with the SHA-256 hash of an allowed
+    /// operation per line.
+    #[clap(long = "safelist-manifest", env = "SAFELIST_MANIFEST")]
+    #[serde(default)]
+    pub manifest: Option<PathBuf>,
+}
+
+impl SafelistConfig {
+    /// Load the safelist from the manifest, if the config enables it.
+    pub fn load(&self) -> Result<Option<Safelist>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let path = self
+            .manifest
+            .as_ref()
+            .context("The safelist requires a manifest of the allowed operations.")?;
+        let manifest = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the safelist from '{}'.", path.display()))?;
+        let safelist =
+            Safelist::parse(&manifest).with_context(|| format!("Invalid safelist manifest '{}'.", path.display()))?;
+        Ok(Some(safelist))
+    }
+}
+
+/// The SHA-256 hashes of the text of the allowed operations.
+#[derive(Clone, Debug, Default)]
+pub struct Safelist {
+    hashes: HashSet<String>,
+}
+
+impl Safelist {
+    /// Parse a safelist manifest, either a persisted operation manifest, in
+    /// the Apollo format or mapping operation ids to queries, or a file with a
+    /// hash per line.
+    ///
+    /// Empty lines and lines starting with `#` are ignored in hash files.
+    pub fn parse(manifest: &str) -> Result<Self> {
+        if manifest.trim_start().starts_with('{') {
+            let operations = parse_manifest(manifest)?;
+            return Ok(Self {
+                hashes: operations.iter().map(|operation| query_hash(&operation.body)).collect(),
+            });
+        }
+
+        let mut hashes = HashSet::new();
+        for (index, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            anyhow::ensure!(
+                line.len() == 64 && line.chars().all(|c| c.is_ascii_hexdigit()),
+                "Line {} is not a SHA-256 hash.",
+                index + 1
+            );
+            hashes.insert(line.to_ascii_lowercase());
+        }
+        Ok(Self { hashes })
+    }
+
+    /// Returns `true` if the operation text is in the safelist.
+    pub fn contains(&self, query: &str) -> bool {
+        self.hashes.contains(&query_hash(query))
+    }
+
+    /// The number of allowed operations.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns `true` if no operation is allowed.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+}
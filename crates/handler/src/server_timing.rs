@@ -0,0 +1,130 @@
+use std::{fmt::Write, sync::Mutex, time::Duration};
+
+use indexmap::IndexMap;
+
+/// A metric of a `Server-Timing` header.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Metric {
+    dur: Option<f64>,
+    /// The distinct descriptions, such as the cache status of each fetch.
+    desc: Vec<String>,
+}
+
+impl Metric {
+    fn merge(&mut self, dur: Option<f64>, desc: Option<String>) {
+        if let Some(dur) = dur {
+            *self.dur.get_or_insert(0.0) += dur;
+        }
+        if let Some(desc) = desc {
+            if !self.desc.contains(&desc) {
+                self.desc.push(desc);
+            }
+        }
+    }
+}
+
+/// Aggregates the durations of the subgraph fetches of a request, with the
+/// `Server-Timing` metrics the subgraphs report, into the `Server-Timing`
+/// header of the response.
+#[derive(Default)]
+pub(crate) struct ServerTiming {
+    /// The metrics by name, the fetch durations of each service come first.
+    metrics: Mutex<IndexMap<String, Metric>>,
+}
+
+impl ServerTiming {
+    /// Record a fetch from `service` and the metrics of its `Server-Timing`
+    /// headers, prefixed with the service name.
+    pub(crate) fn record(&self, service: &str, duration: Duration, server_timing: Option<&[String]>) {
+        let service = metric_name(service);
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics
+            .entry(service.clone())
+            .or_default()
+            .merge(Some(duration.as_secs_f64() * 1000.0), None);
+        for header in server_timing.unwrap_or_default() {
+            for (name, dur, desc) in parse_server_timing(header) {
+                metrics
+                    .entry(format!("{}-{}", service, metric_name(&name)))
+                    .or_default()
+                    .merge(dur, desc);
+            }
+        }
+    }
+
+    /// The value of the `Server-Timing` header, starting with the duration of
+    /// the whole request.
+    pub(crate) fn header_value(&self, total: Duration) -> String {
+        let mut value = format!("gateway;dur={:.1}", total.as_secs_f64() * 1000.0);
+        for (name, metric) in self.metrics.lock().unwrap().iter() {
+            write!(value, ", {}", name).unwrap();
+            if let Some(dur) = metric.dur {
+                write!(value, ";dur={:.1}", dur).unwrap();
+            }
+            if !metric.desc.is_empty() {
+                write!(value, ";desc=\"{}\"", metric.desc.join(", ").replace(['"', '\\'], "")).unwrap();
+            }
+        }
+        value
+    }
+}
+
+/// Replace the characters not allowed in metric names.
+fn metric_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c) {
+            true => c,
+            false => '-',
+        })
+        .collect()
+}
+
+/// Parse the metrics of a `Server-Timing` header into their names, durations
+/// and descriptions, skipping the malformed ones.
+fn parse_server_timing(header: &str) -> Vec<(String, Option<f64>, Option<String>)> {
+    split_unquoted(header, ',')
+        .into_iter()
+        .filter_map(|metric| {
+            let mut params = split_unquoted(&metric, ';').into_iter();
+            let name = params.next()?.trim().to_string();
+            if name.is_empty() {
+                return None;
+            }
+            let (mut dur, mut desc) = (None, None);
+            for param in params {
+                let (key, value) = match param.split_once('=') {
+                    Some((key, value)) => (key.trim(), value.trim()),
+                    None => continue,
+                };
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+                match key.to_ascii_lowercase().as_str() {
+                    "dur" => dur = value.parse().ok(),
+                    "desc" => desc = Some(value.to_string()),
+                    _ => {},
+                }
+            }
+            Some((name, dur, desc))
+        })
+        .collect()
+}
+
+/// Split `s` at the separators outside of quoted strings.
+fn split_unquoted(s: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(String::new());
+                continue;
+            },
+            _ => {},
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    parts
+}
@@ -1,17 +1,55 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
+    fmt,
     ops::{Deref, DerefMut},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
 };
 
+use anyhow::Context;
+use graphgate_executor::{
+    constants::{KEY_REQUEST_BYTES, KEY_RESPONSE_BYTES},
+    EntityMovedError,
+    RateLimitedError,
+    ResponseTooLargeError,
+    SubgraphStatusError,
+    ENTITY_MOVED_HEADER,
+};
 use graphgate_planner::{Request, Response};
-use http::HeaderMap;
+use http::{
+    header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+    HeaderMap,
+    HeaderName,
+    HeaderValue,
+};
 use once_cell::sync::Lazy;
+use opentelemetry::{trace::TraceContextExt, KeyValue};
+use serde::Deserialize;
 use tracing::instrument;
 
-static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(Default::default);
+use crate::{
+    circuit_breaker::CircuitBreaker,
+    constants::{KEY_HTTP_VERSION, KEY_SERVICE},
+    enum_values::EnumValues,
+    fetcher::PlanNodeContext,
+    header_policy::HeaderPolicy,
+    lenient_errors::parse_lenient_response,
+    metrics::METRICS,
+    oauth2::{OAuth2Config, TokenCache},
+    rate_limit::RateLimiter,
+    request_signing::{canonical_body, RequestSigningConfig},
+    response_limit::ResponseBudget,
+    subgraph_request::SubgraphRequestConfig,
+    upstream::Upstream,
+    websocket::{ConnectionParam, Protocols},
+};
+
+pub(crate) static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(Default::default);
 
 /// Service routing information.
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq)]
 pub struct ServiceRoute {
     /// Service address
     ///
@@ -29,32 +67,326 @@ pub struct ServiceRoute {
 
     pub introspection_path: Option<String>,
 
+    /// The URL the SDL of the service is fetched from, if it is composed
+    /// from another source than the address traffic is sent to.
+    pub schema_url: Option<String>,
+
     pub websocket_path: Option<String>,
+
+    /// The WebSocket protocol of the subscriptions of the service, instead
+    /// of negotiating it.
+    pub websocket_protocol: Option<Protocols>,
+
+    /// The connection params sent to the service when opening a WebSocket,
+    /// added to the ones forwarded from the client.
+    pub connection_params: HashMap<String, ConnectionParam>,
+
+    /// A file the SDL of the service is read from, instead of querying the
+    /// service.
+    pub sdl_file: Option<PathBuf>,
+
+    /// Headers sent with every request to the service, replacing forwarded
+    /// headers of the same name.
+    pub headers: HashMap<String, String>,
+
+    /// Which of the forwarded headers are sent to the service.
+    pub header_policy: HeaderPolicy,
+
+    /// The User-Agent of the requests to the service.
+    pub user_agent: Option<String>,
+
+    /// Authenticate to the service with the access token of an OAuth2
+    /// client credentials grant.
+    pub oauth2: Option<OAuth2Config>,
+
+    /// Sign the requests to the service with an HMAC of their body.
+    pub signing: Option<RequestSigningConfig>,
+
+    /// Enum values the service names differently from the supergraph.
+    pub enum_values: EnumValues,
+
+    /// Accept responses whose errors are an object, a string or a top-level
+    /// `error`, for legacy services.
+    pub lenient_errors: bool,
+
+    /// How long to wait for the responses of the service, or for its
+    /// WebSocket subscriptions to start, in milliseconds, without a limit if
+    /// not set.
+    pub timeout_ms: Option<u64>,
+
+    /// How many times the queries failing with a timeout, a connection error
+    /// or a 5xx status, and the WebSocket subscriptions failing to start, are
+    /// sent again. Mutations are never retried.
+    pub retry_count: u32,
+
+    /// The delay before the first retry in milliseconds, doubled for each
+    /// following retry.
+    pub retry_backoff_ms: u64,
+
+    /// Where the route was discovered.
+    pub source: RouteSource,
+}
+
+impl fmt::Debug for ServiceRoute {
+    /// The values of the headers and the connection params are left out, as
+    /// they usually hold the credentials of the gateway.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServiceRoute")
+            .field("addr", &self.addr)
+            .field("tls", &self.tls)
+            .field("query_path", &self.query_path)
+            .field("subscribe_path", &self.subscribe_path)
+            .field("introspection_path", &self.introspection_path)
+            .field("schema_url", &self.schema_url)
+            .field("websocket_path", &self.websocket_path)
+            .field("websocket_protocol", &self.websocket_protocol)
+            .field("connection_params", &self.connection_params.keys().collect::<Vec<_>>())
+            .field("sdl_file", &self.sdl_file)
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .field("header_policy", &self.header_policy)
+            .field("user_agent", &self.user_agent)
+            .field("oauth2", &self.oauth2)
+            .field("signing", &self.signing)
+            .field("enum_values", &self.enum_values)
+            .field("lenient_errors", &self.lenient_errors)
+            .field("timeout_ms", &self.timeout_ms)
+            .field("retry_count", &self.retry_count)
+            .field("retry_backoff_ms", &self.retry_backoff_ms)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// Where a route was discovered.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum RouteSource {
+    /// The configuration file or the environment variables.
+    #[default]
+    Config,
+    /// The services of the Kubernetes namespace.
+    Kubernetes,
+    /// The schema snapshot, until the services are discovered.
+    Snapshot,
+    /// The `join__Graph` enum of a supergraph SDL.
+    Supergraph,
+}
+
+impl ServiceRoute {
+    /// The delay before the first retry, unless configured.
+    pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 100;
+
+    /// The forwarded headers sent to the service, as its header policy
+    /// allows.
+    pub(crate) fn forwarded_headers<'a>(&self, header_map: &'a HeaderMap) -> Cow<'a, HeaderMap> {
+        match self.header_policy.is_empty() {
+            true => Cow::Borrowed(header_map),
+            false => Cow::Owned(self.header_policy.apply(header_map)),
+        }
+    }
+
+    /// The headers identifying and authenticating the gateway to the
+    /// service.
+    pub(crate) async fn credential_headers(&self, token_cache: &TokenCache) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        if let Some(user_agent) = &self.user_agent {
+            headers.insert(
+                USER_AGENT,
+                HeaderValue::from_str(user_agent).context("Invalid User-Agent.")?,
+            );
+        }
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_str(name).with_context(|| format!("Invalid header name '{}'.", name))?;
+            let value = HeaderValue::from_str(value).with_context(|| format!("Invalid value of header '{}'.", name))?;
+            headers.insert(name, value);
+        }
+        if let Some(oauth2) = &self.oauth2 {
+            let access_token = token_cache.access_token(oauth2).await?;
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", access_token)).context("Invalid access token.")?,
+            );
+        }
+        Ok(headers)
+    }
+}
+
+/// The state of the requests to the services that outlives a route table:
+/// the rate limits, the circuits, the access tokens and the connection pools.
+#[derive(Clone, Default)]
+pub(crate) struct SubgraphState {
+    pub(crate) rate_limiter: Arc<RateLimiter>,
+    pub(crate) circuit_breaker: Arc<CircuitBreaker>,
+    pub(crate) token_cache: Arc<TokenCache>,
+    pub(crate) upstream: Arc<Upstream>,
 }
 
 /// Service routing table
 ///
 /// The key is the service name.
-#[derive(Default, Debug, Clone, Eq, PartialEq)]
-pub struct ServiceRouteTable(HashMap<String, ServiceRoute>);
+#[derive(Default, Clone)]
+pub struct ServiceRouteTable {
+    routes: HashMap<String, ServiceRoute>,
+    /// Shared with the other route tables of the same
+    /// [`SharedRouteTable`](crate::SharedRouteTable).
+    state: SubgraphState,
+}
+
+impl fmt::Debug for ServiceRouteTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ServiceRouteTable").field(&self.routes).finish()
+    }
+}
+
+impl PartialEq for ServiceRouteTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.routes == other.routes
+    }
+}
+
+impl Eq for ServiceRouteTable {}
 
 impl Deref for ServiceRouteTable {
     type Target = HashMap<String, ServiceRoute>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.routes
     }
 }
 
 impl DerefMut for ServiceRouteTable {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.routes
     }
 }
 
 impl ServiceRouteTable {
+    /// Rename services using a map from discovered service names to the
+    /// service names used by the schema.
+    ///
+    /// Every consumer of the route table, such as composition, planning and
+    /// metrics, then sees the schema names. If an alias collides with a
+    /// discovered service name, the aliased service wins.
+    pub fn apply_aliases(&mut self, aliases: &HashMap<String, String>) {
+        for (discovered, alias) in aliases {
+            if let Some(route) = self.routes.remove(discovered) {
+                if self.routes.insert(alias.clone(), route).is_some() {
+                    tracing::warn!(
+                        service = %discovered,
+                        alias = %alias,
+                        "Service alias replaces an existing service."
+                    );
+                }
+            }
+        }
+    }
+
+    /// Send the requests to the services with `state`.
+    pub(crate) fn set_state(&mut self, state: SubgraphState) {
+        self.state = state;
+    }
+
+    pub(crate) fn state(&self) -> &SubgraphState {
+        &self.state
+    }
+
+    /// Set the User-Agent and the metadata headers of the requests to the
+    /// services that do not set their own.
+    pub fn apply_request_config(&mut self, config: &SubgraphRequestConfig) {
+        for route in self.routes.values_mut() {
+            if route.user_agent.is_none() {
+                route.user_agent = config.user_agent.clone();
+            }
+            for (name, value) in &config.headers {
+                if !route.headers.keys().any(|header| header.eq_ignore_ascii_case(name)) {
+                    route.headers.insert(name.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    /// Fetch the SDL of every service, sorted by service name.
+    ///
+    /// The SDL is read from the `sdl_file` of the services that have one.
+    pub async fn fetch_sdls(&self) -> anyhow::Result<Vec<(String, String)>> {
+        self.fetch_sdls_by_service()
+            .await
+            .into_iter()
+            .map(|(service, sdl)| Ok((service, sdl?)))
+            .collect()
+    }
+
+    /// Fetch the SDL of every service, sorted by service name, keeping the
+    /// error of each service whose SDL could not be fetched.
+    pub async fn fetch_sdls_by_service(&self) -> Vec<(String, anyhow::Result<String>)> {
+        const QUERY_SDL: &str = "{ _service { sdl }}";
+
+        #[derive(Deserialize)]
+        struct ResponseQuery {
+            #[serde(rename = "_service")]
+            service: ResponseService,
+        }
+
+        #[derive(Deserialize)]
+        struct ResponseService {
+            sdl: String,
+        }
+
+        let mut sdls = futures_util::future::join_all(self.iter().map(|(service, route)| async move {
+            let sdl = async {
+                if let Some(sdl_file) = &route.sdl_file {
+                    return tokio::fs::read_to_string(sdl_file).await.with_context(|| {
+                        format!("Failed to read SDL of '{}' from '{}'.", service, sdl_file.display())
+                    });
+                }
+                let resp = self
+                    .query(service, Request::new(QUERY_SDL), None, Some(true))
+                    .await
+                    .with_context(|| format!("Failed to fetch SDL from '{}'.", service))?;
+                let resp: ResponseQuery = value::from_value(resp.data).context("Failed to parse response.")?;
+                Ok(resp.service.sdl)
+            };
+            (service.to_string(), sdl.await)
+        }))
+        .await;
+        sdls.sort_by(|(a, _), (b, _)| a.cmp(b));
+        sdls
+    }
+
+    /// The URL of the GraphQL endpoint of the specified service.
+    pub fn url(&self, service: &str, introspection: bool) -> Option<String> {
+        let route = self.routes.get(service)?;
+        if let (true, Some(schema_url)) = (introspection, &route.schema_url) {
+            return Some(schema_url.clone());
+        }
+        let scheme = match route.tls {
+            true => "https",
+            false => "http",
+        };
+        let path = match introspection {
+            true => &route.introspection_path,
+            false => &route.query_path,
+        };
+        Some(match path {
+            Some(path) => format!("{}://{}{}", scheme, route.addr, path),
+            None => format!("{}://{}", scheme, route.addr),
+        })
+    }
+
+    /// The URL of the GraphQL WebSocket endpoint of the specified service.
+    pub fn websocket_url(&self, service: &str) -> Option<String> {
+        let route = self.routes.get(service)?;
+        let scheme = match route.tls {
+            true => "wss",
+            false => "ws",
+        };
+        Some(match &route.websocket_path {
+            Some(path) => format!("{}://{}{}", scheme, route.addr, path),
+            None => format!("{}://{}", scheme, route.addr),
+        })
+    }
+
     /// Call the GraphQL query of the specified service.
-    #[instrument(err(Debug), skip(request, header_map), ret, level = "trace")]
+    #[instrument(err(Debug), skip(self, request, header_map), ret, level = "trace")]
     pub async fn query(
         &self,
         service: impl AsRef<str> + std::fmt::Debug,
@@ -62,45 +394,103 @@ impl ServiceRouteTable {
         header_map: Option<&HeaderMap>,
         introspection: Option<bool>,
     ) -> anyhow::Result<Response> {
-        let service = service.as_ref();
-        let route = self
-            .0
-            .get(service)
-            .ok_or_else(|| anyhow::anyhow!("Service '{}' is not defined in the routing table.", service))?;
+        self.send(
+            service.as_ref(),
+            request,
+            header_map,
+            introspection.unwrap_or(false),
+            None,
+        )
+        .await
+    }
 
-        let introspection = introspection.unwrap_or(false);
+    /// Call the GraphQL query of the specified service, counting the
+    /// response against the size limits of a request.
+    pub(crate) async fn query_limited(
+        &self,
+        service: &str,
+        request: Request,
+        header_map: Option<&HeaderMap>,
+        response_budget: Option<&ResponseBudget>,
+    ) -> anyhow::Result<Response> {
+        self.send(service, request, header_map, false, response_budget).await
+    }
 
-        let scheme = match route.tls {
-            true => "https",
-            false => "http",
-        };
+    async fn send(
+        &self,
+        service: &str,
+        request: Request,
+        header_map: Option<&HeaderMap>,
+        introspection: bool,
+        response_budget: Option<&ResponseBudget>,
+    ) -> anyhow::Result<Response> {
+        let url = self
+            .url(service, introspection)
+            .ok_or_else(|| anyhow::anyhow!("Service '{}' is not defined in the routing table.", service))?;
 
-        let url = if introspection {
-            match &route.introspection_path {
-                Some(path) => format!("{}://{}{}", scheme, route.addr, path),
-                None => format!("{}://{}", scheme, route.addr),
-            }
-        } else {
-            match &route.query_path {
-                Some(path) => format!("{}://{}{}", scheme, route.addr, path),
-                None => format!("{}://{}", scheme, route.addr),
-            }
-        };
+        let mut headers = header_map.cloned().unwrap_or_default();
+        let route = self.routes.get(service);
+        if let Some(route) = route {
+            headers.extend(route.credential_headers(&self.state.token_cache).await?);
+        }
 
-        let raw_resp = HTTP_CLIENT
+        self.state.rate_limiter.acquire(service).await?;
+
+        let mut body = serde_json::to_vec(&request)?;
+        if let Some(signing) = route.and_then(|route| route.signing.as_ref()) {
+            // The canonical body is sent, so that the subgraph can verify the
+            // signature against the raw body.
+            body = canonical_body(&body)?;
+            headers.extend(signing.sign(&body)?);
+        }
+        let cx = opentelemetry::Context::current();
+        set_payload_attribute(&cx, KEY_REQUEST_BYTES.i64(body.len() as i64));
+        let _in_flight = self.state.upstream.begin(service);
+        let raw_resp = self
+            .state
+            .upstream
+            .client(service)
             .post(&url)
-            .headers(header_map.cloned().unwrap_or_default())
-            .json(&request)
+            .headers(headers)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
             .send()
             .await?;
+        METRICS.subgraph_request_counter.add(1, &[
+            KEY_SERVICE.string(service.to_string()),
+            KEY_HTTP_VERSION.string(format!("{:?}", raw_resp.version())),
+        ]);
+
+        if raw_resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = self.state.rate_limiter.limit(service, raw_resp.headers());
+            return Err(RateLimitedError {
+                service: service.to_string(),
+                retry_after,
+            }
+            .into());
+        }
 
         if !raw_resp.status().is_success() {
+            if let Some(moved_to) = raw_resp
+                .headers()
+                .get(ENTITY_MOVED_HEADER)
+                .and_then(|value| value.to_str().ok())
+            {
+                return Err(EntityMovedError {
+                    service: service.to_string(),
+                    moved_to: moved_to.trim().to_string(),
+                }
+                .into());
+            }
+            let status = raw_resp.status().as_u16();
             let body = raw_resp.text().await?;
-            return Err(anyhow::anyhow!(
-                "received non-2xx response from service \"{}\", body: \"{}\"",
-                service,
-                body
-            ));
+            tracing::debug!(service = %service, status, body = %body, "The service responded with a non-2xx status.");
+            return Err(SubgraphStatusError {
+                service: service.to_string(),
+                status,
+                body,
+            }
+            .into());
         }
 
         let mut headers: HashMap<String, Vec<String>> = HashMap::new();
@@ -116,8 +506,55 @@ impl ServiceRouteTable {
             }
         }
 
-        let mut resp = raw_resp.json::<Response>().await?;
+        let max_bytes = response_budget.and_then(ResponseBudget::max_subgraph_bytes);
+        let body = read_body(raw_resp, service, max_bytes).await?;
+        if let Some(response_budget) = response_budget {
+            response_budget.receive(body.len())?;
+        }
+        set_payload_attribute(&cx, KEY_RESPONSE_BYTES.i64(body.len() as i64));
+        let mut resp = match self.routes.get(service) {
+            Some(route) if route.lenient_errors => parse_lenient_response(&body)?,
+            _ => serde_json::from_slice::<Response>(&body)?,
+        };
         resp.headers = Some(headers);
         Ok(resp)
     }
 }
+
+/// Record a payload size on the span of the request, and on the span of the
+/// plan node it is sent for.
+fn set_payload_attribute(cx: &opentelemetry::Context, attribute: KeyValue) {
+    if let Some(PlanNodeContext(node_cx)) = cx.get() {
+        node_cx.span().set_attribute(attribute.clone());
+    }
+    cx.span().set_attribute(attribute);
+}
+
+/// Read a response body, failing as soon as it exceeds `max_bytes`.
+async fn read_body(mut resp: reqwest::Response, service: &str, max_bytes: Option<usize>) -> anyhow::Result<Vec<u8>> {
+    let max_bytes = match max_bytes {
+        Some(max_bytes) => max_bytes,
+        None => return Ok(resp.bytes().await?.to_vec()),
+    };
+    let too_large = || {
+        METRICS
+            .subgraph_response_too_large_counter
+            .add(1, &[KEY_SERVICE.string(service.to_string())]);
+        ResponseTooLargeError {
+            service: service.to_string(),
+            limit: max_bytes,
+        }
+    };
+
+    if resp.content_length().is_some_and(|len| len > max_bytes as u64) {
+        return Err(too_large().into());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large().into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
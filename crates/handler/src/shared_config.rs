@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use crate::{auth::Auth, cors::CorsPolicy};
+
+/// The settings of the gateway read by the filters on every request, which
+/// are replaced when the config file changes.
+#[derive(Default)]
+pub struct GatewaySettings {
+    pub forward_headers: Vec<String>,
+    /// The entries of the WebSocket `connection_init` payload forwarded to
+    /// the subgraphs, the rest are dropped, or all of them if empty.
+    pub forward_connection_params: Vec<String>,
+    pub auth: Arc<Auth>,
+    pub cors: CorsPolicy,
+}
+
+/// The current [`GatewaySettings`], swapped without restarting the server.
+#[derive(Clone, Default)]
+pub struct SharedConfig {
+    settings: Arc<ArcSwap<GatewaySettings>>,
+}
+
+impl SharedConfig {
+    pub fn new(settings: GatewaySettings) -> Self {
+        Self {
+            settings: Arc::new(ArcSwap::from_pointee(settings)),
+        }
+    }
+
+    pub fn load(&self) -> Arc<GatewaySettings> {
+        self.settings.load_full()
+    }
+
+    /// Apply new settings to the requests received afterwards.
+    pub fn store(&self, settings: GatewaySettings) {
+        self.settings.store(Arc::new(settings));
+    }
+}
@@ -1,10 +1,30 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
-use anyhow::{Context, Error, Result};
-use graphgate_planner::{PlanBuilder, Request, Response, ServerError};
-use graphgate_schema::ComposedSchema;
+use anyhow::{Context, Result};
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use graphgate_executor::{Executor, Parallelism};
+use graphgate_planner::{
+    nested_defer_warnings,
+    IncrementalResponse,
+    PlanBuilder,
+    PlanFormat,
+    Request,
+    Response,
+    ServerError,
+    WARNINGS_EXTENSION,
+};
+use graphgate_schema::{ComposedSchema, Supergraph};
 use http::{
-    header::{HeaderName, CONTENT_TYPE},
+    header::{HeaderName, CACHE_CONTROL, CONTENT_TYPE},
     HeaderValue,
 };
 use opentelemetry::{
@@ -12,31 +32,223 @@ use opentelemetry::{
     trace::{TraceContextExt, Tracer},
     Context as OpenTelemetryContext,
 };
-use serde::Deserialize;
+use parser::types::ExecutableDocument;
+use sha2::{Digest, Sha256};
 use tokio::{
-    sync::{mpsc, RwLock},
+    sync::{mpsc, watch, RwLock},
     time::{Duration, Instant},
 };
 use tracing::instrument;
 use value::ConstValue;
-use warp::http::{HeaderMap, Response as HttpResponse, StatusCode};
+use warp::{
+    http::{HeaderMap, Response as HttpResponse, StatusCode},
+    hyper::Body,
+};
 
-use crate::{executor::Executor, fetcher::HttpFetcher, service_route::ServiceRouteTable};
+use crate::{
+    audit::{audit_unavailable, is_mutation, AuditConfig, AuditLog},
+    authorization::remove_unauthorized_fields,
+    blocklist::{operation_blocked, Blocklist},
+    bucketing::Bucketing,
+    cache_key::{canonical_url, query_hash},
+    cache_stats::{CacheKind, CacheStats},
+    call_budget::{CallBudget, CallBudgetConfig},
+    chaos::{ChaosConfig, ChaosFaults},
+    circuit_breaker::{CircuitBreaker, CircuitBreakerConfig},
+    composition::CompositionConfig,
+    connection::ConnectionConfig,
+    context_injection::{ContextInjector, ContextRule, RequestContext},
+    cost::{cost_extension, estimate_cost, CostConfig, COST_LIMIT_EXCEEDED},
+    deprecation::{check_sunsets, DeprecationConfig},
+    entity_cache::{CachePartition, EntityCache},
+    entity_check::{check_entity_resolvers, EntityCheckConfig, EntityResolverError},
+    enum_values::{rename_sdl_enum_values, set_enum_value_renames},
+    event_stream::{event_stream_body, EVENT_STREAM_CONTENT_TYPE},
+    explain::{annotate_fetches, fetch_latency_line},
+    fetcher::{BudgetedSubscriber, HttpFetcher},
+    incremental::{multipart_body, DeferConfig, MULTIPART_CONTENT_TYPE},
+    introspection::{
+        introspection_disabled,
+        is_introspection,
+        selects_introspection,
+        IntrospectionConfig,
+        IntrospectionGuard,
+    },
+    metrics::{observe_gauges, CompositionState},
+    oauth2::TokenCache,
+    operation_limits::{check_operation_limits, OperationLimitsConfig},
+    pagination::{apply_default_limits, warnings_extension, PaginationConfig},
+    panic::{incremental_panic_payload, isolate_stream},
+    parallelism::ParallelismConfig,
+    persisted_operations::{check_persisted_operations, InvalidPersistedOperation, PersistedOperation},
+    persisted_queries::PersistedQueryCache,
+    polling::{backoff, PollingConfig},
+    rate_limit::{RateLimitConfig, RateLimiter},
+    redaction::{RedactionRule, Redactor},
+    response_headers::ResponseHeaders,
+    response_limit::{ResponseBudget, ResponseLimitConfig},
+    safelist::Safelist,
+    server_timing::ServerTiming,
+    service_route::{RouteSource, ServiceRouteTable, SubgraphState},
+    snapshot::Snapshot,
+    subgraph_request::SubgraphRequestConfig,
+    supergraph::supergraph_route_table,
+    upstream::{Upstream, UpstreamConfig},
+    verification::{verify_schema, VerificationConfig},
+    websocket::WebSocketController,
+};
 
 enum Command {
     Change(ServiceRouteTable),
+    /// Load the supergraph SDL file.
+    Supergraph,
+    /// Fetch the SDLs of the services and compose the schema now.
+    Refresh,
+}
+
+/// A parsed request ready to be planned.
+struct PreparedQuery {
+    composed_schema: Arc<ComposedSchema>,
+    route_table: Arc<ServiceRouteTable>,
+    document: ExecutableDocument,
+    extensions: HashMap<String, ConstValue>,
+    /// The errors of the fields removed from the operation.
+    errors: Vec<ServerError>,
 }
 
 struct Inner {
     schema: Option<Arc<ComposedSchema>>,
     route_table: Option<Arc<ServiceRouteTable>>,
+    /// The SDLs the schema was composed from, by service name.
+    sdls: Vec<(String, String)>,
+    compositions: u64,
+    /// The schema was loaded from a snapshot and no schema has been composed
+    /// from the SDLs of the services since.
+    bootstrapped: bool,
+    /// The supergraph SDL the schema was loaded from, instead of composing
+    /// the SDLs of the services.
+    supergraph: Option<String>,
+}
+
+/// The outcome of the latest schema update.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum CompositionStatus {
+    /// No schema has been composed yet.
+    #[default]
+    Pending,
+
+    /// A schema was composed from changed SDLs, counting the compositions.
+    Composed(u64),
+
+    /// The SDLs could not be fetched or composed.
+    Failed(String),
+}
+
+/// The version of the SDL of a subgraph the schema is composed from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubgraphSchema {
+    pub service: String,
+
+    /// The SHA-256 hash of the SDL, in hexadecimal.
+    pub sdl_hash: String,
+
+    /// When this version of the SDL was first fetched.
+    pub fetched_at: DateTime<Utc>,
 }
 
+/// Whether the SDL of a service could be fetched in the latest update.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum RouteHealth {
+    /// The SDL has not been fetched yet.
+    #[default]
+    Unknown,
+
+    Healthy,
+
+    /// The SDL could not be fetched.
+    Unhealthy(String),
+}
+
+/// A route of the active route table, as discovered by the gateway.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RouteStatus {
+    pub service: String,
+    pub url: String,
+    pub websocket_url: String,
+    pub source: RouteSource,
+    pub health: RouteHealth,
+
+    /// The SHA-256 hash of the SDL the current schema is composed from, in
+    /// hexadecimal.
+    pub sdl_hash: Option<String>,
+}
+
+/// The extensions of the response to an operation and the errors of the
+/// fields removed from it.
+type CheckedOperation = (HashMap<String, ConstValue>, Vec<ServerError>);
+
 #[derive(Clone)]
 pub struct SharedRouteTable {
     inner: Arc<RwLock<Inner>>,
     tx: mpsc::UnboundedSender<Command>,
     receive_headers: Vec<String>,
+    trace_response_headers: Arc<Vec<String>>,
+    defer_config: DeferConfig,
+    context_injector: Arc<ArcSwap<ContextInjector>>,
+    redaction_rules: Arc<Vec<RedactionRule>>,
+    audit_log: Option<Arc<AuditLog>>,
+    pagination_config: Option<PaginationConfig>,
+    cost_config: Option<CostConfig>,
+    deprecation_config: Option<DeprecationConfig>,
+    service_aliases: HashMap<String, String>,
+    subgraph_request_config: Arc<std::sync::RwLock<SubgraphRequestConfig>>,
+    debug_errors: bool,
+    normalize_error_paths: bool,
+    check_entity_keys: bool,
+    allowed_services_extension: bool,
+    propagate_subgraph_status: bool,
+    server_timing: bool,
+    parallelism: Parallelism,
+    connection_config: ConnectionConfig,
+    response_limit_config: ResponseLimitConfig,
+    call_budget_config: CallBudgetConfig,
+    operation_limits_config: OperationLimitsConfig,
+    chaos_config: Option<Arc<ChaosConfig>>,
+    disable_introspection: bool,
+    safelist: Option<Arc<Safelist>>,
+    blocklist: Arc<Blocklist>,
+    introspection: Arc<IntrospectionGuard>,
+    persisted_query_cache: Option<Arc<dyn PersistedQueryCache>>,
+    entity_cache: Option<Arc<dyn EntityCache>>,
+    entity_cache_partition_claims: Vec<String>,
+    entity_cache_credential_headers: Vec<String>,
+    bucketing: Option<Bucketing>,
+    /// Shared with the update loop, in milliseconds.
+    update_interval: Arc<AtomicU64>,
+    retry_interval: Arc<AtomicU64>,
+    max_backoff: Arc<AtomicU64>,
+    ready: Arc<watch::Sender<bool>>,
+    composition: Arc<watch::Sender<CompositionStatus>>,
+    persisted_operations: Arc<std::sync::RwLock<Vec<PersistedOperation>>>,
+    /// The headers forwarded for the persisted operations with a policy, by
+    /// the hash of their text.
+    operation_forward_headers: Arc<std::sync::RwLock<HashMap<String, Arc<[String]>>>>,
+    invalid_persisted_operations: Arc<std::sync::RwLock<Vec<InvalidPersistedOperation>>>,
+    entity_check_config: Arc<std::sync::RwLock<Option<EntityCheckConfig>>>,
+    entity_resolver_errors: Arc<std::sync::RwLock<Vec<EntityResolverError>>>,
+    subgraph_schemas: Arc<std::sync::RwLock<Vec<SubgraphSchema>>>,
+    route_health: Arc<std::sync::RwLock<HashMap<String, RouteHealth>>>,
+    snapshot_path: Arc<std::sync::RwLock<Option<PathBuf>>>,
+    verification_config: Arc<std::sync::RwLock<Option<VerificationConfig>>>,
+    composition_config: Arc<std::sync::RwLock<CompositionConfig>>,
+    supergraph_path: Arc<std::sync::RwLock<Option<PathBuf>>>,
+    /// The state of the requests to the services, kept across route table
+    /// updates.
+    rate_limiter: Arc<RateLimiter>,
+    circuit_breaker: Arc<CircuitBreaker>,
+    token_cache: Arc<TokenCache>,
+    upstream: Arc<Upstream>,
+    composition_state: Arc<CompositionState>,
 }
 
 impl Default for SharedRouteTable {
@@ -46,10 +258,68 @@ impl Default for SharedRouteTable {
             inner: Arc::new(RwLock::new(Inner {
                 schema: None,
                 route_table: None,
+                sdls: Vec::new(),
+                compositions: 0,
+                bootstrapped: false,
+                supergraph: None,
             })),
             tx,
             receive_headers: vec![],
+            trace_response_headers: Default::default(),
+            defer_config: Default::default(),
+            context_injector: Default::default(),
+            redaction_rules: Default::default(),
+            audit_log: None,
+            pagination_config: None,
+            cost_config: None,
+            deprecation_config: None,
+            service_aliases: Default::default(),
+            subgraph_request_config: Default::default(),
+            debug_errors: false,
+            normalize_error_paths: false,
+            check_entity_keys: false,
+            allowed_services_extension: false,
+            propagate_subgraph_status: false,
+            server_timing: false,
+            parallelism: Default::default(),
+            connection_config: Default::default(),
+            response_limit_config: Default::default(),
+            call_budget_config: Default::default(),
+            operation_limits_config: Default::default(),
+            chaos_config: None,
+            disable_introspection: false,
+            safelist: None,
+            blocklist: Default::default(),
+            introspection: Default::default(),
+            persisted_query_cache: None,
+            entity_cache: None,
+            entity_cache_partition_claims: Vec::new(),
+            entity_cache_credential_headers: Vec::new(),
+            bucketing: None,
+            update_interval: Default::default(),
+            retry_interval: Default::default(),
+            max_backoff: Default::default(),
+            ready: Arc::new(watch::channel(false).0),
+            composition: Arc::new(watch::channel(CompositionStatus::Pending).0),
+            persisted_operations: Default::default(),
+            operation_forward_headers: Default::default(),
+            invalid_persisted_operations: Default::default(),
+            entity_check_config: Default::default(),
+            entity_resolver_errors: Default::default(),
+            subgraph_schemas: Default::default(),
+            route_health: Default::default(),
+            snapshot_path: Default::default(),
+            verification_config: Default::default(),
+            composition_config: Default::default(),
+            supergraph_path: Default::default(),
+            rate_limiter: Default::default(),
+            circuit_breaker: Default::default(),
+            token_cache: Default::default(),
+            upstream: Default::default(),
+            composition_state: Default::default(),
         };
+        shared_route_table.set_polling_config(PollingConfig::default());
+        observe_gauges(&shared_route_table.composition_state, &shared_route_table.upstream);
         tokio::spawn({
             let shared_route_table = shared_route_table.clone();
             async move { shared_route_table.update_loop(rx).await }
@@ -58,25 +328,100 @@ impl Default for SharedRouteTable {
     }
 }
 
+/// Compose the schema from the SDLs of the services, logging the
+/// composition hints.
+pub fn compose_schema(
+    route_table: &ServiceRouteTable,
+    composition_config: &CompositionConfig,
+    sdls: &[(String, String)],
+) -> Result<ComposedSchema> {
+    let mut documents = sdls
+        .iter()
+        .map(|(service, sdl)| {
+            let mut document = parser::parse_schema(sdl).with_context(|| format!("Invalid SDL from '{}'.", service))?;
+            if let Some(route) = route_table.get(service) {
+                rename_sdl_enum_values(&mut document, &route.enum_values);
+            }
+            Ok((service.clone(), document))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for hint in composition_config.prepare(&mut documents) {
+        tracing::warn!(
+            code = hint.code,
+            severity = hint.severity.as_str(),
+            message = %hint.message,
+            "Composition hint."
+        );
+    }
+    let mut schema = ComposedSchema::combine(documents)?;
+    set_enum_value_renames(&mut schema, route_table);
+    Ok(schema)
+}
+
+/// A GraphQL response as the body of a 200 response.
+fn json_response(resp: &Response) -> HttpResponse<Body> {
+    HttpResponse::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(resp).unwrap().into())
+        .unwrap()
+}
+
+/// A GraphQL response without data, failed with `errors`, as the body of a
+/// 200 response.
+fn error_response(errors: Vec<ServerError>) -> HttpResponse<Body> {
+    json_response(&Response {
+        data: ConstValue::Null,
+        errors,
+        extensions: Default::default(),
+        headers: Default::default(),
+    })
+}
+
+/// The status of a query that failed as a whole, without data, because a
+/// subgraph refused it.
+///
+/// Only the errors of the fetches the gateway failed count, not the errors
+/// the subgraphs responded with, whatever their code.
+fn subgraph_failure_status(resp: &Response) -> Option<StatusCode> {
+    if resp.data != ConstValue::Null {
+        return None;
+    }
+    resp.errors.iter().find_map(|error| match error.status? {
+        401 => Some(StatusCode::UNAUTHORIZED),
+        403 => Some(StatusCode::FORBIDDEN),
+        _ => Some(StatusCode::BAD_GATEWAY),
+    })
+}
+
 impl SharedRouteTable {
     async fn update_loop(self, mut rx: mpsc::UnboundedReceiver<Command>) {
-        let mut update_interval =
-            tokio::time::interval_at(Instant::now() + Duration::from_secs(3), Duration::from_secs(30));
+        let mut failures = 0;
+        let mut next_update = Instant::now() + self.retry_interval();
 
         loop {
             tokio::select! {
-                _ = update_interval.tick() => {
-                    if let Err(err) = self.update().await {
-                        tracing::error!(error = %err, "Failed to update schema.");
-                    }
+                _ = tokio::time::sleep_until(next_update) => {
+                    next_update = Instant::now() + self.try_update(&mut failures).await;
                 }
                 command = rx.recv() => {
                     if let Some(command) = command {
                         match command {
                             Command::Change(route_table) => {
-                                let mut inner = self.inner.write().await;
-                                inner.route_table = Some(Arc::new(route_table));
-                                inner.schema = None;
+                                // The previous schema, or the snapshot, is served until
+                                // the schema of the new routes is composed.
+                                let composed = {
+                                    let mut inner = self.inner.write().await;
+                                    inner.route_table = Some(Arc::new(route_table));
+                                    inner.schema.is_some()
+                                };
+                                if !composed {
+                                    self.ready.send_replace(false);
+                                }
+                                next_update = Instant::now() + self.try_update(&mut failures).await;
+                            }
+                            Command::Supergraph | Command::Refresh => {
+                                next_update = Instant::now() + self.try_update(&mut failures).await;
                             }
                         }
                     }
@@ -85,19 +430,54 @@ impl SharedRouteTable {
         }
     }
 
-    #[instrument(err(Debug), skip(self), ret, level = "trace")]
-    async fn update(&self) -> Result<()> {
-        const QUERY_SDL: &str = "{ _service { sdl }}";
-
-        #[derive(Deserialize)]
-        struct ResponseQuery {
-            #[serde(rename = "_service")]
-            service: ResponseService,
+    /// Compose the schema, returning the delay until the next update.
+    ///
+    /// Failed updates are retried sooner, backing off after every
+    /// consecutive failure.
+    async fn try_update(&self, failures: &mut u32) -> Duration {
+        match self.update().await {
+            Ok(()) => {
+                self.composition_state.updated();
+                *failures = 0;
+            },
+            Err(err) => {
+                self.composition_state.failed();
+                *failures += 1;
+                tracing::error!(error = %err, "Failed to update schema.");
+                let status = CompositionStatus::Failed(format!("{:#}", err));
+                self.composition.send_if_modified(|current| {
+                    let changed = *current != status;
+                    *current = status;
+                    changed
+                });
+            },
+        }
+        let update_interval = Duration::from_millis(self.update_interval.load(Ordering::Relaxed));
+        match *failures {
+            0 => update_interval,
+            failures => backoff(
+                self.retry_interval(),
+                Duration::from_millis(self.max_backoff.load(Ordering::Relaxed)),
+                failures,
+            ),
         }
+    }
 
-        #[derive(Deserialize)]
-        struct ResponseService {
-            sdl: String,
+    /// The delay until the first retry of a failed update, never longer than
+    /// the update interval.
+    fn retry_interval(&self) -> Duration {
+        Duration::from_millis(
+            self.retry_interval
+                .load(Ordering::Relaxed)
+                .min(self.update_interval.load(Ordering::Relaxed)),
+        )
+    }
+
+    #[instrument(err(Debug), skip(self), ret, level = "trace")]
+    async fn update(&self) -> Result<()> {
+        let supergraph_path = self.supergraph_path.read().unwrap().clone();
+        if let Some(path) = supergraph_path {
+            return self.update_supergraph(&path).await;
         }
 
         let route_table = match self.inner.read().await.route_table.clone() {
@@ -105,34 +485,675 @@ impl SharedRouteTable {
             None => return Ok(()),
         };
 
-        let resp = futures_util::future::try_join_all(route_table.keys().map(|service| {
-            let route_table = route_table.clone();
-            async move {
-                let resp = route_table
-                    .query(service, Request::new(QUERY_SDL), None, Some(true))
-                    .await
-                    .with_context(|| format!("Failed to fetch SDL from '{}'.", service))?;
-                let resp: ResponseQuery = value::from_value(resp.data).context("Failed to parse response.")?;
-                let document = parser::parse_schema(resp.service.sdl)
-                    .with_context(|| format!("Invalid SDL from '{}'.", service))?;
-                Ok::<_, Error>((service.to_string(), document))
+        let sdls = route_table.fetch_sdls_by_service().await;
+        *self.route_health.write().unwrap() = sdls
+            .iter()
+            .map(|(service, sdl)| {
+                let health = match sdl {
+                    Ok(_) => RouteHealth::Healthy,
+                    Err(err) => RouteHealth::Unhealthy(format!("{:#}", err)),
+                };
+                (service.clone(), health)
+            })
+            .collect();
+        let sdls = sdls
+            .into_iter()
+            .map(|(service, sdl)| Ok((service, sdl?)))
+            .collect::<Result<Vec<_>>>()?;
+        let fetched_at = Utc::now();
+
+        {
+            let inner = self.inner.read().await;
+            if inner.schema.is_some() && inner.sdls == sdls && !inner.bootstrapped {
+                return Ok(());
+            }
+        }
+
+        let start_time = Instant::now();
+        let composition_config = self.composition_config.read().unwrap().clone();
+        let schema = compose_schema(&route_table, &composition_config, &sdls)?;
+        let verification_config = self.verification_config.read().unwrap().clone();
+        if let Some(verification_config) = verification_config {
+            verify_schema(&verification_config, &schema, &route_table)
+                .await
+                .context("The composed schema failed verification, the previous schema is kept.")?;
+        }
+        let subgraph_schemas = {
+            let previous = self.subgraph_schemas.read().unwrap();
+            sdls.iter()
+                .map(|(service, sdl)| {
+                    let sdl_hash = format!("{:x}", Sha256::digest(sdl.as_bytes()));
+                    match previous
+                        .iter()
+                        .find(|subgraph| subgraph.service == *service && subgraph.sdl_hash == sdl_hash)
+                    {
+                        Some(subgraph) => subgraph.clone(),
+                        None => SubgraphSchema {
+                            service: service.clone(),
+                            sdl_hash,
+                            fetched_at,
+                        },
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        self.composition_state
+            .composed(&schema, &subgraph_schemas, start_time.elapsed());
+        *self.subgraph_schemas.write().unwrap() = subgraph_schemas;
+        self.check_persisted_operations(&schema);
+        if let Some(path) = self.snapshot_path.read().unwrap().as_ref() {
+            if let Err(err) = Snapshot::new(&route_table, &sdls).write(path) {
+                tracing::warn!(error = %err, "Failed to write the schema snapshot.");
             }
-        }))
-        .await?;
+        }
+        let compositions = {
+            let mut inner = self.inner.write().await;
+            let schema = Arc::new(schema);
+            self.context_injector.load().valid_rules(&schema);
+            inner.schema = Some(schema);
+            inner.sdls = sdls;
+            inner.compositions += 1;
+            inner.bootstrapped = false;
+            inner.compositions
+        };
+        self.ready.send_replace(true);
+        self.composition.send_replace(CompositionStatus::Composed(compositions));
+        if self.entity_check_config.read().unwrap().is_some() {
+            tokio::spawn({
+                let shared_route_table = self.clone();
+                async move { shared_route_table.check_entity_resolvers().await }
+            });
+        }
+        Ok(())
+    }
+
+    /// Load the schema and the routes from the supergraph SDL file, if it
+    /// changed.
+    async fn update_supergraph(&self, path: &Path) -> Result<()> {
+        let sdl = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read the supergraph '{}'.", path.display()))?;
+        if self.inner.read().await.supergraph.as_deref() == Some(sdl.as_str()) {
+            return Ok(());
+        }
 
-        let schema = ComposedSchema::combine(resp)?;
-        self.inner.write().await.schema = Some(Arc::new(schema));
+        let start_time = Instant::now();
+        let supergraph =
+            Supergraph::parse(&sdl).with_context(|| format!("Invalid supergraph '{}'.", path.display()))?;
+        let mut route_table = supergraph_route_table(&supergraph.graphs)?;
+        route_table.apply_request_config(&self.subgraph_request_config.read().unwrap());
+        route_table.set_state(self.subgraph_state());
+        let schema = supergraph.schema;
+        let verification_config = self.verification_config.read().unwrap().clone();
+        if let Some(verification_config) = verification_config {
+            verify_schema(&verification_config, &schema, &route_table)
+                .await
+                .context("The supergraph failed verification, the previous schema is kept.")?;
+        }
+        self.composition_state.composed(&schema, &[], start_time.elapsed());
+        self.subgraph_schemas.write().unwrap().clear();
+        self.check_persisted_operations(&schema);
+        let compositions = {
+            let mut inner = self.inner.write().await;
+            let schema = Arc::new(schema);
+            self.context_injector.load().valid_rules(&schema);
+            inner.schema = Some(schema);
+            inner.route_table = Some(Arc::new(route_table));
+            inner.supergraph = Some(sdl);
+            inner.compositions += 1;
+            inner.bootstrapped = false;
+            inner.compositions
+        };
+        self.ready.send_replace(true);
+        self.composition.send_replace(CompositionStatus::Composed(compositions));
         Ok(())
     }
 
-    pub fn set_route_table(&self, route_table: ServiceRouteTable) {
+    /// Serve the supergraph SDL at `path`, composed ahead of time by a tool
+    /// such as `rover`, instead of composing the SDLs of the services.
+    ///
+    /// The routes are the graphs of the supergraph, and the file is reloaded
+    /// when it changes.
+    pub fn set_supergraph_path(&self, path: PathBuf) {
+        *self.supergraph_path.write().unwrap() = Some(path);
+        self.tx.send(Command::Supergraph).ok();
+    }
+
+    /// Execute the verification operations against every composed schema
+    /// before serving it.
+    pub fn set_verification_config(&self, verification_config: VerificationConfig) {
+        *self.verification_config.write().unwrap() = Some(verification_config);
+    }
+
+    /// Set how the schemas of the subgraphs are composed.
+    pub fn set_composition_config(&self, composition_config: CompositionConfig) {
+        *self.composition_config.write().unwrap() = composition_config;
+    }
+
+    /// Persist the SDLs and routes of every composed schema to `path`, loaded
+    /// by [`SharedRouteTable::load_snapshot`].
+    pub fn set_snapshot_path(&self, snapshot_path: PathBuf) {
+        *self.snapshot_path.write().unwrap() = Some(snapshot_path);
+    }
+
+    /// Serve the schema of the snapshot until a schema is composed from the
+    /// SDLs of the services, returning `false` if there is no snapshot.
+    ///
+    /// The routes of the snapshot are used until a route table is set.
+    pub async fn load_snapshot(&self) -> Result<bool> {
+        let snapshot_path = self.snapshot_path.read().unwrap().clone();
+        let snapshot = match snapshot_path {
+            Some(path) => match Snapshot::read(&path)? {
+                Some(snapshot) => snapshot,
+                None => return Ok(false),
+            },
+            None => return Ok(false),
+        };
+
+        {
+            let mut inner = self.inner.write().await;
+            if inner.schema.is_some() {
+                return Ok(false);
+            }
+            let route_table = match &inner.route_table {
+                Some(route_table) => route_table.clone(),
+                None => {
+                    let mut route_table = snapshot.route_table();
+                    route_table.apply_request_config(&self.subgraph_request_config.read().unwrap());
+                    route_table.set_state(self.subgraph_state());
+                    Arc::new(route_table)
+                },
+            };
+            let sdls = snapshot.sdls();
+            let composition_config = self.composition_config.read().unwrap().clone();
+            let schema = compose_schema(&route_table, &composition_config, &sdls)?;
+            let schema = Arc::new(schema);
+            self.context_injector.load().valid_rules(&schema);
+            inner.schema = Some(schema);
+            inner.route_table = Some(route_table);
+            inner.sdls = sdls;
+            inner.bootstrapped = true;
+        }
+        self.ready.send_replace(true);
+        Ok(true)
+    }
+
+    /// Plan the persisted operations against a newly composed schema,
+    /// reporting those that broke.
+    fn check_persisted_operations(&self, schema: &ComposedSchema) {
+        let operations = self.persisted_operations.read().unwrap();
+        if operations.is_empty() {
+            return;
+        }
+
+        let invalid = check_persisted_operations(schema, &operations);
+        for operation in &invalid {
+            tracing::warn!(
+                id = %operation.id,
+                errors = ?operation.errors,
+                "Persisted operation is invalid against the composed schema."
+            );
+        }
+        tracing::info!(
+            total = operations.len(),
+            invalid = invalid.len(),
+            "Checked persisted operations."
+        );
+        *self.invalid_persisted_operations.write().unwrap() = invalid;
+    }
+
+    /// Set the persisted operations checked against every composed schema.
+    pub fn set_persisted_operations(&self, operations: Vec<PersistedOperation>) {
+        *self.operation_forward_headers.write().unwrap() = operations
+            .iter()
+            .filter_map(|operation| {
+                let forward_headers = operation.metadata.forward_headers.as_deref()?;
+                Some((query_hash(&operation.body), forward_headers.into()))
+            })
+            .collect();
+        *self.persisted_operations.write().unwrap() = operations;
+    }
+
+    /// The headers forwarded for an operation, if its persisted operation
+    /// overrides the `forward_headers` of the gateway.
+    pub fn operation_forward_headers(&self, query: &str) -> Option<Arc<[String]>> {
+        let operation_forward_headers = self.operation_forward_headers.read().unwrap();
+        if operation_forward_headers.is_empty() {
+            return None;
+        }
+        operation_forward_headers.get(&query_hash(query)).cloned()
+    }
+
+    /// The context rules that are invalid against the current schema.
+    pub fn invalid_context_rules(&self) -> Vec<ContextRule> {
+        self.context_injector.load().invalid_rules()
+    }
+
+    /// The persisted operations that are invalid against the current schema.
+    pub fn invalid_persisted_operations(&self) -> Vec<InvalidPersistedOperation> {
+        self.invalid_persisted_operations.read().unwrap().clone()
+    }
+
+    /// Probe the reference resolvers of the subgraphs after every
+    /// composition.
+    pub fn set_entity_check_config(&self, entity_check_config: EntityCheckConfig) {
+        *self.entity_check_config.write().unwrap() = Some(entity_check_config);
+    }
+
+    /// Probe the reference resolvers of the subgraphs for the current
+    /// schema, returning the entity types that could not be resolved.
+    pub async fn check_entity_resolvers(&self) -> Vec<EntityResolverError> {
+        let config = match self.entity_check_config.read().unwrap().clone() {
+            Some(config) => config,
+            None => return Vec::new(),
+        };
+        let (schema, route_table) = match self.get().await {
+            Some(res) => res,
+            None => return Vec::new(),
+        };
+
+        let errors = check_entity_resolvers(&config, &schema, &route_table).await;
+        for error in &errors {
+            tracing::warn!(
+                service = %error.service,
+                type_name = %error.type_name,
+                message = %error.message,
+                "Entity reference resolver check failed."
+            );
+        }
+        *self.entity_resolver_errors.write().unwrap() = errors.clone();
+        errors
+    }
+
+    /// The entity types the subgraphs failed to resolve in the latest
+    /// check.
+    pub fn entity_resolver_errors(&self) -> Vec<EntityResolverError> {
+        self.entity_resolver_errors.read().unwrap().clone()
+    }
+
+    /// The versions of the SDLs of the subgraphs the current schema is
+    /// composed from.
+    pub fn subgraph_schemas(&self) -> Vec<SubgraphSchema> {
+        self.subgraph_schemas.read().unwrap().clone()
+    }
+
+    /// The routes of the active route table, sorted by service name.
+    pub async fn routes(&self) -> Vec<RouteStatus> {
+        let route_table = match self.inner.read().await.route_table.clone() {
+            Some(route_table) => route_table,
+            None => return Vec::new(),
+        };
+        let route_health = self.route_health.read().unwrap();
+        let subgraph_schemas = self.subgraph_schemas.read().unwrap();
+        let mut routes = route_table
+            .iter()
+            .map(|(service, route)| RouteStatus {
+                service: service.clone(),
+                url: route_table.url(service, false).unwrap_or_default(),
+                websocket_url: route_table.websocket_url(service).unwrap_or_default(),
+                source: route.source,
+                health: route_health.get(service).cloned().unwrap_or_default(),
+                sdl_hash: subgraph_schemas
+                    .iter()
+                    .find(|subgraph| subgraph.service == *service)
+                    .map(|subgraph| subgraph.sdl_hash.clone()),
+            })
+            .collect::<Vec<_>>();
+        routes.sort_by(|a, b| a.service.cmp(&b.service));
+        routes
+    }
+
+    /// The SDLs of the services the current schema is composed from, by
+    /// service name, empty if it is loaded from a supergraph SDL.
+    pub async fn sdls(&self) -> Vec<(String, String)> {
+        self.inner.read().await.sdls.clone()
+    }
+
+    /// Fetch the SDLs of the services and compose the schema without waiting
+    /// for the next update.
+    pub fn refresh(&self) {
+        self.tx.send(Command::Refresh).ok();
+    }
+
+    pub fn set_route_table(&self, mut route_table: ServiceRouteTable) {
+        route_table.apply_aliases(&self.service_aliases);
+        route_table.apply_request_config(&self.subgraph_request_config.read().unwrap());
+        route_table.set_state(self.subgraph_state());
         self.tx.send(Command::Change(route_table)).ok();
     }
 
+    /// The state the route tables send the requests to the services with.
+    fn subgraph_state(&self) -> SubgraphState {
+        SubgraphState {
+            rate_limiter: self.rate_limiter.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+            token_cache: self.token_cache.clone(),
+            upstream: self.upstream.clone(),
+        }
+    }
+
+    /// Set the User-Agent and metadata headers of the requests to the
+    /// subgraphs, applied to every route table set or loaded afterwards.
+    pub fn set_subgraph_request_config(&self, subgraph_request_config: SubgraphRequestConfig) {
+        *self.subgraph_request_config.write().unwrap() = subgraph_request_config;
+    }
+
+    /// Set the map from discovered service names to schema service names,
+    /// applied to every route table set afterwards.
+    pub fn set_service_aliases(&mut self, service_aliases: HashMap<String, String>) {
+        self.service_aliases = service_aliases;
+    }
+
     pub fn set_receive_headers(&mut self, receive_headers: Vec<String>) {
         self.receive_headers = receive_headers;
     }
 
+    /// Record these headers of the subgraph responses as attributes of the
+    /// fetch spans, in the explained plans and, with debug errors, in the
+    /// `subgraphResponseHeaders` extension.
+    pub fn set_trace_response_headers(&mut self, headers: Vec<String>) {
+        self.trace_response_headers = Arc::new(headers.iter().map(|name| name.to_ascii_lowercase()).collect());
+    }
+
+    pub fn set_defer_config(&mut self, defer_config: DeferConfig) {
+        self.defer_config = defer_config;
+    }
+
+    /// Set the context rules, validated against every schema composed
+    /// afterwards.
+    pub fn set_context_rules(&self, context_rules: Vec<ContextRule>) {
+        self.context_injector
+            .store(Arc::new(ContextInjector::new(context_rules)));
+    }
+
+    pub fn set_redaction_rules(&mut self, redaction_rules: Vec<RedactionRule>) {
+        self.redaction_rules = Arc::new(redaction_rules);
+    }
+
+    /// Audit the executed mutations, failing if the audit file cannot be
+    /// opened.
+    pub fn set_audit_config(&mut self, audit_config: AuditConfig) -> std::io::Result<()> {
+        self.audit_log = Some(Arc::new(AuditLog::open(audit_config)?));
+        Ok(())
+    }
+
+    pub fn set_pagination_config(&mut self, pagination_config: PaginationConfig) {
+        self.pagination_config = Some(pagination_config);
+    }
+
+    /// Reject operations whose cost, estimated from the `@cost` and
+    /// `@listSize` directives of the subgraphs, exceeds the limit.
+    pub fn set_cost_config(&mut self, cost_config: CostConfig) {
+        self.cost_config = Some(cost_config);
+    }
+
+    /// Reject operations selecting deprecated fields past their sunset date.
+    pub fn set_deprecation_config(&mut self, deprecation_config: DeprecationConfig) {
+        self.deprecation_config = Some(deprecation_config);
+    }
+
+    /// Add a `Server-Timing` header to the query responses, with the fetch
+    /// durations of each service and the metrics of their `Server-Timing`
+    /// headers.
+    pub fn set_server_timing(&mut self, server_timing: bool) {
+        self.server_timing = server_timing;
+    }
+
+    /// Describe the subgraph request in the extensions of the errors it
+    /// caused, including the query and variable names, but not their values.
+    pub fn set_debug_errors(&mut self, debug_errors: bool) {
+        self.debug_errors = debug_errors;
+    }
+
+    /// Report the errors of the entity fetches at the paths of their entities
+    /// in the response, instead of under the path of the fetch.
+    pub fn set_normalize_error_paths(&mut self, normalize_error_paths: bool) {
+        self.normalize_error_paths = normalize_error_paths;
+    }
+
+    /// Check that the services resolve the entities by the keys they are
+    /// sent, reporting the entities resolved with other keys instead of
+    /// merging them.
+    pub fn set_check_entity_keys(&mut self, check_entity_keys: bool) {
+        self.check_entity_keys = check_entity_keys;
+    }
+
+    /// Plan the operations of the authenticated requests with an
+    /// `allowedServices` extension with only the services it lists.
+    pub fn set_allowed_services_extension(&mut self, allowed_services_extension: bool) {
+        self.allowed_services_extension = allowed_services_extension;
+    }
+
+    /// Respond to the queries that failed as a whole because a subgraph
+    /// refused them with `401`, `403` or `502`, instead of `200`.
+    pub fn set_propagate_subgraph_status(&mut self, propagate_subgraph_status: bool) {
+        self.propagate_subgraph_status = propagate_subgraph_status;
+    }
+
+    /// Set how often the SDLs of the services are checked for changes.
+    pub fn set_update_interval(&self, update_interval: Duration) {
+        self.update_interval
+            .store(update_interval.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Set how often the SDLs of the services are polled, and how failed
+    /// polls are retried.
+    pub fn set_polling_config(&self, polling_config: PollingConfig) {
+        self.set_update_interval(Duration::from_secs(polling_config.interval_secs));
+        self.retry_interval.store(
+            Duration::from_secs(polling_config.retry_secs).as_millis() as u64,
+            Ordering::Relaxed,
+        );
+        self.max_backoff.store(
+            Duration::from_secs(polling_config.max_backoff_secs).as_millis() as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Cap the subgraph fetches running at once.
+    pub fn set_parallelism_config(&mut self, parallelism_config: ParallelismConfig) {
+        self.parallelism = parallelism_config.parallelism();
+    }
+
+    /// Fetch the nodes of large Relay connection pages in batches.
+    pub fn set_connection_config(&mut self, connection_config: ConnectionConfig) {
+        self.connection_config = connection_config;
+    }
+
+    /// Replace responses exceeding the size limits with an error.
+    pub fn set_response_limit_config(&mut self, response_limit_config: ResponseLimitConfig) {
+        self.response_limit_config = response_limit_config;
+    }
+
+    /// Abort operations sending more subgraph requests than the limit.
+    pub fn set_call_budget_config(&mut self, call_budget_config: CallBudgetConfig) {
+        self.call_budget_config = call_budget_config;
+    }
+
+    /// Reject operations that are nested too deeply, or select too many
+    /// aliases or root fields.
+    pub fn set_operation_limits_config(&mut self, operation_limits_config: OperationLimitsConfig) {
+        self.operation_limits_config = operation_limits_config;
+    }
+
+    /// Inject faults in the subgraph requests, if the config enables them.
+    pub fn set_chaos_config(&mut self, chaos_config: ChaosConfig) {
+        self.chaos_config = chaos_config.enabled.then(|| Arc::new(chaos_config));
+    }
+
+    /// Reject the operations selecting `__schema` or `__type`.
+    pub fn set_disable_introspection(&mut self, disable_introspection: bool) {
+        self.disable_introspection = disable_introspection;
+    }
+
+    /// Reject the operations that are not in `safelist`.
+    pub fn set_safelist(&mut self, safelist: Safelist) {
+        self.safelist = Some(Arc::new(safelist));
+    }
+
+    /// Set the rate limit and cache size of the introspection operations.
+    pub fn set_introspection_config(&mut self, introspection_config: IntrospectionConfig) {
+        self.introspection = Arc::new(IntrospectionGuard::new(introspection_config));
+    }
+
+    /// Set how requests to subgraphs that respond with 429 are backed off.
+    pub fn set_rate_limit_config(&self, rate_limit_config: RateLimitConfig) {
+        self.rate_limiter.set_config(rate_limit_config);
+    }
+
+    /// Fail the fetches to the services that failed too many times in a row
+    /// fast, for a cool-down.
+    pub fn set_circuit_breaker_config(&self, circuit_breaker_config: CircuitBreakerConfig) {
+        self.circuit_breaker.set_config(circuit_breaker_config);
+    }
+
+    /// Send the fetches to each service with its own pool of connections,
+    /// optionally over HTTP/2.
+    pub fn set_upstream_config(&self, upstream_config: UpstreamConfig) {
+        self.upstream.set_config(upstream_config);
+    }
+
+    /// Accept the queries of the Automatic Persisted Queries protocol, stored
+    /// in `cache` by their hash.
+    pub fn set_persisted_query_cache(&mut self, cache: Arc<dyn PersistedQueryCache>) {
+        self.persisted_query_cache = Some(cache);
+    }
+
+    /// Serve the entities of the `_entities` fetches from `cache`, for as
+    /// long as their `@cacheControl` hints allow.
+    pub fn set_entity_cache(&mut self, cache: Arc<dyn EntityCache>) {
+        self.entity_cache = Some(cache);
+    }
+
+    /// Partition the `PRIVATE` cached entities by these claims of the
+    /// verified tokens, instead of by the `Authorization` header.
+    pub fn set_entity_cache_partition_claims(&mut self, partition_claims: Vec<String>) {
+        self.entity_cache_partition_claims = partition_claims;
+    }
+
+    /// Keep the anonymous requests with these headers, which carry the
+    /// credentials of their clients, out of the `public` partition.
+    pub fn set_entity_cache_credential_headers(&mut self, credential_headers: Vec<String>) {
+        self.entity_cache_credential_headers = credential_headers;
+    }
+
+    /// Assign the requests to the buckets of `bucketing`, so that the
+    /// injected faults are rolled out to the same clients on every request.
+    pub fn set_bucketing(&mut self, bucketing: Bucketing) {
+        self.bucketing = Some(bucketing);
+    }
+
+    /// The usage of the caches in use, with their `top` most hit entries.
+    pub fn cache_stats(&self, top: usize) -> Vec<(CacheKind, CacheStats)> {
+        let mut stats = Vec::new();
+        if let Some(cache) = &self.persisted_query_cache {
+            stats.push((CacheKind::PersistedQueries, cache.stats(top)));
+        }
+        if let Some(cache) = &self.entity_cache {
+            stats.push((CacheKind::Entities, cache.stats(top)));
+        }
+        stats.push((CacheKind::Introspection, self.introspection.stats(top)));
+        stats
+    }
+
+    /// Remove the entry stored under `key` from a cache, returning whether
+    /// there was one.
+    pub async fn evict_cache_entry(&self, kind: CacheKind, key: &str) -> anyhow::Result<bool> {
+        match kind {
+            CacheKind::PersistedQueries => match &self.persisted_query_cache {
+                Some(cache) => cache.evict(key).await,
+                None => Ok(false),
+            },
+            CacheKind::Entities => match &self.entity_cache {
+                Some(cache) => cache.evict(key).await,
+                None => Ok(false),
+            },
+            CacheKind::Introspection => Ok(self.introspection.evict(key)),
+        }
+    }
+
+    /// Reject the operation with the SHA-256 `hash` until it is unblocked,
+    /// returning `false` if it was already blocked.
+    pub fn block_operation(&self, hash: &str) -> bool {
+        self.blocklist.block(hash)
+    }
+
+    /// Serve the operation with the SHA-256 `hash` again, returning `false`
+    /// if it was not blocked.
+    pub fn unblock_operation(&self, hash: &str) -> bool {
+        self.blocklist.unblock(hash)
+    }
+
+    /// The SHA-256 hashes of the blocked operations, sorted.
+    pub fn blocked_operations(&self) -> Vec<String> {
+        self.blocklist.hashes()
+    }
+
+    pub(crate) fn safelist(&self) -> Option<&Safelist> {
+        self.safelist.as_deref()
+    }
+
+    pub(crate) fn persisted_query_cache(&self) -> Option<&dyn PersistedQueryCache> {
+        self.persisted_query_cache.as_deref()
+    }
+
+    /// The faults injected in the subgraph requests of a request, if chaos
+    /// is enabled.
+    fn chaos_faults(&self, context: &RequestContext) -> Option<ChaosFaults> {
+        let chaos_config = self.chaos_config.clone()?;
+        Some(ChaosFaults::new(chaos_config, self.bucketing.as_ref(), context))
+    }
+
+    /// The services the operation of the request is planned with, `None` for
+    /// all of them.
+    ///
+    /// Only the authenticated requests may restrict the services.
+    fn allowed_services<'c>(&self, context: &'c RequestContext) -> Option<&'c [String]> {
+        context
+            .allowed_services
+            .as_deref()
+            .filter(|_| self.allowed_services_extension && context.claims.is_some())
+    }
+
+    pub(crate) fn redaction_rules(&self) -> Arc<Vec<RedactionRule>> {
+        self.redaction_rules.clone()
+    }
+
+    pub(crate) fn audit_log(&self) -> Option<Arc<AuditLog>> {
+        self.audit_log.clone()
+    }
+
+    /// The schema introspection operations see, unless they see the whole
+    /// composed schema.
+    pub(crate) fn visible_schema(&self, composed_schema: &Arc<ComposedSchema>) -> Option<Arc<ComposedSchema>> {
+        self.introspection.visible_schema(composed_schema)
+    }
+
+    /// How long to wait for the deferred parts of a query before sending them
+    /// as incremental payloads.
+    pub(crate) fn defer_latency_budget(&self) -> Duration {
+        Duration::from_millis(self.defer_config.latency_budget_ms)
+    }
+
+    /// Returns `true` once a schema has been composed for the current route
+    /// table.
+    pub fn is_ready(&self) -> bool {
+        *self.ready.borrow()
+    }
+
+    /// Watch the outcome of the schema updates.
+    pub fn watch_composition(&self) -> watch::Receiver<CompositionStatus> {
+        self.composition.subscribe()
+    }
+
+    /// Wait until a schema has been composed, returning `false` if that did
+    /// not happen within `timeout`.
+    pub async fn wait_ready(&self, timeout: Duration) -> bool {
+        let mut ready = self.ready.subscribe();
+        let res = tokio::time::timeout(timeout, ready.wait_for(|ready| *ready)).await;
+        matches!(res, Ok(Ok(_)))
+    }
+
     pub async fn get(&self) -> Option<(Arc<ComposedSchema>, Arc<ServiceRouteTable>)> {
         let (composed_schema, route_table) = {
             let inner = self.inner.read().await;
@@ -141,24 +1162,29 @@ impl SharedRouteTable {
         composed_schema.zip(route_table)
     }
 
-    #[instrument(skip(self, request, header_map), ret, level = "trace")]
-    pub async fn query(&self, request: Request, header_map: HeaderMap) -> HttpResponse<String> {
+    /// Parse a request and apply the context rules and default limits,
+    /// returning the response to send if that fails.
+    async fn prepare(
+        &self,
+        request: &mut Request,
+        context: &RequestContext,
+    ) -> Result<PreparedQuery, HttpResponse<Body>> {
         let tracer = global::tracer("graphql");
 
-        let document = match tracer.in_span("parse", |_| parser::parse_query(&request.query)) {
+        let mut document = match tracer.in_span("parse", |_| parser::parse_query(&request.query)) {
             Ok(document) => document,
             Err(err) => {
-                return HttpResponse::builder()
+                return Err(HttpResponse::builder()
                     .status(StatusCode::BAD_REQUEST)
-                    .body(err.to_string())
-                    .unwrap();
+                    .body(err.to_string().into())
+                    .unwrap());
             },
         };
 
         let (composed_schema, route_table) = match self.get().await {
             Some((composed_schema, route_table)) => (composed_schema, route_table),
             _ => {
-                return HttpResponse::builder()
+                return Err(HttpResponse::builder()
                     .status(StatusCode::BAD_REQUEST)
                     .body(
                         serde_json::to_string(&Response {
@@ -167,43 +1193,291 @@ impl SharedRouteTable {
                             extensions: Default::default(),
                             headers: Default::default(),
                         })
-                        .unwrap_or_default(),
+                        .unwrap_or_default()
+                        .into(),
                     )
-                    .unwrap_or_default();
+                    .unwrap_or_default());
             },
         };
 
-        let mut plan_builder = PlanBuilder::new(&composed_schema, document).variables(request.variables);
-        if let Some(operation) = request.operation {
+        let (extensions, errors) = self
+            .check_operation(&composed_schema, &mut document, request, context)
+            .map_err(error_response)?;
+
+        Ok(PreparedQuery {
+            composed_schema,
+            route_table,
+            document,
+            extensions,
+            errors,
+        })
+    }
+
+    /// Apply the context rules, the limits, the authorization and the
+    /// default page sizes to the document of an operation, as every transport
+    /// does before planning it.
+    ///
+    /// Returns the extensions of the response and the errors of the fields
+    /// removed from the operation, or the errors failing it.
+    pub(crate) fn check_operation(
+        &self,
+        composed_schema: &Arc<ComposedSchema>,
+        document: &mut ExecutableDocument,
+        request: &mut Request,
+        context: &RequestContext,
+    ) -> Result<CheckedOperation, Vec<ServerError>> {
+        if self.blocklist.contains(&request.query) {
+            return Err(vec![operation_blocked()]);
+        }
+
+        let context_injector = self.context_injector.load();
+        if !context_injector.is_empty() {
+            if let Err(err) = context_injector.inject(composed_schema, document, &mut request.variables, context) {
+                return Err(vec![ServerError::new(err)]);
+            }
+        }
+
+        if let Some(error) =
+            check_operation_limits(&self.operation_limits_config, document, request.operation.as_deref())
+        {
+            return Err(vec![error]);
+        }
+
+        if self.disable_introspection && selects_introspection(document, request.operation.as_deref()) {
+            return Err(vec![introspection_disabled()]);
+        }
+
+        let errors = remove_unauthorized_fields(
+            composed_schema,
+            document,
+            request.operation.as_deref(),
+            context.claims.as_ref(),
+        )?;
+
+        let mut warnings = Vec::new();
+        if let Some(pagination_config) = &self.pagination_config {
+            warnings.extend(apply_default_limits(pagination_config, composed_schema, document));
+        }
+
+        if let Some(deprecation_config) = &self.deprecation_config {
+            warnings.extend(check_sunsets(
+                deprecation_config,
+                composed_schema,
+                document,
+                &request.variables,
+            )?);
+        }
+
+        warnings.extend(nested_defer_warnings(
+            document,
+            request.operation.as_deref(),
+            &request.variables,
+        ));
+
+        let mut extensions = HashMap::new();
+        if !warnings.is_empty() {
+            extensions.insert(WARNINGS_EXTENSION.to_string(), warnings_extension(&warnings));
+        }
+
+        if let Some(cost_config) = &self.cost_config {
+            let cost = estimate_cost(
+                cost_config,
+                composed_schema,
+                document,
+                request.operation.as_deref(),
+                &request.variables,
+            );
+            let max_cost = cost_config.max_cost_for(context);
+            let error = match cost {
+                Some(Ok(cost)) if cost > max_cost => {
+                    let mut error = ServerError::new(format!(
+                        "The estimated cost {} of the operation exceeds the limit of {}.",
+                        cost, max_cost
+                    ));
+                    error
+                        .extensions
+                        .insert("code".to_string(), ConstValue::String(COST_LIMIT_EXCEEDED.to_string()));
+                    error
+                        .extensions
+                        .insert("cost".to_string(), ConstValue::Number(cost.into()));
+                    Some(error)
+                },
+                Some(Ok(cost)) => {
+                    extensions.insert("cost".to_string(), cost_extension(cost, max_cost));
+                    None
+                },
+                Some(Err(err)) => Some(ServerError::new(err)),
+                None => None,
+            };
+            if let Some(error) = error {
+                return Err(vec![error]);
+            }
+        }
+
+        Ok((extensions, errors))
+    }
+
+    #[instrument(skip(self, request, header_map, context), ret, level = "trace")]
+    pub async fn query(
+        &self,
+        mut request: Request,
+        header_map: HeaderMap,
+        context: RequestContext,
+        incremental: bool,
+    ) -> HttpResponse<Body> {
+        let start_time = Instant::now();
+        let tracer = global::tracer("graphql");
+        let PreparedQuery {
+            composed_schema,
+            route_table,
+            document,
+            extensions,
+            errors,
+        } = match self.prepare(&mut request, &context).await {
+            Ok(prepared) => prepared,
+            Err(resp) => return resp,
+        };
+
+        if is_introspection(&document, request.operation.as_deref()) {
+            let mut resp = self
+                .query_introspection(&composed_schema, &route_table, document, request, &header_map)
+                .await;
+            resp.errors.splice(0..0, errors);
+            resp.merge_extensions(extensions);
+            return self.http_response(
+                resp,
+                start_time,
+                &ResponseBudget::new(&self.response_limit_config),
+                &CallBudget::new(&self.call_budget_config),
+                &ResponseHeaders::new(self.trace_response_headers.clone()),
+                &ServerTiming::default(),
+            );
+        }
+
+        // The redacted fields may be in any incremental payload, so the
+        // responses of redacted requests are not streamed.
+        let redactor = Redactor::new(&self.redaction_rules, &context, &document, request.operation.as_deref());
+        // Audited mutations are answered once their result is recorded.
+        let audit = match &self.audit_log {
+            Some(audit_log) if is_mutation(&document, request.operation.as_deref()) => {
+                Some((audit_log, request.variables.clone()))
+            },
+            _ => None,
+        };
+        if incremental && redactor.is_none() && audit.is_none() {
+            let prepared = PreparedQuery {
+                composed_schema,
+                route_table,
+                document,
+                extensions,
+                errors,
+            };
+            let allowed_services = self.allowed_services(&context).map(ToOwned::to_owned);
+            return self
+                .query_incremental(prepared, request, header_map, &context, allowed_services, start_time)
+                .await;
+        }
+
+        let mut plan_builder = PlanBuilder::new(&composed_schema, document)
+            .variables(request.variables)
+            .check_entity_keys(self.check_entity_keys);
+        if let Some(operation) = &request.operation {
             plan_builder = plan_builder.operation_name(operation);
         }
+        if let Some(allowed_services) = self.allowed_services(&context) {
+            plan_builder = plan_builder.allowed_services(allowed_services);
+        }
 
+        #[allow(clippy::result_large_err)]
         let plan = match tracer.in_span("plan", |_| plan_builder.plan()) {
             Ok(plan) => plan,
             Err(response) => {
-                return HttpResponse::builder()
-                    .status(StatusCode::OK)
-                    .header(CONTENT_TYPE, "application/json")
-                    .body(serde_json::to_string(&response).unwrap())
-                    .unwrap();
+                return json_response(&response);
             },
         };
 
-        let executor = Executor::new(&composed_schema);
-        let resp = opentelemetry::trace::FutureExt::with_context(
-            executor.execute_query(&HttpFetcher::new(&route_table, &header_map), &plan),
+        let audit_entry = match &audit {
+            Some((audit_log, variables)) => {
+                match audit_log
+                    .begin(request.operation.as_deref(), &context, variables, &plan)
+                    .await
+                {
+                    Ok(entry) => Some(entry),
+                    Err(err) => {
+                        tracing::error!(error = %err, "Failed to write the audit record of a mutation.");
+                        return json_response(&audit_unavailable());
+                    },
+                }
+            },
+            None => None,
+        };
+
+        let visible_schema = self.introspection.visible_schema(&composed_schema);
+        let mut executor = Executor::new(&composed_schema)
+            .debug_errors(self.debug_errors)
+            .normalize_error_paths(self.normalize_error_paths)
+            .connection_batch_size(self.connection_config.batch_size);
+        if let Some(visible_schema) = &visible_schema {
+            executor = executor.introspection_schema(visible_schema);
+        }
+        let response_budget = ResponseBudget::new(&self.response_limit_config);
+        let call_budget = CallBudget::new(&self.call_budget_config);
+        let response_headers = ResponseHeaders::new(self.trace_response_headers.clone());
+        let server_timing = ServerTiming::default();
+        let cache_partition = CachePartition::new(
+            &self.entity_cache_partition_claims,
+            &self.entity_cache_credential_headers,
+            context.claims.as_ref(),
+            &header_map,
+        );
+        let mut fetcher = HttpFetcher::new(&route_table, &header_map)
+            .response_budget(&response_budget)
+            .call_budget(&call_budget)
+            .response_headers(&response_headers)
+            .schema(&composed_schema);
+        if let Some(entity_cache) = &self.entity_cache {
+            fetcher = fetcher.entity_cache(entity_cache.as_ref(), &cache_partition);
+        }
+        let chaos_faults = self.chaos_faults(&context);
+        if let Some(chaos_faults) = &chaos_faults {
+            fetcher = fetcher.chaos(chaos_faults);
+        }
+        if self.server_timing {
+            fetcher = fetcher.server_timing(&server_timing);
+        }
+        let mut resp = opentelemetry::trace::FutureExt::with_context(
+            executor.execute_query(&self.parallelism.limit(fetcher), &plan),
             OpenTelemetryContext::current_with_span(tracer.span_builder("execute").start(&tracer)),
         )
         .await;
+        if let (Some((audit_log, _)), Some(entry)) = (&audit, audit_entry) {
+            audit_log.complete(entry, &resp).await;
+        }
+        if let Some(redactor) = &redactor {
+            redactor.redact(&composed_schema, &mut resp.data);
+        }
+        resp.errors.splice(0..0, errors);
+        resp.merge_extensions(extensions);
+        self.http_response(
+            resp,
+            start_time,
+            &response_budget,
+            &call_budget,
+            &response_headers,
+            &server_timing,
+        )
+    }
 
-        let mut builder = HttpResponse::builder()
-            .status(StatusCode::OK)
-            .header(CONTENT_TYPE, "application/json");
-
+    /// The headers of the subgraph responses passed on to the client.
+    fn received_headers(&self, resp: &Response) -> HeaderMap {
         let mut header_map = HeaderMap::new();
 
         if let Some(x) = resp.headers.clone() {
-            for (k, v) in x.into_iter().filter(|(k, _v)| self.receive_headers.contains(k)) {
+            // The aggregated Server-Timing replaces those of the subgraphs.
+            for (k, v) in x
+                .into_iter()
+                .filter(|(k, _v)| self.receive_headers.contains(k) && !(self.server_timing && k == "server-timing"))
+            {
                 for val in v {
                     header_map.append(
                         HeaderName::from_bytes(k.as_bytes()).unwrap(),
@@ -212,11 +1486,370 @@ impl SharedRouteTable {
                 }
             }
         }
+        header_map
+    }
+
+    /// The status and headers of the HTTP response of an executed operation,
+    /// from the subgraph responses it was built from, and with debug errors
+    /// the `subgraphResponseHeaders` extension of `resp`.
+    ///
+    /// The response of an incremental operation is built from its initial
+    /// payload.
+    fn response_builder(
+        &self,
+        resp: &mut Response,
+        start_time: Instant,
+        response_headers: &ResponseHeaders,
+        server_timing: &ServerTiming,
+    ) -> http::response::Builder {
+        if self.debug_errors {
+            if let Some(headers) = response_headers.extension() {
+                resp.extensions.insert("subgraphResponseHeaders".to_string(), headers);
+            }
+        }
+
+        let status = match self.propagate_subgraph_status {
+            true => subgraph_failure_status(resp).unwrap_or(StatusCode::OK),
+            false => StatusCode::OK,
+        };
+        let mut builder = HttpResponse::builder().status(status);
+
+        let mut header_map = self.received_headers(resp);
+
+        if self.server_timing {
+            if let Ok(value) = HeaderValue::from_str(&server_timing.header_value(start_time.elapsed())) {
+                header_map.insert(HeaderName::from_static("server-timing"), value);
+            }
+        }
 
         if let Some(x) = builder.headers_mut() {
             x.extend(header_map)
         };
+        builder
+    }
+
+    /// The HTTP response of an executed operation, unless the operation
+    /// exceeded its budgets.
+    fn http_response(
+        &self,
+        mut resp: Response,
+        start_time: Instant,
+        response_budget: &ResponseBudget,
+        call_budget: &CallBudget,
+        response_headers: &ResponseHeaders,
+        server_timing: &ServerTiming,
+    ) -> HttpResponse<Body> {
+        let builder = self
+            .response_builder(&mut resp, start_time, response_headers, server_timing)
+            .header(CONTENT_TYPE, "application/json");
+
+        let body = serde_json::to_string(&resp).unwrap();
+        if let Some(resp) = call_budget.check().or_else(|| response_budget.check(body.len())) {
+            return json_response(&resp);
+        }
+        builder.body(body.into()).unwrap()
+    }
+
+    /// Execute an introspection operation, once per schema and distinct
+    /// request, under the introspection rate limit.
+    async fn query_introspection(
+        &self,
+        composed_schema: &Arc<ComposedSchema>,
+        route_table: &ServiceRouteTable,
+        document: ExecutableDocument,
+        request: Request,
+        header_map: &HeaderMap,
+    ) -> Response {
+        if let Some(resp) = self.introspection.acquire() {
+            return resp;
+        }
+
+        let key = canonical_url("", &request, false);
+        self.introspection
+            .get_or_execute(composed_schema, key, || async {
+                let mut plan_builder = PlanBuilder::new(composed_schema, document).variables(request.variables);
+                if let Some(operation) = request.operation {
+                    plan_builder = plan_builder.operation_name(operation);
+                }
+                let plan = match plan_builder.plan() {
+                    Ok(plan) => plan,
+                    Err(response) => return response,
+                };
+                let fetcher = HttpFetcher::new(route_table, header_map).schema(composed_schema);
+                let visible_schema = self.introspection.visible_schema(composed_schema);
+                let mut executor = Executor::new(composed_schema);
+                if let Some(visible_schema) = &visible_schema {
+                    executor = executor.introspection_schema(visible_schema);
+                }
+                executor.execute_query(&fetcher, &plan).await
+            })
+            .await
+    }
+
+    /// Plan a request without executing it, returning the plan annotated
+    /// with the recent latencies of its fetches, or its graph in the DOT or
+    /// Mermaid format.
+    #[instrument(skip(self, request, context), level = "trace")]
+    pub async fn explain(
+        &self,
+        mut request: Request,
+        context: RequestContext,
+        format: PlanFormat,
+    ) -> HttpResponse<Body> {
+        let PreparedQuery {
+            composed_schema,
+            document,
+            ..
+        } = match self.prepare(&mut request, &context).await {
+            Ok(prepared) => prepared,
+            Err(resp) => return resp,
+        };
 
-        builder.body(serde_json::to_string(&resp).unwrap()).unwrap()
+        let mut plan_builder = PlanBuilder::new(&composed_schema, document)
+            .variables(request.variables)
+            .check_entity_keys(self.check_entity_keys);
+        if let Some(operation) = request.operation {
+            plan_builder = plan_builder.operation_name(operation);
+        }
+        if let Some(allowed_services) = self.allowed_services(&context) {
+            plan_builder = plan_builder.allowed_services(allowed_services);
+        }
+
+        let (content_type, body) = match (plan_builder.plan(), format) {
+            (Ok(plan), PlanFormat::Json) => {
+                let mut plan = serde_json::to_value(&plan).unwrap();
+                annotate_fetches(&mut plan);
+                ("application/json", serde_json::to_string(&plan).unwrap())
+            },
+            (Ok(plan), PlanFormat::Dot) => ("text/vnd.graphviz", plan.to_dot_annotated(&fetch_latency_line)),
+            (Ok(plan), PlanFormat::Mermaid) => ("text/plain", plan.to_mermaid_annotated(&fetch_latency_line)),
+            (Err(response), _) => ("application/json", serde_json::to_string(&response).unwrap()),
+        };
+        HttpResponse::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, content_type)
+            .body(body.into())
+            .unwrap()
+    }
+
+    /// Execute an operation over the WebSocket connections to the services,
+    /// sending its responses as Server-Sent Events.
+    ///
+    /// Subscriptions are sent this way to the clients that do not use
+    /// WebSockets.
+    #[instrument(skip(self, request, header_map, context), ret, level = "trace")]
+    pub async fn subscribe(
+        &self,
+        mut request: Request,
+        header_map: HeaderMap,
+        context: RequestContext,
+    ) -> HttpResponse<Body> {
+        let PreparedQuery {
+            composed_schema,
+            route_table,
+            document,
+            extensions,
+            errors,
+        } = match self.prepare(&mut request, &context).await {
+            Ok(prepared) => prepared,
+            Err(resp) => return resp,
+        };
+        let redactor = Redactor::new(&self.redaction_rules, &context, &document, request.operation.as_deref());
+        let audit_log = self
+            .audit_log
+            .clone()
+            .filter(|_| is_mutation(&document, request.operation.as_deref()));
+        let check_entity_keys = self.check_entity_keys;
+        let allowed_services = self.allowed_services(&context).map(ToOwned::to_owned);
+        let call_budget = Arc::new(CallBudget::new(&self.call_budget_config));
+
+        let stream = async_stream::stream! {
+            let audited_variables = audit_log.as_ref().map(|_| request.variables.clone());
+            let mut plan_builder = PlanBuilder::new(&composed_schema, document)
+                .variables(request.variables)
+                .check_entity_keys(check_entity_keys);
+            if let Some(operation) = &request.operation {
+                plan_builder = plan_builder.operation_name(operation);
+            }
+            if let Some(allowed_services) = allowed_services {
+                plan_builder = plan_builder.allowed_services(allowed_services);
+            }
+            let plan = match plan_builder.plan() {
+                Ok(plan) => plan,
+                Err(resp) => {
+                    yield resp;
+                    return;
+                },
+            };
+
+            let mut audit_entry = match (&audit_log, &audited_variables) {
+                (Some(audit_log), Some(variables)) => {
+                    match audit_log.begin(request.operation.as_deref(), &context, variables, &plan).await {
+                        Ok(entry) => Some(entry),
+                        Err(err) => {
+                            tracing::error!(error = %err, "Failed to write the audit record of a mutation.");
+                            yield audit_unavailable();
+                            return;
+                        },
+                    }
+                },
+                _ => None,
+            };
+
+            // The connections to the services are closed once the client
+            // disconnects and the stream is dropped. The call budget applies
+            // to the requests of each event.
+            let controller = WebSocketController::new(route_table, &header_map, None);
+            let subscriber = BudgetedSubscriber::new(controller, call_budget.clone());
+            let mut stream = Executor::new(&composed_schema)
+                .execute_stream(subscriber, "1", &plan)
+                .await;
+            let mut first = Some((extensions, errors));
+            while let Some(mut resp) = stream.next().await {
+                if let Some(exceeded) = call_budget.check() {
+                    yield exceeded;
+                    return;
+                }
+                call_budget.reset();
+                if let Some((extensions, errors)) = first.take() {
+                    resp.errors.splice(0..0, errors);
+                    resp.merge_extensions(extensions);
+                }
+                if let (Some(audit_log), Some(entry)) = (&audit_log, audit_entry.take()) {
+                    audit_log.complete(entry, &resp).await;
+                }
+                if let Some(redactor) = &redactor {
+                    redactor.redact(&composed_schema, &mut resp.data);
+                }
+                yield resp;
+            }
+        };
+
+        HttpResponse::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, EVENT_STREAM_CONTENT_TYPE)
+            .header(CACHE_CONTROL, "no-cache")
+            .body(event_stream_body(isolate_stream(stream, |resp, _| resp)))
+            .unwrap()
+    }
+
+    async fn query_incremental(
+        &self,
+        prepared: PreparedQuery,
+        request: Request,
+        header_map: HeaderMap,
+        context: &RequestContext,
+        allowed_services: Option<Vec<String>>,
+        start_time: Instant,
+    ) -> HttpResponse<Body> {
+        let PreparedQuery {
+            composed_schema,
+            route_table,
+            document,
+            extensions,
+            errors,
+        } = prepared;
+        let tracer = global::tracer("graphql");
+        let primary_fields = self.defer_config.primary_fields.clone();
+        let latency_budget = Duration::from_millis(self.defer_config.latency_budget_ms);
+        let debug_errors = self.debug_errors;
+        let normalize_error_paths = self.normalize_error_paths;
+        let check_entity_keys = self.check_entity_keys;
+        let connection_batch_size = self.connection_config.batch_size;
+        let parallelism = self.parallelism.clone();
+        let response_limit_config = self.response_limit_config.clone();
+        let call_budget_config = self.call_budget_config.clone();
+        let chaos_faults = self.chaos_faults(context);
+        let visible_schema = self.introspection.visible_schema(&composed_schema);
+        // The subgraph responses of the initial payload are recorded until
+        // it is built, for the headers of the HTTP response.
+        let response_headers = Arc::new(ResponseHeaders::new(self.trace_response_headers.clone()));
+        let server_timing = Arc::new(ServerTiming::default());
+        let server_timing_enabled = self.server_timing;
+        let entity_cache = self.entity_cache.clone();
+        let cache_partition = CachePartition::new(
+            &self.entity_cache_partition_claims,
+            &self.entity_cache_credential_headers,
+            context.claims.as_ref(),
+            &header_map,
+        );
+
+        let stream_response_headers = response_headers.clone();
+        let stream_server_timing = server_timing.clone();
+        let stream = async_stream::stream! {
+            let mut plan_builder = PlanBuilder::new(&composed_schema, document)
+                .variables(request.variables)
+                .primary_fields(primary_fields)
+                .check_entity_keys(check_entity_keys);
+            if let Some(operation) = request.operation {
+                plan_builder = plan_builder.operation_name(operation);
+            }
+            if let Some(allowed_services) = allowed_services {
+                plan_builder = plan_builder.allowed_services(allowed_services);
+            }
+
+            #[allow(clippy::result_large_err)]
+            let plan = match tracer.in_span("plan", |_| plan_builder.plan()) {
+                Ok(plan) => plan,
+                Err(response) => {
+                    yield IncrementalResponse::Initial { response, has_next: false };
+                    return;
+                },
+            };
+
+            // The payloads are streamed as they complete, so the limits only
+            // fail the fetches exceeding them.
+            let response_budget = ResponseBudget::new(&response_limit_config);
+            let call_budget = CallBudget::new(&call_budget_config);
+            let mut fetcher = HttpFetcher::new(&route_table, &header_map)
+                .response_budget(&response_budget)
+                .call_budget(&call_budget)
+                .response_headers(&stream_response_headers)
+                .schema(&composed_schema);
+            if let Some(entity_cache) = &entity_cache {
+                fetcher = fetcher.entity_cache(entity_cache.as_ref(), &cache_partition);
+            }
+            if let Some(chaos_faults) = &chaos_faults {
+                fetcher = fetcher.chaos(chaos_faults);
+            }
+            if server_timing_enabled {
+                fetcher = fetcher.server_timing(&stream_server_timing);
+            }
+            let fetcher = parallelism.limit(fetcher);
+            let mut executor = Executor::new(&composed_schema)
+                .debug_errors(debug_errors)
+                .normalize_error_paths(normalize_error_paths)
+                .connection_batch_size(connection_batch_size);
+            if let Some(visible_schema) = &visible_schema {
+                executor = executor.introspection_schema(visible_schema);
+            }
+            let mut stream = opentelemetry::trace::FutureExt::with_context(
+                executor.execute_incremental(&fetcher, &plan, latency_budget),
+                OpenTelemetryContext::current_with_span(tracer.span_builder("execute").start(&tracer)),
+            );
+            let mut errors = Some(errors);
+            while let Some(mut resp) = stream.next().await {
+                if let IncrementalResponse::Initial { response, .. } = &mut resp {
+                    response.errors.splice(0..0, errors.take().unwrap_or_default());
+                    response.merge_extensions(extensions.clone());
+                }
+                yield resp;
+            }
+        };
+
+        // The headers are sent with the initial payload, so it is awaited
+        // for the headers of the subgraph responses it was built from.
+        let mut stream = Box::pin(isolate_stream(stream, incremental_panic_payload));
+        let mut initial = stream.next().await;
+        let builder = match &mut initial {
+            Some(IncrementalResponse::Initial { response, .. }) => {
+                self.response_builder(response, start_time, &response_headers, &server_timing)
+            },
+            _ => HttpResponse::builder().status(StatusCode::OK),
+        };
+        builder
+            .header(CONTENT_TYPE, MULTIPART_CONTENT_TYPE)
+            .body(multipart_body(futures_util::stream::iter(initial).chain(stream)))
+            .unwrap()
     }
 }
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{enum_values::EnumValues, websocket::Protocols, RouteSource, ServiceRoute, ServiceRouteTable};
+
+/// The SDLs and routes of the last composed schema, persisted so that a
+/// restarting gateway serves it until the subgraphs can be reached.
+///
+/// The headers, credentials and connection params of the routes are left out, they are applied
+/// from the configuration when the snapshot is loaded.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    pub(crate) sdls: Vec<SubgraphSdl>,
+    pub(crate) routes: HashMap<String, SnapshotRoute>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SubgraphSdl {
+    pub(crate) service: String,
+    pub(crate) sdl: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SnapshotRoute {
+    addr: String,
+    tls: bool,
+    query_path: Option<String>,
+    subscribe_path: Option<String>,
+    introspection_path: Option<String>,
+    #[serde(default)]
+    schema_url: Option<String>,
+    websocket_path: Option<String>,
+    #[serde(default)]
+    websocket_protocol: Option<Protocols>,
+    #[serde(default)]
+    enum_values: EnumValues,
+    #[serde(default)]
+    lenient_errors: bool,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    retry_count: u32,
+    #[serde(default)]
+    retry_backoff_ms: u64,
+}
+
+impl Snapshot {
+    pub(crate) fn new(route_table: &ServiceRouteTable, sdls: &[(String, String)]) -> Self {
+        Self {
+            sdls: sdls
+                .iter()
+                .map(|(service, sdl)| SubgraphSdl {
+                    service: service.clone(),
+                    sdl: sdl.clone(),
+                })
+                .collect(),
+            routes: route_table
+                .iter()
+                .map(|(service, route)| {
+                    (service.clone(), SnapshotRoute {
+                        addr: route.addr.clone(),
+                        tls: route.tls,
+                        query_path: route.query_path.clone(),
+                        subscribe_path: route.subscribe_path.clone(),
+                        introspection_path: route.introspection_path.clone(),
+                        schema_url: route.schema_url.clone(),
+                        websocket_path: route.websocket_path.clone(),
+                        websocket_protocol: route.websocket_protocol,
+                        enum_values: route.enum_values.clone(),
+                        lenient_errors: route.lenient_errors,
+                        timeout_ms: route.timeout_ms,
+                        retry_count: route.retry_count,
+                        retry_backoff_ms: route.retry_backoff_ms,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Read the snapshot at `path`, returning `None` if there is none.
+    pub(crate) fn read(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let snapshot = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read schema snapshot '{}'.", path.display()))?;
+        let snapshot = serde_json::from_str(&snapshot)
+            .with_context(|| format!("Invalid schema snapshot '{}'.", path.display()))?;
+        Ok(Some(snapshot))
+    }
+
+    /// Write the snapshot to `path`, replacing the previous one at once so
+    /// that a crash never leaves a partial snapshot.
+    pub(crate) fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write schema snapshot '{}'.", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to write schema snapshot '{}'.", path.display()))?;
+        Ok(())
+    }
+
+    pub(crate) fn sdls(&self) -> Vec<(String, String)> {
+        self.sdls
+            .iter()
+            .map(|subgraph| (subgraph.service.clone(), subgraph.sdl.clone()))
+            .collect()
+    }
+
+    pub(crate) fn route_table(&self) -> ServiceRouteTable {
+        let mut route_table = ServiceRouteTable::default();
+        for (service, route) in &self.routes {
+            route_table.insert(service.clone(), ServiceRoute {
+                addr: route.addr.clone(),
+                tls: route.tls,
+                query_path: route.query_path.clone(),
+                subscribe_path: route.subscribe_path.clone(),
+                introspection_path: route.introspection_path.clone(),
+                schema_url: route.schema_url.clone(),
+                websocket_path: route.websocket_path.clone(),
+                websocket_protocol: route.websocket_protocol,
+                connection_params: Default::default(),
+                sdl_file: None,
+                headers: Default::default(),
+                header_policy: Default::default(),
+                user_agent: None,
+                oauth2: None,
+                signing: None,
+                enum_values: route.enum_values.clone(),
+                lenient_errors: route.lenient_errors,
+                timeout_ms: route.timeout_ms,
+                retry_count: route.retry_count,
+                retry_backoff_ms: route.retry_backoff_ms,
+                source: RouteSource::Snapshot,
+            });
+        }
+        route_table
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// How the gateway identifies itself in the requests to the subgraphs.
+#[derive(Args, Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct SubgraphRequestConfig {
+    /// The User-Agent of the requests to the subgraphs, unless the service
+    /// sets its own.
+    #[clap(long = "subgraph-user-agent", env = "SUBGRAPH_USER_AGENT")]
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// Metadata headers sent with the requests to every subgraph, unless the
+    /// service sets a header of the same name.
+    #[clap(skip)]
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl SubgraphRequestConfig {
+    /// The User-Agent of a gateway that does not configure one,
+    /// `graphgate/<version> (<gateway name>)`.
+    pub fn default_user_agent(gateway_name: &str) -> String {
+        format!("graphgate/{} ({})", env!("CARGO_PKG_VERSION"), gateway_name)
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use graphgate_schema::JoinGraph;
+use reqwest::Url;
+
+use crate::service_route::{RouteSource, ServiceRoute, ServiceRouteTable};
+
+/// The routes of the graphs of a supergraph, by the URLs of the
+/// `@join__graph` directives.
+pub(crate) fn supergraph_route_table(graphs: &[JoinGraph]) -> Result<ServiceRouteTable> {
+    let mut route_table = ServiceRouteTable::default();
+    for graph in graphs {
+        let url = Url::parse(&graph.url).with_context(|| format!("Invalid URL of graph '{}'.", graph.name))?;
+        let host = url
+            .host_str()
+            .with_context(|| format!("The URL of graph '{}' has no host.", graph.name))?;
+        let addr = match url.port_or_known_default() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let path = match url.path() {
+            "/" => None,
+            path => Some(path.to_string()),
+        };
+        route_table.insert(graph.name.clone(), ServiceRoute {
+            addr,
+            tls: url.scheme() == "https",
+            query_path: path.clone(),
+            subscribe_path: None,
+            introspection_path: None,
+            schema_url: None,
+            websocket_path: path,
+            websocket_protocol: None,
+            connection_params: Default::default(),
+            sdl_file: None,
+            headers: Default::default(),
+            header_policy: Default::default(),
+            user_agent: None,
+            oauth2: None,
+            signing: None,
+            enum_values: Default::default(),
+            lenient_errors: false,
+            timeout_ms: None,
+            retry_count: 0,
+            retry_backoff_ms: 0,
+            source: RouteSource::Supergraph,
+        });
+    }
+    Ok(route_table)
+}
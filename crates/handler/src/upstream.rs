@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+        RwLock,
+    },
+    time::Duration,
+};
+
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::service_route::HTTP_CLIENT;
+
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct UpstreamConfig {
+    /// The number of idle connections kept open to each service, to be
+    /// reused by the next fetches.
+    #[clap(
+        long = "upstream-pool-max-idle",
+        env = "UPSTREAM_POOL_MAX_IDLE",
+        default_value_t = 32
+    )]
+    #[serde(default = "default_pool_max_idle")]
+    pub pool_max_idle: usize,
+
+    /// How long an idle connection to a service is kept open, in seconds.
+    #[clap(
+        long = "upstream-pool-idle-timeout-secs",
+        env = "UPSTREAM_POOL_IDLE_TIMEOUT_SECS",
+        default_value_t = 90
+    )]
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+
+    /// How long connecting to a service may take, in milliseconds, before
+    /// the fetch fails.
+    #[clap(
+        long = "upstream-connect-timeout-ms",
+        env = "UPSTREAM_CONNECT_TIMEOUT_MS",
+        default_value_t = 5000
+    )]
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+
+    /// The interval of the TCP keepalive probes of the connections to the
+    /// services, in seconds, so that the pooled connections dropped by the
+    /// network are detected.
+    #[clap(
+        long = "upstream-tcp-keepalive-secs",
+        env = "UPSTREAM_TCP_KEEPALIVE_SECS",
+        default_value_t = 60
+    )]
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+
+    /// Send the fetches over HTTP/2 without negotiating it, multiplexed on
+    /// the connections to the services, with or without TLS. The services
+    /// with TLS negotiate HTTP/2 otherwise.
+    #[clap(long = "upstream-http2", env = "UPSTREAM_HTTP2", default_value_t = false)]
+    #[serde(default)]
+    pub http2: bool,
+
+    /// The settings of the services that differ from the defaults, by
+    /// service name.
+    #[clap(skip)]
+    #[serde(default)]
+    pub services: HashMap<String, ServiceUpstreamConfig>,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle: default_pool_max_idle(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            connect_timeout_ms: default_connect_timeout_ms(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            http2: false,
+            services: Default::default(),
+        }
+    }
+}
+
+/// The settings of the connections to a service, the defaults apply to those
+/// not set.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ServiceUpstreamConfig {
+    #[serde(default)]
+    pub pool_max_idle: Option<usize>,
+
+    #[serde(default)]
+    pub pool_idle_timeout_secs: Option<u64>,
+
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+
+    #[serde(default)]
+    pub http2: Option<bool>,
+}
+
+impl UpstreamConfig {
+    fn client(&self, service: &str) -> reqwest::Result<reqwest::Client> {
+        let config = self.services.get(service);
+        let pool_max_idle = config
+            .and_then(|config| config.pool_max_idle)
+            .unwrap_or(self.pool_max_idle);
+        let pool_idle_timeout_secs = config
+            .and_then(|config| config.pool_idle_timeout_secs)
+            .unwrap_or(self.pool_idle_timeout_secs);
+        let connect_timeout_ms = config
+            .and_then(|config| config.connect_timeout_ms)
+            .unwrap_or(self.connect_timeout_ms);
+        let tcp_keepalive = Duration::from_secs(self.tcp_keepalive_secs);
+        let mut builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(pool_max_idle)
+            .pool_idle_timeout(Duration::from_secs(pool_idle_timeout_secs))
+            .connect_timeout(Duration::from_millis(connect_timeout_ms))
+            .tcp_keepalive(tcp_keepalive);
+        if config.and_then(|config| config.http2).unwrap_or(self.http2) {
+            // The multiplexed connections are pinged while idle, not to
+            // send the next fetches over a dead one.
+            builder = builder
+                .http2_prior_knowledge()
+                .http2_adaptive_window(true)
+                .http2_keep_alive_interval(tcp_keepalive)
+                .http2_keep_alive_while_idle(true);
+        }
+        builder.build()
+    }
+}
+
+/// The HTTP clients of the services, each with its own pool of connections.
+#[derive(Default)]
+pub(crate) struct Upstream {
+    config: RwLock<Option<UpstreamConfig>>,
+    clients: Mutex<HashMap<String, reqwest::Client>>,
+    in_flight: Mutex<HashMap<String, Arc<AtomicU64>>>,
+}
+
+impl Upstream {
+    pub(crate) fn set_config(&self, config: UpstreamConfig) {
+        *self.config.write().unwrap() = Some(config);
+        self.clients.lock().unwrap().clear();
+    }
+
+    /// The client the fetches to the service are sent with, the shared one
+    /// if no upstream config is set.
+    pub(crate) fn client(&self, service: &str) -> reqwest::Client {
+        let config = self.config.read().unwrap();
+        let config = match &*config {
+            Some(config) => config,
+            None => return HTTP_CLIENT.clone(),
+        };
+        if let Some(client) = self.clients.lock().unwrap().get(service) {
+            return client.clone();
+        }
+        // Building a client loads the TLS roots, so the fetches to the other
+        // services are not held up by it. The config cannot change meanwhile.
+        match config.client(service) {
+            Ok(client) => self
+                .clients
+                .lock()
+                .unwrap()
+                .entry(service.to_string())
+                .or_insert(client)
+                .clone(),
+            Err(err) => {
+                tracing::error!(service = %service, error = %err, "Failed to create the HTTP client of the service.");
+                HTTP_CLIENT.clone()
+            },
+        }
+    }
+
+    /// Count a fetch to the service as in flight until the guard is dropped.
+    pub(crate) fn begin(&self, service: &str) -> InFlight {
+        let count = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(service.to_string())
+            .or_default()
+            .clone();
+        count.fetch_add(1, Ordering::Relaxed);
+        InFlight(count)
+    }
+
+    /// The number of fetches in flight, by service.
+    ///
+    /// The connections of the pools are not counted, `reqwest` does not
+    /// report them: the open connections are at least the fetches in
+    /// flight over HTTP/1.1, and at most those plus `pool_max_idle`.
+    pub(crate) fn in_flight(&self) -> Vec<(String, u64)> {
+        self.in_flight
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(service, count)| (service.clone(), count.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+/// A fetch in flight, counted until it is dropped.
+pub(crate) struct InFlight(Arc<AtomicU64>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn default_pool_max_idle() -> usize {
+    32
+}
+
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
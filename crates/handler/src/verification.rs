@@ -0,0 +1,173 @@
+use anyhow::{anyhow, bail, Context as _, Result};
+use graphgate_executor::Executor;
+use graphgate_planner::{PlanBuilder, ServerError};
+use graphgate_schema::ComposedSchema;
+use http::HeaderMap;
+use parser::types::OperationType;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::time::Duration;
+use value::Variables;
+
+use crate::{fetcher::HttpFetcher, metrics::METRICS, service_route::ServiceRouteTable};
+
+/// Smoke operations executed against every newly composed schema before it
+/// is served, so that compositions that are valid but break the operations
+/// are rejected and the previous schema is kept.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct VerificationConfig {
+    /// How long the operations may take altogether, in seconds.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    #[serde(default)]
+    pub operations: Vec<VerificationOperation>,
+}
+
+impl VerificationConfig {
+    /// Check that every operation selects a single query, as the
+    /// verification sends the operations to the services and must not change
+    /// their data.
+    pub fn validate(&self) -> Result<()> {
+        for operation in &self.operations {
+            let document = parser::parse_query(&operation.query)
+                .with_context(|| format!("Invalid query of the verification operation '{}'.", operation.name))?;
+            if operation.operation_name.is_none() && document.operations.iter().nth(1).is_some() {
+                bail!(
+                    "The verification operation '{}' has several operations, its operation_name must select one.",
+                    operation.name
+                );
+            }
+            let mut selected = document.operations.iter().filter(|(name, _)| {
+                operation
+                    .operation_name
+                    .as_deref()
+                    .is_none_or(|operation_name| name.is_some_and(|name| name.as_str() == operation_name))
+            });
+            let mut found = false;
+            for (_, definition) in &mut selected {
+                if definition.node.ty != OperationType::Query {
+                    bail!(
+                        "The verification operation '{}' is a {}, only queries may be verified.",
+                        operation.name,
+                        definition.node.ty
+                    );
+                }
+                found = true;
+            }
+            if !found {
+                bail!(
+                    "The verification operation '{}' has no operation named '{}'.",
+                    operation.name,
+                    operation.operation_name.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_timeout_secs(),
+            operations: Vec::new(),
+        }
+    }
+}
+
+/// An operation that must be planned and executed without errors.
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct VerificationOperation {
+    /// Identifies the operation in the logs.
+    pub name: String,
+
+    pub query: String,
+
+    /// The operation of the query to execute, if it has several.
+    #[serde(default)]
+    pub operation_name: Option<String>,
+
+    #[serde(default)]
+    #[schemars(with = "Option<serde_json::Map<String, serde_json::Value>>")]
+    pub variables: Variables,
+}
+
+/// Execute the verification operations against a composed schema, failing
+/// if any of them fails or they time out.
+pub(crate) async fn verify_schema(
+    config: &VerificationConfig,
+    schema: &ComposedSchema,
+    route_table: &ServiceRouteTable,
+) -> Result<()> {
+    if config.operations.is_empty() {
+        return Ok(());
+    }
+
+    let operations = futures_util::future::join_all(
+        config
+            .operations
+            .iter()
+            .map(|operation| verify_operation(operation, schema, route_table)),
+    );
+    let res = match tokio::time::timeout(Duration::from_secs(config.timeout_secs), operations).await {
+        Ok(errors) => {
+            let errors = errors.into_iter().flatten().collect::<Vec<_>>();
+            match errors.is_empty() {
+                true => Ok(()),
+                false => Err(anyhow!("{}", errors.join(" "))),
+            }
+        },
+        Err(_) => Err(anyhow!(
+            "The verification operations did not complete within {} seconds.",
+            config.timeout_secs
+        )),
+    };
+    if res.is_err() {
+        METRICS.verification_failure_counter.add(1, &[]);
+    }
+    res
+}
+
+/// Plan and execute an operation, returning why it failed.
+async fn verify_operation(
+    operation: &VerificationOperation,
+    schema: &ComposedSchema,
+    route_table: &ServiceRouteTable,
+) -> Option<String> {
+    let res: Result<()> = async {
+        let document = parser::parse_query(&operation.query)?;
+        let mut plan_builder = PlanBuilder::new(schema, document).variables(operation.variables.clone());
+        if let Some(operation_name) = &operation.operation_name {
+            plan_builder = plan_builder.operation_name(operation_name);
+        }
+        let plan = plan_builder
+            .plan()
+            .map_err(|resp| anyhow!("{}", error_messages(&resp.errors)))?;
+
+        let header_map = HeaderMap::new();
+        let fetcher = HttpFetcher::new(route_table, &header_map).schema(schema);
+        let resp = Executor::new(schema).execute_query(&fetcher, &plan).await;
+        if !resp.errors.is_empty() {
+            bail!("{}", error_messages(&resp.errors));
+        }
+        Ok(())
+    }
+    .await;
+
+    let err = res.err()?;
+    tracing::warn!(operation = %operation.name, error = %err, "Verification operation failed.");
+    Some(format!("Verification operation '{}' failed: {}", operation.name, err))
+}
+
+fn error_messages(errors: &[ServerError]) -> String {
+    errors
+        .iter()
+        .map(|err| err.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
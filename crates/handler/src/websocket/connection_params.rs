@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use http::HeaderMap;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// A connection param the gateway sends in the `connection_init` message
+/// when it opens a WebSocket to a service.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionParam {
+    /// A fixed value.
+    Value(serde_json::Value),
+
+    /// The value of a forwarded header of the client request, omitted if the
+    /// request has no such header.
+    Header(String),
+}
+
+/// The `connection_init` payload for a service, the connection params
+/// forwarded from the client with the params of the service template added,
+/// replacing forwarded params of the same name.
+pub(crate) fn connection_params(
+    template: &HashMap<String, ConnectionParam>,
+    header_map: &HeaderMap,
+    forwarded: Option<&serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if template.is_empty() {
+        return forwarded.cloned();
+    }
+
+    let mut params = match forwarded {
+        Some(serde_json::Value::Object(params)) => params.clone(),
+        _ => Default::default(),
+    };
+    for (name, param) in template {
+        let value = match param {
+            ConnectionParam::Value(value) => value.clone(),
+            ConnectionParam::Header(header) => match header_map.get(header).and_then(|value| value.to_str().ok()) {
+                Some(value) => serde_json::Value::String(value.to_string()),
+                None => continue,
+            },
+        };
+        params.insert(name.clone(), value);
+    }
+    (!params.is_empty()).then_some(serde_json::Value::Object(params))
+}
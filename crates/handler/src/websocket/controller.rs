@@ -11,23 +11,26 @@ use futures_util::{
     StreamExt,
 };
 use graphgate_planner::{Request, Response};
-use http::{HeaderMap, Request as HttpRequest};
+use http::{HeaderMap, HeaderValue};
+use opentelemetry::Context;
 use tokio::{
     net::TcpStream,
     sync::{mpsc, oneshot},
     time::Duration,
 };
 use tokio_tungstenite::{
-    tungstenite::{protocol::CloseFrame, Message, Result as WsResult},
+    tungstenite::{client::IntoClientRequest, protocol::CloseFrame, Message, Result as WsResult},
     MaybeTlsStream,
     WebSocketStream,
 };
+use value::ConstValue;
 
 use super::{
+    connection_params::connection_params,
     grouped_stream::{GroupedStream, StreamEvent},
     protocol::{ClientMessage, Protocols, ServerMessage},
 };
-use crate::ServiceRouteTable;
+use crate::{fetcher::inject_trace_context, ServiceRouteTable};
 
 const CONNECT_TIMEOUT_SECONDS: u64 = 5;
 
@@ -38,6 +41,9 @@ struct SubscribeCommand {
     payload: Request,
     tx: mpsc::UnboundedSender<Response>,
     reply: oneshot::Sender<Result<()>>,
+    /// The trace context of the subscription, propagated by the handshake
+    /// of a new connection.
+    context: Context,
 }
 
 struct StopCommand {
@@ -51,6 +57,7 @@ enum Command {
 
 #[derive(Clone)]
 pub struct WebSocketController {
+    route_table: Arc<ServiceRouteTable>,
     tx_command: mpsc::UnboundedSender<Command>,
 }
 
@@ -62,7 +69,7 @@ impl WebSocketController {
     ) -> Self {
         let (tx_command, rx_command) = mpsc::unbounded_channel();
         let ctx = WebSocketContext {
-            route_table,
+            route_table: route_table.clone(),
             header_map: header_map.clone(),
             init_payload,
             upstream: GroupedStream::default(),
@@ -72,7 +79,15 @@ impl WebSocketController {
         };
 
         tokio::spawn(ctx.main());
-        Self { tx_command }
+        Self {
+            route_table,
+            tx_command,
+        }
+    }
+
+    /// The routes of the services subscribed to.
+    pub(crate) fn route_table(&self) -> &ServiceRouteTable {
+        &self.route_table
     }
 
     pub async fn subscribe(
@@ -91,6 +106,7 @@ impl WebSocketController {
                 payload: request,
                 tx,
                 reply: tx_reply,
+                context: Context::current(),
             }))
             .is_err()
         {
@@ -153,41 +169,44 @@ impl WebSocketContext {
     async fn ensure_upstream(
         &mut self,
         service: &str,
+        cx: &Context,
     ) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Protocols)> {
         const PROTOCOLS: &str = "graphql-ws, graphql-transport-ws";
         let route = self
             .route_table
             .get(service)
             .ok_or_else(|| anyhow::anyhow!("Service '{}' is not defined in the routing table.", service))?;
-        let scheme = match route.tls {
-            true => "wss",
-            false => "ws",
-        };
-
-        let url = match &route.websocket_path {
-            Some(path) => format!("{}://{}{}", scheme, route.addr, path),
-            None => format!("{}://{}", scheme, route.addr),
-        };
+        let url = self.route_table.websocket_url(service).unwrap();
 
         tracing::debug!(url = %url, service = service, "Connect to upstream websocket");
-        let mut http_request = HttpRequest::builder()
-            .uri(&url)
-            .header("Sec-WebSocket-Protocol", PROTOCOLS)
-            .body(())
-            .unwrap();
-        http_request.headers_mut().extend(self.header_map.clone());
+        let mut http_request = url.as_str().into_client_request()?;
+        let protocols = route
+            .websocket_protocol
+            .map(|protocol| protocol.sec_websocket_protocol())
+            .unwrap_or(PROTOCOLS);
+        http_request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_str(protocols)?);
+        http_request
+            .headers_mut()
+            .extend(route.forwarded_headers(&self.header_map).into_owned());
+        http_request
+            .headers_mut()
+            .extend(route.credential_headers(&self.route_table.state().token_cache).await?);
+        inject_trace_context(cx, http_request.headers_mut());
         let (mut stream, http_response) = tokio_tungstenite::connect_async(http_request).await?;
         let protocol = http_response
             .headers()
             .get("Sec-WebSocket-Protocol")
             .and_then(|value| value.to_str().ok())
             .and_then(|value| Protocols::from_str(value).ok())
+            .or(route.websocket_protocol)
             .ok_or_else(|| anyhow::anyhow!("Unknown protocol: {}", url))?;
 
         stream
             .send(Message::Text(
                 serde_json::to_string(&ClientMessage::ConnectionInit {
-                    payload: self.init_payload.clone(),
+                    payload: connection_params(&route.connection_params, &self.header_map, self.init_payload.as_ref()),
                 })
                 .unwrap(),
             ))
@@ -225,7 +244,7 @@ impl WebSocketContext {
 
     async fn handle_command_subscribe(&mut self, command: SubscribeCommand) {
         if !self.upstream.contains_key(&command.service) {
-            let (stream, protocol) = match self.ensure_upstream(&command.service).await {
+            let (stream, protocol) = match self.ensure_upstream(&command.service, &command.context).await {
                 Ok(stream) => stream,
                 Err(err) => {
                     command.reply.send(Err(err)).ok();
@@ -284,12 +303,24 @@ impl WebSocketContext {
     }
 
     async fn handle_command_stop(&mut self, command: StopCommand) {
+        if let Some(subscribe_info) = self.subscribes.get(&command.id) {
+            for service in &subscribe_info.services {
+                if let Some(info) = self.upstream_info.get_mut(service) {
+                    info.sink
+                        .send(Message::text(
+                            serde_json::to_string(&info.protocol.stop_message(&command.id)).unwrap(),
+                        ))
+                        .await
+                        .ok();
+                }
+            }
+        }
         self.finish_subscribe(&command.id);
     }
 
     async fn handle_event(&mut self, event: StreamEvent<String, WsResult<Message>>) -> bool {
         match event {
-            StreamEvent::Data(_, Ok(Message::Text(text))) => {
+            StreamEvent::Data(service, Ok(Message::Text(text))) => {
                 let message = match serde_json::from_str::<ServerMessage>(&text) {
                     Ok(message) => message,
                     Err(_) => return false,
@@ -302,9 +333,32 @@ impl WebSocketContext {
                             }
                         }
                     },
+                    ServerMessage::Error { id, payload } => {
+                        if let Some(info) = self.subscribes.get_mut(id) {
+                            info.tx
+                                .send(Response {
+                                    data: ConstValue::Null,
+                                    errors: payload,
+                                    extensions: Default::default(),
+                                    headers: Default::default(),
+                                })
+                                .ok();
+                        }
+                        self.finish_subscribe(id);
+                    },
                     ServerMessage::Complete { id } => {
                         self.finish_subscribe(id);
                     },
+                    ServerMessage::Ping { payload } => {
+                        if let Some(info) = self.upstream_info.get_mut(&service) {
+                            info.sink
+                                .send(Message::text(
+                                    serde_json::to_string(&ClientMessage::Pong { payload }).unwrap(),
+                                ))
+                                .await
+                                .ok();
+                        }
+                    },
                     _ => {},
                 }
                 true
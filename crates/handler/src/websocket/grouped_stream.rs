@@ -30,6 +30,7 @@ impl<K: Eq + Hash + Clone, S> GroupedStream<K, S> {
     }
 
     #[inline]
+    #[allow(clippy::multiple_bound_locations)]
     pub fn remove<Q: ?Sized>(&mut self, key: &Q)
     where
         K: Borrow<Q>,
@@ -39,6 +40,7 @@ impl<K: Eq + Hash + Clone, S> GroupedStream<K, S> {
     }
 
     #[inline]
+    #[allow(clippy::multiple_bound_locations)]
     pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
     where
         K: Borrow<Q>,
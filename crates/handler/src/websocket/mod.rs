@@ -1,8 +1,10 @@
+mod connection_params;
 mod controller;
 mod grouped_stream;
 mod protocol;
 mod server;
 
+pub use connection_params::ConnectionParam;
 pub use controller::WebSocketController;
 pub use protocol::Protocols;
 pub use server::server;
@@ -1,12 +1,16 @@
 use anyhow::Error;
-use graphgate_planner::{Request, Response};
+use graphgate_planner::{IncrementalResponse, Request, Response, ServerError};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use value::ConstValue;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
 pub enum Protocols {
     /// [subscriptions-transport-ws protocol](https://github.com/apollographql/subscriptions-transport-ws/blob/master/PROTOCOL.md).
+    #[serde(rename = "graphql-ws")]
     SubscriptionsTransportWS,
     /// [graphql-ws protocol](https://github.com/enisdenjo/graphql-ws/blob/master/PROTOCOL.md).
+    #[serde(rename = "graphql-transport-ws")]
     GraphQLWS,
 }
 
@@ -25,7 +29,7 @@ impl std::str::FromStr for Protocols {
 }
 
 impl Protocols {
-    pub fn sec_websocket_protocol(&self) -> &str {
+    pub fn sec_websocket_protocol(&self) -> &'static str {
         match self {
             Protocols::SubscriptionsTransportWS => "graphql-ws",
             Protocols::GraphQLWS => "graphql-transport-ws",
@@ -47,18 +51,70 @@ impl Protocols {
             Protocols::GraphQLWS => ServerMessage::Next { id, payload },
         }
     }
+
+    #[inline]
+    pub fn stop_message<'a>(&self, id: &'a str) -> ClientMessage<'a> {
+        match self {
+            Protocols::SubscriptionsTransportWS => ClientMessage::Stop { id },
+            Protocols::GraphQLWS => ClientMessage::Complete { id },
+        }
+    }
+
+    /// The messages reporting an operation that fails before its execution.
+    ///
+    /// The graphql-ws protocol has a dedicated `error` message which also
+    /// ends the operation, the subscriptions-transport-ws protocol sends the
+    /// errors as `data` followed by `complete`.
+    pub fn error_messages<'a>(&self, id: &'a str, errors: Vec<ServerError>) -> Vec<ServerMessage<'a>> {
+        match self {
+            Protocols::SubscriptionsTransportWS => vec![
+                ServerMessage::Data {
+                    id,
+                    payload: Response {
+                        data: ConstValue::Null,
+                        errors,
+                        extensions: Default::default(),
+                        headers: Default::default(),
+                    },
+                },
+                ServerMessage::Complete { id },
+            ],
+            Protocols::GraphQLWS => vec![ServerMessage::Error { id, payload: errors }],
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[allow(dead_code)]
 pub enum ClientMessage<'a> {
-    ConnectionInit { payload: Option<serde_json::Value> },
-    Start { id: &'a str, payload: Request },
-    Subscribe { id: &'a str, payload: Request },
-    Stop { id: &'a str },
-    Complete { id: &'a str },
+    ConnectionInit {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<serde_json::Value>,
+    },
+    Start {
+        id: &'a str,
+        payload: Request,
+    },
+    Subscribe {
+        id: &'a str,
+        payload: Request,
+    },
+    Stop {
+        id: &'a str,
+    },
+    Complete {
+        id: &'a str,
+    },
     ConnectionTerminate,
+    Ping {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<serde_json::Value>,
+    },
+    Pong {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<serde_json::Value>,
+    },
 }
 
 #[derive(Deserialize, Serialize)]
@@ -70,9 +126,42 @@ pub struct ConnectionError<'a> {
 #[serde(tag = "type", rename_all = "snake_case")]
 #[allow(dead_code)]
 pub enum ServerMessage<'a> {
-    ConnectionError { payload: ConnectionError<'a> },
+    ConnectionError {
+        payload: ConnectionError<'a>,
+    },
     ConnectionAck,
-    Data { id: &'a str, payload: Response },
-    Next { id: &'a str, payload: Response },
-    Complete { id: &'a str },
+    Data {
+        id: &'a str,
+        payload: Response,
+    },
+    Next {
+        id: &'a str,
+        payload: Response,
+    },
+    Error {
+        id: &'a str,
+        payload: Vec<ServerError>,
+    },
+    Complete {
+        id: &'a str,
+    },
+    Ping {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<serde_json::Value>,
+    },
+    Pong {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<serde_json::Value>,
+    },
+    /// The keep-alive message of the subscriptions-transport-ws protocol.
+    Ka,
+}
+
+/// A `next` message of the graphql-ws protocol carrying a payload of an
+/// incrementally delivered response.
+#[derive(Serialize)]
+#[serde(tag = "type", rename = "next")]
+pub struct IncrementalNextMessage<'a> {
+    pub id: &'a str,
+    pub payload: IncrementalResponse,
 }
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use futures_util::{sink::Sink, stream::Stream, SinkExt, StreamExt};
-use graphgate_planner::{PlanBuilder, Response, ServerError};
+use graphgate_executor::Executor;
+use graphgate_planner::{IncrementalResponse, PlanBuilder, Response, RootNode, ServerError};
 use graphgate_schema::ComposedSchema;
 use value::ConstValue;
 use warp::{http::HeaderMap, ws::Message, Error};
@@ -9,17 +10,51 @@ use warp::{http::HeaderMap, ws::Message, Error};
 use super::{
     controller::WebSocketController,
     grouped_stream::{GroupedStream, StreamEvent},
-    protocol::{ClientMessage, ConnectionError, Protocols, ServerMessage},
+    protocol::{ClientMessage, ConnectionError, IncrementalNextMessage, Protocols, ServerMessage},
 };
-use crate::{executor::Executor, ServiceRouteTable};
+use crate::{
+    audit::{audit_unavailable, is_mutation},
+    constants::KEY_OPERATION,
+    context_injection::RequestContext,
+    metrics::METRICS,
+    operation_label::OperationLabeler,
+    panic::isolate_stream,
+    redaction::Redactor,
+    safelist::operation_not_safelisted,
+    ServiceRouteTable,
+    SharedRouteTable,
+};
+
+/// An item of the stream of an operation.
+enum Payload {
+    Response(Response),
+    Incremental(IncrementalResponse),
+}
+
+impl From<Response> for Payload {
+    fn from(resp: Response) -> Self {
+        Payload::Response(resp)
+    }
+}
 
+/// Serve the operations of a WebSocket connection with the schema and the
+/// routes current when it opened, checked by the rules of
+/// `shared_route_table` as the operations of the other transports are.
+#[allow(clippy::too_many_arguments)]
 pub async fn server(
+    shared_route_table: SharedRouteTable,
     schema: Arc<ComposedSchema>,
     route_table: Arc<ServiceRouteTable>,
     stream: impl Stream<Item = Result<Message, Error>> + Sink<Message>,
     protocol: Protocols,
     header_map: HeaderMap,
+    forward_connection_params: Arc<Vec<String>>,
+    operation_labeler: Arc<OperationLabeler>,
+    context: Arc<RequestContext>,
 ) {
+    let redaction_rules = shared_route_table.redaction_rules();
+    let audit_log = shared_route_table.audit_log();
+    let latency_budget = shared_route_table.defer_latency_budget();
     let (mut sink, mut stream) = stream.split();
     let mut streams = GroupedStream::default();
     let mut controller = None;
@@ -32,11 +67,17 @@ pub async fn server(
                     let text = message.into_bytes();
                     let client_msg = match serde_json::from_slice::<ClientMessage>(&text) {
                         Ok(client_msg) => client_msg,
-                        Err(_) => return,
+                        Err(_) => {
+                            if protocol == Protocols::GraphQLWS {
+                                sink.send(Message::close_with(4400u16, "Invalid message received.")).await.ok();
+                            }
+                            return;
+                        }
                     };
 
                     match client_msg {
                         ClientMessage::ConnectionInit { payload } if controller.is_none() => {
+                            let payload = do_forward_connection_params(&forward_connection_params, payload);
                             controller = Some(WebSocketController::new(route_table.clone(), &header_map, payload));
                             sink.send(Message::text(serde_json::to_string(&ServerMessage::ConnectionAck).unwrap())).await.ok();
                         }
@@ -60,51 +101,127 @@ pub async fn server(
                         }
                         ClientMessage::Start { id, payload } | ClientMessage::Subscribe { id, payload } => {
                             let controller = controller.get_or_insert_with(|| WebSocketController::new(route_table.clone(), &header_map, None)).clone();
+                            if let Some(safelist) = shared_route_table.safelist() {
+                                if !safelist.contains(&payload.query) {
+                                    let operation = operation_labeler.label(payload.operation.as_deref());
+                                    METRICS
+                                        .safelist_rejected_counter
+                                        .add(1, &[KEY_OPERATION.string(operation.clone())]);
+                                    tracing::debug!(operation = %operation, "Operation rejected by the safelist.");
+                                    for message in protocol.error_messages(id, vec![operation_not_safelisted()]) {
+                                        sink.send(Message::text(serde_json::to_string(&message).unwrap())).await.ok();
+                                    }
+                                    continue;
+                                }
+                            }
                             let document = match parser::parse_query(&payload.query) {
                                 Ok(document) => document,
                                 Err(err) => {
-                                    let resp = Response {
-                                        data: ConstValue::Null,
-                                        errors: vec![ServerError::new(err.to_string())],
-                                        extensions: Default::default(),
-                                        headers: Default::default()
-                                    };
-                                    let data = ServerMessage::Data { id, payload: resp };
-                                    sink.send(Message::text(serde_json::to_string(&data).unwrap())).await.ok();
-
-                                    let complete = ServerMessage::Complete { id };
-                                    sink.send(Message::text(serde_json::to_string(&complete).unwrap())).await.ok();
+                                    for message in protocol.error_messages(id, vec![ServerError::new(err.to_string())]) {
+                                        sink.send(Message::text(serde_json::to_string(&message).unwrap())).await.ok();
+                                    }
                                     continue;
                                 }
                             };
 
                             let id = Arc::new(id.to_string());
+                            let shared_route_table = shared_route_table.clone();
                             let schema = schema.clone();
+                            let redaction_rules = redaction_rules.clone();
+                            let audit_log = audit_log.clone().filter(|_| is_mutation(&document, payload.operation.as_deref()));
+                            let context = context.clone();
                             let stream = {
                                 let id = id.clone();
                                 async_stream::stream! {
-                                    let builder = PlanBuilder::new(&schema, document).variables(payload.variables);
+                                    let mut document = document;
+                                    let mut request = payload;
+                                    let (mut extensions, mut errors) = match shared_route_table.check_operation(&schema, &mut document, &mut request, &context) {
+                                        Ok(checked) => checked,
+                                        Err(errors) => {
+                                            yield Payload::from(Response {
+                                                data: ConstValue::Null,
+                                                errors,
+                                                extensions: Default::default(),
+                                                headers: Default::default(),
+                                            });
+                                            return;
+                                        }
+                                    };
+                                    let redactor = Redactor::new(&redaction_rules, &context, &document, request.operation.as_deref());
+                                    let audited_variables = audit_log.as_ref().map(|_| request.variables.clone());
+                                    let mut builder = PlanBuilder::new(&schema, document).variables(request.variables);
+                                    if let Some(operation) = &request.operation {
+                                        builder = builder.operation_name(operation);
+                                    }
                                     let node = match builder.plan() {
                                         Ok(node) => node,
                                         Err(resp) => {
-                                            yield resp;
+                                            yield Payload::from(resp);
                                             return;
                                         }
                                     };
-                                    let executor = Executor::new(&schema);
-                                    let mut stream = executor.execute_stream(controller.clone(), &id, &node).await;
-                                    while let Some(item) = stream.next().await {
-                                        yield item;
+                                    let mut audit_entry = None;
+                                    if let (Some(audit_log), Some(variables)) = (&audit_log, &audited_variables) {
+                                        match audit_log.begin(request.operation.as_deref(), &context, variables, &node).await {
+                                            Ok(entry) => audit_entry = Some(entry),
+                                            Err(err) => {
+                                                tracing::error!(error = %err, "Failed to write the audit record of a mutation.");
+                                                yield Payload::from(audit_unavailable());
+                                                return;
+                                            }
+                                        }
+                                    }
+                                    let visible_schema = shared_route_table.visible_schema(&schema);
+                                    let mut executor = Executor::new(&schema);
+                                    if let Some(visible_schema) = &visible_schema {
+                                        executor = executor.introspection_schema(visible_schema);
+                                    }
+                                    // The deferred payloads are sent as `next`
+                                    // messages with the graphql-ws protocol,
+                                    // merged into a single one otherwise, or
+                                    // if the operation is redacted, audited,
+                                    // has unauthorized fields or extensions.
+                                    if matches!(node, RootNode::Defer(_)) && protocol == Protocols::GraphQLWS && redactor.is_none() && audit_entry.is_none() && errors.is_empty() && extensions.is_empty() {
+                                        let mut stream = executor.execute_stream_incremental(controller.clone(), &node, latency_budget);
+                                        while let Some(item) = stream.next().await {
+                                            yield Payload::Incremental(item);
+                                        }
+                                    } else {
+                                        let mut stream = executor.execute_stream(controller.clone(), &id, &node).await;
+                                        while let Some(mut item) = stream.next().await {
+                                            if let (Some(audit_log), Some(entry)) = (&audit_log, audit_entry.take()) {
+                                                audit_log.complete(entry, &item).await;
+                                            }
+                                            if let Some(redactor) = &redactor {
+                                                redactor.redact(&schema, &mut item.data);
+                                            }
+                                            item.errors.splice(0..0, std::mem::take(&mut errors));
+                                            item.merge_extensions(std::mem::take(&mut extensions));
+                                            yield Payload::from(item);
+                                        }
                                     }
                                 }
                             };
-                            streams.insert(id, Box::pin(stream));
+                            streams.insert(id, Box::pin(isolate_stream(stream, |resp, _| Payload::from(resp))));
                         }
                         ClientMessage::Stop { id } => {
                             let controller = controller.get_or_insert_with(|| WebSocketController::new(route_table.clone(), &header_map, None)).clone();
                             controller.stop(id).await;
                         }
-                        _ => {}
+                        ClientMessage::Complete { id } => {
+                            // The client of the graphql-ws protocol expects no
+                            // more messages of an operation it completed.
+                            streams.remove(&id.to_string());
+                            if let Some(controller) = &controller {
+                                controller.stop(id).await;
+                            }
+                        }
+                        ClientMessage::Ping { payload } => {
+                            let pong = ServerMessage::Pong { payload };
+                            sink.send(Message::text(serde_json::to_string(&pong).unwrap())).await.ok();
+                        }
+                        ClientMessage::ConnectionTerminate => return,
+                        ClientMessage::Pong { .. } => {}
                     }
                 }
                 Some(Ok(message)) if message.is_close() => return,
@@ -113,9 +230,12 @@ pub async fn server(
             },
             item = streams.next() => if let Some(event) = item {
                 match event {
-                    StreamEvent::Data(id, resp) => {
-                        let data = protocol.next_message(&id, resp);
-                        if sink.send(Message::text(serde_json::to_string(&data).unwrap())).await.is_err() {
+                    StreamEvent::Data(id, payload) => {
+                        let data = match payload {
+                            Payload::Response(resp) => serde_json::to_string(&protocol.next_message(&id, resp)),
+                            Payload::Incremental(payload) => serde_json::to_string(&IncrementalNextMessage { id: &id, payload }),
+                        };
+                        if sink.send(Message::text(data.unwrap())).await.is_err() {
                             return;
                         }
                     }
@@ -130,3 +250,21 @@ pub async fn server(
         }
     }
 }
+
+/// Keep the entries of the `connection_init` payload in
+/// `forward_connection_params`, or all of them if it is empty.
+fn do_forward_connection_params(
+    forward_connection_params: &[String],
+    payload: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    match payload {
+        Some(serde_json::Value::Object(params)) if forward_connection_params.is_empty() => {
+            (!params.is_empty()).then_some(serde_json::Value::Object(params))
+        },
+        Some(serde_json::Value::Object(mut params)) => {
+            params.retain(|name, _| forward_connection_params.contains(name));
+            (!params.is_empty()).then_some(serde_json::Value::Object(params))
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::auth::{Auth, AuthConfig};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::json;
+use value::value;
+use warp::http::StatusCode;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Query { topReviews: [Review!]! }
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+const SECRET: &[u8] = b"secret";
+
+fn auth() -> Auth {
+    Auth::with_keys(
+        AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            ..Default::default()
+        },
+        [("key".to_string(), DecodingKey::from_secret(SECRET))].into(),
+    )
+}
+
+fn authorization() -> String {
+    let header = Header {
+        kid: Some("key".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    let claims = json!({ "sub": "alice", "exp": 4102444800u64 });
+    let token = jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap();
+    format!("Bearer {}", token)
+}
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await
+}
+
+async fn reviews() -> Subgraph {
+    SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .field("topReviews", |_| Ok(value!([{ "body": "Great!" }])))
+        .spawn()
+        .await
+}
+
+#[tokio::test]
+async fn plan_with_allowed_services() {
+    let accounts = accounts().await;
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .auth(auth())
+        .allowed_services_extension(true)
+        .start()
+        .await;
+
+    let authorization = authorization();
+    let resp = gateway
+        .post(
+            json!({
+                "query": "{ me { username } }",
+                "extensions": { "allowedServices": ["accounts"] },
+            }),
+            &[("authorization", &authorization)],
+        )
+        .await;
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(resp, json!({ "data": { "me": { "username": "alice" } } }));
+
+    // The fields of the other services, at the root or of entities, fail the
+    // operation before any service is fetched.
+    let resp = gateway
+        .post(
+            json!({
+                "query": "{ me { username reviews { body } } topReviews { body } }",
+                "extensions": { "allowedServices": ["accounts"] },
+            }),
+            &[("authorization", &authorization)],
+        )
+        .await;
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [
+                { "message": "The field \"User.reviews\" is resolved by the service \"reviews\", which is not allowed." },
+                { "message": "The field \"Query.topReviews\" is resolved by the service \"reviews\", which is not allowed." },
+            ],
+        })
+    );
+    assert!(reviews.requests().is_empty());
+}
+
+#[tokio::test]
+async fn ignore_allowed_services_unless_enabled() {
+    let accounts = accounts().await;
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).auth(auth()).start().await;
+
+    let authorization = authorization();
+    let resp = gateway
+        .post(
+            json!({
+                "query": "{ topReviews { body } }",
+                "extensions": { "allowedServices": ["accounts"] },
+            }),
+            &[("authorization", &authorization)],
+        )
+        .await;
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(resp, json!({ "data": { "topReviews": [{ "body": "Great!" }] } }));
+}
+
+#[tokio::test]
+async fn ignore_allowed_services_unless_authenticated() {
+    let accounts = accounts().await;
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .allowed_services_extension(true)
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(
+            json!({
+                "query": "{ topReviews { body } }",
+                "extensions": { "allowedServices": ["accounts"] },
+            }),
+            &[],
+        )
+        .await;
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(resp, json!({ "data": { "topReviews": [{ "body": "Great!" }] } }));
+}
+
+#[tokio::test]
+async fn reject_invalid_allowed_services() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .allowed_services_extension(true)
+        .start()
+        .await;
+
+    let params = serde_urlencoded::to_string([
+        ("query", "{ me { username } }"),
+        ("extensions", r#"{"allowedServices":"accounts"}"#),
+    ])
+    .unwrap();
+    let resp = gateway.get(&format!("/?{}", params)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert!(resp["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid extensions: "));
+    assert!(accounts.requests().is_empty());
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use std::{
+    net::TcpListener,
+    sync::{Arc, Mutex},
+};
+
+use common::GatewayBuilder;
+use graphgate_handler::{AuditConfig, AUDIT_LOG_UNAVAILABLE};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::{json, Value};
+use value::ConstValue;
+use warp::Filter;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: String }
+    type Mutation { signUp(input: SignUpInput!, referrer: String): Boolean }
+    input SignUpInput { username: String! password: String! }
+"#;
+
+const SIGN_UP: &str = r#"
+    mutation SignUp($input: SignUpInput!, $referrer: String) { signUp(input: $input, referrer: $referrer) }
+"#;
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .field("signUp", |ctx| match ctx.arguments.get("referrer") {
+            Some(ConstValue::String(referrer)) if referrer == "spam" => Err("Referrer rejected.".to_string()),
+            _ => Ok(ConstValue::Boolean(true)),
+        })
+        .spawn()
+        .await
+}
+
+fn records(path: &std::path::Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn audit_mutations_to_file() {
+    let accounts = accounts().await;
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .audit_config(AuditConfig {
+            file: Some(file.path().to_path_buf()),
+            redact_variables: vec!["password".to_string()],
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+    assert!(records(file.path()).is_empty());
+
+    for referrer in ["friend", "spam"] {
+        gateway
+            .query(json!({
+                "query": SIGN_UP,
+                "operationName": "SignUp",
+                "variables": {
+                    "input": { "username": "bob", "password": "hunter2" },
+                    "referrer": referrer,
+                },
+            }))
+            .await;
+    }
+
+    let records = records(file.path());
+    assert_eq!(records.len(), 4);
+    let (started, completed) = (&records[0], &records[1]);
+    assert_eq!(started["phase"], "started");
+    assert_eq!(started["operationName"], "SignUp");
+    assert_eq!(started["actor"], Value::Null);
+    assert_eq!(started["services"], json!(["accounts"]));
+    assert_eq!(
+        started["variables"],
+        json!({ "input": { "username": "bob", "password": "[REDACTED]" }, "referrer": "friend" })
+    );
+    assert_eq!(completed["id"], started["id"]);
+    assert_eq!(completed["phase"], "completed");
+    assert_eq!(completed["status"], "ok");
+    assert_eq!(completed["errors"], json!([]));
+
+    assert_ne!(records[2]["id"], started["id"]);
+    assert_eq!(records[3]["status"], "error");
+    assert_eq!(records[3]["errors"], json!(["Referrer rejected."]));
+}
+
+#[tokio::test]
+async fn audit_mutations_to_url() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = warp::post().and(warp::path("audit")).and(warp::body::json()).map({
+        let received = received.clone();
+        move |record: Value| {
+            received.lock().unwrap().push(record);
+            warp::reply()
+        }
+    });
+    let (addr, server) = warp::serve(sink).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .audit_config(AuditConfig {
+            url: Some(format!("http://{}/audit", addr)),
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({
+            "query": SIGN_UP,
+            "variables": { "input": { "username": "bob", "password": "hunter2" } },
+        }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "signUp": true } }));
+
+    let received = received.lock().unwrap();
+    let phases = received.iter().map(|record| &record["phase"]).collect::<Vec<_>>();
+    assert_eq!(phases, vec!["started", "completed"]);
+    assert_eq!(received[0]["operationName"], Value::Null);
+    assert_eq!(
+        received[0]["variables"]["input"],
+        json!({ "username": "bob", "password": "hunter2" })
+    );
+}
+
+#[tokio::test]
+async fn unavailable_audit_log() {
+    let accounts = accounts().await;
+    // A port nothing listens on.
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .audit_config(AuditConfig {
+            url: Some(format!("http://{}/audit", addr)),
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({
+            "query": SIGN_UP,
+            "variables": { "input": { "username": "bob", "password": "hunter2" } },
+        }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "The mutation was not executed, as it could not be audited.",
+                "extensions": { "code": AUDIT_LOG_UNAVAILABLE },
+            }]
+        })
+    );
+    assert!(accounts
+        .requests()
+        .iter()
+        .all(|request| !request.query.contains("signUp")));
+}
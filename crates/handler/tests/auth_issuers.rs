@@ -0,0 +1,162 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use graphgate_handler::{
+    auth::{with_auth, Auth, AuthConfig, AuthError, IssuerConfig},
+    GatewaySettings,
+    SharedConfig,
+};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde_json::{json, Value};
+use warp::Filter;
+
+/// The shared secrets of the keys, encoded in base64 in the key sets.
+const KEYS: &[(&str, &str, &str)] = &[
+    ("a1", "secret-a1", "c2VjcmV0LWEx"),
+    ("a2", "secret-a2", "c2VjcmV0LWEy"),
+    ("b1", "secret-b1", "c2VjcmV0LWIx"),
+];
+
+fn key(kid: &str) -> (&'static str, &'static str) {
+    let (_, secret, encoded) = KEYS.iter().find(|(id, ..)| *id == kid).unwrap();
+    (secret, encoded)
+}
+
+/// The `kid`s of the keys of each key set, by its name.
+type KeySets = Arc<Mutex<Vec<(&'static str, Vec<&'static str>)>>>;
+
+/// Serve the key sets at `/{name}.json`.
+fn serve_key_sets(key_sets: KeySets) -> String {
+    let routes = warp::path!(String).map(move |file: String| {
+        let key_sets = key_sets.lock().unwrap();
+        let keys = key_sets
+            .iter()
+            .find(|(name, _)| format!("{}.json", name) == file)
+            .map(|(_, kids)| kids.clone())
+            .unwrap_or_default();
+        warp::reply::json(&json!({
+            "keys": keys
+                .iter()
+                .map(|kid| json!({ "kty": "oct", "kid": kid, "alg": "HS256", "k": key(kid).1 }))
+                .collect::<Vec<_>>(),
+        }))
+    });
+    let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    format!("http://{}", addr)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn token(kid: &str, claims: Value) -> String {
+    let header = Header {
+        kid: Some(kid.to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(key(kid).0.as_bytes())).unwrap()
+}
+
+async fn verify(shared_config: &SharedConfig, token: &str) -> Result<Option<Value>, String> {
+    warp::test::request()
+        .header("authorization", format!("Bearer {}", token))
+        .filter(&with_auth(shared_config.clone()))
+        .await
+        .map_err(|err| err.find::<AuthError>().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn verify_tokens_of_several_issuers() {
+    let key_sets = Arc::new(Mutex::new(vec![("a", vec!["a1"]), ("b", vec!["b1"])]));
+    let url = serve_key_sets(key_sets.clone());
+    let auth = Auth::try_new(AuthConfig {
+        enabled: true,
+        header_name: "authorization".to_string(),
+        header_prefix: "Bearer".to_string(),
+        issuers: vec![
+            IssuerConfig {
+                issuer: "https://a.example.com/".to_string(),
+                jwks: format!("{}/a.json", url),
+                audiences: vec!["graphgate".to_string()],
+            },
+            IssuerConfig {
+                issuer: "https://b.example.com/".to_string(),
+                jwks: format!("{}/b.json", url),
+                audiences: Vec::new(),
+            },
+        ],
+        clock_skew_secs: 60,
+        jwks_refresh_secs: 1,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let shared_config = SharedConfig::new(GatewaySettings {
+        auth: Arc::new(auth),
+        ..Default::default()
+    });
+
+    let exp = now() + 300;
+    let claims = verify(
+        &shared_config,
+        &token(
+            "a1",
+            json!({ "sub": "alice", "iss": "https://a.example.com/", "aud": "graphgate", "exp": exp }),
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(claims.unwrap()["sub"], "alice");
+
+    // The expiration is checked with the clock skew.
+    let claims = verify(
+        &shared_config,
+        &token(
+            "b1",
+            json!({ "sub": "bob", "iss": "https://b.example.com/", "exp": now() - 30 }),
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(claims.unwrap()["sub"], "bob");
+
+    for (token, expected) in [
+        (
+            token(
+                "b1",
+                json!({ "iss": "https://a.example.com/", "aud": "graphgate", "exp": exp }),
+            ),
+            "invalid kid in authorization header",
+        ),
+        (
+            token(
+                "a1",
+                json!({ "iss": "https://a.example.com/", "aud": "other", "exp": exp }),
+            ),
+            "jwt decoding error: InvalidAudience",
+        ),
+        (
+            token("a1", json!({ "iss": "https://c.example.com/", "exp": exp })),
+            "unknown issuer of the token",
+        ),
+        (
+            token("b1", json!({ "iss": "https://b.example.com/", "exp": now() - 120 })),
+            "jwt decoding error: ExpiredSignature",
+        ),
+    ] {
+        assert_eq!(verify(&shared_config, &token).await.unwrap_err(), expected);
+    }
+
+    // The rotated keys are fetched again.
+    key_sets.lock().unwrap()[0].1 = vec!["a2"];
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let rotated = token(
+        "a2",
+        json!({ "sub": "carol", "iss": "https://a.example.com/", "aud": "graphgate", "exp": exp }),
+    );
+    let claims = verify(&shared_config, &rotated).await.unwrap();
+    assert_eq!(claims.unwrap()["sub"], "carol");
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    auth::{Auth, AuthConfig},
+    UNAUTHORIZED_FIELD_OR_TYPE,
+};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query {
+        me: User @authenticated
+        products: [Product!]!
+        report: String @requiresScopes(scopes: [["admin"], ["audit", "finance"]])
+    }
+    type User { id: ID! name: String! }
+    type Product { id: ID! name: String! cost: Int @requiresScopes(scopes: [["finance"]]) supplier: Supplier }
+    type Supplier @authenticated { name: String! }
+"#;
+
+const SECRET: &[u8] = b"secret";
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "name": "alice" })))
+        .field("products", |_| {
+            Ok(value!([{ "id": "1", "name": "chair", "cost": 20, "supplier": { "name": "acme" } }]))
+        })
+        .field("report", |_| Ok(value!("ok")))
+        .spawn()
+        .await
+}
+
+fn auth() -> Auth {
+    Auth::with_keys(
+        AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            ..Default::default()
+        },
+        [("key".to_string(), DecodingKey::from_secret(SECRET))].into(),
+    )
+}
+
+fn authorization(scope: &str) -> String {
+    let header = Header {
+        kid: Some("key".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    let claims = json!({ "sub": "alice", "scope": scope, "exp": 4102444800u64 });
+    format!(
+        "Bearer {}",
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    )
+}
+
+fn unauthorized(coordinate: &str, path: serde_json::Value) -> serde_json::Value {
+    json!({
+        "message": format!("Unauthorized field or type \"{}\".", coordinate),
+        "path": path,
+        "extensions": { "code": UNAUTHORIZED_FIELD_OR_TYPE },
+    })
+}
+
+fn without_locations(mut resp: serde_json::Value) -> serde_json::Value {
+    for error in resp["errors"].as_array_mut().into_iter().flatten() {
+        error.as_object_mut().unwrap().remove("locations");
+    }
+    resp
+}
+
+#[tokio::test]
+async fn remove_unauthorized_fields() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).auth(auth()).start().await;
+    let query = json!({
+        "query": r#"
+            query($withCost: Boolean!) {
+                me { ...UserFields }
+                products { name cost @include(if: $withCost) supplier { name } }
+                report
+            }
+            fragment UserFields on User { id name }
+        "#,
+        "variables": { "withCost": true },
+    });
+
+    let resp = gateway.post(query.clone(), &[]).await.json().await.unwrap();
+    assert_eq!(
+        without_locations(resp),
+        json!({
+            "data": { "products": [{ "name": "chair" }] },
+            "errors": [
+                unauthorized("Query.me", json!(["me"])),
+                unauthorized("Product.cost", json!(["products", "cost"])),
+                unauthorized("Product.supplier", json!(["products", "supplier"])),
+                unauthorized("Query.report", json!(["report"])),
+            ],
+        })
+    );
+    // The subgraph is not sent the unauthorized fields.
+    let requests = accounts.requests();
+    assert!(!requests[0].query.contains("cost"));
+    assert!(!requests[0].query.contains("supplier"));
+    assert!(!requests[0].query.contains("report"));
+
+    let resp: serde_json::Value = gateway
+        .post(query.clone(), &[("authorization", &authorization("audit finance"))])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "me": { "id": "1", "name": "alice" },
+                "products": [{ "name": "chair", "cost": 20, "supplier": { "name": "acme" } }],
+                "report": "ok",
+            }
+        })
+    );
+
+    let resp: serde_json::Value = gateway
+        .post(query, &[("authorization", &authorization("audit"))])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        without_locations(resp)["errors"],
+        json!([
+            unauthorized("Product.cost", json!(["products", "cost"])),
+            unauthorized("Query.report", json!(["report"])),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn reject_operations_without_authorized_fields() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).auth(auth()).start().await;
+
+    let resp = gateway.query(json!({ "query": "{ me { id } report }" })).await;
+    assert_eq!(
+        without_locations(resp),
+        json!({
+            "data": null,
+            "errors": [
+                unauthorized("Query.me", json!(["me"])),
+                unauthorized("Query.report", json!(["report"])),
+            ],
+        })
+    );
+    assert!(accounts.requests().is_empty());
+}
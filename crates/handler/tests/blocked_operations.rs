@@ -0,0 +1,40 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::OPERATION_BLOCKED;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use value::ConstValue;
+
+const SDL: &str = "type Query { me: String! }";
+
+#[tokio::test]
+async fn block_operations() {
+    let accounts = SubgraphBuilder::new("accounts", SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    let shared_route_table = gateway.shared_route_table();
+
+    let query = "{ me }";
+    let hash = format!("{:X}", Sha256::digest(query.as_bytes()));
+    assert!(shared_route_table.block_operation(&hash));
+    assert!(!shared_route_table.block_operation(&hash));
+    assert_eq!(shared_route_table.blocked_operations(), vec![hash.to_ascii_lowercase()]);
+
+    let resp = gateway.query(json!({ "query": query })).await;
+    assert_eq!(resp["errors"][0]["message"], "The operation is blocked.");
+    assert_eq!(resp["errors"][0]["extensions"]["code"], OPERATION_BLOCKED);
+    assert!(accounts.requests().is_empty());
+
+    // Other operations are served.
+    let resp = gateway.query(json!({ "query": "query { me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    assert!(shared_route_table.unblock_operation(&hash));
+    assert!(!shared_route_table.unblock_operation(&hash));
+    let resp = gateway.query(json!({ "query": query })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use graphgate_handler::{BucketHasher, BucketKey, Bucketing, BucketingConfig, RequestContext};
+use http::{HeaderMap, HeaderValue};
+use serde_json::json;
+
+fn config(key: BucketKey) -> BucketingConfig {
+    BucketingConfig {
+        key,
+        buckets: 100,
+        salt: String::new(),
+    }
+}
+
+fn user(id: &str) -> RequestContext {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-user-id", HeaderValue::from_str(id).unwrap());
+    RequestContext {
+        headers,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn stable_buckets() {
+    let bucketing = Bucketing::new(config(BucketKey::Header("x-user-id".to_string())));
+
+    let buckets = (0..1000)
+        .map(|id| bucketing.bucket("canary", &user(&id.to_string())).unwrap())
+        .collect::<Vec<_>>();
+    let again = (0..1000)
+        .map(|id| bucketing.bucket("canary", &user(&id.to_string())).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(buckets, again);
+    assert!(buckets.iter().all(|bucket| *bucket < 100));
+    assert!((0..100).all(|bucket| buckets.contains(&bucket)));
+
+    // The assignments of a namespace do not follow those of another.
+    let shadowing = (0..1000)
+        .map(|id| bucketing.bucket("shadowing", &user(&id.to_string())).unwrap())
+        .collect::<Vec<_>>();
+    assert_ne!(buckets, shadowing);
+
+    assert_eq!(bucketing.bucket("canary", &RequestContext::default()), None);
+    assert!(!bucketing.in_rollout("canary", &RequestContext::default(), 100.0));
+}
+
+#[test]
+fn rollouts_keep_their_requests() {
+    let bucketing = Bucketing::new(config(BucketKey::Header("x-user-id".to_string())));
+    let users = (0..1000).map(|id| user(&id.to_string())).collect::<Vec<_>>();
+
+    let in_rollout = |percentage| {
+        users
+            .iter()
+            .map(|user| bucketing.in_rollout("override", user, percentage))
+            .collect::<Vec<_>>()
+    };
+    let ten = in_rollout(10.0);
+    let fifty = in_rollout(50.0);
+    assert!(ten.iter().zip(&fifty).all(|(ten, fifty)| !ten || *fifty));
+    let count = ten.iter().filter(|in_rollout| **in_rollout).count();
+    assert!((50..150).contains(&count), "{} users in a 10% rollout", count);
+    assert!(in_rollout(0.0).iter().all(|in_rollout| !in_rollout));
+    assert!(in_rollout(100.0).iter().all(|in_rollout| *in_rollout));
+}
+
+/// Puts the keys in the bucket of their number.
+struct NumberHasher;
+
+impl BucketHasher for NumberHasher {
+    fn hash(&self, key: &str) -> u64 {
+        key.rsplit(':').next().unwrap().parse().unwrap()
+    }
+}
+
+#[test]
+fn bucket_keys() {
+    let context = RequestContext {
+        claims: Some(json!({ "user": { "id": 42 } })),
+        remote_addr: Some("10.0.0.7:5000".parse().unwrap()),
+        ..Default::default()
+    };
+
+    let bucketing = Bucketing::with_hasher(config(BucketKey::Claim("user.id".to_string())), Arc::new(NumberHasher));
+    assert_eq!(bucketing.bucket("canary", &context), Some(42));
+
+    let bucketing = Bucketing::new(config(BucketKey::Ip));
+    assert_eq!(
+        bucketing.bucket("canary", &context),
+        bucketing.bucket("canary", &RequestContext {
+            remote_addr: Some("10.0.0.7:6000".parse().unwrap()),
+            ..Default::default()
+        })
+    );
+    assert_eq!(bucketing.bucket("canary", &user("1")), None);
+}
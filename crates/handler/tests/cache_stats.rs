@@ -0,0 +1,138 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    CacheKind,
+    CacheStats,
+    HotEntry,
+    MemoryEntityCache,
+    MemoryPersistedQueryCache,
+    PERSISTED_QUERY_NOT_FOUND,
+};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") @cacheControl(maxAge: 60) {
+        id: ID! @external
+        reviews: [Review!]!
+    }
+"#;
+
+const QUERY: &str = "{ me { username reviews { body } } }";
+
+fn extensions(hash: &str) -> Value {
+    json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } })
+}
+
+fn stats(stats: &[(CacheKind, CacheStats)], kind: CacheKind) -> &CacheStats {
+    &stats.iter().find(|(cache, _)| *cache == kind).unwrap().1
+}
+
+#[tokio::test]
+async fn track_and_evict_cache_entries() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| Ok(value!({ "reviews": [{ "body": "Great!" }] })))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .persisted_query_cache(Arc::new(MemoryPersistedQueryCache::new(10)))
+        .entity_cache(Arc::new(MemoryEntityCache::new(10)))
+        .start()
+        .await;
+    let hash = format!("{:x}", Sha256::digest(QUERY.as_bytes()));
+
+    gateway.query(json!({ "extensions": extensions("unknown") })).await;
+    gateway
+        .query(json!({ "query": QUERY, "extensions": extensions(&hash) }))
+        .await;
+    for _ in 0..2 {
+        let resp = gateway.query(json!({ "extensions": extensions(&hash) })).await;
+        assert_eq!(resp["data"]["me"]["reviews"][0]["body"], "Great!");
+    }
+
+    let route_table = gateway.shared_route_table();
+    let all_stats = route_table.cache_stats(10);
+    let persisted_queries = stats(&all_stats, CacheKind::PersistedQueries);
+    assert_eq!(persisted_queries.entries, Some(1));
+    assert_eq!(persisted_queries.memory_bytes, Some(hash.len() + QUERY.len()));
+    assert_eq!((persisted_queries.hits, persisted_queries.misses), (2, 1));
+    assert_eq!(persisted_queries.hit_ratio(), Some(2.0 / 3.0));
+    assert_eq!(persisted_queries.hottest, vec![HotEntry {
+        key: hash.clone(),
+        hits: 2
+    }]);
+
+    let entities = stats(&all_stats, CacheKind::Entities);
+    assert_eq!(entities.entries, Some(1));
+    assert_eq!((entities.hits, entities.misses), (2, 1));
+    let entity_key = entities.hottest[0].key.clone();
+
+    assert!(route_table
+        .evict_cache_entry(CacheKind::PersistedQueries, &hash)
+        .await
+        .unwrap());
+    assert!(!route_table
+        .evict_cache_entry(CacheKind::PersistedQueries, &hash)
+        .await
+        .unwrap());
+    let resp = gateway.query(json!({ "extensions": extensions(&hash) })).await;
+    assert_eq!(resp["errors"][0]["extensions"]["code"], PERSISTED_QUERY_NOT_FOUND);
+
+    // An evicted entity is fetched again.
+    assert!(route_table
+        .evict_cache_entry(CacheKind::Entities, &entity_key)
+        .await
+        .unwrap());
+    gateway.query(json!({ "query": QUERY })).await;
+    let entities_requests = reviews
+        .requests()
+        .into_iter()
+        .filter(|request| request.query.contains("_entities"))
+        .count();
+    assert_eq!(entities_requests, 2);
+}
+
+#[tokio::test]
+async fn track_introspection_responses() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    let query = json!({ "query": "{ __schema { queryType { name } } }" });
+
+    for _ in 0..3 {
+        gateway.query(query.clone()).await;
+    }
+    let all_stats = gateway.shared_route_table().cache_stats(10);
+    assert_eq!(all_stats.len(), 1);
+    let introspection = stats(&all_stats, CacheKind::Introspection);
+    assert_eq!(introspection.entries, Some(1));
+    assert_eq!((introspection.hits, introspection.misses), (2, 1));
+    assert_eq!(introspection.hottest[0].hits, 2);
+    assert!(introspection.memory_bytes.unwrap() > introspection.hottest[0].key.len());
+
+    let key = introspection.hottest[0].key.clone();
+    assert!(gateway
+        .shared_route_table()
+        .evict_cache_entry(CacheKind::Introspection, &key)
+        .await
+        .unwrap());
+    let all_stats = gateway.shared_route_table().cache_stats(10);
+    assert_eq!(stats(&all_stats, CacheKind::Introspection).entries, Some(0));
+}
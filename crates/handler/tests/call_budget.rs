@@ -0,0 +1,61 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{CallBudgetConfig, CALL_BUDGET_EXCEEDED};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! author: User! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[tokio::test]
+async fn call_budget_exceeded() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234" })))
+        .entity("User", |_| Ok(value!({ "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| {
+            Ok(value!({ "reviews": [{ "body": "Great!", "author": { "id": "1234" } }] }))
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .call_budget_config(CallBudgetConfig { max_calls: 2 })
+        .start()
+        .await;
+
+    // The operation takes three requests: me, its reviews and their authors.
+    let query = json!({ "query": "{ me { id reviews { author { username } } } }" });
+    let resp = gateway.query(query).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "The operation exceeds the limit of 2 subgraph requests.",
+                "extensions": { "code": CALL_BUDGET_EXCEEDED },
+            }],
+        })
+    );
+    // The request over the limit is not sent.
+    assert_eq!(accounts.requests().len(), 1);
+    assert_eq!(reviews.requests().len(), 1);
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { id reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": { "id": "1234", "reviews": [{ "body": "Great!" }] } } })
+    );
+}
@@ -0,0 +1,218 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    BucketKey,
+    Bucketing,
+    BucketingConfig,
+    CallBudgetConfig,
+    ChaosConfig,
+    Fault,
+    FaultKind,
+    CALL_BUDGET_EXCEEDED,
+};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query {
+        me: String!
+    }
+"#;
+
+const PRODUCTS_SDL: &str = r#"
+    type Query {
+        topProduct: String!
+    }
+"#;
+
+fn fault(service: &str, kind: FaultKind) -> Fault {
+    Fault {
+        service: Some(service.to_string()),
+        percentage: 100.0,
+        kind,
+    }
+}
+
+#[tokio::test]
+async fn inject_faults_in_matching_services() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("topProduct", |_| Ok(ConstValue::String("table".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &products])
+        .chaos_config(ChaosConfig {
+            enabled: true,
+            faults: vec![fault("accounts", FaultKind::Status {
+                status: 503,
+                body: "injected".to_string(),
+            })],
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ topProduct }" })).await;
+    assert_eq!(resp, json!({ "data": { "topProduct": "table" } }));
+
+    // The faulted request is not sent to the service.
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        json!("service \"accounts\" responded with status 503")
+    );
+    assert_eq!(resp["errors"][0]["extensions"]["code"], json!("BAD_GATEWAY"));
+    assert!(accounts.requests().is_empty());
+}
+
+#[tokio::test]
+async fn drop_connections() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .chaos_config(ChaosConfig {
+            enabled: true,
+            faults: vec![fault("accounts", FaultKind::Drop)],
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert!(resp["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("dropped by an injected fault"));
+    assert!(accounts.requests().is_empty());
+}
+
+#[tokio::test]
+async fn inject_latency() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .chaos_config(ChaosConfig {
+            enabled: true,
+            faults: vec![fault("accounts", FaultKind::Latency { latency_ms: 300 })],
+        })
+        .start()
+        .await;
+
+    let start = Instant::now();
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn ignore_faults_unless_enabled() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .chaos_config(ChaosConfig {
+            enabled: false,
+            faults: vec![fault("accounts", FaultKind::Drop)],
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+}
+
+#[tokio::test]
+async fn retry_injected_faults() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .service_retries("accounts", 3, 1)
+        .call_budget_config(CallBudgetConfig { max_calls: 2 })
+        .chaos_config(ChaosConfig {
+            enabled: true,
+            faults: vec![fault("accounts", FaultKind::Drop)],
+        })
+        .start()
+        .await;
+
+    // The retries of the faulted request count against the call budget.
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["errors"][0]["extensions"]["code"], json!(CALL_BUDGET_EXCEEDED));
+    assert!(accounts.requests().is_empty());
+}
+
+#[tokio::test]
+async fn time_out_injected_latency() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .service_timeout("accounts", 50)
+        .chaos_config(ChaosConfig {
+            enabled: true,
+            faults: vec![fault("accounts", FaultKind::Latency { latency_ms: 300 })],
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        json!("service \"accounts\" did not respond within 50 ms")
+    );
+}
+
+#[tokio::test]
+async fn inject_faults_in_the_same_clients() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .bucketing(Bucketing::new(BucketingConfig {
+            key: BucketKey::Header("x-user-id".to_string()),
+            buckets: 100,
+            salt: String::new(),
+        }))
+        .chaos_config(ChaosConfig {
+            enabled: true,
+            faults: vec![Fault {
+                percentage: 50.0,
+                ..fault("accounts", FaultKind::Drop)
+            }],
+        })
+        .start()
+        .await;
+
+    let mut faulted = Vec::new();
+    for id in 0..20 {
+        let id = id.to_string();
+        let mut outcomes = Vec::new();
+        for _ in 0..3 {
+            let resp: serde_json::Value = gateway
+                .post(json!({ "query": "{ me }" }), &[("x-user-id", &id)])
+                .await
+                .json()
+                .await
+                .unwrap();
+            outcomes.push(resp.get("errors").is_some());
+        }
+        // A client gets the fault on every request or on none.
+        assert!(outcomes.iter().all(|outcome| *outcome == outcomes[0]), "{:?}", outcomes);
+        faulted.push(outcomes[0]);
+    }
+    assert!(faulted.contains(&true) && faulted.contains(&false), "{:?}", faulted);
+}
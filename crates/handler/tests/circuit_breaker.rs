@@ -0,0 +1,79 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{CircuitBreakerConfig, ServiceCircuitBreakerConfig, BAD_GATEWAY, SUBGRAPH_CIRCUIT_OPEN};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+use warp::http::StatusCode;
+
+// The circuits are process wide, so each test uses its own services.
+fn circuit_breaker_config(service: &str, failure_threshold: u32, cooldown_secs: u64) -> CircuitBreakerConfig {
+    CircuitBreakerConfig {
+        services: [(service.to_string(), ServiceCircuitBreakerConfig {
+            failure_threshold: Some(failure_threshold),
+            cooldown_secs: Some(cooldown_secs),
+        })]
+        .into(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn open_circuit_after_consecutive_failures() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    gateway
+        .shared_route_table()
+        .set_circuit_breaker_config(circuit_breaker_config("accounts", 2, 60));
+    accounts.set_status(StatusCode::SERVICE_UNAVAILABLE);
+
+    for _ in 0..2 {
+        let resp = gateway.query(json!({ "query": "{ me }" })).await;
+        assert_eq!(resp["errors"][0]["extensions"]["code"], BAD_GATEWAY);
+    }
+    assert_eq!(accounts.requests().len(), 2);
+
+    // The requests fail fast while the circuit is open.
+    accounts.set_status(StatusCode::OK);
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "the circuit of service \"accounts\" is open, retry after 60 seconds",
+                "extensions": { "code": SUBGRAPH_CIRCUIT_OPEN, "service": "accounts", "retryAfter": 60 },
+            }],
+        })
+    );
+    assert_eq!(accounts.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn close_circuit_after_probe() {
+    let inventory = SubgraphBuilder::new("inventory", "type Query { stock: Int }")
+        .field("stock", |_| Ok(ConstValue::Number(3.into())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&inventory]).start().await;
+    gateway
+        .shared_route_table()
+        .set_circuit_breaker_config(circuit_breaker_config("inventory", 1, 0));
+
+    // The first failure opens the circuit.
+    inventory.set_status(StatusCode::SERVICE_UNAVAILABLE);
+    gateway.query(json!({ "query": "{ stock }" })).await;
+    assert_eq!(inventory.requests().len(), 1);
+
+    // A fetch probes the service once the cool-down ended.
+    inventory.set_status(StatusCode::OK);
+    let resp = gateway.query(json!({ "query": "{ stock }" })).await;
+    assert_eq!(resp, json!({ "data": { "stock": 3 } }));
+    let resp = gateway.query(json!({ "query": "{ stock }" })).await;
+    assert_eq!(resp, json!({ "data": { "stock": 3 } }));
+    assert_eq!(inventory.requests().len(), 3);
+}
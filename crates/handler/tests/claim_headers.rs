@@ -0,0 +1,93 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::auth::{Auth, AuthConfig, ClaimHeader};
+use graphgate_test_utils::SubgraphBuilder;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: String }
+"#;
+
+const SECRET: &[u8] = b"secret";
+
+fn claim_header(claim: &str, header: &str) -> ClaimHeader {
+    ClaimHeader {
+        claim: claim.to_string(),
+        header: header.parse().unwrap(),
+    }
+}
+
+fn auth() -> Auth {
+    Auth::with_keys(
+        AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            claim_headers: vec![
+                claim_header("sub", "x-user-id"),
+                claim_header("roles", "x-roles"),
+                claim_header("org.id", "x-org-id"),
+                claim_header("tenant", "x-tenant"),
+            ],
+            ..Default::default()
+        },
+        [("key".to_string(), DecodingKey::from_secret(SECRET))].into(),
+    )
+}
+
+fn token() -> String {
+    let header = Header {
+        kid: Some("key".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    let claims = json!({
+        "sub": "alice",
+        "roles": ["admin", "billing"],
+        "org": { "id": 42 },
+        "exp": 4102444800u64,
+    });
+    jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+}
+
+#[tokio::test]
+async fn forward_claims() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .auth(auth())
+        .forward_headers(&["x-user-id", "x-tenant"])
+        .start()
+        .await;
+
+    let authorization = format!("Bearer {}", token());
+    let resp = gateway
+        .post(json!({ "query": "{ me }" }), &[
+            ("authorization", &authorization),
+            ("x-user-id", "mallory"),
+            ("x-tenant", "acme"),
+        ])
+        .await;
+    assert!(resp.status().is_success());
+
+    let requests = accounts.requests();
+    let headers = &requests[0].headers;
+    assert_eq!(headers["x-user-id"], "alice");
+    assert_eq!(headers["x-roles"], "admin,billing");
+    assert_eq!(headers["x-org-id"], "42");
+    // The header of a missing claim is not taken from the client.
+    assert!(headers.get("x-tenant").is_none());
+}
+
+#[test]
+fn reject_invalid_header_names() {
+    let err = serde_json::from_value::<ClaimHeader>(json!({ "claim": "sub", "header": "x user id" })).unwrap_err();
+    assert_eq!(err.to_string(), "invalid header name 'x user id'");
+
+    let claim_header = serde_json::from_value::<ClaimHeader>(json!({ "claim": "sub", "header": "X-User-Id" })).unwrap();
+    assert_eq!(claim_header.header, "x-user-id");
+}
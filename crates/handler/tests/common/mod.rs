@@ -0,0 +1,579 @@
+#![allow(dead_code)]
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use graphgate_handler::{
+    auth::Auth,
+    handler,
+    handler::HandlerConfig,
+    with_cors,
+    AuditConfig,
+    Bucketing,
+    CallBudgetConfig,
+    ChaosConfig,
+    ConnectionParam,
+    ContextRule,
+    CorsConfig,
+    CorsPolicy,
+    CostConfig,
+    DeferConfig,
+    DeprecationConfig,
+    DocsConfig,
+    EntityCache,
+    GatewaySettings,
+    HeaderPolicy,
+    IntrospectionConfig,
+    OAuth2Config,
+    OperationLimitsConfig,
+    PaginationConfig,
+    PersistedOperation,
+    PersistedQueryCache,
+    Protocols,
+    RedactionRule,
+    RequestSigningConfig,
+    ResponseLimitConfig,
+    Safelist,
+    ServiceRoute,
+    ServiceRouteTable,
+    SharedConfig,
+    SharedRouteTable,
+    SubgraphRequestConfig,
+};
+use graphgate_test_utils::Subgraph;
+use serde_json::Value;
+use tokio::sync::oneshot;
+use warp::Filter;
+
+/// A gateway serving the handler over HTTP and WebSocket on a random local
+/// port, routing to fake subgraphs.
+pub struct Gateway {
+    addr: SocketAddr,
+    shared_route_table: SharedRouteTable,
+    shared_config: SharedConfig,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+pub struct GatewayBuilder {
+    route_table: ServiceRouteTable,
+    forward_headers: Vec<String>,
+    forward_connection_params: Vec<String>,
+    receive_headers: Vec<String>,
+    trace_response_headers: Vec<String>,
+    context_rules: Vec<ContextRule>,
+    redaction_rules: Vec<RedactionRule>,
+    audit_config: Option<AuditConfig>,
+    pagination_config: Option<PaginationConfig>,
+    cost_config: Option<CostConfig>,
+    deprecation_config: Option<DeprecationConfig>,
+    defer_config: Option<DeferConfig>,
+    response_limit_config: Option<ResponseLimitConfig>,
+    call_budget_config: Option<CallBudgetConfig>,
+    operation_limits_config: Option<OperationLimitsConfig>,
+    chaos_config: Option<ChaosConfig>,
+    bucketing: Option<Bucketing>,
+    introspection_config: Option<IntrospectionConfig>,
+    safelist: Option<Safelist>,
+    docs_config: DocsConfig,
+    persisted_operations: Vec<PersistedOperation>,
+    persisted_query_cache: Option<Arc<dyn PersistedQueryCache>>,
+    entity_cache: Option<Arc<dyn EntityCache>>,
+    entity_cache_partition_claims: Vec<String>,
+    entity_cache_credential_headers: Vec<String>,
+    subgraph_request_config: SubgraphRequestConfig,
+    snapshot_path: Option<PathBuf>,
+    supergraph: Option<PathBuf>,
+    cors_config: Option<CorsConfig>,
+    auth: Arc<Auth>,
+    debug_errors: bool,
+    normalize_error_paths: bool,
+    check_entity_keys: bool,
+    allowed_services_extension: bool,
+    propagate_subgraph_status: bool,
+    server_timing: bool,
+    disable_introspection: bool,
+}
+
+impl GatewayBuilder {
+    pub fn new(subgraphs: &[&Subgraph]) -> Self {
+        let mut route_table = ServiceRouteTable::default();
+        for subgraph in subgraphs {
+            route_table.insert(subgraph.name().to_string(), ServiceRoute {
+                addr: subgraph.addr().to_string(),
+                tls: false,
+                query_path: None,
+                subscribe_path: None,
+                introspection_path: None,
+                schema_url: None,
+                websocket_path: None,
+                websocket_protocol: None,
+                connection_params: Default::default(),
+                sdl_file: None,
+                headers: Default::default(),
+                header_policy: Default::default(),
+                user_agent: None,
+                oauth2: None,
+                signing: None,
+                enum_values: Default::default(),
+                lenient_errors: false,
+                timeout_ms: None,
+                retry_count: 0,
+                retry_backoff_ms: 0,
+                source: Default::default(),
+            });
+        }
+        Self {
+            route_table,
+            forward_headers: Vec::new(),
+            forward_connection_params: Vec::new(),
+            receive_headers: Vec::new(),
+            trace_response_headers: Vec::new(),
+            context_rules: Vec::new(),
+            redaction_rules: Vec::new(),
+            audit_config: None,
+            pagination_config: None,
+            cost_config: None,
+            deprecation_config: None,
+            defer_config: None,
+            response_limit_config: None,
+            call_budget_config: None,
+            operation_limits_config: None,
+            chaos_config: None,
+            bucketing: None,
+            introspection_config: None,
+            safelist: None,
+            docs_config: DocsConfig::default(),
+            persisted_operations: Vec::new(),
+            persisted_query_cache: None,
+            entity_cache: None,
+            entity_cache_partition_claims: Vec::new(),
+            entity_cache_credential_headers: Vec::new(),
+            subgraph_request_config: SubgraphRequestConfig::default(),
+            snapshot_path: None,
+            supergraph: None,
+            cors_config: None,
+            auth: Default::default(),
+            debug_errors: false,
+            normalize_error_paths: false,
+            check_entity_keys: false,
+            allowed_services_extension: false,
+            propagate_subgraph_status: false,
+            server_timing: false,
+            disable_introspection: false,
+        }
+    }
+
+    pub fn forward_headers(mut self, headers: &[&str]) -> Self {
+        self.forward_headers = headers.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn forward_connection_params(mut self, params: &[&str]) -> Self {
+        self.forward_connection_params = params.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn receive_headers(mut self, headers: &[&str]) -> Self {
+        self.receive_headers = headers.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn trace_response_headers(mut self, headers: &[&str]) -> Self {
+        self.trace_response_headers = headers.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn context_rules(mut self, rules: Vec<ContextRule>) -> Self {
+        self.context_rules = rules;
+        self
+    }
+
+    pub fn redaction_rules(mut self, rules: Vec<RedactionRule>) -> Self {
+        self.redaction_rules = rules;
+        self
+    }
+
+    pub fn audit_config(mut self, config: AuditConfig) -> Self {
+        self.audit_config = Some(config);
+        self
+    }
+
+    pub fn pagination_config(mut self, config: PaginationConfig) -> Self {
+        self.pagination_config = Some(config);
+        self
+    }
+
+    pub fn cost_config(mut self, config: CostConfig) -> Self {
+        self.cost_config = Some(config);
+        self
+    }
+
+    pub fn deprecation_config(mut self, config: DeprecationConfig) -> Self {
+        self.deprecation_config = Some(config);
+        self
+    }
+
+    pub fn defer_config(mut self, config: DeferConfig) -> Self {
+        self.defer_config = Some(config);
+        self
+    }
+
+    pub fn response_limit_config(mut self, config: ResponseLimitConfig) -> Self {
+        self.response_limit_config = Some(config);
+        self
+    }
+
+    pub fn call_budget_config(mut self, config: CallBudgetConfig) -> Self {
+        self.call_budget_config = Some(config);
+        self
+    }
+
+    pub fn operation_limits_config(mut self, config: OperationLimitsConfig) -> Self {
+        self.operation_limits_config = Some(config);
+        self
+    }
+
+    pub fn chaos_config(mut self, config: ChaosConfig) -> Self {
+        self.chaos_config = Some(config);
+        self
+    }
+
+    pub fn bucketing(mut self, bucketing: Bucketing) -> Self {
+        self.bucketing = Some(bucketing);
+        self
+    }
+
+    pub fn safelist(mut self, safelist: Safelist) -> Self {
+        self.safelist = Some(safelist);
+        self
+    }
+
+    pub fn introspection_config(mut self, config: IntrospectionConfig) -> Self {
+        self.introspection_config = Some(config);
+        self
+    }
+
+    pub fn docs_config(mut self, config: DocsConfig) -> Self {
+        self.docs_config = config;
+        self
+    }
+
+    pub fn persisted_operations(mut self, operations: Vec<PersistedOperation>) -> Self {
+        self.persisted_operations = operations;
+        self
+    }
+
+    pub fn persisted_query_cache(mut self, cache: Arc<dyn PersistedQueryCache>) -> Self {
+        self.persisted_query_cache = Some(cache);
+        self
+    }
+
+    pub fn entity_cache(mut self, cache: Arc<dyn EntityCache>) -> Self {
+        self.entity_cache = Some(cache);
+        self
+    }
+
+    pub fn entity_cache_partition_claims(mut self, claims: &[&str]) -> Self {
+        self.entity_cache_partition_claims = claims.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn entity_cache_credential_headers(mut self, headers: &[&str]) -> Self {
+        self.entity_cache_credential_headers = headers.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn service_headers(mut self, service: &str, headers: &[(&str, &str)]) -> Self {
+        let route = self.route_table.get_mut(service).unwrap();
+        route.headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    pub fn service_header_policy(mut self, service: &str, header_policy: HeaderPolicy) -> Self {
+        self.route_table.get_mut(service).unwrap().header_policy = header_policy;
+        self
+    }
+
+    pub fn service_oauth2(mut self, service: &str, config: OAuth2Config) -> Self {
+        self.route_table.get_mut(service).unwrap().oauth2 = Some(config);
+        self
+    }
+
+    pub fn service_signing(mut self, service: &str, config: RequestSigningConfig) -> Self {
+        self.route_table.get_mut(service).unwrap().signing = Some(config);
+        self
+    }
+
+    pub fn service_enum_values(mut self, service: &str, ty: &str, values: &[(&str, &str)]) -> Self {
+        let route = self.route_table.get_mut(service).unwrap();
+        route.enum_values.insert(
+            ty.to_string(),
+            values
+                .iter()
+                .map(|(supergraph_value, value)| (supergraph_value.to_string(), value.to_string()))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn subgraph_request_config(mut self, config: SubgraphRequestConfig) -> Self {
+        self.subgraph_request_config = config;
+        self
+    }
+
+    pub fn service_lenient_errors(mut self, service: &str) -> Self {
+        self.route_table.get_mut(service).unwrap().lenient_errors = true;
+        self
+    }
+
+    pub fn service_timeout(mut self, service: &str, timeout_ms: u64) -> Self {
+        self.route_table.get_mut(service).unwrap().timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    pub fn service_retries(mut self, service: &str, retry_count: u32, retry_backoff_ms: u64) -> Self {
+        let route = self.route_table.get_mut(service).unwrap();
+        route.retry_count = retry_count;
+        route.retry_backoff_ms = retry_backoff_ms;
+        self
+    }
+
+    pub fn service_user_agent(mut self, service: &str, user_agent: &str) -> Self {
+        self.route_table.get_mut(service).unwrap().user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    pub fn service_schema_url(mut self, service: &str, url: &str) -> Self {
+        self.route_table.get_mut(service).unwrap().schema_url = Some(url.to_string());
+        self
+    }
+
+    pub fn service_websocket(mut self, service: &str, path: &str, protocol: Protocols) -> Self {
+        let route = self.route_table.get_mut(service).unwrap();
+        route.websocket_path = Some(path.to_string());
+        route.websocket_protocol = Some(protocol);
+        self
+    }
+
+    pub fn service_connection_params(mut self, service: &str, params: &[(&str, ConnectionParam)]) -> Self {
+        let route = self.route_table.get_mut(service).unwrap();
+        for (name, param) in params {
+            route.connection_params.insert(name.to_string(), param.clone());
+        }
+        self
+    }
+
+    pub fn snapshot_path(mut self, path: PathBuf) -> Self {
+        self.snapshot_path = Some(path);
+        self
+    }
+
+    /// Serve the supergraph SDL at `path` instead of composing the SDLs of
+    /// the subgraphs.
+    pub fn supergraph(mut self, path: PathBuf) -> Self {
+        self.supergraph = Some(path);
+        self
+    }
+
+    pub fn cors_config(mut self, config: CorsConfig) -> Self {
+        self.cors_config = Some(config);
+        self
+    }
+
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
+
+    pub fn debug_errors(mut self, debug_errors: bool) -> Self {
+        self.debug_errors = debug_errors;
+        self
+    }
+
+    pub fn normalize_error_paths(mut self, normalize_error_paths: bool) -> Self {
+        self.normalize_error_paths = normalize_error_paths;
+        self
+    }
+
+    pub fn check_entity_keys(mut self, check_entity_keys: bool) -> Self {
+        self.check_entity_keys = check_entity_keys;
+        self
+    }
+
+    pub fn allowed_services_extension(mut self, allowed_services_extension: bool) -> Self {
+        self.allowed_services_extension = allowed_services_extension;
+        self
+    }
+
+    pub fn propagate_subgraph_status(mut self, propagate_subgraph_status: bool) -> Self {
+        self.propagate_subgraph_status = propagate_subgraph_status;
+        self
+    }
+
+    pub fn server_timing(mut self, server_timing: bool) -> Self {
+        self.server_timing = server_timing;
+        self
+    }
+
+    pub fn disable_introspection(mut self, disable_introspection: bool) -> Self {
+        self.disable_introspection = disable_introspection;
+        self
+    }
+
+    pub async fn start(self) -> Gateway {
+        let mut shared_route_table = SharedRouteTable::default();
+        shared_route_table.set_receive_headers(self.receive_headers);
+        shared_route_table.set_trace_response_headers(self.trace_response_headers);
+        shared_route_table.set_context_rules(self.context_rules);
+        shared_route_table.set_redaction_rules(self.redaction_rules);
+        if let Some(audit_config) = self.audit_config {
+            shared_route_table.set_audit_config(audit_config).unwrap();
+        }
+        if let Some(pagination_config) = self.pagination_config {
+            shared_route_table.set_pagination_config(pagination_config);
+        }
+        if let Some(cost_config) = self.cost_config {
+            shared_route_table.set_cost_config(cost_config);
+        }
+        if let Some(deprecation_config) = self.deprecation_config {
+            shared_route_table.set_deprecation_config(deprecation_config);
+        }
+        if let Some(defer_config) = self.defer_config {
+            shared_route_table.set_defer_config(defer_config);
+        }
+        if let Some(response_limit_config) = self.response_limit_config {
+            shared_route_table.set_response_limit_config(response_limit_config);
+        }
+        if let Some(call_budget_config) = self.call_budget_config {
+            shared_route_table.set_call_budget_config(call_budget_config);
+        }
+        if let Some(operation_limits_config) = self.operation_limits_config {
+            shared_route_table.set_operation_limits_config(operation_limits_config);
+        }
+        if let Some(bucketing) = self.bucketing {
+            shared_route_table.set_bucketing(bucketing);
+        }
+        if let Some(chaos_config) = self.chaos_config {
+            shared_route_table.set_chaos_config(chaos_config);
+        }
+        if let Some(safelist) = self.safelist {
+            shared_route_table.set_safelist(safelist);
+        }
+        if let Some(introspection_config) = self.introspection_config {
+            shared_route_table.set_introspection_config(introspection_config);
+        }
+        shared_route_table.set_persisted_operations(self.persisted_operations);
+        if let Some(cache) = self.persisted_query_cache {
+            shared_route_table.set_persisted_query_cache(cache);
+        }
+        if let Some(cache) = self.entity_cache {
+            shared_route_table.set_entity_cache(cache);
+        }
+        shared_route_table.set_entity_cache_partition_claims(self.entity_cache_partition_claims);
+        shared_route_table.set_entity_cache_credential_headers(self.entity_cache_credential_headers);
+        shared_route_table.set_debug_errors(self.debug_errors);
+        shared_route_table.set_normalize_error_paths(self.normalize_error_paths);
+        shared_route_table.set_check_entity_keys(self.check_entity_keys);
+        shared_route_table.set_allowed_services_extension(self.allowed_services_extension);
+        shared_route_table.set_propagate_subgraph_status(self.propagate_subgraph_status);
+        shared_route_table.set_server_timing(self.server_timing);
+        shared_route_table.set_disable_introspection(self.disable_introspection);
+        shared_route_table.set_subgraph_request_config(self.subgraph_request_config);
+        if let Some(snapshot_path) = self.snapshot_path {
+            shared_route_table.set_snapshot_path(snapshot_path);
+            shared_route_table.load_snapshot().await.unwrap();
+        }
+        match self.supergraph {
+            Some(path) => shared_route_table.set_supergraph_path(path),
+            None => shared_route_table.set_route_table(self.route_table),
+        }
+
+        assert!(
+            shared_route_table.wait_ready(Duration::from_secs(10)).await,
+            "the schema was not composed in time"
+        );
+
+        let shared_config = SharedConfig::new(GatewaySettings {
+            forward_headers: self.forward_headers,
+            forward_connection_params: self.forward_connection_params,
+            auth: self.auth,
+            cors: CorsPolicy::new(self.cors_config.as_ref()),
+        });
+        let docs = handler::graphql_docs(shared_config.clone(), self.docs_config, shared_route_table.clone());
+        let config = HandlerConfig {
+            shared_route_table: shared_route_table.clone(),
+            shared_config: shared_config.clone(),
+            operation_labeler: Default::default(),
+        };
+        let routes = warp::path::end()
+            .and(handler::graphql_request(config.clone()).or(handler::graphql_websocket(config.clone())))
+            .or(docs)
+            .or(handler::graphql_explain(config, true));
+        let routes = with_cors(shared_config.clone(), routes);
+
+        let (tx_shutdown, rx_shutdown) = oneshot::channel::<()>();
+        let (addr, server) = warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async move {
+            rx_shutdown.await.ok();
+        });
+        tokio::spawn(server);
+
+        Gateway {
+            addr,
+            shared_route_table,
+            shared_config,
+            shutdown: Some(tx_shutdown),
+        }
+    }
+}
+
+impl Gateway {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub async fn post(&self, body: Value, headers: &[(&str, &str)]) -> reqwest::Response {
+        let mut request = reqwest::Client::new().post(format!("http://{}", self.addr)).json(&body);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.send().await.unwrap()
+    }
+
+    pub fn shared_route_table(&self) -> &SharedRouteTable {
+        &self.shared_route_table
+    }
+
+    pub fn shared_config(&self) -> &SharedConfig {
+        &self.shared_config
+    }
+
+    pub async fn explain(&self, body: Value) -> Value {
+        reqwest::Client::new()
+            .post(format!("http://{}/explain", self.addr))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    pub async fn get(&self, path: &str) -> reqwest::Response {
+        reqwest::get(format!("http://{}{}", self.addr, path)).await.unwrap()
+    }
+
+    pub async fn query(&self, body: Value) -> Value {
+        self.post(body, &[]).await.json().await.unwrap()
+    }
+}
+
+impl Drop for Gateway {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{Gateway, GatewayBuilder};
+use futures_util::{SinkExt, StreamExt};
+use graphgate_handler::{ConnectionParam, Protocols};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use value::ConstValue;
+
+async fn reviews() -> Subgraph {
+    SubgraphBuilder::new(
+        "reviews",
+        "type Query { ok: Boolean } type Subscription { reviewAdded: String! }",
+    )
+    .subscription("reviewAdded", |_| vec![ConstValue::String("first".to_string())])
+    .spawn()
+    .await
+}
+
+async fn subscribe(gateway: &Gateway, init_payload: Value, headers: &[(&'static str, &str)]) {
+    let mut request = format!("ws://{}", gateway.addr()).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "graphql-transport-ws".parse().unwrap());
+    for (name, value) in headers {
+        request.headers_mut().insert(*name, value.parse().unwrap());
+    }
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+    socket
+        .send(Message::text(
+            json!({ "type": "connection_init", "payload": init_payload }).to_string(),
+        ))
+        .await
+        .unwrap();
+    socket
+        .send(Message::text(
+            json!({
+                "type": "subscribe",
+                "id": "1",
+                "payload": { "query": "subscription { reviewAdded }" }
+            })
+            .to_string(),
+        ))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(Ok(message)) = socket.next().await {
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            if message["type"] == "complete" {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("the subscription did not complete in time");
+}
+
+#[tokio::test]
+async fn forward_allowed_connection_params() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews])
+        .forward_connection_params(&["tenant"])
+        .start()
+        .await;
+
+    subscribe(&gateway, json!({ "tenant": "acme", "token": "secret" }), &[]).await;
+    assert_eq!(reviews.connection_params(), vec![Some(json!({ "tenant": "acme" }))]);
+}
+
+#[tokio::test]
+async fn forward_all_connection_params_by_default() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+
+    subscribe(&gateway, json!({ "tenant": "acme", "token": "secret" }), &[]).await;
+    assert_eq!(reviews.connection_params(), vec![Some(
+        json!({ "tenant": "acme", "token": "secret" })
+    )]);
+}
+
+#[tokio::test]
+async fn service_connection_params() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews])
+        .forward_headers(&["authorization", "accept-language"])
+        .forward_connection_params(&["tenant"])
+        .service_connection_params("reviews", &[
+            ("tenant", ConnectionParam::Value(json!("globex"))),
+            ("token", ConnectionParam::Header("authorization".to_string())),
+            ("locale", ConnectionParam::Header("accept-language".to_string())),
+        ])
+        .start()
+        .await;
+
+    subscribe(&gateway, json!({ "tenant": "acme", "region": "eu" }), &[(
+        "authorization",
+        "Bearer secret",
+    )])
+    .await;
+    assert_eq!(reviews.connection_params(), vec![Some(
+        json!({ "tenant": "globex", "token": "Bearer secret" })
+    )]);
+}
+
+#[tokio::test]
+async fn service_websocket_transport() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+    subscribe(&gateway, json!({}), &[]).await;
+    assert_eq!(reviews.websocket_connections(), vec![(
+        "/".to_string(),
+        "graphql-transport-ws".to_string()
+    )]);
+
+    let reviews = self::reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews])
+        .service_websocket("reviews", "/subscriptions", Protocols::SubscriptionsTransportWS)
+        .start()
+        .await;
+    subscribe(&gateway, json!({}), &[]).await;
+    assert_eq!(reviews.websocket_connections(), vec![(
+        "/subscriptions".to_string(),
+        "graphql-ws".to_string()
+    )]);
+}
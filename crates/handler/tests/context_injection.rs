@@ -0,0 +1,167 @@
+mod common;
+
+use std::time::Duration;
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    CompositionStatus,
+    ContextRule,
+    ContextSource,
+    ServiceRoute,
+    ServiceRouteTable,
+    SharedRouteTable,
+};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::json;
+use tempfile::NamedTempFile;
+use value::ConstValue;
+
+const ORDERS_SDL: &str = r#"
+    type Query { orders(userId: ID!, limit: Int): [Order!]! }
+    type Order { id: ID! }
+"#;
+
+async fn orders() -> Subgraph {
+    SubgraphBuilder::new("orders", ORDERS_SDL)
+        .field("orders", |ctx| {
+            let user_id = ctx.arguments.get("userId").cloned().unwrap_or_default();
+            Ok(ConstValue::List(vec![ConstValue::Object(
+                [(value::Name::new("id"), user_id)].into_iter().collect(),
+            )]))
+        })
+        .spawn()
+        .await
+}
+
+fn rule(argument: &str, header: &str) -> ContextRule {
+    ContextRule {
+        field: "Query.orders".to_string(),
+        argument: argument.to_string(),
+        source: ContextSource::Header(header.to_string()),
+    }
+}
+
+#[tokio::test]
+async fn inject_header() {
+    let orders = orders().await;
+    let gateway = GatewayBuilder::new(&[&orders])
+        .context_rules(vec![rule("userId", "x-user-id")])
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": r#"{ orders(userId: "spoofed") { id } }"# }), &[(
+            "x-user-id",
+            "42",
+        )])
+        .await;
+    assert_eq!(
+        resp.json::<serde_json::Value>().await.unwrap(),
+        json!({ "data": { "orders": [{ "id": "42" }] } })
+    );
+
+    let requests = orders.requests();
+    assert_eq!(
+        requests[0].variables.get("__context0"),
+        Some(&ConstValue::String("42".to_string()))
+    );
+
+    let resp = gateway.query(json!({ "query": "{ orders { id } }" })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{ "message": "Missing context value for argument \"userId\" of field \"Query.orders\"." }]
+        })
+    );
+}
+
+#[tokio::test]
+async fn ignore_invalid_rules() {
+    let orders = orders().await;
+
+    for rule in [rule("user", "x-user-id"), rule("limit", "x-limit")] {
+        let gateway = GatewayBuilder::new(&[&orders])
+            .context_rules(vec![rule, self::rule("userId", "x-user-id")])
+            .start()
+            .await;
+        let resp = gateway
+            .post(json!({ "query": "{ orders(userId: \"1\", limit: 1) { id } }" }), &[
+                ("x-user-id", "42"),
+                ("x-limit", "1"),
+            ])
+            .await;
+        assert_eq!(
+            resp.json::<serde_json::Value>().await.unwrap(),
+            json!({ "data": { "orders": [{ "id": "42" }] } })
+        );
+    }
+}
+
+#[tokio::test]
+async fn validate_rules_on_recomposition() {
+    let sdl_file = NamedTempFile::new().unwrap();
+    std::fs::write(sdl_file.path(), ORDERS_SDL).unwrap();
+
+    let shared_route_table = SharedRouteTable::default();
+    shared_route_table.set_update_interval(Duration::from_millis(50));
+    shared_route_table.set_context_rules(vec![rule("userId", "x-user-id"), rule("user", "x-user-id")]);
+    let mut route_table = ServiceRouteTable::default();
+    route_table.insert("orders".to_string(), ServiceRoute {
+        addr: "127.0.0.1:1".to_string(),
+        tls: false,
+        query_path: None,
+        subscribe_path: None,
+        introspection_path: None,
+        schema_url: None,
+        websocket_path: None,
+        websocket_protocol: None,
+        connection_params: Default::default(),
+        sdl_file: Some(sdl_file.path().to_path_buf()),
+        headers: Default::default(),
+        header_policy: Default::default(),
+        user_agent: None,
+        oauth2: None,
+        signing: None,
+        enum_values: Default::default(),
+        lenient_errors: false,
+        timeout_ms: None,
+        retry_count: 0,
+        retry_backoff_ms: 0,
+        source: Default::default(),
+    });
+    shared_route_table.set_route_table(route_table);
+
+    let mut composition = shared_route_table.watch_composition();
+    let invalid_arguments = |shared_route_table: &SharedRouteTable| {
+        shared_route_table
+            .invalid_context_rules()
+            .into_iter()
+            .map(|rule| rule.argument)
+            .collect::<Vec<_>>()
+    };
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        composition.wait_for(|status| *status == CompositionStatus::Composed(1)),
+    )
+    .await
+    .expect("the schema was not composed in time")
+    .unwrap();
+    assert_eq!(invalid_arguments(&shared_route_table), vec!["user"]);
+
+    // The rules are validated again, without any request, when the schema
+    // changes.
+    std::fs::write(
+        sdl_file.path(),
+        "type Query { orders(limit: Int): [Order!]! } type Order { id: ID! }",
+    )
+    .unwrap();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        composition.wait_for(|status| *status == CompositionStatus::Composed(2)),
+    )
+    .await
+    .expect("the schema was not recomposed in time")
+    .unwrap();
+    assert_eq!(invalid_arguments(&shared_route_table), vec!["userId", "user"]);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{CorsConfig, CorsPolicy, GatewaySettings};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+#[tokio::test]
+async fn cors_policy() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .cors_config(CorsConfig {
+            allow_methods: None,
+            allow_credentials: Some(true),
+            allow_headers: Some(vec!["content-type".to_string()]),
+            allow_origins: Some(vec!["https://a.example.com".to_string()]),
+        })
+        .start()
+        .await;
+    let preflight = |origin: &'static str| {
+        reqwest::Client::new()
+            .request(reqwest::Method::OPTIONS, format!("http://{}", gateway.addr()))
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "content-type")
+            .send()
+    };
+
+    let resp = preflight("https://a.example.com").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("access-control-allow-origin").unwrap(),
+        "https://a.example.com"
+    );
+    assert_eq!(resp.headers().get("access-control-allow-credentials").unwrap(), "true");
+    assert_eq!(preflight("https://b.example.com").await.unwrap().status(), 403);
+
+    let resp = gateway
+        .post(json!({ "query": "{ me }" }), &[("origin", "https://a.example.com")])
+        .await;
+    assert_eq!(
+        resp.headers().get("access-control-allow-origin").unwrap(),
+        "https://a.example.com"
+    );
+    let resp = gateway
+        .post(json!({ "query": "{ me }" }), &[("origin", "https://b.example.com")])
+        .await;
+    assert_eq!(resp.status(), 403);
+}
+
+#[tokio::test]
+async fn reload_cors_policy() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let cors_config = |origin: &str| CorsConfig {
+        allow_methods: None,
+        allow_credentials: None,
+        allow_headers: Some(vec!["content-type".to_string()]),
+        allow_origins: Some(vec![origin.to_string()]),
+    };
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .cors_config(cors_config("https://a.example.com"))
+        .start()
+        .await;
+    let query = |origin: &'static str| {
+        let gateway = &gateway;
+        async move { gateway.post(json!({ "query": "{ me }" }), &[("origin", origin)]).await }
+    };
+
+    let resp = query("https://a.example.com").await;
+    assert_eq!(
+        resp.headers().get("access-control-allow-origin").unwrap(),
+        "https://a.example.com"
+    );
+    assert_eq!(query("https://b.example.com").await.status(), 403);
+
+    // The settings of a reloaded config file apply to the next requests.
+    gateway.shared_config().store(GatewaySettings {
+        cors: CorsPolicy::new(Some(&cors_config("https://b.example.com"))),
+        ..Default::default()
+    });
+    let resp = query("https://b.example.com").await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("access-control-allow-origin").unwrap(),
+        "https://b.example.com"
+    );
+    assert_eq!(query("https://a.example.com").await.status(), 403);
+}
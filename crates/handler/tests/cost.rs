@@ -0,0 +1,227 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    auth::{Auth, AuthConfig},
+    CostBudget,
+    CostConfig,
+    COST_LIMIT_EXCEEDED,
+};
+use graphgate_test_utils::SubgraphBuilder;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::{json, Value};
+use value::ConstValue;
+
+const PRODUCTS_SDL: &str = r#"
+    type Query {
+        products(first: Int): [Product!]! @listSize(slicingArguments: ["first"])
+        search(text: String!): [Product!]! @listSize(assumedSize: 5) @cost(weight: 10)
+    }
+    type Product @key(fields: "id") {
+        id: ID!
+        name: String!
+        price: Int! @cost(weight: "2")
+    }
+"#;
+
+fn cost_config(max_cost: u64) -> CostConfig {
+    CostConfig {
+        max_cost,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn cost_from_directives() {
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("products", |_| Ok(ConstValue::List(Vec::new())))
+        .field("search", |_| Ok(ConstValue::List(Vec::new())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&products])
+        .cost_config(cost_config(100))
+        .start()
+        .await;
+
+    // (1 + 2) * 20 = 60
+    let resp = gateway
+        .query(json!({ "query": "{ products(first: 20) { name price } }" }))
+        .await;
+    assert_eq!(resp["data"], json!({ "products": [] }));
+    assert_eq!(
+        resp["extensions"]["cost"],
+        json!({ "estimated": 60, "limit": 100, "remaining": 40 })
+    );
+
+    // (1 + 2) * 40 = 120, with the size taken from a variable.
+    let resp = gateway
+        .query(json!({
+            "query": "query($first: Int) { products(first: $first) { name price } }",
+            "variables": { "first": 40 },
+        }))
+        .await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "The estimated cost 120 of the operation exceeds the limit of 100."
+    );
+    assert_eq!(resp["errors"][0]["extensions"]["code"], COST_LIMIT_EXCEEDED);
+
+    // (10 + 2 * 3) * 5 = 80
+    let resp = gateway
+        .query(json!({ "query": "{ search(text: \"a\") { a: price b: price c: price } }" }))
+        .await;
+    assert_eq!(resp["data"], json!({ "search": [] }));
+    assert_eq!(resp["extensions"]["cost"]["estimated"], 80);
+    assert_eq!(products.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn slicing_argument_required() {
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL).spawn().await;
+    let gateway = GatewayBuilder::new(&[&products])
+        .cost_config(cost_config(1000))
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ products { name } }" })).await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "Exactly one of the arguments \"first\" must be provided to \"Query.products\"."
+    );
+    assert!(products.requests().is_empty());
+}
+
+#[tokio::test]
+async fn budgets_by_client_and_api_key() {
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("products", |_| Ok(ConstValue::List(Vec::new())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&products])
+        .cost_config(CostConfig {
+            max_cost: 100,
+            budgets: vec![
+                CostBudget {
+                    client_name: Some("mobile".to_string()),
+                    scope: None,
+                    api_keys: Vec::new(),
+                    max_cost: 30,
+                },
+                CostBudget {
+                    client_name: None,
+                    scope: None,
+                    api_keys: vec!["gold-key".to_string()],
+                    max_cost: 500,
+                },
+            ],
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    // (1 + 2) * 20 = 60
+    let body = json!({ "query": "{ products(first: 20) { name price } }" });
+    let resp = gateway.query(body.clone()).await;
+    assert_eq!(resp["extensions"]["cost"]["remaining"], 40);
+
+    let resp: Value = gateway
+        .post(body.clone(), &[("apollographql-client-name", "mobile")])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp["errors"][0]["extensions"]["code"], COST_LIMIT_EXCEEDED);
+
+    let resp: Value = gateway
+        .post(body, &[("x-api-key", "gold-key")])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp["extensions"]["cost"],
+        json!({ "estimated": 60, "limit": 500, "remaining": 440 })
+    );
+}
+
+#[tokio::test]
+async fn budgets_by_client_claim() {
+    const SECRET: &[u8] = b"secret";
+
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("products", |_| Ok(ConstValue::List(Vec::new())))
+        .spawn()
+        .await;
+    let auth = Auth::with_keys(
+        AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            ..Default::default()
+        },
+        [("key".to_string(), DecodingKey::from_secret(SECRET))].into(),
+    );
+    let gateway = GatewayBuilder::new(&[&products])
+        .auth(auth)
+        .cost_config(CostConfig {
+            max_cost: 30,
+            client_name_claim: Some("client".to_string()),
+            budgets: vec![CostBudget {
+                client_name: Some("batch".to_string()),
+                scope: None,
+                api_keys: Vec::new(),
+                max_cost: 500,
+            }],
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    // (1 + 2) * 20 = 60
+    let body = json!({ "query": "{ products(first: 20) { name price } }" });
+
+    // The client name header is ignored.
+    let resp: Value = gateway
+        .post(body.clone(), &[("apollographql-client-name", "batch")])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp["errors"][0]["extensions"]["code"], COST_LIMIT_EXCEEDED);
+
+    let header = Header {
+        kid: Some("key".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    let claims = json!({ "client": "batch", "exp": 4102444800u64 });
+    let token = jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap();
+    let resp: Value = gateway
+        .post(body, &[("authorization", &format!("Bearer {}", token))])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp["extensions"]["cost"]["limit"], 500);
+}
+
+#[tokio::test]
+async fn configured_field_weights() {
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("products", |_| Ok(ConstValue::List(Vec::new())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&products])
+        .cost_config(CostConfig {
+            max_cost: 100,
+            field_weights: [("Product.name".to_string(), 3), ("Product.price".to_string(), 1)].into(),
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    // (1 + 3 + 1) * 20 = 100, the configured weights replace `@cost`.
+    let resp = gateway
+        .query(json!({ "query": "{ products(first: 20) { name price } }" }))
+        .await;
+    assert_eq!(resp["extensions"]["cost"]["estimated"], 100);
+}
@@ -0,0 +1,260 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    verify_request_signature,
+    ConnectionParam,
+    OAuth2Config,
+    RequestSigningConfig,
+    ServiceRoute,
+    ServiceRouteTable,
+    SignatureError,
+    SubgraphRequestConfig,
+    SIGNATURE_TIMESTAMP_HEADER,
+};
+use graphgate_test_utils::SubgraphBuilder;
+use http::HeaderValue;
+use serde_json::json;
+use value::ConstValue;
+use warp::Filter;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+#[tokio::test]
+async fn static_headers() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .forward_headers(&["x-service-token"])
+        .service_headers("accounts", &[("x-service-token", "s3cret")])
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": "{ me }" }), &[("x-service-token", "forged")])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    let requests = accounts.requests();
+    let values = requests[0]
+        .headers
+        .get_all("x-service-token")
+        .iter()
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec!["s3cret"]);
+}
+
+#[tokio::test]
+async fn user_agent_and_metadata_headers() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let products = SubgraphBuilder::new("products", "type Query { topProduct: String }")
+        .field("topProduct", |_| Ok(ConstValue::String("chair".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &products])
+        .forward_headers(&["user-agent"])
+        .subgraph_request_config(SubgraphRequestConfig {
+            user_agent: Some(SubgraphRequestConfig::default_user_agent("edge")),
+            headers: [("x-gateway-region".to_string(), "eu-west-1".to_string())].into(),
+        })
+        .service_user_agent("products", "partner-gateway/1.0")
+        .service_headers("products", &[("X-Gateway-Region", "us-east-1")])
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": "{ me topProduct }" }), &[("user-agent", "curl/8.0")])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(resp, json!({ "data": { "me": "alice", "topProduct": "chair" } }));
+
+    let requests = accounts.requests();
+    let headers = &requests.last().unwrap().headers;
+    assert_eq!(
+        headers["user-agent"],
+        format!("graphgate/{} (edge)", env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(headers["x-gateway-region"], "eu-west-1");
+
+    let requests = products.requests();
+    let headers = &requests.last().unwrap().headers;
+    assert_eq!(headers["user-agent"], "partner-gateway/1.0");
+    assert_eq!(headers["x-gateway-region"], "us-east-1");
+}
+
+#[tokio::test]
+async fn oauth2_client_credentials() {
+    let token_requests = Arc::new(AtomicUsize::new(0));
+    let token_endpoint = warp::post().and(warp::body::form()).map({
+        let token_requests = token_requests.clone();
+        move |params: HashMap<String, String>| {
+            token_requests.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(params["grant_type"], "client_credentials");
+            assert_eq!(params["client_id"], "gateway");
+            assert_eq!(params["client_secret"], "secret");
+            assert_eq!(params["scope"], "accounts:read accounts:write");
+            warp::reply::json(&json!({
+                "access_token": "token-1",
+                "token_type": "Bearer",
+                "expires_in": 3600,
+            }))
+        }
+    });
+    let (token_addr, server) = warp::serve(token_endpoint).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .service_oauth2("accounts", OAuth2Config {
+            token_url: format!("http://{}/token", token_addr),
+            client_id: "gateway".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: vec!["accounts:read".to_string(), "accounts:write".to_string()],
+            audience: None,
+        })
+        .start()
+        .await;
+
+    for _ in 0..2 {
+        let resp = gateway.query(json!({ "query": "{ me }" })).await;
+        assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+    }
+
+    for request in accounts.requests() {
+        assert_eq!(request.headers["authorization"], "Bearer token-1");
+    }
+    // The token of the SDL fetch is reused.
+    assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn request_signing() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .service_signing("accounts", RequestSigningConfig {
+            key_id: "2024-06".to_string(),
+            secret: "signing-secret".to_string(),
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    // The subgraph accepts the previous and the new key during a rotation.
+    let secrets = HashMap::from([
+        ("2024-01".to_string(), "old-secret".to_string()),
+        ("2024-06".to_string(), "signing-secret".to_string()),
+    ]);
+    let max_skew = Duration::from_secs(300);
+    let request = accounts.requests().pop().unwrap();
+    assert_eq!(request.headers["x-graphgate-key-id"], "2024-06");
+    assert_eq!(
+        verify_request_signature(&request.headers, &request.body, &secrets, max_skew),
+        Ok(())
+    );
+
+    // The signature covers the canonical body, whatever its formatting.
+    let body = serde_json::to_vec_pretty(&serde_json::from_slice::<serde_json::Value>(&request.body).unwrap()).unwrap();
+    assert_eq!(
+        verify_request_signature(&request.headers, &body, &secrets, max_skew),
+        Ok(())
+    );
+    let body = String::from_utf8(request.body.to_vec()).unwrap().replace("me", "you");
+    assert_eq!(
+        verify_request_signature(&request.headers, body.as_bytes(), &secrets, max_skew),
+        Err(SignatureError::InvalidSignature)
+    );
+
+    let mut headers = request.headers.clone();
+    let timestamp = headers[SIGNATURE_TIMESTAMP_HEADER]
+        .to_str()
+        .unwrap()
+        .parse::<i64>()
+        .unwrap();
+    headers.insert(SIGNATURE_TIMESTAMP_HEADER, HeaderValue::from(timestamp - 600));
+    assert!(matches!(
+        verify_request_signature(&headers, &request.body, &secrets, max_skew),
+        Err(SignatureError::ClockSkew(_))
+    ));
+
+    let secrets = HashMap::from([("2024-01".to_string(), "old-secret".to_string())]);
+    assert_eq!(
+        verify_request_signature(&request.headers, &request.body, &secrets, max_skew),
+        Err(SignatureError::UnknownKey("2024-06".to_string()))
+    );
+}
+
+#[test]
+fn credentials_are_redacted_from_debug() {
+    let route = ServiceRoute {
+        addr: "127.0.0.1:8001".to_string(),
+        tls: false,
+        query_path: None,
+        subscribe_path: None,
+        introspection_path: None,
+        schema_url: None,
+        websocket_path: None,
+        websocket_protocol: None,
+        connection_params: [("token".to_string(), ConnectionParam::Value(json!("param-secret")))]
+            .into_iter()
+            .collect(),
+        sdl_file: None,
+        headers: [("x-service-token".to_string(), "header-secret".to_string())]
+            .into_iter()
+            .collect(),
+        header_policy: Default::default(),
+        user_agent: None,
+        oauth2: Some(OAuth2Config {
+            token_url: "http://127.0.0.1:8002/token".to_string(),
+            client_id: "gateway".to_string(),
+            client_secret: "client-secret".to_string(),
+            scopes: Vec::new(),
+            audience: None,
+        }),
+        signing: Some(RequestSigningConfig {
+            key_id: "key-1".to_string(),
+            secret: "signing-secret".to_string(),
+        }),
+        enum_values: Default::default(),
+        lenient_errors: false,
+        timeout_ms: None,
+        retry_count: 0,
+        retry_backoff_ms: 0,
+        source: Default::default(),
+    };
+    let mut route_table = ServiceRouteTable::default();
+    route_table.insert("accounts".to_string(), route);
+
+    let debug = format!("{:?}", route_table);
+    assert!(debug.contains("x-service-token"));
+    assert!(debug.contains("gateway"));
+    for secret in ["param-secret", "header-secret", "client-secret", "signing-secret"] {
+        assert!(!debug.contains(secret), "{} in {}", secret, debug);
+    }
+}
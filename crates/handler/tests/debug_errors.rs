@@ -0,0 +1,56 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+use warp::http::StatusCode;
+
+#[tokio::test]
+async fn subgraph_request_in_extensions() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { user(id: ID!): String }")
+        .field("user", |_| Err("user not found".to_string()))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).debug_errors(true).start().await;
+
+    let resp = gateway
+        .query(json!({
+            "query": "query($id: ID!) { user(id: $id) }",
+            "variables": { "id": "secret" },
+        }))
+        .await;
+    let request = &resp["errors"][0]["extensions"]["subgraphRequest"];
+    assert_eq!(request["service"], "accounts");
+    assert_eq!(request["url"], format!("http://{}", accounts.addr()));
+    assert_eq!(request["variables"], json!(["id"]));
+    assert!(request["query"].as_str().unwrap().contains("user(id: $id)"));
+    assert!(request.get("status").is_none());
+    assert!(!resp.to_string().contains("secret"));
+}
+
+#[tokio::test]
+async fn subgraph_status_in_extensions() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).debug_errors(true).start().await;
+    accounts.set_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["errors"][0]["extensions"]["subgraphRequest"]["status"], 500);
+}
+
+#[tokio::test]
+async fn disabled_by_default() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Err("unavailable".to_string()))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["errors"][0]["message"], "unavailable");
+    assert!(resp["errors"][0]["extensions"].get("subgraphRequest").is_none());
+}
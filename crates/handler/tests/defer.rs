@@ -0,0 +1,252 @@
+mod common;
+
+use std::time::Duration;
+
+use common::GatewayBuilder;
+use futures_util::{SinkExt, StreamExt};
+use graphgate_handler::DeferConfig;
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use value::ConstValue;
+use warp::http::StatusCode;
+
+const QUERY: &str = r#"{ me { username } ... @defer(label: "reviews") { topReviews { body } } }"#;
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", "type Query { me: User } type User { username: String! }")
+        .field("me", |_| {
+            Ok(ConstValue::from_json(json!({ "username": "alice" })).unwrap())
+        })
+        .spawn()
+        .await
+}
+
+/// Answers after the primary part of the queries is sent.
+async fn reviews() -> Subgraph {
+    SubgraphBuilder::new(
+        "reviews",
+        "type Query { topReviews: [Review!]! } type Review { body: String! }",
+    )
+    .field("topReviews", |_| {
+        Ok(ConstValue::from_json(json!([{ "body": "great" }])).unwrap())
+    })
+    .delay(Duration::from_millis(300))
+    .spawn()
+    .await
+}
+
+fn defer_config() -> DeferConfig {
+    DeferConfig {
+        primary_fields: Vec::new(),
+        latency_budget_ms: 0,
+    }
+}
+
+#[tokio::test]
+async fn deferred_fragment_in_multipart_response() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .defer_config(defer_config())
+        .start()
+        .await;
+
+    let body = gateway
+        .post(json!({ "query": QUERY }), &[("accept", "multipart/mixed")])
+        .await
+        .text()
+        .await
+        .unwrap();
+    let payloads = body
+        .split("\r\n---")
+        .filter_map(|part| part.split("\r\n\r\n").nth(1))
+        .map(|part| serde_json::from_str::<Value>(part.trim()).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(payloads, vec![
+        json!({ "data": { "me": { "username": "alice" } }, "hasNext": true }),
+        json!({
+            "incremental": [{ "data": { "topReviews": [{ "body": "great" }] }, "path": [], "label": "reviews" }],
+            "hasNext": false,
+        }),
+    ]);
+}
+
+#[tokio::test]
+async fn receive_headers_in_multipart_response() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: User } type User { username: String! }")
+        .field("me", |_| {
+            Ok(ConstValue::from_json(json!({ "username": "alice" })).unwrap())
+        })
+        .response_header("x-served-by", "accounts")
+        .spawn()
+        .await;
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .defer_config(defer_config())
+        .receive_headers(&["x-served-by"])
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": QUERY }), &[("accept", "multipart/mixed")])
+        .await;
+    assert_eq!(resp.headers()["x-served-by"], "accounts");
+}
+
+#[tokio::test]
+async fn server_timing_in_multipart_response() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: User } type User { username: String! }")
+        .field("me", |_| {
+            Ok(ConstValue::from_json(json!({ "username": "alice" })).unwrap())
+        })
+        .response_header("server-timing", "db;dur=5.5")
+        .spawn()
+        .await;
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .defer_config(defer_config())
+        .server_timing(true)
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": QUERY }), &[("accept", "multipart/mixed")])
+        .await;
+    // The header is sent with the initial payload, before the deferred fetch.
+    let metrics = resp.headers()["server-timing"].to_str().unwrap().to_string();
+    let metrics = metrics.split(", ").collect::<Vec<_>>();
+    assert_eq!(metrics.len(), 3);
+    assert!(metrics[0].starts_with("gateway;dur="));
+    assert!(metrics[1].starts_with("accounts;dur="));
+    assert_eq!(metrics[2], "accounts-db;dur=5.5");
+}
+
+#[tokio::test]
+async fn subgraph_status_of_multipart_response() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .defer_config(defer_config())
+        .propagate_subgraph_status(true)
+        .start()
+        .await;
+
+    accounts.set_status(StatusCode::UNAUTHORIZED);
+    let resp = gateway
+        .post(
+            json!({ "query": r#"{ ... @defer(label: "me") { me { username } } }"# }),
+            &[("accept", "multipart/mixed")],
+        )
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = gateway
+        .post(json!({ "query": QUERY }), &[("accept", "multipart/mixed")])
+        .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn warn_of_nested_deferred_fragment() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .defer_config(defer_config())
+        .start()
+        .await;
+
+    let body = gateway
+        .post(json!({ "query": "{ me { ... @defer { username } } }" }), &[(
+            "accept",
+            "multipart/mixed",
+        )])
+        .await
+        .text()
+        .await
+        .unwrap();
+    let payloads = body
+        .split("\r\n---")
+        .filter_map(|part| part.split("\r\n\r\n").nth(1))
+        .map(|part| serde_json::from_str::<Value>(part.trim()).unwrap())
+        .collect::<Vec<_>>();
+
+    // The fragment is delivered with its parent field.
+    assert_eq!(payloads, vec![json!({
+        "data": { "me": { "username": "alice" } },
+        "extensions": {
+            "warnings": [
+                r#"The "@defer" of a fragment in the field "me" is ignored, only the fragments of the root selection set are deferred."#,
+            ],
+        },
+        "hasNext": false,
+    })]);
+}
+
+#[tokio::test]
+async fn deferred_fragment_merged_without_multipart() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let resp = gateway.query(json!({ "query": QUERY })).await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": { "username": "alice" }, "topReviews": [{ "body": "great" }] } })
+    );
+}
+
+#[tokio::test]
+async fn deferred_fragment_over_websocket() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .defer_config(defer_config())
+        .start()
+        .await;
+
+    let mut request = format!("ws://{}", gateway.addr()).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "graphql-transport-ws".parse().unwrap());
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+    socket
+        .send(Message::text(json!({ "type": "connection_init" }).to_string()))
+        .await
+        .unwrap();
+    socket
+        .send(Message::text(
+            json!({ "type": "subscribe", "id": "1", "payload": { "query": QUERY } }).to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let mut messages = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(Ok(message)) = socket.next().await {
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            let done = message["type"] == "complete";
+            messages.push(message);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("the query did not complete in time");
+
+    assert_eq!(messages, vec![
+        json!({ "type": "connection_ack" }),
+        json!({
+            "type": "next",
+            "id": "1",
+            "payload": { "data": { "me": { "username": "alice" } }, "hasNext": true },
+        }),
+        json!({
+            "type": "next",
+            "id": "1",
+            "payload": {
+                "incremental": [{ "data": { "topReviews": [{ "body": "great" }] }, "path": [], "label": "reviews" }],
+                "hasNext": false,
+            },
+        }),
+        json!({ "type": "complete", "id": "1" }),
+    ]);
+}
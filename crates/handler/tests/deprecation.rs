@@ -0,0 +1,72 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::GatewayBuilder;
+use graphgate_handler::DeprecationConfig;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query {
+        username: String @deprecated(reason: "Use `name`.")
+        login: String @deprecated(reason: "Use `name`.")
+        name: String
+    }
+"#;
+
+#[tokio::test]
+async fn sunset_fields() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("username", |_| Ok(ConstValue::String("alice".to_string())))
+        .field("login", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let today = Utc::now().date_naive();
+    let mut config = DeprecationConfig {
+        grace_period_days: 30,
+        ..Default::default()
+    };
+    config
+        .sunsets
+        .insert("Query.username".to_string(), today - Duration::days(30));
+    config
+        .sunsets
+        .insert("Query.login".to_string(), today - Duration::days(10));
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .deprecation_config(config)
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ username }" })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": format!(
+                    "Field \"Query.username\" is deprecated and was sunset on {}. Use `name`.",
+                    today - Duration::days(30)
+                ),
+                "locations": [{ "line": 1, "column": 3 }],
+                "extensions": { "code": "DEPRECATED_FIELD_SUNSET" },
+            }],
+        })
+    );
+
+    let resp = gateway.query(json!({ "query": "{ login }" })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": { "login": "alice" },
+            "extensions": {
+                "warnings": [format!(
+                    "Field \"Query.login\" is deprecated and was sunset on {}, operations selecting it are rejected \
+                     from {}. Use `name`.",
+                    today - Duration::days(10),
+                    today + Duration::days(20)
+                )],
+            },
+        })
+    );
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::DocsConfig;
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use warp::http::StatusCode;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    "A registered user."
+    type User @key(fields: "id") {
+        id: ID!
+        username: String!
+        name: String @deprecated(reason: "Use <username>.")
+    }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+async fn subgraphs() -> (Subgraph, Subgraph) {
+    (
+        SubgraphBuilder::new("accounts", ACCOUNTS_SDL).spawn().await,
+        SubgraphBuilder::new("reviews", REVIEWS_SDL).spawn().await,
+    )
+}
+
+#[tokio::test]
+async fn docs() {
+    let (accounts, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .docs_config(DocsConfig {
+            enabled: true,
+            require_auth: false,
+        })
+        .start()
+        .await;
+
+    let resp = gateway.get("/docs").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+
+    let html = resp.text().await.unwrap();
+    assert!(html.contains("<section id=\"User\""));
+    assert!(html.contains("<p>A registered user.</p>"));
+    assert!(html.contains("Owned by <code>accounts</code>"));
+    assert!(html.contains("Key <code>id</code> in <code>reviews</code>"));
+    assert!(html.contains("<code>reviews</code>: <a href=\"#Review\"><code>[Review!]!</code></a>"));
+    assert!(html.contains("<p class=\"deprecated\">Deprecated: Use &lt;username&gt;.</p>"));
+    assert!(!html.contains("id=\"_Service\""));
+    assert!(!html.contains("id=\"__Type\""));
+
+    // Root types come first.
+    assert!(html.find("id=\"Query\"").unwrap() < html.find("id=\"Review\"").unwrap());
+}
+
+#[tokio::test]
+async fn docs_gated() {
+    let (accounts, reviews) = subgraphs().await;
+
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+    assert_eq!(gateway.get("/docs").await.status(), StatusCode::NOT_FOUND);
+
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .docs_config(DocsConfig {
+            enabled: true,
+            require_auth: true,
+        })
+        .start()
+        .await;
+    assert!(!gateway.get("/docs").await.status().is_success());
+}
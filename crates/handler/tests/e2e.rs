@@ -0,0 +1,371 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::GatewayBuilder;
+use futures_util::{SinkExt, StreamExt};
+use graphgate_handler::{
+    auth::{Auth, AuthConfig, AuthError, TokenHeader},
+    handler,
+    handler::{HandlerConfig, RequestError},
+    GatewaySettings,
+    SharedConfig,
+    SharedRouteTable,
+};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Query { topReviews: [Review!]! }
+    type Subscription { reviewAdded: Review! }
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+fn json_value(value: Value) -> ConstValue {
+    ConstValue::from_json(value).unwrap()
+}
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |ctx| match ctx.headers.get("x-user") {
+            Some(user) => Ok(json_value(json!({ "id": "1", "username": user.to_str().unwrap() }))),
+            None => Ok(json_value(json!({ "id": "1", "username": "alice" }))),
+        })
+        .response_header("x-served-by", "accounts")
+        .spawn()
+        .await
+}
+
+async fn reviews() -> Subgraph {
+    SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .field("topReviews", |_| Err("reviews are unavailable".to_string()))
+        .entity("User", |representation| {
+            let mut user = representation.clone();
+            if let ConstValue::Object(obj) = &mut user {
+                obj.insert(
+                    value::Name::new("reviews"),
+                    json_value(json!([{ "body": "great" }, { "body": "okay" }])),
+                );
+            }
+            Ok(user)
+        })
+        .subscription("reviewAdded", |_| {
+            vec![
+                json_value(json!({ "body": "first" })),
+                json_value(json!({ "body": "second" })),
+            ]
+        })
+        .spawn()
+        .await
+}
+
+#[tokio::test]
+async fn merge_entities() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { id username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "me": {
+                    "id": "1",
+                    "username": "alice",
+                    "reviews": [{ "body": "great" }, { "body": "okay" }]
+                }
+            }
+        })
+    );
+    assert_eq!(accounts.requests().len(), 1);
+    assert_eq!(reviews.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn forward_headers() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .forward_headers(&["x-user"])
+        .receive_headers(&["x-served-by"])
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": "{ me { username } }" }), &[
+            ("x-user", "bob"),
+            ("x-ignored", "1"),
+        ])
+        .await;
+    assert_eq!(resp.headers().get("x-served-by").unwrap(), "accounts");
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": { "me": { "username": "bob" } } })
+    );
+
+    let request = &accounts.requests()[0];
+    assert_eq!(request.headers.get("x-user").unwrap(), "bob");
+    assert!(request.headers.get("x-ignored").is_none());
+}
+
+#[tokio::test]
+async fn graphql_content_type() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("http://{}", gateway.addr()))
+        .header("content-type", "application/graphql; charset=utf-8")
+        .body("{ me { username } }")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": { "me": { "username": "alice" } } })
+    );
+
+    let resp = client
+        .post(format!("http://{}", gateway.addr()))
+        .query(&[("operationName", "B"), ("variables", r#"{"skip": true}"#)])
+        .header("content-type", "application/graphql")
+        .body("query A { me { id } } query B($skip: Boolean!) { me { id username @skip(if: $skip) } }")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": { "me": { "id": "1" } } })
+    );
+
+    let rejection = warp::test::request()
+        .method("POST")
+        .path("/?variables=oops")
+        .header("content-type", "application/graphql")
+        .body("{ me { id } }")
+        .filter(&handler::graphql_request(HandlerConfig {
+            shared_route_table: SharedRouteTable::default(),
+            shared_config: Default::default(),
+            operation_labeler: Default::default(),
+        }))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        rejection.find::<RequestError>(),
+        Some(RequestError::InvalidVariables(_))
+    ));
+}
+
+#[tokio::test]
+async fn readiness() {
+    let shared_route_table = SharedRouteTable::default();
+    assert!(!shared_route_table.wait_ready(Duration::from_millis(100)).await);
+
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    let resp = gateway.query(json!({ "query": "{ me { username } }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": { "username": "alice" } } }));
+}
+
+#[tokio::test]
+async fn subgraph_errors() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let resp = gateway.query(json!({ "query": "{ topReviews { body } }" })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{ "message": "reviews are unavailable", "path": ["topReviews"] }]
+        })
+    );
+
+    let resp = gateway.query(json!({ "query": "{ me { unknown } }" })).await;
+    assert_eq!(resp["data"], Value::Null);
+    assert_eq!(resp["errors"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn subgraph_http_errors() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    drop(accounts);
+
+    let resp = gateway.query(json!({ "query": "{ me { username } }" })).await;
+    assert_eq!(resp["data"], Value::Null);
+    assert_eq!(resp["errors"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn subscription() {
+    let (accounts, reviews) = (accounts().await, reviews().await);
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let mut request = format!("ws://{}", gateway.addr()).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "graphql-transport-ws".parse().unwrap());
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+    socket
+        .send(Message::text(json!({ "type": "connection_init" }).to_string()))
+        .await
+        .unwrap();
+    socket
+        .send(Message::text(
+            json!({
+                "type": "subscribe",
+                "id": "1",
+                "payload": { "query": "subscription { reviewAdded { body } }" }
+            })
+            .to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let mut messages = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(Ok(message)) = socket.next().await {
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            let done = message["type"] == "complete";
+            messages.push(message);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("the subscription did not complete in time");
+
+    assert_eq!(messages, vec![
+        json!({ "type": "connection_ack" }),
+        json!({ "type": "next", "id": "1", "payload": { "data": { "reviewAdded": { "body": "first" } } } }),
+        json!({ "type": "next", "id": "1", "payload": { "data": { "reviewAdded": { "body": "second" } } } }),
+        json!({ "type": "complete", "id": "1" }),
+    ]);
+}
+
+#[tokio::test]
+async fn auth_required() {
+    let auth = Arc::new(Auth {
+        config: AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            required: true,
+            jwks: String::new(),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let filter = handler::graphql_request(HandlerConfig {
+        shared_route_table: SharedRouteTable::default(),
+        shared_config: SharedConfig::new(GatewaySettings {
+            auth,
+            ..Default::default()
+        }),
+        operation_labeler: Default::default(),
+    });
+
+    for (authorization, expected) in [
+        (None, "missing authorization header"),
+        (Some("Basic abc"), "authorization prefix not found"),
+        (Some("Bearer abc"), "jwt decoding error: InvalidToken"),
+    ] {
+        let mut request = warp::test::request()
+            .method("POST")
+            .json(&json!({ "query": "{ me { id } }" }));
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        let err = request.filter(&filter).await.err().unwrap();
+        assert_eq!(err.find::<AuthError>().unwrap().to_string(), expected);
+    }
+}
+
+#[tokio::test]
+async fn auth_token_sources() {
+    let auth = Arc::new(Auth {
+        config: AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            required: true,
+            headers: vec![TokenHeader {
+                name: "authorization".to_string(),
+                prefix: "Token".to_string(),
+            }],
+            cookies: vec!["session".to_string()],
+            query_params: vec!["access_token".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let config = HandlerConfig {
+        shared_route_table: SharedRouteTable::default(),
+        shared_config: SharedConfig::new(GatewaySettings {
+            auth,
+            ..Default::default()
+        }),
+        operation_labeler: Default::default(),
+    };
+
+    // Each token reaches the validation, instead of being missing.
+    let filter = handler::graphql_request(config.clone());
+    for (header, value) in [("authorization", "Token abc"), ("cookie", "theme=dark; session=abc")] {
+        let err = warp::test::request()
+            .method("POST")
+            .header(header, value)
+            .json(&json!({ "query": "{ me { id } }" }))
+            .filter(&filter)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.find::<AuthError>().unwrap().to_string(),
+            "jwt decoding error: InvalidToken"
+        );
+    }
+
+    // Query parameters are only read from WebSocket upgrades.
+    let err = warp::test::request()
+        .method("POST")
+        .path("/?access_token=abc")
+        .json(&json!({ "query": "{ me { id } }" }))
+        .filter(&filter)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.find::<AuthError>().unwrap().to_string(),
+        "missing authorization header"
+    );
+
+    let filter = handler::graphql_websocket(config);
+    let err = warp::test::request()
+        .path("/?access_token=abc")
+        .header("connection", "upgrade")
+        .header("upgrade", "websocket")
+        .header("sec-websocket-version", "13")
+        .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .filter(&filter)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.find::<AuthError>().unwrap().to_string(),
+        "jwt decoding error: InvalidToken"
+    );
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    auth::{Auth, AuthConfig},
+    MemoryEntityCache,
+};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::json;
+use value::{value, ConstValue};
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { users(first: Int!): [User!]! }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") @cacheControl(maxAge: 60) {
+        id: ID! @external
+        reviews: [Review!]!
+        followers: Int! @cacheControl(maxAge: 0)
+        inbox: [String!]! @cacheControl(scope: PRIVATE)
+    }
+"#;
+
+async fn subgraphs() -> (Subgraph, Subgraph) {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("users", |ctx| {
+            let first = match ctx.arguments.get("first") {
+                Some(ConstValue::Number(first)) => first.as_u64().unwrap_or_default() as usize,
+                _ => 0,
+            };
+            Ok(ConstValue::List(
+                ["alice", "bob", "carol"]
+                    .iter()
+                    .enumerate()
+                    .take(first)
+                    .map(|(index, username)| value!({ "id": (index + 1).to_string(), "username": username }))
+                    .collect(),
+            ))
+        })
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |representation| {
+            let id = match representation {
+                ConstValue::Object(object) => match object.get("id") {
+                    Some(ConstValue::String(id)) => id.clone(),
+                    _ => return Err("Invalid representation.".to_string()),
+                },
+                _ => return Err("Invalid representation.".to_string()),
+            };
+            if id == "3" {
+                return Err("Reviews unavailable.".to_string());
+            }
+            Ok(value!({
+                "reviews": [{ "body": format!("review of {}", id) }],
+                "followers": 1,
+                "inbox": ["hello"],
+            }))
+        })
+        .spawn()
+        .await;
+    (accounts, reviews)
+}
+
+fn entities_requests(subgraph: &Subgraph) -> Vec<serde_json::Value> {
+    subgraph
+        .requests()
+        .into_iter()
+        .filter(|request| request.query.contains("_entities"))
+        .map(|request| request.variables.into_value().into_json().unwrap()["representations"].clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn serve_cached_entities() {
+    let (accounts, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .entity_cache(Arc::new(MemoryEntityCache::new(100)))
+        .start()
+        .await;
+    let query = json!({ "query": "{ users(first: 2) { username reviews { body } } }" });
+    let expected = json!({
+        "data": {
+            "users": [
+                { "username": "alice", "reviews": [{ "body": "review of 1" }] },
+                { "username": "bob", "reviews": [{ "body": "review of 2" }] },
+            ]
+        }
+    });
+
+    assert_eq!(gateway.query(query.clone()).await, expected);
+    assert_eq!(gateway.query(query).await, expected);
+    assert_eq!(entities_requests(&reviews).len(), 1);
+}
+
+#[tokio::test]
+async fn serve_cached_entities_in_multipart_responses() {
+    let (accounts, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .entity_cache(Arc::new(MemoryEntityCache::new(100)))
+        .start()
+        .await;
+    let query = json!({ "query": "{ users(first: 2) { username reviews { body } } }" });
+
+    for _ in 0..2 {
+        let body = gateway
+            .post(query.clone(), &[("accept", "multipart/mixed")])
+            .await
+            .text()
+            .await
+            .unwrap();
+        assert!(body.contains("review of 2"));
+    }
+    assert_eq!(entities_requests(&reviews).len(), 1);
+}
+
+#[tokio::test]
+async fn fetch_uncached_entities() {
+    let (accounts, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .entity_cache(Arc::new(MemoryEntityCache::new(100)))
+        .start()
+        .await;
+
+    gateway
+        .query(json!({ "query": "{ users(first: 1) { reviews { body } } }" }))
+        .await;
+    let resp = gateway
+        .query(json!({ "query": "{ users(first: 2) { reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "users": [
+                    { "reviews": [{ "body": "review of 1" }] },
+                    { "reviews": [{ "body": "review of 2" }] },
+                ]
+            }
+        })
+    );
+
+    // The entity that failed to resolve is not cached.
+    for _ in 0..2 {
+        let resp = gateway
+            .query(json!({ "query": "{ users(first: 3) { reviews { body } } }" }))
+            .await;
+        assert_eq!(resp["errors"][0]["message"], "Reviews unavailable.");
+    }
+    assert_eq!(entities_requests(&reviews), vec![
+        json!([{ "__typename": "User", "id": "1" }]),
+        json!([{ "__typename": "User", "id": "2" }]),
+        json!([{ "__typename": "User", "id": "3" }]),
+        json!([{ "__typename": "User", "id": "3" }]),
+    ]);
+}
+
+#[tokio::test]
+async fn respect_cache_control_hints() {
+    let (accounts, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .forward_headers(&["authorization"])
+        .entity_cache(Arc::new(MemoryEntityCache::new(100)))
+        .start()
+        .await;
+
+    // `maxAge: 0` on a selected field disables caching.
+    let query = json!({ "query": "{ users(first: 1) { followers } }" });
+    gateway.query(query.clone()).await;
+    gateway.query(query).await;
+    assert_eq!(entities_requests(&reviews).len(), 2);
+
+    // Private entities are cached per `Authorization` header.
+    let query = json!({ "query": "{ users(first: 1) { inbox } }" });
+    gateway.query(query.clone()).await;
+    gateway.query(query.clone()).await;
+    assert_eq!(entities_requests(&reviews).len(), 4);
+    for token in ["Bearer a", "Bearer a", "Bearer b"] {
+        gateway.post(query.clone(), &[("authorization", token)]).await;
+    }
+    assert_eq!(entities_requests(&reviews).len(), 6);
+}
+
+const SECRET: &[u8] = b"secret";
+
+fn authorization(claims: serde_json::Value) -> String {
+    let header = Header {
+        kid: Some("key".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    format!(
+        "Bearer {}",
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    )
+}
+
+#[tokio::test]
+async fn partition_private_entities_by_claims() {
+    let (accounts, reviews) = subgraphs().await;
+    let auth = Auth::with_keys(
+        AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            ..Default::default()
+        },
+        [("key".to_string(), DecodingKey::from_secret(SECRET))].into(),
+    );
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .auth(auth)
+        .entity_cache(Arc::new(MemoryEntityCache::new(100)))
+        .entity_cache_partition_claims(&["org.id"])
+        .entity_cache_credential_headers(&["x-api-key"])
+        .forward_headers(&["cookie", "x-api-key"])
+        .start()
+        .await;
+    let query = json!({ "query": "{ users(first: 1) { inbox } }" });
+    let alice = authorization(json!({ "sub": "alice", "org": { "id": 1 }, "exp": 4102444800u64 }));
+    let bob = authorization(json!({ "sub": "bob", "org": { "id": 1 }, "exp": 4102444800u64 }));
+    let carol = authorization(json!({ "sub": "carol", "org": { "id": 2 }, "exp": 4102444800u64 }));
+    let dave = authorization(json!({ "sub": "dave", "exp": 4102444800u64 }));
+
+    // The users of an organization share its partition.
+    for token in [&alice, &bob] {
+        gateway.post(query.clone(), &[("authorization", token)]).await;
+    }
+    assert_eq!(entities_requests(&reviews).len(), 1);
+    gateway.post(query.clone(), &[("authorization", &carol)]).await;
+    assert_eq!(entities_requests(&reviews).len(), 2);
+
+    // The anonymous requests share the public partition.
+    gateway.query(query.clone()).await;
+    gateway.query(query.clone()).await;
+    assert_eq!(entities_requests(&reviews).len(), 3);
+
+    // The tokens without the claims are not cached.
+    for _ in 0..2 {
+        gateway.post(query.clone(), &[("authorization", &dave)]).await;
+    }
+    assert_eq!(entities_requests(&reviews).len(), 5);
+
+    // Nor are the requests forwarding credentials the gateway does not
+    // verify.
+    for header in [("cookie", "session=alice"), ("x-api-key", "alice")] {
+        gateway.post(query.clone(), &[header]).await;
+    }
+    assert_eq!(entities_requests(&reviews).len(), 7);
+}
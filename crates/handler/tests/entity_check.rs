@@ -0,0 +1,65 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{EntityCheckConfig, EntityResolverError};
+use graphgate_test_utils::SubgraphBuilder;
+use value::{value, ConstValue};
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review @key(fields: "id") { id: ID! body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[tokio::test]
+async fn missing_reference_resolvers() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .entity("User", |_| Ok(ConstValue::Null))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("Review", |representation| match representation {
+            ConstValue::Object(obj) if obj.get("id") == Some(&value!("r1")) => Ok(value!({ "id": "r1" })),
+            _ => Err("Review not found.".to_string()),
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+    let shared_route_table = gateway.shared_route_table();
+
+    // Probing is disabled by default.
+    assert!(shared_route_table.check_entity_resolvers().await.is_empty());
+
+    shared_route_table.set_entity_check_config(EntityCheckConfig::default());
+    let errors = shared_route_table.check_entity_resolvers().await;
+    assert_eq!(errors, vec![
+        EntityResolverError {
+            service: "reviews".to_string(),
+            type_name: "Review".to_string(),
+            message: "Review not found.".to_string(),
+        },
+        EntityResolverError {
+            service: "reviews".to_string(),
+            type_name: "User".to_string(),
+            message: "Unknown entity type \"User\".".to_string(),
+        },
+    ]);
+    assert_eq!(shared_route_table.entity_resolver_errors(), errors);
+
+    let mut config = EntityCheckConfig::default();
+    config.samples.insert("Review".to_string(), value!({ "id": "r1" }));
+    shared_route_table.set_entity_check_config(config);
+    let errors = shared_route_table.check_entity_resolvers().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].type_name, "User");
+
+    let probes = reviews.requests();
+    assert!(probes.iter().all(|request| request.query.contains("_entities")));
+    assert!(probes.iter().any(|request| {
+        request.variables["representations"] == value!([{ "id": "graphgate-probe", "__typename": "Review" }])
+    }));
+}
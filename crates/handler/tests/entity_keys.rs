@@ -0,0 +1,109 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::ENTITY_KEY_MISMATCH;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::{value, ConstValue};
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User topUsers: [User!]! }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[tokio::test]
+async fn reject_entities_resolved_with_other_keys() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("topUsers", |_| {
+            Ok(value!([{ "id": "1", "username": "alice" }, { "id": "2", "username": "bob" }]))
+        })
+        .spawn()
+        .await;
+    // The reviews of the user "2" are resolved from a stale id.
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |representation| match representation {
+            ConstValue::Object(obj) if obj.get("id") == Some(&value!("2")) => {
+                Ok(value!({ "id": "3", "reviews": [{ "body": "Not mine" }] }))
+            },
+            _ => Ok(value!({ "id": "1", "reviews": [{ "body": "Great!" }] })),
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .check_entity_keys(true)
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ topUsers { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp["data"],
+        json!({ "topUsers": [{ "username": "alice", "reviews": [{ "body": "Great!" }] }, { "username": "bob" }] })
+    );
+    let errors = resp["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["extensions"]["code"], json!(ENTITY_KEY_MISMATCH));
+    assert_eq!(errors[0]["extensions"]["service"], json!("reviews"));
+    // The keys are requested back from the subgraph.
+    assert!(reviews.requests()[0].query.contains("id"));
+}
+
+#[tokio::test]
+async fn merge_entities_without_checking_keys() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| {
+            Ok(value!({ "id": "3", "reviews": [{ "body": "Not mine" }] }))
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": { "username": "alice", "reviews": [{ "body": "Not mine" }] } } })
+    );
+}
+
+#[tokio::test]
+async fn report_mismatched_entities_at_their_path() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("topUsers", |_| {
+            Ok(value!([{ "id": "1", "username": "alice" }, { "id": "2", "username": "bob" }]))
+        })
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |representation| match representation {
+            ConstValue::Object(obj) if obj.get("id") == Some(&value!("2")) => {
+                Ok(value!({ "id": "3", "reviews": [{ "body": "Not mine" }] }))
+            },
+            _ => Ok(value!({ "id": "1", "reviews": [{ "body": "Great!" }] })),
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .check_entity_keys(true)
+        .normalize_error_paths(true)
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ topUsers { username reviews { body } } }" }))
+        .await;
+    let errors = resp["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["path"], json!(["topUsers", 1]));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::ENTITY_MOVED_HEADER;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::value;
+use warp::http::StatusCode;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[tokio::test]
+async fn redirect_moved_entities() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .response_header(ENTITY_MOVED_HEADER, "ratings")
+        .spawn()
+        .await;
+    // The service the entities moved to, before the schema is recomposed.
+    let ratings = SubgraphBuilder::new(
+        "ratings",
+        r#"extend type User @key(fields: "id") { id: ID! @external rating: Int }"#,
+    )
+    .entity("User", |_| Ok(value!({ "reviews": [{ "body": "Great!" }] })))
+    .spawn()
+    .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews, &ratings]).start().await;
+    reviews.set_status(StatusCode::GONE);
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp["data"],
+        json!({ "me": { "username": "alice", "reviews": [{ "body": "Great!" }] } })
+    );
+    assert_eq!(
+        resp["extensions"]["warnings"],
+        json!([
+            "The entities at \"me\" moved from service \"reviews\" to service \"ratings\" and were fetched from it."
+        ])
+    );
+    let request = ratings.requests().pop().unwrap();
+    assert_eq!(
+        serde_json::to_value(&request.variables["representations"]).unwrap(),
+        json!([{ "__typename": "User", "id": "1234" }])
+    );
+}
+
+#[tokio::test]
+async fn unknown_moved_service() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .response_header(ENTITY_MOVED_HEADER, "ratings")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+    reviews.set_status(StatusCode::GONE);
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "the entities of service \"reviews\" moved to service \"ratings\""
+    );
+    assert!(resp["extensions"]["warnings"].is_null());
+}
+
+#[tokio::test]
+async fn moved_to_service_not_defining_entities() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .response_header(ENTITY_MOVED_HEADER, "products")
+        .spawn()
+        .await;
+    let products = SubgraphBuilder::new("products", "type Query { topProducts: [String!]! }")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews, &products]).start().await;
+    reviews.set_status(StatusCode::GONE);
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "the entities of service \"reviews\" moved to service \"products\""
+    );
+    assert!(products.requests().is_empty());
+}
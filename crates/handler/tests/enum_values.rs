@@ -0,0 +1,85 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+const INVENTORY_SDL: &str = r#"
+    enum Color { red green }
+    type Car { name: String! color: Color! colors: [Color!]! }
+    input CarFilter { color: Color }
+    type Query { cars(color: Color, filter: CarFilter): [Car!]! }
+"#;
+
+const ACCOUNTS_SDL: &str = r#"
+    enum Color { RED GREEN }
+    type Query { favoriteColor: Color! }
+"#;
+
+#[tokio::test]
+async fn enum_values_are_translated() {
+    let inventory = SubgraphBuilder::new("inventory", INVENTORY_SDL)
+        .field("cars", |ctx| {
+            let color = match (ctx.arguments.get("color"), ctx.arguments.get("filter")) {
+                (Some(ConstValue::Enum(color)), _) => color.to_string(),
+                (Some(ConstValue::String(color)), _) => color.clone(),
+                (_, Some(ConstValue::Object(filter))) => match filter.get("color") {
+                    Some(ConstValue::Enum(color)) => color.to_string(),
+                    other => panic!("unexpected filter color {:?}", other),
+                },
+                other => panic!("unexpected arguments {:?}", other),
+            };
+            if color != "red" {
+                return Err(format!("No car painted {}.", color));
+            }
+            Ok(
+                ConstValue::from_json(json!([{ "name": "Beetle", "color": "red", "colors": ["red", "green"] }]))
+                    .unwrap(),
+            )
+        })
+        .spawn()
+        .await;
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("favoriteColor", |_| Ok(ConstValue::Enum(value::Name::new("RED"))))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&inventory, &accounts])
+        .service_enum_values("inventory", "Color", &[("RED", "red"), ("GREEN", "green")])
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({
+            "query": "query($color: Color) { a: cars(color: $color) { name color } b: cars(filter: { color: RED }) { colors } favoriteColor }",
+            "variables": { "color": "RED" },
+        }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "a": [{ "name": "Beetle", "color": "RED" }],
+                "b": [{ "colors": ["RED", "GREEN"] }],
+                "favoriteColor": "RED",
+            },
+        })
+    );
+    let queries = inventory
+        .requests()
+        .into_iter()
+        .map(|request| request.query)
+        .collect::<Vec<_>>();
+    assert!(
+        queries.iter().any(|query| query.contains("cars(filter: {color: red})")),
+        "{:?}",
+        queries
+    );
+
+    let resp = gateway
+        .query(json!({ "query": "{ cars(color: GREEN) { name } }" }))
+        .await;
+    // The error messages are the service's own, only the enum values of the
+    // data are translated.
+    assert_eq!(resp["errors"][0]["message"], "No car painted green.");
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{CallBudgetConfig, CALL_BUDGET_EXCEEDED};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Query { ok: Boolean }
+    type Subscription { reviewAdded: Review! }
+    type Review { body: String! author: User! }
+    extend type User @key(fields: "id") { id: ID! @external }
+"#;
+
+#[tokio::test]
+async fn subscribe_over_event_stream() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .entity("User", |_| Ok(value!({ "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .subscription("reviewAdded", |_| {
+            vec![
+                value!({ "body": "Great!", "author": { "id": "1234" } }),
+                value!({ "body": "Meh.", "author": { "id": "1234" } }),
+            ]
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let resp = gateway
+        .post(
+            json!({ "query": "subscription { reviewAdded { body author { username } } }" }),
+            &[("accept", "text/event-stream")],
+        )
+        .await;
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    let event = |body: &str| {
+        format!(
+            "event: next\ndata: {}\n\n",
+            json!({ "data": { "reviewAdded": { "body": body, "author": { "username": "alice" } } } })
+        )
+    };
+    assert_eq!(
+        resp.text().await.unwrap(),
+        format!("{}{}event: complete\ndata:\n\n", event("Great!"), event("Meh."))
+    );
+}
+
+#[tokio::test]
+async fn query_without_event_stream() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234", "username": "alice" })))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    // Only subscriptions are streamed.
+    let resp = gateway
+        .post(json!({ "query": "{ me { username } }" }), &[(
+            "accept",
+            "text/event-stream",
+        )])
+        .await;
+    assert_eq!(resp.headers()["content-type"], "application/json");
+    assert_eq!(
+        resp.json::<serde_json::Value>().await.unwrap(),
+        json!({ "data": { "me": { "username": "alice" } } })
+    );
+}
+
+#[tokio::test]
+async fn subscribe_over_event_stream_with_get() {
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .subscription("reviewAdded", |_| {
+            vec![value!({ "body": "Great!", "author": { "id": "1234" } })]
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+    let url = format!(
+        "http://{}/?query=subscription%20%7B%20reviewAdded%20%7B%20body%20%7D%20%7D",
+        gateway.addr()
+    );
+
+    // As sent by `EventSource`.
+    let resp = reqwest::Client::new()
+        .get(&url)
+        .header("accept", "text/event-stream")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    assert_eq!(
+        resp.text().await.unwrap(),
+        format!(
+            "event: next\ndata: {}\n\nevent: complete\ndata:\n\n",
+            json!({ "data": { "reviewAdded": { "body": "Great!" } } })
+        )
+    );
+
+    let resp = gateway.get(&url[url.find("/?").unwrap()..]).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn limit_the_calls_of_each_event() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .entity("User", |_| Ok(value!({ "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .subscription("reviewAdded", |_| {
+            vec![
+                value!({ "body": "Great!", "author": { "id": "1234" } }),
+                value!({ "body": "Meh.", "author": { "id": "1234" } }),
+            ]
+        })
+        .spawn()
+        .await;
+    let subscribe = |max_calls| {
+        let (accounts, reviews) = (&accounts, &reviews);
+        async move {
+            let gateway = GatewayBuilder::new(&[accounts, reviews])
+                .call_budget_config(CallBudgetConfig { max_calls })
+                .start()
+                .await;
+            gateway
+                .post(
+                    json!({ "query": "subscription { reviewAdded { body author { username } } }" }),
+                    &[("accept", "text/event-stream")],
+                )
+                .await
+                .text()
+                .await
+                .unwrap()
+        }
+    };
+
+    // The subscription and the entities of the first event, then the
+    // entities of each event.
+    let events = subscribe(2).await;
+    assert_eq!(events.matches("alice").count(), 2);
+
+    let events = subscribe(1).await;
+    assert!(events.contains(CALL_BUDGET_EXCEEDED));
+    assert!(!events.contains("alice"));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::register_latency_histograms;
+use graphgate_test_utils::SubgraphBuilder;
+use opentelemetry::{global, sdk::trace::TracerProvider};
+use prometheus_client::{encoding::text::encode, registry::Registry};
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: String }
+"#;
+
+#[tokio::test]
+async fn record_latency_exemplars() {
+    global::set_tracer_provider(TracerProvider::builder().build());
+
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": "query Me { me }", "operationName": "Me" }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    let mut registry = Registry::default();
+    register_latency_histograms(&mut registry);
+    let mut encoded = String::new();
+    encode(&mut encoded, &registry).unwrap();
+
+    // The exemplar of the bucket of each observation, by series.
+    let exemplars = |metric: &str, label: &str| {
+        encoded
+            .lines()
+            .filter(|line| line.starts_with(metric) && line.contains(label))
+            .filter_map(|line| line.split_once(" # {trace_id=\"")?.1.split_once('"'))
+            .map(|(trace_id, _)| trace_id.to_string())
+            .collect::<Vec<_>>()
+    };
+    let queries = exemplars(
+        "graphgate_graphql_query_duration_seconds_bucket",
+        "graphgate_operation=\"Me\"",
+    );
+    assert_eq!(queries.len(), 1);
+    assert_eq!(queries[0].len(), 32);
+
+    // The fetch is made in the trace of the query.
+    let fetches = exemplars(
+        "graphgate_subgraph_fetch_duration_seconds_bucket",
+        "graphgate_service=\"accounts\"",
+    );
+    assert_eq!(fetches, queries);
+
+    assert!(exemplars(
+        "graphgate_subgraph_fetch_duration_seconds_bucket",
+        "graphgate_service=\"reviews\""
+    )
+    .is_empty());
+}
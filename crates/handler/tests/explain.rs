@@ -0,0 +1,106 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+fn fetch_latencies(plan: &Value) -> Vec<(String, Value)> {
+    plan["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| (node["type"].as_str().unwrap().to_string(), node["latency"].clone()))
+        .collect()
+}
+
+#[tokio::test]
+async fn explain_latencies() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| {
+            Ok(ConstValue::from_json(json!({ "id": "1", "username": "alice" })).unwrap())
+        })
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| {
+            Ok(ConstValue::from_json(json!({ "reviews": [{ "body": "great" }] })).unwrap())
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+    let query = json!({ "query": "{ me { username reviews { body } } }" });
+
+    let plan = gateway.explain(query.clone()).await;
+    assert_eq!(plan["type"], "sequence");
+    assert_eq!(fetch_latencies(&plan), vec![
+        ("fetch".to_string(), Value::Null),
+        ("flatten".to_string(), Value::Null),
+    ]);
+    assert!(accounts.requests().is_empty());
+
+    for _ in 0..3 {
+        gateway.query(query.clone()).await;
+    }
+
+    let plan = gateway.explain(query).await;
+    for (_, latency) in fetch_latencies(&plan) {
+        assert_eq!(latency["samples"], 3);
+        let p50 = latency["p50Ms"].as_f64().unwrap();
+        let p99 = latency["p99Ms"].as_f64().unwrap();
+        assert!(p50 > 0.0 && p50 <= p99);
+    }
+}
+
+#[tokio::test]
+async fn explain_graphs() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| {
+            Ok(ConstValue::from_json(json!({ "id": "1", "username": "alice" })).unwrap())
+        })
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL).spawn().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+    // The latencies are recorded by query across the tests, so this one is
+    // not explained elsewhere.
+    let query = "{ me { id username reviews { __typename body } } }";
+    let explain = |format: &str| {
+        reqwest::Client::new()
+            .post(format!("http://{}/explain", gateway.addr()))
+            .json(&json!({
+                "query": query,
+                "extensions": { "explain": { "format": format } },
+            }))
+            .send()
+    };
+
+    let resp = explain("dot").await.unwrap();
+    assert_eq!(resp.headers()["content-type"], "text/vnd.graphviz");
+    let dot = resp.text().await.unwrap();
+    assert!(dot.starts_with("digraph plan {"));
+    assert!(dot.contains(r#"[label="Fetch\naccounts", shape=box]"#));
+    assert!(dot.contains(r#"[label="Flatten\nreviews\nme", shape=box]"#));
+
+    let mermaid = explain("mermaid").await.unwrap().text().await.unwrap();
+    assert!(mermaid.starts_with("flowchart TD"));
+    assert!(mermaid.contains(r#"["Fetch<br/>accounts"]"#));
+
+    // The fetches executed since are annotated with their latencies.
+    gateway.query(json!({ "query": query })).await;
+    let dot = explain("dot").await.unwrap().text().await.unwrap();
+    assert!(dot.contains(r#"[label="Fetch\naccounts\np50 "#));
+    assert!(dot.contains("ms (1 samples)"));
+    let mermaid = explain("mermaid").await.unwrap().text().await.unwrap();
+    assert!(mermaid.contains(r#"["Fetch<br/>accounts<br/>p50 "#));
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::GatewayBuilder;
+use graphgate_handler::MemoryPersistedQueryCache;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { user(id: ID!, filter: Filter): String }
+    type Mutation { signOut: Boolean }
+    input Filter { active: Boolean, name: String }
+"#;
+
+const QUERY: &str = "query User($id: ID!, $filter: Filter) { user(id: $id, filter: $filter) }";
+
+const QUERY_HASH: &str = "fe5df7f601e0a285a1f40647625898f49799f72edf7ce09196ec67891da9798d";
+
+fn encode(params: &[(&str, &str)]) -> String {
+    serde_urlencoded::to_string(params).unwrap()
+}
+
+#[tokio::test]
+async fn canonical_url() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("user", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let first = gateway
+        .get(&format!(
+            "/?{}",
+            encode(&[
+                ("query", QUERY),
+                ("variables", r#"{"id":"1","filter":{"name":"a","active":true}}"#),
+                ("operationName", "User"),
+            ])
+        ))
+        .await;
+    let second = gateway
+        .get(&format!(
+            "/?{}",
+            encode(&[
+                ("operationName", "User"),
+                (
+                    "variables",
+                    r#"{ "filter": { "active": true, "name": "a" }, "id": "1" }"#
+                ),
+                ("query", QUERY),
+            ])
+        ))
+        .await;
+
+    let canonical_url = first.headers()["content-location"].to_str().unwrap().to_string();
+    assert_eq!(second.headers()["content-location"], canonical_url.as_str());
+    assert_eq!(
+        first.json::<Value>().await.unwrap(),
+        json!({ "data": { "user": "alice" } })
+    );
+
+    let extensions = format!(r#"{{"persistedQuery":{{"sha256Hash":"{}","version":1}}}}"#, QUERY_HASH);
+    assert_eq!(
+        canonical_url,
+        format!(
+            "/?{}",
+            encode(&[
+                ("extensions", &extensions),
+                ("operationName", "User"),
+                ("query", QUERY),
+                ("variables", r#"{"filter":{"active":true,"name":"a"},"id":"1"}"#),
+            ])
+        )
+    );
+
+    // The canonical URL is a valid request.
+    let resp = gateway.get(&canonical_url).await;
+    assert_eq!(resp.headers()["content-location"], canonical_url.as_str());
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": { "user": "alice" } })
+    );
+}
+
+#[tokio::test]
+async fn canonical_url_of_persisted_queries() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("user", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .persisted_query_cache(Arc::new(MemoryPersistedQueryCache::new(10)))
+        .start()
+        .await;
+
+    let extensions = format!(r#"{{"persistedQuery":{{"version":1,"sha256Hash":"{}"}}}}"#, QUERY_HASH);
+    let registered = gateway
+        .get(&format!(
+            "/?{}",
+            encode(&[
+                ("query", QUERY),
+                ("extensions", &extensions),
+                ("variables", r#"{"id":"1"}"#)
+            ])
+        ))
+        .await;
+    let hash_only = gateway
+        .get(&format!(
+            "/?{}",
+            encode(&[("extensions", &extensions), ("variables", r#"{"id":"1"}"#)])
+        ))
+        .await;
+
+    // The query text is left out, so both requests share the key.
+    let canonical_url = registered.headers()["content-location"].to_str().unwrap().to_string();
+    assert_eq!(hash_only.headers()["content-location"], canonical_url.as_str());
+    assert_eq!(
+        canonical_url,
+        format!(
+            "/?{}",
+            encode(&[
+                (
+                    "extensions",
+                    &format!(r#"{{"persistedQuery":{{"sha256Hash":"{}","version":1}}}}"#, QUERY_HASH)
+                ),
+                ("variables", r#"{"id":"1"}"#),
+            ])
+        )
+    );
+    assert_eq!(
+        hash_only.json::<Value>().await.unwrap(),
+        json!({ "data": { "user": "alice" } })
+    );
+}
+
+#[tokio::test]
+async fn invalid_get_requests() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("signOut", |_| Ok(ConstValue::Boolean(true)))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    for (params, message) in [
+        (
+            encode(&[("query", "mutation { signOut }")]),
+            "only queries can be sent with GET",
+        ),
+        (
+            encode(&[
+                ("query", "{ user(id: 1) }"),
+                ("extensions", r#"{"persistedQuery":{"version":1,"sha256Hash":"abc"}}"#),
+            ]),
+            "provided sha does not match query",
+        ),
+        (
+            encode(&[("extensions", r#"{"persistedQuery":{"version":1,"sha256Hash":"abc"}}"#)]),
+            "PersistedQueryNotSupported",
+        ),
+    ] {
+        let resp = gateway.get(&format!("/?{}", params)).await;
+        assert_eq!(resp.status(), 400);
+        assert!(resp.headers().get("content-location").is_none());
+        assert_eq!(
+            resp.json::<Value>().await.unwrap(),
+            json!({ "data": null, "errors": [{ "message": message }] })
+        );
+    }
+    assert!(accounts
+        .requests()
+        .iter()
+        .all(|request| !request.query.contains("signOut")));
+}
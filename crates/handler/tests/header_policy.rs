@@ -0,0 +1,71 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::HeaderPolicy;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const BILLING_SDL: &str = r#"
+    extend type User @key(fields: "id") { id: ID! @external balance: Int! }
+"#;
+
+#[tokio::test]
+async fn header_policy_per_service() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await;
+    let billing = SubgraphBuilder::new("billing", BILLING_SDL)
+        .entity("User", |_| Ok(value!({ "balance": 42 })))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &billing])
+        .forward_headers(&["authorization", "x-request-id", "x-tenant"])
+        .service_header_policy("accounts", HeaderPolicy {
+            allow: vec!["Authorization".to_string(), "x-request-id".to_string()],
+            ..Default::default()
+        })
+        .service_header_policy("billing", HeaderPolicy {
+            deny: vec!["authorization".to_string()],
+            rename: [("x-request-id".to_string(), "x-correlation-id".to_string())].into(),
+            ..Default::default()
+        })
+        .service_headers("billing", &[("x-internal-key", "billing-key")])
+        .start()
+        .await;
+
+    let resp = gateway
+        .post(json!({ "query": "{ me { username balance } }" }), &[
+            ("authorization", "Bearer token"),
+            ("x-request-id", "42"),
+            ("x-tenant", "acme"),
+        ])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": { "username": "alice", "balance": 42 } } })
+    );
+
+    let request = accounts.requests().pop().unwrap();
+    assert_eq!(request.headers["authorization"], "Bearer token");
+    assert_eq!(request.headers["x-request-id"], "42");
+    assert!(request.headers.contains_key("forwarded"));
+    assert!(!request.headers.contains_key("x-tenant"));
+    assert!(!request.headers.contains_key("x-internal-key"));
+
+    let request = billing.requests().pop().unwrap();
+    assert!(!request.headers.contains_key("authorization"));
+    assert!(!request.headers.contains_key("x-request-id"));
+    assert_eq!(request.headers["x-correlation-id"], "42");
+    assert_eq!(request.headers["x-tenant"], "acme");
+    assert_eq!(request.headers["x-internal-key"], "billing-key");
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::json;
+use value::{value, ConstValue};
+
+const MEDIA_SDL: &str = r#"
+    type Query { media: [Media!]! }
+    interface Media @key(fields: "id") { id: ID! title: String! }
+    type Book implements Media @key(fields: "id") { id: ID! title: String! author: String! }
+    type Movie implements Media @key(fields: "id") { id: ID! title: String! director: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Query { topMedia: Media }
+    type Media @key(fields: "id") @interfaceObject { id: ID! reviews: [String!]! }
+"#;
+
+async fn subgraphs() -> (Subgraph, Subgraph) {
+    let media = SubgraphBuilder::new("media", MEDIA_SDL)
+        .field("media", |_| {
+            Ok(value!([
+                { "__typename": "Book", "id": "1", "title": "Dune", "author": "Frank Herbert" },
+                { "__typename": "Movie", "id": "2", "title": "Alien", "director": "Ridley Scott" },
+            ]))
+        })
+        .entity("Media", |representation| match representation {
+            ConstValue::Object(obj) if obj.get("id") == Some(&value!("2")) => {
+                Ok(value!({ "__typename": "Movie", "id": "2", "title": "Alien" }))
+            },
+            _ => Err("Media not found.".to_string()),
+        })
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .field("topMedia", |_| Ok(value!({ "id": "2", "reviews": ["Scary."] })))
+        .entity("Media", |representation| match representation {
+            ConstValue::Object(obj) if obj.get("id") == Some(&value!("1")) => Ok(value!({ "reviews": ["Long."] })),
+            _ => Ok(value!({ "reviews": [] })),
+        })
+        .spawn()
+        .await;
+    (media, reviews)
+}
+
+#[tokio::test]
+async fn interface_object_fields_of_implementations() {
+    let (media, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&media, &reviews]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ media { __typename title reviews ... on Book { author } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "media": [
+                    { "__typename": "Book", "title": "Dune", "author": "Frank Herbert", "reviews": ["Long."] },
+                    { "__typename": "Movie", "title": "Alien", "reviews": [] },
+                ]
+            }
+        })
+    );
+
+    // The implementations are resolved as the interface.
+    let requests = reviews.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].variables.get("representations"),
+        Some(&value!([
+            { "__typename": "Media", "id": "1" },
+            { "__typename": "Media", "id": "2" },
+        ]))
+    );
+}
+
+#[tokio::test]
+async fn interface_objects_of_contributing_service() {
+    let (media, reviews) = subgraphs().await;
+    let gateway = GatewayBuilder::new(&[&media, &reviews]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ topMedia { __typename title reviews } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "topMedia": { "__typename": "Movie", "title": "Alien", "reviews": ["Scary."] } } })
+    );
+}
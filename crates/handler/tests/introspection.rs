@@ -0,0 +1,276 @@
+mod common;
+
+use common::GatewayBuilder;
+use futures_util::future::join_all;
+use graphgate_handler::{
+    CostBudget,
+    CostConfig,
+    IntrospectionConfig,
+    ResponseLimitConfig,
+    INTROSPECTION_DISABLED,
+    RESPONSE_TOO_LARGE,
+};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+const SCHEMA_QUERY: &str = "query Schema { __schema { queryType { name } types { name } } }";
+
+#[tokio::test]
+async fn cache_concurrent_introspection() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let responses = join_all((0..8).map(|_| gateway.query(json!({ "query": SCHEMA_QUERY })))).await;
+    assert_eq!(
+        responses[0]["data"]["__schema"]["queryType"],
+        json!({ "name": "Query" })
+    );
+    assert!(responses.iter().all(|resp| *resp == responses[0]));
+
+    let resp = gateway
+        .query(json!({ "query": "{ __type(name: \"Query\") { fields { name } } }" }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "__type": { "fields": [{ "name": "me" }] } } }));
+}
+
+#[tokio::test]
+async fn respond_to_cached_introspection_per_request() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .cost_config(CostConfig {
+            max_cost: 100,
+            budgets: vec![CostBudget {
+                client_name: None,
+                scope: None,
+                api_keys: vec!["gold-key".to_string()],
+                max_cost: 500,
+            }],
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    // The cached response has the extensions of each request.
+    let resp = gateway.query(json!({ "query": SCHEMA_QUERY })).await;
+    assert_eq!(resp["extensions"]["cost"]["limit"], 100);
+    let resp: serde_json::Value = gateway
+        .post(json!({ "query": SCHEMA_QUERY }), &[("x-api-key", "gold-key")])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(resp["data"]["__schema"]["queryType"], json!({ "name": "Query" }));
+    assert_eq!(resp["extensions"]["cost"]["limit"], 500);
+
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .response_limit_config(ResponseLimitConfig {
+            max_bytes: 64,
+            ..Default::default()
+        })
+        .start()
+        .await;
+    let resp = gateway.query(json!({ "query": SCHEMA_QUERY })).await;
+    assert_eq!(resp["errors"][0]["extensions"]["code"], RESPONSE_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn rate_limit_introspection() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .introspection_config(IntrospectionConfig {
+            max_per_second: 2,
+            cache_size: 32,
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    for _ in 0..2 {
+        let resp = gateway.query(json!({ "query": SCHEMA_QUERY })).await;
+        assert!(resp.get("errors").is_none(), "{}", resp);
+    }
+    let resp = gateway.query(json!({ "query": SCHEMA_QUERY })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "Introspection is limited to 2 operations per second.",
+                "extensions": { "code": "INTROSPECTION_RATE_LIMITED" },
+            }],
+        })
+    );
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+}
+
+#[tokio::test]
+async fn disable_introspection() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .disable_introspection(true)
+        .start()
+        .await;
+
+    for query in [SCHEMA_QUERY, "{ me __type(name: \"Query\") { name } }"] {
+        let resp = gateway.query(json!({ "query": query })).await;
+        assert_eq!(
+            resp,
+            json!({
+                "data": null,
+                "errors": [{
+                    "message": "Introspection is disabled.",
+                    "extensions": { "code": INTROSPECTION_DISABLED },
+                }],
+            })
+        );
+    }
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+}
+
+#[tokio::test]
+async fn hide_inaccessible_and_tagged_fields() {
+    let accounts = SubgraphBuilder::new(
+        "accounts",
+        r#"
+        type Query {
+            me: User
+            audit: AuditLog @tag(name: "internal")
+        }
+        type User {
+            name: String
+            ssn: String @inaccessible
+            flags: Flags
+        }
+        type Flags @tag(name: "internal") {
+            beta: Boolean
+        }
+        type AuditLog {
+            entries: [String!]!
+        }
+        "#,
+    )
+    .field("me", |_| {
+        Ok(ConstValue::from_json(json!({ "name": "alice", "ssn": "123" })).unwrap())
+    })
+    .spawn()
+    .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .introspection_config(IntrospectionConfig {
+            allowlist: true,
+            hidden_tags: vec!["internal".to_string()],
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ __type(name: \"User\") { fields { name } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "__type": { "fields": [{ "name": "name" }] } } })
+    );
+
+    let resp = gateway
+        .query(json!({ "query": "{ __type(name: \"Flags\") { name } }" }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "__type": null } }));
+
+    let resp = gateway
+        .query(json!({ "query": "{ __schema { queryType { fields { name } } types { name } } }" }))
+        .await;
+    assert_eq!(
+        resp["data"]["__schema"]["queryType"],
+        json!({ "fields": [{ "name": "me" }] })
+    );
+    let types = resp["data"]["__schema"]["types"].as_array().unwrap();
+    assert!(types.contains(&json!({ "name": "User" })));
+    assert!(!types.contains(&json!({ "name": "Flags" })));
+
+    // The hidden fields can still be queried, along with introspection.
+    let resp = gateway
+        .query(json!({ "query": "{ me { name ssn } __type(name: \"User\") { fields { name } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "me": { "name": "alice", "ssn": "123" },
+                "__type": { "fields": [{ "name": "name" }] },
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn hide_inaccessible_and_tagged_values() {
+    let accounts = SubgraphBuilder::new(
+        "accounts",
+        r#"
+        type Query {
+            users(filter: UserFilter, trace: Boolean @tag(name: "internal")): [String!]!
+        }
+        input UserFilter {
+            name: String
+            ssn: String @inaccessible
+            debug: Boolean @tag(name: "internal")
+            role: Role
+        }
+        enum Role {
+            ADMIN
+            STAFF @inaccessible
+            ROOT @tag(name: "internal")
+        }
+        "#,
+    )
+    .field("users", |_| Ok(ConstValue::List(Vec::new())))
+    .spawn()
+    .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .introspection_config(IntrospectionConfig {
+            allowlist: true,
+            hidden_tags: vec!["internal".to_string()],
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({
+            "query": r#"{
+                query: __type(name: "Query") { fields { name args { name } } }
+                filter: __type(name: "UserFilter") { inputFields { name } }
+                role: __type(name: "Role") { enumValues { name } }
+            }"#
+        }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "query": { "fields": [{ "name": "users", "args": [{ "name": "filter" }] }] },
+                "filter": { "inputFields": [{ "name": "name" }, { "name": "role" }] },
+                "role": { "enumValues": [{ "name": "ADMIN" }] },
+            }
+        })
+    );
+}
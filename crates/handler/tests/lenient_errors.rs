@@ -0,0 +1,60 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+const PRODUCTS_SDL: &str = "type Query { topProduct: String }";
+
+async fn query_legacy_service(body: Value, lenient_errors: bool) -> Value {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .raw_response(body)
+        .spawn()
+        .await;
+    let mut builder = GatewayBuilder::new(&[&accounts, &products]);
+    if lenient_errors {
+        builder = builder.service_lenient_errors("products");
+    }
+    let gateway = builder.start().await;
+    gateway.query(json!({ "query": "{ me topProduct }" })).await
+}
+
+#[tokio::test]
+async fn error_object() {
+    let resp = query_legacy_service(
+        json!({ "data": null, "errors": { "message": "catalog offline", "code": 503 } }),
+        true,
+    )
+    .await;
+    assert_eq!(resp["data"], json!({ "me": "alice" }));
+    assert_eq!(resp["errors"][0]["message"], "catalog offline");
+}
+
+#[tokio::test]
+async fn error_strings() {
+    let resp = query_legacy_service(json!({ "errors": ["catalog offline"] }), true).await;
+    assert_eq!(resp["data"], json!({ "me": "alice" }));
+    assert_eq!(resp["errors"][0]["message"], "catalog offline");
+
+    let resp = query_legacy_service(json!({ "error": "catalog offline" }), true).await;
+    assert_eq!(resp["data"], json!({ "me": "alice" }));
+    assert_eq!(resp["errors"][0]["message"], "catalog offline");
+}
+
+#[tokio::test]
+async fn error_object_without_flag() {
+    let resp = query_legacy_service(
+        json!({ "data": null, "errors": { "message": "catalog offline" } }),
+        false,
+    )
+    .await;
+    assert_eq!(resp["data"], json!({ "me": "alice" }));
+    assert_ne!(resp["errors"][0]["message"], "catalog offline");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::GatewayBuilder;
+use graphgate_test_utils::{ResolverContext, SubgraphBuilder};
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: String }
+    type Mutation { createUser(username: String!): String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Query { topReview: String }
+    type Mutation { createReview(body: String!): String! }
+"#;
+
+fn argument(ctx: &ResolverContext<'_>, name: &str) -> String {
+    match ctx.arguments.get(name) {
+        Some(ConstValue::String(value)) => value.clone(),
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn execute_root_mutations_across_services_in_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("createUser", {
+            let log = log.clone();
+            move |ctx| {
+                let username = argument(ctx, "username");
+                log.lock().unwrap().push(username.clone());
+                Ok(ConstValue::String(username))
+            }
+        })
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .field("createReview", {
+            let log = log.clone();
+            move |ctx| {
+                let body = argument(ctx, "body");
+                log.lock().unwrap().push(body.clone());
+                Ok(ConstValue::String(body))
+            }
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    let resp = gateway
+        .query(json!({
+            "query": r#"
+                mutation {
+                    m1: createUser(username: "alice")
+                    m2: createReview(body: "great")
+                    m3: createUser(username: "bob")
+                    m4: createReview(body: "awful")
+                }
+            "#,
+        }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "m1": "alice", "m2": "great", "m3": "bob", "m4": "awful" } })
+    );
+    assert_eq!(*log.lock().unwrap(), vec!["alice", "great", "bob", "awful"]);
+    assert_eq!(accounts.requests().len(), 2);
+    assert_eq!(reviews.requests().len(), 2);
+}
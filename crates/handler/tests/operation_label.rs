@@ -0,0 +1,41 @@
+use graphgate_handler::{OperationLabelConfig, OperationLabelMode, OperationLabeler};
+
+#[test]
+fn limit_operation_labels() {
+    let labeler = OperationLabeler::new(OperationLabelConfig {
+        mode: OperationLabelMode::Limit,
+        limit: 2,
+        ..Default::default()
+    });
+
+    assert_eq!(labeler.label(Some("A")), "A");
+    assert_eq!(labeler.label(Some("B")), "B");
+    assert_eq!(labeler.label(Some("C")), "other");
+    assert_eq!(labeler.label(Some("A")), "A");
+    assert_eq!(labeler.label(None), "anonymous");
+}
+
+#[test]
+fn allowlist_operation_labels() {
+    let labeler = OperationLabeler::new(OperationLabelConfig {
+        mode: OperationLabelMode::Allowlist,
+        allowlist: vec!["GetUser".to_string()],
+        ..Default::default()
+    });
+
+    assert_eq!(labeler.label(Some("GetUser")), "GetUser");
+    assert_eq!(labeler.label(Some("GetUser_1a2b3c")), "other");
+    assert_eq!(labeler.label(Some("")), "anonymous");
+}
+
+#[test]
+fn hash_operation_labels() {
+    let labeler = OperationLabeler::new(OperationLabelConfig {
+        mode: OperationLabelMode::Hash,
+        ..Default::default()
+    });
+
+    assert_eq!(labeler.label(Some("GetUser")), labeler.label(Some("GetUser")));
+    assert_ne!(labeler.label(Some("GetUser")), labeler.label(Some("GetUsers")));
+    assert_eq!(labeler.label(Some("GetUser")).len(), 16);
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use std::time::Duration;
+
+use common::GatewayBuilder;
+use futures_util::{SinkExt, StreamExt};
+use graphgate_handler::{CostConfig, OperationLimitsConfig, COST_LIMIT_EXCEEDED, OPERATION_LIMIT_EXCEEDED};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use value::ConstValue;
+
+const SDL: &str = r#"
+    type Query {
+        me: User
+    }
+    type User {
+        name: String!
+        friends: [User!]!
+    }
+"#;
+
+#[tokio::test]
+async fn reject_operations_over_limits() {
+    let accounts = SubgraphBuilder::new("accounts", SDL)
+        .field("me", |_| Ok(ConstValue::Null))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .operation_limits_config(OperationLimitsConfig {
+            max_depth: 3,
+            max_aliases: 2,
+            max_root_fields: 2,
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me { friends { name } } }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": null } }));
+
+    // The fields of spread fragments count at the depth of the spread.
+    let resp = gateway
+        .query(json!({
+            "query": "{ me { ...Friends } } fragment Friends on User { friends { friends { name } } }"
+        }))
+        .await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "The depth 4 of the operation exceeds the limit of 3."
+    );
+    assert_eq!(resp["errors"][0]["extensions"]["code"], OPERATION_LIMIT_EXCEEDED);
+
+    let resp = gateway
+        .query(json!({ "query": "{ a: me { name } b: me { name } c: me { name } }" }))
+        .await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "The number of aliases 3 of the operation exceeds the limit of 2."
+    );
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { name } a: me { name } __typename }" }))
+        .await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "The number of root fields 3 of the operation exceeds the limit of 2."
+    );
+
+    // Only the first operation was sent to the subgraph.
+    assert_eq!(accounts.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn measure_repeated_fragment_spreads_once() {
+    let accounts = SubgraphBuilder::new("accounts", SDL)
+        .field("me", |_| Ok(ConstValue::Null))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .operation_limits_config(OperationLimitsConfig {
+            max_depth: 3,
+            max_aliases: 0,
+            max_root_fields: 0,
+        })
+        .start()
+        .await;
+
+    // Each fragment spreads the next one twice, 2^64 expanded selections.
+    let mut query = "{ me { ...F0 } }".to_string();
+    for idx in 0..64 {
+        query.push_str(&format!(
+            " fragment F{} on User {{ ...F{} ...F{} }}",
+            idx,
+            idx + 1,
+            idx + 1
+        ));
+    }
+    query.push_str(" fragment F64 on User { friends { friends { name } } }");
+    let resp = tokio::time::timeout(Duration::from_secs(10), gateway.query(json!({ "query": query })))
+        .await
+        .expect("the operation was not measured in time");
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "The depth 4 of the operation exceeds the limit of 3."
+    );
+}
+
+#[tokio::test]
+async fn check_nested_fragment_spreads_once() {
+    const SDL: &str = r#"
+        type Query {
+            me: User
+        }
+        type User {
+            name: String!
+            secret: String! @authenticated
+            friends: [User!]!
+        }
+    "#;
+    let accounts = SubgraphBuilder::new("accounts", SDL)
+        .field("me", |_| Ok(ConstValue::Null))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .operation_limits_config(OperationLimitsConfig {
+            max_depth: 10,
+            max_aliases: 0,
+            max_root_fields: 0,
+        })
+        .cost_config(CostConfig {
+            max_cost: 0,
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    // Each fragment spreads the next one twice, 2^30 expanded selections
+    // measured, authorized and estimated.
+    let mut query = "{ me { ...F0 } }".to_string();
+    for idx in 0..30 {
+        query.push_str(&format!(
+            " fragment F{} on User {{ ...F{} ...F{} }}",
+            idx,
+            idx + 1,
+            idx + 1
+        ));
+    }
+    query.push_str(" fragment F30 on User { secret friends { name } }");
+    let resp = tokio::time::timeout(Duration::from_secs(10), gateway.query(json!({ "query": query })))
+        .await
+        .expect("the operation was not checked in time");
+    // 1 + 2^30 * 10, the unauthorized field is removed first.
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "The estimated cost 10737418241 of the operation exceeds the limit of 0."
+    );
+    assert_eq!(resp["errors"][0]["extensions"]["code"], COST_LIMIT_EXCEEDED);
+    assert!(accounts.requests().is_empty());
+}
+
+#[tokio::test]
+async fn reject_websocket_operations_over_limits() {
+    let accounts = SubgraphBuilder::new("accounts", SDL)
+        .field("me", |_| Ok(ConstValue::Null))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .operation_limits_config(OperationLimitsConfig {
+            max_depth: 3,
+            max_aliases: 0,
+            max_root_fields: 0,
+        })
+        .start()
+        .await;
+
+    let mut request = format!("ws://{}", gateway.addr()).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "graphql-transport-ws".parse().unwrap());
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    for message in [
+        json!({ "type": "connection_init" }),
+        json!({
+            "type": "subscribe",
+            "id": "1",
+            "payload": { "query": "{ me { friends { friends { name } } } }" }
+        }),
+    ] {
+        socket.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    let mut replies = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(Ok(message)) = socket.next().await {
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            let done = message["type"] == "complete";
+            replies.push(message);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("the replies did not arrive in time");
+    assert_eq!(
+        replies[1]["payload"]["errors"][0]["extensions"]["code"],
+        OPERATION_LIMIT_EXCEEDED
+    );
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String version: Int }";
+
+const DOCUMENT: &str = "query Me { me } query Version { version }";
+
+#[tokio::test]
+async fn select_operation_of_document() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .field("version", |_| Ok(ConstValue::Number(2.into())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": DOCUMENT, "operationName": "Version" }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "version": 2 } }));
+
+    let resp = gateway.query(json!({ "query": DOCUMENT, "operationName": "Me" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    let resp = gateway.query(json!({ "query": "query Me { me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+}
+
+#[tokio::test]
+async fn unknown_or_missing_operation_name() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL).spawn().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    for (request, message) in [
+        (
+            json!({ "query": DOCUMENT }),
+            r#"An operation name is required, the document defines "Me", "Version"."#,
+        ),
+        (
+            json!({ "query": DOCUMENT, "operationName": "Missing" }),
+            r#"Unknown operation named "Missing", the document defines "Me", "Version"."#,
+        ),
+        (
+            json!({ "query": "{ me }", "operationName": "Me" }),
+            r#"Unknown operation named "Me", the document defines an anonymous operation."#,
+        ),
+    ] {
+        let resp = gateway.post(request, &[]).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.json::<Value>().await.unwrap(),
+            json!({ "data": null, "errors": [{ "message": message }] })
+        );
+    }
+    assert!(accounts
+        .requests()
+        .iter()
+        .all(|request| request.query.contains("_service")));
+}
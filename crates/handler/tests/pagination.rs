@@ -0,0 +1,61 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::PaginationConfig;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+const PRODUCTS_SDL: &str = r#"
+    type Query { topProducts(first: Int): [Product!]! product: Product }
+    type Product { upc: String! variants(limit: Int): [String!]! tags: [String!]! }
+"#;
+
+#[tokio::test]
+async fn default_limits() {
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("topProducts", |_| {
+            Ok(ConstValue::from_json(json!([{ "upc": "1", "variants": ["a"], "tags": [] }])).unwrap())
+        })
+        .field("product", |_| {
+            Ok(ConstValue::from_json(json!({ "upc": "1", "variants": ["a"], "tags": [] })).unwrap())
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&products])
+        .pagination_config(PaginationConfig {
+            nested_limit: Some(10),
+            ..Default::default()
+        })
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ topProducts { upc variants tags } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": { "topProducts": [{ "upc": "1", "variants": ["a"], "tags": [] }] },
+            "extensions": {
+                "warnings": [
+                    "The default limit first: 100 was applied to \"Query.topProducts\".",
+                    "The default limit limit: 10 was applied to \"Product.variants\".",
+                ]
+            }
+        })
+    );
+    let query = &products.requests()[0].query;
+    assert!(query.contains("topProducts(first: 100)"), "{}", query);
+    assert!(query.contains("variants(limit: 10)"), "{}", query);
+
+    let resp = gateway
+        .query(json!({ "query": "{ topProducts(first: 5) { upc } product { variants } }" }))
+        .await;
+    assert_eq!(
+        resp["extensions"],
+        json!({ "warnings": ["The default limit limit: 100 was applied to \"Product.variants\"."] })
+    );
+    let query = &products.requests()[1].query;
+    assert!(query.contains("topProducts(first: 5)"), "{}", query);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::GatewayBuilder;
+use graphgate_handler::{CacheStats, EntityCache, INTERNAL_SERVER_ERROR};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use value::{value, ConstValue};
+use warp::http::StatusCode;
+
+/// An entity cache panicking on every lookup.
+struct PanickingCache;
+
+#[async_trait::async_trait]
+impl EntityCache for PanickingCache {
+    async fn get(&self, _keys: &[String]) -> anyhow::Result<Vec<Option<ConstValue>>> {
+        panic!("the entity cache panicked");
+    }
+
+    async fn insert(&self, _key: &str, _entity: &ConstValue, _max_age: Duration) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn evict(&self, _key: &str) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
+    fn stats(&self, _top: usize) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+#[tokio::test]
+async fn panics_are_isolated() {
+    graphgate_handler::install_panic_hook();
+    let accounts = SubgraphBuilder::new(
+        "accounts",
+        r#"type Query { me: User } type User @key(fields: "id") { id: ID! }"#,
+    )
+    .field("me", |_| Ok(value!({ "id": "1234" })))
+    .spawn()
+    .await;
+    let reviews = SubgraphBuilder::new(
+        "reviews",
+        r#"extend type User @key(fields: "id") { id: ID! @external name: String }"#,
+    )
+    .entity("User", |_| Ok(value!({ "name": "alice" })))
+    .spawn()
+    .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .entity_cache(Arc::new(PanickingCache))
+        .start()
+        .await;
+
+    let resp = gateway.post(json!({ "query": "{ me { name } }" }), &[]).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let resp: Value = resp.json().await.unwrap();
+    assert_eq!(resp["errors"][0]["message"], "Internal server error.");
+    assert_eq!(resp["errors"][0]["extensions"]["code"], INTERNAL_SERVER_ERROR);
+    assert!(resp["errors"][0]["extensions"]["correlationId"].is_string());
+
+    let resp = gateway.query(json!({ "query": "{ me { id } }" })).await;
+    assert_eq!(resp["data"], json!({ "me": { "id": "1234" } }));
+}
+
+#[tokio::test]
+async fn unset_introspection_variables_are_null() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let resp = gateway
+        .query(json!({ "query": "query($name: String) { __type(name: $name) { name } }" }))
+        .await;
+    assert_eq!(resp["data"], json!({ "__type": null }));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{parse_manifest, InvalidPersistedOperation};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+#[test]
+fn manifest_formats() {
+    let operations = parse_manifest(
+        r#"{
+            "format": "apollo-persisted-query-manifest",
+            "version": 1,
+            "operations": [
+                { "id": "a1", "name": "Me", "type": "query", "body": "query Me { me { id } }" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(operations.len(), 1);
+    assert_eq!(operations[0].id, "a1");
+    assert_eq!(operations[0].name.as_deref(), Some("Me"));
+    assert_eq!(operations[0].metadata.forward_headers, None);
+
+    let operations = parse_manifest(r#"{ "b2": "{ me { id } }" }"#).unwrap();
+    assert_eq!(operations.len(), 1);
+    assert_eq!(operations[0].id, "b2");
+    assert_eq!(operations[0].body, "{ me { id } }");
+}
+
+#[tokio::test]
+async fn invalid_persisted_operations() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL).spawn().await;
+    let operations = parse_manifest(
+        r#"{
+            "valid": "query Me { me { id username } }",
+            "removed": "query Me { me { id email } }",
+            "syntax": "query Me { me {",
+            "unknown-name": "query A { me { id } } query B { me { username } }"
+        }"#,
+    )
+    .unwrap();
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .persisted_operations(operations)
+        .start()
+        .await;
+
+    let invalid = gateway.shared_route_table().invalid_persisted_operations();
+    let ids = invalid
+        .iter()
+        .map(|operation| operation.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["removed", "syntax", "unknown-name"]);
+    assert_eq!(invalid[0], InvalidPersistedOperation {
+        id: "removed".to_string(),
+        errors: vec!["Unknown field \"email\" on type \"User\".".to_string()],
+    });
+    assert!(accounts.requests().is_empty());
+}
+
+#[tokio::test]
+async fn forward_headers_of_operations() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await;
+    let operations = parse_manifest(
+        r#"{
+            "operations": [
+                {
+                    "id": "a1",
+                    "body": "query Account { me { id username } }",
+                    "metadata": { "forwardHeaders": ["authorization", "x-request-id"] }
+                },
+                { "id": "a2", "body": "query Id { me { id } }" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        operations[0].metadata.forward_headers,
+        Some(vec!["authorization".to_string(), "x-request-id".to_string()])
+    );
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .forward_headers(&["x-request-id"])
+        .persisted_operations(operations)
+        .start()
+        .await;
+    let headers = [("authorization", "Bearer token"), ("x-request-id", "42")];
+
+    for query in [
+        "query Account { me { id username } }",
+        "query Id { me { id } }",
+        "{ me { username } }",
+    ] {
+        let resp = gateway.post(json!({ "query": query }), &headers).await;
+        assert!(resp.status().is_success());
+    }
+
+    let requests = accounts.requests();
+    assert_eq!(requests[0].headers["authorization"], "Bearer token");
+    assert_eq!(requests[0].headers["x-request-id"], "42");
+    // The other operations forward the headers of the gateway.
+    for request in &requests[1..] {
+        assert!(request.headers.get("authorization").is_none());
+        assert_eq!(request.headers["x-request-id"], "42");
+    }
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::GatewayBuilder;
+use graphgate_handler::{MemoryPersistedQueryCache, PersistedQueryCache};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+const QUERY: &str = "{ me }";
+
+fn sha256(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+fn extensions(hash: &str) -> Value {
+    json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } })
+}
+
+#[tokio::test]
+async fn register_and_execute_persisted_queries() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .persisted_query_cache(Arc::new(MemoryPersistedQueryCache::new(10)))
+        .start()
+        .await;
+    let hash = sha256(QUERY);
+
+    let resp = gateway.post(json!({ "extensions": extensions(&hash) }), &[]).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "PersistedQueryNotFound",
+                "extensions": { "code": "PERSISTED_QUERY_NOT_FOUND" },
+            }],
+        })
+    );
+
+    let resp = gateway
+        .query(json!({ "query": QUERY, "extensions": extensions(&hash) }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    let resp = gateway.query(json!({ "extensions": extensions(&hash) })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    let params = serde_urlencoded::to_string([("extensions", extensions(&hash).to_string())]).unwrap();
+    let resp = gateway.get(&format!("/?{}", params)).await;
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": { "me": "alice" } })
+    );
+
+    let resp = gateway
+        .post(json!({ "query": QUERY, "extensions": extensions("abc") }), &[])
+        .await;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": null, "errors": [{ "message": "provided sha does not match query" }] })
+    );
+}
+
+#[tokio::test]
+async fn persisted_queries_not_supported() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL).spawn().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let resp = gateway
+        .post(json!({ "extensions": extensions(&sha256(QUERY)) }), &[])
+        .await;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(
+        resp.json::<Value>().await.unwrap(),
+        json!({ "data": null, "errors": [{ "message": "PersistedQueryNotSupported" }] })
+    );
+}
+
+#[tokio::test]
+async fn evict_least_recently_used_queries() {
+    let cache = MemoryPersistedQueryCache::new(2);
+    cache.insert("a", "{ a }").await.unwrap();
+    cache.insert("b", "{ b }").await.unwrap();
+    assert_eq!(cache.get("a").await.unwrap().as_deref(), Some("{ a }"));
+
+    cache.insert("c", "{ c }").await.unwrap();
+    assert_eq!(cache.get("b").await.unwrap(), None);
+    assert_eq!(cache.get("a").await.unwrap().as_deref(), Some("{ a }"));
+    assert_eq!(cache.get("c").await.unwrap().as_deref(), Some("{ c }"));
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use graphgate_handler::{CompositionStatus, PollingConfig, ServiceRoute, ServiceRouteTable, SharedRouteTable};
+use graphgate_test_utils::SubgraphBuilder;
+use warp::http::StatusCode;
+
+#[tokio::test]
+async fn retry_failed_polls() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .spawn()
+        .await;
+
+    let shared_route_table = SharedRouteTable::default();
+    shared_route_table.set_polling_config(PollingConfig {
+        interval_secs: 3600,
+        retry_secs: 1,
+        max_backoff_secs: 1,
+    });
+    let mut route_table = ServiceRouteTable::default();
+    route_table.insert("accounts".to_string(), ServiceRoute {
+        addr: accounts.addr().to_string(),
+        tls: false,
+        query_path: None,
+        subscribe_path: None,
+        introspection_path: None,
+        schema_url: None,
+        websocket_path: None,
+        websocket_protocol: None,
+        connection_params: Default::default(),
+        sdl_file: None,
+        headers: Default::default(),
+        header_policy: Default::default(),
+        user_agent: None,
+        oauth2: None,
+        signing: None,
+        enum_values: Default::default(),
+        lenient_errors: false,
+        timeout_ms: None,
+        retry_count: 0,
+        retry_backoff_ms: 0,
+        source: Default::default(),
+    });
+    shared_route_table.set_route_table(route_table);
+
+    let mut composition = shared_route_table.watch_composition();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        composition.wait_for(|status| matches!(status, CompositionStatus::Failed(_))),
+    )
+    .await
+    .expect("the poll did not fail in time")
+    .unwrap();
+
+    // The failed polls are retried long before the next scheduled poll.
+    accounts.set_status(StatusCode::OK);
+    assert!(
+        shared_route_table.wait_ready(Duration::from_secs(10)).await,
+        "the schema was not composed in time"
+    );
+    assert_eq!(*composition.borrow(), CompositionStatus::Composed(1));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::SUBGRAPH_RATE_LIMITED;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+use warp::http::StatusCode;
+
+#[tokio::test]
+async fn subgraph_rate_limited() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .response_header("retry-after", "30")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+    accounts.set_status(StatusCode::TOO_MANY_REQUESTS);
+
+    let expected = json!({
+        "data": null,
+        "errors": [{
+            "message": "service \"accounts\" is rate limited, retry after 30 seconds",
+            "extensions": { "code": SUBGRAPH_RATE_LIMITED, "service": "accounts", "retryAfter": 30 },
+        }],
+    });
+    assert_eq!(gateway.query(json!({ "query": "{ me }" })).await, expected);
+    assert_eq!(accounts.requests().len(), 1);
+
+    // Requests are shed until the backoff expires.
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["errors"][0]["extensions"]["code"], SUBGRAPH_RATE_LIMITED);
+    assert_eq!(accounts.requests().len(), 1);
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{
+    auth::{Auth, AuthConfig},
+    RedactionAction,
+    RedactionRule,
+};
+use graphgate_schema::ComposedSchema;
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User nodes: [Node!]! }
+    interface Node { id: ID! }
+    type User implements Node { id: ID! email: String! ssn: String phone: String }
+    type Org implements Node { id: ID! email: String! }
+"#;
+
+const SECRET: &[u8] = b"secret";
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| {
+            Ok(value!({ "id": "1", "email": "alice@example.com", "ssn": "078-05-1120", "phone": "555-0100" }))
+        })
+        .field("nodes", |_| {
+            Ok(value!([
+                { "__typename": "User", "id": "1", "email": "alice@example.com" },
+                { "__typename": "Org", "id": "2", "email": "sales@example.com" },
+            ]))
+        })
+        .spawn()
+        .await
+}
+
+fn rule(field: &str, action: RedactionAction) -> RedactionRule {
+    RedactionRule {
+        field: field.to_string(),
+        action,
+        allow_scopes: vec!["pii:read".to_string()],
+    }
+}
+
+fn auth() -> Auth {
+    Auth::with_keys(
+        AuthConfig {
+            enabled: true,
+            header_name: "authorization".to_string(),
+            header_prefix: "Bearer".to_string(),
+            ..Default::default()
+        },
+        [("key".to_string(), DecodingKey::from_secret(SECRET))].into(),
+    )
+}
+
+fn token(scope: &str) -> String {
+    let header = Header {
+        kid: Some("key".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    let claims = json!({ "sub": "alice", "scope": scope, "exp": 4102444800u64 });
+    jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+}
+
+#[tokio::test]
+async fn redact_fields() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .redaction_rules(vec![
+            rule("User.email", RedactionAction::Mask),
+            rule("User.ssn", RedactionAction::Remove),
+            rule("User.phone", RedactionAction::Hash),
+        ])
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { id email contact: email ssn phone } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "me": {
+                    "id": "1",
+                    "email": "****",
+                    "contact": "****",
+                    "phone": format!("{:x}", Sha256::digest(b"555-0100")),
+                }
+            }
+        })
+    );
+
+    // The subgraph still receives the redacted fields.
+    let requests = accounts.requests();
+    assert!(requests[0].query.contains("ssn"));
+}
+
+#[tokio::test]
+async fn redact_fields_of_runtime_types() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .redaction_rules(vec![rule("User.email", RedactionAction::Mask)])
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({
+            "query": r#"
+                { nodes { __typename ...UserEmail ... on Org { email } } }
+                fragment UserEmail on User { email }
+            "#
+        }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": {
+                "nodes": [
+                    { "__typename": "User", "email": "****" },
+                    { "__typename": "Org", "email": "sales@example.com" },
+                ]
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn allow_scopes() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .auth(auth())
+        .redaction_rules(vec![rule("User.email", RedactionAction::Remove)])
+        .start()
+        .await;
+    let query = json!({ "query": "{ me { id email } }" });
+
+    let resp = gateway
+        .post(query.clone(), &[(
+            "authorization",
+            &format!("Bearer {}", token("profile pii:read")),
+        )])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": { "id": "1", "email": "alice@example.com" } } })
+    );
+
+    let resp = gateway
+        .post(query.clone(), &[(
+            "authorization",
+            &format!("Bearer {}", token("profile")),
+        )])
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(resp, json!({ "data": { "me": { "id": "1" } } }));
+
+    let resp = gateway.query(query).await;
+    assert_eq!(resp, json!({ "data": { "me": { "id": "1" } } }));
+}
+
+#[test]
+fn validate_rules() {
+    let schema = ComposedSchema::parse(ACCOUNTS_SDL).unwrap();
+    assert!(rule("User.email", RedactionAction::Mask).validate(&schema).is_ok());
+    assert!(rule("Node.id", RedactionAction::Hash).validate(&schema).is_ok());
+    assert!(rule("Query.me", RedactionAction::Remove).validate(&schema).is_ok());
+    assert_eq!(
+        rule("Query.me", RedactionAction::Hash).validate(&schema),
+        Err(
+            "The redacted field \"Query.me\" of type \"User\" cannot be redacted with \"hash\", only String and ID \
+             fields can."
+                .to_string()
+        )
+    );
+    assert_eq!(
+        rule("User.name", RedactionAction::Mask).validate(&schema),
+        Err("Unknown redacted field \"User.name\".".to_string())
+    );
+}
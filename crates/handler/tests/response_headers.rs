@@ -0,0 +1,48 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+#[tokio::test]
+async fn response_headers_in_debug_extensions() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .response_header("x-cache", "HIT")
+        .response_header("x-internal", "secret")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .trace_response_headers(&["X-Cache", "server-timing"])
+        .debug_errors(true)
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["data"], json!({ "me": "alice" }));
+    assert_eq!(
+        resp["extensions"]["subgraphResponseHeaders"],
+        json!([{ "service": "accounts", "headers": { "x-cache": ["HIT"] } }])
+    );
+
+    // The headers of the responses to other requests are not explained.
+    let plan = gateway.explain(json!({ "query": "{ me }" })).await;
+    assert!(plan.get("responseHeaders").is_none());
+}
+
+#[tokio::test]
+async fn response_headers_require_debug_errors() {
+    let profiles = SubgraphBuilder::new("profiles", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .response_header("x-cache", "MISS")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&profiles])
+        .trace_response_headers(&["x-cache"])
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+}
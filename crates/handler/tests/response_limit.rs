@@ -0,0 +1,79 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::ResponseLimitConfig;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+const PRODUCTS_SDL: &str = "type Query { topProducts: String }";
+
+fn text(len: usize) -> ConstValue {
+    ConstValue::String("x".repeat(len))
+}
+
+#[tokio::test]
+async fn subgraph_response_too_large() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(text(2000)))
+        .spawn()
+        .await;
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("topProducts", |_| Ok(text(10)))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &products])
+        .response_limit_config(ResponseLimitConfig {
+            max_bytes: 0,
+            max_subgraph_bytes: 1000,
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me topProducts }" })).await;
+    assert_eq!(resp["data"], json!({ "topProducts": "xxxxxxxxxx" }));
+    assert_eq!(
+        resp["errors"][0]["message"],
+        "response of service \"accounts\" exceeds the limit of 1000 bytes"
+    );
+    assert_eq!(
+        resp["errors"][0]["extensions"],
+        json!({ "code": "RESPONSE_TOO_LARGE", "service": "accounts" })
+    );
+}
+
+#[tokio::test]
+async fn merged_response_too_large() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(text(600)))
+        .spawn()
+        .await;
+    let products = SubgraphBuilder::new("products", PRODUCTS_SDL)
+        .field("topProducts", |_| Ok(text(600)))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &products])
+        .response_limit_config(ResponseLimitConfig {
+            max_bytes: 1000,
+            max_subgraph_bytes: 0,
+        })
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["data"]["me"].as_str().map(str::len), Some(600));
+
+    let resp = gateway.query(json!({ "query": "{ me topProducts }" })).await;
+    assert_eq!(
+        resp,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "The response exceeds the limit of 1000 bytes.",
+                "extensions": { "code": "RESPONSE_TOO_LARGE" },
+            }],
+        })
+    );
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::time::Duration;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+use warp::http::StatusCode;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: String }
+    type Mutation { logout: Boolean }
+"#;
+
+#[tokio::test]
+async fn retry_failed_queries() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .field("logout", |_| Ok(ConstValue::Boolean(true)))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .service_retries("accounts", 2, 1)
+        .start()
+        .await;
+    accounts.set_status(StatusCode::SERVICE_UNAVAILABLE);
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(
+        resp["errors"][0]["message"],
+        json!("service \"accounts\" responded with status 503")
+    );
+    assert_eq!(accounts.requests().len(), 3);
+
+    // Mutations are never retried.
+    let resp = gateway.query(json!({ "query": "mutation { logout }" })).await;
+    assert_eq!(resp["errors"].as_array().unwrap().len(), 1);
+    assert_eq!(accounts.requests().len(), 4);
+
+    // Client errors are not retried.
+    accounts.set_status(StatusCode::FORBIDDEN);
+    gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(accounts.requests().len(), 5);
+}
+
+#[tokio::test]
+async fn time_out_slow_services() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .delay(Duration::from_millis(500))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .service_timeout("accounts", 50)
+        .service_retries("accounts", 1, 1)
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp["data"], json!(null));
+    assert_eq!(
+        resp["errors"][0]["message"],
+        json!("service \"accounts\" did not respond within 50 ms")
+    );
+    assert_eq!(accounts.requests().len(), 2);
+}
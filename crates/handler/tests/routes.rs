@@ -0,0 +1,27 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{RouteHealth, RouteSource};
+use graphgate_test_utils::SubgraphBuilder;
+use sha2::{Digest, Sha256};
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+#[tokio::test]
+async fn list_discovered_routes() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL).spawn().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let routes = gateway.shared_route_table().routes().await;
+    assert_eq!(routes.len(), 1);
+    let route = &routes[0];
+    assert_eq!(route.service, "accounts");
+    assert_eq!(route.url, format!("http://{}", accounts.addr()));
+    assert_eq!(route.websocket_url, format!("ws://{}", accounts.addr()));
+    assert_eq!(route.source, RouteSource::Config);
+    assert_eq!(route.health, RouteHealth::Healthy);
+    assert_eq!(
+        route.sdl_hash.as_deref(),
+        Some(format!("{:x}", Sha256::digest(ACCOUNTS_SDL.as_bytes()))).as_deref()
+    );
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::time::Duration;
+
+use common::GatewayBuilder;
+use futures_util::{SinkExt, StreamExt};
+use graphgate_handler::{Safelist, OPERATION_NOT_SAFELISTED};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: String version: String }
+"#;
+
+#[test]
+fn manifest_formats() {
+    let safelist = Safelist::parse(
+        r#"{
+            "format": "apollo-persisted-query-manifest",
+            "version": 1,
+            "operations": [
+                { "id": "a1", "name": "Me", "type": "query", "body": "query Me { me }" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(safelist.len(), 1);
+    assert!(safelist.contains("query Me { me }"));
+    assert!(!safelist.contains("query Me { version }"));
+
+    let hash = format!("{:x}", Sha256::digest(b"{ me }"));
+    let safelist = Safelist::parse(&format!("# The mobile app.\n\n{}\n", hash.to_uppercase())).unwrap();
+    assert_eq!(safelist.len(), 1);
+    assert!(safelist.contains("{ me }"));
+
+    let err = Safelist::parse(&format!("{}\nnot-a-hash\n", hash)).unwrap_err();
+    assert_eq!(err.to_string(), "Line 2 is not a SHA-256 hash.");
+}
+
+#[tokio::test]
+async fn reject_operations_not_in_safelist() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .field("version", |_| Ok(ConstValue::String("1.0".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .safelist(Safelist::parse(r#"{ "me": "{ me }" }"#).unwrap())
+        .start()
+        .await;
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "alice" } }));
+
+    let resp = gateway.post(json!({ "query": "{ version }" }), &[]).await;
+    assert_eq!(resp.status(), 403);
+    let resp = resp.json::<Value>().await.unwrap();
+    assert_eq!(resp["data"], Value::Null);
+    assert_eq!(resp["errors"][0]["extensions"]["code"], OPERATION_NOT_SAFELISTED);
+    assert_eq!(accounts.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn reject_websocket_operations_not_in_safelist() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .field("version", |_| Ok(ConstValue::String("1.0".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .safelist(Safelist::parse(r#"{ "me": "{ me }" }"#).unwrap())
+        .start()
+        .await;
+
+    let mut request = format!("ws://{}", gateway.addr()).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "graphql-transport-ws".parse().unwrap());
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    for message in [
+        json!({ "type": "connection_init" }),
+        json!({ "type": "subscribe", "id": "1", "payload": { "query": "{ version }" } }),
+        json!({ "type": "subscribe", "id": "2", "payload": { "query": "{ me }" } }),
+    ] {
+        socket.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    let mut replies = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(Ok(message)) = socket.next().await {
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            let done = message["type"] == "complete";
+            replies.push(message);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("the replies did not arrive in time");
+    assert_eq!(replies[1]["type"], "error");
+    assert_eq!(replies[1]["id"], "1");
+    assert_eq!(replies[1]["payload"][0]["extensions"]["code"], OPERATION_NOT_SAFELISTED);
+    assert_eq!(
+        replies[2],
+        json!({ "type": "next", "id": "2", "payload": { "data": { "me": "alice" } } })
+    );
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+#[tokio::test]
+async fn compose_from_schema_url() {
+    let registry = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .spawn()
+        .await;
+    let stub = SubgraphBuilder::new("accounts", "type Query { ok: Boolean }")
+        .field("me", |_| Ok(ConstValue::String("stub".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&stub])
+        .service_schema_url("accounts", &format!("http://{}", registry.addr()))
+        .start()
+        .await;
+
+    // The schema is composed from the SDL at the schema URL, while traffic
+    // goes to the address of the service.
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": "stub" } }));
+    assert!(registry.requests().is_empty());
+}
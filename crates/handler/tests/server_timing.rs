@@ -0,0 +1,46 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+#[tokio::test]
+async fn server_timing_aggregates_subgraph_metrics() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .response_header("server-timing", "db;dur=5.5, cache;desc=HIT")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .receive_headers(&["server-timing"])
+        .server_timing(true)
+        .start()
+        .await;
+
+    let resp = gateway.post(json!({ "query": "{ me }" }), &[]).await;
+    let values = resp
+        .headers()
+        .get_all("server-timing")
+        .iter()
+        .map(|value| value.to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(values.len(), 1);
+    let metrics = values[0].split(", ").collect::<Vec<_>>();
+    assert!(metrics[0].starts_with("gateway;dur="));
+    assert!(metrics[1].starts_with("accounts;dur="));
+    assert_eq!(&metrics[2..], ["accounts-db;dur=5.5", "accounts-cache;desc=\"HIT\""]);
+}
+
+#[tokio::test]
+async fn server_timing_disabled() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .response_header("server-timing", "db;dur=5.5")
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    let resp = gateway.post(json!({ "query": "{ me }" }), &[]).await;
+    assert!(resp.headers().get("server-timing").is_none());
+}
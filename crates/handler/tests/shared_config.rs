@@ -0,0 +1,34 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::GatewaySettings;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::ConstValue;
+
+#[tokio::test]
+async fn reload_forward_headers() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .forward_headers(&["x-user"])
+        .start()
+        .await;
+
+    let query = json!({ "query": "{ me }" });
+    let headers = [("x-user", "bob"), ("x-tenant", "acme")];
+    gateway.post(query.clone(), &headers).await;
+    gateway.shared_config().store(GatewaySettings {
+        forward_headers: vec!["x-tenant".to_string()],
+        ..Default::default()
+    });
+    gateway.post(query, &headers).await;
+
+    let requests = accounts.requests();
+    assert_eq!(requests[0].headers.get("x-user").unwrap(), "bob");
+    assert!(requests[0].headers.get("x-tenant").is_none());
+    assert!(requests[1].headers.get("x-user").is_none());
+    assert_eq!(requests[1].headers.get("x-tenant").unwrap(), "acme");
+}
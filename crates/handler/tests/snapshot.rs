@@ -0,0 +1,44 @@
+mod common;
+
+use std::time::Duration;
+
+use common::GatewayBuilder;
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use tempfile::TempDir;
+use value::ConstValue;
+
+const ACCOUNTS_SDL: &str = "type Query { me: String }";
+
+#[tokio::test]
+async fn serve_snapshot_until_subgraphs_are_reachable() {
+    let dir = TempDir::new().unwrap();
+    let snapshot_path = dir.path().join("snapshot.json");
+
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .snapshot_path(snapshot_path.clone())
+        .start()
+        .await;
+    assert!(snapshot_path.exists());
+    drop(gateway);
+
+    // The restarted gateway cannot fetch the SDL of the stopped subgraph.
+    let builder = GatewayBuilder::new(&[&accounts]).snapshot_path(snapshot_path.clone());
+    drop(accounts);
+    let gateway = builder.start().await;
+
+    // The route table update does not drop the schema of the snapshot.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(gateway.shared_route_table().is_ready());
+    let resp = gateway
+        .query(json!({ "query": "{ __type(name: \"Query\") { fields { name } } }" }))
+        .await;
+    assert_eq!(resp, json!({ "data": { "__type": { "fields": [{ "name": "me" }] } } }));
+
+    let resp = gateway.query(json!({ "query": "{ me }" })).await;
+    assert!(resp["errors"].is_array(), "{}", resp);
+}
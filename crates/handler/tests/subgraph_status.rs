@@ -0,0 +1,112 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{BAD_GATEWAY, FORBIDDEN, UNAUTHENTICATED};
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::json;
+use value::value;
+use warp::http::StatusCode;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Query { topReviews: [Review!]! }
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+async fn accounts() -> Subgraph {
+    SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .raw_response(json!({ "error": "stack trace of the accounts service" }))
+        .spawn()
+        .await
+}
+
+async fn reviews() -> Subgraph {
+    SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .field("topReviews", |_| Ok(value!([{ "body": "Great!" }])))
+        .spawn()
+        .await
+}
+
+#[tokio::test]
+async fn map_subgraph_status_to_error_codes() {
+    let accounts = accounts().await;
+    let gateway = GatewayBuilder::new(&[&accounts]).start().await;
+
+    for (status, code) in [
+        (StatusCode::UNAUTHORIZED, UNAUTHENTICATED),
+        (StatusCode::FORBIDDEN, FORBIDDEN),
+        (StatusCode::SERVICE_UNAVAILABLE, BAD_GATEWAY),
+    ] {
+        accounts.set_status(status);
+        let resp = gateway.post(json!({ "query": "{ me { username } }" }), &[]).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(
+            resp["errors"],
+            json!([{
+                "message": format!("service \"accounts\" responded with status {}", status.as_u16()),
+                "extensions": { "code": code, "service": "accounts" },
+            }])
+        );
+        // The body of the subgraph response is not returned to the client.
+        assert!(!resp.to_string().contains("stack trace"));
+    }
+}
+
+#[tokio::test]
+async fn propagate_status_of_whole_query_failures() {
+    let accounts = accounts().await;
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .propagate_subgraph_status(true)
+        .start()
+        .await;
+
+    for (status, expected) in [
+        (StatusCode::UNAUTHORIZED, StatusCode::UNAUTHORIZED),
+        (StatusCode::FORBIDDEN, StatusCode::FORBIDDEN),
+        (StatusCode::INTERNAL_SERVER_ERROR, StatusCode::BAD_GATEWAY),
+    ] {
+        accounts.set_status(status);
+        let resp = gateway.post(json!({ "query": "{ me { username } }" }), &[]).await;
+        assert_eq!(resp.status(), expected);
+    }
+
+    // The status of partial failures is kept.
+    accounts.set_status(StatusCode::UNAUTHORIZED);
+    let resp = gateway
+        .post(json!({ "query": "{ me { username } topReviews { body } }" }), &[])
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(resp["data"], json!({ "topReviews": [{ "body": "Great!" }] }));
+    assert_eq!(resp["errors"][0]["extensions"]["code"], json!(UNAUTHENTICATED));
+}
+
+#[tokio::test]
+async fn ignore_codes_of_subgraph_errors() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .raw_response(json!({
+            "data": null,
+            "errors": [{ "message": "Not signed in.", "extensions": { "code": UNAUTHENTICATED } }],
+        }))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts])
+        .propagate_subgraph_status(true)
+        .start()
+        .await;
+
+    // Only the failures of the fetches set the status, not the codes the
+    // subgraphs chose.
+    let resp = gateway.post(json!({ "query": "{ me { username } }" }), &[]).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(resp["errors"][0]["extensions"]["code"], json!(UNAUTHENTICATED));
+}
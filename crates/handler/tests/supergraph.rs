@@ -0,0 +1,126 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{RouteSource, SubgraphRequestConfig};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use tempfile::NamedTempFile;
+use value::ConstValue;
+
+const SUPERGRAPH: &str = r#"
+    schema @link(url: "https://specs.apollo.dev/join/v0.3", for: EXECUTION) { query: Query }
+
+    scalar join__FieldSet
+
+    enum join__Graph {
+      ACCOUNTS @join__graph(name: "accounts", url: "http://ACCOUNTS_ADDR/graphql")
+      REVIEWS @join__graph(name: "reviews", url: "http://REVIEWS_ADDR")
+    }
+
+    type Query @join__type(graph: ACCOUNTS) @join__type(graph: REVIEWS) {
+      me: User @join__field(graph: ACCOUNTS)
+    }
+
+    type Review @join__type(graph: REVIEWS) {
+      body: String!
+    }
+
+    type User @join__type(graph: ACCOUNTS, key: "id") @join__type(graph: REVIEWS, key: "id") {
+      id: ID!
+      username: String! @join__field(graph: ACCOUNTS)
+      reviews: [Review!]! @join__field(graph: REVIEWS)
+    }
+"#;
+
+#[tokio::test]
+async fn serve_supergraph() {
+    let accounts = SubgraphBuilder::new(
+        "accounts",
+        "type Query { me: User } type User @key(fields: \"id\") { id: ID! username: String! }",
+    )
+    .field("me", |_| {
+        Ok(ConstValue::from_json(json!({ "id": "1", "username": "alice" })).unwrap())
+    })
+    .spawn()
+    .await;
+    let reviews = SubgraphBuilder::new(
+        "reviews",
+        "type Review { body: String! } extend type User @key(fields: \"id\") { id: ID! @external reviews: [Review!]! }",
+    )
+    .entity("User", |representation| {
+        let mut user = representation.clone();
+        if let ConstValue::Object(obj) = &mut user {
+            obj.insert(
+                value::Name::new("reviews"),
+                ConstValue::from_json(json!([{ "body": "great" }])).unwrap(),
+            );
+        }
+        Ok(user)
+    })
+    .spawn()
+    .await;
+    let supergraph = NamedTempFile::new().unwrap();
+    let sdl = SUPERGRAPH
+        .replace("ACCOUNTS_ADDR", &accounts.addr().to_string())
+        .replace("REVIEWS_ADDR", &reviews.addr().to_string());
+    std::fs::write(supergraph.path(), sdl).unwrap();
+
+    let gateway = GatewayBuilder::new(&[])
+        .supergraph(supergraph.path().to_path_buf())
+        .start()
+        .await;
+    let resp = gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": { "username": "alice", "reviews": [{ "body": "great" }] } } })
+    );
+
+    // Each subgraph received a single fetch of the query.
+    assert_eq!(accounts.requests().len(), 1);
+    assert_eq!(reviews.requests().len(), 1);
+
+    let routes = gateway.shared_route_table().routes().await;
+    assert_eq!(routes.len(), 2);
+    assert!(routes.iter().all(|route| route.source == RouteSource::Supergraph));
+    assert_eq!(routes[0].url, format!("http://{}/graphql", accounts.addr()));
+}
+
+#[tokio::test]
+async fn supergraph_routes_with_request_config() {
+    let accounts = SubgraphBuilder::new(
+        "accounts",
+        "type Query { me: User } type User @key(fields: \"id\") { id: ID! username: String! }",
+    )
+    .field("me", |_| {
+        Ok(ConstValue::from_json(json!({ "id": "1", "username": "alice" })).unwrap())
+    })
+    .spawn()
+    .await;
+    let supergraph = NamedTempFile::new().unwrap();
+    let sdl = SUPERGRAPH
+        .replace("ACCOUNTS_ADDR", &accounts.addr().to_string())
+        .replace("REVIEWS_ADDR", "127.0.0.1:1");
+    std::fs::write(supergraph.path(), sdl).unwrap();
+
+    // The supergraph is loaded by the update loop, after the configuration.
+    let gateway = GatewayBuilder::new(&[])
+        .subgraph_request_config(SubgraphRequestConfig {
+            user_agent: Some(SubgraphRequestConfig::default_user_agent("edge")),
+            headers: [("x-gateway-region".to_string(), "eu-west-1".to_string())].into(),
+        })
+        .supergraph(supergraph.path().to_path_buf())
+        .start()
+        .await;
+    let resp = gateway.query(json!({ "query": "{ me { username } }" })).await;
+    assert_eq!(resp, json!({ "data": { "me": { "username": "alice" } } }));
+
+    let requests = accounts.requests();
+    let headers = &requests.last().unwrap().headers;
+    assert_eq!(
+        headers["user-agent"],
+        format!("graphgate/{} (edge)", env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(headers["x-gateway-region"], "eu-west-1");
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::GatewayBuilder;
+use futures_util::future::BoxFuture;
+use graphgate_test_utils::SubgraphBuilder;
+use opentelemetry::{
+    global,
+    sdk::{
+        export::trace::{ExportResult, SpanData, SpanExporter},
+        trace::TracerProvider,
+    },
+    trace::SpanKind,
+};
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Subscription { reviewAdded: Review! }
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[derive(Debug, Clone, Default)]
+struct CollectingExporter(Arc<Mutex<Vec<SpanData>>>);
+
+impl SpanExporter for CollectingExporter {
+    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        self.0.lock().unwrap().extend(batch);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn propagate_trace_context() {
+    let exporter = CollectingExporter::default();
+    let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+    global::set_tracer_provider(provider.clone());
+
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| Ok(value!({ "reviews": [{ "body": "Great!" }] })))
+        .subscription("reviewAdded", |ctx| {
+            let traceparent = ctx.headers.get("traceparent").map(|value| value.to_str().unwrap());
+            vec![value!({ "body": traceparent.unwrap_or_default() })]
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+
+    gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+
+    provider.force_flush();
+    let spans = exporter.0.lock().unwrap().clone();
+    let span = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("no span named '{}'", name))
+    };
+
+    let fetch = span("fetch [accounts]");
+    let flatten = span("flatten [reviews]");
+    for (subgraph, parent, name) in [
+        (&accounts, fetch, "query [accounts]"),
+        (&reviews, flatten, "query [reviews]"),
+    ] {
+        let request = subgraph.requests().pop().unwrap();
+        let child = span(name);
+        assert_eq!(child.span_kind, SpanKind::Client);
+        assert_eq!(child.parent_span_id, parent.span_context.span_id());
+        assert_eq!(child.span_context.trace_id(), parent.span_context.trace_id());
+        assert_eq!(
+            request.headers["traceparent"],
+            format!(
+                "00-{}-{}-01",
+                child.span_context.trace_id(),
+                child.span_context.span_id()
+            )
+        );
+    }
+
+    // The handshake of the WebSocket connection of a subscription carries
+    // the span of the subscription.
+    let resp = gateway
+        .post(json!({ "query": "subscription { reviewAdded { body } }" }), &[(
+            "accept",
+            "text/event-stream",
+        )])
+        .await;
+    let body = resp.text().await.unwrap();
+    provider.force_flush();
+    let spans = exporter.0.lock().unwrap().clone();
+    let subscription = spans.iter().find(|span| span.name == "subscription [reviews]").unwrap();
+    assert_eq!(subscription.span_kind, SpanKind::Client);
+    let traceparent = format!(
+        "00-{}-{}-01",
+        subscription.span_context.trace_id(),
+        subscription.span_context.span_id()
+    );
+    assert!(body.contains(&traceparent), "{}", body);
+}
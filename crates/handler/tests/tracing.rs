@@ -0,0 +1,112 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::GatewayBuilder;
+use futures_util::future::BoxFuture;
+use graphgate_executor::constants::*;
+use graphgate_test_utils::SubgraphBuilder;
+use opentelemetry::{
+    global,
+    sdk::{
+        export::trace::{ExportResult, SpanData, SpanExporter},
+        trace::TracerProvider,
+    },
+    trace::SpanKind,
+    Key,
+    Value,
+};
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[derive(Debug, Clone, Default)]
+struct CollectingExporter(Arc<Mutex<Vec<SpanData>>>);
+
+impl SpanExporter for CollectingExporter {
+    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        self.0.lock().unwrap().extend(batch);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn span_per_plan_node() {
+    let exporter = CollectingExporter::default();
+    let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+    global::set_tracer_provider(provider.clone());
+
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1234", "username": "alice" })))
+        .response_header("x-cache", "HIT")
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| {
+            Ok(value!({ "reviews": [{ "body": "A highly effective form of birth control." }] }))
+        })
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews])
+        .trace_response_headers(&["x-cache"])
+        .start()
+        .await;
+
+    let resp = gateway
+        .query(json!({ "query": "{ me { username reviews { body } } }" }))
+        .await;
+    assert_eq!(
+        resp,
+        json!({ "data": { "me": {
+            "username": "alice",
+            "reviews": [{ "body": "A highly effective form of birth control." }],
+        } } })
+    );
+
+    provider.force_flush();
+    let spans = exporter.0.lock().unwrap().clone();
+    let span = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("no span named '{}'", name))
+    };
+
+    let sequence = span("sequence");
+    let fetch = span("fetch [accounts]");
+    let flatten = span("flatten [reviews]");
+    assert_eq!(fetch.parent_span_id, sequence.span_context.span_id());
+    assert_eq!(flatten.parent_span_id, sequence.span_context.span_id());
+
+    assert_eq!(flatten.attributes.get(&KEY_ENTITY_TYPE), Some(&Value::from("User")));
+    assert_eq!(flatten.attributes.get(&KEY_REPRESENTATIONS), Some(&Value::I64(1)));
+    // The traced response headers are attributes of the span of the
+    // subgraph request.
+    let request = spans
+        .iter()
+        .find(|span| span.span_kind == SpanKind::Client && span.name.ends_with("[accounts]"))
+        .expect("no span of the request to accounts");
+    assert_eq!(request.parent_span_id, fetch.span_context.span_id());
+    assert_eq!(
+        request.attributes.get(&Key::new("graphgate.response_header.x-cache")),
+        Some(&Value::from("HIT"))
+    );
+
+    for span in [fetch, flatten] {
+        for key in [KEY_REQUEST_BYTES, KEY_RESPONSE_BYTES] {
+            match span.attributes.get(&key) {
+                Some(Value::I64(bytes)) => assert!(*bytes > 0),
+                value => panic!("unexpected {} of '{}': {:?}", key.as_str(), span.name, value),
+            }
+        }
+    }
+}
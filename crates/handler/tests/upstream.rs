@@ -0,0 +1,60 @@
+mod common;
+
+use common::GatewayBuilder;
+use graphgate_handler::{ServiceUpstreamConfig, UpstreamConfig};
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::json;
+use value::value;
+
+const ACCOUNTS_SDL: &str = r#"
+    type Query { me: User }
+    type User @key(fields: "id") { id: ID! username: String! }
+"#;
+
+const REVIEWS_SDL: &str = r#"
+    type Review { body: String! }
+    extend type User @key(fields: "id") { id: ID! @external reviews: [Review!]! }
+"#;
+
+#[tokio::test]
+async fn fetch_over_http2() {
+    let accounts = SubgraphBuilder::new("accounts", ACCOUNTS_SDL)
+        .field("me", |_| Ok(value!({ "id": "1", "username": "alice" })))
+        .spawn()
+        .await;
+    let reviews = SubgraphBuilder::new("reviews", REVIEWS_SDL)
+        .entity("User", |_| Ok(value!({ "reviews": [{ "body": "Great!" }] })))
+        .spawn()
+        .await;
+    let gateway = GatewayBuilder::new(&[&accounts, &reviews]).start().await;
+    // Only the reviews are fetched over HTTP/2, without TLS.
+    gateway.shared_route_table().set_upstream_config(UpstreamConfig {
+        services: [("reviews".to_string(), ServiceUpstreamConfig {
+            http2: Some(true),
+            ..Default::default()
+        })]
+        .into(),
+        ..Default::default()
+    });
+
+    for _ in 0..2 {
+        let resp = gateway
+            .query(json!({ "query": "{ me { username reviews { body } } }" }))
+            .await;
+        assert_eq!(
+            resp,
+            json!({ "data": { "me": { "username": "alice", "reviews": [{ "body": "Great!" }] } } })
+        );
+    }
+    assert_eq!(reviews.requests().len(), 2);
+    // The requests over HTTP/2 carry their host in the `:authority`
+    // pseudo-header.
+    assert!(reviews
+        .requests()
+        .iter()
+        .all(|request| !request.headers.contains_key("host")));
+    assert!(accounts
+        .requests()
+        .iter()
+        .all(|request| request.headers.contains_key("host")));
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use graphgate_handler::{
+    CompositionStatus,
+    ServiceRoute,
+    ServiceRouteTable,
+    SharedRouteTable,
+    VerificationConfig,
+    VerificationOperation,
+};
+use graphgate_test_utils::SubgraphBuilder;
+use tempfile::NamedTempFile;
+use value::ConstValue;
+
+#[tokio::test]
+async fn keep_schema_failing_verification() {
+    let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+        .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+        .spawn()
+        .await;
+    let sdl_file = NamedTempFile::new().unwrap();
+    std::fs::write(sdl_file.path(), "type Query { me: String }").unwrap();
+
+    let shared_route_table = SharedRouteTable::default();
+    shared_route_table.set_update_interval(Duration::from_millis(50));
+    shared_route_table.set_verification_config(VerificationConfig {
+        timeout_secs: 10,
+        operations: vec![VerificationOperation {
+            name: "me".to_string(),
+            query: "{ me }".to_string(),
+            operation_name: None,
+            variables: Default::default(),
+        }],
+    });
+    let mut route_table = ServiceRouteTable::default();
+    route_table.insert("accounts".to_string(), ServiceRoute {
+        addr: accounts.addr().to_string(),
+        tls: false,
+        query_path: None,
+        subscribe_path: None,
+        introspection_path: None,
+        schema_url: None,
+        websocket_path: None,
+        websocket_protocol: None,
+        connection_params: Default::default(),
+        sdl_file: Some(sdl_file.path().to_path_buf()),
+        headers: Default::default(),
+        header_policy: Default::default(),
+        user_agent: None,
+        oauth2: None,
+        signing: None,
+        enum_values: Default::default(),
+        lenient_errors: false,
+        timeout_ms: None,
+        retry_count: 0,
+        retry_backoff_ms: 0,
+        source: Default::default(),
+    });
+    shared_route_table.set_route_table(route_table);
+    assert!(shared_route_table.wait_ready(Duration::from_secs(10)).await);
+    assert_eq!(accounts.requests().len(), 1);
+
+    // The new schema composes, but no longer has the verified field.
+    std::fs::write(sdl_file.path(), "type Query { user: String }").unwrap();
+    let mut composition = shared_route_table.watch_composition();
+    let status = tokio::time::timeout(
+        Duration::from_secs(10),
+        composition.wait_for(|status| matches!(status, CompositionStatus::Failed(_))),
+    )
+    .await
+    .expect("the schema was not verified in time")
+    .unwrap()
+    .clone();
+    assert!(
+        matches!(&status, CompositionStatus::Failed(err) if err.contains("failed verification") && err.contains("Verification operation 'me' failed")),
+        "{:?}",
+        status
+    );
+
+    let (schema, _) = shared_route_table.get().await.unwrap();
+    assert!(schema.types["Query"].fields.contains_key("me"));
+    assert!(!schema.types["Query"].fields.contains_key("user"));
+}
+
+#[test]
+fn only_queries_are_verified() {
+    let operation = |query: &str, operation_name: Option<&str>| VerificationConfig {
+        timeout_secs: 10,
+        operations: vec![VerificationOperation {
+            name: "smoke".to_string(),
+            query: query.to_string(),
+            operation_name: operation_name.map(ToString::to_string),
+            variables: Default::default(),
+        }],
+    };
+
+    assert!(operation("{ me }", None).validate().is_ok());
+    assert!(operation("query A { me } mutation B { logout }", Some("A"))
+        .validate()
+        .is_ok());
+    assert!(operation("mutation { logout }", None).validate().is_err());
+    assert!(operation("query A { me } mutation B { logout }", Some("B"))
+        .validate()
+        .is_err());
+    assert!(operation("query A { me }", Some("C")).validate().is_err());
+    assert_eq!(
+        operation("query A { me } query B { me }", None)
+            .validate()
+            .unwrap_err()
+            .to_string(),
+        "The verification operation 'smoke' has several operations, its operation_name must select one."
+    );
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use graphgate_handler::{CompositionStatus, ServiceRoute, ServiceRouteTable, SharedRouteTable};
+use tempfile::NamedTempFile;
+
+async fn wait_for(shared_route_table: &SharedRouteTable, f: impl Fn(&CompositionStatus) -> bool) -> CompositionStatus {
+    let mut composition = shared_route_table.watch_composition();
+    let status = tokio::time::timeout(Duration::from_secs(10), composition.wait_for(|status| f(status)))
+        .await
+        .expect("the schema was not updated in time")
+        .unwrap()
+        .clone();
+    status
+}
+
+#[tokio::test]
+async fn recompose_on_sdl_file_change() {
+    let sdl_file = NamedTempFile::new().unwrap();
+    std::fs::write(sdl_file.path(), "type Query { me: String }").unwrap();
+
+    let shared_route_table = SharedRouteTable::default();
+    shared_route_table.set_update_interval(Duration::from_millis(50));
+    let mut route_table = ServiceRouteTable::default();
+    route_table.insert("accounts".to_string(), ServiceRoute {
+        addr: "127.0.0.1:1".to_string(),
+        tls: false,
+        query_path: None,
+        subscribe_path: None,
+        introspection_path: None,
+        schema_url: None,
+        websocket_path: None,
+        websocket_protocol: None,
+        connection_params: Default::default(),
+        sdl_file: Some(sdl_file.path().to_path_buf()),
+        headers: Default::default(),
+        header_policy: Default::default(),
+        user_agent: None,
+        oauth2: None,
+        signing: None,
+        enum_values: Default::default(),
+        lenient_errors: false,
+        timeout_ms: None,
+        retry_count: 0,
+        retry_backoff_ms: 0,
+        source: Default::default(),
+    });
+    shared_route_table.set_route_table(route_table.clone());
+
+    let status = wait_for(&shared_route_table, |status| *status != CompositionStatus::Pending).await;
+    assert_eq!(status, CompositionStatus::Composed(1));
+    let subgraphs = shared_route_table.subgraph_schemas();
+    assert_eq!(subgraphs.len(), 1);
+    assert_eq!(subgraphs[0].service, "accounts");
+    assert_eq!(subgraphs[0].sdl_hash.len(), 64);
+
+    std::fs::write(sdl_file.path(), "type Query { me: String }}").unwrap();
+    let status = wait_for(&shared_route_table, |status| {
+        matches!(status, CompositionStatus::Failed(_))
+    })
+    .await;
+    assert!(matches!(status, CompositionStatus::Failed(err) if err.contains("Invalid SDL from 'accounts'")));
+    // The last composed schema is still served.
+    assert!(shared_route_table.get().await.is_some());
+
+    std::fs::write(sdl_file.path(), "type Query { me: String version: Int }").unwrap();
+    let status = wait_for(&shared_route_table, |status| {
+        matches!(status, CompositionStatus::Composed(_))
+    })
+    .await;
+    assert_eq!(status, CompositionStatus::Composed(2));
+    let (schema, _) = shared_route_table.get().await.unwrap();
+    assert!(schema.types["Query"].fields.contains_key("version"));
+    let updated_subgraphs = shared_route_table.subgraph_schemas();
+    assert_ne!(updated_subgraphs[0].sdl_hash, subgraphs[0].sdl_hash);
+    assert!(updated_subgraphs[0].fetched_at > subgraphs[0].fetched_at);
+
+    // The composed schema is still served, and ready, while the new routes
+    // cannot be composed.
+    let mut route_table = route_table;
+    let mut reviews = route_table["accounts"].clone();
+    reviews.sdl_file = None;
+    route_table.insert("reviews".to_string(), reviews);
+    shared_route_table.set_route_table(route_table);
+    wait_for(&shared_route_table, |status| {
+        matches!(status, CompositionStatus::Failed(_))
+    })
+    .await;
+    assert!(shared_route_table.is_ready());
+    assert!(shared_route_table.get().await.is_some());
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{Gateway, GatewayBuilder};
+use futures_util::{SinkExt, StreamExt};
+use graphgate_handler::INTROSPECTION_DISABLED;
+use graphgate_test_utils::{Subgraph, SubgraphBuilder};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use value::ConstValue;
+
+async fn reviews() -> Subgraph {
+    SubgraphBuilder::new(
+        "reviews",
+        "type Query { ok: Boolean } type Subscription { reviewAdded: String! }",
+    )
+    .subscription("reviewAdded", |_| {
+        vec![
+            ConstValue::String("first".to_string()),
+            ConstValue::String("second".to_string()),
+        ]
+    })
+    .spawn()
+    .await
+}
+
+/// Sends the messages over a connection negotiating the protocol, and
+/// collects the replies until the last one has the type `until`.
+async fn exchange(gateway: &Gateway, protocol: &str, messages: &[Value], until: &str) -> (String, Vec<Value>) {
+    let mut request = format!("ws://{}", gateway.addr()).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", protocol.parse().unwrap());
+    let (mut socket, resp) = tokio_tungstenite::connect_async(request).await.unwrap();
+    let negotiated = resp.headers()["Sec-WebSocket-Protocol"].to_str().unwrap().to_string();
+
+    for message in messages {
+        socket.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    let mut replies = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(Ok(message)) = socket.next().await {
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            let done = message["type"] == until;
+            replies.push(message);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("the replies did not arrive in time");
+    (negotiated, replies)
+}
+
+#[tokio::test]
+async fn subscriptions_transport_ws() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+
+    let (protocol, replies) = exchange(
+        &gateway,
+        "graphql-ws",
+        &[
+            json!({ "type": "connection_init" }),
+            json!({ "type": "start", "id": "1", "payload": { "query": "subscription { reviewAdded }" } }),
+        ],
+        "complete",
+    )
+    .await;
+    assert_eq!(protocol, "graphql-ws");
+    assert_eq!(replies, vec![
+        json!({ "type": "connection_ack" }),
+        json!({ "type": "data", "id": "1", "payload": { "data": { "reviewAdded": "first" } } }),
+        json!({ "type": "data", "id": "1", "payload": { "data": { "reviewAdded": "second" } } }),
+        json!({ "type": "complete", "id": "1" }),
+    ]);
+}
+
+#[tokio::test]
+async fn graphql_transport_ws() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+
+    let (protocol, replies) = exchange(
+        &gateway,
+        "graphql-transport-ws",
+        &[
+            json!({ "type": "connection_init" }),
+            json!({ "type": "subscribe", "id": "1", "payload": { "query": "subscription { reviewAdded }" } }),
+        ],
+        "complete",
+    )
+    .await;
+    assert_eq!(protocol, "graphql-transport-ws");
+    assert_eq!(replies, vec![
+        json!({ "type": "connection_ack" }),
+        json!({ "type": "next", "id": "1", "payload": { "data": { "reviewAdded": "first" } } }),
+        json!({ "type": "next", "id": "1", "payload": { "data": { "reviewAdded": "second" } } }),
+        json!({ "type": "complete", "id": "1" }),
+    ]);
+}
+
+#[tokio::test]
+async fn negotiate_first_supported_protocol() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+
+    let (protocol, _) = exchange(
+        &gateway,
+        "unknown, graphql-transport-ws, graphql-ws",
+        &[json!({ "type": "connection_init" })],
+        "connection_ack",
+    )
+    .await;
+    assert_eq!(protocol, "graphql-transport-ws");
+}
+
+#[tokio::test]
+async fn ping_pong() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+
+    let (_, replies) = exchange(
+        &gateway,
+        "graphql-transport-ws",
+        &[
+            json!({ "type": "connection_init" }),
+            json!({ "type": "ping", "payload": { "at": 1 } }),
+        ],
+        "pong",
+    )
+    .await;
+    assert_eq!(replies, vec![
+        json!({ "type": "connection_ack" }),
+        json!({ "type": "pong", "payload": { "at": 1 } }),
+    ]);
+}
+
+#[tokio::test]
+async fn syntax_errors_by_protocol() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews]).start().await;
+
+    let (_, replies) = exchange(
+        &gateway,
+        "graphql-transport-ws",
+        &[
+            json!({ "type": "connection_init" }),
+            json!({ "type": "subscribe", "id": "1", "payload": { "query": "subscription {" } }),
+        ],
+        "error",
+    )
+    .await;
+    let error = replies.last().unwrap();
+    assert_eq!(error["id"], "1");
+    assert_eq!(error["payload"].as_array().unwrap().len(), 1);
+
+    let (_, replies) = exchange(
+        &gateway,
+        "graphql-ws",
+        &[
+            json!({ "type": "connection_init" }),
+            json!({ "type": "start", "id": "1", "payload": { "query": "subscription {" } }),
+        ],
+        "complete",
+    )
+    .await;
+    assert_eq!(replies[1]["type"], "data");
+    assert_eq!(replies[1]["payload"]["errors"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn disable_introspection() {
+    let reviews = reviews().await;
+    let gateway = GatewayBuilder::new(&[&reviews])
+        .disable_introspection(true)
+        .start()
+        .await;
+
+    let (_, replies) = exchange(
+        &gateway,
+        "graphql-transport-ws",
+        &[
+            json!({ "type": "connection_init" }),
+            json!({ "type": "subscribe", "id": "1", "payload": { "query": "{ __schema { types { name } } }" } }),
+        ],
+        "complete",
+    )
+    .await;
+    assert_eq!(
+        replies[1],
+        json!({
+            "type": "next",
+            "id": "1",
+            "payload": {
+                "data": null,
+                "errors": [{
+                    "message": "Introspection is disabled.",
+                    "extensions": { "code": INTROSPECTION_DISABLED },
+                }],
+            },
+        })
+    );
+}
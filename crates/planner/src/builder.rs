@@ -1,12 +1,16 @@
 #![allow(clippy::too_many_arguments)]
 
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use graphgate_schema::{ComposedSchema, KeyFields, MetaField, MetaType, TypeKind, ValueExt};
 use indexmap::IndexMap;
 use parser::{
     types::{
         BaseType,
+        Directive,
         DocumentOperations,
         ExecutableDocument,
         Field,
@@ -25,6 +29,8 @@ use value::{ConstValue, Name, Value, Variables};
 
 use crate::{
     plan::{
+        DeferNode,
+        DeferredNode,
         FetchNode,
         FlattenNode,
         IntrospectionDirective,
@@ -36,8 +42,10 @@ use crate::{
         PlanNode,
         ResponsePath,
         SequenceNode,
+        DEFAULT_KEY_ALIAS,
     },
     types::{
+        EntityFields,
         FetchEntity,
         FetchEntityGroup,
         FetchEntityKey,
@@ -46,6 +54,7 @@ use crate::{
         MutationRootGroup,
         QueryRootGroup,
         RequiredRef,
+        RootBatch,
         RootGroup,
         SelectionRef,
         SelectionRefSet,
@@ -63,7 +72,46 @@ struct Context<'a> {
     schema: &'a ComposedSchema,
     fragments: &'a HashMap<Name, Positioned<FragmentDefinition>>,
     variables: &'a Variables,
+    key_alias: &'a str,
     key_id: usize,
+    /// Whether the entity fetches select the keys of the entities again.
+    check_entity_keys: bool,
+    /// The fragments of the root selection set marked with `@defer`, planned
+    /// as deferred nodes. `None` if the operation does not support them.
+    deferred_fragments: Option<Vec<DeferredFragment<'a>>>,
+    /// The services the plan may fetch from, `None` for all of them.
+    allowed_services: Option<&'a HashSet<String>>,
+    /// The fields resolved by the services that are not allowed, by their
+    /// coordinate, with their service.
+    excluded_fields: IndexMap<String, &'a str>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct DeferredFragment<'a> {
+    label: Option<&'a str>,
+    selection_set: &'a SelectionSet,
+}
+
+/// Selects which root fields are included in a plan.
+#[derive(Debug, Copy, Clone)]
+enum RootFieldFilter<'b> {
+    All,
+    Only(&'b HashSet<String>),
+    Except(&'b HashSet<String>),
+}
+
+impl RootFieldFilter<'_> {
+    fn accept(&self, field_name: &str) -> bool {
+        match self {
+            RootFieldFilter::All => true,
+            RootFieldFilter::Only(fields) => fields.contains(field_name),
+            RootFieldFilter::Except(fields) => !fields.contains(field_name),
+        }
+    }
+
+    fn accept_introspection(&self) -> bool {
+        !matches!(self, RootFieldFilter::Except(_))
+    }
 }
 
 /// Query plan generator
@@ -72,18 +120,53 @@ pub struct PlanBuilder<'a> {
     document: ExecutableDocument,
     operation_name: Option<String>,
     variables: Variables,
+    primary_fields: HashSet<String>,
+    key_alias: String,
+    check_entity_keys: bool,
+    allowed_services: Option<HashSet<String>>,
 }
 
 impl<'a> PlanBuilder<'a> {
     pub fn new(schema: &'a ComposedSchema, document: ExecutableDocument) -> Self {
         Self {
             schema,
+            key_alias: unique_key_alias(&document),
             document,
             operation_name: None,
             variables: Default::default(),
+            primary_fields: Default::default(),
+            check_entity_keys: false,
+            allowed_services: None,
+        }
+    }
+
+    /// Root query fields that should be resolved first.
+    ///
+    /// When a query selects both primary and other root fields, the plan is
+    /// split into a [`DeferNode`] so the remaining fields can be delivered
+    /// incrementally.
+    pub fn primary_fields(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.primary_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Select the keys of the entities in the entity fetches too, so that the
+    /// executor can check that the services resolve the entities they are
+    /// sent.
+    pub fn check_entity_keys(self, check_entity_keys: bool) -> Self {
+        Self {
+            check_entity_keys,
+            ..self
         }
     }
 
+    /// Plan the operation with only these services, failing if it selects
+    /// fields that only other services resolve.
+    pub fn allowed_services(mut self, services: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_services = Some(services.into_iter().map(Into::into).collect());
+        self
+    }
+
     pub fn operation_name(mut self, operation: impl Into<String>) -> Self {
         self.operation_name = Some(operation.into());
         self
@@ -93,6 +176,7 @@ impl<'a> PlanBuilder<'a> {
         Self { variables, ..self }
     }
 
+    #[allow(clippy::result_large_err)]
     #[instrument(err(Debug), skip(self), ret, level = "trace")]
     fn check_rules(&self) -> Result<(), Response> {
         let rule_errors = graphgate_validation::check_rules(self.schema, &self.document, &self.variables);
@@ -106,6 +190,7 @@ impl<'a> PlanBuilder<'a> {
                         path: Default::default(),
                         locations: err.locations,
                         extensions: Default::default(),
+                        status: None,
                     })
                     .collect(),
                 extensions: Default::default(),
@@ -121,16 +206,22 @@ impl<'a> PlanBuilder<'a> {
             schema: self.schema,
             fragments,
             variables: &self.variables,
+            key_alias: &self.key_alias,
             key_id: 1,
+            check_entity_keys: self.check_entity_keys,
+            deferred_fragments: None,
+            allowed_services: self.allowed_services.as_ref(),
+            excluded_fields: IndexMap::new(),
         }
     }
 
+    #[allow(clippy::result_large_err)]
     #[instrument(err(Debug), skip(self), ret, level = "trace")]
-    pub fn plan(&self) -> Result<RootNode, Response> {
+    pub fn plan(&self) -> Result<RootNode<'_>, Response> {
         self.check_rules()?;
 
         let mut ctx = self.create_context();
-        let operation_definition = get_operation(&self.document, self.operation_name.as_deref());
+        let operation_definition = get_operation(&self.document, self.operation_name.as_deref())?;
 
         let root_type = match operation_definition.node.ty {
             OperationType::Query => ctx.schema.query_type(),
@@ -144,31 +235,115 @@ impl<'a> PlanBuilder<'a> {
                 .expect("The query validator should find this error."),
         };
 
-        if let Some(root_type) = ctx.schema.types.get(root_type) {
+        let root = if let Some(root_type) = ctx.schema.types.get(root_type) {
             match operation_definition.node.ty {
-                OperationType::Query => Ok(RootNode::Query(ctx.build_root_selection_set(
-                    QueryRootGroup::default(),
-                    operation_definition.node.ty,
-                    &operation_definition.node.variable_definitions,
-                    root_type,
-                    &operation_definition.node.selection_set.node,
-                ))),
-                OperationType::Mutation => Ok(RootNode::Query(ctx.build_root_selection_set(
+                OperationType::Query => {
+                    let variable_definitions = &operation_definition.node.variable_definitions;
+                    let selection_set = &operation_definition.node.selection_set.node;
+                    ctx.deferred_fragments = Some(Vec::new());
+
+                    let (primary, mut deferred) = if self.primary_fields.is_empty() {
+                        let primary = ctx.build_root_selection_set(
+                            QueryRootGroup::default(),
+                            operation_definition.node.ty,
+                            variable_definitions,
+                            root_type,
+                            selection_set,
+                            RootFieldFilter::All,
+                        );
+                        (primary, Vec::new())
+                    } else {
+                        let primary = ctx.build_root_selection_set(
+                            QueryRootGroup::default(),
+                            operation_definition.node.ty,
+                            variable_definitions,
+                            root_type,
+                            selection_set,
+                            RootFieldFilter::Only(&self.primary_fields),
+                        );
+                        let deferred = ctx.build_root_selection_set(
+                            QueryRootGroup::default(),
+                            operation_definition.node.ty,
+                            variable_definitions,
+                            root_type,
+                            selection_set,
+                            RootFieldFilter::Except(&self.primary_fields),
+                        );
+                        (primary, vec![DeferredNode {
+                            label: None,
+                            node: deferred,
+                        }])
+                    };
+
+                    // Planning a deferred fragment may queue the deferred
+                    // fragments nested in it.
+                    let mut deferred_count = 0;
+                    while let Some(fragment) = ctx
+                        .deferred_fragments
+                        .as_ref()
+                        .and_then(|fragments| fragments.get(deferred_count))
+                        .copied()
+                    {
+                        deferred_count += 1;
+                        let node = ctx.build_root_selection_set(
+                            QueryRootGroup::default(),
+                            operation_definition.node.ty,
+                            variable_definitions,
+                            root_type,
+                            fragment.selection_set,
+                            RootFieldFilter::All,
+                        );
+                        deferred.push(DeferredNode {
+                            label: fragment.label,
+                            node,
+                        });
+                    }
+
+                    deferred.retain(|deferred| !deferred.node.is_empty());
+                    if deferred.is_empty() {
+                        RootNode::Query(primary)
+                    } else if primary.is_empty() && deferred_count == 0 {
+                        RootNode::Query(deferred.remove(0).node)
+                    } else {
+                        RootNode::Defer(DeferNode { primary, deferred })
+                    }
+                },
+                OperationType::Mutation => RootNode::Query(ctx.build_root_selection_set(
                     MutationRootGroup::default(),
                     operation_definition.node.ty,
                     &operation_definition.node.variable_definitions,
                     root_type,
                     &operation_definition.node.selection_set.node,
-                ))),
-                OperationType::Subscription => Ok(RootNode::Subscribe(ctx.build_subscribe(
+                    RootFieldFilter::All,
+                )),
+                OperationType::Subscription => RootNode::Subscribe(ctx.build_subscribe(
                     &operation_definition.node.variable_definitions,
                     root_type,
                     &operation_definition.node.selection_set.node,
-                ))),
+                )),
             }
         } else {
             unreachable!("The query validator should find this error.")
+        };
+
+        if !ctx.excluded_fields.is_empty() {
+            return Err(Response {
+                data: ConstValue::Null,
+                errors: ctx
+                    .excluded_fields
+                    .iter()
+                    .map(|(coordinate, service)| {
+                        ServerError::new(format!(
+                            r#"The field "{}" is resolved by the service "{}", which is not allowed."#,
+                            coordinate, service
+                        ))
+                    })
+                    .collect(),
+                extensions: Default::default(),
+                headers: Default::default(),
+            });
         }
+        Ok(root)
     }
 }
 
@@ -180,14 +355,15 @@ impl<'a> Context<'a> {
         variable_definitions: &'a [Positioned<VariableDefinition>],
         parent_type: &'a MetaType,
         selection_set: &'a SelectionSet,
+        filter: RootFieldFilter<'_>,
     ) -> PlanNode<'a> {
         fn build_root_selection_set_rec<'a>(
             ctx: &mut Context<'a>,
             root_group: &mut impl RootGroup<'a>,
-            fetch_entity_group: &mut FetchEntityGroup<'a>,
             inspection_selection_set: &mut IntrospectionSelectionSet,
             parent_type: &'a MetaType,
             selection_set: &'a SelectionSet,
+            filter: RootFieldFilter<'_>,
         ) {
             for selection in &selection_set.items {
                 match &selection.node {
@@ -198,12 +374,23 @@ impl<'a> Context<'a> {
                             None => continue,
                         };
                         if is_introspection_field(field_name) {
-                            ctx.build_introspection_field(inspection_selection_set, &field.node);
+                            if filter.accept_introspection() {
+                                ctx.build_introspection_field(inspection_selection_set, &field.node);
+                            }
+                            continue;
+                        }
+                        if !filter.accept(field_name) {
                             continue;
                         }
 
                         if let Some(service) = &field_definition.service {
-                            let selection_ref_set = root_group.selection_set_mut(service);
+                            let service =
+                                match ctx.allowed_service(parent_type, field_name, field_definition, service, None) {
+                                    Some(service) => service,
+                                    None => continue,
+                                };
+                            let (selection_ref_set, fetch_entity_group) =
+                                root_group.selection_set_mut(service, field.node.response_key().node.as_str());
                             let mut path = ResponsePath::default();
                             ctx.build_field(
                                 &mut path,
@@ -217,39 +404,47 @@ impl<'a> Context<'a> {
                     },
                     Selection::FragmentSpread(fragment_spread) => {
                         if let Some(fragment) = ctx.fragments.get(fragment_spread.node.fragment_name.node.as_str()) {
+                            if ctx.defer_fragment(&fragment_spread.node.directives, &fragment.node.selection_set.node) {
+                                continue;
+                            }
                             build_root_selection_set_rec(
                                 ctx,
                                 root_group,
-                                fetch_entity_group,
                                 inspection_selection_set,
                                 parent_type,
                                 &fragment.node.selection_set.node,
+                                filter,
                             );
                         }
                     },
                     Selection::InlineFragment(inline_fragment) => {
+                        if ctx.defer_fragment(
+                            &inline_fragment.node.directives,
+                            &inline_fragment.node.selection_set.node,
+                        ) {
+                            continue;
+                        }
                         build_root_selection_set_rec(
                             ctx,
                             root_group,
-                            fetch_entity_group,
                             inspection_selection_set,
                             parent_type,
                             &inline_fragment.node.selection_set.node,
+                            filter,
                         );
                     },
                 }
             }
         }
 
-        let mut fetch_entity_group = FetchEntityGroup::default();
         let mut inspection_selection_set = IntrospectionSelectionSet::default();
         build_root_selection_set_rec(
             self,
             &mut root_group,
-            &mut fetch_entity_group,
             &mut inspection_selection_set,
             parent_type,
             selection_set,
+            filter,
         );
 
         let mut nodes = Vec::new();
@@ -259,79 +454,134 @@ impl<'a> Context<'a> {
             }));
         }
 
-        let fetch_node = {
-            let mut nodes = Vec::new();
-            for (service, selection_set) in root_group.into_selection_set() {
-                let (variables, variable_definitions) =
-                    referenced_variables(&selection_set, self.variables, variable_definitions);
-                nodes.push(PlanNode::Fetch(FetchNode {
-                    service,
-                    variables,
-                    query: FetchQuery {
-                        entity_type: None,
-                        operation_type,
-                        variable_definitions,
-                        selection_set,
-                    },
-                }));
-            }
-            if operation_type == OperationType::Query {
-                PlanNode::Parallel(ParallelNode { nodes }).flatten()
-            } else {
-                PlanNode::Sequence(SequenceNode { nodes }).flatten()
-            }
-        };
-        nodes.push(fetch_node);
-
-        while !fetch_entity_group.is_empty() {
-            let mut flatten_nodes = Vec::new();
-            let mut next_group = FetchEntityGroup::new();
+        for RootBatch {
+            fetches,
+            mut fetch_entity_group,
+        } in root_group.into_batches()
+        {
+            let fetch_node = {
+                let mut nodes = Vec::new();
+                for (service, selection_set) in fetches {
+                    let (variables, variable_definitions) =
+                        self.referenced_variables(service, &selection_set, variable_definitions);
+                    nodes.push(PlanNode::Fetch(FetchNode {
+                        service,
+                        variables,
+                        query: FetchQuery {
+                            entity_types: Vec::new(),
+                            operation_type,
+                            variable_definitions,
+                            selection_set,
+                        },
+                    }));
+                }
+                if operation_type == OperationType::Query {
+                    PlanNode::Parallel(ParallelNode::new(nodes)).flatten()
+                } else {
+                    PlanNode::Sequence(SequenceNode { nodes }).flatten()
+                }
+            };
+            nodes.push(fetch_node);
 
-            for (
-                FetchEntityKey { service, mut path, .. },
-                FetchEntity {
-                    parent_type,
-                    prefix,
-                    fields,
-                },
-            ) in fetch_entity_group
-            {
-                let mut selection_ref_set = SelectionRefSet::default();
+            // The entities of a batch are fetched before the next batch.
+            while !fetch_entity_group.is_empty() {
+                let mut flatten_nodes = Vec::new();
+                let mut next_group = FetchEntityGroup::new();
 
-                for field in fields {
-                    self.build_field(
-                        &mut path,
-                        &mut selection_ref_set,
+                for (key, fetch_entity) in fetch_entity_group {
+                    flatten_nodes.push(self.build_flatten_node(
+                        key,
+                        fetch_entity,
                         &mut next_group,
-                        service,
-                        parent_type,
-                        field,
-                    );
+                        variable_definitions,
+                    ));
                 }
 
-                let (variables, variable_definitions) =
-                    referenced_variables(&selection_ref_set, self.variables, variable_definitions);
-                flatten_nodes.push(PlanNode::Flatten(FlattenNode {
-                    path,
-                    prefix,
-                    service,
-                    variables,
-                    query: FetchQuery {
-                        entity_type: Some(parent_type.name.as_str()),
-                        operation_type: OperationType::Subscription,
-                        variable_definitions,
-                        selection_set: selection_ref_set,
-                    },
-                }));
+                nodes.push(PlanNode::Parallel(ParallelNode::new(flatten_nodes)).flatten());
+                fetch_entity_group = next_group;
             }
-
-            nodes.push(PlanNode::Parallel(ParallelNode { nodes: flatten_nodes }).flatten());
-            fetch_entity_group = next_group;
         }
 
         PlanNode::Sequence(SequenceNode { nodes }).flatten()
     }
 
+    /// Whether the plan may fetch the field from the service, recording it as
+    /// excluded otherwise.
+    fn allow_service(&mut self, parent_type: &MetaType, field_name: &str, service: &'a str) -> bool {
+        match self.allowed_services {
+            Some(allowed_services) if !allowed_services.contains(service) => {
+                self.excluded_fields
+                    .entry(format!("{}.{}", parent_type.name, field_name))
+                    .or_insert(service);
+                false
+            },
+            _ => true,
+        }
+    }
+
+    /// The service the plan fetches the field from: `service` if it is
+    /// allowed, or else another allowed service defining the `@shareable`
+    /// field, recording the field as excluded if there is none.
+    ///
+    /// Below the root, the field is preferably resolved by the
+    /// `current_service` fetching its parent, or else by a service the entity
+    /// can be fetched from.
+    fn allowed_service(
+        &mut self,
+        parent_type: &'a MetaType,
+        field_name: &str,
+        field_definition: &'a MetaField,
+        service: &'a str,
+        current_service: Option<&'a str>,
+    ) -> Option<&'a str> {
+        let allowed_services = match self.allowed_services {
+            Some(allowed_services) if !allowed_services.contains(service) => allowed_services,
+            _ => return Some(service),
+        };
+        let services = &field_definition.services;
+        let alternate = match current_service {
+            Some(current_service) if services.iter().any(|service| service == current_service) => Some(current_service),
+            _ => services.iter().map(String::as_str).find(|alternate| {
+                allowed_services.contains(*alternate) &&
+                    (current_service.is_none() || entity_keys(parent_type, alternate).is_some())
+            }),
+        };
+        if alternate.is_none() {
+            self.excluded_fields
+                .entry(format!("{}.{}", parent_type.name, field_name))
+                .or_insert(service);
+        }
+        alternate
+    }
+
+    /// Queue a fragment of the root selection set marked with an enabled
+    /// `@defer` to be planned as a deferred node, returning whether it was.
+    ///
+    /// Deferred fragments nested in fields are delivered with their parent,
+    /// with the warnings of [`nested_defer_warnings`].
+    fn defer_fragment(&mut self, directives: &'a [Positioned<Directive>], selection_set: &'a SelectionSet) -> bool {
+        let deferred_fragments = match &mut self.deferred_fragments {
+            Some(deferred_fragments) => deferred_fragments,
+            None => return false,
+        };
+        let directive = match enabled_defer(directives, self.variables) {
+            Some(directive) => directive,
+            None => return false,
+        };
+
+        if !deferred_fragments
+            .iter()
+            .any(|fragment| std::ptr::eq(fragment.selection_set, selection_set))
+        {
+            let label = match directive.get_argument("label").map(|value| &value.node) {
+                Some(Value::String(label)) => Some(label.as_str()),
+                _ => None,
+            };
+            deferred_fragments.push(DeferredFragment { label, selection_set });
+        }
+        true
+    }
+
     fn build_subscribe(
         &mut self,
         variable_definitions: &'a [Positioned<VariableDefinition>],
@@ -339,7 +589,6 @@ impl<'a> Context<'a> {
         selection_set: &'a SelectionSet,
     ) -> SubscribeNode<'a> {
         let mut root_group = QueryRootGroup::default();
-        let mut fetch_entity_group = FetchEntityGroup::default();
 
         for selection in &selection_set.items {
             if let Selection::Field(field) = &selection.node {
@@ -350,12 +599,17 @@ impl<'a> Context<'a> {
                 };
 
                 if let Some(service) = &field_definition.service {
-                    let selection_ref_set = root_group.selection_set_mut(service);
+                    let service = match self.allowed_service(parent_type, field_name, field_definition, service, None) {
+                        Some(service) => service,
+                        None => continue,
+                    };
+                    let (selection_ref_set, fetch_entity_group) =
+                        root_group.selection_set_mut(service, field.node.response_key().node.as_str());
                     let mut path = ResponsePath::default();
                     self.build_field(
                         &mut path,
                         selection_ref_set,
-                        &mut fetch_entity_group,
+                        fetch_entity_group,
                         service,
                         parent_type,
                         &field.node,
@@ -364,70 +618,44 @@ impl<'a> Context<'a> {
             }
         }
 
-        let fetch_nodes = {
-            let mut nodes = Vec::new();
-            for (service, selection_ref_set) in root_group.into_selection_set() {
+        let mut fetch_nodes = Vec::new();
+        let mut query_nodes = Vec::new();
+        for RootBatch {
+            fetches,
+            mut fetch_entity_group,
+        } in root_group.into_batches()
+        {
+            for (service, selection_ref_set) in fetches {
                 let (variables, variable_definitions) =
-                    referenced_variables(&selection_ref_set, self.variables, variable_definitions);
-                nodes.push(FetchNode {
+                    self.referenced_variables(service, &selection_ref_set, variable_definitions);
+                fetch_nodes.push(FetchNode {
                     service,
                     variables,
                     query: FetchQuery {
-                        entity_type: None,
+                        entity_types: Vec::new(),
                         operation_type: OperationType::Subscription,
                         variable_definitions,
                         selection_set: selection_ref_set,
                     },
                 });
             }
-            nodes
-        };
 
-        let mut query_nodes = Vec::new();
-        while !fetch_entity_group.is_empty() {
-            let mut flatten_nodes = Vec::new();
-            let mut next_group = FetchEntityGroup::new();
+            while !fetch_entity_group.is_empty() {
+                let mut flatten_nodes = Vec::new();
+                let mut next_group = FetchEntityGroup::new();
 
-            for (
-                FetchEntityKey { service, mut path, .. },
-                FetchEntity {
-                    parent_type,
-                    prefix,
-                    fields,
-                },
-            ) in fetch_entity_group
-            {
-                let mut selection_ref_set = SelectionRefSet::default();
-
-                for field in fields {
-                    self.build_field(
-                        &mut path,
-                        &mut selection_ref_set,
+                for (key, fetch_entity) in fetch_entity_group {
+                    flatten_nodes.push(self.build_flatten_node(
+                        key,
+                        fetch_entity,
                         &mut next_group,
-                        service,
-                        parent_type,
-                        field,
-                    );
+                        variable_definitions,
+                    ));
                 }
 
-                let (variables, variable_definitions) =
-                    referenced_variables(&selection_ref_set, self.variables, variable_definitions);
-                flatten_nodes.push(PlanNode::Flatten(FlattenNode {
-                    path,
-                    prefix,
-                    service,
-                    variables,
-                    query: FetchQuery {
-                        entity_type: Some(parent_type.name.as_str()),
-                        operation_type: OperationType::Query,
-                        variable_definitions,
-                        selection_set: selection_ref_set,
-                    },
-                }));
+                query_nodes.push(PlanNode::Parallel(ParallelNode::new(flatten_nodes)).flatten());
+                fetch_entity_group = next_group;
             }
-
-            query_nodes.push(PlanNode::Parallel(ParallelNode { nodes: flatten_nodes }).flatten());
-            fetch_entity_group = next_group;
         }
 
         SubscribeNode {
@@ -483,8 +711,11 @@ impl<'a> Context<'a> {
                         value
                             .node
                             .clone()
+                            // Variables that are not set are null.
                             .into_const_with(|name| {
-                                Ok::<_, std::convert::Infallible>(ctx.variables.get(&name).unwrap().clone())
+                                Ok::<_, std::convert::Infallible>(
+                                    ctx.variables.get(&name).cloned().unwrap_or_default(),
+                                )
                             })
                             .unwrap(),
                     )
@@ -510,6 +741,106 @@ impl<'a> Context<'a> {
         });
     }
 
+    fn build_flatten_node(
+        &mut self,
+        key: FetchEntityKey<'a>,
+        fetch_entity: FetchEntity<'a>,
+        next_group: &mut FetchEntityGroup<'a>,
+        variable_definitions: &'a [Positioned<VariableDefinition>],
+    ) -> PlanNode<'a> {
+        let FetchEntityKey { service, mut path, .. } = key;
+        let is_single_type = fetch_entity.types.len() == 1;
+        let mut entity_types = Vec::new();
+        let mut interface_objects = IndexMap::new();
+        let mut selection_ref_set = SelectionRefSet::default();
+
+        for (ty, entity_fields) in fetch_entity.types {
+            let EntityFields {
+                parent_type,
+                path: mut entity_path,
+                keys,
+                fields,
+            } = entity_fields;
+            let mut entity_selection_set = SelectionRefSet::default();
+            for field in fields {
+                self.build_field(
+                    &mut entity_path,
+                    &mut entity_selection_set,
+                    next_group,
+                    service,
+                    parent_type,
+                    field,
+                );
+            }
+            if self.check_entity_keys {
+                entity_selection_set.0.push(SelectionRef::RequiredRef(RequiredRef {
+                    key_alias: self.key_alias,
+                    prefix: fetch_entity.prefix,
+                    fields: keys,
+                    requires: None,
+                }));
+            }
+            // Entities of several types are told apart by the typename
+            // selected with their keys rather than by the path.
+            if is_single_type {
+                path = entity_path;
+            }
+
+            // Services contributing to an interface entity resolve the
+            // implementations as the interface.
+            let entity_type = match parent_type.interface_objects.get(service) {
+                Some(interface) if interface != ty => {
+                    interface_objects.insert(ty, interface.as_str());
+                    interface.as_str()
+                },
+                _ => ty,
+            };
+            let fragment = selection_ref_set.0.iter_mut().find_map(|selection| match selection {
+                SelectionRef::InlineFragment {
+                    type_condition: Some(type_condition),
+                    selection_set,
+                } if *type_condition == entity_type => Some(selection_set),
+                _ => None,
+            });
+            match fragment {
+                Some(selection_set) => {
+                    let printed = selection_set.0.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    for selection in entity_selection_set.0 {
+                        if !printed.contains(&selection.to_string()) {
+                            selection_set.0.push(selection);
+                        }
+                    }
+                },
+                None => {
+                    entity_types.push(entity_type);
+                    selection_ref_set.0.push(SelectionRef::InlineFragment {
+                        type_condition: Some(entity_type),
+                        selection_set: entity_selection_set,
+                    });
+                },
+            }
+        }
+
+        let (variables, variable_definitions) =
+            self.referenced_variables(service, &selection_ref_set, variable_definitions);
+        PlanNode::Flatten(FlattenNode {
+            path,
+            key_alias: self.key_alias,
+            prefix: fetch_entity.prefix,
+            check_keys: self.check_entity_keys,
+            alternate_prefixes: fetch_entity.alternate_prefixes,
+            service,
+            interface_objects,
+            variables,
+            query: FetchQuery {
+                entity_types,
+                operation_type: OperationType::Query,
+                variable_definitions,
+                selection_set: selection_ref_set,
+            },
+        })
+    }
+
     fn build_field(
         &mut self,
         path: &mut ResponsePath<'a>,
@@ -522,6 +853,33 @@ impl<'a> Context<'a> {
         let field_name = field.name.node.as_str();
 
         if field_name == "__typename" {
+            // Services contributing to an interface entity only know the
+            // interface, its owner resolves the type of the object.
+            let owner = parent_type.owner.as_deref().filter(|owner| {
+                parent_type.kind == TypeKind::Interface &&
+                    *owner != current_service &&
+                    parent_type.interface_objects.contains_key(current_service)
+            });
+            if let Some(owner) = owner {
+                if !self.allow_service(parent_type, field_name, owner) {
+                    return;
+                }
+                if let Some((keys, alternate_keys)) = parent_type.keys.get(owner).and_then(|keys| keys.split_first()) {
+                    self.add_fetch_entity(
+                        path,
+                        selection_ref_set,
+                        fetch_entity_group,
+                        parent_type,
+                        field,
+                        current_service,
+                        None,
+                        owner,
+                        keys,
+                        alternate_keys,
+                    );
+                    return;
+                }
+            }
             selection_ref_set.0.push(SelectionRef::IntrospectionTypename);
             return;
         }
@@ -541,28 +899,36 @@ impl<'a> Context<'a> {
         };
 
         if service != current_service {
-            let mut keys = parent_type.keys.get(service).and_then(|x| x.first());
-            if keys.is_none() {
-                if let Some(owner) = &parent_type.owner {
-                    keys = parent_type.keys.get(owner).and_then(|x| x.first());
-                }
-            }
-            let keys = match keys {
-                Some(keys) => keys,
-                None => return,
-            };
-            if !self.field_in_keys(field, keys) {
-                self.add_fetch_entity(
-                    path,
-                    selection_ref_set,
-                    fetch_entity_group,
+            let in_keys =
+                matches!(entity_keys(parent_type, service), Some((keys, _)) if self.field_in_keys(field, keys));
+            if !in_keys {
+                let service = match self.allowed_service(
                     parent_type,
-                    field,
+                    field_name,
                     field_definition,
                     service,
-                    keys,
-                );
-                return;
+                    Some(current_service),
+                ) {
+                    Some(service) => service,
+                    None => return,
+                };
+                if service != current_service {
+                    if let Some((keys, alternate_keys)) = entity_keys(parent_type, service) {
+                        self.add_fetch_entity(
+                            path,
+                            selection_ref_set,
+                            fetch_entity_group,
+                            parent_type,
+                            field,
+                            current_service,
+                            field_definition.requires.as_ref(),
+                            service,
+                            keys,
+                            alternate_keys,
+                        );
+                    }
+                    return;
+                }
             }
         }
 
@@ -573,7 +939,10 @@ impl<'a> Context<'a> {
         });
         let mut sub_selection_set = SelectionRefSet::default();
 
-        if matches!(field_type.kind, TypeKind::Interface | TypeKind::Union) {
+        // The services defining an interface as an `@interfaceObject` select
+        // its fields without type conditions.
+        let is_interface_object = field_type.interface_objects.contains_key(current_service);
+        if matches!(field_type.kind, TypeKind::Interface | TypeKind::Union) && !is_interface_object {
             self.build_abstract_selection_set(
                 path,
                 &mut sub_selection_set,
@@ -593,8 +962,21 @@ impl<'a> Context<'a> {
             );
         }
 
+        let enum_value_renames = self.schema.enum_value_renames.get(current_service);
+        let arguments = enum_value_renames.and_then(|renames| {
+            let mut arguments = field.arguments.clone();
+            let mut renamed = false;
+            for (name, value) in &mut arguments {
+                if let Some(argument) = field_definition.arguments.get(&name.node) {
+                    renamed |= self.rename_value(&renames.to_service, &argument.ty, &mut value.node);
+                }
+            }
+            renamed.then_some(arguments)
+        });
         selection_ref_set.0.push(SelectionRef::FieldRef(FieldRef {
             field,
+            arguments,
+            enum_values: enum_value_renames.and_then(|renames| renames.to_supergraph.get(field_type.name.as_str())),
             selection_set: sub_selection_set,
         }));
         path.pop();
@@ -607,43 +989,129 @@ impl<'a> Context<'a> {
         fetch_entity_group: &mut FetchEntityGroup<'a>,
         parent_type: &'a MetaType,
         field: &'a Field,
-        meta_field: &'a MetaField,
+        current_service: &'a str,
+        requires: Option<&'a KeyFields>,
         service: &'a str,
         keys: &'a KeyFields,
+        alternate_keys: &'a [KeyFields],
     ) {
+        // An alternate key is selected only if the current service defines
+        // every one of its fields, otherwise its query would be invalid.
+        let alternate_keys = alternate_keys
+            .iter()
+            .filter(|keys| self.service_defines_keys(current_service, parent_type, keys))
+            .collect::<Vec<_>>();
+        let mut key_path = path.clone();
+        if let Some(segment) = key_path.last_mut() {
+            segment.possible_type = None;
+        }
         let fetch_entity_key = FetchEntityKey {
             service,
-            path: path.clone(),
-            ty: parent_type.name.as_str(),
+            path: key_path,
+            key_shape: self.key_shape(parent_type, std::iter::once(keys).chain(alternate_keys.iter().copied())),
         };
 
         match fetch_entity_group.get_mut(&fetch_entity_key) {
             Some(fetch_entity) => {
-                fetch_entity.fields.push(field);
-                if meta_field.requires.is_some() {
-                    selection_ref_set.0.push(SelectionRef::RequiredRef(RequiredRef {
-                        prefix: self.key_id - 1,
-                        fields: keys,
-                        requires: meta_field.requires.as_ref(),
-                    }));
+                let prefixes = std::iter::once(fetch_entity.prefix)
+                    .chain(fetch_entity.alternate_prefixes.iter().copied())
+                    .collect::<Vec<_>>();
+                let entity_fields =
+                    fetch_entity
+                        .types
+                        .entry(parent_type.name.as_str())
+                        .or_insert_with(|| EntityFields {
+                            parent_type,
+                            path: path.clone(),
+                            keys,
+                            fields: Vec::new(),
+                        });
+                // A possible type joining the fetch selects its keys under the
+                // prefixes of the first one.
+                if entity_fields.fields.is_empty() || requires.is_some() {
+                    for (prefix, keys) in prefixes
+                        .into_iter()
+                        .zip(std::iter::once(keys).chain(alternate_keys.iter().copied()))
+                    {
+                        selection_ref_set.0.push(SelectionRef::RequiredRef(RequiredRef {
+                            key_alias: self.key_alias,
+                            prefix,
+                            fields: keys,
+                            requires,
+                        }));
+                    }
                 }
+                entity_fields.fields.push(field);
             },
             None => {
                 let prefix = self.take_key_prefix();
                 selection_ref_set.0.push(SelectionRef::RequiredRef(RequiredRef {
+                    key_alias: self.key_alias,
                     prefix,
                     fields: keys,
-                    requires: meta_field.requires.as_ref(),
+                    requires,
                 }));
-                fetch_entity_group.insert(fetch_entity_key, FetchEntity {
+                let alternate_prefixes = alternate_keys
+                    .iter()
+                    .map(|keys| {
+                        let prefix = self.take_key_prefix();
+                        selection_ref_set.0.push(SelectionRef::RequiredRef(RequiredRef {
+                            key_alias: self.key_alias,
+                            prefix,
+                            fields: keys,
+                            requires,
+                        }));
+                        prefix
+                    })
+                    .collect();
+                let mut types = IndexMap::new();
+                types.insert(parent_type.name.as_str(), EntityFields {
                     parent_type,
-                    prefix,
+                    path: path.clone(),
+                    keys,
                     fields: vec![field],
                 });
+                fetch_entity_group.insert(fetch_entity_key, FetchEntity {
+                    prefix,
+                    alternate_prefixes,
+                    types,
+                });
             },
         }
     }
 
+    /// The names and types of the key fields, entities of different types
+    /// are fetched together only if their keys have the same shape.
+    fn key_shape<'b>(&self, parent_type: &MetaType, keys: impl IntoIterator<Item = &'b KeyFields>) -> String {
+        fn key_shape_rec(ctx: &Context<'_>, shape: &mut String, parent_type: &MetaType, fields: &KeyFields) {
+            for (field_name, children) in fields.iter() {
+                let ty = parent_type.fields.get(field_name).map(|field| &field.ty);
+                shape.push(' ');
+                shape.push_str(field_name);
+                if let Some(ty) = ty {
+                    shape.push(':');
+                    shape.push_str(&ty.to_string());
+                }
+                if !children.is_empty() {
+                    shape.push_str(" {");
+                    if let Some(field_type) = ty.and_then(|ty| ctx.schema.get_type(ty)) {
+                        key_shape_rec(ctx, shape, field_type, children);
+                    }
+                    shape.push_str(" }");
+                }
+            }
+        }
+
+        let mut shape = String::new();
+        for (idx, fields) in keys.into_iter().enumerate() {
+            if idx > 0 {
+                shape.push_str(" |");
+            }
+            key_shape_rec(self, &mut shape, parent_type, fields);
+        }
+        shape
+    }
+
     fn build_selection_set(
         &mut self,
         path: &mut ResponsePath<'a>,
@@ -806,15 +1274,7 @@ impl<'a> Context<'a> {
             }
         }
 
-        for (ty, sub_selection_ref_set) in selection_ref_set_group
-            .into_iter()
-            .filter(|(_, selection_ref_set)| !selection_ref_set.0.is_empty())
-        {
-            selection_ref_set.0.push(SelectionRef::InlineFragment {
-                type_condition: Some(ty),
-                selection_set: sub_selection_ref_set,
-            });
-        }
+        selection_ref_set.push_fragments(parent_type, current_service, selection_ref_set_group);
     }
 
     fn take_key_prefix(&mut self) -> usize {
@@ -823,6 +1283,23 @@ impl<'a> Context<'a> {
         id
     }
 
+    /// Whether the service defines every field of the keys of the type,
+    /// either resolving it or declaring it in one of its own keys.
+    fn service_defines_keys(&self, service: &str, parent_type: &MetaType, keys: &KeyFields) -> bool {
+        let service_keys = parent_type.keys.get(service);
+        keys.keys().all(|field_name| {
+            let resolved = parent_type
+                .fields
+                .get(field_name)
+                .is_some_and(|field| field.service.as_deref().or(parent_type.owner.as_deref()) == Some(service));
+            resolved ||
+                service_keys
+                    .into_iter()
+                    .flatten()
+                    .any(|keys| keys.contains_key(field_name))
+        })
+    }
+
     fn field_in_keys(&self, field: &Field, keys: &KeyFields) -> bool {
         fn selection_set_in_keys(ctx: &Context<'_>, selection_set: &SelectionSet, keys: &KeyFields) -> bool {
             for selection in &selection_set.items {
@@ -857,6 +1334,145 @@ impl<'a> Context<'a> {
             false
         }
     }
+
+    /// The variables referenced by a fetch of `service` and their
+    /// definitions, with their enum values named as the service names them.
+    ///
+    /// The default values the service would rename are sent as variables.
+    fn referenced_variables(
+        &self,
+        service: &str,
+        selection_set: &SelectionRefSet<'a>,
+        variable_definitions: &'a [Positioned<VariableDefinition>],
+    ) -> (VariablesRef<'a>, VariableDefinitionsRef<'a>) {
+        let (mut variables_ref, variable_definitions_ref) =
+            referenced_variables(selection_set, self.variables, variable_definitions);
+        if let Some(renames) = self.schema.enum_value_renames.get(service) {
+            for definition in &variable_definitions_ref.variables {
+                let name = definition.name.node.as_str();
+                let mut value = match variables_ref.variables.get(name) {
+                    Some(value) => ConstValue::clone(value),
+                    None => match &definition.default_value {
+                        Some(default_value) => default_value.node.clone(),
+                        None => continue,
+                    },
+                };
+                if self.rename_const_value(&renames.to_service, &definition.var_type.node, &mut value) {
+                    variables_ref.variables.insert(name, Cow::Owned(value));
+                }
+            }
+        }
+        (variables_ref, variable_definitions_ref)
+    }
+
+    /// Rename the enum values of an argument of type `ty` to the values of
+    /// the service, returning whether any was renamed.
+    fn rename_value(
+        &self,
+        to_service: &HashMap<String, HashMap<String, String>>,
+        ty: &Type,
+        value: &mut Value,
+    ) -> bool {
+        match value {
+            Value::Enum(name) => match self.service_enum_value(to_service, ty, name) {
+                Some(service_value) => {
+                    *name = Name::new(service_value);
+                    true
+                },
+                None => false,
+            },
+            Value::List(items) => {
+                let ty = element_type(ty);
+                items
+                    .iter_mut()
+                    .fold(false, |renamed, item| self.rename_value(to_service, ty, item) | renamed)
+            },
+            Value::Object(fields) => match self.schema.get_type(ty) {
+                Some(meta_type) => {
+                    fields
+                        .iter_mut()
+                        .fold(false, |renamed, (name, value)| match meta_type.input_fields.get(name) {
+                            Some(input_field) => self.rename_value(to_service, &input_field.ty, value) | renamed,
+                            None => renamed,
+                        })
+                },
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Rename the enum values of a variable of type `ty` to the values of
+    /// the service, returning whether any was renamed.
+    ///
+    /// The enum values of the variables are strings in JSON.
+    fn rename_const_value(
+        &self,
+        to_service: &HashMap<String, HashMap<String, String>>,
+        ty: &Type,
+        value: &mut ConstValue,
+    ) -> bool {
+        match value {
+            ConstValue::Enum(name) => match self.service_enum_value(to_service, ty, name) {
+                Some(service_value) => {
+                    *name = Name::new(service_value);
+                    true
+                },
+                None => false,
+            },
+            ConstValue::String(name) => match self.service_enum_value(to_service, ty, name) {
+                Some(service_value) => {
+                    *name = service_value.to_string();
+                    true
+                },
+                None => false,
+            },
+            ConstValue::List(items) => {
+                let ty = element_type(ty);
+                items.iter_mut().fold(false, |renamed, item| {
+                    self.rename_const_value(to_service, ty, item) | renamed
+                })
+            },
+            ConstValue::Object(fields) => match self.schema.get_type(ty) {
+                Some(meta_type) => {
+                    fields
+                        .iter_mut()
+                        .fold(false, |renamed, (name, value)| match meta_type.input_fields.get(name) {
+                            Some(input_field) => self.rename_const_value(to_service, &input_field.ty, value) | renamed,
+                            None => renamed,
+                        })
+                },
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The value the service names the enum value `name` of type `ty`, if
+    /// it names it differently.
+    fn service_enum_value<'b>(
+        &self,
+        to_service: &'b HashMap<String, HashMap<String, String>>,
+        ty: &Type,
+        name: &str,
+    ) -> Option<&'b str> {
+        let meta_type = self.schema.get_type(ty).filter(|ty| ty.kind == TypeKind::Enum)?;
+        to_service.get(meta_type.name.as_str())?.get(name).map(String::as_str)
+    }
+}
+
+/// The keys to fetch the entity from the service with, those of its owner if
+/// the service declares none.
+fn entity_keys<'a>(parent_type: &'a MetaType, service: &str) -> Option<(&'a KeyFields, &'a [KeyFields])> {
+    let all_keys = parent_type
+        .keys
+        .get(service)
+        .filter(|keys| !keys.is_empty())
+        .or_else(|| {
+            let owner = parent_type.owner.as_ref()?;
+            parent_type.keys.get(owner).filter(|keys| !keys.is_empty())
+        })?;
+    all_keys.split_first()
 }
 
 #[inline]
@@ -864,11 +1480,123 @@ fn is_list(ty: &Type) -> bool {
     matches!(ty.base, BaseType::List(_))
 }
 
+#[inline]
+fn element_type(ty: &Type) -> &Type {
+    match &ty.base {
+        BaseType::List(element) => element,
+        BaseType::Named(_) => ty,
+    }
+}
+
+#[allow(clippy::result_large_err)]
 #[instrument(ret, level = "trace")]
+/// The operation of the document selected by the requested operation name,
+/// failing with the names of the operations of the document otherwise.
+/// The `@defer` directive of a fragment, unless it is disabled by its `if`
+/// argument.
+fn enabled_defer<'a>(directives: &'a [Positioned<Directive>], variables: &Variables) -> Option<&'a Directive> {
+    let directive = &directives
+        .iter()
+        .find(|directive| directive.node.name.node == "defer")?
+        .node;
+    let enabled = match directive.get_argument("if").map(|value| &value.node) {
+        Some(Value::Boolean(enabled)) => *enabled,
+        Some(Value::Variable(name)) => !matches!(variables.get(name), Some(ConstValue::Boolean(false))),
+        _ => true,
+    };
+    enabled.then_some(directive)
+}
+
+/// The warnings of the enabled `@defer` directives of a query nested in its
+/// fields, whose fragments are delivered with their parent field as only the
+/// fragments of the root selection set are deferred.
+pub fn nested_defer_warnings(
+    document: &ExecutableDocument,
+    operation_name: Option<&str>,
+    variables: &Variables,
+) -> Vec<String> {
+    fn nested_defers_rec<'a>(
+        document: &'a ExecutableDocument,
+        variables: &Variables,
+        parent_field: Option<&'a str>,
+        selection_set: &'a SelectionSet,
+        visited_fragments: &mut HashSet<(&'a str, Option<&'a str>)>,
+        warnings: &mut Vec<String>,
+    ) {
+        for selection in &selection_set.items {
+            let (directives, selection_set) = match &selection.node {
+                Selection::Field(field) => {
+                    let response_key = field.node.response_key().node.as_str();
+                    nested_defers_rec(
+                        document,
+                        variables,
+                        Some(response_key),
+                        &field.node.selection_set.node,
+                        visited_fragments,
+                        warnings,
+                    );
+                    continue;
+                },
+                Selection::FragmentSpread(fragment_spread) => {
+                    // The fragments are checked once in each field, however
+                    // many times they are spread.
+                    let fragment_name = fragment_spread.node.fragment_name.node.as_str();
+                    if !visited_fragments.insert((fragment_name, parent_field)) {
+                        continue;
+                    }
+                    match document.fragments.get(fragment_name) {
+                        Some(fragment) => (&fragment_spread.node.directives, &fragment.node.selection_set.node),
+                        None => continue,
+                    }
+                },
+                Selection::InlineFragment(inline_fragment) => (
+                    &inline_fragment.node.directives,
+                    &inline_fragment.node.selection_set.node,
+                ),
+            };
+            if let Some(parent_field) = parent_field {
+                if enabled_defer(directives, variables).is_some() {
+                    let warning = format!(
+                        r#"The "@defer" of a fragment in the field "{}" is ignored, only the fragments of the root selection set are deferred."#,
+                        parent_field
+                    );
+                    if !warnings.contains(&warning) {
+                        warnings.push(warning);
+                    }
+                }
+            }
+            nested_defers_rec(
+                document,
+                variables,
+                parent_field,
+                selection_set,
+                visited_fragments,
+                warnings,
+            );
+        }
+    }
+
+    let mut warnings = Vec::new();
+    if let Ok(operation) = get_operation(document, operation_name) {
+        if operation.node.ty == OperationType::Query {
+            nested_defers_rec(
+                document,
+                variables,
+                None,
+                &operation.node.selection_set.node,
+                &mut HashSet::new(),
+                &mut warnings,
+            );
+        }
+    }
+    warnings
+}
+
+#[allow(clippy::result_large_err)]
 fn get_operation<'a>(
     document: &'a ExecutableDocument,
     operation_name: Option<&str>,
-) -> &'a Positioned<OperationDefinition> {
+) -> Result<&'a Positioned<OperationDefinition>, Response> {
     let operation = if let Some(operation_name) = operation_name {
         match &document.operations {
             DocumentOperations::Single(_) => None,
@@ -881,7 +1609,69 @@ fn get_operation<'a>(
             DocumentOperations::Multiple(_) => None,
         }
     };
-    operation.expect("The query validator should find this error.")
+    if let Some(operation) = operation {
+        return Ok(operation);
+    }
+
+    let available = match &document.operations {
+        DocumentOperations::Single(_) => "an anonymous operation".to_string(),
+        DocumentOperations::Multiple(operations) => {
+            let mut names = operations
+                .keys()
+                .map(|name| format!(r#""{}""#, name))
+                .collect::<Vec<_>>();
+            names.sort();
+            names.join(", ")
+        },
+    };
+    let message = match operation_name {
+        Some(operation_name) => format!(
+            r#"Unknown operation named "{}", the document defines {}."#,
+            operation_name, available
+        ),
+        None => format!("An operation name is required, the document defines {}.", available),
+    };
+    Err(Response {
+        data: ConstValue::Null,
+        errors: vec![ServerError::new(message)],
+        extensions: Default::default(),
+        headers: Default::default(),
+    })
+}
+
+/// The alias the keys of entities are selected under in the queries of the
+/// services, extended with underscores until no response key of the
+/// document starts with it, so that the keys never collide with the fields
+/// of the operation.
+fn unique_key_alias(document: &ExecutableDocument) -> String {
+    fn collect_response_keys<'a>(selection_set: &'a SelectionSet, response_keys: &mut HashSet<&'a str>) {
+        for selection in &selection_set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    response_keys.insert(field.node.response_key().node.as_str());
+                    collect_response_keys(&field.node.selection_set.node, response_keys);
+                },
+                Selection::InlineFragment(inline_fragment) => {
+                    collect_response_keys(&inline_fragment.node.selection_set.node, response_keys);
+                },
+                Selection::FragmentSpread(_) => {},
+            }
+        }
+    }
+
+    let mut response_keys = HashSet::new();
+    for (_, operation) in document.operations.iter() {
+        collect_response_keys(&operation.node.selection_set.node, &mut response_keys);
+    }
+    for fragment in document.fragments.values() {
+        collect_response_keys(&fragment.node.selection_set.node, &mut response_keys);
+    }
+
+    let mut key_alias = DEFAULT_KEY_ALIAS.to_string();
+    while response_keys.iter().any(|key| key.starts_with(&key_alias)) {
+        key_alias.push('_');
+    }
+    key_alias
 }
 
 fn referenced_variables<'a>(
@@ -905,7 +1695,7 @@ fn referenced_variables<'a>(
                                 .get(name)
                                 .zip(variable_definitions.iter().find(|d| d.node.name.node.as_str() == name))
                             {
-                                variables_ref.variables.insert(name, value);
+                                variables_ref.variables.insert(name, Cow::Borrowed(value));
                                 variables_definition_ref.insert(name, &definition.node);
                             } else {
                                 let definition = variable_definitions
@@ -924,7 +1714,7 @@ fn referenced_variables<'a>(
                                     .get(name)
                                     .zip(variable_definitions.iter().find(|d| d.node.name.node.as_str() == name))
                                 {
-                                    variables_ref.variables.insert(name, value);
+                                    variables_ref.variables.insert(name, Cow::Borrowed(value));
                                     variables_definition_ref.insert(name, &definition.node);
                                 } else {
                                     let definition = variable_definitions
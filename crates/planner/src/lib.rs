@@ -5,9 +5,12 @@ mod plan;
 mod request;
 mod response;
 mod types;
+mod visualize;
 
-pub use builder::PlanBuilder;
+pub use builder::{nested_defer_warnings, PlanBuilder};
 pub use plan::{
+    DeferNode,
+    DeferredNode,
     FetchNode,
     FlattenNode,
     IntrospectionDirective,
@@ -23,4 +26,5 @@ pub use plan::{
     SubscribeNode,
 };
 pub use request::Request;
-pub use response::{ErrorPath, Response, ServerError};
+pub use response::{ErrorPath, IncrementalPayload, IncrementalResponse, Response, ServerError, WARNINGS_EXTENSION};
+pub use visualize::PlanFormat;
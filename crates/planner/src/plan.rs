@@ -12,6 +12,14 @@ use crate::{
     Request,
 };
 
+/// The alias the keys of entities are selected under, unless a response key
+/// of the operation starts with it.
+pub const DEFAULT_KEY_ALIAS: &str = "__key";
+
+fn is_default_key_alias(key_alias: &&str) -> bool {
+    *key_alias == DEFAULT_KEY_ALIAS
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum PlanNode<'a> {
@@ -30,6 +38,14 @@ impl PlanNode<'_> {
             _ => self,
         }
     }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            PlanNode::Sequence(node) => node.nodes.iter().all(PlanNode::is_empty),
+            PlanNode::Parallel(node) => node.nodes.iter().all(PlanNode::is_empty),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -98,6 +114,23 @@ pub struct ParallelNode<'a> {
     pub nodes: Vec<PlanNode<'a>>,
 }
 
+impl<'a> ParallelNode<'a> {
+    /// Order the nodes by service and path, so that plans do not depend on
+    /// the order in which the subgraphs were composed.
+    pub(crate) fn new(mut nodes: Vec<PlanNode<'a>>) -> Self {
+        nodes.sort_by_cached_key(|node| match node {
+            PlanNode::Fetch(fetch) => (fetch.service, String::new(), None),
+            PlanNode::Flatten(flatten) => (
+                flatten.service,
+                flatten.path.to_string(),
+                flatten.query.entity_types.first().copied(),
+            ),
+            _ => ("", String::new(), None),
+        });
+        Self { nodes }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct IntrospectionDirective {
     pub name: Name,
@@ -151,14 +184,35 @@ impl FetchNode<'_> {
 #[serde(rename_all = "camelCase")]
 pub struct FlattenNode<'a> {
     pub path: ResponsePath<'a>,
+    /// The alias the keys of the entities are selected under, followed by
+    /// the prefix, such as `__key1_id`.
+    #[serde(skip_serializing_if = "is_default_key_alias")]
+    pub key_alias: &'a str,
     pub prefix: usize,
+    /// Whether the entities select their keys again under the prefix, to be
+    /// checked against the representations.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub check_keys: bool,
+    /// The prefixes of the alternate keys of the entities, used when the
+    /// service resolves none of them by the first key.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternate_prefixes: Vec<usize>,
     pub service: &'a str,
+    /// The interfaces the service resolves the entities of these types as,
+    /// which replace the `__typename` of their representations.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub interface_objects: IndexMap<&'a str, &'a str>,
     #[serde(skip_serializing_if = "VariablesRef::is_empty")]
     pub variables: VariablesRef<'a>,
     pub query: FetchQuery<'a>,
 }
 
 impl FlattenNode<'_> {
+    /// The alias prefix of the keys selected for the given prefix.
+    pub fn key_prefix(&self, prefix: usize) -> String {
+        format!("{}{}_", self.key_alias, prefix)
+    }
+
     pub fn to_request(&self, representations: Variables) -> Request {
         Request::new(self.query.to_string())
             .variables(representations)
@@ -174,9 +228,28 @@ pub struct SubscribeNode<'a> {
     pub flatten_node: Option<PlanNode<'a>>,
 }
 
+/// A part of the query that may be delivered after the initial response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeferredNode<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<&'a str>,
+    pub node: PlanNode<'a>,
+}
+
+/// A query split into a primary part and parts that may be delivered
+/// incrementally.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeferNode<'a> {
+    pub primary: PlanNode<'a>,
+    pub deferred: Vec<DeferredNode<'a>>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum RootNode<'a> {
     Subscribe(SubscribeNode<'a>),
     Query(PlanNode<'a>),
+    Defer(DeferNode<'a>),
 }
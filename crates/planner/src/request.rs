@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use value::{ConstValue, Variables};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Request {
     pub query: String,
+    #[serde(rename = "operationName", alias = "operation")]
     pub operation: Option<String>,
     #[serde(skip_serializing_if = "variables_is_empty", default)]
     pub variables: Variables,
@@ -11,7 +11,7 @@ pub enum ErrorPath {
     Index(usize),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ServerError {
     pub message: String,
 
@@ -23,6 +23,11 @@ pub struct ServerError {
 
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
     pub extensions: HashMap<String, ConstValue>,
+
+    /// The status of the subgraph response the gateway failed a fetch with,
+    /// never sent to the clients nor read from the subgraphs.
+    #[serde(skip)]
+    pub status: Option<u16>,
 }
 
 impl ServerError {
@@ -32,6 +37,7 @@ impl ServerError {
             path: Default::default(),
             locations: Default::default(),
             extensions: Default::default(),
+            status: None,
         }
     }
 }
@@ -49,3 +55,93 @@ pub struct Response {
     #[serde(skip_serializing)]
     pub headers: Option<HashMap<String, Vec<String>>>,
 }
+
+/// The extension listing the warnings of a response.
+pub const WARNINGS_EXTENSION: &str = "warnings";
+
+impl Response {
+    /// Add a warning to the `warnings` extension, unless it is already
+    /// listed.
+    ///
+    /// Warnings are actionable signals for clients, such as a limit injected
+    /// into the operation, that do not fail the request.
+    pub fn add_warning(&mut self, warning: impl Into<String>) {
+        let warning = ConstValue::String(warning.into());
+        match self.extensions.get_mut(WARNINGS_EXTENSION) {
+            Some(ConstValue::List(warnings)) => {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            },
+            _ => {
+                self.extensions
+                    .insert(WARNINGS_EXTENSION.to_string(), ConstValue::List(vec![warning]));
+            },
+        }
+    }
+
+    /// The warnings listed in the `warnings` extension.
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        let warnings = match self.extensions.get(WARNINGS_EXTENSION) {
+            Some(ConstValue::List(warnings)) => warnings.as_slice(),
+            _ => &[],
+        };
+        warnings.iter().filter_map(|warning| match warning {
+            ConstValue::String(warning) => Some(warning.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Merge extensions into those of the response, appending their warnings
+    /// to the `warnings` extension.
+    pub fn merge_extensions(&mut self, extensions: HashMap<String, ConstValue>) {
+        for (name, value) in extensions {
+            match value {
+                ConstValue::List(warnings) if name == WARNINGS_EXTENSION => {
+                    for warning in warnings {
+                        if let ConstValue::String(warning) = warning {
+                            self.add_warning(warning);
+                        }
+                    }
+                },
+                value => {
+                    self.extensions.insert(name, value);
+                },
+            }
+        }
+    }
+}
+
+/// A deferred part of the response delivered after the initial payload.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncrementalPayload {
+    pub data: ConstValue,
+
+    pub path: Vec<ConstValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub label: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<ServerError>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub extensions: HashMap<String, ConstValue>,
+}
+
+/// A payload of an incrementally delivered response.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum IncrementalResponse {
+    Initial {
+        #[serde(flatten)]
+        response: Response,
+        #[serde(rename = "hasNext")]
+        has_next: bool,
+    },
+    Subsequent {
+        incremental: Vec<IncrementalPayload>,
+        #[serde(rename = "hasNext")]
+        has_next: bool,
+    },
+}
@@ -1,4 +1,8 @@
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
 
 use graphgate_schema::{KeyFields, MetaType};
 use indexmap::IndexMap;
@@ -18,11 +22,18 @@ use crate::plan::ResponsePath;
 #[derive(Debug)]
 pub struct FieldRef<'a> {
     pub field: &'a Field,
+    /// The arguments of the field with their enum values named as the
+    /// service names them, if it names any differently.
+    pub arguments: Option<Vec<(Positioned<Name>, Positioned<Value>)>>,
+    /// The supergraph values of the enum values of the field, by value of
+    /// the service, if it names any differently.
+    pub enum_values: Option<&'a HashMap<String, String>>,
     pub selection_set: SelectionRefSet<'a>,
 }
 
 #[derive(Debug)]
 pub struct RequiredRef<'a> {
+    pub key_alias: &'a str,
     pub prefix: usize,
     pub fields: &'a KeyFields,
     pub requires: Option<&'a KeyFields>,
@@ -48,9 +59,94 @@ impl Display for SelectionRefSet<'_> {
     }
 }
 
+impl<'a> SelectionRefSet<'a> {
+    /// Add the selections of the possible types of an abstract type as inline
+    /// fragments.
+    ///
+    /// When every possible type is selected, the selections found in all the
+    /// fragments are hoisted out of them if the abstract type defines them in
+    /// `service`, the service queried, and the fragments left empty are
+    /// dropped, so that interface-heavy queries do not repeat them for each
+    /// type.
+    pub fn push_fragments(
+        &mut self,
+        parent_type: &MetaType,
+        service: &str,
+        fragments: IndexMap<&'a str, SelectionRefSet<'a>>,
+    ) {
+        let mut fragments = fragments
+            .into_iter()
+            .filter(|(_, selection_set)| !selection_set.0.is_empty())
+            .collect::<Vec<_>>();
+
+        let all_types_selected = !fragments.is_empty() &&
+            parent_type
+                .possible_types
+                .iter()
+                .all(|ty| fragments.iter().any(|(name, _)| *name == ty.as_str()));
+        if all_types_selected {
+            let printed = fragments
+                .iter()
+                .map(|(_, selection_set)| selection_set.0.iter().map(ToString::to_string).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let mut common = Vec::new();
+            for (selection, printed_selection) in fragments[0].1 .0.iter().zip(&printed[0]) {
+                let hoistable = match selection {
+                    SelectionRef::IntrospectionTypename => true,
+                    SelectionRef::FieldRef(field) => {
+                        field.field.name.node == "__typename" ||
+                            parent_type.fields.get(&field.field.name.node).is_some_and(|field| {
+                                // The fields without a service are defined by every service defining
+                                // the type.
+                                field
+                                    .service
+                                    .as_deref()
+                                    .or(parent_type.owner.as_deref())
+                                    .is_none_or(|field_service| field_service == service)
+                            })
+                    },
+                    _ => false,
+                };
+                if hoistable &&
+                    !common.contains(printed_selection) &&
+                    printed[1..]
+                        .iter()
+                        .all(|selections| selections.contains(printed_selection))
+                {
+                    common.push(printed_selection.clone());
+                }
+            }
+
+            let mut hoisted = Vec::new();
+            for (idx, ((_, selection_set), printed)) in fragments.iter_mut().zip(printed).enumerate() {
+                let selections = std::mem::take(&mut selection_set.0);
+                for (selection, printed_selection) in selections.into_iter().zip(printed) {
+                    if !common.contains(&printed_selection) {
+                        selection_set.0.push(selection);
+                    } else if idx == 0 && !hoisted.contains(&printed_selection) {
+                        hoisted.push(printed_selection);
+                        self.0.push(selection);
+                    }
+                }
+            }
+        }
+
+        for (ty, selection_set) in fragments {
+            if !selection_set.0.is_empty() {
+                self.0.push(SelectionRef::InlineFragment {
+                    type_condition: Some(ty),
+                    selection_set,
+                });
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct FetchQuery<'a> {
-    pub entity_type: Option<&'a str>,
+    /// The types of the entities fetched, whose selections are inline
+    /// fragments of the selection set. Empty for root fields.
+    pub entity_types: Vec<&'a str>,
     pub operation_type: OperationType,
     pub variable_definitions: VariableDefinitionsRef<'a>,
     pub selection_set: SelectionRefSet<'a>,
@@ -58,30 +154,121 @@ pub struct FetchQuery<'a> {
 
 impl Display for FetchQuery<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        match self.entity_type {
-            Some(entity_type) => {
-                write!(
-                    f,
-                    "query($representations:[_Any!]!{}{}) {{ _entities(representations:$representations) {{ ... on {} \
-                     {} }} }}",
-                    if self.variable_definitions.variables.is_empty() {
-                        ""
-                    } else {
-                        ", "
+        if !self.entity_types.is_empty() {
+            return write!(
+                f,
+                "query($representations:[_Any!]!{}{}) {{ _entities(representations:$representations) {} }}",
+                if self.variable_definitions.variables.is_empty() {
+                    ""
+                } else {
+                    ", "
+                },
+                self.variable_definitions,
+                self.selection_set
+            );
+        }
+        write!(f, "{}", self.operation_type)?;
+        if !self.variable_definitions.variables.is_empty() {
+            write!(f, "({})", self.variable_definitions)?;
+        }
+        write!(f, "\n{}", self.selection_set)
+    }
+}
+
+impl FetchQuery<'_> {
+    /// Rename the enum values of the data fetched by this query from the
+    /// values of the service to their supergraph values.
+    pub fn rename_enum_values(&self, data: &mut ConstValue) {
+        if !has_enum_values(&self.selection_set) {
+            return;
+        }
+        if self.entity_types.is_empty() {
+            rename_enum_values_rec(&[&self.selection_set], data);
+        } else if let ConstValue::Object(object) = data {
+            if let Some(entities) = object.get_mut("_entities") {
+                rename_enum_values_rec(&[&self.selection_set], entities);
+            }
+        }
+    }
+}
+
+fn has_enum_values(selection_set: &SelectionRefSet<'_>) -> bool {
+    selection_set.0.iter().any(|selection| match selection {
+        SelectionRef::FieldRef(field) => field.enum_values.is_some() || has_enum_values(&field.selection_set),
+        SelectionRef::InlineFragment { selection_set, .. } => has_enum_values(selection_set),
+        _ => false,
+    })
+}
+
+/// The fields of the selection sets by response key, including those of
+/// their inline fragments.
+///
+/// The fields of a response key have the same shape in a valid query, so
+/// the fragments need not be matched against the type of the object.
+fn collect_fields<'s, 'a>(
+    selection_set: &'s SelectionRefSet<'a>,
+    fields: &mut IndexMap<&'s str, Vec<&'s FieldRef<'a>>>,
+) {
+    for selection in &selection_set.0 {
+        match selection {
+            SelectionRef::FieldRef(field) => fields
+                .entry(field.field.response_key().node.as_str())
+                .or_default()
+                .push(field),
+            SelectionRef::InlineFragment { selection_set, .. } => collect_fields(selection_set, fields),
+            _ => {},
+        }
+    }
+}
+
+fn rename_enum_values_rec(selection_sets: &[&SelectionRefSet<'_>], data: &mut ConstValue) {
+    match data {
+        ConstValue::List(items) => {
+            for item in items {
+                rename_enum_values_rec(selection_sets, item);
+            }
+        },
+        ConstValue::Object(object) => {
+            let mut fields = IndexMap::new();
+            for selection_set in selection_sets {
+                collect_fields(selection_set, &mut fields);
+            }
+            for (response_key, fields) in fields {
+                let value = match object.get_mut(response_key) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                match fields[0].enum_values {
+                    Some(enum_values) => rename_enum_value(enum_values, value),
+                    None => {
+                        let selection_sets = fields.iter().map(|field| &field.selection_set).collect::<Vec<_>>();
+                        rename_enum_values_rec(&selection_sets, value);
                     },
-                    self.variable_definitions,
-                    entity_type,
-                    self.selection_set
-                )
-            },
-            None => {
-                write!(f, "{}", self.operation_type)?;
-                if !self.variable_definitions.variables.is_empty() {
-                    write!(f, "({})", self.variable_definitions)?;
                 }
-                write!(f, "\n{}", self.selection_set)
-            },
-        }
+            }
+        },
+        _ => {},
+    }
+}
+
+fn rename_enum_value(enum_values: &HashMap<String, String>, value: &mut ConstValue) {
+    match value {
+        ConstValue::String(name) => {
+            if let Some(supergraph_value) = enum_values.get(name.as_str()) {
+                *name = supergraph_value.clone();
+            }
+        },
+        ConstValue::Enum(name) => {
+            if let Some(supergraph_value) = enum_values.get(name.as_str()) {
+                *name = Name::new(supergraph_value);
+            }
+        },
+        ConstValue::List(items) => {
+            for item in items {
+                rename_enum_value(enum_values, item);
+            }
+        },
+        _ => {},
     }
 }
 
@@ -121,7 +308,7 @@ fn stringify_directives(f: &mut Formatter<'_>, directives: &[Positioned<Directiv
     Ok(())
 }
 
-fn stringify_key_fields(f: &mut Formatter<'_>, prefix: usize, fields: &KeyFields) -> FmtResult {
+fn stringify_key_fields(f: &mut Formatter<'_>, key_alias: &str, prefix: usize, fields: &KeyFields) -> FmtResult {
     fn stringify_key_fields_no_prefix(f: &mut Formatter<'_>, fields: &KeyFields) -> FmtResult {
         if fields.is_empty() {
             return Ok(());
@@ -138,7 +325,7 @@ fn stringify_key_fields(f: &mut Formatter<'_>, prefix: usize, fields: &KeyFields
     }
 
     for (field_name, children) in fields.iter() {
-        write!(f, " __key{}_{}:{}", prefix, field_name, field_name)?;
+        write!(f, " {}{}_{}:{}", key_alias, prefix, field_name, field_name)?;
         stringify_key_fields_no_prefix(f, children)?;
     }
     Ok(())
@@ -150,15 +337,22 @@ fn stringify_selection_ref_set_rec(f: &mut Formatter<'_>, selection_set: &Select
         if idx > 0 {
             write!(f, " ")?;
         }
+        write!(f, "{}", selection)?;
+    }
+    write!(f, " }}")
+}
 
-        match selection {
+impl Display for SelectionRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
             SelectionRef::FieldRef(field) => {
                 if let Some(alias) = &field.field.alias {
                     write!(f, "{}:", alias.node)?;
                 }
                 write!(f, "{}", field.field.name.node)?;
-                if !field.field.arguments.is_empty() {
-                    stringify_argument(f, &field.field.arguments)?;
+                let arguments = field.arguments.as_deref().unwrap_or(&field.field.arguments);
+                if !arguments.is_empty() {
+                    stringify_argument(f, arguments)?;
                 }
                 if !field.field.directives.is_empty() {
                     write!(f, " ")?;
@@ -168,16 +362,20 @@ fn stringify_selection_ref_set_rec(f: &mut Formatter<'_>, selection_set: &Select
                     write!(f, " ")?;
                     stringify_selection_ref_set_rec(f, &field.selection_set)?;
                 }
+                Ok(())
             },
-            SelectionRef::IntrospectionTypename => {
-                write!(f, "__typename")?;
-            },
+            SelectionRef::IntrospectionTypename => write!(f, "__typename"),
             SelectionRef::RequiredRef(require_ref) => {
-                write!(f, "__key{}___typename:__typename", require_ref.prefix,)?;
-                stringify_key_fields(f, require_ref.prefix, require_ref.fields)?;
+                write!(
+                    f,
+                    "{}{}___typename:__typename",
+                    require_ref.key_alias, require_ref.prefix
+                )?;
+                stringify_key_fields(f, require_ref.key_alias, require_ref.prefix, require_ref.fields)?;
                 if let Some(requires) = require_ref.requires {
-                    stringify_key_fields(f, require_ref.prefix, requires)?;
+                    stringify_key_fields(f, require_ref.key_alias, require_ref.prefix, requires)?;
                 }
+                Ok(())
             },
             SelectionRef::InlineFragment {
                 type_condition,
@@ -187,67 +385,132 @@ fn stringify_selection_ref_set_rec(f: &mut Formatter<'_>, selection_set: &Select
                     Some(type_condition) => write!(f, "... on {} ", type_condition)?,
                     None => write!(f, "... ")?,
                 }
-                stringify_selection_ref_set_rec(f, selection_set)?;
+                stringify_selection_ref_set_rec(f, selection_set)
             },
         }
     }
-    write!(f, " }}")
 }
 
 pub trait RootGroup<'a> {
-    fn selection_set_mut(&mut self, service: &'a str) -> &mut SelectionRefSet<'a>;
+    /// The selection set of the root field with the response key fetched from
+    /// `service`, and the group of the entities to fetch for it.
+    fn selection_set_mut(
+        &mut self,
+        service: &'a str,
+        response_key: &'a str,
+    ) -> (&mut SelectionRefSet<'a>, &mut FetchEntityGroup<'a>);
+
+    /// The batches of root fetches, executed one after the other.
+    fn into_batches(self) -> Vec<RootBatch<'a>>;
+}
 
-    fn into_selection_set(self) -> Vec<(&'a str, SelectionRefSet<'a>)>;
+/// Root fetches executed together, followed by the fetches of the entities
+/// they return.
+#[derive(Default)]
+pub struct RootBatch<'a> {
+    pub fetches: Vec<(&'a str, SelectionRefSet<'a>)>,
+    pub fetch_entity_group: FetchEntityGroup<'a>,
 }
 
 #[derive(Default)]
-pub struct QueryRootGroup<'a>(IndexMap<&'a str, SelectionRefSet<'a>>);
+pub struct QueryRootGroup<'a> {
+    selection_sets: IndexMap<&'a str, SelectionRefSet<'a>>,
+    fetch_entity_group: FetchEntityGroup<'a>,
+}
 
 impl<'a> RootGroup<'a> for QueryRootGroup<'a> {
-    fn selection_set_mut(&mut self, service: &'a str) -> &mut SelectionRefSet<'a> {
-        self.0.entry(service).or_default()
+    fn selection_set_mut(
+        &mut self,
+        service: &'a str,
+        _response_key: &'a str,
+    ) -> (&mut SelectionRefSet<'a>, &mut FetchEntityGroup<'a>) {
+        (
+            self.selection_sets.entry(service).or_default(),
+            &mut self.fetch_entity_group,
+        )
     }
 
-    fn into_selection_set(self) -> Vec<(&'a str, SelectionRefSet<'a>)> {
-        self.0.into_iter().collect()
+    fn into_batches(self) -> Vec<RootBatch<'a>> {
+        vec![RootBatch {
+            fetches: self.selection_sets.into_iter().collect(),
+            fetch_entity_group: self.fetch_entity_group,
+        }]
     }
 }
 
+/// The root fields of a mutation, each run of consecutive fields of the same
+/// service fetched in its own batch, so that the fields are executed in the
+/// order they are written and the entities a mutation returns are fetched
+/// before the next mutation is executed.
+///
+/// A field repeated with the same response key is merged into the batch of
+/// its first occurrence, so that it is executed only once.
 #[derive(Default)]
-pub struct MutationRootGroup<'a>(Vec<(&'a str, SelectionRefSet<'a>)>);
+pub struct MutationRootGroup<'a> {
+    batches: Vec<RootBatch<'a>>,
+    response_keys: HashMap<&'a str, usize>,
+}
 
 impl<'a> RootGroup<'a> for MutationRootGroup<'a> {
-    fn selection_set_mut(&mut self, service: &'a str) -> &mut SelectionRefSet<'a> {
-        if self
-            .0
-            .last()
-            .filter(|(last_service, _)| *last_service == service)
-            .is_some()
-        {
-            return &mut self.0.last_mut().unwrap().1;
-        }
-        self.0.push((service, Default::default()));
-        let last = self.0.last_mut().unwrap();
-        &mut last.1
+    fn selection_set_mut(
+        &mut self,
+        service: &'a str,
+        response_key: &'a str,
+    ) -> (&mut SelectionRefSet<'a>, &mut FetchEntityGroup<'a>) {
+        let index = match self.response_keys.get(response_key) {
+            Some(index) => *index,
+            None => {
+                if !self.batches.last().is_some_and(|batch| {
+                    batch
+                        .fetches
+                        .first()
+                        .is_some_and(|(last_service, _)| *last_service == service)
+                }) {
+                    self.batches.push(RootBatch {
+                        fetches: vec![(service, Default::default())],
+                        fetch_entity_group: Default::default(),
+                    });
+                }
+                self.response_keys.insert(response_key, self.batches.len() - 1);
+                self.batches.len() - 1
+            },
+        };
+        let batch = &mut self.batches[index];
+        (&mut batch.fetches[0].1, &mut batch.fetch_entity_group)
     }
 
-    fn into_selection_set(self) -> Vec<(&'a str, SelectionRefSet<'a>)> {
-        self.0
+    fn into_batches(self) -> Vec<RootBatch<'a>> {
+        self.batches
     }
 }
 
 #[derive(Debug)]
 pub struct FetchEntity<'a> {
-    pub parent_type: &'a MetaType,
     pub prefix: usize,
+    pub alternate_prefixes: Vec<usize>,
+    /// The fields to fetch by entity type. The possible types of an abstract
+    /// type whose keys have the same shape share their prefixes, so that they
+    /// are fetched by a single `_entities` call.
+    pub types: IndexMap<&'a str, EntityFields<'a>>,
+}
+
+#[derive(Debug)]
+pub struct EntityFields<'a> {
+    pub parent_type: &'a MetaType,
+    pub path: ResponsePath<'a>,
+    /// The key the entities are fetched by.
+    pub keys: &'a KeyFields,
     pub fields: Vec<&'a Field>,
 }
 
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct FetchEntityKey<'a> {
     pub service: &'a str,
+    /// The path of the entities, without the possible type of its last
+    /// segment.
     pub path: ResponsePath<'a>,
-    pub ty: &'a str,
+    /// The names and types of the key fields of the entities.
+    pub key_shape: String,
 }
 
 pub type FetchEntityGroup<'a> = IndexMap<FetchEntityKey<'a>, FetchEntity<'a>>;
@@ -309,7 +572,7 @@ impl Display for VariableDefinitionsRef<'_> {
 #[derive(Debug, Default, Serialize)]
 #[serde(transparent)]
 pub struct VariablesRef<'a> {
-    pub variables: IndexMap<&'a str, &'a ConstValue>,
+    pub variables: IndexMap<&'a str, Cow<'a, ConstValue>>,
 }
 
 impl VariablesRef<'_> {
@@ -0,0 +1,222 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult, Write},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{types::FetchQuery, PlanNode, RootNode};
+
+/// The formats a plan can be output in.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanFormat {
+    /// The serialized plan.
+    #[default]
+    Json,
+    /// A Graphviz DOT digraph.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+impl FromStr for PlanFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(PlanFormat::Json),
+            "dot" => Ok(PlanFormat::Dot),
+            "mermaid" => Ok(PlanFormat::Mermaid),
+            _ => Err(format!(
+                "Unknown plan format \"{}\", expected \"json\", \"dot\" or \"mermaid\".",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for PlanFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            PlanFormat::Json => "json",
+            PlanFormat::Dot => "dot",
+            PlanFormat::Mermaid => "mermaid",
+        })
+    }
+}
+
+/// Extra lines of the label of a fetch step, given its service and query.
+type Annotate<'f> = &'f dyn Fn(&str, &str) -> Option<String>;
+
+/// The steps of a plan and the edges from each step to the steps it is made
+/// of.
+#[derive(Default)]
+struct PlanGraph {
+    /// The label of each step, its lines, and whether it fetches from a
+    /// service.
+    steps: Vec<(Vec<String>, bool)>,
+    /// The edges between the indices of the steps, with an optional label.
+    edges: Vec<(usize, usize, Option<String>)>,
+}
+
+impl PlanGraph {
+    fn new(root: &RootNode, annotate: Annotate) -> Self {
+        let mut graph = Self::default();
+        match root {
+            RootNode::Query(node) => {
+                graph.add_node(node, annotate);
+            },
+            RootNode::Subscribe(node) => {
+                let step = graph.add_step(vec!["Subscribe".to_string()], false);
+                for fetch in &node.subscribe_nodes {
+                    let lines = vec!["Fetch".to_string(), fetch.service.to_string()];
+                    let child = graph.add_fetch_step(lines, fetch.service, &fetch.query, annotate);
+                    graph.edges.push((step, child, None));
+                }
+                if let Some(flatten_node) = &node.flatten_node {
+                    let child = graph.add_node(flatten_node, annotate);
+                    graph.edges.push((step, child, Some("event".to_string())));
+                }
+            },
+            RootNode::Defer(node) => {
+                let step = graph.add_step(vec!["Defer".to_string()], false);
+                let child = graph.add_node(&node.primary, annotate);
+                graph.edges.push((step, child, Some("primary".to_string())));
+                for deferred in &node.deferred {
+                    let child = graph.add_node(&deferred.node, annotate);
+                    let label = match deferred.label {
+                        Some(label) => format!("deferred {}", label),
+                        None => "deferred".to_string(),
+                    };
+                    graph.edges.push((step, child, Some(label)));
+                }
+            },
+        }
+        graph
+    }
+
+    fn add_step(&mut self, lines: Vec<String>, fetches: bool) -> usize {
+        self.steps.push((lines, fetches));
+        self.steps.len() - 1
+    }
+
+    /// Add the step of a fetch from the service, labeled with `lines` and
+    /// those `annotate` returns for it.
+    fn add_fetch_step(
+        &mut self,
+        mut lines: Vec<String>,
+        service: &str,
+        query: &FetchQuery,
+        annotate: Annotate,
+    ) -> usize {
+        lines.extend(annotate(service, &query.to_string()));
+        self.add_step(lines, true)
+    }
+
+    fn add_node(&mut self, node: &PlanNode, annotate: Annotate) -> usize {
+        match node {
+            PlanNode::Sequence(sequence) => {
+                let step = self.add_step(vec!["Sequence".to_string()], false);
+                for (idx, node) in sequence.nodes.iter().enumerate() {
+                    let child = self.add_node(node, annotate);
+                    self.edges.push((step, child, Some((idx + 1).to_string())));
+                }
+                step
+            },
+            PlanNode::Parallel(parallel) => {
+                let step = self.add_step(vec!["Parallel".to_string()], false);
+                for node in &parallel.nodes {
+                    let child = self.add_node(node, annotate);
+                    self.edges.push((step, child, None));
+                }
+                step
+            },
+            PlanNode::Introspection(_) => self.add_step(vec!["Introspection".to_string()], false),
+            PlanNode::Fetch(fetch) => {
+                let lines = vec!["Fetch".to_string(), fetch.service.to_string()];
+                self.add_fetch_step(lines, fetch.service, &fetch.query, annotate)
+            },
+            PlanNode::Flatten(flatten) => {
+                let lines = vec![
+                    "Flatten".to_string(),
+                    flatten.service.to_string(),
+                    flatten.path.to_string(),
+                ];
+                self.add_fetch_step(lines, flatten.service, &flatten.query, annotate)
+            },
+        }
+    }
+}
+
+impl RootNode<'_> {
+    /// Output the plan as a Graphviz DOT digraph, with a box for each fetch
+    /// and an ellipse for each step made of other steps.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_annotated(&|_, _| None)
+    }
+
+    /// Output the plan as a Graphviz DOT digraph, with the lines `annotate`
+    /// returns for each fetch, given its service and query, in its label.
+    pub fn to_dot_annotated(&self, annotate: &dyn Fn(&str, &str) -> Option<String>) -> String {
+        let graph = PlanGraph::new(self, annotate);
+        let mut dot = String::from("digraph plan {\n");
+        for (idx, (lines, fetches)) in graph.steps.iter().enumerate() {
+            let label = lines
+                .iter()
+                .map(|line| escape_dot(line))
+                .collect::<Vec<_>>()
+                .join("\\n");
+            let shape = if *fetches { "box" } else { "ellipse" };
+            writeln!(dot, "    n{} [label=\"{}\", shape={}];", idx, label, shape).unwrap();
+        }
+        for (from, to, label) in &graph.edges {
+            match label {
+                Some(label) => writeln!(dot, "    n{} -> n{} [label=\"{}\"];", from, to, escape_dot(label)).unwrap(),
+                None => writeln!(dot, "    n{} -> n{};", from, to).unwrap(),
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Output the plan as a Mermaid flowchart, with a rectangle for each
+    /// fetch and a rounded rectangle for each step made of other steps.
+    pub fn to_mermaid(&self) -> String {
+        self.to_mermaid_annotated(&|_, _| None)
+    }
+
+    /// Output the plan as a Mermaid flowchart, with the lines `annotate`
+    /// returns for each fetch, given its service and query, in its label.
+    pub fn to_mermaid_annotated(&self, annotate: &dyn Fn(&str, &str) -> Option<String>) -> String {
+        let graph = PlanGraph::new(self, annotate);
+        let mut mermaid = String::from("flowchart TD\n");
+        for (idx, (lines, fetches)) in graph.steps.iter().enumerate() {
+            let label = lines
+                .iter()
+                .map(|line| escape_mermaid(line))
+                .collect::<Vec<_>>()
+                .join("<br/>");
+            if *fetches {
+                writeln!(mermaid, "    n{}[\"{}\"]", idx, label).unwrap();
+            } else {
+                writeln!(mermaid, "    n{}(\"{}\")", idx, label).unwrap();
+            }
+        }
+        for (from, to, label) in &graph.edges {
+            match label {
+                Some(label) => writeln!(mermaid, "    n{} -->|\"{}\"| n{}", from, escape_mermaid(label), to).unwrap(),
+                None => writeln!(mermaid, "    n{} --> n{}", from, to).unwrap(),
+            }
+        }
+        mermaid
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
@@ -1,7 +1,7 @@
 use std::fs;
 
 use globset::GlobBuilder;
-use graphgate_planner::PlanBuilder;
+use graphgate_planner::{nested_defer_warnings, PlanBuilder, PlanFormat};
 use graphgate_schema::ComposedSchema;
 use pretty_assertions::assert_eq;
 use tracing::debug;
@@ -103,3 +103,353 @@ fn test_federation() {
         assert_eq!(actual_node, expect_node);
     }
 }
+
+#[test]
+fn test_parallel_nodes_sorted_by_service() {
+    let collections_service_document = parser::parse_schema(include_str!("collections.graphql")).unwrap();
+    let collectibles_service_document = parser::parse_schema(include_str!("collectibles.graphql")).unwrap();
+    let schemas = [
+        ComposedSchema::combine([
+            ("collectibles".to_string(), collectibles_service_document.clone()),
+            ("collections".to_string(), collections_service_document.clone()),
+        ])
+        .unwrap(),
+        ComposedSchema::combine([
+            ("collections".to_string(), collections_service_document),
+            ("collectibles".to_string(), collectibles_service_document),
+        ])
+        .unwrap(),
+    ];
+
+    for schema in &schemas {
+        let document = parser::parse_query("{ collectionsAll { id } collectiblesAll { id } }").unwrap();
+        let builder = PlanBuilder::new(schema, document);
+        let plan = serde_json::to_value(builder.plan().unwrap()).unwrap();
+        assert_eq!(plan["type"], "parallel");
+        let services = plan["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["service"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(services, vec!["collectibles", "collections"]);
+    }
+}
+
+#[test]
+fn test_primary_fields() {
+    let schema = ComposedSchema::parse(include_str!("test.graphql")).unwrap();
+    let data = fs::read_to_string("./tests/primary_fields.text").unwrap();
+    let mut s = data.split("---");
+
+    while let Some(graphql) = s.next() {
+        let variables = s.next().unwrap();
+        let planner_json = s.next().unwrap();
+
+        let document = parser::parse_query(graphql).unwrap();
+        let builder = PlanBuilder::new(&schema, document)
+            .variables(serde_json::from_str(variables).unwrap())
+            .primary_fields(["me"]);
+        let expect_node: serde_json::Value = serde_json::from_str(planner_json).unwrap();
+        let actual_node = serde_json::to_value(builder.plan().unwrap()).unwrap();
+
+        assert_eq!(actual_node, expect_node);
+    }
+}
+
+#[test]
+fn test_interface_objects() {
+    let media = parser::parse_schema(
+        r#"
+        type Query { media: [Media!]! }
+        interface Media @key(fields: "id") { id: ID! title: String! }
+        type Book implements Media @key(fields: "id") { id: ID! title: String! }
+        type Movie implements Media @key(fields: "id") { id: ID! title: String! }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        type Query { topMedia: Media }
+        type Media @key(fields: "id") @interfaceObject { id: ID! reviews: [String!]! }
+        "#,
+    )
+    .unwrap();
+    let schema = ComposedSchema::combine([("media".to_string(), media), ("reviews".to_string(), reviews)]).unwrap();
+
+    // The implementations are fetched from the contributing service as the
+    // interface.
+    let document = parser::parse_query("{ media { title reviews } }").unwrap();
+    let plan = serde_json::to_value(PlanBuilder::new(&schema, document).plan().unwrap()).unwrap();
+    assert_eq!(
+        plan["nodes"][0]["query"],
+        "query\n{ media { title ... on Movie { __key1___typename:__typename __key1_id:id } ... on Book { \
+         __key1___typename:__typename __key1_id:id } } }"
+    );
+    assert_eq!(
+        plan["nodes"][1],
+        serde_json::json!({
+            "interfaceObjects": { "Book": "Media", "Movie": "Media" },
+            "path": "[media]",
+            "prefix": 1,
+            "query": "query($representations:[_Any!]!) { _entities(representations:$representations) { ... on Media { reviews } } }",
+            "service": "reviews",
+            "type": "flatten"
+        })
+    );
+
+    // The type of the objects returned by the contributing service is
+    // resolved by the owner of the interface.
+    let document = parser::parse_query("{ topMedia { __typename title reviews } }").unwrap();
+    let plan = serde_json::to_value(PlanBuilder::new(&schema, document).plan().unwrap()).unwrap();
+    assert_eq!(
+        plan["nodes"][0]["query"],
+        "query\n{ topMedia { __key1___typename:__typename __key1_id:id reviews } }"
+    );
+    assert_eq!(
+        plan["nodes"][1],
+        serde_json::json!({
+            "path": "topMedia",
+            "prefix": 1,
+            "query": "query($representations:[_Any!]!) { _entities(representations:$representations) { ... on Media { __typename title } } }",
+            "service": "media",
+            "type": "flatten"
+        })
+    );
+}
+
+#[test]
+fn test_alternate_keys() {
+    let products = parser::parse_schema(
+        r#"
+        type Query { topProducts: [Product!]! }
+        type Product @key(fields: "upc") @key(fields: "sku") { upc: String! sku: String! name: String! }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        type Query { reviews: [Review!]! }
+        type Review { body: String! product: Product! }
+        extend type Product @key(fields: "upc") { upc: String! @external }
+        "#,
+    )
+    .unwrap();
+    let inventory = parser::parse_schema(
+        r#"
+        type Query { stock: [Product!]! }
+        extend type Product @key(fields: "upc") @key(fields: "sku") {
+            upc: String! @external
+            sku: String! @external
+            inStock: Boolean!
+        }
+        "#,
+    )
+    .unwrap();
+    let schema = ComposedSchema::combine([
+        ("products".to_string(), products),
+        ("reviews".to_string(), reviews),
+        ("inventory".to_string(), inventory),
+    ])
+    .unwrap();
+
+    // The parent service doesn't define the alternate key, only the first
+    // key is selected.
+    let document = parser::parse_query("{ reviews { body product { name } } }").unwrap();
+    let plan = serde_json::to_value(PlanBuilder::new(&schema, document).plan().unwrap()).unwrap();
+    assert_eq!(
+        plan["nodes"][0]["query"],
+        "query\n{ reviews { body product { __key1___typename:__typename __key1_upc:upc } } }"
+    );
+    assert_eq!(plan["nodes"][1]["prefix"], 1);
+    assert!(plan["nodes"][1].get("alternatePrefixes").is_none());
+
+    // The parent service defines the alternate key, both keys are selected.
+    let document = parser::parse_query("{ stock { inStock name } }").unwrap();
+    let plan = serde_json::to_value(PlanBuilder::new(&schema, document).plan().unwrap()).unwrap();
+    assert_eq!(
+        plan["nodes"][0]["query"],
+        "query\n{ stock { inStock __key1___typename:__typename __key1_upc:upc __key2___typename:__typename \
+         __key2_sku:sku } }"
+    );
+    assert_eq!(plan["nodes"][1]["alternatePrefixes"], serde_json::json!([2]));
+}
+
+#[test]
+fn test_hoist_fields_of_current_service() {
+    let accounts = parser::parse_schema(
+        r#"
+        type Query { accounts: [Account!]! }
+        interface Account @key(fields: "id") { id: ID! }
+        type Personal implements Account @key(fields: "id") { id: ID! rating: Int! }
+        type Business implements Account @key(fields: "id") { id: ID! rating: Int! }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        type Account @key(fields: "id") @interfaceObject { id: ID! rating: Int! }
+        "#,
+    )
+    .unwrap();
+    let schema =
+        ComposedSchema::combine([("accounts".to_string(), accounts), ("reviews".to_string(), reviews)]).unwrap();
+
+    // `Account.rating` is composed from the reviews service, the accounts
+    // service only defines it on the objects.
+    let document =
+        parser::parse_query("{ accounts { ... on Personal { id rating } ... on Business { id rating } } }").unwrap();
+    let plan = serde_json::to_value(PlanBuilder::new(&schema, document).plan().unwrap()).unwrap();
+    assert_eq!(
+        plan["query"],
+        "query\n{ accounts { id ... on Business { rating } ... on Personal { rating } } }"
+    );
+}
+
+#[test]
+fn test_allowed_services() {
+    let schema = ComposedSchema::parse(include_str!("test.graphql")).unwrap();
+
+    // The fields of the allowed services are planned as usual.
+    let document = parser::parse_query(r#"{ me { id username } reviews(productId: "1") { body } }"#).unwrap();
+    let plan = serde_json::to_value(
+        PlanBuilder::new(&schema, document.clone())
+            .allowed_services(["accounts", "reviews"])
+            .plan()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        plan,
+        serde_json::to_value(PlanBuilder::new(&schema, document).plan().unwrap()).unwrap()
+    );
+
+    // The fields of the other services, at the root or of entities, fail
+    // the plan.
+    let document =
+        parser::parse_query(r#"{ me { id username reviews { body } } topProducts { upc } myName }"#).unwrap();
+    let response = PlanBuilder::new(&schema, document)
+        .allowed_services(["accounts"])
+        .plan()
+        .unwrap_err();
+    let messages = response
+        .errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, [
+        r#"The field "User.reviews" is resolved by the service "reviews", which is not allowed."#,
+        r#"The field "Query.topProducts" is resolved by the service "products", which is not allowed."#,
+    ]);
+}
+
+#[test]
+fn test_allowed_services_shareable() {
+    let accounts = parser::parse_schema(
+        r#"
+        type Query { me: User @shareable }
+        type User @key(fields: "id") { id: ID! name: String! @shareable }
+        "#,
+    )
+    .unwrap();
+    let profiles = parser::parse_schema(
+        r#"
+        type Query { me: User @shareable }
+        type User @key(fields: "id") { id: ID! name: String! @shareable bio: String! }
+        "#,
+    )
+    .unwrap();
+    let schema =
+        ComposedSchema::combine([("accounts".to_string(), accounts), ("profiles".to_string(), profiles)]).unwrap();
+
+    // The shareable fields are resolved by another allowed service defining
+    // them.
+    for (allowed_service, query) in [
+        ("accounts", "query\n{ me { name } }"),
+        ("profiles", "query\n{ me { name } }"),
+    ] {
+        let document = parser::parse_query("{ me { name } }").unwrap();
+        let plan = serde_json::to_value(
+            PlanBuilder::new(&schema, document)
+                .allowed_services([allowed_service])
+                .plan()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(plan["service"], allowed_service);
+        assert_eq!(plan["query"], query);
+    }
+
+    let document = parser::parse_query("{ me { name bio } }").unwrap();
+    let response = PlanBuilder::new(&schema, document)
+        .allowed_services(["accounts"])
+        .plan()
+        .unwrap_err();
+    assert_eq!(
+        response.errors[0].message,
+        r#"The field "User.bio" is resolved by the service "profiles", which is not allowed."#
+    );
+}
+
+#[test]
+fn test_nested_defer_warnings() {
+    let document = parser::parse_query(
+        r#"
+        query($defer: Boolean!) {
+            me {
+                id
+                ... @defer { username }
+                reviews { ...Body @defer(if: $defer) }
+            }
+            ... @defer { topProducts { upc } }
+        }
+
+        fragment Body on Review { body }
+        "#,
+    )
+    .unwrap();
+
+    let warnings = |defer: bool| {
+        let variables = serde_json::from_value(serde_json::json!({ "defer": defer })).unwrap();
+        nested_defer_warnings(&document, None, &variables)
+    };
+    assert_eq!(warnings(false), [
+        r#"The "@defer" of a fragment in the field "me" is ignored, only the fragments of the root selection set are deferred."#,
+    ]);
+    assert_eq!(warnings(true), [
+        r#"The "@defer" of a fragment in the field "me" is ignored, only the fragments of the root selection set are deferred."#,
+        r#"The "@defer" of a fragment in the field "reviews" is ignored, only the fragments of the root selection set are deferred."#,
+    ]);
+}
+
+#[test]
+fn test_visualize() {
+    let schema = ComposedSchema::parse(include_str!("test.graphql")).unwrap();
+    let document = parser::parse_query(r#"{ me { id username reviews { body } } }"#).unwrap();
+    let plan_builder = PlanBuilder::new(&schema, document);
+    let plan = plan_builder.plan().unwrap();
+
+    assert_eq!(
+        plan.to_dot(),
+        r#"digraph plan {
+    n0 [label="Sequence", shape=ellipse];
+    n1 [label="Fetch\naccounts", shape=box];
+    n2 [label="Flatten\nreviews\nme", shape=box];
+    n0 -> n1 [label="1"];
+    n0 -> n2 [label="2"];
+}
+"#
+    );
+    assert_eq!(
+        plan.to_mermaid(),
+        r#"flowchart TD
+    n0("Sequence")
+    n1["Fetch<br/>accounts"]
+    n2["Flatten<br/>reviews<br/>me"]
+    n0 -->|"1"| n1
+    n0 -->|"2"| n2
+"#
+    );
+    assert_eq!("mermaid".parse(), Ok(PlanFormat::Mermaid));
+    assert!("svg".parse::<PlanFormat>().is_err());
+}
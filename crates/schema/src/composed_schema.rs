@@ -30,7 +30,7 @@ use value::{ConstValue, Name};
 
 use crate::{type_ext::TypeExt, CombineError};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Deprecation {
     NoDeprecated,
     Deprecated { reason: Option<String> },
@@ -51,7 +51,7 @@ impl Deprecation {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MetaField {
     pub description: Option<String>,
     pub name: Name,
@@ -60,8 +60,66 @@ pub struct MetaField {
     pub deprecation: Deprecation,
 
     pub service: Option<String>,
+    /// The names of the services defining the field, sorted, several if it
+    /// is `@shareable`.
+    pub services: Vec<String>,
     pub requires: Option<KeyFields>,
     pub provides: Option<KeyFields>,
+
+    pub cost: Option<u64>,
+    pub list_size: Option<ListSize>,
+    pub cache_control: Option<CacheControl>,
+    /// The names of the `@tag` directives of the field.
+    pub tags: Vec<String>,
+    /// Whether the field is marked `@inaccessible`.
+    pub inaccessible: bool,
+    /// Whether the field requires an authenticated request, from
+    /// `@authenticated`.
+    pub authenticated: bool,
+    /// The sets of scopes of which the request must have any, from
+    /// `@requiresScopes`.
+    pub requires_scopes: Vec<Vec<String>>,
+}
+
+/// The size of a list field, from `@listSize`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ListSize {
+    pub assumed_size: Option<u64>,
+    pub slicing_arguments: Vec<Name>,
+    pub sized_fields: Vec<Name>,
+    pub require_one_slicing_argument: bool,
+}
+
+/// How long, and for whom, the values of a type or field may be cached, from
+/// `@cacheControl`.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct CacheControl {
+    pub max_age: Option<u64>,
+    pub scope: CacheScope,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum CacheScope {
+    #[default]
+    Public,
+    /// Cached per user only.
+    Private,
+}
+
+impl CacheControl {
+    /// The most restrictive of two hints.
+    pub fn merge(self, other: CacheControl) -> CacheControl {
+        CacheControl {
+            max_age: match (self.max_age, other.max_age) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+            scope: match (self.scope, other.scope) {
+                (CacheScope::Public, CacheScope::Public) => CacheScope::Public,
+                _ => CacheScope::Private,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -74,7 +132,7 @@ pub enum TypeKind {
     InputObject,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct KeyFields(IndexMap<Name, KeyFields>);
 
 impl Deref for KeyFields {
@@ -85,22 +143,30 @@ impl Deref for KeyFields {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MetaEnumValue {
     pub description: Option<String>,
     pub value: Name,
     pub deprecation: Deprecation,
+    pub tags: Vec<String>,
+    /// Whether the enum value is marked `@inaccessible`.
+    pub inaccessible: bool,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MetaInputValue {
     pub description: Option<String>,
     pub name: Name,
     pub ty: Type,
     pub default_value: Option<ConstValue>,
+    pub deprecation: Deprecation,
+    pub cost: Option<u64>,
+    pub tags: Vec<String>,
+    /// Whether the argument or input field is marked `@inaccessible`.
+    pub inaccessible: bool,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MetaType {
     pub description: Option<String>,
     pub name: Name,
@@ -113,6 +179,24 @@ pub struct MetaType {
     pub possible_types: IndexSet<Name>,
     pub enum_values: IndexMap<Name, MetaEnumValue>,
     pub input_fields: IndexMap<Name, MetaInputValue>,
+    pub cost: Option<u64>,
+    pub cache_control: Option<CacheControl>,
+    /// Whether the type is an input object of which exactly one field must
+    /// be set, from `@oneOf`.
+    pub one_of: bool,
+    /// The interface each service defines in place of the type with
+    /// `@interfaceObject`, by service.
+    pub interface_objects: HashMap<String, Name>,
+    /// The names of the `@tag` directives of the type.
+    pub tags: Vec<String>,
+    /// Whether the type is marked `@inaccessible`.
+    pub inaccessible: bool,
+    /// Whether the type requires an authenticated request, from
+    /// `@authenticated`.
+    pub authenticated: bool,
+    /// The sets of scopes of which the request must have any, from
+    /// `@requiresScopes`.
+    pub requires_scopes: Vec<Vec<String>>,
 }
 
 impl MetaType {
@@ -167,7 +251,7 @@ impl MetaType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MetaDirective {
     pub name: Name,
     pub description: Option<String>,
@@ -175,13 +259,48 @@ pub struct MetaDirective {
     pub arguments: IndexMap<Name, MetaInputValue>,
 }
 
-#[derive(Debug, Default)]
+/// The enum values a service names differently from the supergraph, by enum
+/// type.
+#[derive(Clone, Debug, Default)]
+pub struct EnumValueRenames {
+    /// The values of the service by supergraph value.
+    pub to_service: HashMap<String, HashMap<String, String>>,
+
+    /// The supergraph values by value of the service.
+    pub to_supergraph: HashMap<String, HashMap<String, String>>,
+}
+
+impl EnumValueRenames {
+    /// The renames of the values of a service, given by enum type and
+    /// supergraph value.
+    pub fn new(enum_values: &HashMap<String, HashMap<String, String>>) -> Self {
+        let to_supergraph = enum_values
+            .iter()
+            .map(|(ty, values)| {
+                let values = values
+                    .iter()
+                    .map(|(supergraph_value, value)| (value.clone(), supergraph_value.clone()))
+                    .collect();
+                (ty.clone(), values)
+            })
+            .collect();
+        Self {
+            to_service: enum_values.clone(),
+            to_supergraph,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct ComposedSchema {
     pub query_type: Option<Name>,
     pub mutation_type: Option<Name>,
     pub subscription_type: Option<Name>,
     pub types: IndexMap<Name, MetaType>,
     pub directives: HashMap<Name, MetaDirective>,
+    /// The enum values the services name differently from the supergraph,
+    /// by service.
+    pub enum_value_renames: HashMap<String, EnumValueRenames>,
 }
 
 impl ComposedSchema {
@@ -231,6 +350,14 @@ impl ComposedSchema {
                 possible_types: Default::default(),
                 enum_values: Default::default(),
                 input_fields: Default::default(),
+                cost: None,
+                cache_control: None,
+                one_of: false,
+                interface_objects: Default::default(),
+                tags: Vec::new(),
+                inaccessible: false,
+                authenticated: false,
+                requires_scopes: Vec::new(),
             });
         }
 
@@ -238,16 +365,24 @@ impl ComposedSchema {
         composed_schema.mutation_type = Some(Name::new("Mutation"));
         composed_schema.subscription_type = Some(Name::new("Subscription"));
 
+        let mut interface_objects = Vec::new();
         for (service, doc) in federation_sdl {
             for definition in doc.definitions {
                 match definition {
+                    TypeSystemDefinition::Type(type_definition)
+                        if matches!(type_definition.node.kind, types::TypeKind::Object(_)) &&
+                            has_directive(&type_definition.node.directives, "interfaceObject") =>
+                    {
+                        // Merged into the interface once every subgraph is combined.
+                        interface_objects.push((service.clone(), type_definition.node));
+                    },
                     TypeSystemDefinition::Type(type_definition) => {
                         if let types::TypeKind::Object(ObjectType { implements, fields }) = type_definition.node.kind {
                             let name = type_definition.node.name.node.clone();
                             let description = type_definition.node.description.map(|description| description.node);
                             let is_extend = type_definition.node.extend || root_objects.contains(&&*name);
                             let meta_type = composed_schema.types.entry(name.clone()).or_insert_with(|| MetaType {
-                                description,
+                                description: None,
                                 name,
                                 kind: TypeKind::Object,
                                 owner: None,
@@ -257,7 +392,19 @@ impl ComposedSchema {
                                 possible_types: Default::default(),
                                 enum_values: Default::default(),
                                 input_fields: Default::default(),
+                                cost: None,
+                                cache_control: None,
+                                one_of: false,
+                                interface_objects: Default::default(),
+                                tags: Vec::new(),
+                                inaccessible: false,
+                                authenticated: false,
+                                requires_scopes: Vec::new(),
                             });
+                            // Any subgraph may describe the type, not only the first one.
+                            if meta_type.description.is_none() {
+                                meta_type.description = description;
+                            }
 
                             let mut type_is_shareable = false;
                             let mut type_is_resolvable = true;
@@ -265,6 +412,29 @@ impl ComposedSchema {
                                 if directive.node.name.node.as_str() == "shareable" {
                                     type_is_shareable = true;
                                 }
+                                if directive.node.name.node.as_str() == "cost" {
+                                    meta_type.cost = get_cost(&directive.node.arguments);
+                                }
+                                if directive.node.name.node.as_str() == "tag" {
+                                    add_tag(&mut meta_type.tags, &directive.node);
+                                }
+                                if directive.node.name.node.as_str() == "inaccessible" {
+                                    meta_type.inaccessible = true;
+                                }
+                                if directive.node.name.node.as_str() == "authenticated" {
+                                    meta_type.authenticated = true;
+                                }
+                                if directive.node.name.node.as_str() == "requiresScopes" {
+                                    let scopes = get_scopes(&directive.node.arguments);
+                                    meta_type.requires_scopes = merge_scopes(&meta_type.requires_scopes, &scopes);
+                                }
+                                if directive.node.name.node.as_str() == "cacheControl" {
+                                    let cache_control = get_cache_control(&directive.node.arguments);
+                                    meta_type.cache_control = Some(match meta_type.cache_control {
+                                        Some(existing) => existing.merge(cache_control),
+                                        None => cache_control,
+                                    });
+                                }
                                 if directive.node.name.node.as_str() == "key" {
                                     if let Some(fields) = get_argument_str(&directive.node.arguments, "fields") {
                                         if let Some(selection_set) = parse_fields(fields.node)
@@ -293,6 +463,7 @@ impl ComposedSchema {
                                 .extend(implements.into_iter().map(|implement| implement.node));
 
                             for field in fields {
+                                check_field_set_syntax(&type_definition.node.name.node, &field.node)?;
                                 if is_extend {
                                     let is_external = has_directive(&field.node.directives, "external");
                                     if is_external {
@@ -320,19 +491,74 @@ impl ComposedSchema {
                                     }
                                 }
                                 let mut meta_field = convert_field_definition(field.node);
+                                if let Some(existing_field) = meta_type.fields.get_mut(&meta_field.name) {
+                                    merge_deprecations(&mut existing_field.arguments, &mut meta_field.arguments);
+                                    merge_field_visibility(existing_field, &mut meta_field);
+                                    meta_field.services = std::mem::take(&mut existing_field.services);
+                                }
+                                meta_field.services.push(service.clone());
+                                meta_field.services.sort();
                                 if is_extend {
                                     meta_field.service = Some(service.clone());
                                 }
                                 meta_type.fields.insert(meta_field.name.clone(), meta_field);
                             }
                         } else {
-                            let meta_type = convert_type_definition(type_definition.node);
-                            if let Some(meta_type2) = composed_schema.types.get(&meta_type.name) {
+                            let is_interface = matches!(type_definition.node.kind, types::TypeKind::Interface(_));
+                            let (keys, resolvable) = get_keys(&type_definition.node.directives);
+                            let mut meta_type = convert_type_definition(type_definition.node);
+                            // Interface entities are resolved by the subgraphs declaring their keys.
+                            if is_interface && !keys.is_empty() {
+                                meta_type.keys.insert(service.clone(), keys);
+                                if resolvable {
+                                    meta_type.owner = Some(service.clone());
+                                }
+                            }
+                            if let Some(meta_type2) = composed_schema.types.get_mut(&meta_type.name) {
+                                merge_deprecations(&mut meta_type2.input_fields, &mut meta_type.input_fields);
+                                merge_enum_value_visibility(&mut meta_type2.enum_values, &mut meta_type.enum_values);
+                                for (name, field) in &mut meta_type.fields {
+                                    if let Some(field2) = meta_type2.fields.get_mut(name) {
+                                        merge_deprecations(&mut field2.arguments, &mut field.arguments);
+                                        // Subgraphs may tag the same field differently.
+                                        merge_field_visibility(field2, field);
+                                        field2.tags.clone_from(&field.tags);
+                                        field2.inaccessible = field.inaccessible;
+                                        field2.authenticated = field.authenticated;
+                                        field2.requires_scopes.clone_from(&field.requires_scopes);
+                                    }
+                                }
+                                let mut tags = std::mem::take(&mut meta_type2.tags);
+                                merge_tags(&mut tags, &std::mem::take(&mut meta_type.tags));
+                                let inaccessible = std::mem::take(&mut meta_type2.inaccessible) ||
+                                    std::mem::take(&mut meta_type.inaccessible);
+                                let authenticated = std::mem::take(&mut meta_type2.authenticated) ||
+                                    std::mem::take(&mut meta_type.authenticated);
+                                let requires_scopes = merge_scopes(
+                                    &std::mem::take(&mut meta_type2.requires_scopes),
+                                    &std::mem::take(&mut meta_type.requires_scopes),
+                                );
+                                let mut keys = std::mem::take(&mut meta_type2.keys);
+                                keys.extend(std::mem::take(&mut meta_type.keys));
+                                let owner = meta_type2.owner.take().or(meta_type.owner.take());
+                                // Subgraphs may hint the same type differently.
+                                let cache_control =
+                                    match (meta_type2.cache_control.take(), meta_type.cache_control.take()) {
+                                        (Some(a), Some(b)) => Some(a.merge(b)),
+                                        (a, b) => a.or(b),
+                                    };
                                 if meta_type2 != &meta_type {
                                     return Err(CombineError::DefinitionConflicted {
                                         type_name: meta_type.name.to_string(),
                                     });
                                 }
+                                meta_type.keys = keys;
+                                meta_type.owner = owner;
+                                meta_type.cache_control = cache_control;
+                                meta_type.tags = tags;
+                                meta_type.inaccessible = inaccessible;
+                                meta_type.authenticated = authenticated;
+                                meta_type.requires_scopes = requires_scopes;
                             }
                             composed_schema.types.insert(meta_type.name.clone(), meta_type);
                         }
@@ -343,16 +569,22 @@ impl ComposedSchema {
             }
         }
 
+        for (service, definition) in interface_objects {
+            add_interface_object(&mut composed_schema, &service, definition)?;
+        }
+
+        check_field_sets(&composed_schema)?;
+
         if let Some(mutation) = composed_schema.types.get("Mutation") {
             if mutation.fields.is_empty() {
-                composed_schema.types.remove("Mutation");
+                composed_schema.types.swap_remove("Mutation");
                 composed_schema.mutation_type = None;
             }
         }
 
         if let Some(subscription) = composed_schema.types.get("Subscription") {
             if subscription.fields.is_empty() {
-                composed_schema.types.remove("Subscription");
+                composed_schema.types.swap_remove("Subscription");
                 composed_schema.subscription_type = None;
             }
         }
@@ -399,7 +631,7 @@ fn get_argument<'a>(
         .find_map(|d| if d.0.node.as_str() == name { Some(&d.1) } else { None })
 }
 
-fn get_argument_str<'a>(
+pub(crate) fn get_argument_str<'a>(
     arguments: &'a [(Positioned<Name>, Positioned<ConstValue>)],
     name: &str,
 ) -> Option<Positioned<&'a str>> {
@@ -409,14 +641,17 @@ fn get_argument_str<'a>(
     })
 }
 
-fn get_argument_bool(arguments: &[(Positioned<Name>, Positioned<ConstValue>)], name: &str) -> Option<Positioned<bool>> {
+pub(crate) fn get_argument_bool(
+    arguments: &[(Positioned<Name>, Positioned<ConstValue>)],
+    name: &str,
+) -> Option<Positioned<bool>> {
     get_argument(arguments, name).and_then(|value| match value.node {
         ConstValue::Boolean(s) => Some(Positioned::new(s, value.pos)),
         _ => None,
     })
 }
 
-fn parse_fields(fields: &str) -> Option<SelectionSet> {
+pub(crate) fn parse_fields(fields: &str) -> Option<SelectionSet> {
     parser::parse_query(format!("{{{}}}", fields))
         .ok()
         .and_then(|document| match document.operations {
@@ -425,13 +660,178 @@ fn parse_fields(fields: &str) -> Option<SelectionSet> {
         })
 }
 
-fn convert_schema_definition(composed_schema: &mut ComposedSchema, schema_definition: SchemaDefinition) {
+/// Add the fields a service contributes to an interface entity with an
+/// `@interfaceObject` type, to the interface and the object types
+/// implementing it, which the service resolves by the interface's keys.
+fn add_interface_object(
+    composed_schema: &mut ComposedSchema,
+    service: &str,
+    definition: TypeDefinition,
+) -> ::std::result::Result<(), CombineError> {
+    let name = definition.name.node;
+    let fields = match definition.kind {
+        types::TypeKind::Object(ObjectType { fields, .. }) => fields,
+        _ => return Ok(()),
+    };
+    let interface = match composed_schema.types.get(&name) {
+        Some(interface) if interface.kind == TypeKind::Interface => interface,
+        _ => {
+            return Err(CombineError::MissingInterface {
+                type_name: name.to_string(),
+            })
+        },
+    };
+    let (keys, _) = get_keys(&definition.directives);
+
+    let mut contributed_fields = Vec::new();
+    for field in fields {
+        if has_directive(&field.node.directives, "external") {
+            continue;
+        }
+        if interface.fields.contains_key(&field.node.name.node) {
+            let is_field_entity_key = keys
+                .iter()
+                .any(|key_fields| key_fields.contains_key(&field.node.name.node));
+            if !is_field_entity_key && !has_directive(&field.node.directives, "shareable") {
+                return Err(CombineError::FieldConflicted {
+                    type_name: name.to_string(),
+                    field_name: field.node.name.node.to_string(),
+                });
+            }
+            continue;
+        }
+        contributed_fields.push(field.node);
+    }
+
+    for meta_type in composed_schema.types.values_mut() {
+        let is_implementation = meta_type.kind == TypeKind::Object && meta_type.implements.contains(&name);
+        if meta_type.name != name && !is_implementation {
+            continue;
+        }
+        meta_type.keys.insert(service.to_string(), keys.clone());
+        meta_type.interface_objects.insert(service.to_string(), name.clone());
+        for field in &contributed_fields {
+            if !meta_type.fields.contains_key(&field.name.node) {
+                let mut meta_field = convert_field_definition(field.clone());
+                meta_field.service = Some(service.to_string());
+                meta_field.services.push(service.to_string());
+                meta_type.fields.insert(meta_field.name.clone(), meta_field);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the `@requires` and `@provides` field sets of a field parse.
+fn check_field_set_syntax(type_name: &str, field: &types::FieldDefinition) -> ::std::result::Result<(), CombineError> {
+    for directive in &field.directives {
+        let directive_name = directive.node.name.node.as_str();
+        if !matches!(directive_name, "requires" | "provides") {
+            continue;
+        }
+        if let Some(fields) = get_argument_str(&directive.node.arguments, "fields") {
+            if parse_fields(fields.node).is_none() {
+                return Err(CombineError::InvalidFieldSet {
+                    directive: directive_name.to_string(),
+                    type_name: type_name.to_string(),
+                    field_name: field.name.node.to_string(),
+                    fields: fields.node.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the `@requires` field sets select fields of the parent type,
+/// and the `@provides` field sets fields of the field type, that some
+/// subgraph resolves.
+fn check_field_sets(composed_schema: &ComposedSchema) -> ::std::result::Result<(), CombineError> {
+    for ty in composed_schema.types.values() {
+        for field in ty.fields.values() {
+            let field_sets = [
+                ("requires", field.requires.as_ref(), Some(ty)),
+                (
+                    "provides",
+                    field.provides.as_ref(),
+                    composed_schema.concrete_type_by_name(&field.ty),
+                ),
+            ];
+            for (directive, field_set, field_set_type) in field_sets {
+                let Some(field_set) = field_set else {
+                    continue;
+                };
+                if let Err(unknown_field) = check_field_set(composed_schema, field_set_type, field_set) {
+                    return Err(CombineError::UnknownFieldSetField {
+                        directive: directive.to_string(),
+                        type_name: ty.name.to_string(),
+                        field_name: field.name.to_string(),
+                        unknown_field,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the fields of a field set against a type, returning the
+/// coordinate of the first unknown field.
+fn check_field_set(
+    composed_schema: &ComposedSchema,
+    ty: Option<&MetaType>,
+    field_set: &KeyFields,
+) -> ::std::result::Result<(), String> {
+    for (name, nested) in field_set.iter() {
+        if name.as_str() == "__typename" {
+            continue;
+        }
+        let field = ty.and_then(|ty| ty.fields.get(name));
+        let Some(field) = field else {
+            return Err(match ty {
+                Some(ty) => format!("{}.{}", ty.name, name),
+                None => name.to_string(),
+            });
+        };
+        if !nested.is_empty() {
+            check_field_set(
+                composed_schema,
+                composed_schema.concrete_type_by_name(&field.ty),
+                nested,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The keys of the `@key` directives of a type, and whether the service
+/// resolves the type by them.
+fn get_keys(directives: &[Positioned<ConstDirective>]) -> (Vec<KeyFields>, bool) {
+    let mut keys = Vec::new();
+    let mut resolvable = true;
+    for directive in directives
+        .iter()
+        .filter(|directive| directive.node.name.node.as_str() == "key")
+    {
+        if let Some(fields) = get_argument_str(&directive.node.arguments, "fields") {
+            if let Some(selection_set) = parse_fields(fields.node) {
+                keys.push(convert_key_fields(selection_set));
+            }
+        }
+        if let Some(value) = get_argument_bool(&directive.node.arguments, "resolvable") {
+            resolvable = value.node;
+        }
+    }
+    (keys, resolvable)
+}
+
+pub(crate) fn convert_schema_definition(composed_schema: &mut ComposedSchema, schema_definition: SchemaDefinition) {
     composed_schema.query_type = schema_definition.query.map(|name| name.node);
     composed_schema.mutation_type = schema_definition.mutation.map(|name| name.node);
     composed_schema.subscription_type = schema_definition.subscription.map(|name| name.node);
 }
 
-fn convert_type_definition(definition: TypeDefinition) -> MetaType {
+pub(crate) fn convert_type_definition(definition: TypeDefinition) -> MetaType {
     let mut type_definition = MetaType {
         description: definition.description.map(|description| description.node),
         name: definition.name.node.clone(),
@@ -443,6 +843,14 @@ fn convert_type_definition(definition: TypeDefinition) -> MetaType {
         possible_types: Default::default(),
         enum_values: Default::default(),
         input_fields: Default::default(),
+        cost: None,
+        cache_control: None,
+        one_of: false,
+        interface_objects: Default::default(),
+        tags: Vec::new(),
+        inaccessible: false,
+        authenticated: false,
+        requires_scopes: Vec::new(),
     };
 
     match definition.kind {
@@ -471,10 +879,13 @@ fn convert_type_definition(definition: TypeDefinition) -> MetaType {
         types::TypeKind::Enum(EnumType { values }) => {
             type_definition.kind = TypeKind::Enum;
             type_definition.enum_values.extend(values.into_iter().map(|value| {
+                let (tags, inaccessible) = get_visibility(&value.node.directives);
                 (value.node.value.node.clone(), MetaEnumValue {
                     description: value.node.description.map(|description| description.node),
                     value: value.node.value.node,
                     deprecation: get_deprecated(&value.node.directives),
+                    tags,
+                    inaccessible,
                 })
             }));
         },
@@ -510,6 +921,13 @@ fn convert_type_definition(definition: TypeDefinition) -> MetaType {
                     }
                 }
             },
+            "cost" => type_definition.cost = get_cost(&directive.node.arguments),
+            "cacheControl" => type_definition.cache_control = Some(get_cache_control(&directive.node.arguments)),
+            "oneOf" => type_definition.one_of = type_definition.kind == TypeKind::InputObject,
+            "tag" => add_tag(&mut type_definition.tags, &directive.node),
+            "inaccessible" => type_definition.inaccessible = true,
+            "authenticated" => type_definition.authenticated = true,
+            "requiresScopes" => type_definition.requires_scopes = get_scopes(&directive.node.arguments),
             _ => {},
         }
     }
@@ -529,8 +947,16 @@ fn convert_field_definition(definition: types::FieldDefinition) -> MetaField {
         ty: definition.ty.node,
         deprecation: get_deprecated(&definition.directives),
         service: None,
+        services: Vec::new(),
         requires: None,
         provides: None,
+        cost: None,
+        list_size: None,
+        cache_control: None,
+        tags: Vec::new(),
+        inaccessible: false,
+        authenticated: false,
+        requires_scopes: Vec::new(),
     };
 
     for directive in definition.directives {
@@ -550,6 +976,13 @@ fn convert_field_definition(definition: types::FieldDefinition) -> MetaField {
                     field_definition.provides = parse_fields(fields.node).map(convert_key_fields);
                 }
             },
+            "cost" => field_definition.cost = get_cost(&directive.node.arguments),
+            "listSize" => field_definition.list_size = Some(get_list_size(&directive.node.arguments)),
+            "cacheControl" => field_definition.cache_control = Some(get_cache_control(&directive.node.arguments)),
+            "tag" => add_tag(&mut field_definition.tags, &directive.node),
+            "inaccessible" => field_definition.inaccessible = true,
+            "authenticated" => field_definition.authenticated = true,
+            "requiresScopes" => field_definition.requires_scopes = get_scopes(&directive.node.arguments),
             _ => {},
         }
     }
@@ -557,7 +990,7 @@ fn convert_field_definition(definition: types::FieldDefinition) -> MetaField {
     field_definition
 }
 
-fn convert_key_fields(selection_set: SelectionSet) -> KeyFields {
+pub(crate) fn convert_key_fields(selection_set: SelectionSet) -> KeyFields {
     KeyFields(
         selection_set
             .items
@@ -574,11 +1007,20 @@ fn convert_key_fields(selection_set: SelectionSet) -> KeyFields {
 }
 
 fn convert_input_value_definition(arg: parser::types::InputValueDefinition) -> MetaInputValue {
+    let (tags, inaccessible) = get_visibility(&arg.directives);
     MetaInputValue {
         description: arg.description.map(|description| description.node),
         name: arg.name.node,
         ty: arg.ty.node,
         default_value: arg.default_value.map(|default_value| default_value.node),
+        deprecation: get_deprecated(&arg.directives),
+        cost: arg
+            .directives
+            .iter()
+            .find(|directive| directive.node.name.node.as_str() == "cost")
+            .and_then(|directive| get_cost(&directive.node.arguments)),
+        tags,
+        inaccessible,
     }
 }
 
@@ -601,6 +1043,53 @@ fn convert_directive_definition(directive_definition: DirectiveDefinition) -> Me
     }
 }
 
+/// Deprecate the arguments or input fields deprecated by either of two
+/// subgraphs in both, keeping the reason of the first one.
+fn merge_deprecations(existing: &mut IndexMap<Name, MetaInputValue>, new: &mut IndexMap<Name, MetaInputValue>) {
+    for (name, new_value) in new {
+        if let Some(existing_value) = existing.get_mut(name) {
+            if existing_value.deprecation.is_deprecated() {
+                new_value.deprecation = existing_value.deprecation.clone();
+            } else {
+                existing_value.deprecation = new_value.deprecation.clone();
+            }
+            // Subgraphs may tag the same argument or input field differently.
+            merge_tags(&mut new_value.tags, &existing_value.tags);
+            existing_value.tags.clone_from(&new_value.tags);
+            new_value.inaccessible |= existing_value.inaccessible;
+            existing_value.inaccessible = new_value.inaccessible;
+        }
+    }
+}
+
+/// Merge the `@tag` and `@inaccessible` directives of the values of an enum
+/// defined by several subgraphs, any of them may tag a value or make it
+/// inaccessible.
+fn merge_enum_value_visibility(existing: &mut IndexMap<Name, MetaEnumValue>, new: &mut IndexMap<Name, MetaEnumValue>) {
+    for (name, new_value) in new {
+        if let Some(existing_value) = existing.get_mut(name) {
+            merge_tags(&mut new_value.tags, &existing_value.tags);
+            existing_value.tags.clone_from(&new_value.tags);
+            new_value.inaccessible |= existing_value.inaccessible;
+            existing_value.inaccessible = new_value.inaccessible;
+        }
+    }
+}
+
+/// The tags of `@tag` and whether `@inaccessible` is among the directives.
+fn get_visibility(directives: &[Positioned<ConstDirective>]) -> (Vec<String>, bool) {
+    let mut tags = Vec::new();
+    let mut inaccessible = false;
+    for directive in directives {
+        match directive.node.name.node.as_str() {
+            "tag" => add_tag(&mut tags, &directive.node),
+            "inaccessible" => inaccessible = true,
+            _ => {},
+        }
+    }
+    (tags, inaccessible)
+}
+
 fn get_deprecated(directives: &[Positioned<ConstDirective>]) -> Deprecation {
     directives
         .iter()
@@ -611,13 +1100,134 @@ fn get_deprecated(directives: &[Positioned<ConstDirective>]) -> Deprecation {
         .unwrap_or(Deprecation::NoDeprecated)
 }
 
-fn has_directive(directives: &[Positioned<ConstDirective>], name: &str) -> bool {
+/// The weight of `@cost`, which is an `Int` in Apollo demand control and a
+/// `String` in the IBM cost specification.
+fn get_cost(arguments: &[(Positioned<Name>, Positioned<ConstValue>)]) -> Option<u64> {
+    get_argument(arguments, "weight").and_then(|value| match &value.node {
+        ConstValue::Number(weight) => weight.as_u64(),
+        ConstValue::String(weight) => weight.parse().ok(),
+        _ => None,
+    })
+}
+
+/// The `maxAge` and `scope` of `@cacheControl`.
+fn get_cache_control(arguments: &[(Positioned<Name>, Positioned<ConstValue>)]) -> CacheControl {
+    CacheControl {
+        max_age: get_argument(arguments, "maxAge").and_then(|value| match &value.node {
+            ConstValue::Number(max_age) => max_age.as_u64(),
+            _ => None,
+        }),
+        scope: match get_argument(arguments, "scope").map(|value| &value.node) {
+            Some(ConstValue::Enum(scope)) if scope.as_str() == "PRIVATE" => CacheScope::Private,
+            Some(ConstValue::String(scope)) if scope == "PRIVATE" => CacheScope::Private,
+            _ => CacheScope::Public,
+        },
+    }
+}
+
+fn get_list_size(arguments: &[(Positioned<Name>, Positioned<ConstValue>)]) -> ListSize {
+    let get_names = |name| match get_argument(arguments, name).map(|value| &value.node) {
+        Some(ConstValue::List(names)) => names
+            .iter()
+            .filter_map(|name| match name {
+                ConstValue::String(name) => Some(Name::new(name)),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    ListSize {
+        assumed_size: get_argument(arguments, "assumedSize").and_then(|value| match &value.node {
+            ConstValue::Number(size) => size.as_u64(),
+            _ => None,
+        }),
+        slicing_arguments: get_names("slicingArguments"),
+        sized_fields: get_names("sizedFields"),
+        require_one_slicing_argument: get_argument_bool(arguments, "requireOneSlicingArgument")
+            .map(|value| value.node)
+            .unwrap_or(true),
+    }
+}
+
+/// Add the name of a `@tag` directive, once.
+fn add_tag(tags: &mut Vec<String>, directive: &ConstDirective) {
+    if let Some(name) = get_argument_str(&directive.arguments, "name") {
+        if !tags.iter().any(|tag| tag == name.node) {
+            tags.push(name.node.to_string());
+        }
+    }
+}
+
+/// Merge the `@tag`, `@inaccessible`, `@authenticated` and `@requiresScopes`
+/// directives of a field defined by several subgraphs into `field`: any of
+/// them may tag the field, make it inaccessible or require access to it.
+fn merge_field_visibility(existing_field: &MetaField, field: &mut MetaField) {
+    merge_tags(&mut field.tags, &existing_field.tags);
+    field.inaccessible |= existing_field.inaccessible;
+    field.authenticated |= existing_field.authenticated;
+    field.requires_scopes = merge_scopes(&existing_field.requires_scopes, &field.requires_scopes);
+}
+
+/// The `scopes` of `@requiresScopes`, sets of scopes of which any must be
+/// granted.
+fn get_scopes(arguments: &[(Positioned<Name>, Positioned<ConstValue>)]) -> Vec<Vec<String>> {
+    match get_argument(arguments, "scopes").map(|value| &value.node) {
+        Some(ConstValue::List(sets)) => sets
+            .iter()
+            .filter_map(|set| match set {
+                ConstValue::List(scopes) => Some(
+                    scopes
+                        .iter()
+                        .filter_map(|scope| match scope {
+                            ConstValue::String(scope) => Some(scope.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The scopes required by both of two `@requiresScopes`: any union of a set
+/// of each.
+fn merge_scopes(a: &[Vec<String>], b: &[Vec<String>]) -> Vec<Vec<String>> {
+    if a.is_empty() || a == b {
+        return b.to_vec();
+    }
+    if b.is_empty() {
+        return a.to_vec();
+    }
+    let mut merged: Vec<Vec<String>> = Vec::new();
+    for set_a in a {
+        for set_b in b {
+            let mut set = set_a.clone();
+            set.extend(set_b.iter().filter(|scope| !set_a.contains(scope)).cloned());
+            if !merged.contains(&set) {
+                merged.push(set);
+            }
+        }
+    }
+    merged
+}
+
+fn merge_tags(tags: &mut Vec<String>, other: &[String]) {
+    for tag in other {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+}
+
+pub(crate) fn has_directive(directives: &[Positioned<ConstDirective>], name: &str) -> bool {
     directives
         .iter()
         .any(|directive| directive.node.name.node.as_str() == name)
 }
 
-fn finish_schema(composed_schema: &mut ComposedSchema) {
+pub(crate) fn finish_schema(composed_schema: &mut ComposedSchema) {
     for definition in parser::parse_schema(include_str!("builtin.graphql"))
         .unwrap()
         .definitions
@@ -659,14 +1269,26 @@ fn finish_schema(composed_schema: &mut ComposedSchema) {
                     name,
                     ty: Type::new("String!").unwrap(),
                     default_value: None,
+                    deprecation: Deprecation::NoDeprecated,
+                    cost: None,
+                    tags: Vec::new(),
+                    inaccessible: false,
                 });
                 arguments
             },
             ty: Type::new("__Type").unwrap(),
             deprecation: Deprecation::NoDeprecated,
             service: None,
+            services: Vec::new(),
             requires: None,
             provides: None,
+            cost: None,
+            list_size: None,
+            cache_control: None,
+            tags: Vec::new(),
+            inaccessible: false,
+            authenticated: false,
+            requires_scopes: Vec::new(),
         });
 
         let name = Name::new("__schema");
@@ -677,8 +1299,16 @@ fn finish_schema(composed_schema: &mut ComposedSchema) {
             ty: Type::new("__Schema!").unwrap(),
             deprecation: Deprecation::NoDeprecated,
             service: None,
+            services: Vec::new(),
             requires: None,
             provides: None,
+            cost: None,
+            list_size: None,
+            cache_control: None,
+            tags: Vec::new(),
+            inaccessible: false,
+            authenticated: false,
+            requires_scopes: Vec::new(),
         });
     }
 
@@ -10,4 +10,38 @@ pub enum CombineError {
 
     #[error("Field '{type_name}.{field_name}' definition conflicted.")]
     FieldConflicted { type_name: String, field_name: String },
+
+    #[error("Type '{type_name}' is an interface object, but no subgraph defines the interface.")]
+    MissingInterface { type_name: String },
+
+    #[error("The @{directive} field set \"{fields}\" of '{type_name}.{field_name}' is invalid.")]
+    InvalidFieldSet {
+        directive: String,
+        type_name: String,
+        field_name: String,
+        fields: String,
+    },
+
+    #[error("The @{directive} field set of '{type_name}.{field_name}' references unknown field '{unknown_field}'.")]
+    UnknownFieldSetField {
+        directive: String,
+        type_name: String,
+        field_name: String,
+        unknown_field: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum SupergraphError {
+    #[error("Invalid supergraph SDL: {0}")]
+    Parse(#[from] parser::Error),
+
+    #[error("The supergraph has no `join__Graph` enum.")]
+    MissingGraphs,
+
+    #[error("Graph '{graph}' has no `@join__graph` directive with a name and url.")]
+    InvalidGraph { graph: String },
+
+    #[error("Unknown graph '{graph}'.")]
+    UnknownGraph { graph: String },
 }
@@ -0,0 +1,498 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use parser::{
+    types::{
+        ConstDirective,
+        InputValueDefinition,
+        Selection,
+        SelectionSet,
+        ServiceDocument,
+        TypeDefinition,
+        TypeKind,
+        TypeSystemDefinition,
+    },
+    Pos,
+    Positioned,
+};
+use value::{ConstValue, Name};
+
+use crate::{
+    composed_schema::{get_argument_str, has_directive, parse_fields},
+    TypeExt,
+};
+
+/// A description of an element differs between subgraphs.
+pub const INCONSISTENT_DESCRIPTION: &str = "INCONSISTENT_DESCRIPTION";
+
+/// An argument or input field has a default value in some subgraphs only.
+pub const INCONSISTENT_DEFAULT_VALUE_PRESENCE: &str = "INCONSISTENT_DEFAULT_VALUE_PRESENCE";
+
+/// An argument has different default values in different subgraphs.
+pub const FIELD_ARGUMENT_DEFAULT_MISMATCH: &str = "FIELD_ARGUMENT_DEFAULT_MISMATCH";
+
+/// An input field has different default values in different subgraphs.
+pub const INPUT_FIELD_DEFAULT_MISMATCH: &str = "INPUT_FIELD_DEFAULT_MISMATCH";
+
+/// An `@external` field is used by no `@key`, `@requires` or `@provides`.
+pub const EXTERNAL_UNUSED: &str = "EXTERNAL_UNUSED";
+
+/// A value type defined identically by several subgraphs was made
+/// shareable.
+pub const SHAREABLE_INFERRED: &str = "SHAREABLE_INFERRED";
+
+/// How much a composition hint matters, named after the hint levels of
+/// Apollo composition.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum HintSeverity {
+    /// Likely a mistake, although the schemas compose.
+    Warn,
+
+    /// Worth knowing, but often intended.
+    Info,
+}
+
+impl HintSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HintSeverity::Warn => "WARN",
+            HintSeverity::Info => "INFO",
+        }
+    }
+}
+
+/// Where in a subgraph a composition hint applies.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HintNode {
+    pub subgraph: String,
+    pub pos: Pos,
+}
+
+/// A finding about subgraphs that compose, but may not compose as
+/// intended.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompositionHint {
+    pub code: &'static str,
+    pub severity: HintSeverity,
+    pub message: String,
+    pub nodes: Vec<HintNode>,
+}
+
+/// The definitions of an element in each subgraph, in subgraph order.
+type Definitions<T> = BTreeMap<String, Vec<(String, Positioned<T>)>>;
+
+/// Find the composition hints of the subgraphs, ordered by severity.
+pub fn composition_hints(subgraphs: &[(String, ServiceDocument)]) -> Vec<CompositionHint> {
+    let mut descriptions: Definitions<Option<String>> = BTreeMap::new();
+    let mut argument_defaults: Definitions<Option<ConstValue>> = BTreeMap::new();
+    let mut input_field_defaults: Definitions<Option<ConstValue>> = BTreeMap::new();
+    let mut hints = Vec::new();
+
+    for (subgraph, document) in subgraphs {
+        for definition in &document.definitions {
+            let definition = match definition {
+                TypeSystemDefinition::Type(definition) => definition,
+                _ => continue,
+            };
+            let type_name = definition.node.name.node.as_str();
+            add_description(&mut descriptions, type_name.to_string(), subgraph, definition);
+
+            match &definition.node.kind {
+                TypeKind::Object(object) => {
+                    for field in &object.fields {
+                        let coordinate = format!("{}.{}", type_name, field.node.name.node);
+                        add_description(&mut descriptions, coordinate.clone(), subgraph, field);
+                        for argument in &field.node.arguments {
+                            add_default(
+                                &mut argument_defaults,
+                                format!("{}({}:)", coordinate, argument.node.name.node),
+                                subgraph,
+                                argument,
+                            );
+                        }
+                    }
+                },
+                TypeKind::InputObject(input_object) => {
+                    for field in &input_object.fields {
+                        let coordinate = format!("{}.{}", type_name, field.node.name.node);
+                        add_description(&mut descriptions, coordinate.clone(), subgraph, field);
+                        add_default(&mut input_field_defaults, coordinate, subgraph, field);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        hints.extend(unused_externals(subgraph, document));
+    }
+
+    for (coordinate, definitions) in descriptions {
+        let described = definitions
+            .into_iter()
+            .filter(|(_, description)| description.node.is_some())
+            .collect::<Vec<_>>();
+        if !all_equal(&described) {
+            hints.push(CompositionHint {
+                code: INCONSISTENT_DESCRIPTION,
+                severity: HintSeverity::Info,
+                message: format!(
+                    "Element \"{}\" has inconsistent descriptions across subgraphs {}.",
+                    coordinate,
+                    subgraph_names(&described)
+                ),
+                nodes: nodes(&described),
+            });
+        }
+    }
+
+    for (defaults, mismatch_code, element) in [
+        (argument_defaults, FIELD_ARGUMENT_DEFAULT_MISMATCH, "Argument"),
+        (input_field_defaults, INPUT_FIELD_DEFAULT_MISMATCH, "Input field"),
+    ] {
+        for (coordinate, definitions) in defaults {
+            if all_equal(&definitions) {
+                continue;
+            }
+            let with_default = definitions
+                .iter()
+                .filter(|(_, default)| default.node.is_some())
+                .cloned()
+                .collect::<Vec<_>>();
+            let hint = match with_default.len() == definitions.len() {
+                true => CompositionHint {
+                    code: mismatch_code,
+                    severity: HintSeverity::Warn,
+                    message: format!(
+                        "{} \"{}\" has different default values in subgraphs {}.",
+                        element,
+                        coordinate,
+                        subgraph_names(&definitions)
+                    ),
+                    nodes: nodes(&definitions),
+                },
+                false => CompositionHint {
+                    code: INCONSISTENT_DEFAULT_VALUE_PRESENCE,
+                    severity: HintSeverity::Warn,
+                    message: format!(
+                        "{} \"{}\" has a default value only in subgraphs {}.",
+                        element,
+                        coordinate,
+                        subgraph_names(&with_default)
+                    ),
+                    nodes: nodes(&definitions),
+                },
+            };
+            hints.push(hint);
+        }
+    }
+
+    hints.sort_by_key(|hint| hint.severity);
+    hints
+}
+
+/// Make the value types that several subgraphs define identically
+/// shareable, as if they were marked `@shareable`, returning a hint for
+/// each of them.
+///
+/// Value types are the object types without `@key`, other than the root
+/// types. Those defined differently are left as they are, so that they fail
+/// to compose.
+pub fn infer_shareable(subgraphs: &mut [(String, ServiceDocument)]) -> Vec<CompositionHint> {
+    let mut shapes: Definitions<Option<ValueTypeShape>> = BTreeMap::new();
+    for (subgraph, document) in subgraphs.iter() {
+        for definition in &document.definitions {
+            if let TypeSystemDefinition::Type(definition) = definition {
+                if let TypeKind::Object(_) = &definition.node.kind {
+                    shapes.entry(definition.node.name.node.to_string()).or_default().push((
+                        subgraph.clone(),
+                        Positioned::new(value_type_shape(&definition.node), definition.pos),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut hints = Vec::new();
+    let mut shareable = HashSet::new();
+    for (type_name, definitions) in shapes {
+        if definitions.len() < 2 ||
+            definitions.iter().any(|(_, shape)| shape.node.is_none()) ||
+            !all_equal(&definitions)
+        {
+            continue;
+        }
+        hints.push(CompositionHint {
+            code: SHAREABLE_INFERRED,
+            severity: HintSeverity::Info,
+            message: format!(
+                "Value type \"{}\" is defined identically in subgraphs {} and was made shareable.",
+                type_name,
+                subgraph_names(&definitions)
+            ),
+            nodes: nodes(&definitions),
+        });
+        shareable.insert(type_name);
+    }
+
+    for (_, document) in subgraphs.iter_mut() {
+        for definition in &mut document.definitions {
+            if let TypeSystemDefinition::Type(definition) = definition {
+                if shareable.contains(definition.node.name.node.as_str()) {
+                    let pos = definition.pos;
+                    definition.node.directives.push(Positioned::new(
+                        ConstDirective {
+                            name: Positioned::new(Name::new("shareable"), pos),
+                            arguments: Vec::new(),
+                        },
+                        pos,
+                    ));
+                }
+            }
+        }
+    }
+    hints
+}
+
+/// The fields and interfaces of a value type, by name, ignoring
+/// descriptions and directives.
+#[derive(PartialEq)]
+struct ValueTypeShape {
+    implements: Vec<String>,
+    /// The signatures of the fields, with their arguments by name.
+    fields: Vec<String>,
+}
+
+/// The shape of an object type that may be made shareable, `None` for
+/// entities, root types, extensions and the types already shareable.
+fn value_type_shape(definition: &TypeDefinition) -> Option<ValueTypeShape> {
+    let object = match &definition.kind {
+        TypeKind::Object(object) => object,
+        _ => return None,
+    };
+    let excluded = ["key", "shareable", "interfaceObject", "extends"];
+    if definition.extend ||
+        ["Query", "Mutation", "Subscription"].contains(&definition.name.node.as_str()) ||
+        excluded
+            .iter()
+            .any(|directive| has_directive(&definition.directives, directive))
+    {
+        return None;
+    }
+
+    let mut implements = object
+        .implements
+        .iter()
+        .map(|name| name.node.to_string())
+        .collect::<Vec<_>>();
+    implements.sort();
+    let mut fields = object
+        .fields
+        .iter()
+        .map(|field| {
+            let mut arguments = field
+                .node
+                .arguments
+                .iter()
+                .map(|argument| match &argument.node.default_value {
+                    Some(default) => format!(
+                        "{}: {} = {}",
+                        argument.node.name.node, argument.node.ty.node, default.node
+                    ),
+                    None => format!("{}: {}", argument.node.name.node, argument.node.ty.node),
+                })
+                .collect::<Vec<_>>();
+            arguments.sort();
+            format!(
+                "{}({}): {}",
+                field.node.name.node,
+                arguments.join(", "),
+                field.node.ty.node
+            )
+        })
+        .collect::<Vec<_>>();
+    fields.sort();
+    Some(ValueTypeShape { implements, fields })
+}
+
+fn add_description<T: HasDescription>(
+    descriptions: &mut Definitions<Option<String>>,
+    coordinate: String,
+    subgraph: &str,
+    definition: &Positioned<T>,
+) {
+    descriptions.entry(coordinate).or_default().push((
+        subgraph.to_string(),
+        Positioned::new(definition.node.description().map(str::to_string), definition.pos),
+    ));
+}
+
+fn add_default(
+    defaults: &mut Definitions<Option<ConstValue>>,
+    coordinate: String,
+    subgraph: &str,
+    definition: &Positioned<InputValueDefinition>,
+) {
+    let default = definition.node.default_value.as_ref().map(|value| value.node.clone());
+    defaults
+        .entry(coordinate)
+        .or_default()
+        .push((subgraph.to_string(), Positioned::new(default, definition.pos)));
+}
+
+trait HasDescription {
+    fn description(&self) -> Option<&str>;
+}
+
+impl HasDescription for TypeDefinition {
+    fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|description| description.node.as_str())
+    }
+}
+
+impl HasDescription for parser::types::FieldDefinition {
+    fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|description| description.node.as_str())
+    }
+}
+
+impl HasDescription for InputValueDefinition {
+    fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|description| description.node.as_str())
+    }
+}
+
+fn all_equal<T: PartialEq>(definitions: &[(String, Positioned<T>)]) -> bool {
+    definitions.windows(2).all(|pair| pair[0].1.node == pair[1].1.node)
+}
+
+fn subgraph_names<T>(definitions: &[(String, Positioned<T>)]) -> String {
+    definitions
+        .iter()
+        .map(|(subgraph, _)| format!("\"{}\"", subgraph))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn nodes<T>(definitions: &[(String, Positioned<T>)]) -> Vec<HintNode> {
+    definitions
+        .iter()
+        .map(|(subgraph, definition)| HintNode {
+            subgraph: subgraph.clone(),
+            pos: definition.pos,
+        })
+        .collect()
+}
+
+/// The `@external` fields of a subgraph that no `@key`, `@requires` or
+/// `@provides` of the subgraph selects.
+fn unused_externals(subgraph: &str, document: &ServiceDocument) -> Vec<CompositionHint> {
+    let objects = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            TypeSystemDefinition::Type(definition) => match &definition.node.kind {
+                TypeKind::Object(object) => Some((definition.node.name.node.as_str(), (definition, object))),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let field_type = |type_name: &str, field_name: &str| {
+        let (_, object) = objects.get(type_name)?;
+        let field = object.fields.iter().find(|field| field.node.name.node == field_name)?;
+        Some(field.node.ty.node.concrete_typename().to_string())
+    };
+
+    let mut used = HashSet::new();
+    for (type_name, (definition, object)) in &objects {
+        for directive in &definition.node.directives {
+            if directive.node.name.node == "key" {
+                if let Some(fields) = get_argument_str(&directive.node.arguments, "fields") {
+                    mark_used(&mut used, type_name, fields.node, &field_type);
+                }
+            }
+        }
+        for field in &object.fields {
+            for directive in &field.node.directives {
+                let selected_type = match directive.node.name.node.as_str() {
+                    "requires" => type_name.to_string(),
+                    "provides" => field.node.ty.node.concrete_typename().to_string(),
+                    _ => continue,
+                };
+                if let Some(fields) = get_argument_str(&directive.node.arguments, "fields") {
+                    mark_used(&mut used, &selected_type, fields.node, &field_type);
+                }
+            }
+        }
+    }
+
+    let mut hints = Vec::new();
+    for definition in &document.definitions {
+        let (type_name, object) = match definition {
+            TypeSystemDefinition::Type(definition) => match &definition.node.kind {
+                TypeKind::Object(object) => (definition.node.name.node.as_str(), object),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        for field in &object.fields {
+            let field_name = field.node.name.node.to_string();
+            if has_directive(&field.node.directives, "external") &&
+                !used.contains(&(type_name.to_string(), field_name.clone()))
+            {
+                hints.push(CompositionHint {
+                    code: EXTERNAL_UNUSED,
+                    severity: HintSeverity::Warn,
+                    message: format!(
+                        "Field \"{}.{}\" is marked @external in subgraph \"{}\", but no @key, @requires or @provides \
+                         selects it.",
+                        type_name, field_name, subgraph
+                    ),
+                    nodes: vec![HintNode {
+                        subgraph: subgraph.to_string(),
+                        pos: field.pos,
+                    }],
+                });
+            }
+        }
+    }
+    hints
+}
+
+/// Mark the fields of a `fields` argument selected on a type as used.
+fn mark_used(
+    used: &mut HashSet<(String, String)>,
+    type_name: &str,
+    fields: &str,
+    field_type: &impl Fn(&str, &str) -> Option<String>,
+) {
+    fn walk(
+        used: &mut HashSet<(String, String)>,
+        type_name: &str,
+        selection_set: &SelectionSet,
+        field_type: &impl Fn(&str, &str) -> Option<String>,
+    ) {
+        for selection in &selection_set.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    let field_name = field.node.name.node.as_str();
+                    used.insert((type_name.to_string(), field_name.to_string()));
+                    if let Some(sub_type) = field_type(type_name, field_name) {
+                        walk(used, &sub_type, &field.node.selection_set.node, field_type);
+                    }
+                },
+                Selection::InlineFragment(fragment) => {
+                    let type_name = match &fragment.node.type_condition {
+                        Some(type_condition) => type_condition.node.on.node.as_str(),
+                        None => type_name,
+                    };
+                    walk(used, type_name, &fragment.node.selection_set.node, field_type);
+                },
+                Selection::FragmentSpread(_) => {},
+            }
+        }
+    }
+
+    if let Some(selection_set) = parse_fields(fields) {
+        walk(used, type_name, &selection_set, field_type);
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::{BTreeSet, HashSet};
+
+use indexmap::IndexMap;
+use parser::{
+    types::{
+        BaseType,
+        ConstDirective,
+        FieldDefinition,
+        InputValueDefinition,
+        Selection,
+        ServiceDocument,
+        Type,
+        TypeDefinition,
+        TypeKind,
+        TypeSystemDefinition,
+    },
+    Positioned,
+};
+
+use crate::composed_schema::{get_argument_str, has_directive, parse_fields};
+
+const FEDERATION_LINK: &str = "extend schema @link(url: \"https://specs.apollo.dev/federation/v2.3\", import: \
+                               [\"@key\", \"@shareable\", \"@external\", \"@requires\", \"@provides\"])";
+
+/// The directives of legacy schemas, replaced by those of Federation 2.
+const LEGACY_DIRECTIVES: &[&str] = &["composedGraph", "owner", "key", "resolve"];
+
+/// The Federation 2 SDL of a service, translated from a legacy schema.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SubgraphSdl {
+    pub service: String,
+    pub sdl: String,
+}
+
+/// Translate a schema annotated with the legacy `@owner`, `@key(service:)`
+/// and `@resolve` directives into the Federation 2 SDL of each of its
+/// services, ordered by service.
+///
+/// The entities owned by a service are defined with their keys, the fields
+/// other services resolve are extensions of the entity with its key fields
+/// and required fields marked `@external`, and the other types the SDL of a
+/// service references are added as unresolvable keys of the entities of
+/// other services, or shareable value types.
+pub fn translate_legacy_sdl(sdl: &str) -> Result<Vec<SubgraphSdl>, parser::Error> {
+    let document = parser::parse_schema(sdl)?;
+    let schema = LegacySchema::new(&document);
+    Ok(schema
+        .services()
+        .into_iter()
+        .map(|service| SubgraphSdl {
+            sdl: schema.subgraph_sdl(&service),
+            service,
+        })
+        .collect())
+}
+
+/// The part of a type a service defines.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Part {
+    /// The root fields the service resolves.
+    Root,
+    /// The fields of an entity owned by the service, with its keys.
+    Owned,
+    /// The fields the service resolves on an entity of another service.
+    Extension,
+    /// The keys of an entity of another service which the service returns,
+    /// or the fields as `@external` if the entity has no keys.
+    Reference,
+    /// The whole type, such as enums and value types.
+    Shared,
+}
+
+struct LegacySchema<'a> {
+    root_types: Vec<&'a str>,
+    types: IndexMap<&'a str, &'a TypeDefinition>,
+}
+
+impl<'a> LegacySchema<'a> {
+    fn new(document: &'a ServiceDocument) -> Self {
+        let mut root_types = vec!["Query", "Mutation", "Subscription"];
+        let mut types = IndexMap::new();
+        for definition in &document.definitions {
+            match definition {
+                TypeSystemDefinition::Schema(schema) => {
+                    let schema = &schema.node;
+                    for (idx, name) in [&schema.query, &schema.mutation, &schema.subscription]
+                        .into_iter()
+                        .enumerate()
+                    {
+                        if let Some(name) = name {
+                            root_types[idx] = name.node.as_str();
+                        }
+                    }
+                },
+                TypeSystemDefinition::Type(ty) => {
+                    types.insert(ty.node.name.node.as_str(), &ty.node);
+                },
+                TypeSystemDefinition::Directive(_) => {},
+            }
+        }
+        Self { root_types, types }
+    }
+
+    fn services(&self) -> BTreeSet<String> {
+        let mut services = BTreeSet::new();
+        for ty in self.types.values() {
+            services.extend(owner(ty).map(ToString::to_string));
+            services.extend(keys(ty).into_iter().map(|(service, _)| service.to_string()));
+            services.extend(
+                field_definitions(ty)
+                    .iter()
+                    .filter_map(|field| resolve(&field.node))
+                    .map(ToString::to_string),
+            );
+        }
+        services
+    }
+
+    fn subgraph_sdl(&self, service: &str) -> String {
+        let mut parts = IndexMap::new();
+        for (name, ty) in &self.types {
+            let part = match &ty.kind {
+                TypeKind::Object(object) if self.root_types.contains(name) => {
+                    if !object.fields.iter().any(|field| resolve(&field.node) == Some(service)) {
+                        continue;
+                    }
+                    Part::Root
+                },
+                TypeKind::Object(_) if owner(ty) == Some(service) => Part::Owned,
+                TypeKind::Object(object)
+                    if keys(ty).iter().any(|(key_service, _)| *key_service == service) ||
+                        object.fields.iter().any(|field| resolve(&field.node) == Some(service)) =>
+                {
+                    Part::Extension
+                },
+                _ => continue,
+            };
+            parts.insert(*name, part);
+        }
+
+        // Add the types referenced by the fields of the service.
+        let mut pending = parts.keys().copied().collect::<Vec<_>>();
+        let mut visited = pending.iter().copied().collect::<HashSet<_>>();
+        while let Some(name) = pending.pop() {
+            let ty = self.types[name];
+            let part = parts[name];
+            let mut referenced = Vec::new();
+            for (field, _) in self.fields(ty, part, service) {
+                referenced.push(named_type(&field.ty.node));
+                referenced.extend(field.arguments.iter().map(|arg| named_type(&arg.node.ty.node)));
+            }
+            match &ty.kind {
+                TypeKind::Object(object) if matches!(part, Part::Owned | Part::Shared) => {
+                    referenced.extend(object.implements.iter().map(|name| name.node.as_str()));
+                },
+                TypeKind::Interface(interface) => {
+                    referenced.extend(interface.implements.iter().map(|name| name.node.as_str()));
+                },
+                TypeKind::Union(union) => referenced.extend(union.members.iter().map(|name| name.node.as_str())),
+                TypeKind::InputObject(input) => {
+                    referenced.extend(input.fields.iter().map(|field| named_type(&field.node.ty.node)));
+                },
+                _ => {},
+            }
+
+            for name in referenced {
+                let Some(ty) = self.types.get(name) else {
+                    continue;
+                };
+                if !visited.insert(name) {
+                    continue;
+                }
+                let part = match &ty.kind {
+                    TypeKind::Object(_) if owner(ty).is_some() => Part::Reference,
+                    _ => Part::Shared,
+                };
+                parts.insert(name, part);
+                pending.push(name);
+            }
+        }
+
+        let mut sdl = format!("{}\n", FEDERATION_LINK);
+        for (name, ty) in &self.types {
+            if let Some(part) = parts.get(name) {
+                sdl.push('\n');
+                self.write_type(&mut sdl, ty, *part, service);
+            }
+        }
+        sdl
+    }
+
+    /// The keys an entity is resolved by, those of the service if it
+    /// declares some, otherwise those of the owner, or of any service.
+    fn entity_keys(&self, ty: &'a TypeDefinition, service: &str) -> Vec<&'a str> {
+        let keys = keys(ty);
+        let owner = owner(ty);
+        let mut entity_keys = Vec::new();
+        for preferred in [Some(service), owner, None] {
+            for (key_service, fields) in &keys {
+                if (preferred.is_none() || preferred == Some(*key_service)) && !entity_keys.contains(fields) {
+                    entity_keys.push(*fields);
+                }
+            }
+            if !entity_keys.is_empty() {
+                break;
+            }
+        }
+        entity_keys
+    }
+
+    /// The fields of the part of a type, and whether they are external.
+    fn fields(&self, ty: &'a TypeDefinition, part: Part, service: &str) -> Vec<(&'a FieldDefinition, bool)> {
+        let entity_keys = self.entity_keys(ty, service);
+        let key_fields = entity_keys
+            .iter()
+            .copied()
+            .flat_map(field_names)
+            .collect::<HashSet<_>>();
+        let required = field_definitions(ty)
+            .iter()
+            .filter(|field| resolve(&field.node) == Some(service))
+            .flat_map(|field| &field.node.directives)
+            .filter(|directive| directive.node.name.node.as_str() == "requires")
+            .filter_map(|directive| get_argument_str(&directive.node.arguments, "fields"))
+            .flat_map(|fields| field_names(fields.node))
+            .collect::<HashSet<_>>();
+
+        field_definitions(ty)
+            .iter()
+            .map(|field| &field.node)
+            .filter_map(|field| {
+                let name = field.name.node.as_str();
+                let resolver = resolve(field);
+                match part {
+                    Part::Root => (resolver == Some(service)).then_some((field, false)),
+                    Part::Owned => resolver
+                        .is_none_or(|resolver| resolver == service)
+                        .then_some((field, false)),
+                    Part::Extension if resolver == Some(service) => Some((field, false)),
+                    Part::Extension => (key_fields.contains(name) || required.contains(name)).then_some((field, true)),
+                    Part::Reference if entity_keys.is_empty() => Some((field, true)),
+                    Part::Reference => key_fields.contains(name).then_some((field, false)),
+                    Part::Shared => Some((field, false)),
+                }
+            })
+            .collect()
+    }
+
+    fn write_type(&self, sdl: &mut String, ty: &'a TypeDefinition, part: Part, service: &str) {
+        if matches!(part, Part::Root | Part::Owned | Part::Shared) {
+            write_description(
+                sdl,
+                ty.description.as_ref().map(|description| description.node.as_str()),
+                "",
+            );
+        }
+        let name = ty.name.node.as_str();
+        match &ty.kind {
+            TypeKind::Scalar => {
+                sdl.push_str(&format!("scalar {}", name));
+                write_directives(sdl, &ty.directives);
+                sdl.push('\n');
+            },
+            TypeKind::Object(object) => {
+                let keys = match part {
+                    Part::Shared => Vec::new(),
+                    _ => self.entity_keys(ty, service),
+                };
+                if part == Part::Extension || (part == Part::Reference && keys.is_empty()) {
+                    sdl.push_str("extend ");
+                }
+                sdl.push_str(&format!("type {}", name));
+                if matches!(part, Part::Owned | Part::Shared) {
+                    write_implements(sdl, object.implements.iter().map(|name| name.node.as_str()));
+                }
+                for fields in keys {
+                    sdl.push_str(&format!(
+                        " @key(fields: {}",
+                        value::ConstValue::String(fields.to_string())
+                    ));
+                    if part == Part::Reference {
+                        sdl.push_str(", resolvable: false");
+                    }
+                    sdl.push(')');
+                }
+                if matches!(part, Part::Root | Part::Owned | Part::Shared) {
+                    write_directives(sdl, &ty.directives);
+                }
+                if part == Part::Shared && !has_directive(&ty.directives, "shareable") {
+                    sdl.push_str(" @shareable");
+                }
+                self.write_fields(sdl, ty, part, service);
+            },
+            TypeKind::Interface(interface) => {
+                sdl.push_str(&format!("interface {}", name));
+                write_implements(sdl, interface.implements.iter().map(|name| name.node.as_str()));
+                write_directives(sdl, &ty.directives);
+                self.write_fields(sdl, ty, part, service);
+            },
+            TypeKind::Union(union) => {
+                sdl.push_str(&format!("union {}", name));
+                write_directives(sdl, &ty.directives);
+                let members = union
+                    .members
+                    .iter()
+                    .map(|member| member.node.as_str())
+                    .collect::<Vec<_>>();
+                sdl.push_str(&format!(" = {}\n", members.join(" | ")));
+            },
+            TypeKind::Enum(enum_type) => {
+                sdl.push_str(&format!("enum {}", name));
+                write_directives(sdl, &ty.directives);
+                sdl.push_str(" {\n");
+                for value in &enum_type.values {
+                    write_description(
+                        sdl,
+                        value
+                            .node
+                            .description
+                            .as_ref()
+                            .map(|description| description.node.as_str()),
+                        "  ",
+                    );
+                    sdl.push_str(&format!("  {}", value.node.value.node));
+                    write_directives(sdl, &value.node.directives);
+                    sdl.push('\n');
+                }
+                sdl.push_str("}\n");
+            },
+            TypeKind::InputObject(input) => {
+                sdl.push_str(&format!("input {}", name));
+                write_directives(sdl, &ty.directives);
+                sdl.push_str(" {\n");
+                for field in &input.fields {
+                    write_description(
+                        sdl,
+                        field
+                            .node
+                            .description
+                            .as_ref()
+                            .map(|description| description.node.as_str()),
+                        "  ",
+                    );
+                    sdl.push_str("  ");
+                    write_input_value(sdl, &field.node);
+                    sdl.push('\n');
+                }
+                sdl.push_str("}\n");
+            },
+        }
+    }
+
+    fn write_fields(&self, sdl: &mut String, ty: &'a TypeDefinition, part: Part, service: &str) {
+        sdl.push_str(" {\n");
+        for (field, external) in self.fields(ty, part, service) {
+            write_description(
+                sdl,
+                field.description.as_ref().map(|description| description.node.as_str()),
+                "  ",
+            );
+            sdl.push_str(&format!("  {}", field.name.node));
+            if !field.arguments.is_empty() {
+                sdl.push('(');
+                for (idx, arg) in field.arguments.iter().enumerate() {
+                    if idx > 0 {
+                        sdl.push_str(", ");
+                    }
+                    write_input_value(sdl, &arg.node);
+                }
+                sdl.push(')');
+            }
+            sdl.push_str(&format!(": {}", field.ty.node));
+            if external {
+                sdl.push_str(" @external");
+            } else if part == Part::Reference {
+                write_directives(
+                    sdl,
+                    field
+                        .directives
+                        .iter()
+                        .filter(|directive| !matches!(directive.node.name.node.as_str(), "requires" | "provides")),
+                );
+            } else {
+                write_directives(sdl, &field.directives);
+            }
+            sdl.push('\n');
+        }
+        sdl.push_str("}\n");
+    }
+}
+
+fn owner(ty: &TypeDefinition) -> Option<&str> {
+    ty.directives
+        .iter()
+        .find(|directive| directive.node.name.node.as_str() == "owner")
+        .and_then(|directive| get_argument_str(&directive.node.arguments, "service"))
+        .map(|service| service.node)
+}
+
+/// The services of the `@key` directives of a type, with their fields.
+fn keys(ty: &TypeDefinition) -> Vec<(&str, &str)> {
+    ty.directives
+        .iter()
+        .filter(|directive| directive.node.name.node.as_str() == "key")
+        .filter_map(|directive| {
+            let arguments = &directive.node.arguments;
+            let service = get_argument_str(arguments, "service")?;
+            let fields = get_argument_str(arguments, "fields")?;
+            Some((service.node, fields.node))
+        })
+        .collect()
+}
+
+fn resolve(field: &FieldDefinition) -> Option<&str> {
+    field
+        .directives
+        .iter()
+        .find(|directive| directive.node.name.node.as_str() == "resolve")
+        .and_then(|directive| get_argument_str(&directive.node.arguments, "service"))
+        .map(|service| service.node)
+}
+
+fn field_definitions(ty: &TypeDefinition) -> &[Positioned<FieldDefinition>] {
+    match &ty.kind {
+        TypeKind::Object(object) => &object.fields,
+        TypeKind::Interface(interface) => &interface.fields,
+        _ => &[],
+    }
+}
+
+/// The names of the top-level fields of a field set.
+fn field_names(fields: &str) -> Vec<String> {
+    let Some(selection_set) = parse_fields(fields) else {
+        return Vec::new();
+    };
+    selection_set
+        .items
+        .into_iter()
+        .filter_map(|selection| match selection.node {
+            Selection::Field(field) => Some(field.node.name.node.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn named_type(ty: &Type) -> &str {
+    match &ty.base {
+        BaseType::Named(name) => name.as_str(),
+        BaseType::List(ty) => named_type(ty),
+    }
+}
+
+fn write_description(sdl: &mut String, description: Option<&str>, indent: &str) {
+    if let Some(description) = description {
+        sdl.push_str(&format!(
+            "{}\"\"\"{}\"\"\"\n",
+            indent,
+            description.replace("\"\"\"", "\\\"\"\"")
+        ));
+    }
+}
+
+fn write_implements<'b>(sdl: &mut String, implements: impl Iterator<Item = &'b str>) {
+    let implements = implements.collect::<Vec<_>>();
+    if !implements.is_empty() {
+        sdl.push_str(&format!(" implements {}", implements.join(" & ")));
+    }
+}
+
+fn write_directives<'b>(sdl: &mut String, directives: impl IntoIterator<Item = &'b Positioned<ConstDirective>>) {
+    for directive in directives {
+        let name = directive.node.name.node.as_str();
+        if LEGACY_DIRECTIVES.contains(&name) {
+            continue;
+        }
+        sdl.push_str(&format!(" @{}", name));
+        if !directive.node.arguments.is_empty() {
+            let arguments = directive
+                .node
+                .arguments
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name.node, value.node))
+                .collect::<Vec<_>>();
+            sdl.push_str(&format!("({})", arguments.join(", ")));
+        }
+    }
+}
+
+fn write_input_value(sdl: &mut String, input_value: &InputValueDefinition) {
+    sdl.push_str(&format!("{}: {}", input_value.name.node, input_value.ty.node));
+    if let Some(default_value) = &input_value.default_value {
+        sdl.push_str(&format!(" = {}", default_value.node));
+    }
+    write_directives(sdl, &input_value.directives);
+}
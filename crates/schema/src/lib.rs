@@ -2,19 +2,42 @@
 
 mod composed_schema;
 mod error;
+mod hints;
+mod legacy;
+mod supergraph;
 mod type_ext;
 mod value_ext;
 
 pub use composed_schema::{
+    CacheControl,
+    CacheScope,
     ComposedSchema,
     Deprecation,
+    EnumValueRenames,
     KeyFields,
+    ListSize,
+    MetaDirective,
     MetaEnumValue,
     MetaField,
     MetaInputValue,
     MetaType,
     TypeKind,
 };
-pub use error::CombineError;
+pub use error::{CombineError, SupergraphError};
+pub use hints::{
+    composition_hints,
+    infer_shareable,
+    CompositionHint,
+    HintNode,
+    HintSeverity,
+    EXTERNAL_UNUSED,
+    FIELD_ARGUMENT_DEFAULT_MISMATCH,
+    INCONSISTENT_DEFAULT_VALUE_PRESENCE,
+    INCONSISTENT_DESCRIPTION,
+    INPUT_FIELD_DEFAULT_MISMATCH,
+    SHAREABLE_INFERRED,
+};
+pub use legacy::{translate_legacy_sdl, SubgraphSdl};
+pub use supergraph::{JoinGraph, Supergraph};
 pub use type_ext::TypeExt;
 pub use value_ext::ValueExt;
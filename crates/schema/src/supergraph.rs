@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use parser::{
+    types::{ConstDirective, ServiceDocument, TypeDefinition, TypeKind as DefinitionKind, TypeSystemDefinition},
+    Positioned,
+};
+use value::{ConstValue, Name};
+
+use crate::{
+    composed_schema::{
+        convert_key_fields,
+        convert_schema_definition,
+        convert_type_definition,
+        finish_schema,
+        get_argument_bool,
+        get_argument_str,
+        has_directive,
+        parse_fields,
+    },
+    ComposedSchema,
+    SupergraphError,
+};
+
+/// A subgraph of a supergraph, declared by a value of the `join__Graph`
+/// enum.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JoinGraph {
+    /// The name of the subgraph, which the schema routes to.
+    pub name: String,
+    /// The URL of the subgraph.
+    pub url: String,
+}
+
+/// A schema composed ahead of time, such as the supergraph SDL produced by
+/// `rover supergraph compose`.
+#[derive(Debug)]
+pub struct Supergraph {
+    pub schema: ComposedSchema,
+    pub graphs: Vec<JoinGraph>,
+}
+
+impl Supergraph {
+    pub fn parse(document: &str) -> Result<Self, SupergraphError> {
+        Self::new(parser::parse_schema(document)?)
+    }
+
+    /// Convert the `@join__*` directives of a supergraph into the owners,
+    /// keys and resolving services of the composed schema.
+    pub fn new(document: ServiceDocument) -> Result<Self, SupergraphError> {
+        let graphs = join_graphs(&document)?;
+        let graph_names = graphs
+            .iter()
+            .map(|(value, graph)| (value.clone(), graph.name.clone()))
+            .collect::<HashMap<_, _>>();
+        let graph_name = |directive: &ConstDirective| -> Result<Option<String>, SupergraphError> {
+            let value = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name.node.as_str() == "graph")
+                .and_then(|(_, value)| match &value.node {
+                    ConstValue::Enum(value) => Some(value.as_str()),
+                    ConstValue::String(value) => Some(value.as_str()),
+                    _ => None,
+                });
+            match value {
+                Some(value) => graph_names
+                    .get(value)
+                    .cloned()
+                    .map(Some)
+                    .ok_or_else(|| SupergraphError::UnknownGraph {
+                        graph: value.to_string(),
+                    }),
+                None => Ok(None),
+            }
+        };
+
+        let mut schema = ComposedSchema::default();
+        let mut type_definitions = Vec::new();
+        for definition in document.definitions {
+            match definition {
+                TypeSystemDefinition::Schema(schema_definition) => {
+                    convert_schema_definition(&mut schema, schema_definition.node);
+                },
+                TypeSystemDefinition::Type(type_definition) => {
+                    let name = type_definition.node.name.node.as_str();
+                    if !is_join_type(name) && !has_directive(&type_definition.node.directives, "inaccessible") {
+                        type_definitions.push(type_definition.node);
+                    }
+                },
+                TypeSystemDefinition::Directive(_) => {},
+            }
+        }
+        let root_types = [
+            schema.query_type.clone().unwrap_or_else(|| Name::new("Query")),
+            schema.mutation_type.clone().unwrap_or_else(|| Name::new("Mutation")),
+            schema
+                .subscription_type
+                .clone()
+                .unwrap_or_else(|| Name::new("Subscription")),
+        ];
+
+        for definition in type_definitions {
+            let is_root = root_types.contains(&definition.name.node);
+            let type_directives = definition.directives.clone();
+            let field_directives = field_directives(&definition);
+            let mut ty = convert_type_definition(without_inaccessible(definition));
+
+            // The graphs defining the type, and those resolving its entities
+            // by key.
+            let mut type_graphs = Vec::new();
+            let mut entity_graphs = Vec::new();
+            let mut owner = None;
+            for directive in &type_directives {
+                match directive.node.name.node.as_str() {
+                    "join__type" => {
+                        let Some(graph) = graph_name(&directive.node)? else {
+                            continue;
+                        };
+                        let arguments = &directive.node.arguments;
+                        if let Some(key) = get_argument_str(arguments, "key").and_then(|key| parse_fields(key.node)) {
+                            ty.keys.entry(graph.clone()).or_default().push(convert_key_fields(key));
+                            let extension = get_argument_bool(arguments, "extension").is_some_and(|value| value.node);
+                            let resolvable = get_argument_bool(arguments, "resolvable").is_none_or(|value| value.node);
+                            if resolvable && !extension && !entity_graphs.contains(&graph) {
+                                entity_graphs.push(graph.clone());
+                            }
+                        }
+                        if !type_graphs.contains(&graph) {
+                            type_graphs.push(graph);
+                        }
+                    },
+                    // Federation 1 supergraphs name the owner of entities.
+                    "join__owner" => owner = graph_name(&directive.node)?,
+                    _ => {},
+                }
+            }
+            ty.owner = match owner {
+                _ if is_root => None,
+                Some(owner) => Some(owner),
+                None if !entity_graphs.is_empty() => entity_graphs.first().cloned(),
+                None if type_graphs.len() == 1 => type_graphs.first().cloned(),
+                None => None,
+            };
+
+            for (field_name, directives) in field_directives {
+                let Some(field) = ty.fields.get_mut(&field_name) else {
+                    continue;
+                };
+                let mut field_graphs = Vec::new();
+                for directive in directives.iter().filter(|d| d.node.name.node.as_str() == "join__field") {
+                    let arguments = &directive.node.arguments;
+                    if get_argument_bool(arguments, "external").is_some_and(|value| value.node) {
+                        continue;
+                    }
+                    if let Some(graph) = graph_name(&directive.node)? {
+                        field_graphs.push((graph, arguments));
+                    }
+                }
+
+                // Fields without `@join__field` are resolved by the graphs of
+                // the type, and those resolved by the owner need no service.
+                let resolving = field_graphs
+                    .iter()
+                    .find(|(graph, _)| ty.owner.as_ref() == Some(graph))
+                    .or(field_graphs.first());
+                match resolving {
+                    Some((graph, arguments)) => {
+                        if ty.owner.as_ref() != Some(graph) || is_root {
+                            field.service = Some(graph.clone());
+                        }
+                        field.requires = get_argument_str(arguments, "requires")
+                            .and_then(|fields| parse_fields(fields.node))
+                            .map(convert_key_fields);
+                        field.provides = get_argument_str(arguments, "provides")
+                            .and_then(|fields| parse_fields(fields.node))
+                            .map(convert_key_fields);
+                    },
+                    None if is_root => field.service = type_graphs.first().cloned(),
+                    None => {},
+                }
+            }
+
+            schema.types.insert(ty.name.clone(), ty);
+        }
+
+        if schema.query_type.is_none() && schema.types.contains_key("Query") {
+            schema.query_type = Some(Name::new("Query"));
+        }
+        if schema.mutation_type.is_none() && schema.types.contains_key("Mutation") {
+            schema.mutation_type = Some(Name::new("Mutation"));
+        }
+        if schema.subscription_type.is_none() && schema.types.contains_key("Subscription") {
+            schema.subscription_type = Some(Name::new("Subscription"));
+        }
+        finish_schema(&mut schema);
+
+        Ok(Self {
+            schema,
+            graphs: graphs.into_iter().map(|(_, graph)| graph).collect(),
+        })
+    }
+}
+
+/// The types of the join, link and core specifications, which are not part
+/// of the API schema.
+fn is_join_type(name: &str) -> bool {
+    ["join__", "link__", "core__"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// The graphs of the `join__Graph` enum, by enum value.
+fn join_graphs(document: &ServiceDocument) -> Result<Vec<(String, JoinGraph)>, SupergraphError> {
+    let values = document
+        .definitions
+        .iter()
+        .find_map(|definition| match definition {
+            TypeSystemDefinition::Type(ty) if ty.node.name.node.as_str() == "join__Graph" => match &ty.node.kind {
+                DefinitionKind::Enum(enum_type) => Some(&enum_type.values),
+                _ => None,
+            },
+            _ => None,
+        })
+        .ok_or(SupergraphError::MissingGraphs)?;
+
+    values
+        .iter()
+        .map(|value| {
+            let directive = value
+                .node
+                .directives
+                .iter()
+                .find(|directive| directive.node.name.node.as_str() == "join__graph");
+            let arguments = directive
+                .map(|directive| directive.node.arguments.as_slice())
+                .unwrap_or_default();
+            match (get_argument_str(arguments, "name"), get_argument_str(arguments, "url")) {
+                (Some(name), Some(url)) => Ok((value.node.value.node.to_string(), JoinGraph {
+                    name: name.node.to_string(),
+                    url: url.node.to_string(),
+                })),
+                _ => Err(SupergraphError::InvalidGraph {
+                    graph: value.node.value.node.to_string(),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// The directives of the fields of an object or interface.
+fn field_directives(definition: &TypeDefinition) -> Vec<(Name, Vec<Positioned<ConstDirective>>)> {
+    let fields = match &definition.kind {
+        DefinitionKind::Object(object) => &object.fields,
+        DefinitionKind::Interface(interface) => &interface.fields,
+        _ => return Vec::new(),
+    };
+    fields
+        .iter()
+        .map(|field| (field.node.name.node.clone(), field.node.directives.clone()))
+        .collect()
+}
+
+/// Remove the fields and enum values marked `@inaccessible`.
+fn without_inaccessible(mut definition: TypeDefinition) -> TypeDefinition {
+    match &mut definition.kind {
+        DefinitionKind::Object(object) => object
+            .fields
+            .retain(|field| !has_directive(&field.node.directives, "inaccessible")),
+        DefinitionKind::Interface(interface) => interface
+            .fields
+            .retain(|field| !has_directive(&field.node.directives, "inaccessible")),
+        DefinitionKind::Enum(enum_type) => enum_type
+            .values
+            .retain(|value| !has_directive(&value.node.directives, "inaccessible")),
+        DefinitionKind::InputObject(input_object) => input_object
+            .fields
+            .retain(|field| !has_directive(&field.node.directives, "inaccessible")),
+        _ => {},
+    }
+    definition
+}
@@ -0,0 +1,147 @@
+use graphgate_schema::{
+    composition_hints,
+    infer_shareable,
+    CombineError,
+    ComposedSchema,
+    HintSeverity,
+    EXTERNAL_UNUSED,
+    FIELD_ARGUMENT_DEFAULT_MISMATCH,
+    INCONSISTENT_DEFAULT_VALUE_PRESENCE,
+    INCONSISTENT_DESCRIPTION,
+    SHAREABLE_INFERRED,
+};
+use pretty_assertions::assert_eq;
+
+fn hints(subgraphs: &[(&str, &str)]) -> Vec<(&'static str, HintSeverity, String)> {
+    let subgraphs = subgraphs
+        .iter()
+        .map(|(name, sdl)| (name.to_string(), parser::parse_schema(sdl).unwrap()))
+        .collect::<Vec<_>>();
+    composition_hints(&subgraphs)
+        .into_iter()
+        .map(|hint| (hint.code, hint.severity, hint.message))
+        .collect()
+}
+
+#[test]
+fn no_hints() {
+    assert!(hints(&[
+        (
+            "accounts",
+            r#"type Query { me: User } type User @key(fields: "id") { id: ID! }"#
+        ),
+        (
+            "reviews",
+            r#"extend type User @key(fields: "id") { id: ID! @external reviews(first: Int = 10): [String] }"#
+        ),
+    ])
+    .is_empty());
+}
+
+#[test]
+fn inconsistent_elements() {
+    let hints = hints(&[
+        (
+            "accounts",
+            r#"
+            type User @key(fields: "id") { "The user id" id: ID! users(first: Int = 10, after: String): [User] }
+            "#,
+        ),
+        (
+            "reviews",
+            r#"
+            extend type User @key(fields: "id") {
+                "The reviewer id" id: ID! @external
+                name: String @external
+                users(first: Int = 20, after: String = ""): [User]
+            }
+            "#,
+        ),
+    ]);
+    assert_eq!(hints, vec![
+        (
+            EXTERNAL_UNUSED,
+            HintSeverity::Warn,
+            "Field \"User.name\" is marked @external in subgraph \"reviews\", but no @key, @requires or @provides \
+             selects it."
+                .to_string()
+        ),
+        (
+            INCONSISTENT_DEFAULT_VALUE_PRESENCE,
+            HintSeverity::Warn,
+            "Argument \"User.users(after:)\" has a default value only in subgraphs \"reviews\".".to_string()
+        ),
+        (
+            FIELD_ARGUMENT_DEFAULT_MISMATCH,
+            HintSeverity::Warn,
+            "Argument \"User.users(first:)\" has different default values in subgraphs \"accounts\", \"reviews\"."
+                .to_string()
+        ),
+        (
+            INCONSISTENT_DESCRIPTION,
+            HintSeverity::Info,
+            "Element \"User.id\" has inconsistent descriptions across subgraphs \"accounts\", \"reviews\".".to_string()
+        ),
+    ]);
+}
+
+#[test]
+fn externals_used_by_requires_and_provides() {
+    let hints = hints(&[(
+        "reviews",
+        r#"
+        type Review { author: User @provides(fields: "name") }
+        extend type User @key(fields: "id") {
+            id: ID! @external
+            name: String @external
+            email: String @external
+            domain: String @requires(fields: "email")
+        }
+        "#,
+    )]);
+    assert!(hints.is_empty());
+}
+
+#[test]
+fn infer_shareable_value_types() {
+    let mut subgraphs = [
+        (
+            "accounts",
+            r#"
+            type Query { me: User }
+            type User @key(fields: "id") { id: ID! address: Address price: Money }
+            type Address { street: String! city: String! }
+            type Money { amount: Int! currency: String! }
+            "#,
+        ),
+        (
+            "shipping",
+            r#"
+            type Query { shipment: Shipment }
+            type Shipment { to: Address cost: Money }
+            "A postal address" type Address { city: String! street: String! }
+            type Money { amount: Float! currency: String! }
+            "#,
+        ),
+    ]
+    .map(|(name, sdl)| (name.to_string(), parser::parse_schema(sdl).unwrap()));
+
+    let hints = infer_shareable(&mut subgraphs)
+        .into_iter()
+        .map(|hint| (hint.code, hint.severity, hint.message))
+        .collect::<Vec<_>>();
+    assert_eq!(hints, vec![(
+        SHAREABLE_INFERRED,
+        HintSeverity::Info,
+        r#"Value type "Address" is defined identically in subgraphs "accounts", "shipping" and was made shareable."#
+            .to_string()
+    )]);
+
+    // The value types defined differently still conflict.
+    match ComposedSchema::combine(subgraphs) {
+        Err(CombineError::FieldConflicted { type_name, field_name }) => {
+            assert_eq!((type_name.as_str(), field_name.as_str()), ("Money", "amount"));
+        },
+        res => panic!("unexpected composition result {:?}", res.map(|_| ())),
+    }
+}
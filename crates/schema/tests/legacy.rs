@@ -0,0 +1,139 @@
+use graphgate_schema::{translate_legacy_sdl, ComposedSchema};
+use pretty_assertions::assert_eq;
+
+const LEGACY_SDL: &str = r#"
+directive @owner(service: String!) on OBJECT
+directive @key(fields: String! service: String!) on OBJECT
+directive @resolve(service: String!) on FIELD_DEFINITION
+directive @requires(fields: String!) on FIELD_DEFINITION
+
+schema { query: Query mutation: Mutation }
+
+type Query {
+    me: User @resolve(service: "accounts")
+    topProducts(first: Int = 5): [Product!]! @resolve(service: "products")
+}
+
+type Mutation {
+    createReview(body: String!): Review! @resolve(service: "reviews")
+}
+
+scalar DateTime
+
+type Timestamp {
+    createdAt: DateTime
+}
+
+"""A user."""
+type User
+@owner(service: "accounts")
+@key(fields: "id" service: "accounts")
+@key(fields: "id" service: "reviews")
+{
+    id: ID!
+    username: String!
+    timestamp: Timestamp!
+    reviews: [Review!]! @resolve(service: "reviews")
+}
+
+type Product
+@owner(service: "products")
+@key(fields: "upc" service: "products")
+{
+    upc: String!
+    name: String! @deprecated(reason: "Use `title`.")
+    weight: Int!
+    shippingEstimate: Int! @resolve(service: "inventory") @requires(fields: "weight")
+}
+
+enum Rating { GOOD BAD }
+
+type Review
+@owner(service: "reviews")
+@key(fields: "id" service: "reviews")
+{
+    id: ID!
+    body: String!
+    rating: Rating
+    author: User!
+}
+"#;
+
+#[test]
+fn translate_legacy_schema() {
+    let subgraphs = translate_legacy_sdl(LEGACY_SDL).unwrap();
+    let services = subgraphs
+        .iter()
+        .map(|subgraph| subgraph.service.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(services, ["accounts", "inventory", "products", "reviews"]);
+
+    assert_eq!(
+        subgraphs[1].sdl,
+        r#"extend schema @link(url: "https://specs.apollo.dev/federation/v2.3", import: ["@key", "@shareable", "@external", "@requires", "@provides"])
+
+extend type Product @key(fields: "upc") {
+  upc: String! @external
+  weight: Int! @external
+  shippingEstimate: Int! @requires(fields: "weight")
+}
+"#
+    );
+    assert_eq!(
+        subgraphs[3].sdl,
+        r#"extend schema @link(url: "https://specs.apollo.dev/federation/v2.3", import: ["@key", "@shareable", "@external", "@requires", "@provides"])
+
+type Mutation {
+  createReview(body: String!): Review!
+}
+
+extend type User @key(fields: "id") {
+  id: ID! @external
+  reviews: [Review!]!
+}
+
+enum Rating {
+  GOOD
+  BAD
+}
+
+type Review @key(fields: "id") {
+  id: ID!
+  body: String!
+  rating: Rating
+  author: User!
+}
+"#
+    );
+}
+
+#[test]
+fn translated_schema_routes_like_legacy_schema() {
+    let legacy = ComposedSchema::parse(LEGACY_SDL).unwrap();
+    let translated = ComposedSchema::combine(
+        translate_legacy_sdl(LEGACY_SDL)
+            .unwrap()
+            .into_iter()
+            .map(|subgraph| (subgraph.service, parser::parse_schema(subgraph.sdl).unwrap())),
+    )
+    .unwrap();
+
+    for ty in legacy.types.values().filter(|ty| !ty.name.starts_with("__")) {
+        let translated_ty = &translated.types[&ty.name];
+        assert_eq!(translated_ty.owner, ty.owner, "owner of {}", ty.name);
+        for (service, keys) in &ty.keys {
+            assert_eq!(translated_ty.keys.get(service), Some(keys), "keys of {}", ty.name);
+        }
+        for field in ty.fields.values().filter(|field| !field.name.starts_with("__")) {
+            let translated_field = &translated_ty.fields[&field.name];
+            assert_eq!(
+                translated_field.service.as_ref().or(translated_ty.owner.as_ref()),
+                field.service.as_ref().or(ty.owner.as_ref()),
+                "service of {}.{}",
+                ty.name,
+                field.name
+            );
+            assert_eq!(translated_field.requires, field.requires);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use graphgate_schema::{JoinGraph, Supergraph, SupergraphError};
+
+#[test]
+fn parse_supergraph() {
+    let supergraph = Supergraph::parse(include_str!("supergraph.graphql")).unwrap();
+    assert_eq!(supergraph.graphs, vec![
+        JoinGraph {
+            name: "accounts".to_string(),
+            url: "http://accounts:4001/graphql".to_string(),
+        },
+        JoinGraph {
+            name: "reviews".to_string(),
+            url: "https://reviews.example.com".to_string(),
+        },
+    ]);
+
+    let schema = &supergraph.schema;
+    assert_eq!(schema.query_type(), "Query");
+    assert!(schema
+        .types
+        .keys()
+        .all(|name| !name.starts_with("join__") && !name.starts_with("link__")));
+    assert!(!schema.types.contains_key("Secret"));
+
+    let query = &schema.types["Query"];
+    assert_eq!(query.owner, None);
+    assert_eq!(query.fields["me"].service.as_deref(), Some("accounts"));
+    assert_eq!(query.fields["topReviews"].service.as_deref(), Some("reviews"));
+
+    let review = &schema.types["Review"];
+    assert_eq!(review.owner.as_deref(), Some("reviews"));
+    assert!(review.keys.is_empty());
+    assert!(!review.fields.contains_key("internal"));
+    assert!(review.fields["author"].provides.is_some());
+
+    let user = &schema.types["User"];
+    assert_eq!(user.owner.as_deref(), Some("accounts"));
+    assert_eq!(user.keys.len(), 2);
+    assert_eq!(user.fields["id"].service, None);
+    assert_eq!(user.fields["username"].service, None);
+    assert_eq!(user.fields["reviews"].service.as_deref(), Some("reviews"));
+    assert_eq!(user.fields["reviewCount"].service.as_deref(), Some("reviews"));
+    assert!(user.fields["reviewCount"].requires.is_some());
+}
+
+#[test]
+fn supergraph_without_graphs() {
+    let err = Supergraph::parse("type Query { a: Int }").unwrap_err();
+    assert!(matches!(err, SupergraphError::MissingGraphs));
+}
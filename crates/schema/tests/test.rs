@@ -1,4 +1,4 @@
-use graphgate_schema::ComposedSchema;
+use graphgate_schema::{CacheControl, CacheScope, ComposedSchema, TypeKind};
 use parser::types::Type;
 use pretty_assertions::assert_eq;
 
@@ -34,3 +34,344 @@ fn test_combine_federated_schemas_in_any_order_should_return_same_result() {
     let collection_in_desc_order = schema_in_desc_order.get_type(&Type::new("Collection").unwrap());
     assert_eq!(collection_in_asc_order, collection_in_desc_order);
 }
+
+#[test]
+fn merge_deprecated_arguments_and_input_fields() {
+    let reviews = parser::parse_schema(
+        r#"
+        type Query { reviews(first: Int @deprecated(reason: "Use `limit`."), limit: Int, filter: ReviewFilter): [Review!]! @shareable }
+        type Review @shareable { body: String! }
+        input ReviewFilter { rating: Int @deprecated(reason: "Use `stars`.") stars: Int }
+        "#,
+    )
+    .unwrap();
+    let archive = parser::parse_schema(
+        r#"
+        type Query { reviews(first: Int, limit: Int, filter: ReviewFilter): [Review!]! @shareable }
+        type Review @shareable { body: String! }
+        input ReviewFilter { rating: Int stars: Int @deprecated }
+        "#,
+    )
+    .unwrap();
+
+    for services in [
+        [
+            ("reviews".to_string(), reviews.clone()),
+            ("archive".to_string(), archive.clone()),
+        ],
+        [("archive".to_string(), archive), ("reviews".to_string(), reviews)],
+    ] {
+        let schema = ComposedSchema::combine(services).unwrap();
+        let arguments = &schema.types["Query"].fields["reviews"].arguments;
+        assert_eq!(arguments["first"].deprecation.reason(), Some("Use `limit`."));
+        assert!(!arguments["limit"].deprecation.is_deprecated());
+
+        let input_fields = &schema.types["ReviewFilter"].input_fields;
+        assert_eq!(input_fields["rating"].deprecation.reason(), Some("Use `stars`."));
+        assert!(input_fields["stars"].deprecation.is_deprecated());
+    }
+}
+
+#[test]
+fn combine_interface_objects() {
+    let media = parser::parse_schema(
+        r#"
+        type Query { media: [Media!]! }
+        interface Media @key(fields: "id") { id: ID! title: String! }
+        type Book implements Media @key(fields: "id") { id: ID! title: String! }
+        type Movie implements Media @key(fields: "id") { id: ID! title: String! }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        type Query { topMedia: Media }
+        type Media @key(fields: "id") @interfaceObject { id: ID! reviews: [String!]! }
+        "#,
+    )
+    .unwrap();
+
+    for services in [
+        [
+            ("media".to_string(), media.clone()),
+            ("reviews".to_string(), reviews.clone()),
+        ],
+        [("reviews".to_string(), reviews), ("media".to_string(), media)],
+    ] {
+        let schema = ComposedSchema::combine(services).unwrap();
+        let interface = &schema.types["Media"];
+        assert_eq!(interface.kind, TypeKind::Interface);
+        assert_eq!(interface.owner.as_deref(), Some("media"));
+        assert!(interface.keys.contains_key("media") && interface.keys.contains_key("reviews"));
+        assert_eq!(interface.fields["reviews"].service.as_deref(), Some("reviews"));
+        assert_eq!(interface.fields["id"].service, None);
+
+        for name in ["Book", "Movie"] {
+            let ty = &schema.types[name];
+            assert_eq!(ty.fields["reviews"].service.as_deref(), Some("reviews"));
+            assert!(ty.keys.contains_key("reviews"));
+            assert_eq!(ty.interface_objects["reviews"], "Media");
+        }
+    }
+}
+
+#[test]
+fn combine_interface_object_without_interface() {
+    let reviews = parser::parse_schema(
+        r#"
+        type Query { topMedia: Media }
+        type Media @key(fields: "id") @interfaceObject { id: ID! reviews: [String!]! }
+        "#,
+    )
+    .unwrap();
+    let err = ComposedSchema::combine([("reviews".to_string(), reviews)]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Type 'Media' is an interface object, but no subgraph defines the interface."
+    );
+}
+
+#[test]
+fn combine_rejects_unknown_field_set_fields() {
+    let products = parser::parse_schema(
+        r#"
+        type Query { topProducts: [Product!]! }
+        type Product @key(fields: "upc") { upc: String! weight: Int! dimensions: Dimensions! }
+        type Dimensions @shareable { width: Int! height: Int! }
+        "#,
+    )
+    .unwrap();
+    let combine = |inventory: &str| {
+        ComposedSchema::combine([
+            ("products".to_string(), products.clone()),
+            ("inventory".to_string(), parser::parse_schema(inventory).unwrap()),
+        ])
+    };
+
+    combine(
+        r#"
+        extend type Product @key(fields: "upc") {
+            upc: String! @external
+            weight: Int! @external
+            dimensions: Dimensions! @external
+            shippingEstimate: Int! @requires(fields: "weight dimensions { width }")
+        }
+        type Dimensions @shareable { width: Int! height: Int! }
+        "#,
+    )
+    .unwrap();
+
+    let err = combine(
+        r#"
+        extend type Product @key(fields: "upc") {
+            upc: String! @external
+            shippingEstimate: Int! @requires(fields: "wieght")
+        }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The @requires field set of 'Product.shippingEstimate' references unknown field 'Product.wieght'."
+    );
+
+    let err = combine(
+        r#"
+        extend type Product @key(fields: "upc") {
+            upc: String! @external
+            dimensions: Dimensions! @external
+            shippingEstimate: Int! @requires(fields: "dimensions { depth }")
+        }
+        type Dimensions @shareable { width: Int! height: Int! }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The @requires field set of 'Product.shippingEstimate' references unknown field 'Dimensions.depth'."
+    );
+
+    let err = combine(
+        r#"
+        type Query { cheapest: Product @provides(fields: "price") }
+        extend type Product @key(fields: "upc") { upc: String! @external }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The @provides field set of 'Query.cheapest' references unknown field 'Product.price'."
+    );
+
+    let err = combine(
+        r#"
+        extend type Product @key(fields: "upc") {
+            upc: String! @external
+            shippingEstimate: Int! @requires(fields: "weight {")
+        }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The @requires field set \"weight {\" of 'Product.shippingEstimate' is invalid."
+    );
+}
+
+#[test]
+fn combine_cache_control_hints() {
+    let accounts = parser::parse_schema(
+        r#"
+        type Query { me: User }
+        type User @key(fields: "id") @cacheControl(maxAge: 60) { id: ID! username: String! }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        extend type User @key(fields: "id") @cacheControl(maxAge: 30, scope: PRIVATE) {
+            id: ID! @external
+            reviews: [String!]! @cacheControl(maxAge: 10)
+        }
+        "#,
+    )
+    .unwrap();
+    let schema =
+        ComposedSchema::combine([("accounts".to_string(), accounts), ("reviews".to_string(), reviews)]).unwrap();
+
+    let user = &schema.types["User"];
+    assert_eq!(
+        user.cache_control,
+        Some(CacheControl {
+            max_age: Some(30),
+            scope: CacheScope::Private,
+        })
+    );
+    assert_eq!(
+        user.fields["reviews"].cache_control,
+        Some(CacheControl {
+            max_age: Some(10),
+            scope: CacheScope::Public,
+        })
+    );
+    assert_eq!(user.fields["username"].cache_control, None);
+}
+
+#[test]
+fn combine_one_of_input_objects() {
+    let accounts = parser::parse_schema(
+        r#"
+        type Query { user(by: UserBy!): String }
+        input UserBy @oneOf { id: ID email: String }
+        "#,
+    )
+    .unwrap();
+    let combine = |reviews: &str| {
+        ComposedSchema::combine([
+            ("accounts".to_string(), accounts.clone()),
+            ("reviews".to_string(), parser::parse_schema(reviews).unwrap()),
+        ])
+    };
+
+    let schema = combine(
+        r#"
+        type Query { reviews(author: UserBy!): [String!]! }
+        input UserBy @oneOf { id: ID email: String }
+        "#,
+    )
+    .unwrap();
+    assert!(schema.types["UserBy"].one_of);
+
+    let err = combine(
+        r#"
+        type Query { reviews(author: UserBy!): [String!]! }
+        input UserBy { id: ID email: String }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Type 'UserBy' definition conflicted.");
+}
+
+#[test]
+fn combine_tags_and_inaccessible() {
+    let accounts = parser::parse_schema(
+        r#"
+        type Query { me: User }
+        type User @key(fields: "id") @tag(name: "public") { id: ID! ssn: String @inaccessible }
+        enum Role @tag(name: "internal") { ADMIN USER }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        extend type User @key(fields: "id") @tag(name: "reviews") {
+            id: ID! @external
+            reviews: [String!]! @tag(name: "internal")
+        }
+        enum Role @inaccessible { ADMIN USER }
+        "#,
+    )
+    .unwrap();
+    let schema =
+        ComposedSchema::combine([("accounts".to_string(), accounts), ("reviews".to_string(), reviews)]).unwrap();
+
+    let user = &schema.types["User"];
+    assert_eq!(user.tags, vec!["public", "reviews"]);
+    assert!(!user.inaccessible);
+    assert!(user.fields["ssn"].inaccessible);
+    assert_eq!(user.fields["reviews"].tags, vec!["internal"]);
+    let role = &schema.types["Role"];
+    assert_eq!(role.tags, vec!["internal"]);
+    assert!(role.inaccessible);
+}
+
+#[test]
+fn combine_authenticated_and_requires_scopes() {
+    let accounts = parser::parse_schema(
+        r#"
+        type Query { me: User @authenticated }
+        type User @key(fields: "id") @requiresScopes(scopes: [["user:read"]]) {
+            id: ID!
+            email: String! @requiresScopes(scopes: [["pii"], ["admin"]])
+        }
+        "#,
+    )
+    .unwrap();
+    let reviews = parser::parse_schema(
+        r#"
+        extend type User @key(fields: "id") @authenticated {
+            id: ID! @external
+            reviews: [String!]!
+        }
+        interface Node @requiresScopes(scopes: [["node"]]) { id: ID! }
+        "#,
+    )
+    .unwrap();
+    let billing = parser::parse_schema(
+        r#"
+        type User @key(fields: "id") @shareable {
+            id: ID!
+            email: String! @shareable @requiresScopes(scopes: [["billing"]])
+        }
+        "#,
+    )
+    .unwrap();
+    let schema = ComposedSchema::combine([
+        ("accounts".to_string(), accounts),
+        ("reviews".to_string(), reviews),
+        ("billing".to_string(), billing),
+    ])
+    .unwrap();
+
+    assert!(schema.types["Query"].fields["me"].authenticated);
+    let user = &schema.types["User"];
+    assert!(user.authenticated);
+    assert_eq!(user.requires_scopes, vec![vec!["user:read"]]);
+    assert!(!user.fields["reviews"].authenticated);
+    // Both subgraphs' scopes are required.
+    assert_eq!(user.fields["email"].requires_scopes, vec![
+        vec!["pii", "billing"],
+        vec!["admin", "billing"]
+    ]);
+    assert_eq!(schema.types["Node"].requires_scopes, vec![vec!["node"]]);
+}
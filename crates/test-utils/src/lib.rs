@@ -0,0 +1,32 @@
+//! In-process fake subgraphs for end-to-end tests of a federation.
+//!
+//! A fake subgraph is built from its SDL and a set of resolver closures, and
+//! serves GraphQL over HTTP and WebSocket on a random local port:
+//!
+//! ```no_run
+//! # async fn example() {
+//! use graphgate_test_utils::SubgraphBuilder;
+//! use value::ConstValue;
+//!
+//! let accounts = SubgraphBuilder::new("accounts", "type Query { me: String }")
+//!     .field("me", |_| Ok(ConstValue::String("alice".to_string())))
+//!     .spawn()
+//!     .await;
+//! println!("{}", accounts.addr());
+//! # }
+//! ```
+#![forbid(unsafe_code)]
+#![allow(clippy::result_large_err)]
+
+mod resolve;
+mod subgraph;
+
+pub use subgraph::{
+    EntityResolver,
+    FieldResolver,
+    RecordedRequest,
+    ResolverContext,
+    Subgraph,
+    SubgraphBuilder,
+    SubscriptionResolver,
+};
@@ -0,0 +1,365 @@
+use std::{collections::HashMap, convert::Infallible};
+
+use graphgate_planner::{Request, Response, ServerError};
+use indexmap::IndexMap;
+use parser::{
+    types::{
+        Directive,
+        DocumentOperations,
+        Field,
+        FragmentDefinition,
+        OperationDefinition,
+        OperationType,
+        Selection,
+        SelectionSet,
+    },
+    Positioned,
+};
+use value::{ConstValue, Name, Variables};
+use warp::http::HeaderMap;
+
+use crate::subgraph::{Inner, ResolverContext};
+
+struct Context<'a> {
+    subgraph: &'a Inner,
+    fragments: &'a HashMap<Name, Positioned<FragmentDefinition>>,
+    variables: &'a Variables,
+    headers: &'a HeaderMap,
+    errors: Vec<ServerError>,
+}
+
+impl<'a> Context<'a> {
+    fn arguments(&self, field: &Field) -> IndexMap<Name, ConstValue> {
+        field
+            .arguments
+            .iter()
+            .map(|(name, value)| {
+                let value = value
+                    .node
+                    .clone()
+                    .into_const_with(|name| Ok::<_, Infallible>(self.variables.get(&name).cloned().unwrap_or_default()))
+                    .unwrap();
+                (name.node.clone(), value)
+            })
+            .collect()
+    }
+
+    fn field_type(&self, ty: &str, field: &str) -> Option<&'a str> {
+        self.subgraph
+            .object_types
+            .get(ty)
+            .and_then(|fields| fields.get(field))
+            .map(String::as_str)
+    }
+
+    fn error(&mut self, message: impl Into<String>, path: Vec<ConstValue>) {
+        let mut error = ServerError::new(message);
+        error.path = path;
+        self.errors.push(error);
+    }
+
+    /// Whether a fragment on `type_condition` applies to an object of type
+    /// `typename`.
+    fn type_matches(&self, type_condition: &str, typename: &str) -> bool {
+        type_condition == typename ||
+            self.subgraph
+                .interfaces
+                .get(typename)
+                .is_some_and(|interfaces| interfaces.iter().any(|interface| interface == type_condition))
+    }
+
+    /// Evaluates the `@skip` and `@include` directives of a selection.
+    fn is_included(&self, directives: &[Positioned<Directive>]) -> bool {
+        directives.iter().all(|directive| {
+            let condition = directive
+                .node
+                .get_argument("if")
+                .and_then(|value| {
+                    value
+                        .node
+                        .clone()
+                        .into_const_with(|name| {
+                            Ok::<_, Infallible>(self.variables.get(&name).cloned().unwrap_or_default())
+                        })
+                        .ok()
+                })
+                .map(|value| value == ConstValue::Boolean(true))
+                .unwrap_or_default();
+            match directive.node.name.node.as_str() {
+                "skip" => !condition,
+                "include" => condition,
+                _ => true,
+            }
+        })
+    }
+
+    fn collect_fields(&self, selection_set: &'a SelectionSet, typename: Option<&str>, fields: &mut Vec<&'a Field>) {
+        for selection in &selection_set.items {
+            if !self.is_included(selection.node.directives()) {
+                continue;
+            }
+            match &selection.node {
+                Selection::Field(field) => fields.push(&field.node),
+                Selection::InlineFragment(fragment) => {
+                    let matches = match (&fragment.node.type_condition, typename) {
+                        (Some(type_condition), Some(typename)) => {
+                            self.type_matches(&type_condition.node.on.node, typename)
+                        },
+                        _ => true,
+                    };
+                    if matches {
+                        self.collect_fields(&fragment.node.selection_set.node, typename, fields);
+                    }
+                },
+                Selection::FragmentSpread(spread) => {
+                    if let Some(fragment) = self.fragments.get(&spread.node.fragment_name.node) {
+                        let matches = match typename {
+                            Some(typename) => self.type_matches(&fragment.node.type_condition.node.on.node, typename),
+                            None => true,
+                        };
+                        if matches {
+                            self.collect_fields(&fragment.node.selection_set.node, typename, fields);
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    /// Select the fields of `value`, an instance of the type named `ty`.
+    fn project(&self, value: &ConstValue, ty: Option<&str>, selection_set: &'a SelectionSet) -> ConstValue {
+        if selection_set.items.is_empty() {
+            return value.clone();
+        }
+        match value {
+            ConstValue::Object(obj) => {
+                let typename = match obj.get("__typename") {
+                    Some(ConstValue::String(typename)) => Some(typename.as_str()),
+                    _ => ty.filter(|ty| self.subgraph.object_types.contains_key(*ty)),
+                };
+                let mut fields = Vec::new();
+                self.collect_fields(selection_set, typename, &mut fields);
+
+                let mut res = IndexMap::new();
+                for field in fields {
+                    let key = field.alias.as_ref().unwrap_or(&field.name).node.clone();
+                    let value = if field.name.node == "__typename" {
+                        typename.map(|typename| ConstValue::String(typename.to_string()))
+                    } else {
+                        let field_type = typename.and_then(|typename| self.field_type(typename, &field.name.node));
+                        obj.get(field.name.node.as_str())
+                            .map(|value| self.project(value, field_type, &field.selection_set.node))
+                    };
+                    res.insert(key, value.unwrap_or_default());
+                }
+                ConstValue::Object(res)
+            },
+            ConstValue::List(values) => ConstValue::List(
+                values
+                    .iter()
+                    .map(|value| self.project(value, ty, selection_set))
+                    .collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    fn resolve_entities(&mut self, field: &'a Field, key: &Name) -> ConstValue {
+        let representations = match self.arguments(field).shift_remove("representations") {
+            Some(ConstValue::List(representations)) => representations,
+            _ => {
+                self.error("Missing representations.", vec![ConstValue::String(key.to_string())]);
+                return ConstValue::Null;
+            },
+        };
+
+        let mut entities = Vec::with_capacity(representations.len());
+        for (idx, representation) in representations.iter().enumerate() {
+            let path = vec![ConstValue::String(key.to_string()), ConstValue::Number(idx.into())];
+            let typename = match representation.clone() {
+                ConstValue::Object(obj) => match obj.get("__typename") {
+                    Some(ConstValue::String(typename)) => typename.clone(),
+                    _ => String::new(),
+                },
+                _ => String::new(),
+            };
+            let resolver = match self.subgraph.entities.get(&typename) {
+                Some(resolver) => resolver.clone(),
+                None => {
+                    self.error(format!("Unknown entity type \"{}\".", typename), path);
+                    entities.push(ConstValue::Null);
+                    continue;
+                },
+            };
+            match resolver(representation) {
+                Ok(ConstValue::Object(mut obj)) => {
+                    obj.entry(Name::new("__typename"))
+                        .or_insert_with(|| ConstValue::String(typename));
+                    entities.push(self.project(&ConstValue::Object(obj), None, &field.selection_set.node));
+                },
+                Ok(value) => entities.push(self.project(&value, Some(&typename), &field.selection_set.node)),
+                Err(message) => {
+                    self.error(message, path);
+                    entities.push(ConstValue::Null);
+                },
+            }
+        }
+        ConstValue::List(entities)
+    }
+
+    fn resolve_root(&mut self, operation: &'a OperationDefinition) -> ConstValue {
+        let root_type = match operation.ty {
+            OperationType::Query => "Query",
+            OperationType::Mutation => "Mutation",
+            OperationType::Subscription => "Subscription",
+        };
+        let mut fields = Vec::new();
+        self.collect_fields(&operation.selection_set.node, Some(root_type), &mut fields);
+
+        let mut data = IndexMap::new();
+        for field in fields {
+            let key = field.alias.as_ref().unwrap_or(&field.name).node.clone();
+            let value = match field.name.node.as_str() {
+                "__typename" => ConstValue::String(root_type.to_string()),
+                "_service" => {
+                    let mut service = IndexMap::new();
+                    service.insert(Name::new("sdl"), ConstValue::String(self.subgraph.sdl.clone()));
+                    self.project(&ConstValue::Object(service), None, &field.selection_set.node)
+                },
+                "_entities" => self.resolve_entities(field, &key),
+                name => match self.subgraph.fields.get(name).cloned() {
+                    Some(resolver) => {
+                        let arguments = self.arguments(field);
+                        let ctx = ResolverContext {
+                            arguments: &arguments,
+                            headers: self.headers,
+                        };
+                        match resolver(&ctx) {
+                            Ok(value) => {
+                                let ty = self.field_type(root_type, name);
+                                self.project(&value, ty, &field.selection_set.node)
+                            },
+                            Err(message) => {
+                                self.error(message, vec![ConstValue::String(key.to_string())]);
+                                ConstValue::Null
+                            },
+                        }
+                    },
+                    None => {
+                        self.error(format!("No resolver for field \"{}\".", name), vec![
+                            ConstValue::String(key.to_string()),
+                        ]);
+                        ConstValue::Null
+                    },
+                },
+            };
+            data.insert(key, value);
+        }
+        ConstValue::Object(data)
+    }
+}
+
+fn error_response(message: impl Into<String>) -> Response {
+    Response {
+        data: ConstValue::Null,
+        errors: vec![ServerError::new(message)],
+        extensions: Default::default(),
+        headers: None,
+    }
+}
+
+fn with_operation<T>(
+    request: &Request,
+    f: impl FnOnce(&OperationDefinition, &HashMap<Name, Positioned<FragmentDefinition>>) -> T,
+) -> Result<T, Response> {
+    let document = parser::parse_query(&request.query).map_err(|err| error_response(err.to_string()))?;
+    let operation = match &document.operations {
+        DocumentOperations::Single(operation) => Some(operation),
+        DocumentOperations::Multiple(operations) => match &request.operation {
+            Some(name) => operations.get(name.as_str()),
+            None => operations.values().next(),
+        },
+    };
+    match operation {
+        Some(operation) => Ok(f(&operation.node, &document.fragments)),
+        None => Err(error_response("Unknown operation.")),
+    }
+}
+
+/// Execute a query or mutation against the resolvers of a subgraph.
+pub(crate) fn execute(subgraph: &Inner, request: &Request, headers: &HeaderMap) -> Response {
+    with_operation(request, |operation, fragments| {
+        let mut ctx = Context {
+            subgraph,
+            fragments,
+            variables: &request.variables,
+            headers,
+            errors: Vec::new(),
+        };
+        let data = ctx.resolve_root(operation);
+        Response {
+            data,
+            errors: ctx.errors,
+            extensions: Default::default(),
+            headers: None,
+        }
+    })
+    .unwrap_or_else(|resp| resp)
+}
+
+/// Execute a subscription against the resolvers of a subgraph, returning
+/// one response per event.
+///
+/// Queries and mutations produce a single response.
+pub(crate) fn subscribe(subgraph: &Inner, request: &Request, headers: &HeaderMap) -> Vec<Response> {
+    with_operation(request, |operation, fragments| {
+        if operation.ty != OperationType::Subscription {
+            return vec![execute(subgraph, request, headers)];
+        }
+
+        let ctx = Context {
+            subgraph,
+            fragments,
+            variables: &request.variables,
+            headers,
+            errors: Vec::new(),
+        };
+        let mut fields = Vec::new();
+        ctx.collect_fields(&operation.selection_set.node, Some("Subscription"), &mut fields);
+        let field = match fields.first() {
+            Some(field) => *field,
+            None => return Vec::new(),
+        };
+        let key = field.alias.as_ref().unwrap_or(&field.name).node.clone();
+        let resolver = match subgraph.subscriptions.get(field.name.node.as_str()) {
+            Some(resolver) => resolver,
+            None => {
+                return vec![error_response(format!(
+                    "No resolver for field \"{}\".",
+                    field.name.node
+                ))]
+            },
+        };
+
+        let arguments = ctx.arguments(field);
+        let events = resolver(&ResolverContext {
+            arguments: &arguments,
+            headers,
+        });
+        events
+            .iter()
+            .map(|event| {
+                let mut data = IndexMap::new();
+                let ty = ctx.field_type("Subscription", &field.name.node);
+                data.insert(key.clone(), ctx.project(event, ty, &field.selection_set.node));
+                Response {
+                    data: ConstValue::Object(data),
+                    errors: Vec::new(),
+                    extensions: Default::default(),
+                    headers: None,
+                }
+            })
+            .collect()
+    })
+    .unwrap_or_else(|resp| vec![resp])
+}
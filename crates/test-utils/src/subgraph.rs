@@ -0,0 +1,409 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::{SinkExt, StreamExt};
+use graphgate_planner::Request;
+use indexmap::IndexMap;
+use parser::types::{BaseType, TypeKind, TypeSystemDefinition};
+use tokio::sync::oneshot;
+use value::{ConstValue, Name, Variables};
+use warp::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    hyper::body::Bytes,
+    path::FullPath,
+    ws::{Message, WebSocket, Ws},
+    Filter,
+};
+
+use crate::resolve;
+
+/// Arguments and request headers available to a resolver.
+pub struct ResolverContext<'a> {
+    pub arguments: &'a IndexMap<Name, ConstValue>,
+    pub headers: &'a HeaderMap,
+}
+
+/// Resolves a root `Query` or `Mutation` field to a value.
+///
+/// The returned value may contain more fields than are selected, the
+/// subgraph only returns the selected ones.
+pub type FieldResolver = Arc<dyn Fn(&ResolverContext<'_>) -> Result<ConstValue, String> + Send + Sync>;
+
+/// Resolves an entity representation passed to `_entities`.
+pub type EntityResolver = Arc<dyn Fn(&ConstValue) -> Result<ConstValue, String> + Send + Sync>;
+
+/// Resolves a root `Subscription` field to the list of events it emits.
+pub type SubscriptionResolver = Arc<dyn Fn(&ResolverContext<'_>) -> Vec<ConstValue> + Send + Sync>;
+
+/// A request received by a fake subgraph.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub headers: HeaderMap,
+    pub query: String,
+    pub variables: Variables,
+    /// The raw body of the request.
+    pub body: Bytes,
+}
+
+pub(crate) struct Inner {
+    pub(crate) sdl: String,
+    /// The named type of every field of every object type in the SDL.
+    pub(crate) object_types: HashMap<String, HashMap<String, String>>,
+    /// The interfaces implemented by every object type in the SDL.
+    pub(crate) interfaces: HashMap<String, Vec<String>>,
+    pub(crate) fields: HashMap<String, FieldResolver>,
+    pub(crate) entities: HashMap<String, EntityResolver>,
+    pub(crate) subscriptions: HashMap<String, SubscriptionResolver>,
+    status: Mutex<StatusCode>,
+    response_headers: HeaderMap,
+    raw_response: Option<serde_json::Value>,
+    delay: Duration,
+    requests: Mutex<Vec<RecordedRequest>>,
+    connection_params: Mutex<Vec<Option<serde_json::Value>>>,
+    websocket_connections: Mutex<Vec<(String, String)>>,
+}
+
+impl Inner {
+    fn record(&self, headers: &HeaderMap, request: &Request, body: &Bytes) {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            headers: headers.clone(),
+            query: request.query.clone(),
+            variables: request.variables.clone(),
+            body: body.clone(),
+        });
+    }
+}
+
+/// Builder for an in-process fake subgraph.
+pub struct SubgraphBuilder {
+    name: String,
+    sdl: String,
+    fields: HashMap<String, FieldResolver>,
+    entities: HashMap<String, EntityResolver>,
+    subscriptions: HashMap<String, SubscriptionResolver>,
+    status: StatusCode,
+    response_headers: HeaderMap,
+    raw_response: Option<serde_json::Value>,
+    delay: Duration,
+}
+
+impl SubgraphBuilder {
+    pub fn new(name: impl Into<String>, sdl: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            sdl: sdl.into(),
+            fields: Default::default(),
+            entities: Default::default(),
+            subscriptions: Default::default(),
+            status: StatusCode::OK,
+            response_headers: Default::default(),
+            raw_response: None,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Register a resolver for a root `Query` or `Mutation` field.
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        resolver: impl Fn(&ResolverContext<'_>) -> Result<ConstValue, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.fields.insert(name.into(), Arc::new(resolver));
+        self
+    }
+
+    /// Register a reference resolver for an entity type.
+    pub fn entity(
+        mut self,
+        typename: impl Into<String>,
+        resolver: impl Fn(&ConstValue) -> Result<ConstValue, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.entities.insert(typename.into(), Arc::new(resolver));
+        self
+    }
+
+    /// Register a resolver for a root `Subscription` field.
+    pub fn subscription(
+        mut self,
+        name: impl Into<String>,
+        resolver: impl Fn(&ResolverContext<'_>) -> Vec<ConstValue> + Send + Sync + 'static,
+    ) -> Self {
+        self.subscriptions.insert(name.into(), Arc::new(resolver));
+        self
+    }
+
+    /// Respond to every HTTP request with the specified status code.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a header to every HTTP response.
+    pub fn response_header(mut self, name: &'static str, value: &'static str) -> Self {
+        self.response_headers
+            .append(HeaderName::from_static(name), HeaderValue::from_static(value));
+        self
+    }
+
+    /// Respond to every query but the SDL query with the specified body,
+    /// to mimic services that do not follow the GraphQL specification.
+    pub fn raw_response(mut self, body: serde_json::Value) -> Self {
+        self.raw_response = Some(body);
+        self
+    }
+
+    /// Wait for `delay` before answering every query but the SDL query.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Start serving the subgraph on a random local port.
+    pub async fn spawn(self) -> Subgraph {
+        let inner = Arc::new(Inner {
+            object_types: object_types(&self.sdl),
+            interfaces: interfaces(&self.sdl),
+            sdl: self.sdl,
+            fields: self.fields,
+            entities: self.entities,
+            subscriptions: self.subscriptions,
+            status: Mutex::new(self.status),
+            response_headers: self.response_headers,
+            raw_response: self.raw_response,
+            delay: self.delay,
+            requests: Default::default(),
+            connection_params: Default::default(),
+            websocket_connections: Default::default(),
+        });
+
+        let websocket = warp::ws()
+            .and(warp::path::full())
+            .and(warp::header::optional::<String>("sec-websocket-protocol"))
+            .and(warp::header::headers_cloned())
+            .map({
+                let inner = inner.clone();
+                move |ws: Ws, path: FullPath, protocols: Option<String>, headers: HeaderMap| {
+                    let inner = inner.clone();
+                    let protocol = match protocols {
+                        Some(protocols) if protocols.contains("graphql-transport-ws") => "graphql-transport-ws",
+                        _ => "graphql-ws",
+                    };
+                    inner
+                        .websocket_connections
+                        .lock()
+                        .unwrap()
+                        .push((path.as_str().to_string(), protocol.to_string()));
+                    let reply = ws.on_upgrade(move |socket| serve_websocket(inner, socket, protocol, headers));
+                    warp::reply::with_header(reply, "Sec-WebSocket-Protocol", protocol)
+                }
+            });
+
+        let http = warp::post()
+            .and(warp::header::headers_cloned())
+            .and(warp::body::bytes())
+            .then({
+                let inner = inner.clone();
+                move |headers: HeaderMap, body: Bytes| {
+                    let inner = inner.clone();
+                    async move {
+                        let request = match serde_json::from_slice::<Request>(&body) {
+                            Ok(request) => request,
+                            Err(err) => {
+                                return warp::http::Response::builder()
+                                    .status(StatusCode::BAD_REQUEST)
+                                    .body(err.to_string())
+                                    .unwrap()
+                            },
+                        };
+                        inner.record(&headers, &request, &body);
+                        if !request.query.contains("_service") {
+                            tokio::time::sleep(inner.delay).await;
+                        }
+                        let body = match &inner.raw_response {
+                            Some(body) if !request.query.contains("_service") => body.to_string(),
+                            _ => serde_json::to_string(&resolve::execute(&inner, &request, &headers)).unwrap(),
+                        };
+                        let mut reply = warp::http::Response::builder().status(*inner.status.lock().unwrap());
+                        if let Some(reply_headers) = reply.headers_mut() {
+                            reply_headers.extend(inner.response_headers.clone());
+                        }
+                        reply.header("content-type", "application/json").body(body).unwrap()
+                    }
+                }
+            });
+
+        let (tx_shutdown, rx_shutdown) = oneshot::channel::<()>();
+        let (addr, server) =
+            warp::serve(websocket.or(http)).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async move {
+                rx_shutdown.await.ok();
+            });
+        tokio::spawn(server);
+
+        Subgraph {
+            name: self.name,
+            addr,
+            inner,
+            shutdown: Some(tx_shutdown),
+        }
+    }
+}
+
+/// A running fake subgraph.
+///
+/// The server is stopped when this value is dropped.
+pub struct Subgraph {
+    name: String,
+    addr: SocketAddr,
+    inner: Arc<Inner>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl Subgraph {
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Requests received over HTTP, excluding SDL fetches.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.inner
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| !request.query.contains("_service"))
+            .cloned()
+            .collect()
+    }
+
+    /// Respond to subsequent HTTP requests with the specified status code.
+    pub fn set_status(&self, status: StatusCode) {
+        *self.inner.status.lock().unwrap() = status;
+    }
+
+    /// The `connection_init` payloads received over WebSocket.
+    pub fn connection_params(&self) -> Vec<Option<serde_json::Value>> {
+        self.inner.connection_params.lock().unwrap().clone()
+    }
+
+    /// The path and the negotiated protocol of the WebSocket connections.
+    pub fn websocket_connections(&self) -> Vec<(String, String)> {
+        self.inner.websocket_connections.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Subgraph {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+fn object_types(sdl: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut object_types: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let document = match parser::parse_schema(sdl) {
+        Ok(document) => document,
+        Err(_) => return object_types,
+    };
+
+    for definition in document.definitions {
+        if let TypeSystemDefinition::Type(type_definition) = definition {
+            if let TypeKind::Object(object) = &type_definition.node.kind {
+                let fields = object_types
+                    .entry(type_definition.node.name.node.to_string())
+                    .or_default();
+                for field in &object.fields {
+                    let mut ty = &field.node.ty.node;
+                    while let BaseType::List(element) = &ty.base {
+                        ty = element;
+                    }
+                    if let BaseType::Named(name) = &ty.base {
+                        fields.insert(field.node.name.node.to_string(), name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    object_types
+}
+
+fn interfaces(sdl: &str) -> HashMap<String, Vec<String>> {
+    let document = match parser::parse_schema(sdl) {
+        Ok(document) => document,
+        Err(_) => return HashMap::new(),
+    };
+    document
+        .definitions
+        .into_iter()
+        .filter_map(|definition| match definition {
+            TypeSystemDefinition::Type(type_definition) => match type_definition.node.kind {
+                TypeKind::Object(object) => Some((
+                    type_definition.node.name.node.to_string(),
+                    object
+                        .implements
+                        .into_iter()
+                        .map(|name| name.node.to_string())
+                        .collect(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+async fn serve_websocket(inner: Arc<Inner>, socket: WebSocket, protocol: &'static str, headers: HeaderMap) {
+    let (mut sink, mut stream) = socket.split();
+    let next_type = match protocol {
+        "graphql-transport-ws" => "next",
+        _ => "data",
+    };
+
+    while let Some(Ok(message)) = stream.next().await {
+        if message.is_close() {
+            return;
+        }
+        let message = match serde_json::from_slice::<serde_json::Value>(message.as_bytes()) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+
+        match message["type"].as_str() {
+            Some("connection_init") => {
+                inner
+                    .connection_params
+                    .lock()
+                    .unwrap()
+                    .push(message.get("payload").cloned());
+                let ack = serde_json::json!({ "type": "connection_ack" });
+                sink.send(Message::text(ack.to_string())).await.ok();
+            },
+            Some("start") | Some("subscribe") => {
+                let id = message["id"].clone();
+                let request = match serde_json::from_value::<Request>(message["payload"].clone()) {
+                    Ok(request) => request,
+                    Err(_) => continue,
+                };
+                tokio::time::sleep(inner.delay).await;
+                for resp in resolve::subscribe(&inner, &request, &headers) {
+                    let data = serde_json::json!({ "type": next_type, "id": id, "payload": resp });
+                    sink.send(Message::text(data.to_string())).await.ok();
+                }
+                let complete = serde_json::json!({ "type": "complete", "id": id });
+                sink.send(Message::text(complete.to_string())).await.ok();
+            },
+            Some("connection_terminate") => return,
+            _ => {},
+        }
+    }
+}
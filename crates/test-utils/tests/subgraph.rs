@@ -0,0 +1,61 @@
+use graphgate_test_utils::SubgraphBuilder;
+use serde_json::{json, Value};
+use value::ConstValue;
+
+async fn post(addr: std::net::SocketAddr, body: Value) -> Value {
+    reqwest::Client::new()
+        .post(format!("http://{}", addr))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_subgraph() {
+    let sdl = "type Query { me: User } type User @key(fields: \"id\") { id: ID! username: String! }";
+    let accounts = SubgraphBuilder::new("accounts", sdl)
+        .field("me", |_| {
+            Ok(ConstValue::from_json(json!({ "id": "1", "username": "alice", "password": "secret" })).unwrap())
+        })
+        .entity("User", |representation| {
+            let mut user = representation.clone();
+            if let ConstValue::Object(obj) = &mut user {
+                obj.insert(value::Name::new("username"), ConstValue::String("bob".to_string()));
+            }
+            Ok(user)
+        })
+        .spawn()
+        .await;
+
+    assert_eq!(
+        post(accounts.addr(), json!({ "query": "{ _service { sdl } }" })).await,
+        json!({ "data": { "_service": { "sdl": sdl } } })
+    );
+
+    assert_eq!(
+        post(
+            accounts.addr(),
+            json!({ "query": "{ me { name: username ... on User { id } } }" })
+        )
+        .await,
+        json!({ "data": { "me": { "name": "alice", "id": "1" } } })
+    );
+
+    assert_eq!(
+        post(
+            accounts.addr(),
+            json!({
+                "query": "query($representations: [_Any!]!) { _entities(representations: $representations) { ... on User { username } } }",
+                "variables": { "representations": [{ "__typename": "User", "id": "2" }] }
+            })
+        )
+        .await,
+        json!({ "data": { "_entities": [{ "username": "bob" }] } })
+    );
+
+    assert_eq!(accounts.requests().len(), 2);
+}
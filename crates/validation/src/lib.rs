@@ -10,6 +10,9 @@ mod suggestion;
 mod utils;
 mod visitor;
 
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
 pub use error::RuleError;
 use graphgate_schema::ComposedSchema;
 use parser::types::ExecutableDocument;
@@ -53,3 +56,20 @@ pub fn check_rules(
     visit(&mut visitor, &mut ctx, document);
     ctx.errors
 }
+
+/// Check the deprecated fields selected by a document against their sunset
+/// dates, returning the errors of the fields past their grace period and the
+/// warnings of the fields within it.
+pub fn check_deprecation_sunsets(
+    composed_schema: &ComposedSchema,
+    document: &ExecutableDocument,
+    variables: &Variables,
+    sunsets: &HashMap<String, NaiveDate>,
+    grace_period: Duration,
+    today: NaiveDate,
+) -> (Vec<RuleError>, Vec<RuleError>) {
+    let mut ctx = VisitorContext::new(composed_schema, document, variables);
+    let mut rule = rules::DeprecationSunset::new(sunsets, grace_period, today);
+    visit(&mut rule, &mut ctx, document);
+    (ctx.errors, rule.warnings)
+}
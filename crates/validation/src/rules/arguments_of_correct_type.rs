@@ -959,4 +959,60 @@ mod tests {
         "#,
         );
     }
+
+    #[test]
+    fn one_of_input_with_one_field() {
+        expect_passes_rule!(
+            factory,
+            r#"
+            {
+              complicatedArgs {
+                oneOfArgField(oneOfArg: { name: "Fido" })
+              }
+            }
+        "#,
+        );
+    }
+
+    #[test]
+    fn one_of_input_with_two_fields() {
+        expect_fails_rule!(
+            factory,
+            r#"
+            {
+              complicatedArgs {
+                oneOfArgField(oneOfArg: { id: "1", name: "Fido" })
+              }
+            }
+        "#,
+        );
+    }
+
+    #[test]
+    fn one_of_input_with_no_fields() {
+        expect_fails_rule!(
+            factory,
+            r#"
+            {
+              complicatedArgs {
+                oneOfArgField(oneOfArg: {})
+              }
+            }
+        "#,
+        );
+    }
+
+    #[test]
+    fn one_of_input_with_null_field() {
+        expect_fails_rule!(
+            factory,
+            r#"
+            {
+              complicatedArgs {
+                oneOfArgField(oneOfArg: { id: null })
+              }
+            }
+        "#,
+        );
+    }
 }
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use parser::{types::Field, Positioned};
+
+use crate::{RuleError, Visitor, VisitorContext};
+
+/// Rejects deprecated fields past their sunset date, keyed by coordinate
+/// such as `User.name`, and warns about them during the grace period that
+/// follows the date.
+pub struct DeprecationSunset<'a> {
+    sunsets: &'a HashMap<String, NaiveDate>,
+    grace_period: Duration,
+    today: NaiveDate,
+    pub warnings: Vec<RuleError>,
+}
+
+impl<'a> DeprecationSunset<'a> {
+    pub fn new(sunsets: &'a HashMap<String, NaiveDate>, grace_period: Duration, today: NaiveDate) -> Self {
+        Self {
+            sunsets,
+            grace_period,
+            today,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for DeprecationSunset<'_> {
+    fn enter_field(&mut self, ctx: &mut VisitorContext<'a>, field: &'a Positioned<Field>) {
+        let parent_type = match ctx.parent_type() {
+            Some(parent_type) => parent_type,
+            None => return,
+        };
+        let schema_field = match parent_type.field_by_name(&field.node.name.node) {
+            Some(schema_field) if schema_field.deprecation.is_deprecated() => schema_field,
+            _ => return,
+        };
+        let coordinate = format!("{}.{}", parent_type.name, schema_field.name);
+        let sunset = match self.sunsets.get(&coordinate) {
+            Some(sunset) if *sunset <= self.today => *sunset,
+            _ => return,
+        };
+
+        let replacement = schema_field
+            .deprecation
+            .reason()
+            .map(|reason| format!(" {}", reason))
+            .unwrap_or_default();
+        let rejected_from = sunset + self.grace_period;
+        if self.today < rejected_from {
+            self.warnings.push(RuleError {
+                message: format!(
+                    "Field \"{}\" is deprecated and was sunset on {}, operations selecting it are rejected from {}.{}",
+                    coordinate, sunset, rejected_from, replacement
+                ),
+                locations: vec![field.pos],
+            });
+        } else {
+            ctx.report_error(
+                vec![field.pos],
+                format!(
+                    "Field \"{}\" is deprecated and was sunset on {}.{}",
+                    coordinate, sunset, replacement
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graphgate_schema::ComposedSchema;
+    use once_cell::sync::Lazy;
+
+    use super::*;
+
+    static SCHEMA: Lazy<ComposedSchema> =
+        Lazy::new(|| ComposedSchema::parse(include_str!("deprecation_sunset.graphql")).unwrap());
+
+    static SUNSETS: Lazy<HashMap<String, NaiveDate>> = Lazy::new(|| {
+        HashMap::from([
+            (
+                "Dog.barkVolume".to_string(),
+                NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            ),
+            ("Dog.nickname".to_string(), NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        ])
+    });
+
+    fn factory_on(today: NaiveDate) -> impl Fn() -> DeprecationSunset<'static> {
+        move || DeprecationSunset::new(&SUNSETS, Duration::days(30), today)
+    }
+
+    #[test]
+    fn before_sunset() {
+        expect_passes_rule!(
+            schema: &SCHEMA,
+            factory_on(NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()),
+            r#"
+          { dog { barkVolume } }
+        "#,
+        );
+    }
+
+    #[test]
+    fn during_grace_period() {
+        expect_passes_rule!(
+            schema: &SCHEMA,
+            factory_on(NaiveDate::from_ymd_opt(2024, 1, 30).unwrap()),
+            r#"
+          { dog { barkVolume } }
+        "#,
+        );
+    }
+
+    #[test]
+    fn after_grace_period() {
+        expect_fails_rule!(
+            schema: &SCHEMA,
+            factory_on(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()),
+            r#"
+          { dog { ...volume } }
+          fragment volume on Dog { barkVolume }
+        "#,
+        );
+    }
+
+    #[test]
+    fn fields_not_deprecated() {
+        expect_passes_rule!(
+            schema: &SCHEMA,
+            factory_on(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()),
+            r#"
+          { dog { nickname } }
+        "#,
+        );
+    }
+}
@@ -13,10 +13,13 @@ use value::{Name, Value};
 
 use crate::{utils::Scope, Visitor, VisitorContext};
 
+/// The name of a variable, where it is used, and the type expected there.
+type VariableUsage<'a> = (&'a str, Pos, Cow<'a, Type>);
+
 #[derive(Default)]
 pub struct VariableInAllowedPosition<'a> {
     spreads: HashMap<Scope<'a>, HashSet<&'a str>>,
-    variable_usages: HashMap<Scope<'a>, Vec<(&'a str, Pos, &'a Type)>>,
+    variable_usages: HashMap<Scope<'a>, Vec<VariableUsage<'a>>>,
     variable_defs: HashMap<Scope<'a>, Vec<&'a Positioned<VariableDefinition>>>,
     current_scope: Option<Scope<'a>>,
 }
@@ -117,20 +120,41 @@ impl<'a> Visitor<'a> for VariableInAllowedPosition<'a> {
 
     fn enter_input_value(
         &mut self,
-        _ctx: &mut VisitorContext<'a>,
+        ctx: &mut VisitorContext<'a>,
         pos: Pos,
         expected_type: &Option<&'a Type>,
         value: &'a Value,
     ) {
-        if let Value::Variable(name) = value {
-            if let Some(expected_type) = expected_type {
-                if let Some(scope) = &self.current_scope {
-                    self.variable_usages
-                        .entry(*scope)
-                        .or_default()
-                        .push((name, pos, *expected_type));
+        let (scope, expected_type) = match (&self.current_scope, expected_type) {
+            (Some(scope), Some(expected_type)) => (*scope, *expected_type),
+            _ => return,
+        };
+        match value {
+            Value::Variable(name) => {
+                self.variable_usages
+                    .entry(scope)
+                    .or_default()
+                    .push((name, pos, Cow::Borrowed(expected_type)));
+            },
+            // The variables of the fields of `@oneOf` input objects must be
+            // non-null.
+            Value::Object(fields) => {
+                let ty = match ctx.schema.concrete_type_by_name(expected_type) {
+                    Some(ty) if ty.one_of => ty,
+                    _ => return,
+                };
+                for (name, value) in fields {
+                    if let (Value::Variable(var_name), Some(field)) = (value, ty.input_fields.get(name)) {
+                        let mut field_type = field.ty.clone();
+                        field_type.nullable = false;
+                        self.variable_usages
+                            .entry(scope)
+                            .or_default()
+                            .push((var_name, pos, Cow::Owned(field_type)));
+                    }
                 }
-            }
+            },
+            _ => {},
         }
     }
 }
@@ -457,4 +481,32 @@ mod tests {
         "#,
         );
     }
+
+    #[test]
+    fn non_null_variable_into_one_of_field() {
+        expect_passes_rule!(
+            factory,
+            r#"
+          query Query($id: ID!) {
+            complicatedArgs {
+              oneOfArgField(oneOfArg: { id: $id })
+            }
+          }
+        "#,
+        );
+    }
+
+    #[test]
+    fn nullable_variable_into_one_of_field() {
+        expect_fails_rule!(
+            factory,
+            r#"
+          query Query($id: ID) {
+            complicatedArgs {
+              oneOfArgField(oneOfArg: { id: $id })
+            }
+          }
+        "#,
+        );
+    }
 }
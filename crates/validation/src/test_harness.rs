@@ -8,10 +8,11 @@ use crate::{
     RuleError,
 };
 
-static SCHEMA: Lazy<ComposedSchema> =
+pub static SCHEMA: Lazy<ComposedSchema> =
     Lazy::new(|| ComposedSchema::parse(include_str!("test_harness.graphql")).unwrap());
 
 pub fn validate<'a, V, F>(
+    schema: &'a ComposedSchema,
     doc: &'a ExecutableDocument,
     variables: &'a Variables,
     factory: F,
@@ -20,7 +21,7 @@ where
     V: Visitor<'a> + 'a,
     F: Fn() -> V,
 {
-    let mut ctx = VisitorContext::new(&SCHEMA, doc, variables);
+    let mut ctx = VisitorContext::new(schema, doc, variables);
     let mut visitor = factory();
     visit(&mut visitor, &mut ctx, doc);
     if ctx.errors.is_empty() {
@@ -30,12 +31,16 @@ where
     }
 }
 
-pub fn expect_passes_rule_<'a, V, F>(doc: &'a ExecutableDocument, variables: &'a Variables, factory: F)
-where
+pub fn expect_passes_rule_<'a, V, F>(
+    schema: &'a ComposedSchema,
+    doc: &'a ExecutableDocument,
+    variables: &'a Variables,
+    factory: F,
+) where
     V: Visitor<'a> + 'a,
     F: Fn() -> V,
 {
-    if let Err(errors) = validate(doc, variables, factory) {
+    if let Err(errors) = validate(schema, doc, variables, factory) {
         for err in errors {
             if let Some(position) = err.locations.first() {
                 print!("[{}:{}] ", position.line, position.column);
@@ -47,27 +52,37 @@ where
 }
 
 macro_rules! expect_passes_rule {
-    ($factory:expr, $query_source:literal $(,)?) => {
+    (schema: $schema:expr, $factory:expr, $query_source:literal $(,)?) => {
         let variables = value::Variables::default();
         let doc = parser::parse_query($query_source).expect("Parse error");
-        crate::test_harness::expect_passes_rule_(&doc, &variables, $factory);
+        crate::test_harness::expect_passes_rule_($schema, &doc, &variables, $factory);
+    };
+    ($factory:expr, $query_source:literal $(,)?) => {
+        expect_passes_rule!(schema: &crate::test_harness::SCHEMA, $factory, $query_source)
     };
 }
 
-pub fn expect_fails_rule_<'a, V, F>(doc: &'a ExecutableDocument, variables: &'a Variables, factory: F)
-where
+pub fn expect_fails_rule_<'a, V, F>(
+    schema: &'a ComposedSchema,
+    doc: &'a ExecutableDocument,
+    variables: &'a Variables,
+    factory: F,
+) where
     V: Visitor<'a> + 'a,
     F: Fn() -> V,
 {
-    if validate(doc, variables, factory).is_ok() {
+    if validate(schema, doc, variables, factory).is_ok() {
         panic!("Expected rule to fail, but no errors were found");
     }
 }
 
 macro_rules! expect_fails_rule {
-    ($factory:expr, $query_source:literal $(,)?) => {
+    (schema: $schema:expr, $factory:expr, $query_source:literal $(,)?) => {
         let variables = value::Variables::default();
         let doc = parser::parse_query($query_source).expect("Parse error");
-        crate::test_harness::expect_fails_rule_(&doc, &variables, $factory);
+        crate::test_harness::expect_fails_rule_($schema, &doc, &variables, $factory);
+    };
+    ($factory:expr, $query_source:literal $(,)?) => {
+        expect_fails_rule!(schema: &crate::test_harness::SCHEMA, $factory, $query_source)
     };
 }
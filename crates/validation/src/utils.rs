@@ -133,6 +133,30 @@ pub fn is_valid_input_value(
                         },
                         TypeKind::InputObject => {
                             if let ConstValue::Object(values) = value {
+                                if ty.one_of {
+                                    match values.iter().next() {
+                                        Some((name, ConstValue::Null)) if values.len() == 1 => {
+                                            return Some(valid_error(
+                                                &path_node,
+                                                format!(
+                                                    "field \"{}\" of @oneOf type \"{}\" must not be null",
+                                                    name, ty.name,
+                                                ),
+                                            ));
+                                        },
+                                        Some(_) if values.len() == 1 => {},
+                                        _ => {
+                                            return Some(valid_error(
+                                                &path_node,
+                                                format!(
+                                                    "exactly one field of @oneOf type \"{}\" must be provided",
+                                                    ty.name
+                                                ),
+                                            ));
+                                        },
+                                    }
+                                }
+
                                 let mut input_names = values.keys().collect::<HashSet<_>>();
 
                                 for field in ty.input_fields.values() {
@@ -0,0 +1,250 @@
+use graphgate_handler::{rename_sdl_enum_values, CompositionConfig, ServiceRouteTable};
+use graphgate_schema::{CombineError, ComposedSchema, CompositionHint, HintNode};
+use serde::Serialize;
+
+/// The rover error code of composition failures.
+const ROVER_BUILD_ERRORS: &str = "E029";
+
+/// The output of `rover supergraph compose --output json`.
+#[derive(Serialize)]
+struct RoverOutput {
+    json_version: &'static str,
+    data: RoverData,
+    error: Option<RoverError>,
+}
+
+#[derive(Serialize)]
+struct RoverData {
+    success: bool,
+    core_schema: Option<String>,
+    hints: Vec<RoverBuildMessage>,
+}
+
+#[derive(Serialize)]
+struct RoverError {
+    message: String,
+    code: Option<&'static str>,
+    details: RoverErrorDetails,
+}
+
+#[derive(Serialize)]
+struct RoverErrorDetails {
+    build_errors: Vec<RoverBuildMessage>,
+}
+
+/// A composition hint or error.
+#[derive(Serialize)]
+struct RoverBuildMessage {
+    message: String,
+    code: Option<&'static str>,
+    /// Not part of the rover format, which leaves hint levels out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<&'static str>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    ty: Option<&'static str>,
+    nodes: Vec<RoverNode>,
+    #[serde(rename = "omittedNodesCount")]
+    omitted_nodes_count: usize,
+}
+
+#[derive(Serialize)]
+struct RoverNode {
+    subgraph: String,
+    start: RoverPoint,
+}
+
+#[derive(Serialize)]
+struct RoverPoint {
+    line: usize,
+    column: usize,
+}
+
+impl From<CompositionHint> for RoverBuildMessage {
+    fn from(hint: CompositionHint) -> Self {
+        Self {
+            message: hint.message,
+            code: Some(hint.code),
+            severity: Some(hint.severity.as_str()),
+            ty: None,
+            nodes: hint.nodes.into_iter().map(RoverNode::from).collect(),
+            omitted_nodes_count: 0,
+        }
+    }
+}
+
+impl From<HintNode> for RoverNode {
+    fn from(node: HintNode) -> Self {
+        Self {
+            subgraph: node.subgraph,
+            start: RoverPoint {
+                line: node.pos.line,
+                column: node.pos.column,
+            },
+        }
+    }
+}
+
+fn build_error(message: String, code: &'static str) -> RoverBuildMessage {
+    RoverBuildMessage {
+        message,
+        code: Some(code),
+        severity: None,
+        ty: Some("composition"),
+        nodes: Vec::new(),
+        omitted_nodes_count: 0,
+    }
+}
+
+fn combine_error_code(err: &CombineError) -> &'static str {
+    match err {
+        CombineError::SchemaIsNotAllowed => "SCHEMA_DEFINITION_NOT_ALLOWED",
+        CombineError::DefinitionConflicted { .. } => "TYPE_DEFINITION_CONFLICT",
+        CombineError::FieldConflicted { .. } => "FIELD_DEFINITION_CONFLICT",
+        CombineError::MissingInterface { .. } => "INTERFACE_OBJECT_USAGE_ERROR",
+        CombineError::InvalidFieldSet { directive, .. } | CombineError::UnknownFieldSetField { directive, .. } => {
+            match directive.as_str() {
+                "provides" => "PROVIDES_INVALID_FIELDS",
+                _ => "REQUIRES_INVALID_FIELDS",
+            }
+        },
+    }
+}
+
+/// Compose the schema of the services once and print the composition hints
+/// and errors in the JSON output format of rover, returning whether the
+/// schema composed.
+pub async fn check(route_table: &ServiceRouteTable, composition_config: &CompositionConfig) -> bool {
+    let output = match route_table.fetch_sdls().await {
+        Ok(sdls) => compose(route_table, composition_config, sdls),
+        Err(err) => RoverOutput {
+            json_version: "1",
+            data: RoverData {
+                success: false,
+                core_schema: None,
+                hints: Vec::new(),
+            },
+            error: Some(RoverError {
+                message: format!("{:#}", err),
+                code: None,
+                details: RoverErrorDetails {
+                    build_errors: Vec::new(),
+                },
+            }),
+        },
+    };
+    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    output.data.success
+}
+
+fn compose(
+    route_table: &ServiceRouteTable,
+    composition_config: &CompositionConfig,
+    sdls: Vec<(String, String)>,
+) -> RoverOutput {
+    let mut build_errors = Vec::new();
+    let mut documents = Vec::new();
+    for (service, sdl) in sdls {
+        match parser::parse_schema(&sdl) {
+            Ok(mut document) => {
+                if let Some(route) = route_table.get(&service) {
+                    rename_sdl_enum_values(&mut document, &route.enum_values);
+                }
+                documents.push((service, document))
+            },
+            Err(err) => build_errors.push(build_error(
+                format!("Invalid SDL from '{}': {}", service, err),
+                "INVALID_GRAPHQL",
+            )),
+        }
+    }
+
+    let hints = composition_config
+        .prepare(&mut documents)
+        .into_iter()
+        .map(RoverBuildMessage::from)
+        .collect();
+    if build_errors.is_empty() {
+        if let Err(err) = ComposedSchema::combine(documents) {
+            build_errors.push(build_error(err.to_string(), combine_error_code(&err)));
+        }
+    }
+
+    let error = (!build_errors.is_empty()).then(|| RoverError {
+        message: format!(
+            "Encountered {} build error{} while trying to build a supergraph.",
+            build_errors.len(),
+            if build_errors.len() == 1 { "" } else { "s" }
+        ),
+        code: Some(ROVER_BUILD_ERRORS),
+        details: RoverErrorDetails { build_errors },
+    });
+    RoverOutput {
+        json_version: "1",
+        data: RoverData {
+            success: error.is_none(),
+            core_schema: None,
+            hints,
+        },
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn rover_output() {
+        let output = compose(&ServiceRouteTable::default(), &Default::default(), vec![
+            (
+                "accounts".to_string(),
+                r#"type Query { me: User } type User @key(fields: "id") { id: ID! }"#.to_string(),
+            ),
+            (
+                "reviews".to_string(),
+                r#"extend type User @key(fields: "id") { id: ID! @external name: String @external }"#.to_string(),
+            ),
+        ]);
+        let output = serde_json::to_value(&output).unwrap();
+        assert_eq!(output["json_version"], "1");
+        assert_eq!(output["data"]["success"], true);
+        assert_eq!(output["error"], json!(null));
+        assert_eq!(output["data"]["hints"][0]["code"], "EXTERNAL_UNUSED");
+        assert_eq!(output["data"]["hints"][0]["severity"], "WARN");
+        assert_eq!(output["data"]["hints"][0]["nodes"][0]["subgraph"], "reviews");
+        assert_eq!(output["data"]["hints"][0]["omittedNodesCount"], 0);
+
+        let output = compose(&ServiceRouteTable::default(), &Default::default(), vec![
+            ("accounts".to_string(), "type User { id: ID! }".to_string()),
+            ("reviews".to_string(), "type User { id: ID! }".to_string()),
+        ]);
+        let output = serde_json::to_value(&output).unwrap();
+        assert_eq!(output["data"]["success"], false);
+        assert_eq!(output["error"]["code"], "E029");
+        assert_eq!(
+            output["error"]["details"]["build_errors"],
+            json!([{
+                "message": "Field 'User.id' definition conflicted.",
+                "code": "FIELD_DEFINITION_CONFLICT",
+                "type": "composition",
+                "nodes": [],
+                "omittedNodesCount": 0,
+            }])
+        );
+
+        let composition_config = CompositionConfig { infer_shareable: true };
+        let output = compose(&ServiceRouteTable::default(), &composition_config, vec![
+            (
+                "accounts".to_string(),
+                "type Query { me: User } type User { id: ID! }".to_string(),
+            ),
+            ("reviews".to_string(), "type User { id: ID! }".to_string()),
+        ]);
+        let output = serde_json::to_value(&output).unwrap();
+        assert_eq!(output["data"]["success"], true);
+        assert_eq!(output["data"]["hints"][0]["code"], "SHAREABLE_INFERRED");
+        assert_eq!(output["data"]["hints"][0]["severity"], "INFO");
+    }
+}
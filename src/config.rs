@@ -1,14 +1,60 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
-use clap::{Args, Parser};
-use graphgate_handler::{auth::AuthConfig, ServiceRoute, ServiceRouteTable};
+use clap::{Args, Parser, ValueEnum};
+use graphgate_handler::{
+    auth::AuthConfig,
+    AuditConfig,
+    BucketingConfig,
+    CallBudgetConfig,
+    ChaosConfig,
+    CircuitBreakerConfig,
+    CompositionConfig,
+    ConnectionConfig,
+    ConnectionParam,
+    ContextRule,
+    CorsConfig,
+    CostConfig,
+    DeferConfig,
+    DeprecationConfig,
+    DocsConfig,
+    EntityCacheConfig,
+    EntityCheckConfig,
+    EnumValues,
+    HeaderPolicy,
+    IntrospectionConfig,
+    OAuth2Config,
+    OperationLabelConfig,
+    OperationLimitsConfig,
+    PaginationConfig,
+    ParallelismConfig,
+    PersistedQueryConfig,
+    PollingConfig,
+    Protocols,
+    RateLimitConfig,
+    RedactionRule,
+    RequestSigningConfig,
+    ResponseLimitConfig,
+    RouteSource,
+    SafelistConfig,
+    ServiceRoute,
+    ServiceRouteTable,
+    SubgraphRequestConfig,
+    UpstreamConfig,
+    VerificationConfig,
+};
+use graphgate_planner::PlanFormat;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
 use serde::Deserialize;
 use tracing::instrument;
 
-#[derive(Debug, Default, Deserialize, Parser)]
+#[derive(Debug, Default, Deserialize, JsonSchema, Parser)]
 pub struct Config {
-    /// Path of the config file
+    /// Path of the config file, whose services, forwarded headers,
+    /// authorization and CORS settings are reloaded when it changes.
     #[clap(long, env = "CONFIG_FILE", default_value = "config.toml")]
     #[serde(skip)]
     pub file: PathBuf,
@@ -17,6 +63,16 @@ pub struct Config {
     #[serde(default = "default_bind")]
     pub bind: String,
 
+    /// The address the admin endpoints are served at, apart from the GraphQL
+    /// traffic.
+    ///
+    /// Without it, the read-only endpoints (`/status`, `/routes`, `/schema`,
+    /// `/config-schema` and `/startup-report`) are served at `bind`, and
+    /// `/refresh`, `/blocked-operations` and `/caches` are not served.
+    #[clap(long, env)]
+    #[serde(default)]
+    pub admin_bind: Option<String>,
+
     #[clap(long, env, default_value = "graphgate")]
     #[serde(default)]
     pub path: String,
@@ -25,14 +81,174 @@ pub struct Config {
     #[serde(default = "default_service_name")]
     pub gateway_name: String,
 
+    /// Route the Kubernetes services labeled with the name of another one as
+    /// `{service}-{name}`, instead of leaving them out.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub k8s_suffix_duplicates: bool,
+
     #[clap(long, env, value_delimiter = ',')]
     #[serde(default)]
     pub forward_headers: Vec<String>,
 
+    /// Entries of the WebSocket `connection_init` payload forwarded to the
+    /// subgraphs, all of them if unset.
+    #[clap(long, env, value_delimiter = ',')]
+    #[serde(default)]
+    pub forward_connection_params: Vec<String>,
+
     #[clap(long, env, value_delimiter = ',')]
     #[serde(default)]
     pub receive_headers: Vec<String>,
 
+    /// Headers of the subgraph responses, such as `x-cache`, recorded in the
+    /// fetch spans and the debug extensions.
+    #[clap(long, env, value_delimiter = ',')]
+    #[serde(default)]
+    pub trace_response_headers: Vec<String>,
+
+    /// Path of a persisted operation manifest, whose operations are
+    /// planned against every composed schema to report breaking changes.
+    /// The plans are only checked, the requests are planned as they come.
+    /// The `forwardHeaders` of the metadata of an operation replace the
+    /// `forward_headers` for its requests.
+    #[clap(long, env)]
+    #[serde(default)]
+    pub persisted_operations: Option<PathBuf>,
+
+    /// Path of the snapshot of the last composed schema, served at startup
+    /// until the SDLs of the services can be fetched.
+    #[clap(long, env)]
+    #[serde(default)]
+    pub schema_snapshot: Option<PathBuf>,
+
+    /// Path of a supergraph SDL composed ahead of time, such as by `rover
+    /// supergraph compose`, served instead of composing the SDLs of the
+    /// services. The services are routed to by the URLs of its graphs.
+    #[clap(long, env)]
+    #[serde(default)]
+    pub supergraph: Option<PathBuf>,
+
+    /// The environment the gateway runs in, selecting the address overrides
+    /// of the services.
+    #[clap(long, env)]
+    #[serde(default)]
+    pub environment: Option<String>,
+
+    /// Serve query plans annotated with recent subgraph latencies at
+    /// `/explain`.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub explain: bool,
+
+    /// Describe the subgraph request in the extensions of errors, for
+    /// debugging. Variable values are left out.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub debug_errors: bool,
+
+    /// Report the errors of the entity fetches at the paths of their
+    /// entities in the response, such as `["users", 2, "reviews"]`, instead
+    /// of their paths relative to the `_entities` field of the fetch.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub normalize_error_paths: bool,
+
+    /// Check that the services resolve the entities with the keys they are
+    /// sent, to diagnose key drift. The entities fetches select the keys
+    /// again, for debugging.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub check_entity_keys: bool,
+
+    /// Plan the operations of the authenticated requests with an
+    /// `allowedServices` extension with only the services it lists, failing
+    /// those selecting fields of other services.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub allowed_services_extension: bool,
+
+    /// Respond with `401`, `403` or `502` to the queries that failed as a
+    /// whole because a subgraph refused them, instead of `200`.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub propagate_subgraph_status: bool,
+
+    /// Report the fetch durations of each service, with the metrics of the
+    /// `Server-Timing` headers of the subgraphs, in the `Server-Timing`
+    /// header of the responses.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub server_timing: bool,
+
+    /// Reject the operations selecting `__schema` or `__type`, to hide the
+    /// schema from clients.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub disable_introspection: bool,
+
+    /// Development mode: recompose as soon as the SDL files or the services
+    /// change, report composition errors on the console, reload the
+    /// playground and disable authorization.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub watch: bool,
+
+    /// Compose the schema of the configured services once, print the
+    /// composition hints and errors in the JSON output format of rover and
+    /// exit, failing if the schema does not compose.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(default)]
+    pub check: bool,
+
+    /// Print the JSON schema of the config file and exit.
+    #[clap(long, env, default_value_t = false)]
+    #[serde(skip)]
+    pub config_schema: bool,
+
+    /// Print the routes discovered by the running gateway at this URL and
+    /// exit.
+    #[clap(long, value_name = "URL")]
+    #[serde(skip)]
+    pub routes: Option<String>,
+
+    /// Translate the legacy SDL of this file, annotated with `@owner`,
+    /// `@key(service:)` and `@resolve`, into the Federation 2 SDL of each
+    /// service and exit.
+    #[clap(long, value_name = "FILE")]
+    #[serde(skip)]
+    pub migrate_sdl: Option<PathBuf>,
+
+    /// Write the SDLs translated by `--migrate-sdl` to `<service>.graphql`
+    /// files in this directory instead of printing them.
+    #[clap(long, value_name = "DIR", requires = "migrate_sdl")]
+    #[serde(skip)]
+    pub migrate_sdl_out: Option<PathBuf>,
+
+    /// Plan the operations of this query file against the schema composed
+    /// from the configured services, print their plans and exit.
+    #[clap(long, value_name = "FILE")]
+    #[serde(skip)]
+    pub plan: Option<PathBuf>,
+
+    /// The format `--plan` prints the plans in: `json`, `dot` for Graphviz
+    /// or `mermaid`.
+    #[clap(long, value_name = "FORMAT", default_value = "json")]
+    #[serde(skip)]
+    pub plan_format: PlanFormat,
+
+    /// The operation of the query file `--plan` plans, instead of each of
+    /// them.
+    #[clap(long = "operation-name", value_name = "NAME", requires = "plan")]
+    #[serde(skip)]
+    pub plan_operation_name: Option<String>,
+
+    /// The variables of the operations `--plan` plans, as a JSON object,
+    /// validated and sent with their fetches.
+    #[clap(long = "variables", value_name = "JSON", requires = "plan")]
+    #[serde(skip)]
+    pub plan_variables: Option<String>,
+
     #[clap(flatten)]
     pub jaeger: Option<JaegerConfig>,
 
@@ -42,12 +258,111 @@ pub struct Config {
     #[clap(flatten)]
     pub authorization: Option<AuthConfig>,
 
+    #[clap(flatten)]
+    pub defer: Option<DeferConfig>,
+
+    #[clap(flatten)]
+    pub pagination: Option<PaginationConfig>,
+
+    #[clap(flatten)]
+    pub cost: Option<CostConfig>,
+
+    #[clap(flatten)]
+    pub deprecation: Option<DeprecationConfig>,
+
+    #[clap(flatten)]
+    pub startup: Option<StartupConfig>,
+
+    #[clap(flatten)]
+    pub rate_limit: Option<RateLimitConfig>,
+
+    #[clap(flatten)]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+
+    #[clap(flatten)]
+    pub upstream: Option<UpstreamConfig>,
+
+    #[clap(flatten)]
+    pub parallelism: Option<ParallelismConfig>,
+
+    #[clap(flatten)]
+    pub connections: Option<ConnectionConfig>,
+
+    #[clap(flatten)]
+    pub response_limit: Option<ResponseLimitConfig>,
+
+    #[clap(flatten)]
+    pub call_budget: Option<CallBudgetConfig>,
+
+    #[clap(flatten)]
+    pub operation_limits: Option<OperationLimitsConfig>,
+
+    #[clap(flatten)]
+    pub chaos: Option<ChaosConfig>,
+
+    #[clap(flatten)]
+    pub introspection: Option<IntrospectionConfig>,
+
+    #[clap(flatten)]
+    pub safelist: Option<SafelistConfig>,
+
+    #[clap(flatten)]
+    pub persisted_queries: Option<PersistedQueryConfig>,
+
+    #[clap(flatten)]
+    pub entity_cache: Option<EntityCacheConfig>,
+
+    #[clap(flatten)]
+    pub operation_labels: Option<OperationLabelConfig>,
+
+    #[clap(flatten)]
+    pub docs: Option<DocsConfig>,
+
+    #[clap(flatten)]
+    pub composition: Option<CompositionConfig>,
+
+    #[clap(flatten)]
+    pub entity_check: Option<EntityCheckConfig>,
+
+    #[clap(flatten)]
+    pub subgraph_requests: Option<SubgraphRequestConfig>,
+
+    #[clap(flatten)]
+    pub polling: Option<PollingConfig>,
+
+    #[clap(flatten)]
+    pub audit: Option<AuditConfig>,
+
     #[clap(skip)]
     #[serde(default)]
     pub services: Vec<ServiceConfig>,
+
+    /// Maps discovered service names to the service names used in the
+    /// schema.
+    #[clap(skip)]
+    #[serde(default)]
+    pub service_aliases: HashMap<String, String>,
+
+    #[clap(skip)]
+    #[serde(default)]
+    pub context: Vec<ContextRule>,
+
+    /// Fields redacted from the responses to the requests without an
+    /// allowed scope.
+    #[clap(skip)]
+    #[serde(default)]
+    pub redaction: Vec<RedactionRule>,
+
+    #[clap(skip)]
+    #[serde(default)]
+    pub bucketing: Option<BucketingConfig>,
+
+    #[clap(skip)]
+    #[serde(default)]
+    pub verification: Option<VerificationConfig>,
 }
 
-#[derive(Args, Debug, Deserialize, Clone)]
+#[derive(Args, Debug, Deserialize, JsonSchema, Clone)]
 pub struct ServiceConfig {
     pub name: String,
     pub addr: String,
@@ -56,7 +371,80 @@ pub struct ServiceConfig {
     pub query_path: Option<String>,
     pub subscribe_path: Option<String>,
     pub introspection_path: Option<String>,
+    /// The URL the SDL of the service is fetched from, instead of its
+    /// address, such as a schema registry.
+    #[clap(skip)]
+    #[serde(default)]
+    pub schema_url: Option<String>,
+    /// The address traffic is sent to by environment name, replacing `addr`
+    /// in the selected environment while the SDL is still fetched from it.
+    #[clap(skip)]
+    #[serde(default)]
+    pub addr_overrides: HashMap<String, String>,
     pub websocket_path: Option<String>,
+    /// The WebSocket protocol of the subscriptions of the service,
+    /// `graphql-ws` or `graphql-transport-ws`, negotiated if not set.
+    #[clap(skip)]
+    #[serde(default)]
+    pub websocket_protocol: Option<Protocols>,
+    /// The connection params sent to the service when opening a WebSocket,
+    /// a fixed value or a forwarded header of the client request by param
+    /// name.
+    #[clap(skip)]
+    #[serde(default)]
+    pub connection_params: HashMap<String, ConnectionParam>,
+    /// Read the SDL of the service from this file instead of querying it.
+    pub sdl_file: Option<PathBuf>,
+    /// Headers sent with every request to the service.
+    #[clap(skip)]
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Which of the forwarded headers are sent to the service, and under
+    /// which name.
+    #[clap(skip)]
+    #[serde(default)]
+    pub header_policy: HeaderPolicy,
+    /// The User-Agent of the requests to the service, replacing the one of
+    /// the gateway.
+    #[clap(skip)]
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Authenticate to the service with an OAuth2 client credentials grant.
+    #[clap(skip)]
+    #[serde(default)]
+    pub oauth2: Option<OAuth2Config>,
+    /// Sign the requests to the service with an HMAC-SHA256 of their body
+    /// and a timestamp, keys are rotated by reloading the config file.
+    #[clap(skip)]
+    #[serde(default)]
+    pub signing: Option<RequestSigningConfig>,
+    /// Enum values the service names differently from the supergraph, by
+    /// enum type and supergraph value.
+    #[clap(skip)]
+    #[serde(default)]
+    pub enum_values: EnumValues,
+    /// Accept responses whose errors are an object, a string or a top-level
+    /// `error`, for legacy services.
+    #[clap(skip)]
+    #[serde(default)]
+    pub lenient_errors: bool,
+    /// How long to wait for the responses of the service, or for its
+    /// WebSocket subscriptions to start, in milliseconds, without a limit if
+    /// not set.
+    #[clap(skip)]
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// How many times the queries failing with a timeout, a connection error
+    /// or a 5xx status, and the WebSocket subscriptions failing to start, are
+    /// sent again. Mutations are never retried.
+    #[clap(skip)]
+    #[serde(default)]
+    pub retry_count: u32,
+    /// The delay before the first retry in milliseconds, doubled for each
+    /// following retry.
+    #[clap(skip)]
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
 }
 
 impl ServiceConfig {
@@ -68,24 +456,65 @@ impl ServiceConfig {
             self.query_path.clone()
         }
     }
+
+    /// The address traffic is sent to in the environment, and the URL the
+    /// SDL is fetched from if it differs.
+    fn addrs(&self, environment: Option<&str>) -> (String, Option<String>) {
+        match environment.and_then(|environment| self.addr_overrides.get(environment)) {
+            Some(addr) => {
+                let schema_url = self.schema_url.clone().unwrap_or_else(|| {
+                    let scheme = if self.tls { "https" } else { "http" };
+                    let path = self.introspection_path.as_deref().unwrap_or_default();
+                    format!("{}://{}{}", scheme, self.addr, path)
+                });
+                (addr.clone(), Some(schema_url))
+            },
+            None => (self.addr.clone(), self.schema_url.clone()),
+        }
+    }
 }
 
-#[derive(Args, Clone, Debug, Deserialize)]
-pub struct CorsConfig {
-    #[clap(long, env = "CORS_ALLOW_METHODS", value_delimiter = ',')]
-    pub allow_methods: Option<Vec<String>>,
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
+pub struct StartupConfig {
+    /// How long to wait for the first schema composition before accepting
+    /// traffic, in seconds.
+    #[clap(long = "startup-timeout-secs", env = "STARTUP_TIMEOUT_SECS", default_value_t = 30)]
+    #[serde(default = "default_startup_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// What to do if no schema was composed within the timeout.
+    #[clap(
+        long = "startup-on-timeout",
+        env = "STARTUP_ON_TIMEOUT",
+        value_enum,
+        default_value = "serve"
+    )]
+    #[serde(default)]
+    pub on_timeout: StartupTimeoutAction,
+}
 
-    #[clap(long, env = "CORS_ALLOW_CREDENTIALS")]
-    pub allow_credentials: Option<bool>,
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_startup_timeout_secs(),
+            on_timeout: Default::default(),
+        }
+    }
+}
 
-    #[clap(long, env = "CORS_ALLOW_HEADERS", value_delimiter = ',')]
-    pub allow_headers: Option<Vec<String>>,
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupTimeoutAction {
+    /// Start serving anyway, responding with errors until a schema is
+    /// composed.
+    #[default]
+    Serve,
 
-    #[clap(long, env = "CORS_ALLOW_ORIGINS", value_delimiter = ',')]
-    pub allow_origins: Option<Vec<String>>,
+    /// Exit with an error.
+    Fail,
 }
 
-#[derive(Args, Clone, Debug, Deserialize)]
+#[derive(Args, Clone, Debug, Deserialize, JsonSchema)]
 pub struct JaegerConfig {
     #[clap(long, env = "JAEGER_AGENT_ENDPOINT")]
     pub agent_endpoint: Option<String>,
@@ -99,6 +528,7 @@ impl Config {
     /// Parse the config file and environment variables.
     /// If the config file exists, it will be parsed first and ignore
     /// environment variables.
+    #[allow(clippy::manual_ok_err, clippy::useless_borrows_in_formatting)]
     pub fn try_parse() -> anyhow::Result<Self> {
         let mut env_config = Config::parse();
 
@@ -108,6 +538,17 @@ impl Config {
             let mut file_config: Config = toml::from_str(&file_config)
                 .with_context(|| format!("Failed to parse config file '{}'.", &env_config.file.display()))?;
 
+            file_config.watch |= env_config.watch;
+            file_config.check |= env_config.check;
+            file_config.config_schema |= env_config.config_schema;
+            file_config.routes = env_config.routes;
+            file_config.migrate_sdl = env_config.migrate_sdl;
+            file_config.migrate_sdl_out = env_config.migrate_sdl_out;
+            file_config.plan = env_config.plan;
+            file_config.plan_format = env_config.plan_format;
+            file_config.plan_operation_name = env_config.plan_operation_name;
+            file_config.plan_variables = env_config.plan_variables;
+
             // Override service URI with env var if set
             for service in &mut file_config.services {
                 if let Ok(addr) = std::env::var(format!("SERVICE_{}_ADDR", service.name.to_ascii_uppercase())) {
@@ -136,7 +577,12 @@ impl Config {
             // SERVICE_<SERVICE_NAME>_QUERY_PATH
             // SERVICE_<SERVICE_NAME>_SUBSCRIBE_PATH
             // SERVICE_<SERVICE_NAME>_INTROSPECTION_PATH
+            // SERVICE_<SERVICE_NAME>_SCHEMA_URL
             // SERVICE_<SERVICE_NAME>_WEBSOCKET_PATH
+            // SERVICE_<SERVICE_NAME>_WEBSOCKET_PROTOCOL
+            // SERVICE_<SERVICE_NAME>_SDL_FILE
+            // SERVICE_<SERVICE_NAME>_USER_AGENT
+            // SERVICE_<SERVICE_NAME>_LENIENT_ERRORS
             env_config.services = service_prefixes
                 .into_iter()
                 .map(|service_prefix| ServiceConfig {
@@ -174,6 +620,8 @@ impl Config {
                     } else {
                         None
                     },
+                    schema_url: std::env::var(format!("{}{}_SCHEMA_URL", env_prefix, service_prefix)).ok(),
+                    addr_overrides: Default::default(),
                     websocket_path: if let Ok(path) =
                         std::env::var(format!("{}{}_WEBSOCKET_PATH", env_prefix, service_prefix))
                     {
@@ -181,6 +629,34 @@ impl Config {
                     } else {
                         None
                     },
+                    websocket_protocol: std::env::var(format!("{}{}_WEBSOCKET_PROTOCOL", env_prefix, service_prefix))
+                        .ok()
+                        .and_then(|protocol| protocol.parse().ok()),
+                    connection_params: Default::default(),
+                    sdl_file: std::env::var(format!("{}{}_SDL_FILE", env_prefix, service_prefix))
+                        .ok()
+                        .map(PathBuf::from),
+                    headers: Default::default(),
+                    header_policy: Default::default(),
+                    user_agent: std::env::var(format!("{}{}_USER_AGENT", env_prefix, service_prefix)).ok(),
+                    oauth2: None,
+                    signing: None,
+                    enum_values: Default::default(),
+                    lenient_errors: std::env::var(format!("{}{}_LENIENT_ERRORS", env_prefix, service_prefix))
+                        .unwrap_or("false".to_string())
+                        .parse()
+                        .unwrap_or_default(),
+                    timeout_ms: std::env::var(format!("{}{}_TIMEOUT_MS", env_prefix, service_prefix))
+                        .ok()
+                        .and_then(|timeout| timeout.parse().ok()),
+                    retry_count: std::env::var(format!("{}{}_RETRY_COUNT", env_prefix, service_prefix))
+                        .ok()
+                        .and_then(|count| count.parse().ok())
+                        .unwrap_or_default(),
+                    retry_backoff_ms: std::env::var(format!("{}{}_RETRY_BACKOFF_MS", env_prefix, service_prefix))
+                        .ok()
+                        .and_then(|backoff| backoff.parse().ok())
+                        .unwrap_or_else(default_retry_backoff_ms),
                 })
                 .collect::<Vec<ServiceConfig>>();
 
@@ -188,17 +664,184 @@ impl Config {
         }
     }
 
+    /// The User-Agent and metadata headers of the requests to the
+    /// subgraphs, identifying the gateway by its name unless a User-Agent is
+    /// configured.
+    pub fn subgraph_request_config(&self) -> SubgraphRequestConfig {
+        let mut config = self.subgraph_requests.clone().unwrap_or_default();
+        config
+            .user_agent
+            .get_or_insert_with(|| SubgraphRequestConfig::default_user_agent(&self.gateway_name));
+        config
+    }
+
+    /// The configured features by the name of their config key, sorted.
+    pub fn features(&self) -> Vec<&'static str> {
+        // Destructured without `..`, so that a new config key does not compile
+        // until it is listed here.
+        let Config {
+            file: _,
+            bind: _,
+            admin_bind,
+            path: _,
+            gateway_name: _,
+            k8s_suffix_duplicates,
+            forward_headers: _,
+            forward_connection_params: _,
+            receive_headers: _,
+            trace_response_headers: _,
+            persisted_operations,
+            schema_snapshot,
+            supergraph,
+            environment: _,
+            explain,
+            debug_errors,
+            normalize_error_paths,
+            check_entity_keys,
+            allowed_services_extension,
+            propagate_subgraph_status,
+            server_timing,
+            disable_introspection,
+            watch,
+            check: _,
+            config_schema: _,
+            routes: _,
+            migrate_sdl: _,
+            migrate_sdl_out: _,
+            plan: _,
+            plan_format: _,
+            plan_operation_name: _,
+            plan_variables: _,
+            jaeger,
+            cors,
+            authorization,
+            defer,
+            pagination,
+            cost,
+            deprecation,
+            startup,
+            rate_limit,
+            circuit_breaker,
+            upstream,
+            parallelism,
+            connections,
+            response_limit,
+            call_budget,
+            operation_limits,
+            chaos,
+            introspection,
+            safelist,
+            persisted_queries,
+            entity_cache,
+            operation_labels,
+            docs,
+            composition,
+            entity_check,
+            subgraph_requests,
+            polling,
+            audit,
+            services,
+            service_aliases,
+            context,
+            redaction,
+            bucketing,
+            verification,
+        } = self;
+
+        let mut features = [
+            ("admin_bind", admin_bind.is_some()),
+            ("allowed_services_extension", *allowed_services_extension),
+            ("audit", audit.is_some()),
+            ("authorization", authorization.as_ref().is_some_and(|auth| auth.enabled)),
+            ("bucketing", bucketing.is_some()),
+            ("call_budget", call_budget.is_some()),
+            ("chaos", chaos.as_ref().is_some_and(|chaos| chaos.enabled)),
+            ("circuit_breaker", circuit_breaker.is_some()),
+            ("check_entity_keys", *check_entity_keys),
+            ("composition", composition.is_some()),
+            ("connections", connections.is_some()),
+            ("context", !context.is_empty()),
+            ("cors", cors.is_some()),
+            ("cost", cost.is_some()),
+            ("debug_errors", *debug_errors),
+            ("defer", defer.is_some()),
+            ("deprecation", deprecation.is_some()),
+            ("disable_introspection", *disable_introspection),
+            ("docs", docs.as_ref().is_some_and(|docs| docs.enabled)),
+            ("entity_cache", entity_cache.is_some()),
+            ("entity_check", entity_check.is_some()),
+            ("explain", *explain),
+            ("introspection", introspection.is_some()),
+            (
+                "jaeger",
+                jaeger.as_ref().is_some_and(|jaeger| jaeger.agent_endpoint.is_some()),
+            ),
+            ("k8s_suffix_duplicates", *k8s_suffix_duplicates),
+            ("normalize_error_paths", *normalize_error_paths),
+            ("operation_labels", operation_labels.is_some()),
+            ("operation_limits", operation_limits.is_some()),
+            ("pagination", pagination.is_some()),
+            ("parallelism", parallelism.is_some()),
+            ("persisted_operations", persisted_operations.is_some()),
+            ("persisted_queries", persisted_queries.is_some()),
+            ("polling", polling.is_some()),
+            ("propagate_subgraph_status", *propagate_subgraph_status),
+            ("rate_limit", rate_limit.is_some()),
+            ("redaction", !redaction.is_empty()),
+            ("response_limit", response_limit.is_some()),
+            ("safelist", safelist.as_ref().is_some_and(|safelist| safelist.enabled)),
+            ("schema_snapshot", schema_snapshot.is_some()),
+            ("server_timing", *server_timing),
+            ("service_aliases", !service_aliases.is_empty()),
+            ("signing", services.iter().any(|service| service.signing.is_some())),
+            ("startup", startup.is_some()),
+            ("subgraph_requests", subgraph_requests.is_some()),
+            ("supergraph", supergraph.is_some()),
+            ("upstream", upstream.is_some()),
+            ("verification", verification.is_some()),
+            ("watch", *watch),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(feature, _)| feature)
+        .collect::<Vec<_>>();
+        features.sort_unstable();
+        features
+    }
+
+    /// The JSON schema of the config file, for editor completion and
+    /// validating config repositories in CI.
+    pub fn json_schema() -> RootSchema {
+        schema_for!(Config)
+    }
+
     #[instrument(ret, level = "trace")]
     pub fn create_route_table(&self) -> ServiceRouteTable {
         let mut route_table = ServiceRouteTable::default();
         for service in &self.services {
+            let (addr, schema_url) = service.addrs(self.environment.as_deref());
             route_table.insert(service.name.clone(), ServiceRoute {
-                addr: service.addr.clone(),
+                addr,
                 tls: service.tls,
                 query_path: service.query_path.clone(),
                 subscribe_path: service.subscribe_path.clone(),
                 introspection_path: service.introspection_path.clone(),
+                schema_url,
                 websocket_path: service.default_or_set_websocket_path(),
+                websocket_protocol: service.websocket_protocol,
+                connection_params: service.connection_params.clone(),
+                sdl_file: service.sdl_file.clone(),
+                headers: service.headers.clone(),
+                header_policy: service.header_policy.clone(),
+                user_agent: service.user_agent.clone(),
+                oauth2: service.oauth2.clone(),
+                signing: service.signing.clone(),
+                enum_values: service.enum_values.clone(),
+                lenient_errors: service.lenient_errors,
+                timeout_ms: service.timeout_ms,
+                retry_count: service.retry_count,
+                retry_backoff_ms: service.retry_backoff_ms,
+                source: RouteSource::Config,
             });
         }
         route_table
@@ -213,10 +856,19 @@ fn default_service_name() -> String {
     "graphgate".to_string()
 }
 
+fn default_startup_timeout_secs() -> u64 {
+    30
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    ServiceRoute::DEFAULT_RETRY_BACKOFF_MS
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
 
+    use graphgate_handler::{BucketKey, ContextSource, FaultKind, OperationLabelMode};
     use serial_test::serial;
     use tempfile::NamedTempFile;
 
@@ -295,6 +947,8 @@ mod tests {
             r#"
         bind = "0.0.0.0:4000"
         forward_headers = ["authorization"]
+        trace_response_headers = ["x-cache"]
+        supergraph = "supergraph.graphql"
         [[services]]
         name = "test"
         addr = "test:4000"
@@ -302,6 +956,7 @@ mod tests {
         subscribe_path = "/public/graphql"
         introspection_path = "/public/graphql"
         websocket_path = "/public/graphql"
+        sdl_file = "test.graphql"
         "#
         )
         .expect("Failed to write temp config");
@@ -311,6 +966,8 @@ mod tests {
         let parsed_config = Config::try_parse().expect("Failed to parse config");
         assert_eq!(parsed_config.bind, "0.0.0.0:4000");
         assert_eq!(parsed_config.forward_headers, vec!["authorization".to_string()]);
+        assert_eq!(parsed_config.trace_response_headers, vec!["x-cache".to_string()]);
+        assert_eq!(parsed_config.supergraph, Some(PathBuf::from("supergraph.graphql")));
         assert_eq!(parsed_config.services.len(), 1);
 
         let service_config = parsed_config.services.first().expect("No service config");
@@ -320,11 +977,115 @@ mod tests {
         assert_eq!(service_config.subscribe_path, Some("/public/graphql".to_string()));
         assert_eq!(service_config.introspection_path, Some("/public/graphql".to_string()));
         assert_eq!(service_config.websocket_path, Some("/public/graphql".to_string()));
+        assert_eq!(service_config.sdl_file, Some(PathBuf::from("test.graphql")));
+        assert!(!parsed_config.watch);
 
         std::env::remove_var("CONFIG_FILE");
         std::env::remove_var("BIND");
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn parse_auth_token_sources() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [authorization]
+        enabled = true
+        jwks = "https://test.tld/jwks.json"
+        cookies = ["session"]
+        query_params = ["access_token"]
+        [[authorization.headers]]
+        name = "x-api-token"
+        [[authorization.headers]]
+        name = "authorization"
+        prefix = "Token"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let auth_config = parsed_config.authorization.expect("No auth config");
+        assert_eq!(auth_config.cookies, vec!["session".to_string()]);
+        assert_eq!(auth_config.query_params, vec!["access_token".to_string()]);
+        assert_eq!(auth_config.headers.len(), 2);
+        assert_eq!(auth_config.headers[0].name, "x-api-token");
+        assert_eq!(auth_config.headers[0].prefix, "");
+        assert_eq!(auth_config.headers[1].prefix, "Token");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_auth_issuers() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [authorization]
+        enabled = true
+        clock_skew_secs = 30
+        jwks_refresh_secs = 600
+        [[authorization.issuers]]
+        issuer = "https://a.example.com/"
+        jwks = "https://a.example.com/jwks.json"
+        audiences = ["graphgate"]
+        [[authorization.issuers]]
+        issuer = "https://b.example.com/"
+        jwks = "https://b.example.com/jwks.json"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let auth_config = parsed_config.authorization.expect("No auth config");
+        assert_eq!(auth_config.jwks, "");
+        assert_eq!(auth_config.clock_skew_secs, 30);
+        assert_eq!(auth_config.jwks_refresh_secs, 600);
+        assert_eq!(auth_config.issuers.len(), 2);
+        assert_eq!(auth_config.issuers[0].issuer, "https://a.example.com/");
+        assert_eq!(auth_config.issuers[0].audiences, vec!["graphgate".to_string()]);
+        assert_eq!(auth_config.issuers[1].jwks, "https://b.example.com/jwks.json");
+        assert!(auth_config.issuers[1].audiences.is_empty());
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_auth_claim_headers() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [authorization]
+        enabled = true
+        jwks = "https://example.com/jwks.json"
+        [[authorization.claim_headers]]
+        claim = "sub"
+        header = "x-user-id"
+        [[authorization.claim_headers]]
+        claim = "scope"
+        header = "x-scopes"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let auth_config = parsed_config.authorization.expect("No auth config");
+        assert_eq!(auth_config.claim_headers.len(), 2);
+        assert_eq!(auth_config.claim_headers[0].claim, "sub");
+        assert_eq!(auth_config.claim_headers[0].header, "x-user-id");
+        assert_eq!(auth_config.claim_headers[1].header, "x-scopes");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
     #[tokio::test]
     #[serial]
     async fn parse_config_file_no_auth() {
@@ -348,28 +1109,1120 @@ mod tests {
 
     #[tokio::test]
     #[serial]
-    async fn parse_config_file_service_addr_override() {
+    async fn parse_config_file_defer() {
         let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
         write!(
             tmpfile,
             r#"
-        [[services]]
-        name = "testoverride"
-        addr = "test:4000"
+        [defer]
+        primary_fields = ["me"]
         "#
         )
         .expect("Failed to write temp config");
         std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
-        std::env::set_var("SERVICE_TESTOVERRIDE_ADDR", "127.0.0.1:8000");
 
         let parsed_config = Config::try_parse().expect("Failed to parse config");
-        assert_eq!(parsed_config.services.len(), 1);
+        let defer_config = parsed_config.defer.expect("No defer config");
+        assert_eq!(defer_config.primary_fields, vec!["me".to_string()]);
+        assert_eq!(defer_config.latency_budget_ms, 100);
 
-        let service_config = parsed_config.services.first().expect("No service config");
-        assert_eq!(service_config.name, "testoverride");
-        assert_eq!(service_config.addr, "127.0.0.1:8000");
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_pagination() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [pagination]
+        nested_limit = 10
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let pagination_config = parsed_config.pagination.expect("No pagination config");
+        assert_eq!(pagination_config.limit_arguments, vec![
+            "first".to_string(),
+            "limit".to_string()
+        ]);
+        assert_eq!(pagination_config.default_limit, 100);
+        assert_eq!(pagination_config.nested_limit, Some(10));
 
         std::env::remove_var("CONFIG_FILE");
-        std::env::remove_var("SERVICE_TESTOVERRIDE_ADDR");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_cost() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [cost]
+        max_cost = 500
+
+        [[cost.budgets]]
+        scope = "premium"
+        max_cost = 5000
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let cost_config = parsed_config.cost.expect("No cost config");
+        assert_eq!(cost_config.max_cost, 500);
+        assert_eq!(cost_config.default_list_size, 10);
+        assert_eq!(cost_config.budgets.len(), 1);
+        assert_eq!(cost_config.budgets[0].scope.as_deref(), Some("premium"));
+        assert_eq!(cost_config.budgets[0].max_cost, 5000);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_deprecation() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [deprecation]
+        grace_period_days = 30
+
+        [deprecation.sunsets]
+        "User.name" = "2026-01-01"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let deprecation_config = parsed_config.deprecation.expect("No deprecation config");
+        assert_eq!(deprecation_config.grace_period_days, 30);
+        assert_eq!(deprecation_config.sunsets["User.name"].to_string(), "2026-01-01");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_audit() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [audit]
+        file = "/var/log/graphgate/audit.log"
+        redact_variables = ["password"]
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let audit_config = parsed_config.audit.expect("No audit config");
+        assert_eq!(audit_config.file, Some(PathBuf::from("/var/log/graphgate/audit.log")));
+        assert_eq!(audit_config.url, None);
+        assert_eq!(audit_config.actor_claim, "sub");
+        assert_eq!(audit_config.redact_variables, vec!["password".to_string()]);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_parallelism() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [parallelism]
+        max_fetches = 64
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let parallelism_config = parsed_config.parallelism.expect("No parallelism config");
+        assert_eq!(parallelism_config.max_fetches, 64);
+        assert_eq!(parallelism_config.max_request_fetches, 0);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_connections() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [connections]
+        batch_size = 50
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let connection_config = parsed_config.connections.expect("No connections config");
+        assert_eq!(connection_config.batch_size, 50);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_subgraph_requests() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        gateway_name = "edge"
+
+        [subgraph_requests.headers]
+        x-gateway-region = "eu-west-1"
+
+        [[services]]
+        name = "accounts"
+        addr = "127.0.0.1:8001"
+        user_agent = "partner-gateway/1.0"
+
+        [[services]]
+        name = "products"
+        addr = "127.0.0.1:8002"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let subgraph_request_config = parsed_config.subgraph_request_config();
+        assert_eq!(
+            subgraph_request_config.user_agent.as_deref(),
+            Some(concat!("graphgate/", env!("CARGO_PKG_VERSION"), " (edge)"))
+        );
+
+        let mut route_table = parsed_config.create_route_table();
+        route_table.apply_request_config(&subgraph_request_config);
+        assert_eq!(
+            route_table["accounts"].user_agent.as_deref(),
+            Some("partner-gateway/1.0")
+        );
+        assert_eq!(route_table["products"].user_agent, subgraph_request_config.user_agent);
+        assert_eq!(route_table["products"].headers["x-gateway-region"], "eu-west-1");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_lenient_errors() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "legacy"
+        addr = "127.0.0.1:8001"
+        lenient_errors = true
+
+        [[services]]
+        name = "accounts"
+        addr = "127.0.0.1:8002"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let route_table = Config::try_parse()
+            .expect("Failed to parse config")
+            .create_route_table();
+        assert!(route_table["legacy"].lenient_errors);
+        assert!(!route_table["accounts"].lenient_errors);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_retries() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "accounts"
+        addr = "127.0.0.1:8001"
+        timeout_ms = 2000
+        retry_count = 2
+        retry_backoff_ms = 50
+
+        [[services]]
+        name = "products"
+        addr = "127.0.0.1:8002"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let route_table = Config::try_parse()
+            .expect("Failed to parse config")
+            .create_route_table();
+        assert_eq!(route_table["accounts"].timeout_ms, Some(2000));
+        assert_eq!(route_table["accounts"].retry_count, 2);
+        assert_eq!(route_table["accounts"].retry_backoff_ms, 50);
+        assert_eq!(route_table["products"].timeout_ms, None);
+        assert_eq!(route_table["products"].retry_count, 0);
+        assert_eq!(
+            route_table["products"].retry_backoff_ms,
+            ServiceRoute::DEFAULT_RETRY_BACKOFF_MS
+        );
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_websocket_transport() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "reviews"
+        addr = "127.0.0.1:8001"
+        websocket_path = "/subscriptions"
+        websocket_protocol = "graphql-ws"
+        connection_params = {{ tenant = {{ value = "acme" }}, token = {{ header = "authorization" }} }}
+
+        [[services]]
+        name = "accounts"
+        addr = "127.0.0.1:8002"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let route_table = Config::try_parse()
+            .expect("Failed to parse config")
+            .create_route_table();
+        assert_eq!(
+            route_table.websocket_url("reviews").unwrap(),
+            "ws://127.0.0.1:8001/subscriptions"
+        );
+        assert_eq!(
+            route_table["reviews"].websocket_protocol,
+            Some(Protocols::SubscriptionsTransportWS)
+        );
+        assert_eq!(
+            route_table["reviews"].connection_params,
+            HashMap::from([
+                ("tenant".to_string(), ConnectionParam::Value("acme".into())),
+                (
+                    "token".to_string(),
+                    ConnectionParam::Header("authorization".to_string())
+                ),
+            ])
+        );
+        assert_eq!(route_table["accounts"].websocket_protocol, None);
+        assert!(route_table["accounts"].connection_params.is_empty());
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_addr_overrides() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        environment = "local"
+
+        [[services]]
+        name = "accounts"
+        addr = "accounts.prod:8000"
+        introspection_path = "/graphql"
+        addr_overrides = {{ local = "127.0.0.1:9001", staging = "accounts.staging:8000" }}
+
+        [[services]]
+        name = "reviews"
+        addr = "reviews.prod:8000"
+        schema_url = "https://registry.example.com/graphs/reviews"
+        addr_overrides = {{ local = "127.0.0.1:9002" }}
+
+        [[services]]
+        name = "products"
+        addr = "products.prod:8000"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let route_table = Config::try_parse()
+            .expect("Failed to parse config")
+            .create_route_table();
+        assert_eq!(route_table["accounts"].addr, "127.0.0.1:9001");
+        assert_eq!(
+            route_table.url("accounts", true).unwrap(),
+            "http://accounts.prod:8000/graphql"
+        );
+        assert_eq!(route_table.url("accounts", false).unwrap(), "http://127.0.0.1:9001");
+        assert_eq!(
+            route_table.url("reviews", true).unwrap(),
+            "https://registry.example.com/graphs/reviews"
+        );
+        assert_eq!(route_table.url("reviews", false).unwrap(), "http://127.0.0.1:9002");
+        assert_eq!(route_table.url("products", true).unwrap(), "http://products.prod:8000");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_operation_limits() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [operation_limits]
+        max_depth = 10
+        max_aliases = 20
+
+        [cost]
+        field_weights = {{ "Query.search" = 25 }}
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let operation_limits = parsed_config.operation_limits.expect("No operation limits config");
+        assert_eq!(operation_limits.max_depth, 10);
+        assert_eq!(operation_limits.max_aliases, 20);
+        assert_eq!(operation_limits.max_root_fields, 0);
+        let cost = parsed_config.cost.expect("No cost config");
+        assert_eq!(cost.field_weights["Query.search"], 25);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_chaos() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [chaos]
+        enabled = true
+
+        [[chaos.faults]]
+        service = "accounts"
+        percentage = 25
+        kind = "latency"
+        latency_ms = 500
+
+        [[chaos.faults]]
+        kind = "status"
+        status = 503
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let chaos = parsed_config.chaos.expect("No chaos config");
+        assert!(chaos.enabled);
+        assert_eq!(chaos.faults[0].service.as_deref(), Some("accounts"));
+        assert_eq!(chaos.faults[0].percentage, 25.0);
+        assert_eq!(chaos.faults[0].kind, FaultKind::Latency { latency_ms: 500 });
+        assert_eq!(chaos.faults[1].service, None);
+        assert_eq!(chaos.faults[1].percentage, 100.0);
+        assert_eq!(chaos.faults[1].kind, FaultKind::Status {
+            status: 503,
+            body: String::new(),
+        });
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_safelist() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [safelist]
+        enabled = true
+        manifest = "/etc/graphgate/operations.json"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let safelist = parsed_config.safelist.expect("No safelist config");
+        assert!(safelist.enabled);
+        assert_eq!(safelist.manifest, Some(PathBuf::from("/etc/graphgate/operations.json")));
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_call_budget() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [call_budget]
+        max_calls = 50
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let call_budget_config = parsed_config.call_budget.expect("No call budget config");
+        assert_eq!(call_budget_config.max_calls, 50);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_response_limit() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [response_limit]
+        max_bytes = 1048576
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let response_limit_config = parsed_config.response_limit.expect("No response limit config");
+        assert_eq!(response_limit_config.max_bytes, 1048576);
+        assert_eq!(response_limit_config.max_subgraph_bytes, 0);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_bucketing() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [bucketing]
+        key = {{ claim = "sub" }}
+        salt = "2024"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let bucketing_config = parsed_config.bucketing.expect("No bucketing config");
+        assert_eq!(bucketing_config.key, BucketKey::Claim("sub".to_string()));
+        assert_eq!(bucketing_config.buckets, 100);
+        assert_eq!(bucketing_config.salt, "2024");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_verification() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[verification.operations]]
+        name = "me"
+        query = "query($id: ID!) {{ user(id: $id) {{ name }} }}"
+        variables = {{ id = "1" }}
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let verification_config = parsed_config.verification.expect("No verification config");
+        assert_eq!(verification_config.timeout_secs, 10);
+        assert_eq!(verification_config.operations.len(), 1);
+        let operation = &verification_config.operations[0];
+        assert_eq!(operation.name, "me");
+        assert_eq!(operation.operation_name, None);
+        assert_eq!(operation.variables.to_string(), r#"{id: "1"}"#);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_entity_cache() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [entity_cache]
+        cache_size = 500
+        partition_claims = ["org.id", "scope"]
+        credential_headers = ["x-api-key"]
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let entity_cache_config = parsed_config.entity_cache.expect("No entity cache config");
+        assert_eq!(entity_cache_config.cache_size, 500);
+        assert_eq!(entity_cache_config.redis_url, None);
+        assert_eq!(entity_cache_config.partition_claims, vec!["org.id", "scope"]);
+        assert_eq!(entity_cache_config.credential_headers, vec!["x-api-key"]);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_persisted_queries() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [persisted_queries]
+        redis_url = "redis://127.0.0.1:6379"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let persisted_query_config = parsed_config.persisted_queries.expect("No persisted queries config");
+        assert_eq!(persisted_query_config.cache_size, 1000);
+        assert_eq!(
+            persisted_query_config.redis_url.as_deref(),
+            Some("redis://127.0.0.1:6379")
+        );
+        assert_eq!(persisted_query_config.ttl_secs, 86400);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_introspection() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        disable_introspection = true
+
+        [introspection]
+        max_per_second = 5
+        allowlist = true
+        hidden_tags = ["internal"]
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        assert!(parsed_config.disable_introspection);
+        let introspection_config = parsed_config.introspection.expect("No introspection config");
+        assert_eq!(introspection_config.max_per_second, 5);
+        assert_eq!(introspection_config.cache_size, 32);
+        assert!(introspection_config.allowlist);
+        assert_eq!(introspection_config.hidden_tags, vec!["internal"]);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_composition() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [composition]
+        infer_shareable = true
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let composition_config = parsed_config.composition.expect("No composition config");
+        assert!(composition_config.infer_shareable);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_entity_check() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [entity_check]
+        interval_secs = 300
+
+        [entity_check.samples]
+        Product = {{ upc = "1" }}
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let entity_check_config = parsed_config.entity_check.expect("No entity check config");
+        assert_eq!(entity_check_config.interval_secs, 300);
+        assert_eq!(entity_check_config.samples["Product"].to_string(), r#"{upc: "1"}"#);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_rate_limit() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [rate_limit]
+        max_wait_ms = 500
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let rate_limit_config = parsed_config.rate_limit.expect("No rate limit config");
+        assert_eq!(rate_limit_config.max_wait_ms, 500);
+        assert_eq!(rate_limit_config.default_retry_after_secs, 1);
+        assert_eq!(rate_limit_config.max_retry_after_secs, 60);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_circuit_breaker() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [circuit_breaker]
+        failure_threshold = 10
+
+        [circuit_breaker.services.accounts]
+        failure_threshold = 3
+        cooldown_secs = 5
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let circuit_breaker_config = parsed_config.circuit_breaker.expect("No circuit breaker config");
+        assert_eq!(circuit_breaker_config.failure_threshold, 10);
+        assert_eq!(circuit_breaker_config.cooldown_secs, 30);
+        let accounts = &circuit_breaker_config.services["accounts"];
+        assert_eq!(accounts.failure_threshold, Some(3));
+        assert_eq!(accounts.cooldown_secs, Some(5));
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_upstream() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [upstream]
+        pool_max_idle = 8
+        connect_timeout_ms = 2000
+
+        [upstream.services.reviews]
+        http2 = true
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let upstream_config = parsed_config.upstream.expect("No upstream config");
+        assert_eq!(upstream_config.pool_max_idle, 8);
+        assert_eq!(upstream_config.pool_idle_timeout_secs, 90);
+        assert_eq!(upstream_config.connect_timeout_ms, 2000);
+        assert_eq!(upstream_config.tcp_keepalive_secs, 60);
+        assert!(!upstream_config.http2);
+        let reviews = &upstream_config.services["reviews"];
+        assert_eq!(reviews.http2, Some(true));
+        assert_eq!(reviews.pool_max_idle, None);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_polling() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [polling]
+        interval_secs = 10
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let polling_config = parsed_config.polling.expect("No polling config");
+        assert_eq!(polling_config.interval_secs, 10);
+        assert_eq!(polling_config.retry_secs, 3);
+        assert_eq!(polling_config.max_backoff_secs, 30);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_aliases() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "accounts-v2"
+        addr = "accounts:4000"
+
+        [service_aliases]
+        accounts-v2 = "accounts"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        assert_eq!(
+            parsed_config.service_aliases.get("accounts-v2"),
+            Some(&"accounts".to_string())
+        );
+
+        let mut route_table = parsed_config.create_route_table();
+        route_table.apply_aliases(&parsed_config.service_aliases);
+        assert_eq!(route_table.len(), 1);
+        assert_eq!(route_table["accounts"].addr, "accounts:4000");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_credentials() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "accounts"
+        addr = "accounts:4000"
+
+        [services.headers]
+        x-api-key = "secret"
+
+        [services.oauth2]
+        token_url = "https://auth.example.com/oauth/token"
+        client_id = "graphgate"
+        client_secret = "client-secret"
+        scopes = ["accounts"]
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let route_table = parsed_config.create_route_table();
+        let route = &route_table["accounts"];
+        assert_eq!(route.headers.get("x-api-key"), Some(&"secret".to_string()));
+        let oauth2 = route.oauth2.as_ref().expect("No OAuth2 config");
+        assert_eq!(oauth2.client_id, "graphgate");
+        assert_eq!(oauth2.scopes, vec!["accounts".to_string()]);
+        assert_eq!(oauth2.audience, None);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_header_policy() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        forward_headers = ["authorization", "x-request-id"]
+
+        [[services]]
+        name = "billing"
+        addr = "billing:4000"
+
+        [services.headers]
+        x-internal-key = "billing-key"
+
+        [services.header_policy]
+        deny = ["authorization"]
+        rename = {{ x-request-id = "x-correlation-id" }}
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let route_table = parsed_config.create_route_table();
+        let route = &route_table["billing"];
+        assert!(route.header_policy.allow.is_empty());
+        assert_eq!(route.header_policy.deny, vec!["authorization".to_string()]);
+        assert_eq!(
+            route.header_policy.rename.get("x-request-id"),
+            Some(&"x-correlation-id".to_string())
+        );
+        assert_eq!(route.headers.get("x-internal-key"), Some(&"billing-key".to_string()));
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_signing() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "accounts"
+        addr = "accounts:4000"
+
+        [services.signing]
+        key_id = "2024-06"
+        secret = "signing-secret"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let route_table = parsed_config.create_route_table();
+        let signing = route_table["accounts"].signing.as_ref().expect("No signing config");
+        assert_eq!(signing.key_id, "2024-06");
+        assert_eq!(signing.secret, "signing-secret");
+        assert!(!format!("{:?}", route_table).contains("signing-secret"));
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_enum_values() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "inventory"
+        addr = "inventory:4000"
+
+        [services.enum_values.Color]
+        RED = "red"
+        GREEN = "green"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let route_table = parsed_config.create_route_table();
+        let colors = &route_table["inventory"].enum_values["Color"];
+        assert_eq!(colors["RED"], "red");
+        assert_eq!(colors["GREEN"], "green");
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_startup() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [startup]
+        on_timeout = "fail"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let startup_config = parsed_config.startup.expect("No startup config");
+        assert_eq!(startup_config.timeout_secs, 30);
+        assert_eq!(startup_config.on_timeout, StartupTimeoutAction::Fail);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_operation_labels() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [operation_labels]
+        mode = "allowlist"
+        allowlist = ["GetUser"]
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        let operation_labels = parsed_config.operation_labels.expect("No operation label config");
+        assert_eq!(operation_labels.mode, OperationLabelMode::Allowlist);
+        assert_eq!(operation_labels.limit, 100);
+        assert_eq!(operation_labels.allowlist, vec!["GetUser".to_string()]);
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_context() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[context]]
+        field = "Query.orders"
+        argument = "userId"
+        claim = "sub"
+
+        [[context]]
+        field = "Query.orders"
+        argument = "tenant"
+        header = "x-tenant-id"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        assert_eq!(parsed_config.context.len(), 2);
+        assert_eq!(parsed_config.context[0].field, "Query.orders");
+        assert_eq!(parsed_config.context[0].argument, "userId");
+        assert!(matches!(&parsed_config.context[0].source, ContextSource::Claim(claim) if claim == "sub"));
+        assert!(matches!(&parsed_config.context[1].source, ContextSource::Header(header) if header == "x-tenant-id"));
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_redaction() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[redaction]]
+        field = "User.email"
+        action = "mask"
+        allow_scopes = ["pii:read"]
+
+        [[redaction]]
+        field = "User.ssn"
+        action = "remove"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        assert_eq!(parsed_config.redaction.len(), 2);
+        assert_eq!(parsed_config.redaction[0].field, "User.email");
+        assert_eq!(
+            parsed_config.redaction[0].action,
+            graphgate_handler::RedactionAction::Mask
+        );
+        assert_eq!(parsed_config.redaction[0].allow_scopes, vec!["pii:read".to_string()]);
+        assert_eq!(
+            parsed_config.redaction[1].action,
+            graphgate_handler::RedactionAction::Remove
+        );
+        assert!(parsed_config.redaction[1].allow_scopes.is_empty());
+
+        std::env::remove_var("CONFIG_FILE");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn parse_config_file_service_addr_override() {
+        let mut tmpfile = NamedTempFile::with_prefix("graphgate").expect("Failed to create temp config");
+        write!(
+            tmpfile,
+            r#"
+        [[services]]
+        name = "testoverride"
+        addr = "test:4000"
+        "#
+        )
+        .expect("Failed to write temp config");
+        std::env::set_var("CONFIG_FILE", tmpfile.path().display().to_string());
+        std::env::set_var("SERVICE_TESTOVERRIDE_ADDR", "127.0.0.1:8000");
+
+        let parsed_config = Config::try_parse().expect("Failed to parse config");
+        assert_eq!(parsed_config.services.len(), 1);
+
+        let service_config = parsed_config.services.first().expect("No service config");
+        assert_eq!(service_config.name, "testoverride");
+        assert_eq!(service_config.addr, "127.0.0.1:8000");
+
+        std::env::remove_var("CONFIG_FILE");
+        std::env::remove_var("SERVICE_TESTOVERRIDE_ADDR");
+    }
+
+    #[test]
+    fn json_schema_describes_config_file() {
+        let schema = serde_json::to_value(Config::json_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        assert!(properties.contains_key("services"));
+        assert!(properties.contains_key("subgraph_requests"));
+        assert!(!properties.contains_key("file"));
+        assert!(!properties.contains_key("config_schema"));
+        assert!(!properties.contains_key("routes"));
+        assert!(!properties.contains_key("migrate_sdl"));
+        assert_eq!(
+            schema["definitions"]["ServiceConfig"]["required"],
+            serde_json::json!(["addr", "name"])
+        );
     }
 }
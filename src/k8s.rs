@@ -1,6 +1,8 @@
+use std::collections::{BTreeMap, HashSet};
+
 use anyhow::{Context, Result};
-use graphgate_handler::{ServiceRoute, ServiceRouteTable};
-use k8s_openapi::api::core::v1::Service;
+use graphgate_handler::{record_route_conflict, RouteSource, ServiceRoute, ServiceRouteTable};
+use k8s_openapi::api::core::v1::{Endpoints, Service, ServicePort};
 use kube::{
     api::{ListParams, ObjectMeta},
     Api,
@@ -10,11 +12,18 @@ use kube::{
 const NAMESPACE_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount/namespace";
 const LABEL_GRAPHQL_SERVICE: &str = "graphgate.org/service";
 const LABEL_GRAPHQL_GATEWAY: &str = "graphgate.org/gateway";
+const ANNOTATIONS_PORT: &str = "graphgate.org/port";
+const PORT_NAME_GRAPHQL: &str = "graphql";
 const ANNOTATIONS_TLS: &str = "graphgate.org/tls";
 const ANNOTATIONS_QUERY_PATH: &str = "graphgate.org/queryPath";
 const ANNOTATIONS_SUBSCRIBE_PATH: &str = "graphgate.org/subscribePath";
 const ANNOTATIONS_INTROSPECTION_PATH: &str = "graphgate.org/introspectionPath";
 const ANNOTATIONS_WEBSOCKET_PATH: &str = "graphgate.org/websocketPath";
+const ANNOTATIONS_WEBSOCKET_PROTOCOL: &str = "graphgate.org/websocketProtocol";
+const ANNOTATIONS_LENIENT_ERRORS: &str = "graphgate.org/lenientErrors";
+const ANNOTATIONS_TIMEOUT_MS: &str = "graphgate.org/timeoutMs";
+const ANNOTATIONS_RETRY_COUNT: &str = "graphgate.org/retryCount";
+const ANNOTATIONS_RETRY_BACKOFF_MS: &str = "graphgate.org/retryBackoffMs";
 
 fn get_label_value<'a>(meta: &'a ObjectMeta, name: &str) -> Option<&'a str> {
     meta.labels
@@ -45,15 +54,163 @@ fn get_gateway_or_default(gateway_name: &str) -> String {
     }
 }
 
-pub async fn find_graphql_services(gateway_name: &str) -> Result<ServiceRouteTable> {
+/// The port of a Kubernetes service the subgraph is served on.
+///
+/// The port named by the `graphgate.org/port` annotation, or else the port
+/// named `graphql`, or else the first port.
+fn service_port(service: &Service) -> Option<&ServicePort> {
+    let ports = service
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.ports.as_deref())
+        .unwrap_or_default();
+    if let Some(port) = get_annotation_value(&service.metadata, ANNOTATIONS_PORT) {
+        let found = ports
+            .iter()
+            .find(|service_port| service_port.name.as_deref() == Some(port) || service_port.port.to_string() == port);
+        if found.is_none() {
+            tracing::warn!(service = ?service.metadata.name, port = %port, "The annotated port is not a port of the service.");
+        }
+        return found;
+    }
+    if let Some(service_port) = ports
+        .iter()
+        .find(|service_port| service_port.name.as_deref() == Some(PORT_NAME_GRAPHQL))
+    {
+        return Some(service_port);
+    }
+    if ports.len() > 1 {
+        tracing::warn!(
+            service = ?service.metadata.name,
+            "The service has several ports, the first one is used. Name the port `{}` or annotate the service with `{}` to choose it.",
+            PORT_NAME_GRAPHQL,
+            ANNOTATIONS_PORT,
+        );
+    }
+    ports.first()
+}
+
+fn service_route(service: &Service, host: &str, service_port: &ServicePort) -> ServiceRoute {
+    let meta = &service.metadata;
+    ServiceRoute {
+        addr: format!("{}:{}", host, service_port.port),
+        tls: get_annotation_value(meta, ANNOTATIONS_TLS).is_some(),
+        query_path: get_annotation_value(meta, ANNOTATIONS_QUERY_PATH).map(ToString::to_string),
+        subscribe_path: get_annotation_value(meta, ANNOTATIONS_SUBSCRIBE_PATH).map(ToString::to_string),
+        introspection_path: get_annotation_value(meta, ANNOTATIONS_INTROSPECTION_PATH).map(ToString::to_string),
+        schema_url: None,
+        websocket_path: get_annotation_value(meta, ANNOTATIONS_WEBSOCKET_PATH).map(ToString::to_string),
+        websocket_protocol: get_annotation_value(meta, ANNOTATIONS_WEBSOCKET_PROTOCOL)
+            .and_then(|protocol| protocol.parse().ok()),
+        connection_params: Default::default(),
+        sdl_file: None,
+        headers: Default::default(),
+        header_policy: Default::default(),
+        user_agent: None,
+        oauth2: None,
+        signing: None,
+        enum_values: Default::default(),
+        lenient_errors: get_annotation_value(meta, ANNOTATIONS_LENIENT_ERRORS).is_some(),
+        timeout_ms: get_annotation_value(meta, ANNOTATIONS_TIMEOUT_MS).and_then(|timeout| timeout.parse().ok()),
+        retry_count: get_annotation_value(meta, ANNOTATIONS_RETRY_COUNT)
+            .and_then(|count| count.parse().ok())
+            .unwrap_or_default(),
+        retry_backoff_ms: get_annotation_value(meta, ANNOTATIONS_RETRY_BACKOFF_MS)
+            .and_then(|backoff| backoff.parse().ok())
+            .unwrap_or(ServiceRoute::DEFAULT_RETRY_BACKOFF_MS),
+        source: RouteSource::Kubernetes,
+    }
+}
+
+/// Create the route table of the labeled Kubernetes services.
+///
+/// When several Kubernetes services have the same `graphgate.org/service`
+/// label, the ones with ready endpoints are preferred, then the first by
+/// name, so that the route does not change between discoveries. The others
+/// are routed as `{service}-{name}` if `suffix_duplicates` is set, or else
+/// left out. `ready` holds the names of the services with ready endpoints,
+/// all services are considered ready if it is `None`.
+fn resolve_routes(services: &[Service], ready: Option<&HashSet<String>>, suffix_duplicates: bool) -> ServiceRouteTable {
+    let mut candidates: BTreeMap<&str, Vec<(&str, &Service)>> = BTreeMap::new();
+    for service in services {
+        if let Some((host, service_name)) = service
+            .metadata
+            .name
+            .as_deref()
+            .zip(get_label_value(&service.metadata, LABEL_GRAPHQL_SERVICE))
+        {
+            candidates.entry(service_name).or_default().push((host, service));
+        }
+    }
+
+    let is_ready = |host: &str| ready.map(|ready| ready.contains(host)).unwrap_or(true);
+    let mut route_table = ServiceRouteTable::default();
+    let mut duplicates = Vec::new();
+    for (service_name, mut hosts) in candidates {
+        hosts.sort_by_key(|(host, _)| (!is_ready(host), *host));
+        if hosts.len() > 1 {
+            let names = hosts.iter().map(|(host, _)| *host).collect::<Vec<_>>();
+            tracing::warn!(
+                service = %service_name,
+                chosen = %names[0],
+                services = ?names,
+                "Several Kubernetes services are labeled with the same service name."
+            );
+            record_route_conflict(service_name);
+        }
+
+        let mut hosts = hosts.into_iter();
+        if let Some((host, service)) = hosts.next() {
+            if let Some(service_port) = service_port(service) {
+                route_table.insert(service_name.to_string(), service_route(service, host, service_port));
+            }
+        }
+        if suffix_duplicates {
+            duplicates.extend(hosts.map(|(host, service)| (format!("{}-{}", service_name, host), host, service)));
+        }
+    }
+
+    // The suffixed names are routed unless they are taken by a labeled service.
+    for (name, host, service) in duplicates {
+        if route_table.contains_key(&name) {
+            tracing::warn!(service = %name, "The suffixed service name is already taken.");
+            continue;
+        }
+        if let Some(service_port) = service_port(service) {
+            route_table.insert(name, service_route(service, host, service_port));
+        }
+    }
+    route_table
+}
+
+/// The names of the Kubernetes services with ready endpoints.
+async fn ready_services(client: Client, namespace: &str, label: &str) -> Result<HashSet<String>> {
+    let endpoints_api: Api<Endpoints> = Api::namespaced(client, namespace);
+    let endpoints = endpoints_api
+        .list(&ListParams::default().labels(label))
+        .await
+        .context("Failed to call list endpoints api")?;
+    Ok(endpoints
+        .iter()
+        .filter(|endpoints| {
+            endpoints
+                .subsets
+                .iter()
+                .flatten()
+                .any(|subset| subset.addresses.as_ref().is_some_and(|addresses| !addresses.is_empty()))
+        })
+        .filter_map(|endpoints| endpoints.metadata.name.clone())
+        .collect())
+}
+
+pub async fn find_graphql_services(gateway_name: &str, suffix_duplicates: bool) -> Result<ServiceRouteTable> {
     tracing::trace!("Find GraphQL services.");
     let client = Client::try_default().await.context("Failed to create kube client.")?;
 
     let namespace = std::fs::read_to_string(NAMESPACE_PATH).unwrap_or_else(|_| "default".to_string());
     tracing::trace!(namespace = %namespace, "Get current namespace.");
 
-    let mut route_table = ServiceRouteTable::default();
-    let services_api: Api<Service> = Api::namespaced(client, &namespace);
+    let services_api: Api<Service> = Api::namespaced(client.clone(), &namespace);
 
     let label = get_gateway_or_default(gateway_name);
 
@@ -63,30 +220,116 @@ pub async fn find_graphql_services(gateway_name: &str) -> Result<ServiceRouteTab
         .await
         .context("Failed to call list services api")?;
 
-    for service in &services {
-        if let Some((host, service_name)) = service
-            .metadata
-            .name
-            .as_deref()
-            .zip(get_label_value(&service.metadata, LABEL_GRAPHQL_SERVICE))
-        {
-            for service_port in service.spec.iter().flat_map(|spec| spec.ports.iter()).flatten() {
-                let tls = get_annotation_value(&service.metadata, ANNOTATIONS_TLS).is_some();
-                let query_path = get_annotation_value(&service.metadata, ANNOTATIONS_QUERY_PATH);
-                let subscribe_path = get_annotation_value(&service.metadata, ANNOTATIONS_SUBSCRIBE_PATH);
-                let introspection_path = get_annotation_value(&service.metadata, ANNOTATIONS_INTROSPECTION_PATH);
-                let websocket_path = get_annotation_value(&service.metadata, ANNOTATIONS_WEBSOCKET_PATH);
-                route_table.insert(service_name.to_string(), ServiceRoute {
-                    addr: format!("{}:{}", host, service_port.port),
-                    tls,
-                    query_path: query_path.map(ToString::to_string),
-                    subscribe_path: subscribe_path.map(ToString::to_string),
-                    introspection_path: introspection_path.map(ToString::to_string),
-                    websocket_path: websocket_path.map(ToString::to_string),
-                });
-            }
+    // The endpoints only decide between the services with the same name.
+    let ready = match ready_services(client, &namespace, &label).await {
+        Ok(ready) => Some(ready),
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to find the ready services.");
+            None
+        },
+    };
+
+    Ok(resolve_routes(&services.items, ready.as_ref(), suffix_duplicates))
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::ServiceSpec;
+
+    use super::*;
+
+    fn service(name: &str, service_name: &str, ports: &[(&str, i32)]) -> Service {
+        Service {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                labels: Some([(LABEL_GRAPHQL_SERVICE.to_string(), service_name.to_string())].into()),
+                ..Default::default()
+            },
+            spec: Some(ServiceSpec {
+                ports: Some(
+                    ports
+                        .iter()
+                        .map(|(name, port)| ServicePort {
+                            name: Some(name.to_string()),
+                            port: *port,
+                            ..Default::default()
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
         }
     }
 
-    Ok(route_table)
+    fn addrs(route_table: &ServiceRouteTable) -> BTreeMap<&str, &str> {
+        route_table
+            .iter()
+            .map(|(name, route)| (name.as_str(), route.addr.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn prefer_ready_services() {
+        let services = [
+            service("accounts-v1", "accounts", &[("http", 8000)]),
+            service("accounts-v2", "accounts", &[("http", 8000)]),
+            service("reviews", "reviews", &[("http", 8000)]),
+        ];
+
+        let route_table = resolve_routes(&services, None, false);
+        assert_eq!(
+            addrs(&route_table),
+            [("accounts", "accounts-v1:8000"), ("reviews", "reviews:8000")].into()
+        );
+
+        let ready = ["accounts-v2".to_string(), "reviews".to_string()].into();
+        let route_table = resolve_routes(&services, Some(&ready), false);
+        assert_eq!(
+            addrs(&route_table),
+            [("accounts", "accounts-v2:8000"), ("reviews", "reviews:8000")].into()
+        );
+
+        // The order of the listed services does not change the route.
+        let reversed = services.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(resolve_routes(&reversed, Some(&ready), false), route_table);
+    }
+
+    #[test]
+    fn suffix_duplicates() {
+        let services = [
+            service("accounts-v2", "accounts", &[("http", 8000)]),
+            service("accounts-v1", "accounts", &[("http", 8000)]),
+        ];
+        let route_table = resolve_routes(&services, None, true);
+        assert_eq!(
+            addrs(&route_table),
+            [
+                ("accounts", "accounts-v1:8000"),
+                ("accounts-accounts-v2", "accounts-v2:8000")
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn choose_port() {
+        let mut annotated = service("reviews", "reviews", &[("metrics", 9000), ("http", 8001)]);
+        annotated.metadata.annotations = Some([(ANNOTATIONS_PORT.to_string(), "http".to_string())].into());
+        let services = [
+            service("accounts", "accounts", &[("metrics", 9000), ("graphql", 8000)]),
+            annotated,
+            service("products", "products", &[("http", 8002), ("metrics", 9000)]),
+        ];
+        let route_table = resolve_routes(&services, None, false);
+        assert_eq!(
+            addrs(&route_table),
+            [
+                ("accounts", "accounts:8000"),
+                ("products", "products:8002"),
+                ("reviews", "reviews:8001")
+            ]
+            .into()
+        );
+    }
 }
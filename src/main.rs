@@ -1,20 +1,49 @@
 #![forbid(unsafe_code)]
 
+mod check;
 mod config;
 mod k8s;
+mod migrate;
+mod openmetrics;
+mod plan;
+mod report;
+mod routes;
 
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::{Context, Result};
-use config::Config;
+use chrono::Utc;
+use config::{Config, StartupTimeoutAction};
 use futures_util::FutureExt;
+use graphgate_admin_client::{self as admin, Status};
 use graphgate_handler::{
     auth::{Auth, AuthError},
     handler,
-    handler::HandlerConfig,
+    handler::{HandlerConfig, RequestError},
+    with_cors,
+    Bucketing,
+    CacheKind,
+    CompositionStatus,
+    CorsPolicy,
+    GatewaySettings,
+    OperationLabeler,
+    PollingConfig,
+    RouteHealth,
+    RouteSource,
+    SafelistConfig,
+    ServiceRouteTable,
+    SharedConfig,
     SharedRouteTable,
 };
 use graphgate_planner::{Response, ServerError};
+use notify::{RecursiveMode, Watcher};
+use openmetrics::{accepts_openmetrics, openmetrics_registry, OPENMETRICS_CONTENT_TYPE};
 use opentelemetry::{
     global,
     global::GlobalTracerProvider,
@@ -22,10 +51,16 @@ use opentelemetry::{
     trace::noop::NoopTracerProvider,
 };
 use prometheus::{Encoder, Registry, TextEncoder};
-use tokio::{signal, time::Duration};
+use tokio::{signal, sync::watch, time::Duration};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use value::ConstValue;
-use warp::{http::Response as HttpResponse, hyper::StatusCode, Filter, Rejection, Reply};
+use warp::{
+    http::{header::CONTENT_TYPE, Response as HttpResponse},
+    hyper::{Body, StatusCode},
+    Filter,
+    Rejection,
+    Reply,
+};
 
 fn init_tracing() {
     tracing_subscriber::registry()
@@ -38,10 +73,14 @@ fn init_tracing() {
         .init();
 }
 
-async fn update_route_table_in_k8s(shared_route_table: SharedRouteTable, gateway_name: String) {
+async fn update_route_table_in_k8s(
+    shared_route_table: SharedRouteTable,
+    gateway_name: String,
+    suffix_duplicates: bool,
+) {
     let mut prev_route_table = None;
     loop {
-        match k8s::find_graphql_services(&gateway_name).await {
+        match k8s::find_graphql_services(&gateway_name, suffix_duplicates).await {
             Ok(route_table) => {
                 if Some(&route_table) != prev_route_table.as_ref() {
                     tracing::info!(route_table = ?route_table, "Route table updated.");
@@ -58,6 +97,124 @@ async fn update_route_table_in_k8s(shared_route_table: SharedRouteTable, gateway
     }
 }
 
+/// Probe the reference resolvers of the subgraphs every `interval`, besides
+/// the probes after compositions.
+async fn check_entity_resolvers(shared_route_table: SharedRouteTable, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        shared_route_table.check_entity_resolvers().await;
+    }
+}
+
+/// The settings read by the filters on every request.
+async fn gateway_settings(config: &Config) -> Result<GatewaySettings> {
+    let auth = match config.authorization.clone() {
+        Some(_) if config.watch => {
+            tracing::warn!("Authorization is disabled in watch mode.");
+            Arc::new(Auth::default())
+        },
+        Some(auth_config) => Arc::new(Auth::try_new(auth_config).await?),
+        None => Arc::new(Auth::default()),
+    };
+    Ok(GatewaySettings {
+        forward_headers: config.forward_headers.clone(),
+        forward_connection_params: config.forward_connection_params.clone(),
+        auth,
+        cors: CorsPolicy::new(config.cors.as_ref()),
+    })
+}
+
+/// How long the changes of the config file settle before it is reloaded, as
+/// editors write it in several steps.
+const CONFIG_RELOAD_DELAY: Duration = Duration::from_millis(200);
+
+/// Apply the changes of the config file at `path` to the settings of the
+/// filters and, if the routes are configured in the file, to the route table,
+/// without restarting.
+///
+/// The other settings are only read at startup, and invalid changes are
+/// logged and ignored.
+async fn reload_config(
+    path: PathBuf,
+    shared_config: SharedConfig,
+    shared_route_table: SharedRouteTable,
+    mut route_table: Option<ServiceRouteTable>,
+) {
+    // The directory of the file is watched, as editors and Kubernetes
+    // replace the file instead of writing it.
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if res.is_ok_and(|event| !event.kind.is_access()) {
+            let _ = tx.send(());
+        }
+    })
+    .and_then(|mut watcher| {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    let _watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            tracing::error!(path = %path.display(), error = %err, "Failed to watch the config file.");
+            return;
+        },
+    };
+
+    let mut contents = tokio::fs::read_to_string(&path).await.ok();
+    while rx.recv().await.is_some() {
+        tokio::time::sleep(CONFIG_RELOAD_DELAY).await;
+        while rx.try_recv().is_ok() {}
+        let current = tokio::fs::read_to_string(&path).await.ok();
+        if current.is_none() || current == contents {
+            continue;
+        }
+        contents = current;
+
+        let config = match Config::try_parse() {
+            Ok(config) => config,
+            Err(err) => {
+                tracing::error!(error = %format!("{:#}", err), "Failed to reload the config file.");
+                continue;
+            },
+        };
+        match gateway_settings(&config).await {
+            Ok(settings) => shared_config.store(settings),
+            Err(err) => {
+                tracing::error!(error = %format!("{:#}", err), "Failed to reload the config file.");
+                continue;
+            },
+        }
+        if let Some(route_table) = &mut route_table {
+            let new_route_table = config.create_route_table();
+            if new_route_table != *route_table {
+                tracing::info!(route_table = ?new_route_table, "Route table updated.");
+                shared_route_table.set_route_table(new_route_table.clone());
+                *route_table = new_route_table;
+            }
+        }
+        tracing::info!(path = %path.display(), "Config file reloaded.");
+    }
+}
+
+/// How often the SDLs are checked for changes in watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Print the outcome of schema compositions to the console in watch mode.
+async fn report_compositions(mut composition: watch::Receiver<CompositionStatus>) {
+    while composition.changed().await.is_ok() {
+        let status = composition.borrow_and_update().clone();
+        match status {
+            CompositionStatus::Pending => {},
+            CompositionStatus::Composed(_) => eprintln!("\x1b[1;32m✔ Schema composed.\x1b[0m"),
+            CompositionStatus::Failed(err) => eprintln!("\x1b[1;31m✘ Composition failed:\x1b[0m {}", err),
+        }
+    }
+}
+
 fn init_tracer(config: &Config) -> Result<GlobalTracerProvider> {
     fn default_provider() -> GlobalTracerProvider {
         let provider = NoopTracerProvider::new();
@@ -88,28 +245,220 @@ fn init_tracer(config: &Config) -> Result<GlobalTracerProvider> {
     Ok(uninstall)
 }
 
-pub fn metrics(registry: Registry) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
-    warp::path!("metrics").and(warp::get()).map({
+/// The routes of the route table at `/routes`.
+async fn admin_routes(shared_route_table: &SharedRouteTable) -> Vec<admin::Route> {
+    shared_route_table
+        .routes()
+        .await
+        .into_iter()
+        .map(|route| {
+            let (health, error) = match route.health {
+                RouteHealth::Unknown => (admin::RouteHealth::Unknown, None),
+                RouteHealth::Healthy => (admin::RouteHealth::Healthy, None),
+                RouteHealth::Unhealthy(err) => (admin::RouteHealth::Unhealthy, Some(err)),
+            };
+            admin::Route {
+                service: route.service,
+                url: route.url,
+                websocket_url: route.websocket_url,
+                source: match route.source {
+                    RouteSource::Config => admin::RouteSource::Config,
+                    RouteSource::Kubernetes => admin::RouteSource::Kubernetes,
+                    RouteSource::Snapshot => admin::RouteSource::Snapshot,
+                    RouteSource::Supergraph => admin::RouteSource::Supergraph,
+                },
+                health,
+                error,
+                sdl_hash: route.sdl_hash,
+            }
+        })
+        .collect()
+}
+
+/// The number of most hit entries of each cache listed by `/caches`,
+/// unless set by the `top` parameter.
+const DEFAULT_TOP_CACHE_ENTRIES: usize = 10;
+
+/// The usage of the caches at `/caches`, and the eviction of their entries
+/// by `DELETE /caches/<cache>?key=<key>`.
+fn caches(shared_route_table: SharedRouteTable) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let stats = warp::path!("caches")
+        .and(warp::get())
+        .and(warp::query::<HashMap<String, String>>())
+        .map({
+            let shared_route_table = shared_route_table.clone();
+            move |params: HashMap<String, String>| {
+                let top = params
+                    .get("top")
+                    .and_then(|top| top.parse().ok())
+                    .unwrap_or(DEFAULT_TOP_CACHE_ENTRIES);
+                let caches = shared_route_table
+                    .cache_stats(top)
+                    .into_iter()
+                    .map(|(kind, stats)| admin::Cache {
+                        name: kind.name().to_string(),
+                        entries: stats.entries,
+                        memory_bytes: stats.memory_bytes,
+                        hits: stats.hits,
+                        misses: stats.misses,
+                        hit_ratio: stats.hit_ratio(),
+                        hottest: stats
+                            .hottest
+                            .into_iter()
+                            .map(|entry| admin::CacheEntry {
+                                key: entry.key,
+                                hits: entry.hits,
+                            })
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>();
+                warp::reply::json(&caches)
+            }
+        });
+    let evict = warp::path!("caches" / String)
+        .and(warp::delete())
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(move |name: String, params: HashMap<String, String>| {
+            let shared_route_table = shared_route_table.clone();
+            async move {
+                let (kind, key) = match (CacheKind::from_name(&name), params.get("key")) {
+                    (Some(kind), Some(key)) => (kind, key),
+                    (None, _) => {
+                        let message = format!("Unknown cache '{}'.", name);
+                        return Ok::<_, Infallible>(warp::reply::with_status(
+                            warp::reply::json(&message),
+                            StatusCode::NOT_FOUND,
+                        ));
+                    },
+                    (_, None) => {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&"The key of the entry is missing."),
+                            StatusCode::BAD_REQUEST,
+                        ))
+                    },
+                };
+                Ok(match shared_route_table.evict_cache_entry(kind, key).await {
+                    Ok(true) => warp::reply::with_status(warp::reply::json(&"evicted"), StatusCode::OK),
+                    Ok(false) => warp::reply::with_status(warp::reply::json(&"not found"), StatusCode::NOT_FOUND),
+                    Err(err) => {
+                        tracing::error!(error = %err, cache = kind.name(), "Failed to evict a cache entry.");
+                        warp::reply::with_status(warp::reply::json(&err.to_string()), StatusCode::INTERNAL_SERVER_ERROR)
+                    },
+                })
+            }
+        });
+    stats.or(evict)
+}
+
+/// The SDLs of the services the schema is composed from at `/schema`.
+fn schema(shared_route_table: SharedRouteTable) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("schema").and(warp::get()).and_then({
         move || {
-            let mut buffer = Vec::new();
-            let encoder = TextEncoder::new();
-            let metric_families = registry.gather();
-            if let Err(err) = encoder.encode(&metric_families, &mut buffer) {
-                return HttpResponse::builder()
-                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                    .body(err.to_string().into_bytes())
-                    .unwrap();
+            let shared_route_table = shared_route_table.clone();
+            async move {
+                let sdls = shared_route_table
+                    .sdls()
+                    .await
+                    .into_iter()
+                    .map(|(service, sdl)| admin::ServiceSdl { service, sdl })
+                    .collect::<Vec<_>>();
+                Ok::<_, Infallible>(warp::reply::json(&sdls))
             }
-            HttpResponse::builder().status(StatusCode::OK).body(buffer).unwrap()
         }
     })
 }
 
+/// The composition of the schema without waiting for the next update, by
+/// `POST /refresh`.
+fn refresh(shared_route_table: SharedRouteTable) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("refresh").and(warp::post()).map(move || {
+        shared_route_table.refresh();
+        warp::reply::with_status(warp::reply::json(&"refreshing"), StatusCode::ACCEPTED)
+    })
+}
+
+/// The blocked operations at `/blocked-operations`, blocked by
+/// `PUT /blocked-operations/<sha256>` and unblocked by
+/// `DELETE /blocked-operations/<sha256>`.
+fn blocked_operations(
+    shared_route_table: SharedRouteTable,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let list = warp::path!("blocked-operations").and(warp::get()).map({
+        let shared_route_table = shared_route_table.clone();
+        move || warp::reply::json(&shared_route_table.blocked_operations())
+    });
+    let block = warp::path!("blocked-operations" / String).and(warp::put()).map({
+        let shared_route_table = shared_route_table.clone();
+        move |hash: String| {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return warp::reply::with_status(
+                    warp::reply::json(&"The hash is not a SHA-256 hash."),
+                    StatusCode::BAD_REQUEST,
+                );
+            }
+            shared_route_table.block_operation(&hash);
+            warp::reply::with_status(warp::reply::json(&"blocked"), StatusCode::OK)
+        }
+    });
+    let unblock = warp::path!("blocked-operations" / String)
+        .and(warp::delete())
+        .map(move |hash: String| match shared_route_table.unblock_operation(&hash) {
+            true => warp::reply::with_status(warp::reply::json(&"unblocked"), StatusCode::OK),
+            false => warp::reply::with_status(warp::reply::json(&"not found"), StatusCode::NOT_FOUND),
+        });
+    list.or(block).or(unblock)
+}
+
+/// Serves the metrics in the Prometheus text format, or in the OpenMetrics
+/// text format with the exemplars of the latency histograms if the client
+/// accepts it.
+pub fn metrics(registry: Registry) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let openmetrics = Arc::new(openmetrics_registry(registry.clone()));
+    warp::path!("metrics")
+        .and(warp::get())
+        .and(warp::header::optional::<String>("accept"))
+        .map({
+            move |accept: Option<String>| {
+                if accepts_openmetrics(accept.as_deref()) {
+                    let mut body = String::new();
+                    if let Err(err) = prometheus_client::encoding::text::encode(&mut body, &openmetrics) {
+                        return HttpResponse::builder()
+                            .status(StatusCode::INTERNAL_SERVER_ERROR)
+                            .body(Body::from(err.to_string()))
+                            .unwrap();
+                    }
+                    return HttpResponse::builder()
+                        .status(StatusCode::OK)
+                        .header(CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)
+                        .body(Body::from(body))
+                        .unwrap();
+                }
+
+                let metric_families = registry.gather();
+                let mut buffer = Vec::new();
+                let encoder = TextEncoder::new();
+                if let Err(err) = encoder.encode(&metric_families, &mut buffer) {
+                    return HttpResponse::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(err.to_string().into())
+                        .unwrap();
+                }
+                HttpResponse::builder()
+                    .status(StatusCode::OK)
+                    .header(CONTENT_TYPE, encoder.format_type())
+                    .body(buffer.into())
+                    .unwrap()
+            }
+        })
+}
+
 async fn handle_rejection(err: Rejection) -> std::result::Result<impl Reply, Infallible> {
     let (code, message) = if err.is_not_found() {
         (StatusCode::OK, "Not Found".to_string())
     } else if let Some(e) = err.find::<AuthError>() {
         (StatusCode::OK, e.to_string())
+    } else if let Some(e) = err.find::<RequestError>() {
+        (StatusCode::BAD_REQUEST, e.to_string())
     } else {
         tracing::error!("unhandled error: {:?}", err);
         (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error".to_string())
@@ -128,8 +477,20 @@ async fn handle_rejection(err: Rejection) -> std::result::Result<impl Reply, Inf
 #[tokio::main]
 async fn main() -> Result<()> {
     init_tracing();
+    graphgate_handler::install_panic_hook();
 
+    let started_at = Utc::now();
     let config = Config::try_parse()?;
+    if config.config_schema {
+        println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+        return Ok(());
+    }
+    if let Some(url) = &config.routes {
+        return routes::print_routes(url).await;
+    }
+    if let Some(path) = &config.migrate_sdl {
+        return migrate::migrate_sdl(path, config.migrate_sdl_out.as_deref());
+    }
     let _uninstall = init_tracer(&config)?;
     let registry = Registry::new();
     let exporter = opentelemetry_prometheus::exporter()
@@ -138,67 +499,311 @@ async fn main() -> Result<()> {
     let meter_provider = MeterProvider::builder().with_reader(exporter).build();
     global::set_meter_provider(meter_provider);
 
+    if config.check {
+        let mut route_table = config.create_route_table();
+        route_table.apply_aliases(&config.service_aliases);
+        route_table.apply_request_config(&config.subgraph_request_config());
+        if !check::check(&route_table, &config.composition.clone().unwrap_or_default()).await {
+            anyhow::bail!("The schema does not compose.");
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = &config.plan {
+        let mut route_table = config.create_route_table();
+        route_table.apply_aliases(&config.service_aliases);
+        route_table.apply_request_config(&config.subgraph_request_config());
+        return plan::print_plan(
+            &route_table,
+            &config.composition.clone().unwrap_or_default(),
+            path,
+            config.plan_operation_name.as_deref(),
+            config.plan_variables.as_deref(),
+            config.plan_format,
+        )
+        .await;
+    }
+
     let mut shared_route_table = SharedRouteTable::default();
+    if let Some(defer_config) = config.defer.clone() {
+        shared_route_table.set_defer_config(defer_config);
+    }
+    if let Some(pagination_config) = config.pagination.clone() {
+        shared_route_table.set_pagination_config(pagination_config);
+    }
+    if let Some(cost_config) = config.cost.clone() {
+        shared_route_table.set_cost_config(cost_config);
+    }
+    if let Some(deprecation_config) = config.deprecation.clone() {
+        shared_route_table.set_deprecation_config(deprecation_config);
+    }
+    if let Some(audit_config) = config.audit.clone() {
+        shared_route_table
+            .set_audit_config(audit_config)
+            .context("Failed to open the audit file.")?;
+    }
+    if let Some(rate_limit_config) = config.rate_limit.clone() {
+        shared_route_table.set_rate_limit_config(rate_limit_config);
+    }
+    if let Some(circuit_breaker_config) = config.circuit_breaker.clone() {
+        shared_route_table.set_circuit_breaker_config(circuit_breaker_config);
+    }
+    if let Some(upstream_config) = config.upstream.clone() {
+        shared_route_table.set_upstream_config(upstream_config);
+    }
+    if let Some(parallelism_config) = config.parallelism.clone() {
+        shared_route_table.set_parallelism_config(parallelism_config);
+    }
+    if let Some(connection_config) = config.connections.clone() {
+        shared_route_table.set_connection_config(connection_config);
+    }
+    if let Some(response_limit_config) = config.response_limit.clone() {
+        shared_route_table.set_response_limit_config(response_limit_config);
+    }
+    if let Some(call_budget_config) = config.call_budget.clone() {
+        shared_route_table.set_call_budget_config(call_budget_config);
+    }
+    if let Some(operation_limits_config) = config.operation_limits.clone() {
+        shared_route_table.set_operation_limits_config(operation_limits_config);
+    }
+    if let Some(chaos_config) = config.chaos.clone().filter(|chaos| chaos.enabled) {
+        tracing::warn!(faults = chaos_config.faults.len(), "Fault injection is enabled.");
+        shared_route_table.set_chaos_config(chaos_config);
+    }
+    if let Some(safelist) = config
+        .safelist
+        .as_ref()
+        .map(SafelistConfig::load)
+        .transpose()?
+        .flatten()
+    {
+        tracing::info!(
+            operations = safelist.len(),
+            "Only the safelisted operations are accepted."
+        );
+        shared_route_table.set_safelist(safelist);
+    }
+    if let Some(introspection_config) = config.introspection.clone() {
+        shared_route_table.set_introspection_config(introspection_config);
+    }
+    if let Some(bucketing_config) = config.bucketing.clone() {
+        shared_route_table.set_bucketing(Bucketing::new(bucketing_config));
+    }
+    if let Some(persisted_query_config) = &config.persisted_queries {
+        shared_route_table.set_persisted_query_cache(persisted_query_config.create_cache()?);
+    }
+    if let Some(entity_cache_config) = &config.entity_cache {
+        shared_route_table.set_entity_cache(entity_cache_config.create_cache()?);
+        shared_route_table.set_entity_cache_partition_claims(entity_cache_config.partition_claims.clone());
+        shared_route_table.set_entity_cache_credential_headers(entity_cache_config.credential_headers.clone());
+    }
+    shared_route_table.set_debug_errors(config.debug_errors);
+    shared_route_table.set_normalize_error_paths(config.normalize_error_paths);
+    shared_route_table.set_check_entity_keys(config.check_entity_keys);
+    shared_route_table.set_allowed_services_extension(config.allowed_services_extension);
+    shared_route_table.set_propagate_subgraph_status(config.propagate_subgraph_status);
+    shared_route_table.set_server_timing(config.server_timing);
+    shared_route_table.set_disable_introspection(config.disable_introspection);
+    shared_route_table.set_trace_response_headers(config.trace_response_headers.clone());
+    if let Some(polling_config) = config.polling.clone() {
+        shared_route_table.set_polling_config(polling_config);
+    }
+    if config.watch {
+        shared_route_table.set_polling_config(PollingConfig::fixed(WATCH_INTERVAL));
+        tokio::spawn(report_compositions(shared_route_table.watch_composition()));
+    }
+    if let Some(composition_config) = config.composition.clone() {
+        shared_route_table.set_composition_config(composition_config);
+    }
+    if let Some(entity_check_config) = config.entity_check.clone() {
+        if entity_check_config.interval_secs > 0 {
+            tokio::spawn(check_entity_resolvers(
+                shared_route_table.clone(),
+                Duration::from_secs(entity_check_config.interval_secs),
+            ));
+        }
+        shared_route_table.set_entity_check_config(entity_check_config);
+    }
+    shared_route_table.set_context_rules(config.context.clone());
+    shared_route_table.set_redaction_rules(config.redaction.clone());
+    shared_route_table.set_service_aliases(config.service_aliases.clone());
+    shared_route_table.set_subgraph_request_config(config.subgraph_request_config());
+    if let Some(path) = &config.persisted_operations {
+        let manifest = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read persisted operations from '{}'.", path.display()))?;
+        let operations = graphgate_handler::parse_manifest(&manifest)
+            .with_context(|| format!("Invalid persisted operation manifest '{}'.", path.display()))?;
+        shared_route_table.set_persisted_operations(operations);
+    }
+    if let Some(verification_config) = config.verification.clone() {
+        verification_config.validate()?;
+        shared_route_table.set_verification_config(verification_config);
+    }
+    if let Some(path) = &config.schema_snapshot {
+        shared_route_table.set_snapshot_path(path.clone());
+        match shared_route_table.load_snapshot().await {
+            Ok(true) => tracing::info!(path = %path.display(), "Serving the schema snapshot."),
+            Ok(false) => {},
+            Err(err) => tracing::warn!(error = %err, "Failed to load the schema snapshot."),
+        }
+    }
 
-    if !config.services.is_empty() {
+    let reloaded_route_table =
+        (config.supergraph.is_none() && !config.services.is_empty()).then(|| config.create_route_table());
+    if let Some(path) = &config.supergraph {
+        tracing::info!(path = %path.display(), "Route table in the supergraph.");
+        shared_route_table.set_supergraph_path(path.clone());
+        shared_route_table.set_receive_headers(config.receive_headers.clone());
+    } else if !config.services.is_empty() {
         tracing::info!("Route table in the configuration file.");
         shared_route_table.set_route_table(config.create_route_table());
-        shared_route_table.set_receive_headers(config.receive_headers);
+        shared_route_table.set_receive_headers(config.receive_headers.clone());
     } else if std::env::var("KUBERNETES_SERVICE_HOST").is_ok() {
         tracing::info!("Route table within the current namespace in Kubernetes cluster.");
-        shared_route_table.set_receive_headers(config.receive_headers);
+        shared_route_table.set_receive_headers(config.receive_headers.clone());
         tokio::spawn(update_route_table_in_k8s(
             shared_route_table.clone(),
             config.gateway_name.clone(),
+            config.k8s_suffix_duplicates,
         ));
     } else {
         tracing::info!("Route table is empty.");
         return Ok(());
     }
 
+    let mut startup_config = config.startup.clone().unwrap_or_default();
+    if config.watch {
+        // Composition errors are reported until they are fixed.
+        startup_config.on_timeout = StartupTimeoutAction::Serve;
+    }
+    tracing::info!(timeout_secs = startup_config.timeout_secs, "Waiting for the schema.");
+    if !shared_route_table
+        .wait_ready(Duration::from_secs(startup_config.timeout_secs))
+        .await
+    {
+        match startup_config.on_timeout {
+            StartupTimeoutAction::Serve => {
+                tracing::warn!("No schema composed before the startup timeout, serving anyway.");
+            },
+            StartupTimeoutAction::Fail => {
+                anyhow::bail!(
+                    "No schema composed within {} seconds after startup.",
+                    startup_config.timeout_secs
+                );
+            },
+        }
+    }
+
+    if let Some((composed_schema, _)) = shared_route_table.get().await {
+        let mut redaction_rules = config.redaction.clone();
+        redaction_rules.retain(|rule| match rule.validate(&composed_schema) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::error!(error = %err, "Invalid redaction rule, it is ignored.");
+                false
+            },
+        });
+        shared_route_table.set_redaction_rules(redaction_rules);
+    }
+
+    let shared_config = SharedConfig::new(gateway_settings(&config).await?);
+    if Path::exists(&config.file) {
+        tokio::spawn(reload_config(
+            config.file.clone(),
+            shared_config.clone(),
+            shared_route_table.clone(),
+            reloaded_route_table,
+        ));
+    }
     let handler_config = HandlerConfig {
         shared_route_table,
-        forward_headers: Arc::new(config.forward_headers),
-    };
-
-    let auth: Arc<Auth> = match config.authorization {
-        Some(config) => Arc::new(Auth::try_new(config).await?),
-        None => Arc::new(Auth::default()),
+        shared_config: shared_config.clone(),
+        operation_labeler: Arc::new(OperationLabeler::new(
+            config.operation_labels.clone().unwrap_or_default(),
+        )),
     };
 
-    let cors = match config.cors {
-        Some(cors_config) => warp::cors()
-            .allow_methods(
-                cors_config
-                    .allow_methods
-                    .unwrap_or(vec!["POST".to_string(), "OPTIONS".to_string()])
-                    .iter()
-                    .map(|s| s as &str)
-                    .collect::<Vec<&str>>(),
-            )
-            .allow_credentials(cors_config.allow_credentials.unwrap_or(false))
-            .allow_headers(cors_config.allow_headers.unwrap_or_default())
-            .allow_origins(
-                cors_config
-                    .allow_origins
-                    .unwrap_or_default()
-                    .iter()
-                    .map(|s| s as &str)
-                    .collect::<Vec<&str>>(),
-            )
-            .build(),
-        None => warp::cors()
-            .allow_any_origin()
-            .allow_methods(vec!["POST", "OPTIONS"])
-            .build(),
-    };
+    let docs_config = config.docs.clone().unwrap_or_default();
+    if docs_config.enabled && docs_config.require_auth && !shared_config.load().auth.config.enabled {
+        tracing::warn!("The schema docs require authorization, but authorization is disabled.");
+    }
 
-    let graphql = warp::path::end().and(
-        handler::graphql_request(auth.clone(), handler_config.clone())
-            .or(handler::graphql_websocket(auth, handler_config.clone()))
-            .or(handler::graphql_playground(config.path.clone())),
+    let graphql = warp::path::end()
+        .and(
+            handler::graphql_request(handler_config.clone())
+                .or(handler::graphql_websocket(handler_config.clone()))
+                .or(handler::graphql_playground(config.path.clone(), config.watch)),
+        )
+        .boxed();
+    let watch = handler::graphql_watch(handler_config.shared_route_table.clone(), config.watch);
+    let docs = handler::graphql_docs(
+        shared_config.clone(),
+        docs_config,
+        handler_config.shared_route_table.clone(),
     );
+    let explain = handler::graphql_explain(handler_config.clone(), config.explain);
     let health = warp::path!("health").map(|| warp::reply::json(&"healthy"));
+    let ready = warp::path!("ready").map({
+        let shared_route_table = handler_config.shared_route_table.clone();
+        move || match shared_route_table.is_ready() {
+            true => warp::reply::with_status(warp::reply::json(&"ready"), StatusCode::OK),
+            false => warp::reply::with_status(warp::reply::json(&"not ready"), StatusCode::SERVICE_UNAVAILABLE),
+        }
+    });
+    let status = warp::path!("status").map({
+        let shared_route_table = handler_config.shared_route_table.clone();
+        move || {
+            warp::reply::json(&Status {
+                ready: shared_route_table.is_ready(),
+                entity_resolver_errors: shared_route_table
+                    .entity_resolver_errors()
+                    .into_iter()
+                    .map(|err| admin::EntityResolverError {
+                        service: err.service,
+                        type_name: err.type_name,
+                        message: err.message,
+                    })
+                    .collect(),
+                subgraphs: shared_route_table
+                    .subgraph_schemas()
+                    .into_iter()
+                    .map(|subgraph| admin::SubgraphSchema {
+                        service: subgraph.service,
+                        sdl_hash: subgraph.sdl_hash,
+                        fetched_at: subgraph.fetched_at,
+                    })
+                    .collect(),
+            })
+        }
+    });
+    let route_table = warp::path!("routes").and_then({
+        let shared_route_table = handler_config.shared_route_table.clone();
+        move || {
+            let shared_route_table = shared_route_table.clone();
+            async move { Ok::<_, Infallible>(warp::reply::json(&admin_routes(&shared_route_table).await)) }
+        }
+    });
+    let caches = caches(handler_config.shared_route_table.clone());
+    let schema = schema(handler_config.shared_route_table.clone());
+    let refresh = refresh(handler_config.shared_route_table.clone());
+    let blocked_operations = blocked_operations(handler_config.shared_route_table.clone());
+    let config = Arc::new(config);
+    let startup_report = warp::path!("startup-report").and_then({
+        let config = config.clone();
+        let shared_route_table = handler_config.shared_route_table.clone();
+        move || {
+            let config = config.clone();
+            let shared_route_table = shared_route_table.clone();
+            async move {
+                let report = report::startup_report(&config, started_at, admin_routes(&shared_route_table).await);
+                Ok::<_, Infallible>(warp::reply::json(&report))
+            }
+        }
+    });
+    let config_schema = warp::path!("config-schema").map({
+        let schema = Config::json_schema();
+        move || warp::reply::json(&schema)
+    });
     let preflight_request = warp::options().map(warp::reply);
 
     let bind_addr: SocketAddr = config
@@ -206,15 +811,56 @@ async fn main() -> Result<()> {
         .parse()
         .context(format!("Failed to parse bind addr '{}'", config.bind))?;
 
+    // The read-only admin endpoints are served with the GraphQL traffic,
+    // unless there is an admin address.
+    let status_routes = status.or(route_table).or(config_schema).or(startup_report).or(schema);
+    let public_status = config.admin_bind.is_none();
+    let public_status_routes = warp::any()
+        .and_then(move || async move {
+            match public_status {
+                true => Ok(()),
+                false => Err(warp::reject::not_found()),
+            }
+        })
+        .untuple_one()
+        .and(status_routes.clone());
+
     let routes = graphql
+        .or(watch)
         .or(health)
+        .or(ready)
+        .or(docs)
+        .or(explain)
         .or(metrics(registry))
-        .or(preflight_request)
-        .with(cors)
-        .recover(handle_rejection);
+        .or(public_status_routes)
+        .or(preflight_request);
+    let routes = with_cors(shared_config, routes).recover(handle_rejection);
     let (addr, server) = warp::serve(routes).bind_with_graceful_shutdown(bind_addr, signal::ctrl_c().map(|_| ()));
 
+    // The endpoints that refresh the schema, block operations and evict the
+    // cached entities are only served apart from the GraphQL traffic.
+    if let Some(admin_bind) = &config.admin_bind {
+        let admin_addr: SocketAddr = admin_bind
+            .parse()
+            .context(format!("Failed to parse admin bind addr '{}'", admin_bind))?;
+        let admin_routes = status_routes
+            .or(refresh)
+            .or(blocked_operations)
+            .or(caches)
+            .recover(handle_rejection);
+        let (admin_addr, admin_server) =
+            warp::serve(admin_routes).bind_with_graceful_shutdown(admin_addr, signal::ctrl_c().map(|_| ()));
+        tracing::info!(addr = %admin_addr, "Serving the admin endpoints");
+        tokio::spawn(admin_server);
+    }
+
     tracing::info!(addr = %addr, "Listening");
+    let report = report::startup_report(
+        &config,
+        started_at,
+        admin_routes(&handler_config.shared_route_table).await,
+    );
+    println!("{}", serde_json::to_string(&report)?);
     server.await;
     tracing::info!("Server shutdown");
 
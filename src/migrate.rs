@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Translate the legacy SDL at `path` into the Federation 2 SDL of each
+/// service, printing them or writing them to `<service>.graphql` files in
+/// `out_dir`.
+pub fn migrate_sdl(path: &Path, out_dir: Option<&Path>) -> Result<()> {
+    let sdl = std::fs::read_to_string(path).with_context(|| format!("Failed to read '{}'.", path.display()))?;
+    let subgraphs = graphgate_schema::translate_legacy_sdl(&sdl)
+        .with_context(|| format!("Failed to parse the legacy SDL of '{}'.", path.display()))?;
+
+    match out_dir {
+        Some(out_dir) => {
+            std::fs::create_dir_all(out_dir)
+                .with_context(|| format!("Failed to create directory '{}'.", out_dir.display()))?;
+            for subgraph in subgraphs {
+                let path = out_dir.join(format!("{}.graphql", subgraph.service));
+                std::fs::write(&path, subgraph.sdl)
+                    .with_context(|| format!("Failed to write '{}'.", path.display()))?;
+                println!("{}", path.display());
+            }
+        },
+        None => {
+            for (i, subgraph) in subgraphs.into_iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("# {}", subgraph.service);
+                print!("{}", subgraph.sdl);
+            }
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashSet, fmt};
+
+use graphgate_handler::{escape_label_value, latency_histogram_names, register_latency_histograms};
+use prometheus::proto::{Metric, MetricFamily, MetricType as FamilyType};
+use prometheus_client::{
+    collector::Collector,
+    encoding::{DescriptorEncoder, MetricEncoder, NoLabelSet},
+    metrics::MetricType,
+    registry::Registry,
+};
+
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Returns `true` if the `Accept` header allows the OpenMetrics text format.
+pub fn accepts_openmetrics(accept: Option<&str>) -> bool {
+    accept
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media_type| media_type.trim().starts_with("application/openmetrics-text"))
+        })
+        .unwrap_or_default()
+}
+
+/// The registry of the OpenMetrics exposition: the latency histograms with
+/// their exemplars, and the other metrics gathered by the Prometheus
+/// `registry`.
+pub fn openmetrics_registry(registry: prometheus::Registry) -> Registry {
+    let mut openmetrics = Registry::default();
+    register_latency_histograms(&mut openmetrics);
+    openmetrics.register_collector(Box::new(GatheredMetrics {
+        registry,
+        skip: latency_histogram_names().into(),
+    }));
+    openmetrics
+}
+
+/// Collects the metric families gathered by a Prometheus registry, except
+/// those registered natively.
+#[derive(Debug)]
+struct GatheredMetrics {
+    registry: prometheus::Registry,
+    skip: HashSet<String>,
+}
+
+impl Collector for GatheredMetrics {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> fmt::Result {
+        for family in self.registry.gather() {
+            if !self.skip.contains(family.get_name()) {
+                encode_family(&mut encoder, &family)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn encode_family(encoder: &mut DescriptorEncoder, family: &MetricFamily) -> fmt::Result {
+    let name = family.get_name();
+    // The samples of counters have the `_total` suffix, their family does not.
+    let (name, ty) = match family.get_field_type() {
+        FamilyType::COUNTER => (name.strip_suffix("_total").unwrap_or(name), MetricType::Counter),
+        FamilyType::GAUGE => (name, MetricType::Gauge),
+        FamilyType::HISTOGRAM => (name, MetricType::Histogram),
+        FamilyType::UNTYPED => (name, MetricType::Unknown),
+        // The OpenTelemetry exporter exports no summaries.
+        FamilyType::SUMMARY => return Ok(()),
+    };
+    let mut family_encoder = encoder.encode_descriptor(name, family.get_help(), None, ty)?;
+    for metric in family.get_metric() {
+        let labels = metric
+            .get_label()
+            .iter()
+            .map(|label| (label.get_name(), escape_label_value(label.get_value())))
+            .collect::<Vec<_>>();
+        encode_metric(family_encoder.encode_family(&labels)?, family.get_field_type(), metric)?;
+    }
+    Ok(())
+}
+
+fn encode_metric(mut encoder: MetricEncoder, ty: FamilyType, metric: &Metric) -> fmt::Result {
+    match ty {
+        FamilyType::COUNTER => encoder.encode_counter::<NoLabelSet, _, f64>(&metric.get_counter().get_value(), None),
+        FamilyType::GAUGE => encoder.encode_gauge(&metric.get_gauge().get_value()),
+        FamilyType::UNTYPED => encoder.encode_gauge(&metric.get_untyped().get_value()),
+        FamilyType::HISTOGRAM => {
+            let histogram = metric.get_histogram();
+            // The buckets are gathered with cumulative counts, and are encoded
+            // with the count of each, the last one being `+Inf`.
+            let mut cumulative_count = 0;
+            let mut buckets = Vec::with_capacity(histogram.get_bucket().len() + 1);
+            for bucket in histogram.get_bucket() {
+                let upper_bound = match bucket.get_upper_bound() {
+                    upper_bound if upper_bound == f64::INFINITY => f64::MAX,
+                    upper_bound => upper_bound,
+                };
+                buckets.push((upper_bound, bucket.get_cumulative_count() - cumulative_count));
+                cumulative_count = bucket.get_cumulative_count();
+            }
+            if buckets.last().map(|(upper_bound, _)| *upper_bound) != Some(f64::MAX) {
+                buckets.push((f64::MAX, histogram.get_sample_count() - cumulative_count));
+            }
+            encoder.encode_histogram::<NoLabelSet>(
+                histogram.get_sample_sum(),
+                histogram.get_sample_count(),
+                &buckets,
+                None,
+            )
+        },
+        FamilyType::SUMMARY => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::{
+        core::Desc,
+        proto::{Bucket, Counter, Histogram, LabelPair},
+    };
+    use prometheus_client::encoding::text::encode;
+
+    use super::*;
+
+    /// Collects fixed metric families.
+    struct Families(Vec<MetricFamily>);
+
+    impl prometheus::core::Collector for Families {
+        fn desc(&self) -> Vec<&Desc> {
+            Vec::new()
+        }
+
+        fn collect(&self) -> Vec<MetricFamily> {
+            self.0.clone()
+        }
+    }
+
+    fn label(name: &str, value: &str) -> LabelPair {
+        let mut label = LabelPair::default();
+        label.set_name(name.to_string());
+        label.set_value(value.to_string());
+        label
+    }
+
+    fn encode_families(families: Vec<MetricFamily>) -> String {
+        let registry = prometheus::Registry::new();
+        registry.register(Box::new(Families(families))).unwrap();
+        let mut openmetrics = Registry::default();
+        openmetrics.register_collector(Box::new(GatheredMetrics {
+            registry,
+            skip: HashSet::from(["graphgate_skipped".to_string()]),
+        }));
+        let mut encoded = String::new();
+        encode(&mut encoded, &openmetrics).unwrap();
+        encoded
+    }
+
+    #[test]
+    fn encode_counter() {
+        let mut counter = Counter::default();
+        counter.set_value(3.0);
+        let mut metric = Metric::default();
+        metric.set_label(vec![label("graphgate_service", "acc\"ounts")].into());
+        metric.set_counter(counter);
+        let mut family = MetricFamily::default();
+        family.set_name("graphgate_requests_total".to_string());
+        family.set_help("Total number of requests".to_string());
+        family.set_field_type(FamilyType::COUNTER);
+        family.set_metric(vec![metric].into());
+        let mut skipped = family.clone();
+        skipped.set_name("graphgate_skipped".to_string());
+
+        assert_eq!(
+            encode_families(vec![family, skipped]),
+            "# HELP graphgate_requests Total number of requests\n# TYPE graphgate_requests \
+             counter\ngraphgate_requests_total{graphgate_service=\"acc\\\"ounts\"} 3.0\n# EOF\n"
+        );
+    }
+
+    #[test]
+    fn encode_histogram() {
+        let buckets = [(0.1, 1), (1.0, 2)].map(|(upper, count)| {
+            let mut bucket = Bucket::default();
+            bucket.set_upper_bound(upper);
+            bucket.set_cumulative_count(count);
+            bucket
+        });
+        let mut histogram = Histogram::default();
+        histogram.set_bucket(buckets.to_vec().into());
+        histogram.set_sample_count(3);
+        histogram.set_sample_sum(2.55);
+        let mut metric = Metric::default();
+        metric.set_label(vec![label("graphgate_operation", "Me")].into());
+        metric.set_histogram(histogram);
+        let mut family = MetricFamily::default();
+        family.set_name("graphgate_composition_duration_seconds".to_string());
+        family.set_help("The schema composition durations in seconds.".to_string());
+        family.set_field_type(FamilyType::HISTOGRAM);
+        family.set_metric(vec![metric].into());
+
+        assert_eq!(
+            encode_families(vec![family]),
+            "# HELP graphgate_composition_duration_seconds The schema composition durations in seconds.\n# TYPE \
+             graphgate_composition_duration_seconds \
+             histogram\ngraphgate_composition_duration_seconds_sum{graphgate_operation=\"Me\"} \
+             2.55\ngraphgate_composition_duration_seconds_count{graphgate_operation=\"Me\"} \
+             3\ngraphgate_composition_duration_seconds_bucket{le=\"0.1\",graphgate_operation=\"Me\"} \
+             1\ngraphgate_composition_duration_seconds_bucket{le=\"1.0\",graphgate_operation=\"Me\"} \
+             2\ngraphgate_composition_duration_seconds_bucket{le=\"+Inf\",graphgate_operation=\"Me\"} 3\n# EOF\n"
+        );
+    }
+}
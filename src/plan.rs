@@ -0,0 +1,269 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{Context, Result};
+use graphgate_handler::{compose_schema, CompositionConfig, ServiceRouteTable};
+use graphgate_planner::{PlanBuilder, PlanFormat};
+use parser::types::{DocumentOperations, ExecutableDocument};
+use value::Variables;
+
+/// Plan the operations of the query file at `path` against the schema
+/// composed from the SDLs of the services and print their plans in
+/// `format`.
+///
+/// Only the operation named `operation_name` is planned if it is set.
+pub async fn print_plan(
+    route_table: &ServiceRouteTable,
+    composition_config: &CompositionConfig,
+    path: &Path,
+    operation_name: Option<&str>,
+    variables: Option<&str>,
+    format: PlanFormat,
+) -> Result<()> {
+    let query = std::fs::read_to_string(path).with_context(|| format!("Failed to read '{}'.", path.display()))?;
+    let variables = match variables {
+        Some(variables) => Variables::from_json(serde_json::from_str(variables).context("Invalid variables.")?),
+        None => Variables::default(),
+    };
+    let sdls = route_table.fetch_sdls().await?;
+    print!(
+        "{}",
+        plan(
+            route_table,
+            composition_config,
+            sdls,
+            &query,
+            operation_name,
+            variables,
+            format
+        )?
+    );
+    Ok(())
+}
+
+fn plan(
+    route_table: &ServiceRouteTable,
+    composition_config: &CompositionConfig,
+    sdls: Vec<(String, String)>,
+    query: &str,
+    operation_name: Option<&str>,
+    variables: Variables,
+    format: PlanFormat,
+) -> Result<String> {
+    let schema = compose_schema(route_table, composition_config, &sdls).context("The schema does not compose.")?;
+    let document = parser::parse_query(query).context("Invalid query.")?;
+
+    let operation_names = match operation_name {
+        Some(operation_name) => vec![Some(operation_name.to_string())],
+        None => operation_names(&document),
+    };
+    // The plans of several operations are printed one after the other
+    // following their names, or as an object by name in JSON.
+    let several = operation_names.len() > 1;
+    let mut json_plans = serde_json::Map::new();
+    let mut output = String::new();
+    for operation_name in operation_names {
+        let mut plan_builder = PlanBuilder::new(&schema, document.clone()).variables(variables.clone());
+        if let Some(operation_name) = &operation_name {
+            plan_builder = plan_builder.operation_name(operation_name);
+        }
+        let operation_name = operation_name.unwrap_or_default();
+        let plan = match plan_builder.plan() {
+            Ok(plan) => plan,
+            Err(response) => anyhow::bail!(
+                "{}",
+                response
+                    .errors
+                    .iter()
+                    .map(|error| if several {
+                        format!("{}: {}", operation_name, error.message)
+                    } else {
+                        error.message.clone()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        };
+        match format {
+            PlanFormat::Json => {
+                json_plans.insert(operation_name, serde_json::to_value(&plan)?);
+            },
+            PlanFormat::Dot => {
+                if several {
+                    if !output.is_empty() {
+                        output.push('\n');
+                    }
+                    writeln!(output, "// {}", operation_name)?;
+                }
+                output.push_str(&plan.to_dot());
+            },
+            PlanFormat::Mermaid => {
+                if several {
+                    if !output.is_empty() {
+                        output.push('\n');
+                    }
+                    writeln!(output, "%% {}", operation_name)?;
+                }
+                output.push_str(&plan.to_mermaid());
+            },
+        }
+    }
+    if format == PlanFormat::Json {
+        let plans = if several {
+            serde_json::Value::Object(json_plans)
+        } else {
+            json_plans.into_iter().next().map(|(_, plan)| plan).unwrap_or_default()
+        };
+        output = format!("{}\n", serde_json::to_string_pretty(&plans)?);
+    }
+    Ok(output)
+}
+
+/// The names of the operations of the document in the order they are
+/// defined, `None` for a single operation.
+fn operation_names(document: &ExecutableDocument) -> Vec<Option<String>> {
+    match &document.operations {
+        DocumentOperations::Single(_) => vec![None],
+        DocumentOperations::Multiple(operations) => {
+            let mut operations = operations.iter().collect::<Vec<_>>();
+            operations.sort_by_key(|(_, operation)| operation.pos);
+            operations.into_iter().map(|(name, _)| Some(name.to_string())).collect()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_formats() {
+        let sdls = || {
+            vec![
+                (
+                    "accounts".to_string(),
+                    r#"type Query { me: User } type User @key(fields: "id") { id: ID! }"#.to_string(),
+                ),
+                (
+                    "reviews".to_string(),
+                    r#"extend type User @key(fields: "id") { id: ID! @external name: String }"#.to_string(),
+                ),
+            ]
+        };
+        let query = "{ me { name } }";
+
+        let json = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            None,
+            Variables::default(),
+            PlanFormat::Json,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["type"], "sequence");
+        assert_eq!(json["nodes"][1]["service"], "reviews");
+
+        let dot = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            None,
+            Variables::default(),
+            PlanFormat::Dot,
+        )
+        .unwrap();
+        assert!(dot.contains(r#"n2 [label="Flatten\nreviews\nme", shape=box];"#));
+
+        let mermaid = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            None,
+            Variables::default(),
+            PlanFormat::Mermaid,
+        )
+        .unwrap();
+        assert!(mermaid.contains(r#"n1["Fetch<br/>accounts"]"#));
+
+        let err = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            "{ you }",
+            None,
+            Variables::default(),
+            PlanFormat::Json,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("you"));
+    }
+
+    #[test]
+    fn plan_operations() {
+        let sdls = || {
+            vec![(
+                "accounts".to_string(),
+                "type Query { me: String you: String }".to_string(),
+            )]
+        };
+        let query = "query Me($you: Boolean!) { me you @include(if: $you) } query You { you }";
+
+        // Each operation is planned without an operation name.
+        let json = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            None,
+            Variables::from_json(serde_json::json!({ "you": false })),
+            PlanFormat::Json,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["Me"]["variables"], serde_json::json!({ "you": false }));
+        assert_eq!(json["You"]["query"], "query\n{ you }");
+
+        let dot = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            None,
+            Variables::from_json(serde_json::json!({ "you": true })),
+            PlanFormat::Dot,
+        )
+        .unwrap();
+        assert!(dot.starts_with("// Me\ndigraph plan {"));
+        assert!(dot.contains("}\n\n// You\ndigraph plan {"));
+
+        // Only the named operation is planned otherwise.
+        let json = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            Some("Me"),
+            Variables::from_json(serde_json::json!({ "you": true })),
+            PlanFormat::Json,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["variables"], serde_json::json!({ "you": true }));
+
+        let err = plan(
+            &ServiceRouteTable::default(),
+            &Default::default(),
+            sdls(),
+            query,
+            Some("Them"),
+            Variables::default(),
+            PlanFormat::Json,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Them"));
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use graphgate_admin_client::{AdminClient, Route, RouteHealth, RouteSource};
+
+/// Print the routes discovered by the gateway at `url` as a table.
+pub async fn print_routes(url: &str) -> Result<()> {
+    let client = AdminClient::new(url);
+    let routes = client
+        .routes()
+        .await
+        .with_context(|| format!("Failed to fetch the routes of '{}'.", client.url()))?;
+    print!("{}", format_routes(&routes));
+    Ok(())
+}
+
+fn format_routes(routes: &[Route]) -> String {
+    let mut rows = vec![["SERVICE", "URL", "WEBSOCKET", "SOURCE", "HEALTH", "SDL"].map(ToString::to_string)];
+    for route in routes {
+        let source = match route.source {
+            RouteSource::Config => "config",
+            RouteSource::Kubernetes => "k8s",
+            RouteSource::Snapshot => "snapshot",
+            RouteSource::Supergraph => "supergraph",
+        };
+        let health = match (route.health, &route.error) {
+            (RouteHealth::Unhealthy, Some(err)) => format!("unhealthy: {}", err),
+            (RouteHealth::Unhealthy, None) => "unhealthy".to_string(),
+            (RouteHealth::Healthy, _) => "healthy".to_string(),
+            (RouteHealth::Unknown, _) => "unknown".to_string(),
+        };
+        let sdl_hash = match &route.sdl_hash {
+            Some(sdl_hash) => sdl_hash.chars().take(12).collect(),
+            None => "-".to_string(),
+        };
+        rows.push([
+            route.service.clone(),
+            route.url.clone(),
+            route.websocket_url.clone(),
+            source.to_string(),
+            health,
+            sdl_hash,
+        ]);
+    }
+
+    let mut widths = [0; 6];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut output = String::new();
+    for row in &rows {
+        let cells = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>();
+        output.push_str(cells.join("  ").trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_table() {
+        let output = format_routes(&[
+            Route {
+                service: "accounts".to_string(),
+                url: "http://accounts:8000".to_string(),
+                websocket_url: "ws://accounts:8000".to_string(),
+                source: RouteSource::Kubernetes,
+                health: RouteHealth::Healthy,
+                error: None,
+                sdl_hash: Some("0123456789abcdef".to_string()),
+            },
+            Route {
+                service: "products".to_string(),
+                url: "https://products".to_string(),
+                websocket_url: "wss://products/ws".to_string(),
+                source: RouteSource::Config,
+                health: RouteHealth::Unhealthy,
+                error: Some("connection refused".to_string()),
+                sdl_hash: None,
+            },
+        ]);
+        assert_eq!(
+            output,
+            "SERVICE   URL                   WEBSOCKET           SOURCE  HEALTH                         SDL\n\
+             accounts  http://accounts:8000  ws://accounts:8000  k8s     healthy                        0123456789ab\n\
+             products  https://products      wss://products/ws   config  unhealthy: connection refused  -\n"
+        );
+    }
+}